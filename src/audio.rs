@@ -0,0 +1,202 @@
+//! Sonification: every generation becomes a short note in a WAV file.
+//!
+//! The pitch follows the population, births and deaths add a burst of noise
+//! proportional to how much changed, and an empty world is silent. Oscillators
+//! therefore come out as rhythms and chaotic soups as hiss.
+
+use std::f32::consts::PI;
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+
+use conway::{Changes, Rule, World};
+
+const SAMPLE_RATE: u32 = 44_100;
+const BASE_FREQ: f32 = 220.0;
+const OCTAVES: usize = 3;
+/// Notes shorter than this are just clicks, so fast runs are stretched.
+const MIN_NOTE_MS: u64 = 60;
+/// The most samples a WAV file's 32-bit lengths allow, in bytes, leaving
+/// room for the header and the comment.
+const MAX_DATA: u64 = u32::MAX as u64 - 4096;
+
+#[derive(Copy, Clone, Debug)]
+pub enum Scale {
+    Major,
+    Minor,
+    Pentatonic,
+    Chromatic,
+}
+
+impl Scale {
+    pub fn parse(name: &str) -> Result<Scale, String> {
+        match name {
+            "major" => Ok(Scale::Major),
+            "minor" => Ok(Scale::Minor),
+            "pentatonic" => Ok(Scale::Pentatonic),
+            "chromatic" => Ok(Scale::Chromatic),
+            _ => Err(format!(
+                "unknown scale '{}', expected major, minor, pentatonic or chromatic",
+                name
+            )),
+        }
+    }
+
+    /// Semitone offsets of the degrees within one octave.
+    pub fn steps(self) -> &'static [u8] {
+        match self {
+            Scale::Major => &[0, 2, 4, 5, 7, 9, 11],
+            Scale::Minor => &[0, 2, 3, 5, 7, 8, 10],
+            Scale::Pentatonic => &[0, 2, 4, 7, 9],
+            Scale::Chromatic => &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+        }
+    }
+
+    /// Semitones from the bottom of the scale up to `degree`, counting on
+    /// into the octaves above.
+    pub fn semitones(self, degree: usize) -> usize {
+        let steps = self.steps();
+        12 * (degree / steps.len()) + steps[degree % steps.len()] as usize
+    }
+
+    fn frequency(self, degree: usize) -> f32 {
+        BASE_FREQ * 2f32.powf(self.semitones(degree) as f32 / 12.0)
+    }
+}
+
+pub struct Sonifier {
+    scale: Scale,
+    volume: f32,
+    note_len: usize,
+    /// Cells born and died since the last note.
+    changes: usize,
+    rule: Rule,
+    /// Written as the notes come, the header's lengths filled in at the end.
+    out: BufWriter<File>,
+    data_len: u64,
+    /// The first write that failed, reported at the end.
+    failed: Option<io::Error>,
+    phase: f32,
+    noise: u32,
+}
+
+impl Sonifier {
+    /// Starts the WAV file at `path`. `volume` is clamped to 0..=1, `delay`
+    /// is the time between generations in ms.
+    pub fn create(
+        path: &str,
+        world: &World,
+        scale: Scale,
+        volume: f32,
+        delay: u64,
+    ) -> io::Result<Sonifier> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(b"RIFF")?;
+        out.write_all(&0u32.to_le_bytes())?;
+        out.write_all(b"WAVEfmt ")?;
+        out.write_all(&16u32.to_le_bytes())?;
+        out.write_all(&1u16.to_le_bytes())?; // PCM
+        out.write_all(&1u16.to_le_bytes())?; // mono
+        out.write_all(&SAMPLE_RATE.to_le_bytes())?;
+        out.write_all(&(SAMPLE_RATE * 2).to_le_bytes())?;
+        out.write_all(&2u16.to_le_bytes())?;
+        out.write_all(&16u16.to_le_bytes())?;
+        out.write_all(b"data")?;
+        out.write_all(&0u32.to_le_bytes())?;
+        let note_ms = delay.max(MIN_NOTE_MS);
+        Ok(Sonifier {
+            scale,
+            volume: volume.clamp(0.0, 1.0),
+            note_len: (u64::from(SAMPLE_RATE) * note_ms / 1000) as usize,
+            changes: 0,
+            rule: world.rule(),
+            out,
+            data_len: 0,
+            failed: None,
+            phase: 0.0,
+            noise: 0x9E37_79B9,
+        })
+    }
+
+    /// Counts the cells a generation changed, for the next note's noise.
+    pub fn advanced(&mut self, changes: Changes) {
+        self.changes += changes.births + changes.deaths;
+    }
+
+    /// Appends the note for the world's current generation, unless the file
+    /// is already as long as a WAV file can be.
+    pub fn observe(&mut self, world: &World) {
+        let population = world.population();
+        let changes = std::mem::take(&mut self.changes);
+        self.rule = world.rule();
+        if self.failed.is_some() {
+            return;
+        }
+        let bytes = 2 * self.note_len as u64;
+        if self.data_len + bytes > MAX_DATA {
+            self.failed = Some(io::Error::other(
+                "the run is longer than the 4 GiB a WAV file can hold",
+            ));
+            return;
+        }
+
+        let area = (world.width() * world.height()) as f32;
+        let degrees = OCTAVES * self.scale.steps().len();
+        // populations rarely go above half the board, so spread that range over the scale
+        let density = (population as f32 / area * 2.0).min(1.0);
+        let degree = ((density * (degrees - 1) as f32).round() as usize).min(degrees - 1);
+        let freq = self.scale.frequency(degree);
+        let tone = if population == 0 { 0.0 } else { 0.6 };
+        let hiss = (changes as f32 / area * 8.0).min(1.0) * 0.4;
+
+        let mut note = Vec::with_capacity(bytes as usize);
+        for i in 0..self.note_len {
+            // short linear attack and release so consecutive notes don't click
+            let edge = (self.note_len / 20).max(1);
+            let envelope = (i.min(self.note_len - 1 - i) as f32 / edge as f32).min(1.0);
+            self.phase = (self.phase + 2.0 * PI * freq / SAMPLE_RATE as f32) % (2.0 * PI);
+            let sample = tone * self.phase.sin() + hiss * self.next_noise();
+            let sample = sample * envelope * self.volume;
+            let sample = (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16;
+            note.extend_from_slice(&sample.to_le_bytes());
+        }
+        match self.out.write_all(&note) {
+            Ok(()) => self.data_len += bytes,
+            Err(e) => self.failed = Some(e),
+        }
+    }
+
+    /// xorshift32 mapped to -1..1
+    fn next_noise(&mut self) -> f32 {
+        self.noise ^= self.noise << 13;
+        self.noise ^= self.noise >> 17;
+        self.noise ^= self.noise << 5;
+        self.noise as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+
+    /// Ends the file with the rule in an INFO comment after the notes, and
+    /// fills in the lengths, or fails with why its notes stopped early.
+    pub fn finish(self) -> io::Result<()> {
+        let mut out = self.out;
+        // INFO strings are NUL terminated and chunks padded to an even size
+        let mut comment = format!("rule {}\0", self.rule).into_bytes();
+        if comment.len() % 2 == 1 {
+            comment.push(0);
+        }
+        let list_len = 4 + 8 + comment.len() as u32;
+        out.write_all(b"LIST")?;
+        out.write_all(&list_len.to_le_bytes())?;
+        out.write_all(b"INFOICMT")?;
+        out.write_all(&(comment.len() as u32).to_le_bytes())?;
+        out.write_all(&comment)?;
+        let data_len = self.data_len as u32;
+        out.seek(SeekFrom::Start(4))?;
+        out.write_all(&(36 + 8 + list_len + data_len).to_le_bytes())?;
+        out.seek(SeekFrom::Start(40))?;
+        out.write_all(&data_len.to_le_bytes())?;
+        out.flush()?;
+        match self.failed {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
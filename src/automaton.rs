@@ -0,0 +1,149 @@
+//! One interface to the ways there are of stepping a world of cells, so what
+//! shows, analyses or serves a run can be written once for them all, and an
+//! engine from elsewhere can be handed to any of it:
+//!
+//! ```text
+//! fn run_for(automaton: &mut dyn Automaton, generations: u64) -> usize {
+//!     for _ in 0..generations {
+//!         automaton.step();
+//!     }
+//!     automaton.population()
+//! }
+//!
+//! run_for(&mut Stepper::new(world), 100);
+//! run_for(&mut immigration, 100);
+//! ```
+//!
+//! `World` needs a second world to step into, which `Stepper` keeps beside
+//! it. The colored worlds, `Immigration` and `Species`, are automata too,
+//! their cells live whoever they belong to.
+
+use crate::immigration::Immigration;
+use crate::species::Species;
+use crate::{Cell, Changes, World};
+
+pub trait Automaton {
+    /// Steps every cell a generation, counting the cells born and died.
+    fn step(&mut self) -> Changes;
+
+    fn cell(&self, x: usize, y: usize) -> Cell;
+
+    /// The width and height.
+    fn dimensions(&self) -> (usize, usize);
+
+    /// The live cells, counted one by one unless the engine keeps count.
+    fn population(&self) -> usize {
+        let (width, height) = self.dimensions();
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.cell(x, y) == Cell::Live)
+            .count()
+    }
+}
+
+impl<A: Automaton + ?Sized> Automaton for Box<A> {
+    fn step(&mut self) -> Changes {
+        (**self).step()
+    }
+
+    fn cell(&self, x: usize, y: usize) -> Cell {
+        (**self).cell(x, y)
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        (**self).dimensions()
+    }
+
+    fn population(&self) -> usize {
+        (**self).population()
+    }
+}
+
+/// A world and the one it steps into.
+#[derive(Clone)]
+pub struct Stepper {
+    world: World,
+    tmp: World,
+}
+
+impl Stepper {
+    pub fn new(world: World) -> Stepper {
+        Stepper {
+            tmp: world.clone(),
+            world,
+        }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    pub fn into_world(self) -> World {
+        self.world
+    }
+}
+
+impl Automaton for Stepper {
+    fn step(&mut self) -> Changes {
+        self.world.advance(&mut self.tmp)
+    }
+
+    fn cell(&self, x: usize, y: usize) -> Cell {
+        self.world.get(x, y)
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        (self.world.width(), self.world.height())
+    }
+
+    fn population(&self) -> usize {
+        self.world.population()
+    }
+}
+
+impl Automaton for Immigration {
+    fn step(&mut self) -> Changes {
+        self.advance()
+    }
+
+    fn cell(&self, x: usize, y: usize) -> Cell {
+        match self.get(x, y) {
+            Some(_) => Cell::Live,
+            None => Cell::Dead,
+        }
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        (self.width(), self.height())
+    }
+
+    fn population(&self) -> usize {
+        let (one, two) = self.score();
+        one + two
+    }
+}
+
+impl Automaton for Species {
+    fn step(&mut self) -> Changes {
+        self.advance()
+    }
+
+    fn cell(&self, x: usize, y: usize) -> Cell {
+        match self.get(x, y) {
+            Some(_) => Cell::Live,
+            None => Cell::Dead,
+        }
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        (self.width(), self.height())
+    }
+
+    fn population(&self) -> usize {
+        self.populations().iter().sum()
+    }
+}
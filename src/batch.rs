@@ -0,0 +1,180 @@
+//! Experiments of many runs: the worlds a manifest lists, run on a pool of
+//! threads, with a row of results for each in one CSV or JSON report.
+
+use std::fs;
+use std::io::{self, Write};
+use std::time::Instant;
+
+use conway::{Rng, World};
+
+use crate::cli::{BatchOptions, BatchRun, ReportFormat, WorldOptions};
+use crate::jsonl;
+use crate::pool;
+use crate::stop::{self, Watch};
+
+const COLUMNS: [&str; 13] = [
+    "line",
+    "rule",
+    "width",
+    "height",
+    "seed",
+    "generations",
+    "population",
+    "peak",
+    "peak_generation",
+    "births",
+    "deaths",
+    "outcome",
+    "seconds",
+];
+
+/// How one run went.
+struct Report {
+    line: usize,
+    rule: String,
+    width: usize,
+    height: usize,
+    seed: Option<u64>,
+    generations: u64,
+    population: usize,
+    peak: usize,
+    peak_at: u64,
+    births: u64,
+    deaths: u64,
+    /// Why it stopped before its generation limit, if it did.
+    outcome: Option<String>,
+    seconds: f64,
+}
+
+/// Runs every world of the manifest, `build` making each one's starting
+/// world from its options.
+pub fn run(
+    mut opts: BatchOptions,
+    build: fn(&WorldOptions) -> Result<World, String>,
+) -> Result<(), String> {
+    // fixed before any run starts so the report can say what they were
+    for run in &mut opts.runs {
+        if run.world.random.is_some() {
+            run.world.seed.get_or_insert_with(Rng::time_seed);
+        }
+    }
+    let reports = pool::run(opts.runs.len(), opts.threads, |i| {
+        let run = &opts.runs[i];
+        run_one(run, build).map_err(|e| format!("{} line {}: {}", opts.manifest, run.line, e))
+    })?;
+    let text = match opts.format {
+        ReportFormat::Csv => csv(&reports),
+        ReportFormat::Json => json(&reports),
+    };
+    match &opts.output {
+        Some(path) => fs::write(path, text).map_err(|e| format!("cannot write {}: {}", path, e)),
+        None => io::stdout()
+            .write_all(text.as_bytes())
+            .map_err(|e| format!("cannot write stdout: {}", e)),
+    }
+}
+
+fn run_one(
+    run: &BatchRun,
+    build: fn(&WorldOptions) -> Result<World, String>,
+) -> Result<Report, String> {
+    let started = Instant::now();
+    let mut world = build(&run.world)?;
+    let mut tmp = world.clone();
+    let mut report = Report {
+        line: run.line,
+        rule: world.rule().to_string(),
+        width: world.width(),
+        height: world.height(),
+        seed: run.world.random.and(run.world.seed),
+        generations: 0,
+        population: world.population(),
+        peak: world.population(),
+        peak_at: 0,
+        births: 0,
+        deaths: 0,
+        outcome: None,
+        seconds: 0.0,
+    };
+    let mut watch = run
+        .until
+        .map(|until| Watch::new(until, &world, run.max_period));
+    for generation in 1..=run.generations {
+        let changes = world.advance(&mut tmp);
+        report.generations = generation;
+        report.population = world.population();
+        if report.population > report.peak {
+            report.peak = report.population;
+            report.peak_at = generation;
+        }
+        report.births += changes.births as u64;
+        report.deaths += changes.deaths as u64;
+        report.outcome = watch.as_mut().and_then(|w| w.check(&world, generation));
+        if report.outcome.is_none() && !changes.any() {
+            report.outcome = Some(stop::settled(world.population(), generation - 1));
+        }
+        if report.outcome.is_some() {
+            break;
+        }
+    }
+    report.seconds = started.elapsed().as_secs_f64();
+    Ok(report)
+}
+
+fn csv(reports: &[Report]) -> String {
+    let mut out = COLUMNS.join(",");
+    out.push('\n');
+    for r in reports {
+        let seed = r.seed.map_or(String::new(), |s| s.to_string());
+        let outcome = r.outcome.as_deref().unwrap_or("");
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{},{},\"{}\",{:.3}\n",
+            r.line,
+            r.rule,
+            r.width,
+            r.height,
+            seed,
+            r.generations,
+            r.population,
+            r.peak,
+            r.peak_at,
+            r.births,
+            r.deaths,
+            outcome.replace('"', "\"\""),
+            r.seconds
+        ));
+    }
+    out
+}
+
+fn json(reports: &[Report]) -> String {
+    let rows: Vec<String> = reports
+        .iter()
+        .map(|r| {
+            let seed = r.seed.map_or("null".to_string(), |s| s.to_string());
+            let outcome = r
+                .outcome
+                .as_deref()
+                .map_or("null".to_string(), jsonl::string);
+            format!(
+                "  {{\"line\":{},\"rule\":{},\"width\":{},\"height\":{},\"seed\":{},\
+                 \"generations\":{},\"population\":{},\"peak\":{},\"peak_generation\":{},\
+                 \"births\":{},\"deaths\":{},\"outcome\":{},\"seconds\":{:.3}}}",
+                r.line,
+                jsonl::string(&r.rule),
+                r.width,
+                r.height,
+                seed,
+                r.generations,
+                r.population,
+                r.peak,
+                r.peak_at,
+                r.births,
+                r.deaths,
+                outcome,
+                r.seconds
+            )
+        })
+        .collect();
+    format!("[\n{}\n]\n", rows.join(",\n"))
+}
@@ -0,0 +1,142 @@
+//! What the terminal the frames go to can show, worked out from the
+//! environment at startup: how many colors, whether Unicode, and whether it
+//! draws images, so the displays use the best of it and fall back to plain
+//! ASCII without escapes on a dumb terminal or when the frames go to neither
+//! a terminal nor anything that could show them.
+//!
+//! Terminals are known by what they say about themselves: `COLORTERM` set to
+//! `truecolor` or `24bit` for any color, a `TERM` like `xterm-256color` for
+//! the palette, and a UTF-8 locale for Unicode. `NO_COLOR` turns colors off
+//! whatever the terminal, and `[terminal]` in the config file overrides what
+//! was worked out:
+//!
+//! ```toml
+//! [terminal]
+//! colors = "256"                 # or "none", "16", "truecolor" or "auto"
+//! unicode = false
+//! ```
+
+use std::env;
+use std::fmt;
+use std::io::{self, IsTerminal};
+
+/// How many colors the terminal shows, fewest first.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Depth {
+    None,
+    /// The eight basic colors.
+    Basic,
+    /// The 256-color palette.
+    Indexed,
+    /// Any color as `#rrggbb`.
+    Rgb,
+}
+
+impl Depth {
+    pub fn parse(s: &str) -> Result<Option<Depth>, String> {
+        match s {
+            "auto" => Ok(None),
+            "none" => Ok(Some(Depth::None)),
+            "16" | "8" => Ok(Some(Depth::Basic)),
+            "256" => Ok(Some(Depth::Indexed)),
+            "truecolor" | "24bit" => Ok(Some(Depth::Rgb)),
+            _ => Err(format!(
+                "invalid colors '{}', expected auto, none, 16, 256 or truecolor",
+                s
+            )),
+        }
+    }
+}
+
+/// The inline images a terminal can draw.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Graphics {
+    Kitty,
+    Sixel,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Caps {
+    pub depth: Depth,
+    pub unicode: bool,
+    pub graphics: Option<Graphics>,
+}
+
+impl Caps {
+    /// What the terminal can show, going by the environment, with the
+    /// `[terminal]` settings in place of what they set.
+    pub fn detect(depth: Option<Depth>, unicode: Option<bool>) -> Caps {
+        let var = |name: &str| env::var(name).unwrap_or_default();
+        let term = var("TERM");
+        // frames go to stdout when it is a terminal, to stderr otherwise
+        let terminal = io::stdout().is_terminal() || io::stderr().is_terminal();
+        let dumb = !terminal || term.is_empty() || term == "dumb";
+        let program = var("TERM_PROGRAM");
+        let colorterm = var("COLORTERM");
+        let detected = if dumb {
+            Depth::None
+        } else if colorterm == "truecolor"
+            || colorterm == "24bit"
+            || term.ends_with("-direct")
+            || ["iTerm.app", "WezTerm", "vscode"].contains(&program.as_str())
+            || term == "xterm-kitty"
+        {
+            Depth::Rgb
+        } else if term.contains("256color") {
+            Depth::Indexed
+        } else {
+            Depth::Basic
+        };
+        let no_color = |name| env::var_os(name).is_some_and(|v| !v.is_empty());
+        let depth = match depth {
+            _ if no_color("NO_COLOR") || no_color("CONWAY_NO_COLOR") => Depth::None,
+            Some(depth) => depth,
+            None => detected,
+        };
+        // the first of these that is set says which locale text is in
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .map(|name| var(name))
+            .find(|value| !value.is_empty())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let utf8 = locale.contains("utf-8") || locale.contains("utf8");
+        let unicode = unicode.unwrap_or(!dumb && utf8);
+        let graphics = if dumb {
+            None
+        } else if term == "xterm-kitty" || !var("KITTY_WINDOW_ID").is_empty() {
+            Some(Graphics::Kitty)
+        } else if term.contains("sixel")
+            || term.starts_with("foot")
+            || term.starts_with("mlterm")
+            || program == "WezTerm"
+        {
+            Some(Graphics::Sixel)
+        } else {
+            None
+        };
+        Caps {
+            depth,
+            unicode,
+            graphics,
+        }
+    }
+}
+
+impl fmt::Display for Caps {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let depth = match self.depth {
+            Depth::None => "no colors",
+            Depth::Basic => "16 colors",
+            Depth::Indexed => "256 colors",
+            Depth::Rgb => "truecolor",
+        };
+        let text = if self.unicode { "Unicode" } else { "ASCII" };
+        let graphics = match self.graphics {
+            Some(Graphics::Kitty) => ", kitty images",
+            Some(Graphics::Sixel) => ", sixel images",
+            None => "",
+        };
+        write!(f, "{}, {}{}", depth, text, graphics)
+    }
+}
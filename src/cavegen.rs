@@ -0,0 +1,175 @@
+//! `conway cavegen`: the cave maps of many games, made the usual way. Rock is
+//! scattered at random, smoothed for a few generations by the 4/5 rule, that
+//! a cell is rock when at least five of the nine around it, itself included,
+//! are, and then every pocket of floor that can't be reached from the
+//! biggest cave is filled in. Rock is a live cell to the engine, the rule
+//! being B5678/S45678, and the map is ringed by walls counted as rock so the
+//! caves don't run off one edge and in at the other.
+
+use std::collections::VecDeque;
+use std::fs;
+
+use conway::{Cell, Rng, Rule, World};
+
+use crate::cli::{CaveFormat, CaveOptions};
+use crate::jsonl;
+use crate::png;
+
+/// The 4/5 rule, rock being alive.
+const SMOOTHING: &str = "B5678/S45678";
+
+pub struct Cave {
+    pub width: usize,
+    pub height: usize,
+    /// Row by row from the top.
+    pub rock: Vec<bool>,
+}
+
+impl Cave {
+    fn floor(&self) -> usize {
+        self.rock.iter().filter(|&&rock| !rock).count()
+    }
+
+    /// Fills in every pocket of floor but the biggest, counting floor as
+    /// joined only along the sides of cells, as something walking it would.
+    fn connect(&mut self) {
+        let (w, h) = (self.width, self.height);
+        let mut region = vec![usize::MAX; w * h];
+        let mut sizes = Vec::new();
+        for start in 0..w * h {
+            if self.rock[start] || region[start] != usize::MAX {
+                continue;
+            }
+            let id = sizes.len();
+            let mut size = 0;
+            let mut queue = VecDeque::from(vec![start]);
+            region[start] = id;
+            while let Some(i) = queue.pop_front() {
+                size += 1;
+                let (x, y) = (i % w, i / w);
+                let sides = [
+                    (x > 0).then(|| i - 1),
+                    (x + 1 < w).then(|| i + 1),
+                    (y > 0).then(|| i - w),
+                    (y + 1 < h).then(|| i + w),
+                ];
+                for j in sides.iter().flatten().copied() {
+                    if !self.rock[j] && region[j] == usize::MAX {
+                        region[j] = id;
+                        queue.push_back(j);
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+        let biggest = (0..sizes.len()).max_by_key(|&id| sizes[id]);
+        for (rock, &id) in self.rock.iter_mut().zip(&region) {
+            if Some(id) != biggest {
+                *rock = true;
+            }
+        }
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = String::with_capacity((self.width + 1) * self.height);
+        for row in self.rock.chunks(self.width) {
+            text.extend(row.iter().map(|&rock| if rock { '#' } else { '.' }));
+            text.push('\n');
+        }
+        text
+    }
+
+    fn to_json(&self, seed: u64) -> String {
+        let rows: Vec<String> = self.to_text().lines().map(jsonl::string).collect();
+        format!(
+            "{{\"width\":{},\"height\":{},\"seed\":{},\"floor\":{},\"rows\":[{}]}}\n",
+            self.width,
+            self.height,
+            seed,
+            self.floor(),
+            rows.join(",")
+        )
+    }
+
+    fn to_png(&self, path: &str, opts: &CaveOptions) -> Result<(), String> {
+        let (rock, floor) = (opts.style.live_rgb(), opts.style.dead_rgb());
+        let s = opts.scale;
+        let (w, h) = (self.width * s, self.height * s);
+        let mut rgb = Vec::with_capacity(w * h * 3);
+        for y in 0..h {
+            for x in 0..w {
+                let (r, g, b) = match self.rock[y / s * self.width + x / s] {
+                    true => rock,
+                    false => floor,
+                };
+                rgb.extend_from_slice(&[r, g, b]);
+            }
+        }
+        png::write(path, w, h, &rgb, "conway cavegen")
+    }
+}
+
+/// A cave of the size `opts` gives, from `seed`.
+pub fn generate(opts: &CaveOptions, seed: u64) -> Result<Cave, String> {
+    let (w, h) = (opts.width, opts.height);
+    let mut world = World::new(w + 2, h + 2)?;
+    world.set_rule(Rule::parse(SMOOTHING)?);
+    world.set_walls_alive(true);
+    for x in 0..w + 2 {
+        world.set_wall(x, 0, true);
+        world.set_wall(x, h + 1, true);
+    }
+    for y in 0..h + 2 {
+        world.set_wall(0, y, true);
+        world.set_wall(w + 1, y, true);
+    }
+    let mut rng = Rng::new(seed);
+    for y in 1..=h {
+        for x in 1..=w {
+            if rng.chance(opts.fill) {
+                world.set(x, y, Cell::Live);
+            }
+        }
+    }
+    let mut tmp = world.clone();
+    for _ in 0..opts.smooth {
+        if !world.advance(&mut tmp).any() {
+            break;
+        }
+    }
+    let rock = (0..w * h)
+        .map(|i| world.get(i % w + 1, i / w + 1) == Cell::Live)
+        .collect();
+    let mut cave = Cave {
+        width: w,
+        height: h,
+        rock,
+    };
+    if !opts.keep_pockets {
+        cave.connect();
+    }
+    Ok(cave)
+}
+
+/// Makes the cave and writes it where and as `opts` says.
+pub fn run(opts: &CaveOptions, seed: u64) -> Result<(), String> {
+    let cave = generate(opts, seed)?;
+    let text = match opts.format {
+        CaveFormat::Png => {
+            let path = opts
+                .output
+                .as_deref()
+                .ok_or("a PNG map needs an --output FILE")?;
+            return cave.to_png(path, opts);
+        }
+        CaveFormat::Text => cave.to_text(),
+        CaveFormat::Json => cave.to_json(seed),
+    };
+    match &opts.output {
+        Some(path) => fs::write(path, text).map_err(|e| format!("cannot write {}: {}", path, e)),
+        None => {
+            print!("{}", text);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,490 @@
+//! Splitting a world into separate objects and naming the common ones, like
+//! apgsearch's census of the ash a soup leaves behind, and picking out the
+//! spaceships among them.
+//!
+//! Objects are groups of live cells touching each other, diagonals included,
+//! or a cell apart where the pieces aren't all common objects on their own,
+//! so a spaceship coming apart in some phases is still one object. They are
+//! named by their shape as in Conway's Life, whatever the world's rule.
+//! They can also be given the apgcodes Catagolue files them under, like
+//! `xs4_33` for a block or `xq4_153` for a glider, to look them up there or
+//! to tell them apart from one run to the next.
+
+use std::collections::VecDeque;
+
+use crate::{Cell, Pattern, Rect, Rule, World};
+
+/// The longest period an apgcode is looked for within.
+pub const APGCODE_MAX_PERIOD: usize = 64;
+
+/// Still lifes, oscillator phases and glider phases that are recognised. A
+/// glider's other two phases are mirror images of these.
+const KNOWN: [(&str, &str); 12] = [
+    ("block", "x = 2, y = 2\n2o$2o!"),
+    ("beehive", "x = 4, y = 3\nb2o$o2bo$b2o!"),
+    ("loaf", "x = 4, y = 4\nb2o$o2bo$bobo$2bo!"),
+    ("boat", "x = 3, y = 3\n2o$obo$bo!"),
+    ("ship", "x = 3, y = 3\n2o$obo$b2o!"),
+    ("tub", "x = 3, y = 3\nbo$obo$bo!"),
+    ("pond", "x = 4, y = 4\nb2o$o2bo$o2bo$b2o!"),
+    ("long boat", "x = 4, y = 4\n2o$obo$bobo$2bo!"),
+    ("blinker", "x = 3, y = 1\n3o!"),
+    ("toad", "x = 4, y = 2\nb3o$3o!"),
+    ("glider", "x = 3, y = 3\n2bo$obo$b2o!"),
+    ("glider", "x = 3, y = 3\no$b2o$2o!"),
+];
+
+/// One group of touching live cells.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Object {
+    /// Top left corner of the object's bounding box. Objects wrapping around
+    /// an edge of the world start near the far edge.
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    /// The live cells, relative to the corner and sorted row by row.
+    pub cells: Vec<(usize, usize)>,
+    /// What the shape is called, if it is one of the common ones.
+    pub name: Option<&'static str>,
+}
+
+/// The cells shifted to start at 0, 0 and sorted row by row.
+fn normalize(cells: &[(i64, i64)]) -> Vec<(usize, usize)> {
+    let x0 = cells.iter().map(|c| c.0).min().unwrap_or(0);
+    let y0 = cells.iter().map(|c| c.1).min().unwrap_or(0);
+    let mut out: Vec<_> = cells
+        .iter()
+        .map(|&(x, y)| ((x - x0) as usize, (y - y0) as usize))
+        .collect();
+    out.sort_by_key(|&(x, y)| (y, x));
+    out
+}
+
+/// The shape turned and mirrored each of the eight ways.
+fn orientations(cells: &[(usize, usize)]) -> impl Iterator<Item = Vec<(usize, usize)>> + '_ {
+    (0..8).map(move |turn| {
+        let moved: Vec<(i64, i64)> = cells
+            .iter()
+            .map(|&(x, y)| {
+                let (x, y) = (x as i64, y as i64);
+                let x = if turn & 4 != 0 { -x } else { x };
+                match turn & 3 {
+                    0 => (x, y),
+                    1 => (-y, x),
+                    2 => (-x, -y),
+                    _ => (y, -x),
+                }
+            })
+            .collect();
+        normalize(&moved)
+    })
+}
+
+/// The same shape whichever way it is rotated or mirrored.
+fn canonical(cells: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    orientations(cells).min().unwrap_or_default()
+}
+
+fn known_shapes() -> Vec<(Vec<(usize, usize)>, &'static str)> {
+    KNOWN
+        .iter()
+        .map(|&(name, rle)| {
+            let pattern = Pattern::parse_rle(rle).expect("known shapes are valid RLE");
+            let mut cells = Vec::new();
+            for y in 0..pattern.height() {
+                for x in 0..pattern.width() {
+                    if pattern.get(x, y) {
+                        cells.push((x, y));
+                    }
+                }
+            }
+            (canonical(&cells), name)
+        })
+        .collect()
+}
+
+/// The top left corner of a group's bounding box and its normalized cells.
+type Group = (usize, usize, Vec<(usize, usize)>);
+
+/// Groups of live cells no more than `reach` cells apart.
+fn groups(world: &World, reach: i64) -> Vec<Group> {
+    let (w, h) = (world.width(), world.height());
+    let mut seen = vec![false; w * h];
+    let mut groups = Vec::new();
+    for start in 0..w * h {
+        if seen[start] || world.get(start % w, start / w) != Cell::Live {
+            continue;
+        }
+        // walk the group keeping coordinates unwrapped, so an object crossing
+        // an edge comes out in one piece
+        let mut cells = Vec::new();
+        let mut queue = VecDeque::new();
+        seen[start] = true;
+        queue.push_back(((start % w) as i64, (start / w) as i64));
+        while let Some((x, y)) = queue.pop_front() {
+            cells.push((x, y));
+            for dy in -reach..=reach {
+                for dx in -reach..=reach {
+                    let (nx, ny) = (x + dx, y + dy);
+                    let (wx, wy) = (nx.rem_euclid(w as i64), ny.rem_euclid(h as i64));
+                    let i = wy as usize * w + wx as usize;
+                    if !seen[i] && world.get(wx as usize, wy as usize) == Cell::Live {
+                        seen[i] = true;
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+        }
+
+        let x0 = cells.iter().map(|c| c.0).min().unwrap_or(0);
+        let y0 = cells.iter().map(|c| c.1).min().unwrap_or(0);
+        groups.push((
+            x0.rem_euclid(w as i64) as usize,
+            y0.rem_euclid(h as i64) as usize,
+            normalize(&cells),
+        ));
+    }
+    groups
+}
+
+/// Every object in the world, in the order their first cells appear row by
+/// row. Pieces a cell apart are taken together unless each is named, as in
+/// `apgcodes`.
+pub fn objects(world: &World) -> Vec<Object> {
+    let (w, h) = (world.width(), world.height());
+    let known = known_shapes();
+    let object = |(x, y, cells): Group| {
+        let shape = canonical(&cells);
+        Object {
+            x,
+            y,
+            width: cells.iter().map(|c| c.0 + 1).max().unwrap_or(0),
+            height: cells.iter().map(|c| c.1 + 1).max().unwrap_or(0),
+            name: known.iter().find(|k| k.0 == shape).map(|k| k.1),
+            cells,
+        }
+    };
+    // which of the looser groups each live cell is in
+    let loose = groups(world, 2);
+    let mut group_of = vec![0; w * h];
+    for (i, (x, y, cells)) in loose.iter().enumerate() {
+        for &(cx, cy) in cells {
+            group_of[(y + cy) % h * w + (x + cx) % w] = i;
+        }
+    }
+    let pieces: Vec<(usize, Object)> = groups(world, 1)
+        .into_iter()
+        .map(|(x, y, cells)| {
+            (
+                group_of[y * w + (x + cells[0].0) % w],
+                object((x, y, cells)),
+            )
+        })
+        .collect();
+    let mut whole = vec![false; loose.len()];
+    for (g, piece) in &pieces {
+        whole[*g] |= piece.name.is_none();
+    }
+    let mut loose: Vec<Option<Group>> = loose.into_iter().map(Some).collect();
+    let mut objects = Vec::new();
+    for (g, piece) in pieces {
+        if !whole[g] {
+            objects.push(piece);
+        } else if let Some(group) = loose[g].take() {
+            // the whole group, where its first piece would have been
+            objects.push(object(group));
+        }
+    }
+    objects
+}
+
+/// How many of each named object the world holds, most common first, with
+/// everything unrecognised counted as "other".
+pub fn census(world: &World) -> Vec<(&'static str, usize)> {
+    let mut counts: Vec<(&'static str, usize)> = Vec::new();
+    for object in objects(world) {
+        let name = object.name.unwrap_or("other");
+        match counts.iter_mut().find(|c| c.0 == name) {
+            Some(count) => count.1 += 1,
+            None => counts.push((name, 1)),
+        }
+    }
+    // "other" goes last however common it is
+    counts.sort_by_key(|&(name, n)| (name == "other", std::cmp::Reverse(n), name));
+    counts
+}
+
+/// Spaceships recognised by their period, speed and smallest population.
+const SHIPS: [(&str, usize, i64, i64, usize); 4] = [
+    ("glider", 4, 1, 1, 5),
+    ("lwss", 4, 2, 0, 9),
+    ("mwss", 4, 2, 0, 11),
+    ("hwss", 4, 2, 0, 13),
+];
+
+/// An object that keeps its shape but moves.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spaceship {
+    /// Top left corner of its bounding box, and its size.
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    /// Generations until it looks the same again, `dx`, `dy` cells away.
+    pub period: usize,
+    pub dx: i64,
+    pub dy: i64,
+    pub name: Option<&'static str>,
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl Spaceship {
+    /// The speed the way it is usually written, like "c/4 diagonal" for a
+    /// glider or "2c/5 orthogonal".
+    pub fn speed(&self) -> String {
+        let (ax, ay) = (self.dx.unsigned_abs(), self.dy.unsigned_abs());
+        let distance = ax.max(ay);
+        let divisor = gcd(distance, self.period as u64);
+        let (cells, period) = (distance / divisor, self.period as u64 / divisor);
+        let direction = if ax == 0 || ay == 0 {
+            "orthogonal"
+        } else if ax == ay {
+            "diagonal"
+        } else {
+            "oblique"
+        };
+        match (cells, period) {
+            (1, 1) => format!("c {}", direction),
+            (1, p) => format!("c/{} {}", p, direction),
+            (c, 1) => format!("{}c {}", c, direction),
+            (c, p) => format!("{}c/{} {}", c, p, direction),
+        }
+    }
+}
+
+/// The live cells of `world` inside its bounding box, normalized.
+fn shape(world: &World) -> Option<(Rect, Vec<(usize, usize)>)> {
+    let area = world.bounding_box()?;
+    let mut cells = Vec::new();
+    for y in 0..area.height {
+        for x in 0..area.width {
+            if world.get(area.x + x, area.y + y) == Cell::Live {
+                cells.push((x, y));
+            }
+        }
+    }
+    Some((area, cells))
+}
+
+/// Every object in the world that comes back to its own shape somewhere else
+/// within `max_period` generations. Each object is run on its own, so ships
+/// about to crash into something are still reported.
+pub fn spaceships(world: &World, max_period: usize) -> Vec<Spaceship> {
+    // nothing moves faster than a cell a generation, so this much room around
+    // an object keeps it from meeting itself across the edges
+    let pad = max_period + 2;
+    let mut ships = Vec::new();
+    // reach 2 keeps ships together in phases where they fall apart in pieces
+    for (x, y, cells) in groups(world, 2) {
+        let width = cells.iter().map(|c| c.0 + 1).max().unwrap_or(0);
+        let height = cells.iter().map(|c| c.1 + 1).max().unwrap_or(0);
+        let mut alone = match World::new(width + 2 * pad, height + 2 * pad) {
+            Ok(alone) => alone,
+            Err(_) => continue,
+        };
+        alone.set_rule(world.rule());
+        for &(cx, cy) in &cells {
+            alone.set(pad + cx, pad + cy, Cell::Live);
+        }
+        let mut tmp = alone.clone();
+        let mut smallest = cells.len();
+        for period in 1..=max_period {
+            alone.advance(&mut tmp);
+            smallest = smallest.min(alone.population());
+            let (area, now) = match shape(&alone) {
+                Some(shape) => shape,
+                None => break,
+            };
+            if now != cells {
+                continue;
+            }
+            let (dx, dy) = (area.x as i64 - pad as i64, area.y as i64 - pad as i64);
+            if (dx, dy) != (0, 0) {
+                let name = SHIPS
+                    .iter()
+                    .find(|s| {
+                        (s.1, s.2, s.3, s.4)
+                            == (
+                                period,
+                                dx.abs().max(dy.abs()),
+                                dx.abs().min(dy.abs()),
+                                smallest,
+                            )
+                    })
+                    .map(|s| s.0);
+                ships.push(Spaceship {
+                    x,
+                    y,
+                    width,
+                    height,
+                    period,
+                    dx,
+                    dy,
+                    name,
+                });
+            }
+            break;
+        }
+    }
+    ships
+}
+
+/// The digits of extended Wechsler format, one to a column of five cells.
+const WECHSLER: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// One phase of a shape in extended Wechsler format: strips of five rows
+/// from the top, separated by `z`, each a digit per column with the top
+/// cell its lowest bit, and runs of empty columns shortened to `w` for two,
+/// `x` for three and `y` and a digit for four to 39.
+fn wechsler(cells: &[(usize, usize)]) -> String {
+    let width = cells.iter().map(|c| c.0 + 1).max().unwrap_or(0);
+    let height = cells.iter().map(|c| c.1 + 1).max().unwrap_or(0);
+    let mut code = String::new();
+    for strip in 0..height.div_ceil(5) {
+        if strip > 0 {
+            code.push('z');
+        }
+        let mut columns = vec![0usize; width];
+        for &(x, y) in cells.iter().filter(|c| c.1 / 5 == strip) {
+            columns[x] |= 1 << (y % 5);
+        }
+        // empty columns at the end of a strip are left out
+        let mut zeros = 0;
+        for &column in &columns {
+            if column == 0 {
+                zeros += 1;
+                continue;
+            }
+            while zeros > 0 {
+                let run: usize = zeros.min(39);
+                match run {
+                    1 => code.push('0'),
+                    2 => code.push('w'),
+                    3 => code.push('x'),
+                    _ => {
+                        code.push('y');
+                        code.push(char::from(WECHSLER[run - 4]));
+                    }
+                }
+                zeros -= run;
+            }
+            code.push(char::from(WECHSLER[column]));
+        }
+    }
+    code
+}
+
+/// The apgcode of the shape `cells` make on their own under `rule`: `xs`
+/// and the population for a still life, `xp` and the period for an
+/// oscillator or `xq` and the period for a spaceship, then after `_` the
+/// shortest, then first, of its phases turned and mirrored every way. The
+/// same shape gets the same code however it was found. `None` if it doesn't
+/// come back within `APGCODE_MAX_PERIOD` generations or dies out.
+pub fn apgcode(cells: &[(usize, usize)], rule: Rule) -> Option<String> {
+    let cells = normalize(
+        &cells
+            .iter()
+            .map(|&(x, y)| (x as i64, y as i64))
+            .collect::<Vec<_>>(),
+    );
+    if cells.is_empty() {
+        return None;
+    }
+    let width = cells.iter().map(|c| c.0 + 1).max().unwrap_or(0);
+    let height = cells.iter().map(|c| c.1 + 1).max().unwrap_or(0);
+    // most objects come back in a few generations, which a small world is
+    // quicker to find, and the rest get room to travel as far as they can
+    for &limit in &[4, APGCODE_MAX_PERIOD] {
+        let pad = limit + 2;
+        let mut alone = World::new(width + 2 * pad, height + 2 * pad).ok()?;
+        alone.set_rule(rule);
+        for &(x, y) in &cells {
+            alone.set(pad + x, pad + y, Cell::Live);
+        }
+        let mut tmp = alone.clone();
+        let mut phases = vec![cells.clone()];
+        for period in 1..=limit {
+            alone.advance(&mut tmp);
+            let (area, now) = shape(&alone)?;
+            if now != cells {
+                phases.push(now);
+                continue;
+            }
+            let prefix = if (area.x, area.y) != (pad, pad) {
+                format!("xq{}", period)
+            } else if period == 1 {
+                format!("xs{}", cells.len())
+            } else {
+                format!("xp{}", period)
+            };
+            return Some(format!("{}_{}", prefix, smallest(&phases)));
+        }
+    }
+    None
+}
+
+/// The shortest, then first, code of any of `phases` any way round.
+fn smallest(phases: &[Vec<(usize, usize)>]) -> String {
+    phases
+        .iter()
+        .flat_map(|phase| orientations(phase))
+        .map(|cells| wechsler(&cells))
+        .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+        .unwrap_or_default()
+}
+
+/// How many objects in the world have each apgcode, most common first, with
+/// any that don't come back in time counted as "other". Objects a cell apart
+/// are taken together when one of them doesn't come back on its own, as a
+/// spaceship falling apart in pieces in some phases doesn't.
+pub fn apgcodes(world: &World) -> Vec<(String, usize)> {
+    let (w, h) = (world.width(), world.height());
+    let rule = world.rule();
+    // which of the looser groups each live cell is in
+    let loose = groups(world, 2);
+    let mut group_of = vec![0; w * h];
+    for (i, (x, y, cells)) in loose.iter().enumerate() {
+        for &(cx, cy) in cells {
+            group_of[(y + cy) % h * w + (x + cx) % w] = i;
+        }
+    }
+    let mut pieces: Vec<Vec<Option<String>>> = vec![Vec::new(); loose.len()];
+    for (x, y, cells) in groups(world, 1) {
+        pieces[group_of[y * w + (x + cells[0].0) % w]].push(apgcode(&cells, rule));
+    }
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for ((_, _, cells), pieces) in loose.iter().zip(pieces) {
+        let codes = if pieces.iter().all(Option::is_some) {
+            pieces
+        } else {
+            vec![apgcode(cells, rule)]
+        };
+        for code in codes {
+            let code = code.unwrap_or_else(|| "other".to_string());
+            match counts.iter_mut().find(|c| c.0 == code) {
+                Some(count) => count.1 += 1,
+                None => counts.push((code, 1)),
+            }
+        }
+    }
+    counts.sort_by_key(|(code, n)| (code == "other", std::cmp::Reverse(*n), code.clone()));
+    counts
+}
@@ -0,0 +1,297 @@
+//! `conway check`: reads pattern files more strictly than loading them does,
+//! reporting every problem with the line and column it is at, so a
+//! collection can be tidied up before anything trips over it.
+
+use std::collections::HashSet;
+use std::fs;
+
+use conway::{Format, Pattern, Rule};
+
+use crate::cli::CheckOptions;
+use crate::memory;
+
+/// A problem at a 1-based line and column, the column 0 for the whole line.
+struct Problem {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+#[derive(Default)]
+struct Problems(Vec<Problem>);
+
+impl Problems {
+    fn add(&mut self, line: usize, column: usize, message: String) {
+        self.0.push(Problem {
+            line,
+            column,
+            message,
+        });
+    }
+}
+
+/// The lines of `text`, numbered from 1, each with the column its first
+/// character other than whitespace is at.
+fn lines(text: &str) -> impl Iterator<Item = (usize, usize, &str)> {
+    text.lines().enumerate().map(|(i, line)| {
+        let trimmed = line.trim_start();
+        let column = line[..line.len() - trimmed.len()].chars().count() + 1;
+        (i + 1, column, trimmed.trim_end())
+    })
+}
+
+fn check_rule(problems: &mut Problems, line: usize, column: usize, rule: &str) {
+    if let Err(e) = Rule::parse(rule) {
+        problems.add(line, column, e);
+    }
+}
+
+fn check_rle(text: &str, problems: &mut Problems) {
+    let mut lines = lines(text).filter(|&(_, _, l)| !l.is_empty() && !l.starts_with('#'));
+    let (header_line, start, header) = match lines.next() {
+        Some(header) => header,
+        None => return problems.add(1, 0, "no RLE header, expected x = N, y = N".to_string()),
+    };
+    let (mut width, mut height) = (None, None);
+    let mut annotated = false;
+    let mut column = start;
+    for field in header.split(',') {
+        let at = column + field.len() - field.trim_start().len();
+        column += field.chars().count() + 1;
+        let (key, value) = match field.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => {
+                problems.add(
+                    header_line,
+                    at,
+                    format!("malformed header field '{}'", field.trim()),
+                );
+                continue;
+            }
+        };
+        let number = |problems: &mut Problems| match value.parse::<usize>() {
+            Ok(n) => Some(n),
+            Err(_) => {
+                problems.add(header_line, at, format!("invalid {} '{}'", key, value));
+                None
+            }
+        };
+        match key {
+            "x" => width = number(problems),
+            "y" => height = number(problems),
+            "rule" => {
+                let (rule, grid) = value.split_once(':').unwrap_or((value, ""));
+                check_rule(problems, header_line, at, rule);
+                annotated = Rule::parse(rule).is_ok_and(|rule| rule.history());
+                if !grid.is_empty() {
+                    let message = format!("bounded grid ':{}' is ignored, worlds wrap", grid);
+                    problems.add(header_line, at, message);
+                }
+            }
+            _ => problems.add(header_line, at, format!("unknown header field '{}'", key)),
+        }
+    }
+    let (width, height) = match (width, height) {
+        (Some(w), Some(h)) => (w, h),
+        _ => {
+            problems.add(header_line, 0, "the header needs both x and y".to_string());
+            (usize::MAX, usize::MAX)
+        }
+    };
+
+    let (mut x, mut y) = (0, 0);
+    let (mut right, mut bottom) = (0, 0);
+    let mut count = String::new();
+    let mut ended = None;
+    for (line, start, body) in lines {
+        // anything after the '!' is free text
+        if ended.is_some() {
+            break;
+        }
+        for (i, c) in body.chars().enumerate() {
+            let column = start + i;
+            let run = count.parse::<usize>().unwrap_or(1);
+            match c {
+                '0'..='9' => {
+                    count.push(c);
+                    continue;
+                }
+                'b' => x += run,
+                '.' if annotated => x += run,
+                'o' | 'A'..='F' if c == 'o' || annotated => {
+                    if x + run > width || y >= height {
+                        problems.add(
+                            line,
+                            column,
+                            format!(
+                                "cells {}..{},{} are outside the declared {}x{}",
+                                x,
+                                x + run - 1,
+                                y,
+                                width,
+                                height
+                            ),
+                        );
+                    }
+                    x += run;
+                    right = right.max(x);
+                    bottom = bottom.max(y + 1);
+                }
+                '$' => {
+                    y += run;
+                    x = 0;
+                }
+                '!' => {
+                    if !count.is_empty() {
+                        problems.add(line, column, format!("a count of {} with no state", count));
+                    }
+                    ended = Some(line);
+                    break;
+                }
+                c if c.is_whitespace() && count.is_empty() => continue,
+                c if c.is_whitespace() => {
+                    problems.add(line, column, format!("a count of {} with no state", count));
+                }
+                'A'..='Z' | '.' => problems.add(
+                    line,
+                    column,
+                    format!("cell state '{}' needs more than two states", c),
+                ),
+                _ => problems.add(line, column, format!("unexpected '{}'", c)),
+            }
+            count.clear();
+        }
+    }
+    if ended.is_none() {
+        problems.add(0, 0, "no '!' at the end of the pattern".to_string());
+    }
+    let declared = width != usize::MAX;
+    if declared && (right, bottom) != (width, height) && (right, bottom) != (0, 0) {
+        problems.add(
+            header_line,
+            0,
+            format!(
+                "the header says {}x{} but the cells take up {}x{}",
+                width, height, right, bottom
+            ),
+        );
+    }
+}
+
+fn check_plaintext(text: &str, problems: &mut Problems) {
+    for (line, start, row) in lines(text) {
+        if let Some(rule) = row.strip_prefix("!Rule:") {
+            check_rule(problems, line, start + 6, rule.trim());
+        } else if !row.starts_with('!') {
+            for (i, c) in row.chars().enumerate() {
+                if !matches!(c, '.' | 'O' | '*') {
+                    problems.add(line, start + i, format!("unexpected '{}'", c));
+                }
+            }
+            if start > 1 {
+                let message = "spaces before a row shift it over; use '.'".to_string();
+                problems.add(line, 1, message);
+            }
+        }
+    }
+}
+
+fn check_life105(text: &str, problems: &mut Problems) {
+    let mut placed = false;
+    for (line, start, row) in lines(text).skip(1) {
+        if let Some(rule) = row.strip_prefix("#R") {
+            check_rule(problems, line, start + 2, rule.trim());
+        } else if let Some(at) = row.strip_prefix("#P") {
+            let numbers: Vec<_> = at.split_whitespace().map(str::parse::<i64>).collect();
+            if numbers.len() != 2 || numbers.iter().any(Result::is_err) {
+                problems.add(line, start, "expected #P X Y".to_string());
+            }
+            placed = true;
+        } else if row.starts_with('#') || row.is_empty() {
+            continue;
+        } else {
+            if !placed {
+                problems.add(line, start, "cells before any #P line".to_string());
+                placed = true;
+            }
+            for (i, c) in row.chars().enumerate() {
+                if !matches!(c, '.' | '*') {
+                    problems.add(line, start + i, format!("unexpected '{}'", c));
+                }
+            }
+        }
+    }
+}
+
+fn check_life106(text: &str, problems: &mut Problems) {
+    let mut seen = HashSet::new();
+    for (line, start, row) in lines(text).skip(1) {
+        if row.starts_with('#') || row.is_empty() {
+            continue;
+        }
+        let numbers: Vec<_> = row.split_whitespace().map(str::parse::<i64>).collect();
+        match numbers.as_slice() {
+            [Ok(x), Ok(y)] => {
+                if !seen.insert((*x, *y)) {
+                    problems.add(line, start, format!("cell {} {} is listed twice", x, y));
+                }
+            }
+            _ => problems.add(
+                line,
+                start,
+                format!("expected a cell like '3 -1', not '{}'", row),
+            ),
+        }
+    }
+}
+
+/// Checks each file, printing its problems or a line saying it is fine, and
+/// fails if any had problems.
+pub fn run(opts: &CheckOptions) -> Result<(), String> {
+    let mut failed = 0;
+    for path in &opts.files {
+        let text = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        let format = match opts.format {
+            Some(format) => format,
+            // the header is what tells the two versions of Life apart
+            None => match Format::from_path(path) {
+                Ok(Format::Life106) | Err(_) => Format::detect(&text),
+                Ok(format) => format,
+            },
+        };
+        let mut problems = Problems::default();
+        match format {
+            Format::Rle => check_rle(&text, &mut problems),
+            Format::Plaintext => check_plaintext(&text, &mut problems),
+            Format::Life105 => check_life105(&text, &mut problems),
+            Format::Life106 => check_life106(&text, &mut problems),
+        }
+        if problems.0.is_empty() {
+            // whatever the strict reading lets through loads too
+            let pattern = Pattern::parse_as_within(&text, format, &memory::fits(None))
+                .map_err(|e| format!("{}: {}", path, e))?;
+            println!(
+                "{}: ok, {}x{} with {} cells, {}",
+                path,
+                pattern.width(),
+                pattern.height(),
+                pattern.population(),
+                pattern.rule().unwrap_or_default()
+            );
+            continue;
+        }
+        failed += 1;
+        for problem in &problems.0 {
+            match (problem.line, problem.column) {
+                (0, _) => println!("{}: {}", path, problem.message),
+                (line, 0) => println!("{}:{}: {}", path, line, problem.message),
+                (line, column) => println!("{}:{}:{}: {}", path, line, column, problem.message),
+            }
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        1 if opts.files.len() == 1 => Err(format!("{} has problems", opts.files[0])),
+        n => Err(format!("{} of {} files have problems", n, opts.files.len())),
+    }
+}
@@ -0,0 +1,203 @@
+//! Snapshots of a long run from which it can carry on after an interruption.
+//!
+//! A checkpoint file is the magic `CONWAYCK` and a version byte, then, with
+//! numbers little-endian: the generation as a `u64`, a byte saying whether a
+//! seed follows and the `u64` seed, the width and height as `u32`s, the rule
+//! in B/S notation after its `u16` length, the cells row by row, eight to a
+//! byte with the first in the lowest bit, and last a `u64` checksum of all
+//! that, 64-bit FNV-1a. Version 1 files, from before the checksum, are still
+//! read.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+
+use crate::pattern::any_size;
+use crate::{gzip, Cell, Fits, Rule, World};
+
+const MAGIC: &[u8; 8] = b"CONWAYCK";
+const VERSION: u8 = 2;
+
+const CUT_SHORT: &str = "the checkpoint is cut short, as if the run stopped while writing it";
+
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[derive(Clone)]
+pub struct Checkpoint {
+    pub world: World,
+    pub generation: u64,
+    /// The seed of the soup the run started from, if it was random.
+    pub seed: Option<u64>,
+}
+
+/// Takes bytes off the front of a checkpoint being read.
+struct Bytes<'a>(&'a [u8]);
+
+impl<'a> Bytes<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if self.0.len() < n {
+            return Err(CUT_SHORT.to_string());
+        }
+        let (taken, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+}
+
+impl Checkpoint {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let world = &self.world;
+        let rule = world.rule().to_string();
+        let cells = world.width() * world.height();
+        let mut out = Vec::with_capacity(40 + rule.len() + cells.div_ceil(8));
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&self.generation.to_le_bytes());
+        out.push(u8::from(self.seed.is_some()));
+        out.extend_from_slice(&self.seed.unwrap_or(0).to_le_bytes());
+        out.extend_from_slice(&(world.width() as u32).to_le_bytes());
+        out.extend_from_slice(&(world.height() as u32).to_le_bytes());
+        out.extend_from_slice(&(rule.len() as u16).to_le_bytes());
+        out.extend_from_slice(rule.as_bytes());
+        let mut bits = vec![0u8; cells.div_ceil(8)];
+        for y in 0..world.height() {
+            for x in 0..world.width() {
+                if world.get(x, y) == Cell::Live {
+                    let i = y * world.width() + x;
+                    bits[i / 8] |= 1 << (i % 8);
+                }
+            }
+        }
+        out.extend_from_slice(&bits);
+        let sum = checksum(&out);
+        out.extend_from_slice(&sum.to_le_bytes());
+        out
+    }
+
+    /// Reads a checkpoint, refusing one that is cut short or whose checksum
+    /// doesn't match.
+    pub fn from_bytes(all: &[u8]) -> Result<Checkpoint, String> {
+        Checkpoint::from_bytes_within(all, &any_size)
+    }
+
+    /// Reads a checkpoint as `from_bytes` does, and refuses it if `fits` has
+    /// no room for a world of the size it gives.
+    pub fn from_bytes_within(all: &[u8], fits: Fits) -> Result<Checkpoint, String> {
+        let mut bytes = Bytes(all);
+        if bytes.take(MAGIC.len())? != MAGIC {
+            return Err("not a checkpoint".to_string());
+        }
+        let version = bytes.array::<1>()?[0];
+        if version != 1 && version != VERSION {
+            return Err(format!(
+                "checkpoint version {} is not supported, expected {}",
+                version, VERSION
+            ));
+        }
+        let generation = u64::from_le_bytes(bytes.array()?);
+        let has_seed = bytes.array::<1>()?[0] != 0;
+        let seed = u64::from_le_bytes(bytes.array()?);
+        let width = u32::from_le_bytes(bytes.array()?) as usize;
+        let height = u32::from_le_bytes(bytes.array()?) as usize;
+        let rule_len = u16::from_le_bytes(bytes.array()?) as usize;
+        let rule = bytes.take(rule_len)?;
+        // the size is only the header's word until the length and the
+        // checksum bear it out, so no world is made before they have
+        let cells = width
+            .checked_mul(height)
+            .ok_or("the checkpoint is corrupt: its size is impossible")?
+            .div_ceil(8);
+        let sum = if version == VERSION { 8 } else { 0 };
+        let rest = bytes.0.len();
+        if rest < cells + sum {
+            return Err(CUT_SHORT.to_string());
+        }
+        if rest > cells + sum {
+            return Err(format!(
+                "the checkpoint is corrupt: {} bytes follow its end",
+                rest - cells - sum
+            ));
+        }
+        let bits = bytes.take(cells)?;
+        if version == VERSION {
+            let summed = all.len() - bytes.0.len();
+            let expected = u64::from_le_bytes(bytes.array()?);
+            let actual = checksum(&all[..summed]);
+            if actual != expected {
+                return Err(format!(
+                    "the checkpoint is corrupt: its checksum is {:016x} but it sums to {:016x}",
+                    expected, actual
+                ));
+            }
+        }
+        let rule = Rule::parse(&String::from_utf8_lossy(rule))?;
+        fits(width, height)?;
+        let mut world = World::new(width, height)?;
+        world.set_rule(rule);
+        for y in 0..height {
+            for x in 0..width {
+                let i = y * width + x;
+                if bits[i / 8] & 1 << (i % 8) != 0 {
+                    world.set(x, y, Cell::Live);
+                }
+            }
+        }
+        Ok(Checkpoint {
+            world,
+            generation,
+            seed: if has_seed { Some(seed) } else { None },
+        })
+    }
+
+    /// Reads the checkpoint at `path`, which may be compressed.
+    pub fn load(path: &str) -> Result<Checkpoint, String> {
+        Checkpoint::load_within(path, &any_size)
+    }
+
+    pub fn load_within(path: &str, fits: Fits) -> Result<Checkpoint, String> {
+        let bytes = gzip::read(path)?;
+        Checkpoint::from_bytes_within(&bytes, fits).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    /// Writes the checkpoint next to `path` first, flushed to the disk, and
+    /// then moves it there, so a crash never leaves half a checkpoint behind
+    /// or loses the one that was there. A path ending in `.gz` is compressed.
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let partial = format!("{}.partial", path);
+        let bytes = gzip::encode(path, self.to_bytes())?;
+        let write = || -> io::Result<()> {
+            let mut file = File::create(&partial)?;
+            file.write_all(&bytes)?;
+            file.sync_all()?;
+            fs::rename(&partial, path)?;
+            sync_dir(path)
+        };
+        write().map_err(|e| format!("cannot write {}: {}", path, e))
+    }
+}
+
+/// Flushes the directory `path` is in, so a file just moved into it stays.
+#[cfg(unix)]
+fn sync_dir(path: &str) -> io::Result<()> {
+    use std::path::Path;
+
+    let dir = Path::new(path)
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_: &str) -> io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,4422 @@
+//! Command line parsing.
+
+use std::env;
+use std::fs;
+use std::iter::Peekable;
+use std::mem;
+use std::str::FromStr;
+use std::thread;
+
+use conway::species::{Ties, MAX_SPECIES};
+use conway::stack::Link;
+use conway::{library, Cell, Format, Pattern, Rect, Rng, Rule, Symmetry, Transform};
+
+use crate::audio::Scale;
+use crate::collide::Outcome;
+use crate::config::{Config, Renderer};
+use crate::engines;
+use crate::enumerate;
+use crate::evolve::Fitness;
+use crate::explore::Space;
+use crate::faults::Faults;
+use crate::figure::{Filter, ImageStyle};
+use crate::frames::Template;
+use crate::hashes::HashWhen;
+use crate::jsonl::Cells;
+use crate::layers;
+use crate::log;
+use crate::memory;
+use crate::osc;
+use crate::perturb::Perturb;
+use crate::render;
+use crate::soup;
+use crate::stop::{self, Until};
+use crate::sweep::{self, Axis, Measure};
+use crate::term::{Color, Footprint, Style, Zoom};
+
+const USAGE: &str = "\
+Conway's Game of Life on a toroidal grid.
+
+Usage: conway [COMMAND] [OPTIONS]
+
+Commands:
+  run       simulate and print every generation (the default)
+  edit      draw a pattern interactively and save it
+  convert   translate a pattern file to another format
+  check     read pattern files strictly and report what is wrong with them
+  diff      show the cells two patterns differ in
+  analyze   simulate without output and report statistics
+  bench     measure simulation speed on a random soup
+  render    write the world after some generations as a PNG image
+  thumb     draw a pattern to a square PNG image of a fixed size
+  step      advance a world read from stdin and write it to stdout
+  search    run many random soups and count what they leave behind
+  evolve    breed small patterns for long lives, growth or gliders
+  serve     stream the simulation to web browsers
+  worker    hold part of a world for 'distribute'
+  distribute
+            run one world across several worker processes
+  replay    play back a run recorded with --record
+  scrub     run a world with a timeline to go back through and branch from
+  soak      cross-check the engines on random worlds and rules
+  explore   run one world under every rule of a family and chart the outcomes
+  verify    record a golden run or check one still comes out the same
+  batch     run the worlds listed in a manifest and report on each one
+  sweep     run soups over a range of settings and tabulate how they turn out
+  versus    play two-player Life, each side placing cells between rounds
+  species   run colored Life with several species competing for space
+  cavegen   make a cave map for a game, as text, PNG or JSON
+  enumerate list every still life and oscillator that fits in a small box
+  collide   throw one pattern at another from every nearby offset and phase
+  repl      drive a world command by command at a prompt
+  help      print help for a command
+
+Options:
+      --config FILE    read settings from FILE instead of ~/.config/conway/config.toml
+      --profile NAME   use the settings of a profile: demo, bench, screensaver,
+                       research, or one defined in the config file
+      --theme NAME     color the terminal, images and window with a theme:
+                       default, mono, phosphor, amber, ice, or one defined
+                       in the config file
+  -v, --verbose        log more of what is going on to stderr, like connections
+                       and commands; -vv for every generation as well
+      --log-format FMT log as 'text' or as one JSON object a line with 'json'
+  -h, --help           print help
+  -V, --version        print the version
+
+Settings from the config file become the defaults of the matching options.
+CONWAY_* environment variables such as CONWAY_RULE or CONWAY_DELAY override
+the file, CONWAY_CONFIG names another file, and NO_COLOR turns colors off.
+Colors are written as the terminal shows them, going by TERM and COLORTERM,
+braille and shades fall back to ASCII without a UTF-8 locale, and frames
+going to no terminal at all are plain text; [terminal] in the config file
+says otherwise with colors = none, 16, 256 or truecolor and
+unicode = true or false.
+Run 'conway help COMMAND' for the options of a command.
+";
+
+const RUN_USAGE: &str = "\
+Simulate and print every generation.
+
+Usage: conway run [OPTIONS] [PATTERN]
+
+PATTERN is an .rle or plaintext file placed at the center of the world,
+on top of the soup with --random. Without either the world starts with
+a glider.
+
+Frames are for watching and go to the terminal: stdout if it is one, or
+else stderr. What a run produces for other programs, the JSON lines of
+--output-format jsonl, the summary and the world as it ends up with
+--final, goes to stdout or --output FILE, so piping a run passes on the
+data alone.
+
+Runs without a generation limit end with Ctrl-C, or Esc or Q in the
+--window. Ctrl-C ends a run in the terminal after the frame being shown,
+still writing what it records and its summary, and exits with status 130;
+a second Ctrl-C exits at once. On Unix, 'kill -USR1 PID' saves the world
+as it is then to files like conway-20240131-235959.rle and .png, named for
+the time with -2, -3 and so on after it for more in the same second, and
+the run carries on.
+
+Patterns given with --place replace the glider too. They must fit in
+the world without wrapping around and must not overlap each other.
+
+Built-in patterns for --pattern and --place: glider, lwss, gosper-gun,
+pulsar, pentadecathlon, r-pentomino, acorn, diehard.
+
+A rule with History after it, like LifeHistory or B36/S23History, steps as
+the rule does while keeping the states of LifeHistory that annotated
+patterns use: history ':' where cells have been, marked '#' and 'x', start
+'@' and boundary '%', each in its own color.
+
+With --radius, or a rule in Golly's Larger than Life notation such as
+R5,C0,M1,S34..58,B34..45,NM, every cell counts the live cells in the square
+around it reaching N cells each way, itself too with M1. Counting takes the
+same time whatever the radius. Engines that see only the nearest neighbors,
+like distribute's strips, refuse such rules.
+
+A rule description file for --rule-file gives each cell of the square
+around a cell, itself in the middle, a weight, and the sums of the weights
+of the live ones that make a birth or let a cell survive:
+
+  # orthogonal neighbors count twice
+  weights
+  1 2 1
+  2 0 2
+  1 2 1
+  birth 3 5
+  survival 2 3 6
+
+Patterns and checkpoints write it as W1:1,2,1,2,0,2,1,2,1/B3,5/S2,3,6,
+which --rule takes too.
+
+--rule-file also loads Golly .rule files with a @TABLE of two states over
+the Moore or vonNeumann neighborhood, the colors of their @COLORS section
+coloring the cells. Such a rule is written by the name after @RULE, which
+--rule and pattern headers take once the file is loaded.
+
+Walls, W in RLE and # in plaintext patterns or placed with w in the editor,
+stay dead whatever the rule, and their neighbors count them as dead or,
+with --walls-alive, as alive, for building enclosures and corridors.
+Sources and sinks, from --source and --sink or placed with o and x in the
+editor, are ordinary cells to their neighbors but are set alive or dead
+again after every step, to drive a boundary or inject a signal.
+
+A directional rule sees which of the eight nearest neighbors are alive. Its
+B/S terms, apart by commas, are counts followed by neighbors named N, NE,
+E, SE, S, SW, W or NW that must be alive after @ or dead after !: with
+--rule B3,2@N/S23 a cell is also born when two neighbors are alive and one
+is to the north, and S23,@E!W adds survival whenever the east neighbor is
+alive and the west one dead.
+
+Options:
+  -W, --width N           world width in cells [default: 40]
+  -H, --height N          world height in cells [default: 20]
+      --max-mem SIZE      largest world to allow, like 512M or 4G, so one far
+                          too big is refused rather than crashing [default:
+                          the memory available]
+      --fit [MARGIN]      size the world to the pattern's live cells with
+                          MARGIN dead cells all round, instead of --width
+                          and --height [default margin: 10]
+  -r, --rule RULE         B/S rule like B36/S23, or a name like highlife
+                          [default: the pattern's rule, or B3/S23]
+      --radius N          count the cells up to N away in every direction, so
+                          --rule gives ranges of counts like B34..45/S33..57
+                          [default: 1, the eight nearest]
+      --rule-file FILE    the rule a Golly .rule file or a rule description
+                          file gives
+      --walls-alive       count the pattern's walls as alive around them,
+                          rather than dead
+      --source X,Y[,WxH]  keep the cell at X,Y, or the W by H block there,
+                          alive every generation whatever the rule; may be
+                          repeated
+      --sink X,Y[,WxH]    keep the cell or block dead likewise; may be
+                          repeated
+      --random [DENSITY]  start from a random soup [default density: 0.3]
+      --random-mode MODE  shape the soup: 'uniform', 'cluster' for blobs of
+                          it, 'gradient' thinning out to the right, 'ring'
+                          round the middle, or 'symmetric' for a square the
+                          same every way round, ':c2', ':d4' or another
+                          symmetry after it for fewer [default: uniform]
+      --seed N            seed for the random soup, printed when not given
+      --seed-string TEXT  start from the random soup TEXT hashes to, the same
+                          one every time
+      --pattern NAME      start from a built-in pattern instead of a file
+      --seed-text FILE    start from the text in FILE instead, each character
+                          other than whitespace a live cell
+      --text-scale N      grow each character of --seed-text into N by N
+                          cells [default: 1]
+      --seed-image FILE   start from a PNG image instead, fitted to the world
+                          with the cells darker than --threshold alive
+      --threshold T       lightness from 0 to 1 below which a cell of
+                          --seed-image is alive [default: 0.5]
+      --dither            dither --seed-image so grays become sparser cells
+      --place NAME@X,Y    put a built-in pattern or file with its top left
+                          corner at X,Y, turned first by any of ,rot90
+                          ,rot180 ,rot270 ,flipx ,flipy after Y in the
+                          order given; may be repeated
+      --tile NAME:WxH     cover the world in copies of a built-in pattern or
+                          file, one every W cells along and H down, with
+                          @X,Y after H to put one at X,Y and transforms
+                          after that as for --place; may be repeated
+  -g, --generations N     number of generations to simulate, 0 to run until
+                          stopped [default: 10, unlimited with --until or
+                          --until-pattern]
+      --forever           same as --generations 0
+      --until WHEN        stop once the world is 'extinct' or 'stable', meaning
+                          extinct, still or oscillating with a period up to 32
+      --until-pattern NAME
+                          stop once a copy of a built-in pattern or file turns
+                          up anywhere on its own, turned or not, saying where
+                          and when
+      --max-period N      longest oscillation that counts as stable [default: 32]
+      --break-on X,Y      stop the first time the cell at X,Y changes, or pause
+                          the window; may be repeated
+      --watch X,Y[,X,Y...]
+                          log the watched cells every generation to stderr,
+                          a line of the generation and 1 or 0 for each cell;
+                          may be repeated
+      --watch-log FILE    write the --watch log to FILE instead
+      --keep-going        carry on once the world stops changing instead of
+                          ending the run early
+      --watch-input       start over whenever the PATTERN file, --seed-text
+                          or --seed-image is saved again, and once the run
+                          ends wait for that instead of quitting
+      --perturb P@N       flip each cell with chance P every N generations,
+                          drawn from --seed, to see how well the world holds
+                          up; the run then carries on once it stops changing
+      --faults SPEC       fail like hardware after every generation, drawn
+                          from --seed: cells get stuck for good, flip or lose
+                          their row for the generation with the chances in
+                          'stuck=P,flip=P,row=P'; -v logs each stuck cell and
+                          dead row
+      --stop-on-growth    end the run with exit status 3 once the world seems
+                          to grow without bound
+  -d, --delay MS          pause between generations [default: 500,
+                          screensaver and demo: 100]
+      --fps N             show N generations a second however long each takes,
+                          instead of a fixed --delay, leaving frames undrawn
+                          when behind to keep up
+      --every N           only show every Nth generation and the last one,
+                          including in --audio, the ones between simulated
+                          at full speed with no --delay or --fps between
+                          them, to watch a long run at a pace [default: 1]
+      --steps-per-frame N the same as --every
+      --compare R1 R2     run side by side under two rules, e.g. B3/S23 B36/S23,
+                          given as two arguments as rules can hold commas
+      --layer RULE[@D]    stack a random soup of density D [default: 0.3]
+                          under RULE on the world, seeded --seed plus one for
+                          each layer before, and show them over each other
+                          in colors that add up; may be repeated
+      --couple L:M        let layer L come alive only where layer M was,
+                          or with L:!M only where it wasn't, the world being
+                          layer 1 and each --layer the next; may be repeated
+      --screensaver       endless random soups sized to the terminal
+      --demo              tour the built-in patterns sized to the terminal,
+                          each with a caption under it
+      --demo-seconds N    how long --demo shows each pattern [default: 15]
+      --window            show the simulation in an SDL window (sdl feature),
+                          with a panel beside it for the rule, the speed, a
+                          random soup's density and the built-in patterns;
+                          a pattern file dropped on it goes in the middle
+      --output-format F   'text', or 'jsonl' for one JSON object per generation
+                          [default: text, jsonl has no default delay]
+      --renderer NAME     how frames are shown: 'text' or 'jsonl' as with
+                          --output-format, 'incremental' for text drawn in
+                          place, redrawing only the cells that changed,
+                          'braille' for two by four cells a character, or
+                          'window' as with --window [default: text]
+      --diff              mark the cells born since the generation before each
+                          one shown with a green +, those that died with a red -
+      --trails N          let cells that died fade out over N generations
+                          instead of vanishing at once, to follow fast ships
+      --timing            show under each frame how long working it out,
+                          drawing and waiting took, the first two beside
+                          their averages over the last 30 frames, the frame
+                          rate and the frames that came late
+      --sparkline N       chart the population over the last N generations
+                          in braille under each frame
+      --metrics           show under each frame the density, the centroid of
+                          the live cells, their radius of gyration and how
+                          many are in each quarter of the world
+      --ghosts            draw a dim border of the cells from the opposite
+                          edges, the neighbors the world wraps around to
+      --color MODE        'plain', or 'density' to color each live cell by
+                          its live neighbors, blue with few through green to
+                          red with many [default: plain]
+      --footprint         keep every cell that has been alive in a frame
+                          shown on a faint background, the ground the
+                          pattern has covered
+      --scroll DX,DY      slide the view across the torus by DX columns and
+                          DY rows each generation, fractions like 0.25,0.1
+                          welcome; goes on even once the world is still
+      --zoom Z            cells each character of the text view shows: 'N'
+                          or 'NxM' for blocks shaded from ░ to █ by how
+                          many of their cells are live, or 'fit' for one a
+                          cell unless the world is bigger than the terminal
+                          [default: fit]
+      --cells WHAT        in jsonl, also give the live cells as a 'list' of
+                          [x, y] pairs or as 'rle' [default: none]
+      --audio FILE        also render the run as a WAV file, written as it
+                          goes, up to the 4 GiB a WAV file can hold
+      --midi PORT         also play the births and deaths of every generation
+                          shown as MIDI notes to PORT, a raw MIDI device like
+                          /dev/snd/midiC1D0 or a FIFO: the column is the
+                          pitch, births on channel 1, deaths on 2, and the
+                          population the velocity and channel pressure
+      --osc HOST:PORT     also send every generation over UDP as an Open
+                          Sound Control bundle of /conway/generation,
+                          /conway/population, /conway/births,
+                          /conway/deaths and /conway/bbox (x, y, width,
+                          height)
+      --osc-rows N        add /conway/rows, the live cells in each of N
+                          bands of rows from the top [default: 0, none]
+      --scale NAME        scale of --audio and --midi: major, minor, pentatonic, chromatic [default: pentatonic]
+      --volume V          audio volume between 0 and 1 [default: 0.5]
+      --heatmap FILE      also write a PNG of how often each cell was alive
+      --contact-sheet FILE
+                          also write a PNG of every generation shown, tiled
+                          in rows and numbered, so --every sets how many
+      --frames DIR        also write every generation shown to DIR as a PNG
+                          of its own, drawn as the [image] config table says
+      --frame-template T  what to call each of the --frames, {gen} standing
+                          for the generation or {gen:06} for it in six
+                          digits padded with zeros [default: gen{gen:06}.png]
+      --no-summary        don't report on the run once it is over
+  -o, --output FILE       write the JSON lines, summary and --final world to
+                          FILE instead of stdout
+      --final             write the world as it ends up, as RLE, or as a
+                          JSON line with --output-format jsonl
+      --print-hash WHEN   write the state hash of the world, the same on any
+                          machine for the same cells, after 'each'
+                          generation or at the 'end', as a line like
+                          'hash GEN HEX' or a JSON line with --output-format
+                          jsonl, to check runs come out alike
+      --telnet-port PORT  also show the frames to everyone who connects to
+                          PORT on any interface, e.g. with 'nc HOST PORT'
+      --checkpoint-every N
+                          write the world, its generation, rule and seed to
+                          a checkpoint file every N generations
+      --checkpoint-dir DIR
+                          where the checkpoints go [default: .]
+      --checkpoint-gzip   compress the checkpoints, as checkpoint-N.bin.gz
+      --resume FILE       carry on from a checkpoint, gzipped or not, up to
+                          the same --generations limit
+      --save-session FILE when the run ends, Ctrl-C or not, save its world,
+                          generation, speed, view, glyphs and sparkline
+                          history to FILE, gzipped if it ends in .gz
+      --load-session FILE carry on from a saved session, its settings in
+                          place of the flags, up to --generations
+      --save-on-interrupt when stopped with Ctrl-C, save the world to a file
+                          like conway-20240131-235959.rle named for the time
+      --record FILE       record the world and every cell toggled in the
+                          --window or set by --script for 'conway replay'
+      --script PROGRAM    run PROGRAM through the shell alongside, showing
+                          it every generation as a JSON line on its stdin
+                          and reading back commands until 'next': 'set X Y
+                          live|dead', 'place NAME X Y', 'log MESSAGE' and
+                          'stop [REASON]'
+      --control-fifo PATH take commands from the named pipe PATH, made if
+                          missing: 'pause', 'resume', 'speed FPS', 'set X Y
+                          [live|dead]', 'zoom Z' as --zoom, 'dump FILE' and
+                          'show GEN' to look back at a past generation
+";
+
+const EDIT_USAGE: &str = "\
+Draw a pattern interactively and save it.
+
+Usage: conway edit [OPTIONS] FILE
+
+FILE is loaded if it exists and written on save, as RLE or plaintext
+depending on its extension. A new FILE can start from a built-in pattern
+given with --pattern.
+
+Keys: arrows or hjkl move, space toggles, n steps one generation,
+c clears, Ctrl-Z undoes the last toggle, step, clear or paste and Ctrl-Y
+redoes it, t shows every cell that has been alive since the editor
+opened on a faint background or hides them again, s saves, q quits.
+After a step the status line shows how long it took to work out and
+draw, and the averages over the last 30 steps. A world bigger than the terminal shows the part around the cursor, with
+a minimap of the whole in the top right corner, the part in view
+lighter, which M hides or shows.
+
+p pastes the RLE or plaintext pattern on the system clipboard, read with
+wl-paste, xclip, xsel or pbpaste: it follows the cursor by its top left
+corner until enter or space stamps it, r turns it a quarter turn, f flips
+it and q puts it away.
+
+v starts selecting a rectangle from the cursor, which the arrows then
+stretch; y copies it to the clipboard as RLE and v stops selecting.
+While selecting, d clears the selected cells, f fills them, i inverts
+them, r randomizes them as full as + and - set, and m picks them up to
+move like a paste. L draws a line from where the selection started to
+the cursor, b a box around its edge, e an ellipse that fits it and E the
+same filled in; f fills a rectangle.
+
+Options:
+  -W, --width N           world width in cells [default: 40]
+  -H, --height N          world height in cells [default: 20]
+      --max-mem SIZE      largest world to allow, like 512M or 4G, so one far
+                          too big is refused rather than crashing [default:
+                          the memory available]
+      --fit [MARGIN]      size the world to the pattern's live cells with
+                          MARGIN dead cells all round, instead of --width
+                          and --height [default margin: 10]
+  -r, --rule RULE         B/S rule like B36/S23, or a name like highlife
+                          [default: the pattern's rule, or B3/S23]
+      --random [DENSITY]  start from a random soup [default density: 0.3]
+      --random-mode MODE  shape the soup: 'uniform', 'cluster' for blobs of
+                          it, 'gradient' thinning out to the right, 'ring'
+                          round the middle, or 'symmetric' for a square the
+                          same every way round, ':c2', ':d4' or another
+                          symmetry after it for fewer [default: uniform]
+      --seed N            seed for the random soup, printed when not given
+      --seed-string TEXT  start from the random soup TEXT hashes to, the same
+                          one every time
+      --pattern NAME      start from a built-in pattern instead of a file
+      --seed-text FILE    start from the text in FILE instead, each character
+                          other than whitespace a live cell
+      --text-scale N      grow each character of --seed-text into N by N
+                          cells [default: 1]
+      --seed-image FILE   start from a PNG image instead, fitted to the world
+                          with the cells darker than --threshold alive
+      --threshold T       lightness from 0 to 1 below which a cell of
+                          --seed-image is alive [default: 0.5]
+      --dither            dither --seed-image so grays become sparser cells
+      --place NAME@X,Y    put a built-in pattern or file with its top left
+                          corner at X,Y, turned first by any of ,rot90
+                          ,rot180 ,rot270 ,flipx ,flipy after Y in the
+                          order given; may be repeated
+      --tile NAME:WxH     cover the world in copies of a built-in pattern or
+                          file, one every W cells along and H down, with
+                          @X,Y after H to put one at X,Y and transforms
+                          after that as for --place; may be repeated
+";
+
+const CONVERT_USAGE: &str = "\
+Translate a pattern file to another format.
+
+Usage: conway convert [OPTIONS] INPUT OUTPUT
+
+Options:
+      --from FORMAT       read INPUT as rle, plaintext, life105 or life106
+                          whatever its extension
+      --to FORMAT         write OUTPUT in one of the same formats
+      --crop              trim empty rows and columns from the edges
+
+Formats are otherwise picked by extension: .rle, .cells or .txt for
+plaintext, and .lif for Life 1.06 when writing or either Life version when
+reading. The input's name, author and comments, #N, #O and #C in RLE, are
+carried over to the output, as comment lines in plaintext and Life 1.05;
+Life 1.06 has no place for them. A further .gz, as in soup.rle.gz,
+compresses the output, and gzipped input is read whatever its name.
+";
+
+const ANALYZE_USAGE: &str = "\
+Simulate without output and report statistics.
+
+Usage: conway analyze [OPTIONS] [PATTERN]
+
+Options:
+  -W, --width N           world width in cells [default: 40]
+  -H, --height N          world height in cells [default: 20]
+      --max-mem SIZE      largest world to allow, like 512M or 4G, so one far
+                          too big is refused rather than crashing [default:
+                          the memory available]
+      --fit [MARGIN]      size the world to the pattern's live cells with
+                          MARGIN dead cells all round, instead of --width
+                          and --height [default margin: 10]
+  -r, --rule RULE         B/S rule like B36/S23, or a name like highlife
+                          [default: the pattern's rule, or B3/S23]
+      --random [DENSITY]  start from a random soup [default density: 0.3]
+      --random-mode MODE  shape the soup: 'uniform', 'cluster' for blobs of
+                          it, 'gradient' thinning out to the right, 'ring'
+                          round the middle, or 'symmetric' for a square the
+                          same every way round, ':c2', ':d4' or another
+                          symmetry after it for fewer [default: uniform]
+      --seed N            seed for the random soup, printed when not given
+      --seed-string TEXT  start from the random soup TEXT hashes to, the same
+                          one every time
+      --pattern NAME      start from a built-in pattern instead of a file
+      --seed-text FILE    start from the text in FILE instead, each character
+                          other than whitespace a live cell
+      --text-scale N      grow each character of --seed-text into N by N
+                          cells [default: 1]
+      --seed-image FILE   start from a PNG image instead, fitted to the world
+                          with the cells darker than --threshold alive
+      --threshold T       lightness from 0 to 1 below which a cell of
+                          --seed-image is alive [default: 0.5]
+      --dither            dither --seed-image so grays become sparser cells
+      --place NAME@X,Y    put a built-in pattern or file with its top left
+                          corner at X,Y, turned first by any of ,rot90
+                          ,rot180 ,rot270 ,flipx ,flipy after Y in the
+                          order given; may be repeated
+      --tile NAME:WxH     cover the world in copies of a built-in pattern or
+                          file, one every W cells along and H down, with
+                          @X,Y after H to put one at X,Y and transforms
+                          after that as for --place; may be repeated
+  -g, --generations N     number of generations to simulate [default: 1000,
+                          unlimited with --until]
+      --until WHEN        stop once the world is 'extinct' or 'stable', meaning
+                          extinct, still or oscillating with a period up to 32
+      --max-period N      longest oscillation that counts as stable [default: 32]
+      --stop-on-growth    stop with exit status 3 once the world seems to grow
+                          without bound
+      --perturb P@N       flip each cell with chance P every N generations,
+                          drawn from --seed, and report how many flipped
+      --faults SPEC       cells stuck, flipped or in a row gone dead with the
+                          chances in 'stuck=P,flip=P,row=P', as for run
+      --apgcodes          also count the objects left by the apgcodes
+                          Catagolue knows them by, like xs4_33 for a block
+      --predecessor       instead look for a state that becomes the world's
+                          live cells in one generation, on an empty plane
+      --margin N          how far the predecessor may reach past the live
+                          cells on each side [default: 1]
+      --ensemble N        run N random soups seeded --seed, --seed plus 1
+                          and so on, each for all of --generations, and
+                          report on them together: how many settled, their
+                          mean lifespan and population, and how their
+                          mean population changed over the generations
+  -j, --threads N         worlds of the ensemble to run at once [default:
+                          one per CPU]
+      --curve FILE        also write the ensemble's mean, least and most
+                          population at every generation to FILE as CSV
+";
+
+const BENCH_USAGE: &str = "\
+Measure simulation speed on a random soup.
+
+Usage: conway bench [OPTIONS]
+
+Options:
+  -W, --width N           world width in cells [default: 256]
+  -H, --height N          world height in cells [default: 256]
+  -g, --generations N     number of generations to simulate [default: 1000]
+      --seed N            seed for the soup [default: 0]
+";
+
+const RENDER_USAGE: &str = "\
+Write the world after some generations as a PNG image.
+
+Usage: conway render [OPTIONS] -o FILE [PATTERN]
+
+Options:
+  -W, --width N           world width in cells [default: 40]
+  -H, --height N          world height in cells [default: 20]
+      --max-mem SIZE      largest world to allow, like 512M or 4G, so one far
+                          too big is refused rather than crashing [default:
+                          the memory available]
+      --fit [MARGIN]      size the world to the pattern's live cells with
+                          MARGIN dead cells all round, instead of --width
+                          and --height [default margin: 10]
+  -r, --rule RULE         B/S rule like B36/S23, or a name like highlife
+                          [default: the pattern's rule, or B3/S23]
+      --random [DENSITY]  start from a random soup [default density: 0.3]
+      --random-mode MODE  shape the soup: 'uniform', 'cluster' for blobs of
+                          it, 'gradient' thinning out to the right, 'ring'
+                          round the middle, or 'symmetric' for a square the
+                          same every way round, ':c2', ':d4' or another
+                          symmetry after it for fewer [default: uniform]
+      --seed N            seed for the random soup, printed when not given
+      --seed-string TEXT  start from the random soup TEXT hashes to, the same
+                          one every time
+      --pattern NAME      start from a built-in pattern instead of a file
+      --seed-text FILE    start from the text in FILE instead, each character
+                          other than whitespace a live cell
+      --text-scale N      grow each character of --seed-text into N by N
+                          cells [default: 1]
+      --seed-image FILE   start from a PNG image instead, fitted to the world
+                          with the cells darker than --threshold alive
+      --threshold T       lightness from 0 to 1 below which a cell of
+                          --seed-image is alive [default: 0.5]
+      --dither            dither --seed-image so grays become sparser cells
+      --place NAME@X,Y    put a built-in pattern or file with its top left
+                          corner at X,Y, turned first by any of ,rot90
+                          ,rot180 ,rot270 ,flipx ,flipy after Y in the
+                          order given; may be repeated
+      --tile NAME:WxH     cover the world in copies of a built-in pattern or
+                          file, one every W cells along and H down, with
+                          @X,Y after H to put one at X,Y and transforms
+                          after that as for --place; may be repeated
+  -g, --generations N     generations to simulate before rendering [default: 0]
+  -o, --output FILE       image to write
+      --cell-size PX      pixels per cell [default: 8]
+      --live-color C      color of live cells, a name like green, 0-255 or
+                          #rrggbb [default: the theme's]
+      --dead-color C      color of dead cells [default: the theme's]
+      --background C      color of the margin [default: the dead color]
+      --grid C            draw lines of color C around every cell, or 'none'
+      --margin PX         pixels of background around the cells [default: 0]
+      --by-age            shade live cells by how long they have been alive,
+                          the newborn brightest
+
+An [image] table in the config file sets the look of images for render and
+thumb alike.
+";
+
+const SEARCH_USAGE: &str = "\
+Run many random soups until they settle and count what they leave behind.
+
+Usage: conway search [OPTIONS]
+
+Each soup is a random square in the middle of an otherwise empty world.
+The census counts the objects left once it settled or gave up, and soups
+that produced spaceships other than gliders are listed. Soups that seem
+to grow without bound are counted as explosive and not run further.
+Soup N of a search is the first soup of the search with seed plus N.
+
+With --methuselah the soups are 5x5, none is given up on for growing,
+and the ones that take longest to settle are printed as RLE, to look
+for small patterns with long lives.
+
+Options:
+  -n, --soups N           number of soups to run [default: 1000]
+      --soup-size N       side of the random square [default: 16, or 5 with
+                          --methuselah]
+      --density D         chance of each soup cell being alive [default: 0.5]
+      --symmetry S        'c2' or 'c4' for soups that look the same after a
+                          half or quarter turn, 'd2' for soups mirrored left
+                          to right, 'd4' also top to bottom, 'd8' for all of
+                          those, 'd2x' and 'd4x' for diagonal mirrors
+                          [default: c1, no symmetry]
+  -W, --width N           world width in cells [default: 64]
+  -H, --height N          world height in cells [default: 64]
+  -r, --rule RULE         B/S rule like B36/S23, or a name like highlife
+                          [default: B3/S23]
+      --seed N            seed of the first soup, printed when not given
+  -g, --generations N     give up on soups still changing after this many
+                          generations [default: 10000]
+  -j, --threads N         soups to run at once [default: one per CPU]
+      --methuselah        look for long-lived soups instead of a census
+      --apgcodes          also count what the soups leave by the apgcodes
+                          Catagolue knows it by, like xs4_33 for a block
+      --top N             how many of the longest-lived soups to keep
+                          [default: 10 with --methuselah, otherwise 0]
+      --save DIR          also write them to DIR as .rle files
+      --progress          show on stderr how many soups are done, how fast
+                          and how long is left
+";
+
+const EVOLVE_USAGE: &str = "\
+Breed small patterns for long lives, growth or gliders.
+
+Usage: conway evolve [OPTIONS]
+
+A genetic search: --population random squares are each run in the middle
+of an empty world for up to --steps generations and scored by --fitness,
+then the next population is bred from them. The --elite best are kept as
+they are, and the rest are children of two parents picked by tournament,
+each cell taken from either one and then flipped with chance --mutation.
+The best pattern of every generation is printed as RLE after its score.
+The whole search follows from the seed, however many threads it runs on.
+
+Fitness is 'longevity', the generations until the pattern settles,
+'population', its live cells at the end, 'growth', how many more cells
+its bounding box covers at the end than at the start, or 'glider', the
+gliders flying at the end.
+
+Options:
+      --fitness F         what patterns are scored on [default: longevity]
+      --size N            side of the patterns [default: 8]
+      --density D         chance of each cell of the first patterns being
+                          alive [default: 0.5]
+      --population N      patterns in each generation [default: 50]
+  -g, --generations N     generations of patterns to breed [default: 20]
+      --steps N           generations to run each pattern for [default: 1000]
+      --mutation P        chance of each cell of a child flipping
+                          [default: 0.02]
+      --elite N           best patterns carried over unchanged [default: 2]
+  -W, --width N           world width in cells [default: 64]
+  -H, --height N          world height in cells [default: 64]
+  -r, --rule RULE         B/S rule like B36/S23, or a name like highlife
+                          [default: B3/S23]
+      --seed N            seed of the search, printed when not given
+  -j, --threads N         patterns to score at once [default: one per CPU]
+      --save DIR          also write the best of each generation to DIR as
+                          best-N.rle
+";
+
+const SERVE_USAGE: &str = "\
+Stream the simulation to web browsers.
+
+Usage: conway serve [OPTIONS] [PATTERN]
+
+Open the address printed at startup for a page that shows the run:
+click to toggle a cell, space pauses, n steps, + and - change the speed.
+Everyone connected shares the same world.
+
+The frames themselves are server-sent events at /stream, each the world
+as text or, with --output-format jsonl, the JSON object 'run' prints for
+it. The WebSocket at /ws sends the same events as JSON and takes the
+commands pause, resume, step, 'set X Y live|dead', 'toggle X Y' and
+'delay MS'. /metrics has counters for Prometheus to scrape.
+
+Other programs can drive the run over HTTP:
+  GET  /state             generation, population, size, rule and live
+                          cells as JSON, or the world as RLE with
+                          ?format=rle
+  POST /cells             set the cells given as 'X Y live|dead' lines
+  POST /place?x=X&y=Y     stamp the RLE or plaintext pattern in the body
+  POST /pause, /resume, /step, /delay?ms=MS
+                          steer the run
+Each POST answers with the generation, population, paused and delay.
+
+With --dbus the run is on the session bus too, as org.conway.Life with
+the object /org/conway/Life, for desktop widgets and scripts:
+  busctl --user call org.conway.Life /org/conway/Life org.conway.Life \
+      SetCell uub 4 5 true
+It has the methods Pause, Resume, Step, GetPopulation, which returns a
+number of cells, and SetCell(x, y, live).
+
+The server carries on once the run is over, until stopped with Ctrl-C.
+On Unix, 'kill -USR1 PID' saves the world as it is then to files like
+conway-20240131-235959.rle and .png, named for the time, without stopping.
+
+Options:
+  -W, --width N           world width in cells [default: 40]
+  -H, --height N          world height in cells [default: 20]
+      --max-mem SIZE      largest world to allow, like 512M or 4G, so one far
+                          too big is refused rather than crashing [default:
+                          the memory available]
+      --fit [MARGIN]      size the world to the pattern's live cells with
+                          MARGIN dead cells all round, instead of --width
+                          and --height [default margin: 10]
+  -r, --rule RULE         B/S rule like B36/S23, or a name like highlife
+                          [default: the pattern's rule, or B3/S23]
+      --random [DENSITY]  start from a random soup [default density: 0.3]
+      --random-mode MODE  shape the soup: 'uniform', 'cluster' for blobs of
+                          it, 'gradient' thinning out to the right, 'ring'
+                          round the middle, or 'symmetric' for a square the
+                          same every way round, ':c2', ':d4' or another
+                          symmetry after it for fewer [default: uniform]
+      --seed N            seed for the random soup, printed when not given
+      --seed-string TEXT  start from the random soup TEXT hashes to, the same
+                          one every time
+      --pattern NAME      start from a built-in pattern instead of a file
+      --seed-text FILE    start from the text in FILE instead, each character
+                          other than whitespace a live cell
+      --text-scale N      grow each character of --seed-text into N by N
+                          cells [default: 1]
+      --seed-image FILE   start from a PNG image instead, fitted to the world
+                          with the cells darker than --threshold alive
+      --threshold T       lightness from 0 to 1 below which a cell of
+                          --seed-image is alive [default: 0.5]
+      --dither            dither --seed-image so grays become sparser cells
+      --place NAME@X,Y    put a built-in pattern or file with its top left
+                          corner at X,Y, turned first by any of ,rot90
+                          ,rot180 ,rot270 ,flipx ,flipy after Y in the
+                          order given; may be repeated
+      --tile NAME:WxH     cover the world in copies of a built-in pattern or
+                          file, one every W cells along and H down, with
+                          @X,Y after H to put one at X,Y and transforms
+                          after that as for --place; may be repeated
+  -g, --generations N     number of generations to simulate, 0 for no limit
+                          [default: 0]
+      --until WHEN        stop once the world is 'extinct' or 'stable', meaning
+                          extinct, still or oscillating with a period up to 32
+      --max-period N      longest oscillation that counts as stable [default: 32]
+  -d, --delay MS          pause between generations [default: 200]
+      --output-format F   'text' or 'jsonl' frames [default: text]
+      --cells WHAT        in jsonl, also give the live cells as a 'list' of
+                          [x, y] pairs or as 'rle' [default: none]
+      --bind ADDR         address to listen on, 0.0.0.0:PORT to let other
+                          machines connect [default: 127.0.0.1:8080]
+      --record FILE       record the world and every cell the viewers change
+                          for 'conway replay'
+      --dbus              also take calls on the session bus, see above
+";
+
+const WORKER_USAGE: &str = "\
+Hold part of a world for 'conway distribute'.
+
+Usage: conway worker [OPTIONS]
+
+Waits for a coordinator started with 'conway distribute', steps the rows
+of the world it is handed, and then waits for the next one, until stopped
+with Ctrl-C.
+
+Options:
+      --listen ADDR       address to listen on, 0.0.0.0:PORT to let other
+                          machines connect [default: 127.0.0.1:7070]
+";
+
+const DISTRIBUTE_USAGE: &str = "\
+Run one world across several worker processes.
+
+Usage: conway distribute [OPTIONS] --worker ADDR... [PATTERN]
+
+Each worker, started with 'conway worker' on this machine or another one,
+holds a strip of the world's rows, and only the rows where strips meet
+pass between them each generation. A random soup from --random without
+any patterns is made a strip at a time, so the world doesn't have to fit
+in this process's memory unless --show or --output gather it.
+
+Options:
+      --worker ADDR       address of a worker; may be repeated, the first
+                          one takes the top rows
+  -W, --width N           world width in cells [default: 40]
+  -H, --height N          world height in cells [default: 20]
+      --max-mem SIZE      largest world to allow, like 512M or 4G, so one far
+                          too big is refused rather than crashing [default:
+                          the memory available]
+      --fit [MARGIN]      size the world to the pattern's live cells with
+                          MARGIN dead cells all round, instead of --width
+                          and --height [default margin: 10]
+  -r, --rule RULE         B/S rule like B36/S23, or a name like highlife
+                          [default: the pattern's rule, or B3/S23]
+      --random [DENSITY]  start from a random soup [default density: 0.3]
+      --random-mode MODE  shape the soup: 'uniform', 'cluster' for blobs of
+                          it, 'gradient' thinning out to the right, 'ring'
+                          round the middle, or 'symmetric' for a square the
+                          same every way round, ':c2', ':d4' or another
+                          symmetry after it for fewer [default: uniform]
+      --seed N            seed for the random soup, printed when not given
+      --seed-string TEXT  start from the random soup TEXT hashes to, the same
+                          one every time
+      --pattern NAME      start from a built-in pattern instead of a file
+      --seed-text FILE    start from the text in FILE instead, each character
+                          other than whitespace a live cell
+      --text-scale N      grow each character of --seed-text into N by N
+                          cells [default: 1]
+      --seed-image FILE   start from a PNG image instead, fitted to the world
+                          with the cells darker than --threshold alive
+      --threshold T       lightness from 0 to 1 below which a cell of
+                          --seed-image is alive [default: 0.5]
+      --dither            dither --seed-image so grays become sparser cells
+      --place NAME@X,Y    put a built-in pattern or file with its top left
+                          corner at X,Y, turned first by any of ,rot90
+                          ,rot180 ,rot270 ,flipx ,flipy after Y in the
+                          order given; may be repeated
+      --tile NAME:WxH     cover the world in copies of a built-in pattern or
+                          file, one every W cells along and H down, with
+                          @X,Y after H to put one at X,Y and transforms
+                          after that as for --place; may be repeated
+  -g, --generations N     number of generations to simulate [default: 100]
+      --every N           only report every Nth generation and the last one
+                          [default: 1]
+      --show              print the whole world along with each report
+  -o, --output FILE       write the world as it ends up to FILE
+";
+
+const REPLAY_USAGE: &str = "\
+Play back a run recorded with --record.
+
+Usage: conway replay [OPTIONS] FILE
+
+The recording has the world the run started from and every cell changed by
+hand along with the generation it was changed at, so the run comes out the
+same every time.
+
+Options:
+  -d, --delay MS          pause between generations [default: 500]
+      --every N           only show every Nth generation and the last one
+                          [default: 1]
+      --output-format F   'text', or 'jsonl' for one JSON object per generation
+                          [default: text]
+      --cells WHAT        in jsonl, also give the live cells as a 'list' of
+                          [x, y] pairs or as 'rle' [default: none]
+";
+
+const SCRUB_USAGE: &str = "\
+Run a world in the terminal with a timeline to go back through and branch
+from.
+
+Usage: conway scrub [OPTIONS] [PATTERN]
+
+Under the world, the timeline charts the population of every generation
+since the start, the one shown marked. Clicking or dragging along it goes
+to that generation, as do left and right a generation at a time, [ and ] a
+tenth of the run, and 0 and $ the first and the latest. p plays and pauses,
+carrying on from the generation shown: the generations seen before come
+out as they did, unless space toggles the cell under the cursor, moved
+with hjkl or a click, which branches the run there and forgets what came
+after. q quits.
+
+Options:
+  -W, --width N           world width in cells [default: 40]
+  -H, --height N          world height in cells [default: 20]
+  -r, --rule RULE         B/S rule like B36/S23, or a name like highlife
+                          [default: the pattern's rule, or B3/S23]
+      --random [DENSITY]  start from a random soup [default density: 0.3]
+      --seed N            seed for the random soup, printed when not given
+      --pattern NAME      start from a built-in pattern instead of a file
+  -d, --delay MS          pause between generations while playing
+                          [default: 100]
+
+Going back steps the world again from the nearest snapshot before, which
+are kept further apart the older they are, so memory grows slowly however
+long the run.
+";
+
+const SOAK_USAGE: &str = "\
+Cross-check the engines on random worlds and rules.
+
+Usage: conway soak [OPTIONS]
+
+Each configuration is a random soup of random size and density under a
+random Life-like rule. It is stepped by the world, by the world cut into
+the strips 'distribute' uses and by a plain reference grid, and every
+generation they must agree on each cell, on the births and deaths, and
+the world's population and bounding box must match its live cells. A
+panic counts as a failure too. Configuration N is the first configuration
+of a soak with seed plus N.
+
+The exit status is 1 if any configuration failed.
+
+Options:
+  -n, --configs N         number of configurations, 0 to go on until
+                          stopped [default: 0]
+  -g, --generations N     longest each configuration runs [default: 200]
+      --max-size N        largest width and height [default: 64]
+      --seed N            seed for the first configuration, printed when
+                          not given
+";
+
+const VERIFY_USAGE: &str = "\
+Record a golden run, or check the engine still evolves one the same way.
+
+Usage: conway verify --record FILE [OPTIONS] [PATTERN]
+       conway verify --against FILE
+       conway verify --engines A,B [OPTIONS] [PATTERN]
+
+--record steps the world and writes the state hash of every generation,
+the one 'run --print-hash' gives, to FILE as JSON with the world it
+started from. --against plays that world forward again from FILE alone
+and fails at the first generation whose hash differs, so worlds recorded
+once keep an engine that is changed honest. A soup's seed is printed
+when not given and kept in FILE.
+
+--engines steps the same world with two engines at once and fails at the
+first generation they disagree in, naming the first cell, row by row,
+they disagree on. The engines are 'world', the one every command uses,
+'strips', the world cut into four as 'distribute' runs it, and 'grid', a
+plain reference counted the slow and obvious way.
+
+Options:
+      --record FILE       write a golden run to FILE
+      --against FILE      check the golden run in FILE
+      --engines A,B       check engine A against engine B
+  -W, --width N           world width in cells [default: 40]
+  -H, --height N          world height in cells [default: 20]
+      --max-mem SIZE      largest world to allow, like 512M or 4G, so one far
+                          too big is refused rather than crashing [default:
+                          the memory available]
+      --fit [MARGIN]      size the world to the pattern's live cells with
+                          MARGIN dead cells all round, instead of --width
+                          and --height [default margin: 10]
+      --random [DENSITY]  start from a random soup [default density: 0.3]
+      --random-mode MODE  shape the soup: 'uniform', 'cluster' for blobs of
+                          it, 'gradient' thinning out to the right, 'ring'
+                          round the middle, or 'symmetric' for a square the
+                          same every way round, ':c2', ':d4' or another
+                          symmetry after it for fewer [default: uniform]
+      --seed N            seed for the random soup, printed when not given
+      --seed-string TEXT  start from the random soup TEXT hashes to, the same
+                          one every time
+      --pattern NAME      start from a built-in pattern instead of a file
+      --seed-text FILE    start from the text in FILE instead, each character
+                          other than whitespace a live cell
+      --text-scale N      grow each character of --seed-text into N by N
+                          cells [default: 1]
+      --seed-image FILE   start from a PNG image instead, fitted to the world
+                          with the cells darker than --threshold alive
+      --threshold T       lightness from 0 to 1 below which a cell of
+                          --seed-image is alive [default: 0.5]
+      --dither            dither --seed-image so grays become sparser cells
+      --place NAME@X,Y    put a built-in pattern or file with its top left
+                          corner at X,Y, turned first by any of ,rot90
+                          ,rot180 ,rot270 ,flipx ,flipy after Y in the
+                          order given; may be repeated
+      --tile NAME:WxH     cover the world in copies of a built-in pattern or
+                          file, one every W cells along and H down, with
+                          @X,Y after H to put one at X,Y and transforms
+                          after that as for --place; may be repeated
+  -g, --generations N     generations to record or check [default: 100]
+";
+
+const EXPLORE_USAGE: &str = "\
+Run one world under every rule of a family and chart the outcomes.
+
+Usage: conway explore [OPTIONS] [PATTERN]
+
+--space gives the birth and survival counts to vary: every rule using any
+of them is run from the same start, a random soup unless a pattern is
+given, until it dies out, settles or the generations run out. The matrix
+has a row for each set of birth counts and a column for each set of
+survival counts, read down the header, marking each rule's outcome:
+
+  .  dies out
+  S  stabilizes into a still life
+  O  oscillates, with the periods listed below the matrix
+  X  explodes, growing without bound
+  ~  churns, still changing at the end without having grown
+
+Options:
+      --space SPACE       counts to vary, like B3678/S2345 [default: B3678/S2345]
+  -W, --width N           world width in cells [default: 40]
+  -H, --height N          world height in cells [default: 20]
+      --max-mem SIZE      largest world to allow, like 512M or 4G, so one far
+                          too big is refused rather than crashing [default:
+                          the memory available]
+      --fit [MARGIN]      size the world to the pattern's live cells with
+                          MARGIN dead cells all round, instead of --width
+                          and --height [default margin: 10]
+      --random [DENSITY]  start from a random soup [default density: 0.3]
+      --random-mode MODE  shape the soup: 'uniform', 'cluster' for blobs of
+                          it, 'gradient' thinning out to the right, 'ring'
+                          round the middle, or 'symmetric' for a square the
+                          same every way round, ':c2', ':d4' or another
+                          symmetry after it for fewer [default: uniform]
+      --seed N            seed for the random soup, printed when not given
+      --seed-string TEXT  start from the random soup TEXT hashes to, the same
+                          one every time
+      --pattern NAME      start from a built-in pattern instead of a file
+      --seed-text FILE    start from the text in FILE instead, each character
+                          other than whitespace a live cell
+      --text-scale N      grow each character of --seed-text into N by N
+                          cells [default: 1]
+      --seed-image FILE   start from a PNG image instead, fitted to the world
+                          with the cells darker than --threshold alive
+      --threshold T       lightness from 0 to 1 below which a cell of
+                          --seed-image is alive [default: 0.5]
+      --dither            dither --seed-image so grays become sparser cells
+      --place NAME@X,Y    put a built-in pattern or file with its top left
+                          corner at X,Y, turned first by any of ,rot90
+                          ,rot180 ,rot270 ,flipx ,flipy after Y in the
+                          order given; may be repeated
+      --tile NAME:WxH     cover the world in copies of a built-in pattern or
+                          file, one every W cells along and H down, with
+                          @X,Y after H to put one at X,Y and transforms
+                          after that as for --place; may be repeated
+  -g, --generations N     longest each rule runs [default: 1000]
+      --max-period N      longest oscillation that counts as settled [default: 32]
+";
+
+const BATCH_USAGE: &str = "\
+Run the worlds listed in a manifest and report on each one.
+
+Usage: conway batch [OPTIONS] MANIFEST
+
+Each line of MANIFEST is a run, given by the options below that 'conway
+run' takes for the world, how long it goes on and when it stops, and
+optionally a PATTERN file. A value with spaces goes in double quotes.
+Blank lines and lines starting with # are skipped. For example:
+
+    --pattern r-pentomino -W 120 -H 120 -g 2000
+    --random 0.35 --seed 7 -r highlife --until stable
+
+The runs are shared out among --threads at once. The report has a row for
+each in the order of the manifest: its line, rule, size and seed, the
+generations it ran, its final and peak population, births and deaths, why
+it stopped and how many seconds it took. A run stops after --generations,
+once its world stops changing, or when --until is met.
+
+Options:
+  -j, --threads N         runs at once [default: one per CPU]
+  -o, --output FILE       write the report to FILE instead of stdout
+      --format FORMAT     'csv' or 'json' [default: json if FILE ends in
+                          .json, otherwise csv]
+
+Options of a run:
+  -W, --width N           world width in cells [default: 40]
+  -H, --height N          world height in cells [default: 20]
+      --max-mem SIZE      largest world to allow, like 512M or 4G, so one far
+                          too big is refused rather than crashing [default:
+                          the memory available]
+      --fit [MARGIN]      size the world to the pattern's live cells with
+                          MARGIN dead cells all round, instead of --width
+                          and --height [default margin: 10]
+  -r, --rule RULE         B/S rule like B36/S23, or a name like highlife
+                          [default: the pattern's rule, or B3/S23]
+      --random [DENSITY]  start from a random soup [default density: 0.3]
+      --random-mode MODE  shape the soup: 'uniform', 'cluster' for blobs of
+                          it, 'gradient' thinning out to the right, 'ring'
+                          round the middle, or 'symmetric' for a square the
+                          same every way round, ':c2', ':d4' or another
+                          symmetry after it for fewer [default: uniform]
+      --seed N            seed for the random soup [default: a fresh one]
+      --seed-string TEXT  start from the random soup TEXT hashes to, the same
+                          one every time
+      --pattern NAME      start from a built-in pattern instead of a file
+      --seed-text FILE    start from the text in FILE instead, each character
+                          other than whitespace a live cell
+      --text-scale N      grow each character of --seed-text into N by N
+                          cells [default: 1]
+      --seed-image FILE   start from a PNG image instead, fitted to the world
+                          with the cells darker than --threshold alive
+      --threshold T       lightness from 0 to 1 below which a cell of
+                          --seed-image is alive [default: 0.5]
+      --dither            dither --seed-image so grays become sparser cells
+      --place NAME@X,Y    put a built-in pattern or file with its top left
+                          corner at X,Y, turned first by any of ,rot90
+                          ,rot180 ,rot270 ,flipx ,flipy after Y in the
+                          order given; may be repeated
+      --tile NAME:WxH     cover the world in copies of a built-in pattern or
+                          file, one every W cells along and H down, with
+                          @X,Y after H to put one at X,Y and transforms
+                          after that as for --place; may be repeated
+  -g, --generations N     longest the run goes on [default: 1000]
+      --until WHEN        also stop once the world is 'extinct' or 'stable',
+                          meaning extinct, still or oscillating with a
+                          period up to --max-period
+      --max-period N      longest oscillation that counts as stable [default: 32]
+";
+
+const SWEEP_USAGE: &str = "\
+Run soups over a range of settings and tabulate how they turn out.
+
+Usage: conway sweep --vary PARAM=VALUES [OPTIONS] [PATTERN]
+
+--vary gives a setting and the values it takes, as START:END:STEP or a
+list like A,B,C; a second --vary sweeps both, every value of one with
+every value of the other. The settings are:
+
+  density  the density of the random soup
+  birth    the birth counts of the rule, like 3,36,378, each kept with the
+           survival counts of --rule
+  size     the width and height of a square world
+  width    the width of the world
+  height   the height of the world
+
+At each point --runs soups, seeded --seed, --seed plus 1 and so on, run
+until they settle into something extinct, still or oscillating, or the
+generations run out. The table has a row for each point with their mean
+lifespan, counting a soup that never settled as living all of
+--generations; the fraction that settled and that died out; and the mean
+fraction of the cells alive at the end. A start that is not a random soup
+runs once at each point, every run of it being the same.
+
+Options:
+      --vary PARAM=VALUES
+                          setting to sweep and its values; may be given twice
+      --runs N            soups to run at each point [default: 8]
+  -j, --threads N         soups to run at once [default: one per CPU]
+      --format FORMAT     write the table as 'csv' or 'json' instead of text
+      --heatmap FILE      also draw the points as a PNG, the first setting
+                          across and the second down, from black for the
+                          least --measure to white for the most
+      --measure MEASURE   what --heatmap shows: 'lifespan', 'settled',
+                          'extinct' or 'alive' [default: lifespan]
+  -W, --width N           world width in cells [default: 40]
+  -H, --height N          world height in cells [default: 20]
+      --max-mem SIZE      largest world to allow, like 512M or 4G, so one far
+                          too big is refused rather than crashing [default:
+                          the memory available]
+      --fit [MARGIN]      size the world to the pattern's live cells with
+                          MARGIN dead cells all round, instead of --width
+                          and --height [default margin: 10]
+  -r, --rule RULE         B/S rule like B36/S23, or a name like highlife
+                          [default: the pattern's rule, or B3/S23]
+      --random [DENSITY]  start from a random soup [default density: 0.3]
+      --random-mode MODE  shape the soup: 'uniform', 'cluster' for blobs of
+                          it, 'gradient' thinning out to the right, 'ring'
+                          round the middle, or 'symmetric' for a square the
+                          same every way round, ':c2', ':d4' or another
+                          symmetry after it for fewer [default: uniform]
+      --seed N            seed for the random soup, printed when not given
+      --seed-string TEXT  start from the random soup TEXT hashes to, the same
+                          one every time
+      --pattern NAME      start from a built-in pattern instead of a file
+      --seed-text FILE    start from the text in FILE instead, each character
+                          other than whitespace a live cell
+      --text-scale N      grow each character of --seed-text into N by N
+                          cells [default: 1]
+      --seed-image FILE   start from a PNG image instead, fitted to the world
+                          with the cells darker than --threshold alive
+      --threshold T       lightness from 0 to 1 below which a cell of
+                          --seed-image is alive [default: 0.5]
+      --dither            dither --seed-image so grays become sparser cells
+      --place NAME@X,Y    put a built-in pattern or file with its top left
+                          corner at X,Y, turned first by any of ,rot90
+                          ,rot180 ,rot270 ,flipx ,flipy after Y in the
+                          order given; may be repeated
+      --tile NAME:WxH     cover the world in copies of a built-in pattern or
+                          file, one every W cells along and H down, with
+                          @X,Y after H to put one at X,Y and transforms
+                          after that as for --place; may be repeated
+  -g, --generations N     longest each soup runs [default: 2000]
+      --max-period N      longest oscillation that counts as settled [default: 32]
+";
+
+const CHECK_USAGE: &str = "\
+Read pattern files strictly and report every problem with its line and
+column: malformed headers, unsupported rules, cells outside the declared
+size, a header that doesn't match the cells, stray characters.
+
+Usage: conway check [OPTIONS] FILE...
+
+Options:
+  -f, --format FORMAT     read the files as rle, plaintext, life105 or
+                          life106 [default: by extension and contents]
+
+Files without problems get a line with their size, cells and rule. The exit
+status is 1 if any file has problems.
+";
+
+const DIFF_USAGE: &str = "\
+Show the cells two patterns differ in.
+
+Usage: conway diff [OPTIONS] FIRST SECOND
+
+Options:
+      --offset DX,DY      put the top left corner of SECOND's file at DX,DY
+                          in FIRST's rather than lining up their live cells
+      --brief             leave out the map of the differences
+
+The patterns are lined up by the top left corners of their live cells, so
+the same pattern padded differently is the same. The map covers the cells
+of both: '-' only in FIRST, '+' only in SECOND, 'O' in both, '.' in
+neither. Differing rules count as a difference too. As with diff(1), the
+exit status is 0 if the patterns are the same, 1 if they differ and 2 if
+either can't be read.
+";
+
+const THUMB_USAGE: &str = "\
+Draw a pattern to a square PNG image of a fixed size, scaled up or down to
+fit and centered, as a preview.
+
+Usage: conway thumb [OPTIONS] -o FILE PATTERN
+
+Options:
+  -o, --output FILE       image to write
+  -s, --size PX           width and height of the image [default: 128]
+  -g, --generations N     generations to simulate first [default: 0]
+  -r, --rule RULE         B/S rule like B36/S23, or a name like highlife
+                          [default: the pattern's rule, or B3/S23]
+      --live-color C      color of live cells, a name like green, 0-255 or
+                          #rrggbb [default: the theme's]
+      --dead-color C      color of dead cells [default: the theme's]
+      --background C      color of the margin [default: the dead color]
+      --grid C            draw lines of color C around every cell, or 'none'
+      --margin PX         pixels of background around the cells [default: 0]
+      --filter F          how cells sharing a pixel are shown: 'average' shades
+                          it by how many are live, 'nearest' takes the first
+                          [default: average]
+
+Patterns larger than the image are shrunk, with no grid between the cells
+sharing pixels. The [image] table in the config file sets the look of
+images for render and thumb alike. To preview a whole collection:
+
+  for f in patterns/*.rle; do conway thumb \"$f\" -o \"${f%.rle}.png\"; done
+";
+
+const VERSUS_USAGE: &str = "\
+Play two-player Life, each side placing cells between rounds.
+
+Usage: conway versus [OPTIONS]
+
+Every live cell belongs to player one (X, red) or player two (O, blue).
+Cells live and die by the rule as always, and a cell being born takes the
+side of most of its live neighbors. The game starts from a random soup on
+the left half, mirrored onto the right half for player two, and before
+every round of --every generations each player in turn may place up to
+--cells cells of their own on empty squares. It ends after --generations
+or once a side has no cells left, and the side with more cells wins.
+
+Keys on a turn: arrows or hjkl move, space places a cell or takes back
+one placed this turn, enter ends the turn, q quits.
+
+Options:
+  -W, --width N           world width in cells [default: 40]
+  -H, --height N          world height in cells [default: 20]
+  -r, --rule RULE         B/S rule like B36/S23, or a name like highlife
+                          [default: B3/S23]
+      --random [DENSITY]  density of the starting soup, 0 for an empty
+                          world [default: 0.3]
+      --seed N            seed for the random soup, printed when not given
+      --every N           generations in a round [default: 10]
+      --cells N           cells each player may place a turn [default: 3]
+  -g, --generations N     length of the game [default: 200]
+  -d, --delay MS          pause between generations [default: 100]
+";
+
+const SPECIES_USAGE: &str = "\
+Run colored Life with several species competing for space.
+
+Usage: conway species [OPTIONS]
+
+Every live cell belongs to one of --species species, A to F, each its own
+color. Cells live and die by the rule as always, and a cell being born
+takes the species of most of its live neighbors, --ties saying who gets a
+birth two species have as many neighbors for. With --dead-to a species'
+cells count as dead to another's: they neither keep its cells alive nor
+help them be born. The world starts from a random soup, each live cell of
+a species picked evenly, or from the patterns given with --place, the
+first one's cells species A, the next one's B and so on. The population of each species is shown as the world
+runs and printed at the end, which comes after --generations or once no
+more than one species is left.
+
+Options:
+  -W, --width N           world width in cells [default: 40]
+  -H, --height N          world height in cells [default: 20]
+  -r, --rule RULE         B/S rule like B36/S23, or a name like highlife
+                          [default: B3/S23]
+      --random [DENSITY]  density of the starting soup [default: 0.3 unless
+                          a pattern is placed]
+      --seed N            seed for the random soup, printed when not given
+      --place NAME@X,Y    put a built-in pattern or file with its top left
+                          corner at X,Y, its cells all of the next species,
+                          turned first by any of ,rot90 ,rot180 ,rot270
+                          ,flipx ,flipy after Y; may be repeated
+      --species N         how many species, from 1 to 6 [default: 3]
+      --ties TIES         who gets a tied birth: 'alternate' takes turns
+                          along the diagonals, 'first' gives it to the
+                          first species in order, 'none' leaves the cell
+                          dead [default: alternate]
+      --dead-to A:B       count species A's cells as dead to species B's;
+                          may be repeated
+  -g, --generations N     generations to run [default: 200]
+  -d, --delay MS          pause between generations [default: 100]
+";
+
+const CAVEGEN_USAGE: &str = "\
+Make a cave map for a game, as text, PNG or JSON.
+
+Usage: conway cavegen [OPTIONS] WxH
+
+The map is W cells across and H down, made the usual way: rock is scattered
+at random over --fill of the cells, then smoothed for --smooth generations
+by the 4/5 rule, B5678/S45678 with rock alive, so a cell is rock when at
+least five of the nine around it are, past the edges counting as rock.
+Last, every pocket of floor that cannot be reached from the biggest cave
+along the sides of cells is filled in, unless --keep-pockets is given.
+
+Text maps have # for rock and . for floor. JSON maps are one object with
+the width, height, seed, the floor cells and the rows as those strings.
+
+Options:
+      --fill P            share of the cells to start as rock [default: 0.45]
+      --smooth N          generations of smoothing [default: 5]
+      --seed N            seed for the rock, printed when not given
+      --keep-pockets      keep the floor that is cut off from the biggest
+                          cave instead of filling it in
+  -o, --output FILE       write the map to FILE instead of stdout
+  -f, --format F          'text', 'png' or 'json' [default: from the --output
+                          extension, or text]
+      --scale PX          pixels along each side of a cell in a PNG map
+                          [default: 4]
+";
+
+const ENUMERATE_USAGE: &str = "\
+List every still life and oscillator that fits in a small box.
+
+Usage: conway enumerate [OPTIONS] [WxH]
+
+Every pattern that fills a box of up to W by H cells edge to edge is run
+in an otherwise empty, unbounded world, and those that come back to
+themselves within --max-period generations are kept: still lifes, which
+never change, and oscillators. Each is listed once, however it is turned,
+flipped or in whichever phase it was found, smallest first, with its
+period and cells and then as RLE. Patterns made of smaller ones side by
+side are listed too.
+
+The search doubles in length with every cell of the box: 5x5 takes
+seconds, 6x6 hours. Boxes are at most 8 cells a side.
+
+Options:
+      --max-period P      longest period of the oscillators to find, 1 for
+                          still lifes alone [default: 4]
+  -r, --rule RULE         B/S rule like B36/S23, or a name like highlife
+                          [default: B3/S23]
+  -j, --threads N         boxes to search at once [default: one per CPU]
+      --save DIR          also write each of them to DIR as still-N.rle or
+                          pP-N.rle
+";
+
+const COLLIDE_USAGE: &str = "\
+Throw one pattern at another from every nearby offset and phase.
+
+Usage: conway collide [OPTIONS] TARGET BULLET
+
+TARGET and BULLET are pattern files or built-in patterns. The bullet is
+put in every phase at every offset up to --range cells either way from
+the target, counting from the top left corners of their live cells, and
+each collision is run for --steps generations. Offsets where the two
+start close enough to react at once are skipped. Each run is one row of
+a table, with how it turned out, the live cells left and what they are:
+
+  miss           the two came out as they would have on their own
+  annihilation   nothing is left
+  still life     what is left is still
+  oscillator     what is left has settled, some of it oscillating
+  spaceship      spaceships fly off from whatever settled ash is left
+  explosion      it is still changing at the end
+
+Options:
+      --range N           furthest offset either way [default: 8]
+      --phases N          phases of the bullet to try [default: its period]
+      --steps N           generations to run each collision for
+                          [default: 256]
+      --only OUTCOME      list only the collisions that turned out so: miss,
+                          annihilation, still-life, oscillator, spaceship or
+                          explosion
+  -r, --rule RULE         B/S rule like B36/S23, or a name like highlife
+                          [default: the target's, or B3/S23]
+  -j, --threads N         collisions to run at once [default: one per CPU]
+";
+
+const REPL_USAGE: &str = "\
+Drive a world command by command at a prompt.
+
+Usage: conway repl [OPTIONS] [PATTERN]
+
+Commands are read from stdin one per line, such as 'load glider',
+'step 50', 'set 10 12', 'show', 'rule B36/S23' and 'save out.rle'; 'help'
+lists them all. A command that fails is reported and the next one is read,
+and lines starting with # are skipped, so a file of commands can be piped
+in. The world starts empty unless a pattern, --random or --place is given.
+
+Options:
+  -W, --width N           world width in cells [default: 40]
+  -H, --height N          world height in cells [default: 20]
+      --max-mem SIZE      largest world to allow, like 512M or 4G, so one far
+                          too big is refused rather than crashing [default:
+                          the memory available]
+      --fit [MARGIN]      size the world to the pattern's live cells with
+                          MARGIN dead cells all round, instead of --width
+                          and --height [default margin: 10]
+  -r, --rule RULE         B/S rule like B36/S23, or a name like highlife
+                          [default: the pattern's rule, or B3/S23]
+      --random [DENSITY]  start from a random soup [default density: 0.3]
+      --random-mode MODE  shape the soup: 'uniform', 'cluster' for blobs of
+                          it, 'gradient' thinning out to the right, 'ring'
+                          round the middle, or 'symmetric' for a square the
+                          same every way round, ':c2', ':d4' or another
+                          symmetry after it for fewer [default: uniform]
+      --seed N            seed for the random soups, printed when not given
+      --pattern NAME      start from a built-in pattern instead of a file
+      --seed-text FILE    start from the text in FILE instead, each character
+                          other than whitespace a live cell
+      --text-scale N      grow each character of --seed-text into N by N
+                          cells [default: 1]
+      --seed-image FILE   start from a PNG image instead, fitted to the world
+                          with the cells darker than --threshold alive
+      --threshold T       lightness from 0 to 1 below which a cell of
+                          --seed-image is alive [default: 0.5]
+      --dither            dither --seed-image so grays become sparser cells
+      --place NAME@X,Y    put a built-in pattern or file with its top left
+                          corner at X,Y, turned first by any of ,rot90
+                          ,rot180 ,rot270 ,flipx ,flipy after Y in the
+                          order given; may be repeated
+      --tile NAME:WxH     cover the world in copies of a built-in pattern or
+                          file, one every W cells along and H down, with
+                          @X,Y after H to put one at X,Y and transforms
+                          after that as for --place; may be repeated
+";
+
+const DEFAULT_DENSITY: f64 = 0.3;
+/// Dead cells `--fit` leaves around the pattern.
+const DEFAULT_FIT_MARGIN: usize = 10;
+
+#[derive(Clone)]
+pub enum PatternSource {
+    File(String),
+    Builtin(Pattern),
+    /// A text file from `--seed-text`.
+    Text(String),
+    /// A PNG image from `--seed-image`.
+    Image(String),
+}
+
+const STEP_USAGE: &str = "\
+Advance a world read from stdin and write it to stdout.
+
+Usage: conway step [OPTIONS]
+
+The input is RLE or plaintext and is the whole world, dead borders
+included, so the output can be piped into another step.
+
+Options:
+  -n, --generations N     number of generations to advance [default: 1]
+  -W, --width N           widen the world to N cells, keeping it centered
+  -H, --height N          make the world N cells tall, keeping it centered
+  -r, --rule RULE         rule instead of the input's own [default: B3/S23]
+  -f, --format FORMAT     output 'rle', 'plaintext', 'life105' or 'life106'
+                          [default: the input's]
+      --progress          show on stderr how far along it is, how fast and
+                          how long is left
+";
+
+/// A pattern at a chosen offset, from `--place NAME@X,Y`.
+#[derive(Clone)]
+pub struct Placement {
+    pub source: PatternSource,
+    pub x: usize,
+    pub y: usize,
+    /// Applied to the pattern in order before it is placed.
+    pub transforms: Vec<Transform>,
+    /// From `--tile`: copies across the whole world this many cells apart
+    /// along and down, one of them at `x`, `y`.
+    pub every: Option<(usize, usize)>,
+}
+
+/// Where the starting world comes from.
+#[derive(Clone)]
+pub struct WorldOptions {
+    pub width: usize,
+    pub height: usize,
+    pub pattern: Option<PatternSource>,
+    /// Used unless the pattern file names its own rule.
+    pub default_rule: Option<Rule>,
+    /// From `--rule`, which wins over the pattern's rule.
+    pub rule: Option<Rule>,
+    /// `--rule` as given, parsed again when `--radius` follows it.
+    pub rule_text: Option<String>,
+    /// From `--radius`, how far the cells `--rule` counts are.
+    pub radius: Option<usize>,
+    /// Whether wall cells count as alive for their neighbors.
+    pub walls_alive: bool,
+    /// Density of a random soup to start from instead of the glider.
+    pub random: Option<f64>,
+    /// Unset means a fresh seed, which is printed so the run can be repeated.
+    pub seed: Option<u64>,
+    /// How the cells of the soup are laid out.
+    pub random_mode: soup::Mode,
+    pub placements: Vec<Placement>,
+    /// From `--source` and `--sink`: cells forced alive or dead every
+    /// generation, set after the patterns are placed.
+    pub emitters: Vec<(Rect, Cell)>,
+    /// Cells to each character of `--seed-text`.
+    pub text_scale: usize,
+    /// Lightness below which a cell of `--seed-image` is alive.
+    pub threshold: f64,
+    pub dither: bool,
+    /// Bytes a world may take, unset meaning the memory available.
+    pub max_mem: Option<u64>,
+    /// From `--fit`: size the world to the pattern with this many dead
+    /// cells around it.
+    pub fit: Option<usize>,
+    /// Whether `--width` or `--height` was given, which `--fit` can't be.
+    pub sized: bool,
+}
+
+impl WorldOptions {
+    /// Sets `rule` from `--rule` and `--radius`, whichever comes last.
+    fn parse_rule(&mut self) -> Result<(), String> {
+        if let Some(text) = &self.rule_text {
+            self.rule = Some(match self.radius {
+                Some(radius) => Rule::parse_radius(text, radius)?,
+                None => Rule::parse(text)?,
+            });
+        }
+        Ok(())
+    }
+
+    fn from_config(config: &Config) -> WorldOptions {
+        WorldOptions {
+            width: config.width.unwrap_or(40),
+            height: config.height.unwrap_or(20),
+            pattern: None,
+            default_rule: config.rule,
+            rule: None,
+            rule_text: None,
+            radius: None,
+            walls_alive: false,
+            random: config.random,
+            seed: config.seed,
+            random_mode: soup::Mode::Uniform,
+            placements: Vec::new(),
+            emitters: Vec::new(),
+            text_scale: 1,
+            threshold: 0.5,
+            dither: false,
+            max_mem: None,
+            fit: None,
+            sized: false,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Jsonl,
+}
+
+impl OutputFormat {
+    pub fn parse(s: &str) -> Result<OutputFormat, String> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "jsonl" => Ok(OutputFormat::Jsonl),
+            _ => Err(format!(
+                "invalid value '{}' for --output-format: expected text or jsonl",
+                s
+            )),
+        }
+    }
+}
+
+pub struct RunOptions {
+    pub world: WorldOptions,
+    pub generations: u64,
+    /// Unset means the mode's own default.
+    pub delay: Option<u64>,
+    /// Replaces the delay with a frame rate that allows for the time spent on
+    /// each frame.
+    pub fps: Option<u32>,
+    /// Only every Nth generation is shown, though all are simulated.
+    pub every: u64,
+    pub until: Option<Until>,
+    /// Stop once a copy of this turns up.
+    pub until_pattern: Option<PatternSource>,
+    /// Cells that stop the run the first time they change.
+    pub breakpoints: Vec<(usize, usize)>,
+    /// Cells whose states are logged every generation, and the file they go
+    /// to rather than stderr.
+    pub watched: Vec<(usize, usize)>,
+    pub watch_log: Option<String>,
+    /// Longest cycle `--until stable` looks for.
+    pub max_period: usize,
+    /// Carry on after the world has stopped changing.
+    pub keep_going: bool,
+    /// The pattern file to start over from whenever it is saved.
+    pub watch_input: Option<String>,
+    pub perturb: Option<Perturb>,
+    pub faults: Option<Faults>,
+    pub stop_on_growth: bool,
+    pub compare: Option<(Rule, Rule)>,
+    /// Soups stacked on the world by `--layer`, each rule and density.
+    pub layers: Vec<(Rule, f64)>,
+    pub couplings: Vec<Link>,
+    /// Off with NO_COLOR, for the displays that choose their own colors.
+    pub color: bool,
+    pub screensaver: bool,
+    pub demo: bool,
+    pub demo_seconds: u64,
+    pub window: bool,
+    pub audio: Option<String>,
+    /// The raw MIDI device or FIFO births and deaths are played to.
+    pub midi: Option<String>,
+    /// The `host:port` Open Sound Control messages go to, and the bands of
+    /// rows they sum the cells of.
+    pub osc: Option<String>,
+    pub osc_rows: usize,
+    pub scale: Scale,
+    pub volume: f32,
+    /// Where to write the heat map of the run.
+    pub heatmap: Option<String>,
+    pub contact_sheet: Option<String>,
+    /// The directory to write every frame shown to as an image, and what
+    /// to call them, `gen{gen:06}.png` unless given.
+    pub frames: Option<String>,
+    pub frame_template: Option<Template>,
+    /// How the frames look, from `[image]`.
+    pub image: ImageStyle,
+    /// Report on the run once it is over.
+    pub summary: bool,
+    /// Mark what changed in each frame.
+    pub diff: bool,
+    /// Show how long each frame took under it.
+    pub timing: bool,
+    /// Chart the population over this many generations under each frame.
+    pub sparkline: Option<usize>,
+    /// Show where the live cells are and how spread out under each frame.
+    pub metrics: bool,
+    pub style: Style,
+    pub output: OutputFormat,
+    /// Where the JSON lines, summary and final world go instead of stdout.
+    pub data_file: Option<String>,
+    /// Write the world as it ends up with the data.
+    pub print_final: bool,
+    /// Write the state hash of each generation or the last with the data.
+    pub print_hash: Option<HashWhen>,
+    /// One of `render::NAMES` other than `window`, which sets `window`
+    /// instead.
+    pub renderer: String,
+    pub cells: Cells,
+    /// Also show the frames to anyone connecting to this port.
+    pub telnet_port: Option<u16>,
+    /// A program to show every generation and take commands from.
+    pub script: Option<String>,
+    /// A named pipe to take commands from while running.
+    pub control_fifo: Option<String>,
+    /// Write a checkpoint into `checkpoint_dir` every this many generations.
+    pub checkpoint_every: Option<u64>,
+    pub checkpoint_dir: String,
+    /// Compress the checkpoints with gzip.
+    pub checkpoint_gzip: bool,
+    /// Carry on from this checkpoint instead of starting a new world.
+    pub resume: Option<String>,
+    /// Where to save the session once the run ends, and the one to carry on
+    /// from.
+    pub save_session: Option<String>,
+    pub load_session: Option<String>,
+    pub save_on_interrupt: bool,
+    /// Where to record the run for `replay`.
+    pub record: Option<String>,
+}
+
+pub struct EditOptions {
+    pub world: WorldOptions,
+    pub path: String,
+}
+
+pub struct ConvertOptions {
+    pub input: String,
+    pub output: String,
+    /// Formats given rather than told by the extensions.
+    pub from: Option<Format>,
+    pub to: Option<Format>,
+    /// Trim empty rows and columns from the edges.
+    pub crop: bool,
+}
+
+pub struct AnalyzeOptions {
+    pub world: WorldOptions,
+    pub generations: u64,
+    pub until: Option<Until>,
+    pub max_period: usize,
+    pub stop_on_growth: bool,
+    pub perturb: Option<Perturb>,
+    pub faults: Option<Faults>,
+    /// Search for a predecessor instead of simulating.
+    pub predecessor: bool,
+    pub margin: usize,
+    /// Worlds to run instead of one, seeded one after another; 0 for one.
+    pub ensemble: u64,
+    pub threads: usize,
+    /// Where to write the ensemble's population curve as CSV.
+    pub curve: Option<String>,
+    /// Also count the objects left by their apgcodes.
+    pub apgcodes: bool,
+}
+
+pub struct BenchOptions {
+    pub width: usize,
+    pub height: usize,
+    pub generations: u64,
+    pub seed: u64,
+}
+
+pub struct RenderOptions {
+    pub world: WorldOptions,
+    pub generations: u64,
+    pub output: String,
+    pub by_age: bool,
+    pub style: Style,
+    pub image: ImageStyle,
+}
+
+pub struct StepOptions {
+    /// Unset keeps the size of the input.
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub generations: u64,
+    pub rule: Option<Rule>,
+    /// Unset writes the format that was read.
+    pub format: Option<Format>,
+    pub progress: bool,
+}
+
+pub struct SearchOptions {
+    pub width: usize,
+    pub height: usize,
+    pub soups: u64,
+    pub soup_size: usize,
+    pub density: f64,
+    pub symmetry: Symmetry,
+    pub rule: Rule,
+    pub seed: Option<u64>,
+    /// Soups still changing after this many generations are given up on.
+    pub max_generations: u64,
+    pub threads: usize,
+    /// The longest-lived soups to print, best first.
+    pub top: usize,
+    /// Print those instead of the census.
+    pub methuselah: bool,
+    pub save: Option<String>,
+    pub progress: bool,
+    /// Also count what the soups leave by apgcode.
+    pub apgcodes: bool,
+}
+
+pub struct EvolveOptions {
+    pub width: usize,
+    pub height: usize,
+    pub rule: Rule,
+    pub seed: Option<u64>,
+    pub fitness: Fitness,
+    /// Side of the patterns bred.
+    pub size: usize,
+    pub density: f64,
+    pub population: usize,
+    pub generations: u64,
+    /// Generations each pattern is run for to score it.
+    pub steps: u64,
+    pub mutation: f64,
+    pub elite: usize,
+    pub threads: usize,
+    pub save: Option<String>,
+}
+
+pub struct ServeOptions {
+    pub world: WorldOptions,
+    pub generations: u64,
+    pub delay: u64,
+    pub until: Option<Until>,
+    pub max_period: usize,
+    pub output: OutputFormat,
+    pub cells: Cells,
+    pub bind: String,
+    pub record: Option<String>,
+    /// Also take calls on the session bus.
+    pub dbus: bool,
+}
+
+pub struct ExploreOptions {
+    pub world: WorldOptions,
+    pub space: Space,
+    pub generations: u64,
+    pub max_period: usize,
+}
+
+pub enum VerifyMode {
+    Record(String),
+    Against(String),
+    /// Two of `engines::NAMES` stepping the same world.
+    Engines(String, String),
+}
+
+pub struct VerifyOptions {
+    pub world: WorldOptions,
+    pub mode: VerifyMode,
+    pub generations: u64,
+}
+
+pub struct VersusOptions {
+    pub world: WorldOptions,
+    /// Generations between turns.
+    pub every: u64,
+    /// Cells a player may place each turn.
+    pub cells: usize,
+    pub generations: u64,
+    pub delay: u64,
+    pub color: bool,
+}
+
+pub struct ScrubOptions {
+    pub world: WorldOptions,
+    pub delay: u64,
+    /// For the glyphs, and the blocks of the timeline without Unicode.
+    pub style: Style,
+}
+
+pub struct SpeciesOptions {
+    pub world: WorldOptions,
+    pub species: usize,
+    pub ties: Ties,
+    /// From `--dead-to A:B`: species whose cells count as dead to another's.
+    pub dead_to: Vec<(usize, usize)>,
+    pub generations: u64,
+    pub delay: u64,
+    pub color: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CaveFormat {
+    Text,
+    Png,
+    Json,
+}
+
+impl CaveFormat {
+    pub fn parse(s: &str) -> Result<CaveFormat, String> {
+        match s {
+            "text" => Ok(CaveFormat::Text),
+            "png" => Ok(CaveFormat::Png),
+            "json" => Ok(CaveFormat::Json),
+            _ => Err(format!(
+                "unknown map format '{}', expected text, png or json",
+                s
+            )),
+        }
+    }
+}
+
+pub struct CaveOptions {
+    pub width: usize,
+    pub height: usize,
+    /// Share of the cells that start as rock.
+    pub fill: f64,
+    /// Generations of the 4/5 rule.
+    pub smooth: u64,
+    pub seed: Option<u64>,
+    pub keep_pockets: bool,
+    pub output: Option<String>,
+    pub format: CaveFormat,
+    /// Pixels to a cell of a PNG map.
+    pub scale: usize,
+    pub style: Style,
+}
+
+pub struct EnumerateOptions {
+    /// The largest box searched.
+    pub width: usize,
+    pub height: usize,
+    pub max_period: usize,
+    pub rule: Rule,
+    pub threads: usize,
+    pub save: Option<String>,
+}
+
+pub struct CollideOptions {
+    pub first: PatternSource,
+    pub second: PatternSource,
+    /// The names they were given, for the report.
+    pub first_name: String,
+    pub second_name: String,
+    pub range: i64,
+    pub phases: Option<usize>,
+    pub steps: u64,
+    pub only: Option<Outcome>,
+    pub rule: Option<Rule>,
+    pub threads: usize,
+}
+
+pub struct ReplOptions {
+    pub world: WorldOptions,
+}
+
+/// A line of a `batch` manifest.
+pub struct BatchRun {
+    /// Where in the manifest it is, counting from 1.
+    pub line: usize,
+    pub world: WorldOptions,
+    pub generations: u64,
+    pub until: Option<Until>,
+    pub max_period: usize,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ReportFormat {
+    Csv,
+    Json,
+}
+
+pub struct BatchOptions {
+    pub manifest: String,
+    pub runs: Vec<BatchRun>,
+    pub threads: usize,
+    /// Unset writes the report to stdout.
+    pub output: Option<String>,
+    pub format: ReportFormat,
+}
+
+pub struct SweepOptions {
+    pub world: WorldOptions,
+    /// One or two settings to vary, the first fastest.
+    pub axes: Vec<Axis>,
+    pub runs: usize,
+    pub threads: usize,
+    pub generations: u64,
+    pub max_period: usize,
+    /// Unset writes the table as text.
+    pub format: Option<ReportFormat>,
+    pub heatmap: Option<String>,
+    pub measure: Measure,
+}
+
+pub struct CheckOptions {
+    pub files: Vec<String>,
+    /// The format the files are in, rather than going by their names.
+    pub format: Option<Format>,
+}
+
+pub struct ThumbOptions {
+    pub input: String,
+    pub output: String,
+    /// Pixels along each side.
+    pub size: usize,
+    pub generations: u64,
+    pub rule: Option<Rule>,
+    pub style: Style,
+    pub image: ImageStyle,
+}
+
+pub struct DiffOptions {
+    pub first: String,
+    pub second: String,
+    /// Where the second pattern goes on the first, rather than lining up
+    /// their live cells.
+    pub offset: Option<(i64, i64)>,
+    pub brief: bool,
+}
+
+pub struct SoakOptions {
+    /// Zero means no end.
+    pub configs: u64,
+    pub generations: u64,
+    pub max_size: usize,
+    pub seed: Option<u64>,
+}
+
+pub struct ReplayOptions {
+    pub path: String,
+    pub delay: u64,
+    pub every: u64,
+    pub style: Style,
+    pub output: OutputFormat,
+    pub cells: Cells,
+}
+
+pub struct WorkerOptions {
+    pub listen: String,
+}
+
+pub struct DistributeOptions {
+    pub world: WorldOptions,
+    pub workers: Vec<String>,
+    pub generations: u64,
+    pub every: u64,
+    /// Gather and print the world with every report.
+    pub show: bool,
+    pub output: Option<String>,
+}
+
+pub enum Command {
+    Run(Box<RunOptions>),
+    Edit(EditOptions),
+    Convert(ConvertOptions),
+    Analyze(AnalyzeOptions),
+    Bench(BenchOptions),
+    Render(RenderOptions),
+    Step(StepOptions),
+    Search(SearchOptions),
+    Evolve(EvolveOptions),
+    Serve(ServeOptions),
+    Worker(WorkerOptions),
+    Distribute(DistributeOptions),
+    Replay(ReplayOptions),
+    Soak(SoakOptions),
+    Explore(ExploreOptions),
+    Verify(VerifyOptions),
+    Versus(VersusOptions),
+    Scrub(ScrubOptions),
+    Species(SpeciesOptions),
+    Cavegen(CaveOptions),
+    Enumerate(EnumerateOptions),
+    Collide(CollideOptions),
+    Repl(ReplOptions),
+    Batch(BatchOptions),
+    Sweep(SweepOptions),
+    Check(CheckOptions),
+    Thumb(ThumbOptions),
+    Diff(DiffOptions),
+    Help(&'static str),
+    Version,
+}
+
+enum Arg {
+    Flag(String),
+    Positional(String),
+}
+
+/// Splits the arguments into flags, their values and positionals.
+struct Parser<'a, I: Iterator> {
+    args: Peekable<I>,
+    flag: String,
+    /// The `value` part of `--flag=value`, until it is consumed.
+    inline: Option<String>,
+    config: &'a Config,
+}
+
+impl<'a, I: Iterator<Item = String>> Parser<'a, I> {
+    fn next(&mut self) -> Result<Option<Arg>, String> {
+        if self.inline.is_some() {
+            return Err(format!("{} does not take a value", self.flag));
+        }
+        let arg = match self.args.next() {
+            Some(arg) => arg,
+            None => return Ok(None),
+        };
+        if !arg.starts_with('-') || arg == "-" {
+            return Ok(Some(Arg::Positional(arg)));
+        }
+        match arg.find('=') {
+            Some(i) if arg.starts_with("--") => {
+                self.flag = arg[..i].to_string();
+                self.inline = Some(arg[i + 1..].to_string());
+            }
+            _ => self.flag = arg,
+        }
+        Ok(Some(Arg::Flag(self.flag.clone())))
+    }
+
+    fn value(&mut self) -> Result<String, String> {
+        match self.inline.take() {
+            Some(value) => Ok(value),
+            None => self
+                .args
+                .next()
+                .ok_or_else(|| format!("{} requires a value", self.flag)),
+        }
+    }
+
+    fn parse<T: FromStr>(&mut self, expected: &str) -> Result<T, String> {
+        let value = self.value()?;
+        value.parse().map_err(|_| {
+            format!(
+                "invalid value '{}' for {}: expected {}",
+                value, self.flag, expected
+            )
+        })
+    }
+
+    /// An optional value: `--flag=value`, or the next argument if it parses.
+    fn optional<T: FromStr>(&mut self, expected: &str) -> Result<Option<T>, String> {
+        if self.inline.is_some() {
+            return self.parse(expected).map(Some);
+        }
+        match self.args.peek().map(|arg| arg.parse()) {
+            Some(Ok(value)) => {
+                self.args.next();
+                Ok(Some(value))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Handles the flags every world-building command shares, returning false
+    /// for flags it doesn't know.
+    fn world_flag(&mut self, flag: &str, world: &mut WorldOptions) -> Result<bool, String> {
+        match flag {
+            "-W" | "--width" => {
+                world.width = self.parse("a number of cells")?;
+                world.sized = true;
+            }
+            "-H" | "--height" => {
+                world.height = self.parse("a number of cells")?;
+                world.sized = true;
+            }
+            "--fit" => {
+                let margin = self.optional("a number of cells")?;
+                world.fit = Some(margin.unwrap_or(DEFAULT_FIT_MARGIN));
+            }
+            "--max-mem" => {
+                let value = self.value()?;
+                let size = memory::parse_size(&value).ok_or_else(|| {
+                    format!(
+                        "invalid value '{}' for --max-mem: expected a size like 512M or 4G",
+                        value
+                    )
+                })?;
+                world.max_mem = Some(size);
+            }
+            "--random" => {
+                let density = self
+                    .optional("a density between 0 and 1")?
+                    .unwrap_or(DEFAULT_DENSITY);
+                if !(0.0..=1.0).contains(&density) {
+                    return Err(format!(
+                        "--random density must be between 0 and 1, got {}",
+                        density
+                    ));
+                }
+                world.random = Some(density);
+            }
+            "--seed" => world.seed = Some(self.parse("a number")?),
+            "--random-mode" => {
+                world.random_mode = soup::Mode::parse(&self.value()?)?;
+                world.random.get_or_insert(DEFAULT_DENSITY);
+            }
+            "--seed-string" => {
+                world.seed = Some(Rng::text_seed(&self.value()?));
+                world.random.get_or_insert(DEFAULT_DENSITY);
+            }
+            "-r" | "--rule" => {
+                world.rule_text = Some(self.value()?);
+                world.parse_rule()?;
+            }
+            "--radius" => {
+                world.radius = Some(self.parse("a number of cells")?);
+                world.parse_rule()?;
+            }
+            "--walls-alive" => world.walls_alive = true,
+            "--source" | "--sink" => {
+                let value = self.value()?;
+                let rect = parse_cells(&value).ok_or_else(|| {
+                    format!(
+                        "invalid {} '{}', expected a cell like 12,7 or a block like 12,7,3x2",
+                        flag, value
+                    )
+                })?;
+                let cell = if flag == "--source" {
+                    Cell::Live
+                } else {
+                    Cell::Dead
+                };
+                world.emitters.push((rect, cell));
+            }
+            "--rule-file" => {
+                let rule = Rule::load(&self.value()?)?;
+                world.rule_text = Some(rule.to_string());
+                world.parse_rule()?;
+            }
+            "--pattern" => {
+                let name = self.value()?;
+                let pattern = library::get(&name).ok_or_else(|| {
+                    let names: Vec<_> = library::names().collect();
+                    format!(
+                        "unknown pattern '{}', expected one of {}",
+                        name,
+                        names.join(", ")
+                    )
+                })?;
+                if world.pattern.is_some() {
+                    return Err("give either a PATTERN file or --pattern, not both".to_string());
+                }
+                world.pattern = Some(PatternSource::Builtin(pattern));
+            }
+            "--seed-text" => {
+                let path = self.value()?;
+                if world.pattern.is_some() {
+                    return Err("give either a PATTERN file or --seed-text, not both".to_string());
+                }
+                world.pattern = Some(PatternSource::Text(path));
+            }
+            "--text-scale" => {
+                world.text_scale = self.parse("a number of cells")?;
+                if world.text_scale == 0 {
+                    return Err("--text-scale must be at least 1".to_string());
+                }
+            }
+            "--seed-image" => {
+                let path = self.value()?;
+                if world.pattern.is_some() {
+                    return Err("give either a PATTERN file or --seed-image, not both".to_string());
+                }
+                world.pattern = Some(PatternSource::Image(path));
+            }
+            "--threshold" => {
+                world.threshold = self.parse("a lightness")?;
+                if !(0.0..=1.0).contains(&world.threshold) {
+                    return Err(format!(
+                        "--threshold must be between 0 and 1, got {}",
+                        world.threshold
+                    ));
+                }
+            }
+            "--dither" => world.dither = true,
+            "--place" => {
+                let value = self.value()?;
+                let placement = parse_placement(&value, self.config)
+                    .ok_or_else(|| {
+                        format!(
+                            "invalid --place '{}', expected NAME@X,Y and maybe ,rot90, ,rot180, ,rot270, ,flipx or ,flipy",
+                            value
+                        )
+                    })?;
+                world.placements.push(placement);
+            }
+            "--tile" => {
+                let value = self.value()?;
+                let tile = parse_tile(&value, self.config).ok_or_else(|| {
+                    format!(
+                        "invalid --tile '{}', expected NAME:WxH and maybe @X,Y and transforms as for --place",
+                        value
+                    )
+                })?;
+                world.placements.push(tile);
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Handles the flags of how images look that `render` and `thumb` share,
+    /// returning false for flags it doesn't know.
+    fn image_flag(&mut self, flag: &str, image: &mut ImageStyle) -> Result<bool, String> {
+        match flag {
+            "--live-color" => image.live = Some(Color::parse(&self.value()?)?),
+            "--dead-color" => image.dead = Some(Color::parse(&self.value()?)?),
+            "--background" => image.background = Some(Color::parse(&self.value()?)?),
+            "--grid" => {
+                image.grid = match self.value()?.as_str() {
+                    "none" => None,
+                    color => Some(Color::parse(color)?),
+                }
+            }
+            "--margin" => image.margin = self.parse("a number of pixels")?,
+            "--filter" => image.filter = Filter::parse(&self.value()?)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+/// `NAME@X,Y`, where NAME is a built-in pattern or a pattern file, then any
+/// transforms like `,rot90`.
+fn parse_placement(value: &str, config: &Config) -> Option<Placement> {
+    let (name, at) = value.rsplit_once('@')?;
+    let mut at = at.split(',');
+    let (x, y) = (at.next()?, at.next()?);
+    let transforms = at.map(|t| Transform::parse(t.trim()).ok());
+    let source = match library::get(name) {
+        Some(pattern) => PatternSource::Builtin(pattern),
+        None if !name.is_empty() => PatternSource::File(config.resolve_pattern(name)),
+        None => return None,
+    };
+    Some(Placement {
+        source,
+        x: x.trim().parse().ok()?,
+        y: y.trim().parse().ok()?,
+        transforms: transforms.collect::<Option<_>>()?,
+        every: None,
+    })
+}
+
+/// `DX,DY` for `--scroll`, cells per generation either way.
+fn parse_scroll(value: &str) -> Result<(f64, f64), String> {
+    let number = |n: &str| n.trim().parse::<f64>().ok().filter(|n| n.is_finite());
+    let pair = value.split_once(',');
+    match pair.map(|(dx, dy)| (number(dx), number(dy))) {
+        Some((Some(dx), Some(dy))) => Ok((dx, dy)),
+        _ => Err(format!(
+            "invalid --scroll '{}', expected columns and rows per generation like 0.5,0",
+            value
+        )),
+    }
+}
+
+fn parse_offset(value: &str) -> Result<(i64, i64), String> {
+    let number = |n: &str| n.trim().parse::<i64>().ok();
+    let pair = value.split_once(',');
+    match pair.map(|(dx, dy)| (number(dx), number(dy))) {
+        Some((Some(dx), Some(dy))) => Ok((dx, dy)),
+        _ => Err(format!(
+            "invalid --offset '{}', expected columns and rows like 3,-2",
+            value
+        )),
+    }
+}
+
+/// `X,Y` for one cell, or `X,Y,WxH` for the block with its top left corner
+/// there.
+fn parse_cells(value: &str) -> Option<Rect> {
+    let mut parts = value.split(',');
+    let (x, y) = (parts.next()?, parts.next()?);
+    let (width, height) = match parts.next() {
+        Some(size) => {
+            let (w, h) = size.split_once('x')?;
+            (w.trim().parse().ok()?, h.trim().parse().ok()?)
+        }
+        None => (1, 1),
+    };
+    if parts.next().is_some() || width == 0 || height == 0 {
+        return None;
+    }
+    Some(Rect {
+        x: x.trim().parse().ok()?,
+        y: y.trim().parse().ok()?,
+        width,
+        height,
+    })
+}
+
+/// `NAME:WxH`, then optionally `@X,Y` and transforms as for `--place`.
+fn parse_tile(value: &str, config: &Config) -> Option<Placement> {
+    let (name, rest) = value.rsplit_once(':')?;
+    let (spacing, at) = match rest.split_once('@') {
+        Some((spacing, at)) => (spacing, format!("{}@{}", name, at)),
+        None => match rest.split_once(',') {
+            Some((spacing, transforms)) => (spacing, format!("{}@0,0,{}", name, transforms)),
+            None => (rest, format!("{}@0,0", name)),
+        },
+    };
+    let (across, down) = spacing.split_once('x')?;
+    let every = (across.trim().parse().ok()?, down.trim().parse().ok()?);
+    if every.0 == 0 || every.1 == 0 {
+        return None;
+    }
+    Some(Placement {
+        every: Some(every),
+        ..parse_placement(&at, config)?
+    })
+}
+
+fn parse_max_period<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<usize, String> {
+    let max = p.parse("a number of generations")?;
+    if max == 0 {
+        return Err("--max-period must be at least 1".to_string());
+    }
+    Ok(max)
+}
+
+fn unknown(arg: Arg) -> String {
+    match arg {
+        Arg::Flag(flag) => format!("unknown option '{}'", flag),
+        Arg::Positional(arg) => format!("unexpected argument '{}'", arg),
+    }
+}
+
+fn usage_of(command: &str) -> Option<&'static str> {
+    match command {
+        "run" => Some(RUN_USAGE),
+        "edit" => Some(EDIT_USAGE),
+        "convert" => Some(CONVERT_USAGE),
+        "check" => Some(CHECK_USAGE),
+        "thumb" => Some(THUMB_USAGE),
+        "diff" => Some(DIFF_USAGE),
+        "analyze" => Some(ANALYZE_USAGE),
+        "bench" => Some(BENCH_USAGE),
+        "render" => Some(RENDER_USAGE),
+        "step" => Some(STEP_USAGE),
+        "search" => Some(SEARCH_USAGE),
+        "evolve" => Some(EVOLVE_USAGE),
+        "serve" => Some(SERVE_USAGE),
+        "worker" => Some(WORKER_USAGE),
+        "distribute" => Some(DISTRIBUTE_USAGE),
+        "replay" => Some(REPLAY_USAGE),
+        "scrub" => Some(SCRUB_USAGE),
+        "soak" => Some(SOAK_USAGE),
+        "explore" => Some(EXPLORE_USAGE),
+        "verify" => Some(VERIFY_USAGE),
+        "versus" => Some(VERSUS_USAGE),
+        "species" => Some(SPECIES_USAGE),
+        "cavegen" => Some(CAVEGEN_USAGE),
+        "enumerate" => Some(ENUMERATE_USAGE),
+        "collide" => Some(COLLIDE_USAGE),
+        "repl" => Some(REPL_USAGE),
+        "batch" => Some(BATCH_USAGE),
+        "sweep" => Some(SWEEP_USAGE),
+        _ => None,
+    }
+}
+
+/// Removes `flag VALUE` or `flag=VALUE` from the arguments, for the flags
+/// that have to be handled before everything else is parsed.
+fn take_global(args: &mut Vec<String>, flag: &str) -> Result<Option<String>, String> {
+    let inline = format!("{}=", flag);
+    let i = match args
+        .iter()
+        .position(|a| a == flag || a.starts_with(&inline))
+    {
+        Some(i) => i,
+        None => return Ok(None),
+    };
+    let arg = args.remove(i);
+    if let Some(value) = arg.strip_prefix(&inline) {
+        return Ok(Some(value.to_string()));
+    }
+    if i < args.len() {
+        Ok(Some(args.remove(i)))
+    } else {
+        Err(format!("{} requires a value", flag))
+    }
+}
+
+/// Removes `--config FILE`, since the file is read before anything else.
+/// `CONWAY_CONFIG` applies when the flag is absent.
+pub fn take_config(args: &mut Vec<String>) -> Result<Option<String>, String> {
+    Ok(take_global(args, "--config")?.or_else(|| env::var("CONWAY_CONFIG").ok()))
+}
+
+/// Removes `-v`, `-vv`, `--verbose` and `--log-format FMT`, which every
+/// command takes wherever they are given, and returns how verbose to be and
+/// in what format.
+pub fn take_logging(args: &mut Vec<String>) -> Result<(u8, log::Format), String> {
+    let mut verbose = 0;
+    args.retain(|arg| {
+        let more = match arg.as_str() {
+            "-v" | "--verbose" => 1,
+            "-vv" => 2,
+            _ => return true,
+        };
+        verbose += more;
+        false
+    });
+    let format = match take_global(args, "--log-format")? {
+        Some(format) => log::Format::parse(&format)?,
+        None => log::Format::Text,
+    };
+    Ok((verbose, format))
+}
+
+/// Removes `--profile NAME`, which changes the defaults of the other options.
+pub fn take_profile(args: &mut Vec<String>) -> Result<Option<String>, String> {
+    take_global(args, "--profile")
+}
+
+/// Removes `--theme NAME`, which changes the colors of the others.
+pub fn take_theme(args: &mut Vec<String>) -> Result<Option<String>, String> {
+    take_global(args, "--theme")
+}
+
+/// Parses the arguments without the program name, `--config`, `--profile`
+/// and `--theme` already taken out.
+pub fn parse<I: IntoIterator<Item = String>>(args: I, config: &Config) -> Result<Command, String> {
+    let mut args = args.into_iter().peekable();
+    if let Some(command) = args.peek().and_then(|arg| global_flag(arg, USAGE)) {
+        return Ok(command);
+    }
+    // plain `conway --width 80` keeps meaning `conway run --width 80`
+    let command = match args.peek() {
+        Some(arg) if !arg.starts_with('-') => args.next().unwrap(),
+        _ => "run".to_string(),
+    };
+    let mut p = Parser {
+        args,
+        flag: String::new(),
+        inline: None,
+        config,
+    };
+
+    match command.as_str() {
+        "run" => parse_run(&mut p),
+        "edit" => parse_edit(&mut p),
+        "convert" => parse_convert(&mut p),
+        "check" => parse_check(&mut p),
+        "thumb" => parse_thumb(&mut p),
+        "diff" => parse_diff(&mut p),
+        "analyze" => parse_analyze(&mut p),
+        "bench" => parse_bench(&mut p),
+        "render" => parse_render(&mut p),
+        "step" => parse_step(&mut p),
+        "search" => parse_search(&mut p),
+        "evolve" => parse_evolve(&mut p),
+        "serve" => parse_serve(&mut p),
+        "worker" => parse_worker(&mut p),
+        "distribute" => parse_distribute(&mut p),
+        "replay" => parse_replay(&mut p),
+        "scrub" => parse_scrub(&mut p),
+        "soak" => parse_soak(&mut p),
+        "explore" => parse_explore(&mut p),
+        "verify" => parse_verify(&mut p),
+        "versus" => parse_versus(&mut p),
+        "species" => parse_species(&mut p),
+        "cavegen" => parse_cavegen(&mut p),
+        "enumerate" => parse_enumerate(&mut p),
+        "collide" => parse_collide(&mut p),
+        "repl" => parse_repl(&mut p),
+        "batch" => parse_batch(&mut p),
+        "sweep" => parse_sweep(&mut p),
+        "help" => match p.next()? {
+            None => Ok(Command::Help(USAGE)),
+            Some(Arg::Positional(c)) => usage_of(&c)
+                .map(Command::Help)
+                .ok_or_else(|| format!("unknown command '{}'", c)),
+            Some(arg) => Err(unknown(arg)),
+        },
+        _ => Err(format!("unknown command '{}'", command)),
+    }
+}
+
+/// Flags accepted by every command.
+fn global_flag(flag: &str, usage: &'static str) -> Option<Command> {
+    match flag {
+        "-h" | "--help" => Some(Command::Help(usage)),
+        "-V" | "--version" => Some(Command::Version),
+        _ => None,
+    }
+}
+
+fn parse_run<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let config = p.config;
+    let mut generations = config.generations;
+    let (mut dir_given, mut watch_input) = (false, false);
+    let (mut renderer, mut output_given) = (None, false);
+    let mut opts = RunOptions {
+        world: WorldOptions::from_config(config),
+        generations: 10,
+        delay: config.delay,
+        fps: config.fps,
+        every: config.every.unwrap_or(1),
+        until: config.until,
+        until_pattern: None,
+        breakpoints: Vec::new(),
+        watched: Vec::new(),
+        watch_log: None,
+        max_period: stop::DEFAULT_WINDOW,
+        keep_going: false,
+        watch_input: None,
+        perturb: None,
+        faults: None,
+        stop_on_growth: false,
+        compare: None,
+        layers: Vec::new(),
+        couplings: Vec::new(),
+        color: !config.no_color,
+        screensaver: config.screensaver.unwrap_or(false),
+        demo: false,
+        demo_seconds: 15,
+        window: config.renderer == Some(Renderer::Window),
+        audio: None,
+        midi: None,
+        osc: None,
+        osc_rows: 0,
+        scale: Scale::Pentatonic,
+        volume: 0.5,
+        heatmap: None,
+        contact_sheet: None,
+        frames: None,
+        frame_template: None,
+        image: config.image.clone(),
+        summary: true,
+        diff: false,
+        timing: false,
+        sparkline: None,
+        metrics: false,
+        style: config.style.clone(),
+        output: config.output_format.unwrap_or(OutputFormat::Text),
+        data_file: None,
+        print_final: false,
+        print_hash: None,
+        renderer: "text".to_string(),
+        cells: Cells::None,
+        telnet_port: None,
+        script: None,
+        control_fifo: None,
+        checkpoint_every: None,
+        checkpoint_dir: ".".to_string(),
+        checkpoint_gzip: false,
+        resume: None,
+        save_session: None,
+        load_session: None,
+        save_on_interrupt: false,
+        record: None,
+    };
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(path) if opts.world.pattern.is_none() => {
+                opts.world.pattern = Some(PatternSource::File(config.resolve_pattern(&path)));
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, RUN_USAGE) {
+            return Ok(command);
+        }
+        if p.world_flag(&flag, &mut opts.world)? {
+            continue;
+        }
+        match flag.as_str() {
+            "-g" | "--generations" => generations = Some(p.parse("a number of generations")?),
+            "--until" => opts.until = Some(Until::parse(&p.value()?)?),
+            "--until-pattern" => {
+                let name = p.value()?;
+                opts.until_pattern = Some(match library::get(&name) {
+                    Some(pattern) => PatternSource::Builtin(pattern),
+                    None => PatternSource::File(config.resolve_pattern(&name)),
+                });
+            }
+            "--max-period" => opts.max_period = parse_max_period(p)?,
+            "--break-on" => {
+                let value = p.value()?;
+                let cell = value
+                    .split_once(',')
+                    .and_then(|(x, y)| Some((x.trim().parse().ok()?, y.trim().parse().ok()?)))
+                    .ok_or_else(|| {
+                        format!("invalid --break-on '{}', expected a cell like 12,7", value)
+                    })?;
+                opts.breakpoints.push(cell);
+            }
+            "--watch" => {
+                let value = p.value()?;
+                let numbers: Option<Vec<usize>> =
+                    value.split(',').map(|n| n.trim().parse().ok()).collect();
+                match numbers {
+                    Some(numbers) if numbers.len() % 2 == 0 => {
+                        opts.watched.extend(numbers.chunks(2).map(|c| (c[0], c[1])));
+                    }
+                    _ => {
+                        return Err(format!(
+                            "invalid --watch '{}', expected cells like 12,7 or 12,7,13,7",
+                            value
+                        ))
+                    }
+                }
+            }
+            "--watch-log" => opts.watch_log = Some(p.value()?),
+            "--forever" => generations = Some(0),
+            "--keep-going" => opts.keep_going = true,
+            "--watch-input" => watch_input = true,
+            "--perturb" => opts.perturb = Some(Perturb::parse(&p.value()?)?),
+            "--faults" => opts.faults = Some(Faults::parse(&p.value()?)?),
+            "--stop-on-growth" => opts.stop_on_growth = true,
+            "-d" | "--delay" => opts.delay = Some(p.parse("milliseconds")?),
+            "--fps" => {
+                let fps = p.parse("frames per second")?;
+                if fps == 0 {
+                    return Err("--fps must be at least 1".to_string());
+                }
+                opts.fps = Some(fps);
+            }
+            "--every" | "--steps-per-frame" => {
+                opts.every = p.parse("a number of generations")?;
+                if opts.every == 0 {
+                    return Err(format!("{} must be at least 1", flag));
+                }
+            }
+            "--compare" => {
+                let first = Rule::parse(&p.value()?)?;
+                opts.compare = Some((first, Rule::parse(&p.value()?)?));
+            }
+            "--layer" => opts
+                .layers
+                .push(layers::parse_layer(&p.value()?, DEFAULT_DENSITY)?),
+            "--couple" => opts.couplings.push(layers::parse_link(&p.value()?)?),
+            "--screensaver" => opts.screensaver = true,
+            "--demo" => opts.demo = true,
+            "--demo-seconds" => {
+                opts.demo_seconds = p.parse("a number of seconds")?;
+                if opts.demo_seconds == 0 {
+                    return Err("--demo-seconds must be at least 1".to_string());
+                }
+            }
+            "--window" => opts.window = true,
+            "--output-format" => {
+                opts.output = OutputFormat::parse(&p.value()?)?;
+                output_given = true;
+            }
+            "--renderer" => renderer = Some(p.value()?),
+            "--cells" => opts.cells = Cells::parse(&p.value()?)?,
+            "--audio" => opts.audio = Some(p.value()?),
+            "--midi" => opts.midi = Some(p.value()?),
+            "--osc" => opts.osc = Some(p.value()?),
+            "--osc-rows" => {
+                opts.osc_rows = p.parse("a number of bands")?;
+                if opts.osc_rows > osc::MAX_ROWS {
+                    return Err(format!(
+                        "--osc-rows can be at most {}, got {}",
+                        osc::MAX_ROWS,
+                        opts.osc_rows
+                    ));
+                }
+            }
+            "--scale" => opts.scale = Scale::parse(&p.value()?)?,
+            "--volume" => {
+                let volume: f32 = p.parse("a number between 0 and 1")?;
+                if !(0.0..=1.0).contains(&volume) {
+                    return Err(format!("--volume must be between 0 and 1, got {}", volume));
+                }
+                opts.volume = volume;
+            }
+            "--heatmap" => opts.heatmap = Some(p.value()?),
+            "--contact-sheet" => opts.contact_sheet = Some(p.value()?),
+            "--frames" => opts.frames = Some(p.value()?),
+            "--frame-template" => opts.frame_template = Some(Template::parse(&p.value()?)?),
+            "--no-summary" => opts.summary = false,
+            "-o" | "--output" => opts.data_file = Some(p.value()?),
+            "--final" => opts.print_final = true,
+            "--print-hash" => opts.print_hash = Some(HashWhen::parse(&p.value()?)?),
+            "--diff" => opts.diff = true,
+            "--trails" => opts.style.trails = p.parse("a number of generations")?,
+            "--ghosts" => opts.style.ghosts = true,
+            "--footprint" => opts.style.footprint = Some(Footprint::default()),
+            "--color" => {
+                opts.style.density = match p.value()?.as_str() {
+                    "plain" => false,
+                    "density" => true,
+                    other => {
+                        return Err(format!(
+                            "invalid color mode '{}', expected plain or density",
+                            other
+                        ))
+                    }
+                }
+            }
+            "--timing" => opts.timing = true,
+            "--metrics" => opts.metrics = true,
+            "--sparkline" => {
+                let span: usize = p.parse("a number of generations")?;
+                if span < 2 {
+                    return Err("--sparkline needs at least 2 generations".to_string());
+                }
+                opts.sparkline = Some(span);
+            }
+            "--scroll" => opts.style.scroll = Some(parse_scroll(&p.value()?)?),
+            "--zoom" => opts.style.zoom = Zoom::parse(&p.value()?)?,
+            "--telnet-port" => opts.telnet_port = Some(p.parse("a port number")?),
+            "--script" => opts.script = Some(p.value()?),
+            "--control-fifo" => opts.control_fifo = Some(p.value()?),
+            "--checkpoint-every" => {
+                let every = p.parse("a number of generations")?;
+                if every == 0 {
+                    return Err("--checkpoint-every must be at least 1".to_string());
+                }
+                opts.checkpoint_every = Some(every);
+            }
+            "--checkpoint-dir" => {
+                opts.checkpoint_dir = p.value()?;
+                dir_given = true;
+            }
+            "--checkpoint-gzip" => opts.checkpoint_gzip = true,
+            "--resume" => opts.resume = Some(p.value()?),
+            "--save-session" => opts.save_session = Some(p.value()?),
+            "--load-session" => opts.load_session = Some(p.value()?),
+            "--save-on-interrupt" => opts.save_on_interrupt = true,
+            "--record" => opts.record = Some(p.value()?),
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+
+    // a stop condition makes the generation count a limit rather than a length,
+    // and zero means no limit at all
+    let stops = opts.until.is_some() || opts.until_pattern.is_some();
+    opts.generations = match (generations, stops) {
+        (Some(0), _) | (None, true) => u64::MAX,
+        (Some(n), _) => n,
+        (None, false) => 10,
+    };
+    match renderer.as_deref() {
+        Some("window") => opts.window = true,
+        Some(name) if render::NAMES.contains(&name) => {
+            let output = if name == "jsonl" {
+                OutputFormat::Jsonl
+            } else {
+                OutputFormat::Text
+            };
+            if output_given && output != opts.output {
+                return Err(format!(
+                    "--renderer {} cannot be used with another --output-format",
+                    name
+                ));
+            }
+            opts.output = output;
+            opts.renderer = name.to_string();
+        }
+        Some(name) => return Err(render::invalid(name)),
+        None if opts.output == OutputFormat::Jsonl => opts.renderer = "jsonl".to_string(),
+        None => {}
+    }
+    if opts.window && !cfg!(feature = "sdl") {
+        return Err("--window needs a build with the sdl feature".to_string());
+    }
+    // the modes that pick their own worlds
+    let tour = if opts.screensaver {
+        Some("--screensaver")
+    } else if opts.demo {
+        Some("--demo")
+    } else {
+        None
+    };
+    if opts.screensaver && opts.demo {
+        return Err("give either --screensaver or --demo, not both".to_string());
+    }
+    if opts.output == OutputFormat::Jsonl {
+        let other = if tour.is_some() {
+            tour
+        } else if opts.compare.is_some() {
+            Some("--compare")
+        } else if !opts.layers.is_empty() {
+            Some("--layer")
+        } else if opts.window {
+            Some("--window")
+        } else {
+            None
+        };
+        if let Some(other) = other {
+            return Err(format!(
+                "--output-format jsonl cannot be used with {}",
+                other
+            ));
+        }
+    }
+    if !opts.couplings.is_empty() && opts.layers.is_empty() {
+        return Err("--couple needs a --layer to couple".to_string());
+    }
+    for link in &opts.couplings {
+        let layers = opts.layers.len() + 1;
+        if link.layer.max(link.on) >= layers {
+            return Err(format!(
+                "--couple names layer {}, but there are only {}",
+                link.layer.max(link.on) + 1,
+                layers
+            ));
+        }
+        if link.layer == link.on {
+            return Err(format!(
+                "layer {} cannot be coupled to itself",
+                link.layer + 1
+            ));
+        }
+    }
+    if !opts.layers.is_empty() && (tour.is_some() || opts.compare.is_some() || opts.window) {
+        let other = tour.unwrap_or(if opts.window { "--window" } else { "--compare" });
+        return Err(format!("--layer cannot be used with {}", other));
+    }
+    let plain = tour.is_none() && opts.compare.is_none() && opts.layers.is_empty() && !opts.window;
+    if watch_input {
+        opts.watch_input = match &opts.world.pattern {
+            Some(
+                PatternSource::File(path) | PatternSource::Text(path) | PatternSource::Image(path),
+            ) => Some(path.clone()),
+            _ => return Err("--watch-input needs a PATTERN file to watch".to_string()),
+        };
+        if !plain {
+            return Err("--watch-input only works in the terminal".to_string());
+        }
+        let kept = [
+            ("--record", opts.record.is_some()),
+            ("--checkpoint-every", opts.checkpoint_every.is_some()),
+            ("--print-hash", opts.print_hash.is_some()),
+        ];
+        if let Some((flag, _)) = kept.iter().find(|&&(_, given)| given) {
+            return Err(format!(
+                "--watch-input cannot be used with {}, as the run starts over",
+                flag
+            ));
+        }
+    }
+    if opts.frame_template.is_some() && opts.frames.is_none() {
+        return Err("--frame-template names the images --frames DIR writes".to_string());
+    }
+    if opts.print_hash.is_some() && (opts.compare.is_some() || !opts.layers.is_empty()) {
+        let other = if opts.compare.is_some() {
+            "--compare"
+        } else {
+            "--layer"
+        };
+        return Err(format!("--print-hash cannot be used with {}", other));
+    }
+    if opts.diff && !(plain && opts.renderer == "text") {
+        return Err("--diff only works with the plain text output".to_string());
+    }
+    if opts.style.trails > 0 && !(plain && opts.renderer == "text") {
+        return Err("--trails only works with the plain text output".to_string());
+    }
+    if opts.timing && !(plain && opts.renderer == "text") {
+        return Err("--timing only works with the plain text output".to_string());
+    }
+    if opts.sparkline.is_some() && !(plain && opts.renderer == "text") {
+        return Err("--sparkline only works with the plain text output".to_string());
+    }
+    if opts.style.ghosts && !(plain && opts.renderer == "text") {
+        return Err("--ghosts only works with the plain text output".to_string());
+    }
+    if opts.style.density && !(plain && ["text", "incremental"].contains(&opts.renderer.as_str())) {
+        return Err("--color density only works with the plain text output".to_string());
+    }
+    if opts.style.footprint.is_some() && !(plain && opts.renderer == "text") {
+        return Err("--footprint only works with the plain text output".to_string());
+    }
+    if opts.style.scroll.is_some() && !(plain && opts.renderer == "text") {
+        return Err("--scroll only works with the plain text output".to_string());
+    }
+    if opts.style.trails > 0 && opts.diff {
+        return Err("give either --diff or --trails, not both".to_string());
+    }
+    let window = opts.window && tour.is_none();
+    if !(opts.breakpoints.is_empty() || plain || window) {
+        return Err("--break-on only works in the terminal or the window".to_string());
+    }
+    if !(opts.watched.is_empty() || plain || window) {
+        return Err("--watch only works in the terminal or the window".to_string());
+    }
+    if opts.watch_log.is_some() && opts.watched.is_empty() {
+        return Err("--watch-log needs --watch".to_string());
+    }
+    if opts.until_pattern.is_some() && !plain {
+        return Err("--until-pattern only works in the terminal".to_string());
+    }
+    if opts.stop_on_growth && !plain {
+        return Err("--stop-on-growth only works in the terminal".to_string());
+    }
+    if opts.telnet_port.is_some() && !plain {
+        return Err("--telnet-port only works in the terminal".to_string());
+    }
+    if opts.script.is_some() && !plain {
+        return Err("--script only works in the terminal".to_string());
+    }
+    if opts.control_fifo.is_some() && !plain {
+        return Err("--control-fifo only works in the terminal".to_string());
+    }
+    if opts.save_on_interrupt && !plain {
+        return Err("--save-on-interrupt only works in the terminal".to_string());
+    }
+    if opts.perturb.is_some() && !plain {
+        return Err("--perturb only works in the terminal".to_string());
+    }
+    if opts.faults.is_some() && !plain {
+        return Err("--faults only works in the terminal".to_string());
+    }
+    if dir_given && opts.checkpoint_every.is_none() {
+        return Err("--checkpoint-dir needs --checkpoint-every".to_string());
+    }
+    if opts.checkpoint_gzip && opts.checkpoint_every.is_none() {
+        return Err("--checkpoint-gzip needs --checkpoint-every".to_string());
+    }
+    let checkpoints = opts.checkpoint_every.is_some() || opts.resume.is_some();
+    if checkpoints && !plain {
+        let flag = if opts.resume.is_some() {
+            "--resume"
+        } else {
+            "--checkpoint-every"
+        };
+        return Err(format!("{} only works in the terminal", flag));
+    }
+    let world = &opts.world;
+    if opts.resume.is_some()
+        && (world.pattern.is_some() || world.random.is_some() || !world.placements.is_empty())
+    {
+        return Err("--resume carries on with the checkpoint's world, so it takes no pattern, --random or --place".to_string());
+    }
+    let sessions = [
+        ("--save-session", opts.save_session.is_some()),
+        ("--load-session", opts.load_session.is_some()),
+    ];
+    if let Some((flag, _)) = sessions.iter().find(|&&(_, given)| given) {
+        if !plain {
+            return Err(format!("{} only works in the terminal", flag));
+        }
+    }
+    if opts.load_session.is_some() {
+        if world.pattern.is_some() || world.random.is_some() || !world.placements.is_empty() {
+            return Err("--load-session carries on with the session's world, so it takes no pattern, --random or --place".to_string());
+        }
+        if opts.resume.is_some() {
+            return Err("--load-session cannot be used with --resume".to_string());
+        }
+    }
+    let recorded = [
+        ("--heatmap", opts.heatmap.is_some()),
+        ("--contact-sheet", opts.contact_sheet.is_some()),
+        ("--frames", opts.frames.is_some()),
+        ("--record", opts.record.is_some()),
+    ];
+    if let Some((flag, _)) = recorded.iter().find(|&&(_, given)| given) {
+        if let Some(other) = tour.or(opts.compare.as_ref().map(|_| "--compare")) {
+            return Err(format!("{} cannot be used with {}", flag, other));
+        }
+    }
+    Ok(Command::Run(Box::new(opts)))
+}
+
+fn parse_edit<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut world = WorldOptions::from_config(p.config);
+    let mut path = None;
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(file) if path.is_none() => {
+                path = Some(file);
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, EDIT_USAGE) {
+            return Ok(command);
+        }
+        if !p.world_flag(&flag, &mut world)? {
+            return Err(unknown(Arg::Flag(flag)));
+        }
+    }
+    let path = path.ok_or("edit needs a FILE to load and save")?;
+    Ok(Command::Edit(EditOptions { world, path }))
+}
+
+fn parse_convert<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut files = Vec::new();
+    let (mut from, mut to, mut crop) = (None, None, false);
+    while let Some(arg) = p.next()? {
+        match arg {
+            Arg::Positional(file) if files.len() < 2 => files.push(file),
+            Arg::Flag(flag) => match flag.as_str() {
+                "--from" => from = Some(Format::parse(&p.value()?)?),
+                "--to" => to = Some(Format::parse(&p.value()?)?),
+                "--crop" => crop = true,
+                _ => match global_flag(&flag, CONVERT_USAGE) {
+                    Some(command) => return Ok(command),
+                    None => return Err(unknown(Arg::Flag(flag))),
+                },
+            },
+            arg => return Err(unknown(arg)),
+        }
+    }
+    let output = files.pop();
+    let input = files.pop();
+    match (input, output) {
+        (Some(input), Some(output)) => Ok(Command::Convert(ConvertOptions {
+            input,
+            output,
+            from,
+            to,
+            crop,
+        })),
+        _ => Err("convert needs an INPUT and an OUTPUT file".to_string()),
+    }
+}
+
+fn parse_analyze<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut opts = AnalyzeOptions {
+        world: WorldOptions::from_config(p.config),
+        generations: 1000,
+        until: p.config.until,
+        max_period: stop::DEFAULT_WINDOW,
+        stop_on_growth: false,
+        perturb: None,
+        faults: None,
+        predecessor: false,
+        margin: 1,
+        ensemble: 0,
+        threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        curve: None,
+        apgcodes: false,
+    };
+    let mut generations = None;
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(path) if opts.world.pattern.is_none() => {
+                opts.world.pattern = Some(PatternSource::File(p.config.resolve_pattern(&path)));
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, ANALYZE_USAGE) {
+            return Ok(command);
+        }
+        if p.world_flag(&flag, &mut opts.world)? {
+            continue;
+        }
+        match flag.as_str() {
+            "-g" | "--generations" => generations = Some(p.parse("a number of generations")?),
+            "--until" => opts.until = Some(Until::parse(&p.value()?)?),
+            "--max-period" => opts.max_period = parse_max_period(p)?,
+            "--stop-on-growth" => opts.stop_on_growth = true,
+            "--perturb" => opts.perturb = Some(Perturb::parse(&p.value()?)?),
+            "--faults" => opts.faults = Some(Faults::parse(&p.value()?)?),
+            "--predecessor" => opts.predecessor = true,
+            "--margin" => opts.margin = p.parse("a number of cells")?,
+            "--ensemble" => {
+                opts.ensemble = p.parse("a number of worlds")?;
+                if opts.ensemble == 0 {
+                    return Err("--ensemble must be at least 1".to_string());
+                }
+            }
+            "-j" | "--threads" => {
+                opts.threads = p.parse("a number of threads")?;
+                if opts.threads == 0 {
+                    return Err("--threads must be at least 1".to_string());
+                }
+            }
+            "--curve" => opts.curve = Some(p.value()?),
+            "--apgcodes" => opts.apgcodes = true,
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    // every world of an ensemble runs the same length, whatever --until says
+    opts.generations = match (generations, opts.until) {
+        (Some(n), _) => n,
+        (None, Some(_)) if opts.ensemble == 0 => u64::MAX,
+        (None, _) => 1000,
+    };
+    if opts.ensemble > 0 {
+        if opts.world.random.is_none() {
+            return Err("--ensemble needs --random, or every world would be the same".to_string());
+        }
+        if opts.predecessor {
+            return Err("--ensemble cannot be used with --predecessor".to_string());
+        }
+        if opts.perturb.is_some() {
+            return Err("--ensemble cannot be used with --perturb".to_string());
+        }
+        if opts.faults.is_some() {
+            return Err("--ensemble cannot be used with --faults".to_string());
+        }
+        if opts.apgcodes {
+            return Err("--ensemble cannot be used with --apgcodes".to_string());
+        }
+    } else if opts.curve.is_some() {
+        return Err("--curve only works with --ensemble".to_string());
+    }
+    Ok(Command::Analyze(opts))
+}
+
+fn parse_bench<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut opts = BenchOptions {
+        width: 256,
+        height: 256,
+        generations: 1000,
+        seed: 0,
+    };
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, BENCH_USAGE) {
+            return Ok(command);
+        }
+        match flag.as_str() {
+            "-W" | "--width" => opts.width = p.parse("a number of cells")?,
+            "-H" | "--height" => opts.height = p.parse("a number of cells")?,
+            "-g" | "--generations" => opts.generations = p.parse("a number of generations")?,
+            "--seed" => opts.seed = p.parse("a number")?,
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    Ok(Command::Bench(opts))
+}
+
+fn parse_render<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut world = WorldOptions::from_config(p.config);
+    let mut generations = 0;
+    let mut output = None;
+    let mut image = p.config.image.clone();
+    let mut by_age = false;
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(path) if world.pattern.is_none() => {
+                world.pattern = Some(PatternSource::File(p.config.resolve_pattern(&path)));
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, RENDER_USAGE) {
+            return Ok(command);
+        }
+        if p.world_flag(&flag, &mut world)? || p.image_flag(&flag, &mut image)? {
+            continue;
+        }
+        match flag.as_str() {
+            "-g" | "--generations" => generations = p.parse("a number of generations")?,
+            "-o" | "--output" => output = Some(p.value()?),
+            "--cell-size" => image.cell_size = p.parse("a number of pixels")?,
+            "--by-age" => by_age = true,
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    if image.cell_size == 0 {
+        return Err("--cell-size must be at least 1".to_string());
+    }
+    let output = output.ok_or("render needs an --output FILE")?;
+    Ok(Command::Render(RenderOptions {
+        world,
+        generations,
+        output,
+        by_age,
+        style: p.config.style.clone(),
+        image,
+    }))
+}
+
+fn parse_step<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut opts = StepOptions {
+        width: None,
+        height: None,
+        generations: 1,
+        rule: None,
+        format: None,
+        progress: false,
+    };
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, STEP_USAGE) {
+            return Ok(command);
+        }
+        match flag.as_str() {
+            "-n" | "-g" | "--generations" => {
+                opts.generations = p.parse("a number of generations")?
+            }
+            "-W" | "--width" => opts.width = Some(p.parse("a number of cells")?),
+            "-H" | "--height" => opts.height = Some(p.parse("a number of cells")?),
+            "-r" | "--rule" => opts.rule = Some(Rule::parse(&p.value()?)?),
+            "-f" | "--format" => opts.format = Some(Format::parse(&p.value()?)?),
+            "--progress" => opts.progress = true,
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    Ok(Command::Step(opts))
+}
+
+fn parse_search<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut opts = SearchOptions {
+        width: 64,
+        height: 64,
+        soups: 1000,
+        soup_size: 16,
+        density: 0.5,
+        symmetry: Symmetry::C1,
+        rule: p.config.rule.unwrap_or_else(Rule::conway),
+        seed: p.config.seed,
+        max_generations: 10_000,
+        threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        top: 0,
+        methuselah: false,
+        save: None,
+        progress: false,
+        apgcodes: false,
+    };
+    let (mut soup_size, mut top) = (None, None);
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, SEARCH_USAGE) {
+            return Ok(command);
+        }
+        match flag.as_str() {
+            "-n" | "--soups" => opts.soups = p.parse("a number of soups")?,
+            "--soup-size" => soup_size = Some(p.parse("a number of cells")?),
+            "--density" => {
+                opts.density = p.parse("a number between 0 and 1")?;
+                if !(0.0..=1.0).contains(&opts.density) {
+                    return Err(format!(
+                        "--density must be between 0 and 1, got {}",
+                        opts.density
+                    ));
+                }
+            }
+            "--symmetry" => opts.symmetry = Symmetry::parse(&p.value()?)?,
+            "-W" | "--width" => opts.width = p.parse("a number of cells")?,
+            "-H" | "--height" => opts.height = p.parse("a number of cells")?,
+            "-r" | "--rule" => opts.rule = Rule::parse(&p.value()?)?,
+            "--seed" => opts.seed = Some(p.parse("a number")?),
+            "-g" | "--generations" => opts.max_generations = p.parse("a number of generations")?,
+            "-j" | "--threads" => {
+                opts.threads = p.parse("a number of threads")?;
+                if opts.threads == 0 {
+                    return Err("--threads must be at least 1".to_string());
+                }
+            }
+            "--methuselah" => opts.methuselah = true,
+            "--top" => top = Some(p.parse("a number of soups")?),
+            "--save" => opts.save = Some(p.value()?),
+            "--progress" => opts.progress = true,
+            "--apgcodes" => opts.apgcodes = true,
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    if opts.apgcodes && opts.methuselah {
+        return Err(
+            "--apgcodes cannot be used with --methuselah, which takes no census".to_string(),
+        );
+    }
+    let (size, kept) = if opts.methuselah { (5, 10) } else { (16, 0) };
+    opts.soup_size = soup_size.unwrap_or(size);
+    opts.top = top.unwrap_or(kept);
+    if opts.soup_size == 0 || opts.soup_size > opts.width.min(opts.height) {
+        return Err(format!(
+            "--soup-size must be between 1 and the world's smaller side, {}",
+            opts.width.min(opts.height)
+        ));
+    }
+    Ok(Command::Search(opts))
+}
+
+fn parse_evolve<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut opts = EvolveOptions {
+        width: 64,
+        height: 64,
+        rule: p.config.rule.unwrap_or_else(Rule::conway),
+        seed: p.config.seed,
+        fitness: Fitness::Longevity,
+        size: 8,
+        density: 0.5,
+        population: 50,
+        generations: 20,
+        steps: 1000,
+        mutation: 0.02,
+        elite: 2,
+        threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        save: None,
+    };
+    let chance = |p: &mut Parser<I>, flag: &str| -> Result<f64, String> {
+        let value: f64 = p.parse("a number between 0 and 1")?;
+        if !(0.0..=1.0).contains(&value) {
+            return Err(format!("{} must be between 0 and 1, got {}", flag, value));
+        }
+        Ok(value)
+    };
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, EVOLVE_USAGE) {
+            return Ok(command);
+        }
+        match flag.as_str() {
+            "--fitness" => opts.fitness = Fitness::parse(&p.value()?)?,
+            "--size" => opts.size = p.parse("a number of cells")?,
+            "--density" => opts.density = chance(p, &flag)?,
+            "--population" => opts.population = p.parse("a number of patterns")?,
+            "-g" | "--generations" => opts.generations = p.parse("a number of generations")?,
+            "--steps" => opts.steps = p.parse("a number of generations")?,
+            "--mutation" => opts.mutation = chance(p, &flag)?,
+            "--elite" => opts.elite = p.parse("a number of patterns")?,
+            "-W" | "--width" => opts.width = p.parse("a number of cells")?,
+            "-H" | "--height" => opts.height = p.parse("a number of cells")?,
+            "-r" | "--rule" => opts.rule = Rule::parse(&p.value()?)?,
+            "--seed" => opts.seed = Some(p.parse("a number")?),
+            "-j" | "--threads" => {
+                opts.threads = p.parse("a number of threads")?;
+                if opts.threads == 0 {
+                    return Err("--threads must be at least 1".to_string());
+                }
+            }
+            "--save" => opts.save = Some(p.value()?),
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    if opts.size == 0 || opts.size > opts.width.min(opts.height) {
+        return Err(format!(
+            "--size must be between 1 and the world's smaller side, {}",
+            opts.width.min(opts.height)
+        ));
+    }
+    if opts.population < 2 {
+        return Err("--population must be at least 2".to_string());
+    }
+    if opts.elite >= opts.population {
+        return Err(format!(
+            "--elite must be fewer than the {} patterns of a generation",
+            opts.population
+        ));
+    }
+    Ok(Command::Evolve(opts))
+}
+
+fn parse_serve<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut opts = ServeOptions {
+        world: WorldOptions::from_config(p.config),
+        generations: 0,
+        delay: p.config.delay.unwrap_or(200),
+        until: p.config.until,
+        max_period: stop::DEFAULT_WINDOW,
+        output: OutputFormat::Text,
+        cells: Cells::None,
+        bind: "127.0.0.1:8080".to_string(),
+        record: None,
+        dbus: false,
+    };
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(path) if opts.world.pattern.is_none() => {
+                opts.world.pattern = Some(PatternSource::File(p.config.resolve_pattern(&path)));
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, SERVE_USAGE) {
+            return Ok(command);
+        }
+        if p.world_flag(&flag, &mut opts.world)? {
+            continue;
+        }
+        match flag.as_str() {
+            "-g" | "--generations" => opts.generations = p.parse("a number of generations")?,
+            "--until" => opts.until = Some(Until::parse(&p.value()?)?),
+            "--max-period" => opts.max_period = parse_max_period(p)?,
+            "-d" | "--delay" => opts.delay = p.parse("milliseconds")?,
+            "--output-format" => opts.output = OutputFormat::parse(&p.value()?)?,
+            "--cells" => opts.cells = Cells::parse(&p.value()?)?,
+            "--bind" => opts.bind = p.value()?,
+            "--record" => opts.record = Some(p.value()?),
+            "--dbus" => opts.dbus = true,
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    if opts.generations == 0 {
+        opts.generations = u64::MAX;
+    }
+    Ok(Command::Serve(opts))
+}
+
+fn parse_worker<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut opts = WorkerOptions {
+        listen: "127.0.0.1:7070".to_string(),
+    };
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, WORKER_USAGE) {
+            return Ok(command);
+        }
+        match flag.as_str() {
+            "--listen" => opts.listen = p.value()?,
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    Ok(Command::Worker(opts))
+}
+
+fn parse_distribute<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut opts = DistributeOptions {
+        world: WorldOptions::from_config(p.config),
+        workers: Vec::new(),
+        generations: 100,
+        every: 1,
+        show: false,
+        output: None,
+    };
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(path) if opts.world.pattern.is_none() => {
+                opts.world.pattern = Some(PatternSource::File(p.config.resolve_pattern(&path)));
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, DISTRIBUTE_USAGE) {
+            return Ok(command);
+        }
+        if p.world_flag(&flag, &mut opts.world)? {
+            continue;
+        }
+        match flag.as_str() {
+            "--worker" => opts.workers.push(p.value()?),
+            "-g" | "--generations" => opts.generations = p.parse("a number of generations")?,
+            "--every" => {
+                opts.every = p.parse("a number of generations")?;
+                if opts.every == 0 {
+                    return Err("--every must be at least 1".to_string());
+                }
+            }
+            "--show" => opts.show = true,
+            "-o" | "--output" => opts.output = Some(p.value()?),
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    if opts.workers.is_empty() {
+        return Err("distribute needs at least one --worker ADDR".to_string());
+    }
+    Ok(Command::Distribute(opts))
+}
+
+fn parse_replay<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut path = None;
+    let mut opts = ReplayOptions {
+        path: String::new(),
+        delay: p.config.delay.unwrap_or(500),
+        every: 1,
+        style: p.config.style.clone(),
+        output: OutputFormat::Text,
+        cells: Cells::None,
+    };
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(file) if path.is_none() => {
+                path = Some(file);
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, REPLAY_USAGE) {
+            return Ok(command);
+        }
+        match flag.as_str() {
+            "-d" | "--delay" => opts.delay = p.parse("milliseconds")?,
+            "--every" => {
+                opts.every = p.parse("a number of generations")?;
+                if opts.every == 0 {
+                    return Err("--every must be at least 1".to_string());
+                }
+            }
+            "--output-format" => opts.output = OutputFormat::parse(&p.value()?)?,
+            "--cells" => opts.cells = Cells::parse(&p.value()?)?,
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    opts.path = path.ok_or("replay needs the FILE of a recording")?;
+    Ok(Command::Replay(opts))
+}
+
+fn parse_soak<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut opts = SoakOptions {
+        configs: 0,
+        generations: 200,
+        max_size: 64,
+        seed: p.config.seed,
+    };
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, SOAK_USAGE) {
+            return Ok(command);
+        }
+        match flag.as_str() {
+            "-n" | "--configs" => opts.configs = p.parse("a number of configurations")?,
+            "-g" | "--generations" => opts.generations = p.parse("a number of generations")?,
+            "--max-size" => {
+                opts.max_size = p.parse("a number of cells")?;
+                if opts.max_size < 3 {
+                    return Err("--max-size must be at least 3".to_string());
+                }
+            }
+            "--seed" => opts.seed = Some(p.parse("a number")?),
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    Ok(Command::Soak(opts))
+}
+
+fn parse_explore<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut opts = ExploreOptions {
+        world: WorldOptions::from_config(p.config),
+        space: Space::parse("B3678/S2345")?,
+        generations: 1000,
+        max_period: stop::DEFAULT_WINDOW,
+    };
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(path) if opts.world.pattern.is_none() => {
+                opts.world.pattern = Some(PatternSource::File(p.config.resolve_pattern(&path)));
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, EXPLORE_USAGE) {
+            return Ok(command);
+        }
+        if p.world_flag(&flag, &mut opts.world)? {
+            continue;
+        }
+        match flag.as_str() {
+            "--space" => opts.space = Space::parse(&p.value()?)?,
+            "-g" | "--generations" => opts.generations = p.parse("a number of generations")?,
+            "--max-period" => opts.max_period = parse_max_period(p)?,
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    // every rule replaces the world's own, so a rule of its own means nothing
+    if opts.world.rule.is_some() {
+        return Err("explore runs every rule of --space, so it takes no --rule".to_string());
+    }
+    let world = &mut opts.world;
+    if world.pattern.is_none() && world.placements.is_empty() && world.random.is_none() {
+        world.random = Some(DEFAULT_DENSITY);
+    }
+    Ok(Command::Explore(opts))
+}
+
+fn parse_verify<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut world = WorldOptions::from_config(p.config);
+    let mut mode = None;
+    let mut generations = None;
+    // anything that says what to record, which --against takes from the file
+    let mut configured = false;
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(path) if world.pattern.is_none() => {
+                world.pattern = Some(PatternSource::File(p.config.resolve_pattern(&path)));
+                configured = true;
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, VERIFY_USAGE) {
+            return Ok(command);
+        }
+        if p.world_flag(&flag, &mut world)? {
+            configured = true;
+            continue;
+        }
+        let given = match flag.as_str() {
+            "--record" => VerifyMode::Record(p.value()?),
+            "--against" => VerifyMode::Against(p.value()?),
+            "--engines" => {
+                let value = p.value()?;
+                match value.split_once(',') {
+                    Some((a, b)) if [a, b].iter().all(|e| engines::NAMES.contains(e)) => {
+                        VerifyMode::Engines(a.to_string(), b.to_string())
+                    }
+                    _ => {
+                        return Err(format!(
+                            "invalid --engines '{}', expected two of {} like world,grid",
+                            value,
+                            engines::NAMES.join(", ")
+                        ))
+                    }
+                }
+            }
+            "-g" | "--generations" => {
+                generations = Some(p.parse("a number of generations")?);
+                configured = true;
+                continue;
+            }
+            _ => return Err(unknown(Arg::Flag(flag))),
+        };
+        if mode.replace(given).is_some() {
+            return Err("give one of --record, --against and --engines, once".to_string());
+        }
+    }
+    let mode = mode.ok_or("verify needs --record FILE, --against FILE or --engines A,B")?;
+    if matches!(mode, VerifyMode::Against(_)) && configured {
+        return Err(
+            "--against runs the world recorded in the file, so it takes no other options"
+                .to_string(),
+        );
+    }
+    Ok(Command::Verify(VerifyOptions {
+        world,
+        mode,
+        generations: generations.unwrap_or(100),
+    }))
+}
+
+fn parse_versus<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut opts = VersusOptions {
+        world: WorldOptions::from_config(p.config),
+        every: 10,
+        cells: 3,
+        generations: 200,
+        delay: p.config.delay.unwrap_or(100),
+        color: !p.config.no_color,
+    };
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, VERSUS_USAGE) {
+            return Ok(command);
+        }
+        if p.world_flag(&flag, &mut opts.world)? {
+            continue;
+        }
+        match flag.as_str() {
+            "--every" => {
+                opts.every = p.parse("a number of generations")?;
+                if opts.every == 0 {
+                    return Err("--every must be at least 1".to_string());
+                }
+            }
+            "--cells" => opts.cells = p.parse("a number of cells")?,
+            "-g" | "--generations" => opts.generations = p.parse("a number of generations")?,
+            "-d" | "--delay" => opts.delay = p.parse("milliseconds")?,
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    // a pattern has no sides to take
+    if opts.world.pattern.is_some() || !opts.world.placements.is_empty() {
+        return Err("versus starts from a mirrored soup, so it takes no pattern".to_string());
+    }
+    if opts.world.random.is_none() {
+        opts.world.random = Some(DEFAULT_DENSITY);
+    }
+    Ok(Command::Versus(opts))
+}
+
+fn parse_scrub<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut opts = ScrubOptions {
+        world: WorldOptions::from_config(p.config),
+        delay: p.config.delay.unwrap_or(100),
+        style: p.config.style.clone(),
+    };
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(path) if opts.world.pattern.is_none() => {
+                opts.world.pattern = Some(PatternSource::File(p.config.resolve_pattern(&path)));
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, SCRUB_USAGE) {
+            return Ok(command);
+        }
+        if p.world_flag(&flag, &mut opts.world)? {
+            continue;
+        }
+        match flag.as_str() {
+            "-d" | "--delay" => opts.delay = p.parse("milliseconds")?,
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    Ok(Command::Scrub(opts))
+}
+
+/// A species by its letter, `A` for the first.
+fn parse_species_name(name: &str) -> Option<usize> {
+    match name.trim().as_bytes() {
+        &[c] if c.is_ascii_alphabetic() => Some(usize::from(c.to_ascii_uppercase() - b'A')),
+        _ => None,
+    }
+}
+
+fn parse_species<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut opts = SpeciesOptions {
+        world: WorldOptions::from_config(p.config),
+        species: 3,
+        ties: Ties::Alternate,
+        dead_to: Vec::new(),
+        generations: 200,
+        delay: p.config.delay.unwrap_or(100),
+        color: !p.config.no_color,
+    };
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, SPECIES_USAGE) {
+            return Ok(command);
+        }
+        if p.world_flag(&flag, &mut opts.world)? {
+            continue;
+        }
+        match flag.as_str() {
+            "--species" => {
+                opts.species = p.parse("a number of species")?;
+                if !(1..=MAX_SPECIES).contains(&opts.species) {
+                    return Err(format!(
+                        "--species must be from 1 to {}, got {}",
+                        MAX_SPECIES, opts.species
+                    ));
+                }
+            }
+            "--ties" => opts.ties = Ties::parse(&p.value()?)?,
+            "--dead-to" => {
+                let value = p.value()?;
+                let pair = value
+                    .split_once(':')
+                    .and_then(|(a, b)| Some((parse_species_name(a)?, parse_species_name(b)?)))
+                    .ok_or_else(|| {
+                        format!(
+                            "invalid --dead-to '{}', expected two species like A:B",
+                            value
+                        )
+                    })?;
+                opts.dead_to.push(pair);
+            }
+            "-g" | "--generations" => opts.generations = p.parse("a number of generations")?,
+            "-d" | "--delay" => opts.delay = p.parse("milliseconds")?,
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    if let Some(&(a, b)) = opts
+        .dead_to
+        .iter()
+        .find(|&&(a, b)| a.max(b) >= opts.species)
+    {
+        return Err(format!(
+            "--dead-to {}:{} names a species past the {} there are",
+            char::from(b'A' + a as u8),
+            char::from(b'A' + b as u8),
+            opts.species
+        ));
+    }
+    if opts.world.pattern.is_some() {
+        return Err("species starts from a soup or --place patterns only".to_string());
+    }
+    if opts.world.placements.iter().any(|p| p.every.is_some()) {
+        return Err("species cannot --tile, give each pattern with --place".to_string());
+    }
+    if opts.world.placements.is_empty() && opts.world.random.is_none() {
+        opts.world.random = Some(DEFAULT_DENSITY);
+    }
+    Ok(Command::Species(opts))
+}
+
+fn parse_cavegen<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let (mut size, mut format) = (None, None);
+    let mut opts = CaveOptions {
+        width: 0,
+        height: 0,
+        fill: 0.45,
+        smooth: 5,
+        seed: None,
+        keep_pockets: false,
+        output: None,
+        format: CaveFormat::Text,
+        scale: 4,
+        style: p.config.style.clone(),
+    };
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(value) if size.is_none() => {
+                let parsed = value
+                    .split_once('x')
+                    .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)));
+                size =
+                    Some(parsed.ok_or_else(|| {
+                        format!("invalid size '{}', expected WxH like 80x40", value)
+                    })?);
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, CAVEGEN_USAGE) {
+            return Ok(command);
+        }
+        match flag.as_str() {
+            "--fill" => {
+                opts.fill = p.parse("a share of the cells")?;
+                if !(0.0..=1.0).contains(&opts.fill) {
+                    return Err(format!("--fill must be between 0 and 1, got {}", opts.fill));
+                }
+            }
+            "--smooth" => opts.smooth = p.parse("a number of generations")?,
+            "--seed" => opts.seed = Some(p.parse("a number")?),
+            "--keep-pockets" => opts.keep_pockets = true,
+            "-o" | "--output" => opts.output = Some(p.value()?),
+            "-f" | "--format" => format = Some(CaveFormat::parse(&p.value()?)?),
+            "--scale" => {
+                opts.scale = p.parse("a number of pixels")?;
+                if opts.scale == 0 {
+                    return Err("--scale must be at least 1".to_string());
+                }
+            }
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    let (width, height) = size.ok_or("cavegen needs a size WxH")?;
+    opts.width = width;
+    opts.height = height;
+    opts.format = match (format, opts.output.as_deref()) {
+        (Some(format), _) => format,
+        (None, Some(path)) if path.ends_with(".png") => CaveFormat::Png,
+        (None, Some(path)) if path.ends_with(".json") => CaveFormat::Json,
+        _ => CaveFormat::Text,
+    };
+    if opts.format == CaveFormat::Png && opts.output.is_none() {
+        return Err("a PNG map needs an --output FILE".to_string());
+    }
+    Ok(Command::Cavegen(opts))
+}
+
+fn parse_enumerate<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut opts = EnumerateOptions {
+        width: 5,
+        height: 5,
+        max_period: 4,
+        rule: p.config.rule.unwrap_or_else(Rule::conway),
+        threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        save: None,
+    };
+    let mut size = None;
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(value) if size.is_none() => {
+                let parsed = value
+                    .split_once('x')
+                    .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)));
+                size =
+                    Some(parsed.ok_or_else(|| {
+                        format!("invalid size '{}', expected WxH like 5x5", value)
+                    })?);
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, ENUMERATE_USAGE) {
+            return Ok(command);
+        }
+        match flag.as_str() {
+            "--max-period" => opts.max_period = p.parse("a number of generations")?,
+            "-r" | "--rule" => opts.rule = Rule::parse(&p.value()?)?,
+            "-j" | "--threads" => {
+                opts.threads = p.parse("a number of threads")?;
+                if opts.threads == 0 {
+                    return Err("--threads must be at least 1".to_string());
+                }
+            }
+            "--save" => opts.save = Some(p.value()?),
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    if let Some((width, height)) = size {
+        opts.width = width;
+        opts.height = height;
+    }
+    let sides = 1..=enumerate::MAX_SIDE;
+    if !sides.contains(&opts.width) || !sides.contains(&opts.height) {
+        return Err(format!(
+            "the box must be between 1 and {} cells a side, got {}x{}",
+            enumerate::MAX_SIDE,
+            opts.width,
+            opts.height
+        ));
+    }
+    if !(1..=enumerate::MAX_PERIOD).contains(&opts.max_period) {
+        return Err(format!(
+            "--max-period must be between 1 and {}, got {}",
+            enumerate::MAX_PERIOD,
+            opts.max_period
+        ));
+    }
+    opts.rule.nearest_only("enumerate")?;
+    Ok(Command::Enumerate(opts))
+}
+
+fn parse_collide<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let config = p.config;
+    let mut names = Vec::new();
+    let (mut range, mut phases, mut steps, mut only) = (8, None, 256, None);
+    let mut rule = config.rule;
+    let mut threads = thread::available_parallelism().map_or(1, |n| n.get());
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(name) if names.len() < 2 => {
+                names.push(name);
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, COLLIDE_USAGE) {
+            return Ok(command);
+        }
+        match flag.as_str() {
+            "--range" => range = p.parse("a number of cells")?,
+            "--phases" => {
+                let n: usize = p.parse("a number of phases")?;
+                if n == 0 {
+                    return Err("--phases must be at least 1".to_string());
+                }
+                phases = Some(n);
+            }
+            "--steps" => steps = p.parse("a number of generations")?,
+            "--only" => only = Some(Outcome::parse(&p.value()?)?),
+            "-r" | "--rule" => rule = Some(Rule::parse(&p.value()?)?),
+            "-j" | "--threads" => {
+                threads = p.parse("a number of threads")?;
+                if threads == 0 {
+                    return Err("--threads must be at least 1".to_string());
+                }
+            }
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    if names.len() < 2 {
+        return Err("collide needs a target and a bullet pattern".to_string());
+    }
+    if range < 0 {
+        return Err(format!("--range must not be negative, got {}", range));
+    }
+    let source = |name: &str| match library::get(name) {
+        Some(pattern) => PatternSource::Builtin(pattern),
+        None => PatternSource::File(config.resolve_pattern(name)),
+    };
+    Ok(Command::Collide(CollideOptions {
+        first: source(&names[0]),
+        second: source(&names[1]),
+        second_name: names.pop().unwrap_or_default(),
+        first_name: names.pop().unwrap_or_default(),
+        range,
+        phases,
+        steps,
+        only,
+        rule,
+        threads,
+    }))
+}
+
+fn parse_repl<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut opts = ReplOptions {
+        world: WorldOptions::from_config(p.config),
+    };
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(path) if opts.world.pattern.is_none() => {
+                opts.world.pattern = Some(PatternSource::File(p.config.resolve_pattern(&path)));
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, REPL_USAGE) {
+            return Ok(command);
+        }
+        if !p.world_flag(&flag, &mut opts.world)? {
+            return Err(unknown(Arg::Flag(flag)));
+        }
+    }
+    Ok(Command::Repl(opts))
+}
+
+fn parse_batch<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut manifest = None;
+    let mut threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let (mut output, mut format) = (None, None);
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(path) if manifest.is_none() => {
+                manifest = Some(path);
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, BATCH_USAGE) {
+            return Ok(command);
+        }
+        match flag.as_str() {
+            "-j" | "--threads" => {
+                threads = p.parse("a number of threads")?;
+                if threads == 0 {
+                    return Err("--threads must be at least 1".to_string());
+                }
+            }
+            "-o" | "--output" => output = Some(p.value()?),
+            "--format" => {
+                format = Some(match p.value()?.as_str() {
+                    "csv" => ReportFormat::Csv,
+                    "json" => ReportFormat::Json,
+                    other => {
+                        return Err(format!(
+                            "invalid value '{}' for --format: expected csv or json",
+                            other
+                        ))
+                    }
+                })
+            }
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    let manifest = manifest.ok_or("batch needs a MANIFEST file")?;
+    let text =
+        fs::read_to_string(&manifest).map_err(|e| format!("cannot read {}: {}", manifest, e))?;
+    let mut runs = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let run = split_words(line)
+            .and_then(|words| parse_batch_run(words, i + 1, p.config))
+            .map_err(|e| format!("{} line {}: {}", manifest, i + 1, e))?;
+        runs.push(run);
+    }
+    if runs.is_empty() {
+        return Err(format!("{} lists no runs", manifest));
+    }
+    let json = output
+        .as_deref()
+        .is_some_and(|path| path.ends_with(".json"));
+    Ok(Command::Batch(BatchOptions {
+        manifest,
+        runs,
+        threads,
+        output,
+        format: format.unwrap_or(if json {
+            ReportFormat::Json
+        } else {
+            ReportFormat::Csv
+        }),
+    }))
+}
+
+/// The words of a manifest line, a double-quoted stretch counting as part of
+/// one word however many spaces it has.
+fn split_words(line: &str) -> Result<Vec<String>, String> {
+    let (mut words, mut word, mut quoted, mut any) = (Vec::new(), String::new(), false, false);
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                any = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if any {
+                    words.push(mem::take(&mut word));
+                    any = false;
+                }
+            }
+            c => {
+                word.push(c);
+                any = true;
+            }
+        }
+    }
+    if quoted {
+        return Err("a quote is not closed".to_string());
+    }
+    if any {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+fn parse_batch_run(words: Vec<String>, line: usize, config: &Config) -> Result<BatchRun, String> {
+    let mut p = Parser {
+        args: words.into_iter().peekable(),
+        flag: String::new(),
+        inline: None,
+        config,
+    };
+    let mut run = BatchRun {
+        line,
+        world: WorldOptions::from_config(config),
+        generations: 1000,
+        until: None,
+        max_period: stop::DEFAULT_WINDOW,
+    };
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(path) if run.world.pattern.is_none() => {
+                run.world.pattern = Some(PatternSource::File(config.resolve_pattern(&path)));
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if p.world_flag(&flag, &mut run.world)? {
+            continue;
+        }
+        match flag.as_str() {
+            "-g" | "--generations" => run.generations = p.parse("a number of generations")?,
+            "--until" => run.until = Some(Until::parse(&p.value()?)?),
+            "--max-period" => run.max_period = parse_max_period(&mut p)?,
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    Ok(run)
+}
+
+fn parse_sweep<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut opts = SweepOptions {
+        world: WorldOptions::from_config(p.config),
+        axes: Vec::new(),
+        runs: 8,
+        threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        generations: 2000,
+        max_period: stop::DEFAULT_WINDOW,
+        format: None,
+        heatmap: None,
+        measure: Measure::Lifespan,
+    };
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(path) if opts.world.pattern.is_none() => {
+                opts.world.pattern = Some(PatternSource::File(p.config.resolve_pattern(&path)));
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, SWEEP_USAGE) {
+            return Ok(command);
+        }
+        if p.world_flag(&flag, &mut opts.world)? {
+            continue;
+        }
+        match flag.as_str() {
+            "--vary" => {
+                if opts.axes.len() == 2 {
+                    return Err("at most two settings can be swept at once".to_string());
+                }
+                opts.axes.push(Axis::parse(&p.value()?)?);
+            }
+            "--runs" => {
+                opts.runs = p.parse("a number of soups")?;
+                if opts.runs == 0 {
+                    return Err("--runs must be at least 1".to_string());
+                }
+            }
+            "-j" | "--threads" => {
+                opts.threads = p.parse("a number of threads")?;
+                if opts.threads == 0 {
+                    return Err("--threads must be at least 1".to_string());
+                }
+            }
+            "-g" | "--generations" => opts.generations = p.parse("a number of generations")?,
+            "--max-period" => opts.max_period = parse_max_period(p)?,
+            "--format" => {
+                opts.format = Some(match p.value()?.as_str() {
+                    "csv" => ReportFormat::Csv,
+                    "json" => ReportFormat::Json,
+                    other => {
+                        return Err(format!(
+                            "invalid value '{}' for --format: expected csv or json",
+                            other
+                        ))
+                    }
+                })
+            }
+            "--heatmap" => opts.heatmap = Some(p.value()?),
+            "--measure" => opts.measure = Measure::parse(&p.value()?)?,
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    if opts.axes.is_empty() {
+        return Err("sweep needs a setting to --vary, like --vary density=0.1:0.9:0.1".to_string());
+    }
+    if opts.axes.len() == 2 && opts.axes[0].name() == opts.axes[1].name() {
+        return Err(format!("--vary {} is given twice", opts.axes[0].name()));
+    }
+    let points: usize = opts.axes.iter().map(Axis::len).product();
+    if points > sweep::MAX_POINTS {
+        return Err(format!(
+            "the sweep has {} points, at most {} can be run",
+            points,
+            sweep::MAX_POINTS
+        ));
+    }
+    let world = &mut opts.world;
+    if world.pattern.is_none() && world.placements.is_empty() && world.random.is_none() {
+        world.random = Some(DEFAULT_DENSITY);
+    }
+    Ok(Command::Sweep(opts))
+}
+
+fn parse_check<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut opts = CheckOptions {
+        files: Vec::new(),
+        format: None,
+    };
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(file) => {
+                opts.files.push(file);
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+        };
+        if let Some(command) = global_flag(&flag, CHECK_USAGE) {
+            return Ok(command);
+        }
+        match flag.as_str() {
+            "-f" | "--format" => opts.format = Some(Format::parse(&p.value()?)?),
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    if opts.files.is_empty() {
+        return Err("check needs a pattern FILE".to_string());
+    }
+    Ok(Command::Check(opts))
+}
+
+fn parse_thumb<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let (mut input, mut output) = (None, None);
+    let (mut size, mut generations, mut rule) = (128, 0, None);
+    let mut image = p.config.image.clone();
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(file) if input.is_none() => {
+                input = Some(file);
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, THUMB_USAGE) {
+            return Ok(command);
+        }
+        if p.image_flag(&flag, &mut image)? {
+            continue;
+        }
+        match flag.as_str() {
+            "-o" | "--output" => output = Some(p.value()?),
+            "-s" | "--size" => size = p.parse("a number of pixels")?,
+            "-g" | "--generations" => generations = p.parse("a number of generations")?,
+            "-r" | "--rule" => rule = Some(Rule::parse(&p.value()?)?),
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    if size == 0 {
+        return Err("--size must be at least 1".to_string());
+    }
+    Ok(Command::Thumb(ThumbOptions {
+        input: input.ok_or("thumb needs a PATTERN file")?,
+        output: output.ok_or("thumb needs an --output FILE")?,
+        size,
+        generations,
+        rule,
+        style: p.config.style.clone(),
+        image,
+    }))
+}
+
+fn parse_diff<I: Iterator<Item = String>>(p: &mut Parser<I>) -> Result<Command, String> {
+    let mut files = Vec::new();
+    let (mut offset, mut brief) = (None, false);
+    while let Some(arg) = p.next()? {
+        let flag = match arg {
+            Arg::Positional(file) if files.len() < 2 => {
+                files.push(file);
+                continue;
+            }
+            Arg::Flag(flag) => flag,
+            arg => return Err(unknown(arg)),
+        };
+        if let Some(command) = global_flag(&flag, DIFF_USAGE) {
+            return Ok(command);
+        }
+        match flag.as_str() {
+            "--offset" => offset = Some(parse_offset(&p.value()?)?),
+            "--brief" => brief = true,
+            _ => return Err(unknown(Arg::Flag(flag))),
+        }
+    }
+    let second = files.pop();
+    let first = files.pop();
+    match (first, second) {
+        (Some(first), Some(second)) => Ok(Command::Diff(DiffOptions {
+            first,
+            second,
+            offset,
+            brief,
+        })),
+        _ => Err("diff needs a FIRST and a SECOND pattern file".to_string()),
+    }
+}
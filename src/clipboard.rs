@@ -0,0 +1,75 @@
+//! The system clipboard, read and written through whichever of the usual
+//! tools is installed, as there is no portable way to reach it without a
+//! library.
+
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+/// Tried in order: Wayland, then X11 both ways, then macOS and Windows.
+const READERS: [&[&str]; 5] = [
+    &["wl-paste", "--no-newline"],
+    &["xclip", "-selection", "clipboard", "-out"],
+    &["xsel", "--clipboard", "--output"],
+    &["pbpaste"],
+    &["powershell", "-NoProfile", "-Command", "Get-Clipboard"],
+];
+
+/// The text on the clipboard.
+pub fn read() -> Result<String, String> {
+    for reader in READERS {
+        let output = Command::new(reader[0])
+            .args(&reader[1..])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output();
+        match output {
+            Ok(output) if output.status.success() => {
+                return String::from_utf8(output.stdout)
+                    .map_err(|_| "the clipboard does not hold text".to_string());
+            }
+            // installed, but with no display to ask or nothing copied
+            Ok(_) => continue,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("cannot run {}: {}", reader[0], e)),
+        }
+    }
+    Err("cannot read the clipboard: install wl-clipboard, xclip or xsel".to_string())
+}
+
+/// The same in the other direction, each taking the text on its stdin.
+const WRITERS: [&[&str]; 5] = [
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard", "-in"],
+    &["xsel", "--clipboard", "--input"],
+    &["pbcopy"],
+    &["clip"],
+];
+
+/// Puts `text` on the clipboard.
+pub fn write(text: &str) -> Result<(), String> {
+    for writer in WRITERS {
+        // the X11 tools stay behind to hand the text over, so nothing may
+        // wait for their output
+        let child = Command::new(writer[0])
+            .args(&writer[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("cannot run {}: {}", writer[0], e)),
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .map_err(|e| format!("cannot write to {}: {}", writer[0], e))?;
+        }
+        match child.wait() {
+            Ok(status) if status.success() => return Ok(()),
+            _ => continue,
+        }
+    }
+    Err("cannot write the clipboard: install wl-clipboard, xclip or xsel".to_string())
+}
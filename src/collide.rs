@@ -0,0 +1,373 @@
+//! `conway collide`: one pattern thrown at another from every nearby offset
+//! and in every phase, and what each crash leaves behind, the way glider
+//! syntheses are hunted for.
+//!
+//! Each collision is run for a fixed number of generations in a world wide
+//! enough that nothing leaving it comes back around in that time, then
+//! judged by what is left: nothing, the two patterns as they would have
+//! been on their own, settled ash, ash with spaceships flying off it, or
+//! something still changing.
+
+use conway::census;
+use conway::{Cell, Pattern, Rect, Rule, World};
+
+use crate::cli::{CollideOptions, PatternSource};
+use crate::memory;
+use crate::pool;
+use crate::stop;
+
+/// What a collision came to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The patterns passed by each other, or never met.
+    Miss,
+    Annihilation,
+    StillLife,
+    /// Settled ash, some of it oscillating.
+    Oscillator,
+    /// Settled ash, if any, and spaceships leaving it.
+    Spaceship,
+    /// Still changing at the end.
+    Explosion,
+}
+
+const OUTCOMES: [Outcome; 6] = [
+    Outcome::Miss,
+    Outcome::Annihilation,
+    Outcome::StillLife,
+    Outcome::Oscillator,
+    Outcome::Spaceship,
+    Outcome::Explosion,
+];
+
+impl Outcome {
+    pub fn parse(s: &str) -> Result<Outcome, String> {
+        OUTCOMES
+            .iter()
+            .copied()
+            .find(|outcome| outcome.name() == s.replace('-', " "))
+            .ok_or_else(|| {
+                format!(
+                    "unknown outcome '{}', expected miss, annihilation, still-life, \
+                     oscillator, spaceship or explosion",
+                    s
+                )
+            })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Outcome::Miss => "miss",
+            Outcome::Annihilation => "annihilation",
+            Outcome::StillLife => "still life",
+            Outcome::Oscillator => "oscillator",
+            Outcome::Spaceship => "spaceship",
+            Outcome::Explosion => "explosion",
+        }
+    }
+}
+
+/// One collision: where the second pattern started and in which phase.
+#[derive(Copy, Clone)]
+struct Trial {
+    dx: i64,
+    dy: i64,
+    phase: usize,
+}
+
+struct Reaction {
+    outcome: Outcome,
+    population: usize,
+    /// What the ash and ships are, like "2 glider, block".
+    leaves: String,
+}
+
+fn load(source: &PatternSource) -> Result<Pattern, String> {
+    match source {
+        PatternSource::Builtin(pattern) => Ok(pattern.clone()),
+        PatternSource::File(path) => Pattern::load_within(path, &memory::fits(None)),
+        _ => Err("collide reads pattern files and built-in patterns".to_string()),
+    }
+}
+
+/// The period of `pattern` as a spaceship or oscillator, or 1.
+fn period(pattern: &Pattern, rule: Rule) -> Result<usize, String> {
+    let pad = stop::DEFAULT_WINDOW + 2;
+    let mut world = World::new(pattern.width() + 2 * pad, pattern.height() + 2 * pad)?;
+    world.set_rule(rule);
+    world.stamp(pattern, pad, pad);
+    Ok(census::spaceships(&world, stop::DEFAULT_WINDOW)
+        .first()
+        .map(|ship| ship.period)
+        .or_else(|| world.detect_period(stop::DEFAULT_WINDOW))
+        .unwrap_or(1))
+}
+
+/// Generations between looks at whether a collision is over.
+const CHECK: u64 = 16;
+
+/// The generations a collision is looked at after: every `CHECK`, and the
+/// last.
+fn checkpoints(steps: u64) -> Vec<u64> {
+    let mut at: Vec<u64> = (1..)
+        .map(|k| k * CHECK)
+        .take_while(|&g| g < steps)
+        .collect();
+    at.push(steps);
+    at
+}
+
+/// Whether boxes `a` and `b`, `b` moved by `dx`, `dy`, are too far apart
+/// to meet before the next checkpoint, even heading straight for each
+/// other at half a cell a generation.
+fn far_apart(a: Option<Rect>, b: Option<Rect>, dx: i64, dy: i64) -> bool {
+    let (a, b) = match (a, b) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return true,
+    };
+    let span =
+        |from: usize, size: usize, by: i64| (from as i64 + by, from as i64 + by + size as i64);
+    let ((ax0, ax1), (ay0, ay1)) = (span(a.x, a.width, 0), span(a.y, a.height, 0));
+    let ((bx0, bx1), (by0, by1)) = (span(b.x, b.width, dx), span(b.y, b.height, dy));
+    let gap = (bx0 - ax1).max(ax0 - bx1).max(by0 - ay1).max(ay0 - by1);
+    gap > CHECK as i64 + 2
+}
+
+/// `world` at each of the generations `at`.
+fn evolve(mut world: World, at: &[u64]) -> Vec<World> {
+    let mut tmp = world.clone();
+    let mut generation = 0;
+    at.iter()
+        .map(|&until| {
+            while generation < until && world.advance(&mut tmp).any() {
+                generation += 1;
+            }
+            // a world that stopped changing is the same ever after
+            generation = until;
+            world.clone()
+        })
+        .collect()
+}
+
+/// Names the objects in `world`, spaceships first, like "2 glider, block".
+fn describe(ships: &[census::Spaceship], ash: &World) -> String {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for ship in ships {
+        let name = ship.name.unwrap_or("ship");
+        match counts.iter_mut().find(|c| c.0 == name) {
+            Some(count) => count.1 += 1,
+            None => counts.push((name, 1)),
+        }
+    }
+    counts.extend(census::census(ash));
+    let names: Vec<String> = counts
+        .iter()
+        .map(|&(name, n)| match n {
+            1 => name.to_string(),
+            n => format!("{} {}", n, name),
+        })
+        .collect();
+    names.join(", ")
+}
+
+/// Whether the worlds have the same cells alive.
+fn same(a: &World, b: &World) -> Result<bool, String> {
+    let mut differs = a.clone();
+    differs.xor(b, 0, 0)?;
+    Ok(differs.population() == 0)
+}
+
+/// Judges the world a collision came to, `missed` if it is what the two
+/// patterns would have become without meeting.
+fn judge(world: &World, missed: bool) -> Reaction {
+    let ships = census::spaceships(world, stop::DEFAULT_WINDOW);
+    let mut ash = world.clone();
+    for ship in &ships {
+        for y in ship.y..ship.y + ship.height {
+            for x in ship.x..ship.x + ship.width {
+                let (x, y) = ash.wrap(x as i64, y as i64);
+                ash.set(x, y, Cell::Dead);
+            }
+        }
+    }
+    let outcome = if missed {
+        Outcome::Miss
+    } else if world.population() == 0 {
+        Outcome::Annihilation
+    } else {
+        match ash.detect_period(stop::DEFAULT_WINDOW) {
+            None => Outcome::Explosion,
+            Some(_) if !ships.is_empty() => Outcome::Spaceship,
+            Some(1) => Outcome::StillLife,
+            Some(_) => Outcome::Oscillator,
+        }
+    };
+    Reaction {
+        outcome,
+        population: world.population(),
+        leaves: describe(&ships, &ash),
+    }
+}
+
+pub fn run(opts: &CollideOptions) -> Result<(), String> {
+    let (a, b) = (load(&opts.first)?.crop(), load(&opts.second)?.crop());
+    if a.population() == 0 || b.population() == 0 {
+        return Err("collide needs two patterns with live cells".to_string());
+    }
+    let rule = opts.rule.or_else(|| a.rule()).unwrap_or_default();
+    rule.nearest_only("collide")?;
+    let phases = match opts.phases {
+        Some(phases) => phases,
+        None => period(&b, rule)?,
+    };
+
+    // the second pattern in each of its phases, each cropped again so the
+    // offsets are from where its cells are
+    let mut shapes = Vec::with_capacity(phases);
+    let side = b.width().max(b.height()) + 2 * (phases + 2);
+    let mut world = World::new(side, side)?;
+    world.set_rule(rule);
+    world.stamp(&b, phases + 2, phases + 2);
+    let mut tmp = world.clone();
+    for _ in 0..phases {
+        shapes.push(Pattern::from_world(&world).crop());
+        world.advance(&mut tmp);
+    }
+
+    // nothing but explosions goes faster than half a cell a generation, so a
+    // ship thrown off can't come back around to the ash in time
+    let reach = opts.range as usize;
+    let biggest = shapes
+        .iter()
+        .map(|s| s.width().max(s.height()))
+        .max()
+        .unwrap_or(0);
+    let side = a.width().max(a.height())
+        + biggest
+        + 2 * reach
+        + opts.steps as usize / 2
+        + stop::DEFAULT_WINDOW;
+    let (ax, ay) = ((side - a.width()) / 2, (side - a.height()) / 2);
+    let blank = || -> Result<World, String> {
+        let mut world = World::new(side, side)?;
+        world.set_rule(rule);
+        Ok(world)
+    };
+    let mut first = blank()?;
+    first.stamp(&a, ax, ay);
+    let at = checkpoints(opts.steps);
+    let first_alone = evolve(first.clone(), &at);
+    let mut alone = Vec::with_capacity(phases);
+    for shape in &shapes {
+        let mut world = blank()?;
+        world.stamp(shape, ax, ay);
+        alone.push(evolve(world, &at));
+    }
+
+    let a_cells: Vec<(i64, i64)> = cells(&a).collect();
+    let mut trials = Vec::new();
+    let mut touching = 0;
+    for dy in -opts.range..=opts.range {
+        for dx in -opts.range..=opts.range {
+            for (phase, shape) in shapes.iter().enumerate() {
+                // cells two apart already share a neighbor to be born in
+                let near = cells(shape).any(|(x, y)| {
+                    a_cells
+                        .iter()
+                        .any(|&(ax, ay)| (x + dx - ax).abs() <= 2 && (y + dy - ay).abs() <= 2)
+                });
+                if near {
+                    touching += 1;
+                } else {
+                    trials.push(Trial { dx, dy, phase });
+                }
+            }
+        }
+    }
+
+    let results = pool::run(trials.len(), opts.threads, |i| {
+        let trial = trials[i];
+        let (x, y) = (ax as i64 + trial.dx, ay as i64 + trial.dy);
+        let mut world = first.clone();
+        world.stamp_at(&shapes[trial.phase], x, y);
+        let mut tmp = world.clone();
+        let mut generation = 0;
+        for (k, &until) in at.iter().enumerate() {
+            while generation < until && world.advance(&mut tmp).any() {
+                generation += 1;
+            }
+            generation = until;
+            let mut apart = first_alone[k].clone();
+            apart.union(&alone[trial.phase][k], trial.dx, trial.dy)?;
+            let last = k + 1 == at.len();
+            let missed = same(&world, &apart)?;
+            // until the two meet it looks like a miss, and they
+            // won't if they stay far enough apart
+            let never = (k..at.len()).all(|j| {
+                far_apart(
+                    first_alone[j].bounding_box(),
+                    alone[trial.phase][j].bounding_box(),
+                    trial.dx,
+                    trial.dy,
+                )
+            });
+            if missed && !never && !last {
+                continue;
+            }
+            let reaction = judge(&world, missed);
+            // while they react it looks like an explosion
+            if reaction.outcome != Outcome::Explosion || last {
+                return Ok(reaction);
+            }
+        }
+        unreachable!("there is always a last checkpoint")
+    })?;
+
+    println!(
+        "collide      {} at {}, offsets {}..{}, {} {}, {} generations, rule {}",
+        opts.second_name,
+        opts.first_name,
+        -opts.range,
+        opts.range,
+        phases,
+        if phases == 1 { "phase" } else { "phases" },
+        opts.steps,
+        rule
+    );
+    println!("   dx   dy  phase  outcome       cells  leaves");
+    for (trial, result) in trials.iter().zip(&results) {
+        if opts.only.is_some_and(|only| only != result.outcome) {
+            continue;
+        }
+        println!(
+            "{:>5}{:>5}{:>7}  {:<12}{:>6}  {}",
+            trial.dx,
+            trial.dy,
+            trial.phase,
+            result.outcome.name(),
+            result.population,
+            result.leaves
+        );
+    }
+    let counts: Vec<String> = OUTCOMES
+        .iter()
+        .map(|&outcome| {
+            let n = results.iter().filter(|r| r.outcome == outcome).count();
+            format!("{} {}", n, outcome.name())
+        })
+        .collect();
+    println!(
+        "outcomes     {}, {} skipped as touching",
+        counts.join(", "),
+        touching
+    );
+    Ok(())
+}
+
+/// The live cells of `pattern`.
+fn cells(pattern: &Pattern) -> impl Iterator<Item = (i64, i64)> + '_ {
+    (0..pattern.height())
+        .flat_map(move |y| (0..pattern.width()).map(move |x| (x, y)))
+        .filter(move |&(x, y)| pattern.get(x, y))
+        .map(|(x, y)| (x as i64, y as i64))
+}
@@ -0,0 +1,56 @@
+//! Side-by-side view of one starting world evolving under two rules.
+
+use conway::{Rule, World};
+
+use crate::output;
+use crate::pace::Pacer;
+
+const SEPARATOR: &str = "  |  ";
+
+fn frame(left: &World, right: &World) -> String {
+    let label = |world: &World| format!("{} ({})", world.rule(), world.population());
+    let mut s = format!(
+        "{:width$}{}{}\n",
+        label(left),
+        SEPARATOR,
+        label(right),
+        width = left.width()
+    );
+
+    let (left, right) = (left.as_string(), right.as_string());
+    for (l, r) in left.lines().zip(right.lines()) {
+        s.push_str(l);
+        s.push_str(SEPARATOR);
+        s.push_str(r);
+        s.push('\n');
+    }
+    s
+}
+
+/// Steps copies of `world` under each rule in lockstep, printing both panes
+/// every `every` generations and after the last one.
+pub fn run(
+    world: &World,
+    rules: (Rule, Rule),
+    generations: u64,
+    every: u64,
+    mut pacer: Pacer,
+) -> Result<(), String> {
+    let mut left = world.clone();
+    left.set_rule(rules.0);
+    let mut right = world.clone();
+    right.set_rule(rules.1);
+    let mut tmp = world.clone();
+
+    output::screen(&frame(&left, &right));
+    for generation in 1..=generations {
+        left.advance(&mut tmp);
+        right.advance(&mut tmp);
+        if generation % every != 0 && generation != generations {
+            continue;
+        }
+        pacer.wait();
+        output::screen(&frame(&left, &right));
+    }
+    Ok(())
+}
@@ -0,0 +1,630 @@
+//! User configuration from `~/.config/conway/config.toml`.
+//!
+//! Only the subset of TOML the settings need is understood: `[tables]`,
+//! `key = value` with strings, integers, floats, booleans and arrays, and
+//! `#` comments. Every setting is optional; `CONWAY_*` environment variables
+//! override it, and command line flags override both.
+//!
+//! ```toml
+//! renderer = "terminal"          # or "window"
+//! rule = "B3/S23"
+//! delay = 200
+//! pattern_dirs = ["~/patterns"]
+//!
+//! [glyphs]
+//! live = "#"
+//! dead = " "
+//!
+//! [colors]
+//! live = "green"                 # a name, a 256-color index or "#rrggbb"
+//! dead = 236
+//!
+//! # used with --profile night, on top of the settings above
+//! [profiles.night]
+//! screensaver = true
+//! fps = 5
+//! ```
+//!
+//! An `[image]` table sets how images look, as `figure` describes, and a
+//! `[terminal]` table what the terminal can show, as `caps` describes.
+//!
+//! The built-in profiles in `BUILTIN_PROFILES` can be changed the same way,
+//! and so can the themes `theme = "NAME"` picks, with `[themes.NAME]` tables
+//! as `theme` describes. A theme comes first, whatever `[colors]` sets then
+//! changing it.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use conway::Rule;
+
+use crate::caps::{Caps, Depth};
+use crate::cli::OutputFormat;
+use crate::figure::{Filter, ImageStyle};
+use crate::stop::Until;
+use crate::term::{Color, Style};
+use crate::theme::{self, Theme};
+
+const BUILTIN_PROFILES: &str = r#"
+[profiles.demo]
+random = 0.3
+fps = 15
+until = "stable"
+
+[profiles.bench]
+width = 256
+height = 256
+random = 0.3
+seed = 0
+generations = 1000
+delay = 0
+every = 1000
+
+[profiles.screensaver]
+screensaver = true
+fps = 10
+
+[profiles.research]
+random = 0.3
+until = "stable"
+output_format = "jsonl"
+"#;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Str(_) => "a string",
+            Value::Int(_) => "an integer",
+            Value::Float(_) => "a number",
+            Value::Bool(_) => "a boolean",
+            Value::Array(_) => "an array",
+        }
+    }
+
+    /// For errors: numbers as they are, since their type is usually right.
+    fn describe(&self) -> String {
+        match self {
+            Value::Int(n) => n.to_string(),
+            Value::Float(f) => f.to_string(),
+            _ => self.type_name().to_string(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Renderer {
+    Terminal,
+    Window,
+}
+
+/// Every setting, as written in the file.
+const KEYS: [&str; 28] = [
+    "renderer",
+    "rule",
+    "width",
+    "height",
+    "generations",
+    "delay",
+    "fps",
+    "every",
+    "until",
+    "screensaver",
+    "random",
+    "seed",
+    "output_format",
+    "theme",
+    "glyphs.live",
+    "glyphs.dead",
+    "colors.live",
+    "colors.dead",
+    "image.cell_size",
+    "image.live",
+    "image.dead",
+    "image.background",
+    "image.grid",
+    "image.margin",
+    "image.filter",
+    "terminal.colors",
+    "terminal.unicode",
+    "pattern_dirs",
+];
+
+#[derive(Default)]
+pub struct Config {
+    pub renderer: Option<Renderer>,
+    pub rule: Option<Rule>,
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub generations: Option<u64>,
+    pub delay: Option<u64>,
+    pub fps: Option<u32>,
+    pub every: Option<u64>,
+    pub until: Option<Until>,
+    pub screensaver: Option<bool>,
+    pub random: Option<f64>,
+    pub seed: Option<u64>,
+    pub output_format: Option<OutputFormat>,
+    pub style: Style,
+    /// How `render` and `thumb` draw their images, from `[image]`.
+    pub image: ImageStyle,
+    /// Set by `NO_COLOR`, for the displays that choose their own colors.
+    pub no_color: bool,
+    /// What `[terminal]` says the terminal can show, instead of working it
+    /// out.
+    pub terminal_colors: Option<Depth>,
+    pub terminal_unicode: Option<bool>,
+    pub pattern_dirs: Vec<PathBuf>,
+    /// `[profiles.NAME]` settings, applied only when asked for.
+    profiles: HashMap<String, Vec<(String, Value)>>,
+    /// The themes of `[themes.NAME]` tables, built-in ones they change
+    /// included.
+    themes: HashMap<String, Theme>,
+}
+
+/// A color setting: a name, a palette index, `#rrggbb`, or `none` for the
+/// terminal's own.
+fn parse_color(value: &Value) -> Result<Option<Color>, String> {
+    match value {
+        Value::Str(s) if s == "none" => Ok(None),
+        Value::Str(s) => Color::parse(s).map(Some),
+        Value::Int(n) if (0..=255).contains(n) => Ok(Some(Color::Indexed(*n as u8))),
+        _ => Err(format!(
+            "expected a color name, 0-255, \"#rrggbb\" or \"none\", not {}",
+            value.describe()
+        )),
+    }
+}
+
+/// Sets `key` of a `[themes.NAME]` table.
+fn set_theme(theme: &mut Theme, key: &str, value: &Value) -> Result<(), String> {
+    let color = || parse_color(value).map_err(|e| format!("'{}': {}", key, e));
+    let required = || color()?.ok_or_else(|| format!("'{}' needs a color, not none", key));
+    match key {
+        "live" => theme.live = color()?,
+        "dead" => theme.dead = color()?,
+        "old" => theme.old = color()?,
+        "birth" => theme.birth = color()?,
+        "death" => theme.death = color()?,
+        "hud" => theme.hud = color()?,
+        "ghost" => theme.ghost = required()?,
+        "footprint" => theme.footprint = required()?,
+        "trail" => match value {
+            Value::Array(colors) if colors.len() == 2 => {
+                let mut pair = colors.iter().map(|c| {
+                    parse_color(c)?.ok_or_else(|| "trail colors cannot be none".to_string())
+                });
+                theme.trail = (pair.next().unwrap()?, pair.next().unwrap()?);
+            }
+            _ => return Err("'trail' must be an array of two colors".to_string()),
+        },
+        "monochrome" => match value {
+            Value::Bool(b) => theme.monochrome = *b,
+            _ => {
+                return Err(format!(
+                    "'monochrome' must be a boolean, not {}",
+                    value.describe()
+                ))
+            }
+        },
+        _ => return Err(format!("unknown theme setting '{}'", key)),
+    }
+    Ok(())
+}
+
+fn parse_string(s: &str, line: usize) -> Result<(String, &str), String> {
+    let quote = s.chars().next().unwrap();
+    let mut out = String::new();
+    let mut chars = s[1..].char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            c if c == quote => return Ok((out, &s[i + 2..])),
+            // literal 'strings' take backslashes as they are
+            '\\' if quote == '"' => match chars.next().map(|(_, c)| c) {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some('\\') => out.push('\\'),
+                Some('"') => out.push('"'),
+                _ => return Err(format!("line {}: unsupported escape in string", line)),
+            },
+            c => out.push(c),
+        }
+    }
+    Err(format!("line {}: unterminated string", line))
+}
+
+/// Parses one value at the start of `s`, returning it and the rest of the line.
+fn parse_value(s: &str, line: usize) -> Result<(Value, &str), String> {
+    let s = s.trim_start();
+    match s.chars().next() {
+        Some('"') | Some('\'') => parse_string(s, line).map(|(v, rest)| (Value::Str(v), rest)),
+        Some('[') => {
+            let mut items = Vec::new();
+            let mut rest = s[1..].trim_start();
+            loop {
+                if let Some(after) = rest.strip_prefix(']') {
+                    return Ok((Value::Array(items), after));
+                }
+                let (item, after) = parse_value(rest, line)?;
+                items.push(item);
+                rest = after.trim_start();
+                if let Some(after) = rest.strip_prefix(',') {
+                    rest = after.trim_start();
+                } else if !rest.starts_with(']') {
+                    return Err(format!("line {}: expected ',' or ']' in array", line));
+                }
+            }
+        }
+        Some(_) => {
+            let end = s.find([',', ']', '#']).unwrap_or(s.len());
+            let (token, rest) = (s[..end].trim(), &s[end..]);
+            let value = match token {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                _ => {
+                    let digits = token.replace('_', "");
+                    if let Ok(n) = digits.parse() {
+                        Value::Int(n)
+                    } else if let Ok(f) = digits.parse() {
+                        Value::Float(f)
+                    } else {
+                        return Err(format!("line {}: invalid value '{}'", line, token));
+                    }
+                }
+            };
+            Ok((value, rest))
+        }
+        None => Err(format!("line {}: missing value", line)),
+    }
+}
+
+/// Flattens the document into `table.key` entries, remembering their lines.
+fn parse_document(text: &str) -> Result<Vec<(String, Value, usize)>, String> {
+    let mut entries = Vec::new();
+    let mut table = String::new();
+    for (i, raw) in text.lines().enumerate() {
+        let line = i + 1;
+        let trimmed = raw.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if trimmed.starts_with('[') {
+            let end = trimmed
+                .find(']')
+                .ok_or_else(|| format!("line {}: unterminated table header", line))?;
+            table = trimmed[1..end].trim().to_string();
+            continue;
+        }
+        let eq = trimmed
+            .find('=')
+            .ok_or_else(|| format!("line {}: expected key = value", line))?;
+        let key = trimmed[..eq].trim().trim_matches('"');
+        let (value, rest) = parse_value(&trimmed[eq + 1..], line)?;
+        let rest = rest.trim();
+        if !rest.is_empty() && !rest.starts_with('#') {
+            return Err(format!("line {}: unexpected '{}' after value", line, rest));
+        }
+        let key = if table.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", table, key)
+        };
+        entries.push((key, value, line));
+    }
+    Ok(entries)
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// `$XDG_CONFIG_HOME/conway/config.toml`, falling back to `~/.config`.
+pub fn default_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(base.join("conway").join("config.toml"))
+}
+
+impl Config {
+    /// Loads the given file, or the default one if it exists.
+    pub fn load(path: Option<&str>) -> Result<Config, String> {
+        let path = match path {
+            Some(path) => PathBuf::from(path),
+            None => match default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Config::default()),
+            },
+        };
+        let text = fs::read_to_string(&path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        Config::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str) -> Result<Config, String> {
+        let mut config = Config::default();
+        let mut seen = HashMap::new();
+        let mut entries = parse_document(text)?;
+        // themes first, so `theme` can pick one defined further down
+        entries.sort_by_key(|(key, _, _)| !key.starts_with("themes."));
+        for (key, value, line) in entries {
+            if let Some(first) = seen.insert(key.clone(), line) {
+                return Err(format!(
+                    "line {}: '{}' was already set on line {}",
+                    line, key, first
+                ));
+            }
+            let at_line = |e| format!("line {}: {}", line, e);
+            if let Some(rest) = key.strip_prefix("profiles.") {
+                let (name, setting) = rest
+                    .split_once('.')
+                    .ok_or_else(|| at_line(format!("'{}' is not inside a profile table", key)))?;
+                // catch mistakes now rather than when the profile is used
+                let mut check = Config {
+                    themes: config.themes.clone(),
+                    ..Config::default()
+                };
+                check.set(setting, &value).map_err(at_line)?;
+                config
+                    .profiles
+                    .entry(name.to_string())
+                    .or_default()
+                    .push((setting.to_string(), value));
+                continue;
+            }
+            config.set(&key, &value).map_err(at_line)?;
+        }
+        Ok(config)
+    }
+
+    /// Applies the named profile: the built-in one if there is one, then the
+    /// config file's `[profiles.NAME]` table on top.
+    pub fn apply_profile(&mut self, name: &str) -> Result<(), String> {
+        let builtin = Config::parse(BUILTIN_PROFILES).expect("built-in profiles are valid");
+        let mut entries = builtin.profiles.get(name).cloned().unwrap_or_default();
+        entries.extend(self.profiles.get(name).cloned().unwrap_or_default());
+        if entries.is_empty() {
+            let mut names: Vec<&str> = builtin
+                .profiles
+                .keys()
+                .chain(self.profiles.keys())
+                .map(String::as_str)
+                .collect();
+            names.sort_unstable();
+            names.dedup();
+            return Err(format!(
+                "unknown profile '{}', expected one of {}",
+                name,
+                names.join(", ")
+            ));
+        }
+        for (key, value) in entries {
+            self.set(&key, &value)
+                .map_err(|e| format!("profile {}: {}", name, e))?;
+        }
+        Ok(())
+    }
+
+    /// Applies the named theme: one from the config file, or a built-in one.
+    pub fn apply_theme(&mut self, name: &str) -> Result<(), String> {
+        let theme = match self
+            .themes
+            .get(name)
+            .copied()
+            .or_else(|| theme::builtin(name))
+        {
+            Some(theme) => theme,
+            None => {
+                let mut names: Vec<&str> = theme::BUILTIN
+                    .iter()
+                    .map(|&(name, _)| name)
+                    .chain(self.themes.keys().map(String::as_str))
+                    .collect();
+                names.sort_unstable();
+                names.dedup();
+                return Err(format!(
+                    "unknown theme '{}', expected one of {}",
+                    name,
+                    names.join(", ")
+                ));
+            }
+        };
+        theme.apply(&mut self.style);
+        Ok(())
+    }
+
+    /// Overrides settings from `CONWAY_*` environment variables, named after
+    /// the keys: `CONWAY_RULE`, `CONWAY_GLYPHS_LIVE` and so on.
+    /// `CONWAY_PATTERN_DIRS` is a colon-separated list. `NO_COLOR` or
+    /// `CONWAY_NO_COLOR` turn colors off.
+    pub fn apply_env(&mut self) -> Result<(), String> {
+        for &key in KEYS.iter() {
+            let var = format!("CONWAY_{}", key.replace('.', "_").to_uppercase());
+            let text = match env::var(&var) {
+                Ok(text) => text,
+                Err(_) => continue,
+            };
+            let value = if key == "pattern_dirs" {
+                Value::Array(
+                    env::split_paths(&text)
+                        .map(|dir| Value::Str(dir.to_string_lossy().into_owned()))
+                        .collect(),
+                )
+            } else if let Ok(n) = text.parse() {
+                Value::Int(n)
+            } else if let Ok(f) = text.parse() {
+                Value::Float(f)
+            } else if let Ok(b) = text.parse() {
+                Value::Bool(b)
+            } else {
+                Value::Str(text)
+            };
+            if key == "pattern_dirs" {
+                self.pattern_dirs.clear();
+            }
+            self.set(key, &value)
+                .map_err(|e| format!("{}: {}", var, e))?;
+        }
+        // https://no-color.org: present and not empty
+        let set = |var| env::var_os(var).is_some_and(|v| !v.is_empty());
+        if set("NO_COLOR") || set("CONWAY_NO_COLOR") {
+            self.style.live_color = None;
+            self.style.dead_color = None;
+            self.style.birth_color = None;
+            self.style.death_color = None;
+            self.style.hud_color = None;
+            self.style.old_color = None;
+            self.style.dim_colors = false;
+            self.no_color = true;
+        }
+        Ok(())
+    }
+
+    /// Works out what the terminal can show and fits the style to it, once
+    /// every other setting is in: glyphs in place of colors on a terminal
+    /// without them, and ASCII glyphs in place of others without Unicode.
+    /// Images keep their colors either way.
+    pub fn fit_terminal(&mut self) -> Caps {
+        let caps = Caps::detect(self.terminal_colors, self.terminal_unicode);
+        if caps.depth == Depth::None {
+            self.style.dim_colors = false;
+            self.no_color = true;
+        }
+        if !caps.unicode {
+            let ascii = |glyph: &str, fallback: &str| match glyph.is_ascii() {
+                true => glyph.to_string(),
+                false => fallback.repeat(glyph.chars().count().max(1)),
+            };
+            self.style.live = ascii(&self.style.live, "O");
+            self.style.dead = ascii(&self.style.dead, ".");
+            self.style.unicode = false;
+        }
+        caps
+    }
+
+    fn set(&mut self, key: &str, value: &Value) -> Result<(), String> {
+        let wrong_type =
+            |expected: &str| format!("'{}' must be {}, not {}", key, expected, value.describe());
+        match (key, value) {
+            ("renderer", Value::Str(s)) => {
+                self.renderer = Some(match s.as_str() {
+                    "terminal" => Renderer::Terminal,
+                    "window" => Renderer::Window,
+                    _ => {
+                        return Err(format!(
+                            "unknown renderer '{}', expected terminal or window",
+                            s
+                        ))
+                    }
+                })
+            }
+            ("rule", Value::Str(s)) => self.rule = Some(Rule::parse(s)?),
+            ("width", Value::Int(n)) if *n >= 0 => self.width = Some(*n as usize),
+            ("height", Value::Int(n)) if *n >= 0 => self.height = Some(*n as usize),
+            ("generations", Value::Int(n)) if *n >= 0 => self.generations = Some(*n as u64),
+            ("delay", Value::Int(n)) if *n >= 0 => self.delay = Some(*n as u64),
+            ("fps", Value::Int(n)) if (1..=i64::from(u32::MAX)).contains(n) => {
+                self.fps = Some(*n as u32)
+            }
+            ("every", Value::Int(n)) if *n >= 1 => self.every = Some(*n as u64),
+            ("until", Value::Str(s)) => self.until = Some(Until::parse(s)?),
+            ("screensaver", Value::Bool(b)) => self.screensaver = Some(*b),
+            ("random", Value::Float(f)) if (0.0..=1.0).contains(f) => self.random = Some(*f),
+            ("random", Value::Int(n)) if (0..=1).contains(n) => self.random = Some(*n as f64),
+            ("seed", Value::Int(n)) if *n >= 0 => self.seed = Some(*n as u64),
+            ("output_format", Value::Str(s)) => self.output_format = Some(OutputFormat::parse(s)?),
+            ("theme", Value::Str(s)) => self.apply_theme(s)?,
+            (key, value) if key.starts_with("themes.") => {
+                let (name, setting) = key["themes.".len()..]
+                    .split_once('.')
+                    .ok_or_else(|| format!("'{}' is not inside a theme table", key))?;
+                let theme = self
+                    .themes
+                    .entry(name.to_string())
+                    .or_insert_with(|| theme::builtin(name).unwrap_or(theme::DEFAULT));
+                set_theme(theme, setting, value)?;
+            }
+            ("glyphs.live", Value::Str(s)) => self.style.live = s.clone(),
+            ("glyphs.dead", Value::Str(s)) => self.style.dead = s.clone(),
+            ("colors.live", v) | ("colors.dead", v) => {
+                let color = match v {
+                    Value::Str(s) => Color::parse(s)?,
+                    Value::Int(n) if (0..=255).contains(n) => Color::Indexed(*n as u8),
+                    _ => return Err(wrong_type("a color name, 0-255 or \"#rrggbb\"")),
+                };
+                if key == "colors.live" {
+                    self.style.live_color = Some(color);
+                } else {
+                    self.style.dead_color = Some(color);
+                }
+            }
+            ("image.cell_size", Value::Int(n)) if *n >= 1 => self.image.cell_size = *n as usize,
+            ("image.margin", Value::Int(n)) if *n >= 0 => self.image.margin = *n as usize,
+            ("image.live", v) => self.image.live = parse_color(v)?,
+            ("image.dead", v) => self.image.dead = parse_color(v)?,
+            ("image.background", v) => self.image.background = parse_color(v)?,
+            ("image.grid", v) => self.image.grid = parse_color(v)?,
+            ("image.filter", Value::Str(s)) => self.image.filter = Filter::parse(s)?,
+            ("terminal.colors", Value::Str(s)) => self.terminal_colors = Depth::parse(s)?,
+            ("terminal.colors", Value::Int(n)) => {
+                self.terminal_colors = Depth::parse(&n.to_string())?
+            }
+            ("terminal.unicode", Value::Bool(b)) => self.terminal_unicode = Some(*b),
+            ("pattern_dirs", Value::Array(items)) => {
+                for item in items {
+                    match item {
+                        Value::Str(s) => self.pattern_dirs.push(expand_home(s)),
+                        _ => return Err(wrong_type("an array of strings")),
+                    }
+                }
+            }
+            ("renderer", _)
+            | ("rule", _)
+            | ("glyphs.live", _)
+            | ("glyphs.dead", _)
+            | ("until", _)
+            | ("theme", _)
+            | ("image.filter", _)
+            | ("output_format", _) => return Err(wrong_type("a string")),
+            ("width", _) | ("height", _) | ("generations", _) | ("delay", _) | ("seed", _) => {
+                return Err(wrong_type("a non-negative integer"))
+            }
+            ("fps", _) | ("every", _) | ("image.cell_size", _) => {
+                return Err(wrong_type("a positive integer"))
+            }
+            ("image.margin", _) => return Err(wrong_type("a non-negative integer")),
+            ("screensaver", _) | ("terminal.unicode", _) => return Err(wrong_type("a boolean")),
+            ("terminal.colors", _) => return Err(wrong_type("a string")),
+            ("random", _) => return Err(wrong_type("a density between 0 and 1")),
+            ("pattern_dirs", _) => return Err(wrong_type("an array of strings")),
+            _ => return Err(format!("unknown setting '{}'", key)),
+        }
+        Ok(())
+    }
+
+    /// Looks a pattern file up as given, then in each pattern directory.
+    pub fn resolve_pattern(&self, path: &str) -> String {
+        if Path::new(path).exists() {
+            return path.to_string();
+        }
+        self.pattern_dirs
+            .iter()
+            .map(|dir| dir.join(path))
+            .find(|candidate| candidate.exists())
+            .map(|found| found.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.to_string())
+    }
+}
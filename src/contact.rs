@@ -0,0 +1,133 @@
+//! Contact sheets: every frame a run shows, tiled into one PNG in rows with
+//! its generation written under it, to document how a pattern evolves.
+
+use conway::{Cell, World};
+
+use crate::png;
+
+/// Pixels per cell, half of what the heat map uses so more frames fit.
+const CELL_SIZE: usize = 4;
+/// Pixels around and between the tiles.
+const GAP: usize = 6;
+/// Pixels per dot of the digits in the labels.
+const DOT: usize = 2;
+
+const BACKGROUND: [u8; 3] = [40, 40, 40];
+const DEAD: [u8; 3] = [0, 0, 0];
+const LIVE: [u8; 3] = [230, 230, 230];
+const LABEL: [u8; 3] = [170, 170, 170];
+
+/// The digits 0 to 9, three dots wide and five high, a row a byte with the
+/// leftmost dot in the highest of the three bits.
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+pub struct ContactSheet {
+    width: usize,
+    height: usize,
+    /// The generation and live cells, row-major, of each frame.
+    frames: Vec<(u64, Vec<bool>)>,
+    rule: String,
+}
+
+/// An RGB image being drawn on.
+struct Image {
+    width: usize,
+    rgb: Vec<u8>,
+}
+
+impl Image {
+    fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, color: [u8; 3]) {
+        for py in y..y + height {
+            for px in x..x + width {
+                let i = (py * self.width + px) * 3;
+                self.rgb[i..i + 3].copy_from_slice(&color);
+            }
+        }
+    }
+
+    /// Writes `n` with its top left corner at `x`, `y`.
+    fn number(&mut self, x: usize, y: usize, n: u64) {
+        for (i, digit) in n.to_string().bytes().enumerate() {
+            let glyph = DIGITS[usize::from(digit - b'0')];
+            let left = x + i * 4 * DOT;
+            for (row, bits) in glyph.iter().enumerate() {
+                for column in 0..3 {
+                    if bits & (0b100 >> column) != 0 {
+                        self.fill(left + column * DOT, y + row * DOT, DOT, DOT, LABEL);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl ContactSheet {
+    pub fn new(world: &World) -> ContactSheet {
+        ContactSheet {
+            width: world.width(),
+            height: world.height(),
+            frames: Vec::new(),
+            rule: world.rule().to_string(),
+        }
+    }
+
+    pub fn observe(&mut self, world: &World, generation: u64) {
+        let cells = (0..self.width * self.height)
+            .map(|i| world.get(i % self.width, i / self.width) == Cell::Live)
+            .collect();
+        self.frames.push((generation, cells));
+    }
+
+    pub fn write_png(&self, path: &str) -> Result<(), String> {
+        let n = self.frames.len().max(1);
+        // as square a grid as the frames fill
+        let mut columns = 1;
+        while columns * columns < n {
+            columns += 1;
+        }
+        let rows = n.div_ceil(columns);
+        let (tile_width, tile_height) = (self.width * CELL_SIZE, self.height * CELL_SIZE);
+        let label = 5 * DOT + GAP / 2;
+        let width = GAP + columns * (tile_width + GAP);
+        let height = GAP + rows * (tile_height + label + GAP);
+        let mut image = Image {
+            width,
+            rgb: BACKGROUND.repeat(width * height),
+        };
+        for (i, (generation, cells)) in self.frames.iter().enumerate() {
+            let x = GAP + i % columns * (tile_width + GAP);
+            let y = GAP + i / columns * (tile_height + label + GAP);
+            image.fill(x, y, tile_width, tile_height, DEAD);
+            for (c, &live) in cells.iter().enumerate() {
+                if live {
+                    let (cx, cy) = (c % self.width, c / self.width);
+                    image.fill(
+                        x + cx * CELL_SIZE,
+                        y + cy * CELL_SIZE,
+                        CELL_SIZE,
+                        CELL_SIZE,
+                        LIVE,
+                    );
+                }
+            }
+            image.number(x, y + tile_height + GAP / 2, *generation);
+        }
+        let comment = format!(
+            "contact sheet of {} frames, rule {}",
+            self.frames.len(),
+            self.rule
+        );
+        png::write(path, width, height, &image.rgb, &comment)
+    }
+}
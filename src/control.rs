@@ -0,0 +1,269 @@
+//! `run --control-fifo PATH`: a named pipe the run takes line commands from,
+//! so scripts and key bindings can drive a run they didn't start:
+//!
+//! ```text
+//! pause                   stop stepping, still taking commands
+//! resume                  carry on
+//! speed N                 N frames a second from now on, as --fps
+//! set X Y [live|dead]     set a cell, live if not said
+//! zoom fit|N|NxM          show N by M cells a character, or fit the terminal
+//! dump FILE               save the world to FILE
+//! show GEN                show the world as it was at generation GEN
+//! ```
+//!
+//! For example `echo pause > /tmp/life`. A command that makes no sense is
+//! logged and the run goes on.
+//!
+//! `show` scrubs back through the run while it is paused, from a
+//! `Timeline` of it recorded as it goes; the run carries on from where it
+//! was when resumed.
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use conway::timeline::Timeline;
+use conway::{Cell, Pattern, World};
+
+use crate::interrupt;
+use crate::log;
+use crate::pace::Pacer;
+use crate::record::Recorders;
+use crate::term::Zoom;
+
+/// How often a paused run looks for Ctrl-C and snapshot requests.
+const POLL: Duration = Duration::from_millis(250);
+
+pub struct Control {
+    path: String,
+    /// Whether the run made the pipe, and so removes it at the end.
+    created: bool,
+    lines: Receiver<String>,
+    paused: bool,
+    /// A zoom asked for and not yet passed on to the renderers.
+    zoom: Option<Zoom>,
+    /// The run's past for `show`, once a generation has been recorded, and
+    /// a past generation asked for and not yet drawn.
+    timeline: Option<Timeline>,
+    shown: Option<(u64, World)>,
+}
+
+impl Control {
+    /// Opens the pipe at `path`, making it first if there is nothing there.
+    pub fn open(path: &str) -> Result<Control, String> {
+        let created = match fs::metadata(path) {
+            Ok(meta) if is_fifo(&meta) => false,
+            Ok(_) => return Err(format!("{} is not a named pipe", path)),
+            Err(_) => {
+                mkfifo(path)?;
+                true
+            }
+        };
+        let (send, lines) = mpsc::channel();
+        let reading = path.to_string();
+        thread::spawn(move || loop {
+            // opening waits for a writer and reading ends with the last one,
+            // so open it again for whoever writes next
+            let file = match File::open(&reading) {
+                Ok(file) => file,
+                Err(e) => {
+                    log::warn("control", &format!("cannot read {}: {}", reading, e));
+                    return;
+                }
+            };
+            for line in BufReader::new(file).lines().map_while(Result::ok) {
+                if send.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(Control {
+            path: path.to_string(),
+            created,
+            lines,
+            paused: false,
+            zoom: None,
+            timeline: None,
+            shown: None,
+        })
+    }
+
+    /// Carries out the commands written since the last call, at `generation`.
+    /// While paused it keeps waiting for more until `resume` or Ctrl-C,
+    /// calling `redraw` after each edit so the change shows, and it calls it
+    /// with the new zoom whenever one is asked for, and with a past world and
+    /// its generation for `show`.
+    pub fn obey(
+        &mut self,
+        world: &mut World,
+        pacer: &mut Pacer,
+        recorders: &mut Recorders,
+        generation: u64,
+        mut redraw: impl FnMut(&World, u64, Option<Zoom>) -> Result<(), String>,
+    ) -> Result<(), String> {
+        loop {
+            let line = if self.paused {
+                match self.lines.recv_timeout(POLL) {
+                    Ok(line) => line,
+                    Err(RecvTimeoutError::Timeout) if interrupt::requested() => return Ok(()),
+                    Err(RecvTimeoutError::Timeout) => {
+                        if interrupt::snapshot_requested() {
+                            let (rle, png) = interrupt::snapshot(world, generation)?;
+                            log::info("run", &format!("saved a snapshot to {} and {}", rle, png));
+                        }
+                        continue;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => return Ok(()),
+                }
+            } else {
+                match self.lines.try_recv() {
+                    Ok(line) => line,
+                    Err(_) => return Ok(()),
+                }
+            };
+            match self.command(&line, world, pacer, recorders) {
+                Ok(_) if self.shown.is_some() => {
+                    if let Some((past, shown)) = self.shown.take() {
+                        redraw(&shown, past, self.zoom.take())?;
+                    }
+                }
+                Ok(_) if self.zoom.is_some() => redraw(world, generation, self.zoom.take())?,
+                Ok(true) => {
+                    // stepping what came before wouldn't make the edit again
+                    if let Some(timeline) = self.timeline.as_mut() {
+                        timeline.keep(world, generation);
+                    }
+                    if self.paused {
+                        redraw(world, generation, None)?;
+                    }
+                }
+                Ok(false) => {}
+                Err(e) => log::warn("control", &format!("{}: {}", self.path, e)),
+            }
+        }
+    }
+
+    /// Adds the world at `generation` to the past `show` looks back on,
+    /// `edited` if it was changed by more than stepping, starting it over
+    /// if the run went back.
+    pub fn record(&mut self, world: &World, generation: u64, edited: bool) {
+        match self.timeline.as_mut() {
+            Some(timeline) if edited => timeline.keep(world, generation),
+            Some(timeline) => timeline.record(world, generation),
+            None => self.timeline = Some(Timeline::new(world, generation)),
+        }
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Starts out paused, as a run that was left paused carries on.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Carries out one line, saying whether it changed the world.
+    fn command(
+        &mut self,
+        line: &str,
+        world: &mut World,
+        pacer: &mut Pacer,
+        recorders: &mut Recorders,
+    ) -> Result<bool, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let invalid = || format!("invalid command '{}'", line.trim());
+        match words.as_slice() {
+            ["pause"] => self.paused = true,
+            ["resume"] => self.paused = false,
+            ["speed", fps] => match fps.parse::<u32>() {
+                Ok(fps) if fps > 0 => pacer.set_fps(fps),
+                _ => return Err(format!("expected frames a second, not '{}'", fps)),
+            },
+            ["set", x, y, state @ ..] => {
+                let cell = match state {
+                    [] | ["live"] => Cell::Live,
+                    ["dead"] => Cell::Dead,
+                    _ => return Err(invalid()),
+                };
+                let (x, y) = match (x.parse::<usize>(), y.parse::<usize>()) {
+                    (Ok(x), Ok(y)) => (x, y),
+                    _ => return Err(format!("no cell {},{} in the world", x, y)),
+                };
+                if world.try_get(x, y)? != cell {
+                    world.try_set(x, y, cell)?;
+                    recorders.edit(x, y, cell);
+                    return Ok(true);
+                }
+            }
+            ["zoom", zoom] => self.zoom = Some(Zoom::parse(zoom)?),
+            ["show", past] => {
+                let past = past
+                    .parse::<u64>()
+                    .map_err(|_| format!("expected a generation, not '{}'", past))?;
+                let timeline = self.timeline.as_ref().ok_or("nothing recorded yet")?;
+                let world = timeline.at(past).ok_or_else(|| {
+                    let (first, last) = timeline.range();
+                    format!("generation {} is not between {} and {}", past, first, last)
+                })?;
+                self.shown = Some((past, world));
+            }
+            ["dump", path] => {
+                Pattern::from_world(world).save(path)?;
+                log::info("control", &format!("saved the world to {}", path));
+            }
+            [] => {}
+            _ => return Err(invalid()),
+        }
+        Ok(false)
+    }
+}
+
+impl Drop for Control {
+    fn drop(&mut self) {
+        if self.created {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_fifo(meta: &fs::Metadata) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    meta.file_type().is_fifo()
+}
+
+#[cfg(not(unix))]
+fn is_fifo(_: &fs::Metadata) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn mkfifo(path: &str) -> Result<(), String> {
+    use std::ffi::CString;
+    use std::io;
+    use std::os::raw::{c_char, c_int};
+
+    #[cfg(target_os = "macos")]
+    type Mode = u16;
+    #[cfg(not(target_os = "macos"))]
+    type Mode = u32;
+
+    extern "C" {
+        fn mkfifo(path: *const c_char, mode: Mode) -> c_int;
+    }
+
+    let c_path = CString::new(path).map_err(|_| format!("invalid path {}", path))?;
+    if unsafe { mkfifo(c_path.as_ptr(), 0o600) } != 0 {
+        let e = io::Error::last_os_error();
+        return Err(format!("cannot make the named pipe {}: {}", path, e));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn mkfifo(path: &str) -> Result<(), String> {
+    Err(format!("cannot make the named pipe {}: only on Unix", path))
+}
@@ -0,0 +1,494 @@
+//! Just enough of D-Bus for `serve --dbus`: connecting to the session bus,
+//! owning a name on it, and taking method calls whose arguments are plain
+//! numbers, booleans and strings, answered in kind.
+
+use std::convert::TryInto;
+use std::env;
+use std::io::{self, Read, Write};
+use std::os::unix::net::UnixStream;
+
+const METHOD_CALL: u8 = 1;
+const METHOD_RETURN: u8 = 2;
+const ERROR: u8 = 3;
+
+/// The caller wants no answer.
+const NO_REPLY_EXPECTED: u8 = 0x1;
+
+const PATH: u8 = 1;
+const INTERFACE: u8 = 2;
+const MEMBER: u8 = 3;
+const ERROR_NAME: u8 = 4;
+const REPLY_SERIAL: u8 = 5;
+const DESTINATION: u8 = 6;
+const SENDER: u8 = 7;
+const SIGNATURE: u8 = 8;
+
+/// Longest message the specification allows.
+const MAX_MESSAGE: usize = 1 << 27;
+
+pub const UNKNOWN_METHOD: &str = "org.freedesktop.DBus.Error.UnknownMethod";
+pub const UNKNOWN_OBJECT: &str = "org.freedesktop.DBus.Error.UnknownObject";
+pub const INVALID_ARGS: &str = "org.freedesktop.DBus.Error.InvalidArgs";
+pub const FAILED: &str = "org.freedesktop.DBus.Error.Failed";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Byte(u8),
+    Bool(bool),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    Str(String),
+    Path(String),
+    Signature(String),
+}
+
+impl Value {
+    fn code(&self) -> char {
+        match self {
+            Value::Byte(_) => 'y',
+            Value::Bool(_) => 'b',
+            Value::I32(_) => 'i',
+            Value::U32(_) => 'u',
+            Value::I64(_) => 'x',
+            Value::U64(_) => 't',
+            Value::Str(_) => 's',
+            Value::Path(_) => 'o',
+            Value::Signature(_) => 'g',
+        }
+    }
+}
+
+/// A message off the bus, with the header fields that matter here.
+#[derive(Debug, Default)]
+pub struct Message {
+    kind: u8,
+    flags: u8,
+    serial: u32,
+    pub path: Option<String>,
+    pub interface: Option<String>,
+    pub member: Option<String>,
+    pub error_name: Option<String>,
+    reply_serial: Option<u32>,
+    pub sender: Option<String>,
+    /// The arguments, or `None` if they are of a type not read here.
+    pub body: Option<Vec<Value>>,
+}
+
+impl Message {
+    pub fn is_call(&self) -> bool {
+        self.kind == METHOD_CALL
+    }
+
+    pub fn wants_reply(&self) -> bool {
+        self.flags & NO_REPLY_EXPECTED == 0
+    }
+}
+
+/// Marshals values in little-endian order, aligned from the start of the
+/// message.
+#[derive(Default)]
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn align(&mut self, n: usize) {
+        while !self.0.len().is_multiple_of(n) {
+            self.0.push(0);
+        }
+    }
+
+    fn u32(&mut self, n: u32) {
+        self.align(4);
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn u64(&mut self, n: u64) {
+        self.align(8);
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn string(&mut self, s: &str) {
+        self.u32(s.len() as u32);
+        self.0.extend_from_slice(s.as_bytes());
+        self.0.push(0);
+    }
+
+    fn signature(&mut self, s: &str) {
+        self.0.push(s.len() as u8);
+        self.0.extend_from_slice(s.as_bytes());
+        self.0.push(0);
+    }
+
+    fn value(&mut self, value: &Value) {
+        match value {
+            Value::Byte(b) => self.0.push(*b),
+            Value::Bool(b) => self.u32(u32::from(*b)),
+            Value::I32(n) => self.u32(*n as u32),
+            Value::U32(n) => self.u32(*n),
+            Value::I64(n) => self.u64(*n as u64),
+            Value::U64(n) => self.u64(*n),
+            Value::Str(s) | Value::Path(s) => self.string(s),
+            Value::Signature(s) => self.signature(s),
+        }
+    }
+}
+
+/// A whole message: the header, its fields and the body.
+fn marshal(kind: u8, flags: u8, serial: u32, fields: &[(u8, Value)], body: &[Value]) -> Vec<u8> {
+    let mut payload = Writer::default();
+    for value in body {
+        payload.value(value);
+    }
+    let mut fields = fields.to_vec();
+    if !body.is_empty() {
+        let signature: String = body.iter().map(Value::code).collect();
+        fields.push((SIGNATURE, Value::Signature(signature)));
+    }
+
+    let mut out = Writer(vec![b'l', kind, flags, 1]);
+    out.u32(payload.0.len() as u32);
+    out.u32(serial);
+    // the fields' length goes in once they are written
+    out.u32(0);
+    let start = out.0.len();
+    for (code, value) in &fields {
+        out.align(8);
+        out.0.push(*code);
+        out.signature(&value.code().to_string());
+        out.value(value);
+    }
+    let length = (out.0.len() - start) as u32;
+    out.0[12..16].copy_from_slice(&length.to_le_bytes());
+    out.align(8);
+    out.0.extend_from_slice(&payload.0);
+    out.0
+}
+
+/// Reads values back out of a message in either byte order.
+struct Reader<'a> {
+    data: &'a [u8],
+    at: usize,
+    big: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.at..self.at.checked_add(n)?)?;
+        self.at += n;
+        Some(bytes)
+    }
+
+    fn align(&mut self, n: usize) -> Option<()> {
+        let padding = (n - self.at % n) % n;
+        self.take(padding).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.align(4)?;
+        let b: [u8; 4] = self.take(4)?.try_into().ok()?;
+        Some(if self.big {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        })
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.align(8)?;
+        let b: [u8; 8] = self.take(8)?.try_into().ok()?;
+        Some(if self.big {
+            u64::from_be_bytes(b)
+        } else {
+            u64::from_le_bytes(b)
+        })
+    }
+
+    fn text(&mut self, length: usize) -> Option<String> {
+        let bytes = self.take(length)?;
+        self.take(1)?;
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+
+    /// One value of the type `code` stands for, if it is a basic one.
+    fn value(&mut self, code: char) -> Option<Value> {
+        Some(match code {
+            'y' => Value::Byte(self.u8()?),
+            'b' => Value::Bool(self.u32()? != 0),
+            'i' => Value::I32(self.u32()? as i32),
+            // a file descriptor's index, which is a number too
+            'u' | 'h' => Value::U32(self.u32()?),
+            'x' => Value::I64(self.u64()? as i64),
+            't' => Value::U64(self.u64()?),
+            's' => Value::Str(self.string()?),
+            'o' => Value::Path(self.string()?),
+            'g' => Value::Signature(self.signature()?),
+            _ => return None,
+        })
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let length = self.u32()? as usize;
+        self.text(length)
+    }
+
+    fn signature(&mut self) -> Option<String> {
+        let length = usize::from(self.u8()?);
+        self.text(length)
+    }
+}
+
+/// A connection to the session bus.
+pub struct Connection {
+    stream: UnixStream,
+    serial: u32,
+}
+
+/// Where the session bus is: the first `unix:` address in
+/// `DBUS_SESSION_BUS_ADDRESS`, or the usual socket in `XDG_RUNTIME_DIR`.
+fn connect() -> Result<UnixStream, String> {
+    let address = match env::var("DBUS_SESSION_BUS_ADDRESS") {
+        Ok(address) => address,
+        Err(_) => match env::var("XDG_RUNTIME_DIR") {
+            Ok(dir) => format!("unix:path={}/bus", dir),
+            Err(_) => return Err("no session bus, DBUS_SESSION_BUS_ADDRESS is not set".into()),
+        },
+    };
+    for entry in address.split(';') {
+        let params = match entry.strip_prefix("unix:") {
+            Some(params) => params,
+            None => continue,
+        };
+        for param in params.split(',') {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            let value = unescape(value);
+            let stream = match key {
+                "path" => UnixStream::connect(&value),
+                #[cfg(target_os = "linux")]
+                "abstract" => {
+                    use std::os::linux::net::SocketAddrExt;
+                    use std::os::unix::net::SocketAddr;
+                    SocketAddr::from_abstract_name(value.as_bytes())
+                        .and_then(|addr| UnixStream::connect_addr(&addr))
+                }
+                _ => continue,
+            };
+            return stream.map_err(|e| format!("cannot connect to the session bus: {}", e));
+        }
+    }
+    Err(format!(
+        "no session bus address conway can use in '{}'",
+        address
+    ))
+}
+
+/// A value from a bus address with its `%XX` escapes undone.
+fn unescape(value: &str) -> String {
+    let mut bytes = Vec::new();
+    let mut rest = value.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        let escaped = tail
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (b, escaped) {
+            (b'%', Some(decoded)) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(b);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Says who we are with the EXTERNAL mechanism, the user id we run as.
+fn authenticate(stream: &mut UnixStream) -> io::Result<bool> {
+    extern "C" {
+        fn getuid() -> u32;
+    }
+    let uid = unsafe { getuid() }.to_string();
+    let hex: String = uid.bytes().map(|b| format!("{:02x}", b)).collect();
+    stream.write_all(format!("\0AUTH EXTERNAL {}\r\n", hex).as_bytes())?;
+    // a byte at a time, so nothing after the line is read too early
+    let mut line = Vec::new();
+    let mut byte = [0];
+    while !line.ends_with(b"\r\n") {
+        stream.read_exact(&mut byte)?;
+        line.push(byte[0]);
+    }
+    if !line.starts_with(b"OK ") {
+        return Ok(false);
+    }
+    stream.write_all(b"BEGIN\r\n")?;
+    Ok(true)
+}
+
+impl Connection {
+    /// Connects to the session bus and says hello, the first thing the bus
+    /// expects.
+    pub fn session() -> Result<Connection, String> {
+        let mut stream = connect()?;
+        let failed = |e: io::Error| format!("cannot talk to the session bus: {}", e);
+        if !authenticate(&mut stream).map_err(failed)? {
+            return Err("the session bus turned us away".to_string());
+        }
+        let mut connection = Connection { stream, serial: 0 };
+        connection.bus("Hello", &[])?;
+        Ok(connection)
+    }
+
+    /// Takes `name` for our own, failing if someone else has it.
+    pub fn request_name(&mut self, name: &str) -> Result<(), String> {
+        // flags: don't wait in line for it
+        let args = [Value::Str(name.to_string()), Value::U32(4)];
+        match self.bus("RequestName", &args)?.as_slice() {
+            // the primary owner now, or already
+            [Value::U32(1)] | [Value::U32(4)] => Ok(()),
+            _ => Err(format!("the bus name {} is taken", name)),
+        }
+    }
+
+    /// Calls `member` on the bus itself and waits for its answer.
+    fn bus(&mut self, member: &str, args: &[Value]) -> Result<Vec<Value>, String> {
+        let failed = |e: io::Error| format!("cannot talk to the session bus: {}", e);
+        let fields = [
+            (PATH, Value::Path("/org/freedesktop/DBus".to_string())),
+            (INTERFACE, Value::Str("org.freedesktop.DBus".to_string())),
+            (MEMBER, Value::Str(member.to_string())),
+            (DESTINATION, Value::Str("org.freedesktop.DBus".to_string())),
+        ];
+        let serial = self.send(METHOD_CALL, 0, &fields, args).map_err(failed)?;
+        loop {
+            // signals like NameAcquired may come first
+            let message = self.read().map_err(failed)?;
+            if message.reply_serial != Some(serial) {
+                continue;
+            }
+            return match message.kind {
+                ERROR => Err(format!(
+                    "the session bus refused {}: {}",
+                    member,
+                    message.error_name.unwrap_or_default()
+                )),
+                _ => Ok(message.body.unwrap_or_default()),
+            };
+        }
+    }
+
+    fn send(
+        &mut self,
+        kind: u8,
+        flags: u8,
+        fields: &[(u8, Value)],
+        body: &[Value],
+    ) -> io::Result<u32> {
+        self.serial += 1;
+        let bytes = marshal(kind, flags, self.serial, fields, body);
+        self.stream.write_all(&bytes)?;
+        Ok(self.serial)
+    }
+
+    /// Answers `call` with `body`.
+    pub fn reply(&mut self, call: &Message, body: &[Value]) -> io::Result<()> {
+        let mut fields = vec![(REPLY_SERIAL, Value::U32(call.serial))];
+        if let Some(sender) = &call.sender {
+            fields.push((DESTINATION, Value::Str(sender.clone())));
+        }
+        self.send(METHOD_RETURN, NO_REPLY_EXPECTED, &fields, body)
+            .map(|_| ())
+    }
+
+    /// Answers `call` with the error `name`, `message` saying what went
+    /// wrong.
+    pub fn error(&mut self, call: &Message, name: &str, message: &str) -> io::Result<()> {
+        let mut fields = vec![
+            (REPLY_SERIAL, Value::U32(call.serial)),
+            (ERROR_NAME, Value::Str(name.to_string())),
+        ];
+        if let Some(sender) = &call.sender {
+            fields.push((DESTINATION, Value::Str(sender.clone())));
+        }
+        let body = [Value::Str(message.to_string())];
+        self.send(ERROR, NO_REPLY_EXPECTED, &fields, &body)
+            .map(|_| ())
+    }
+
+    /// Waits for the next message.
+    pub fn read(&mut self) -> io::Result<Message> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed D-Bus message");
+        let mut fixed = [0; 16];
+        self.stream.read_exact(&mut fixed)?;
+        let big = match fixed[0] {
+            b'l' => false,
+            b'B' => true,
+            _ => return Err(invalid()),
+        };
+        let number = |b: &[u8]| {
+            let b = [b[0], b[1], b[2], b[3]];
+            let n = if big {
+                u32::from_be_bytes(b)
+            } else {
+                u32::from_le_bytes(b)
+            };
+            n as usize
+        };
+        let (body_length, fields_length) = (number(&fixed[4..8]), number(&fixed[12..16]));
+        let body_start = (16 + fields_length).next_multiple_of(8);
+        let total = body_start + body_length;
+        if total > MAX_MESSAGE {
+            return Err(invalid());
+        }
+        let mut data = fixed.to_vec();
+        data.resize(total, 0);
+        self.stream.read_exact(&mut data[16..])?;
+
+        let mut message = Message {
+            kind: fixed[1],
+            flags: fixed[2],
+            serial: number(&fixed[8..12]) as u32,
+            ..Message::default()
+        };
+        let mut reader = Reader {
+            data: &data[..body_start],
+            at: 16,
+            big,
+        };
+        let mut signature = String::new();
+        while reader.at < 16 + fields_length {
+            reader.align(8).ok_or_else(invalid)?;
+            let code = reader.u8().ok_or_else(invalid)?;
+            let kind = reader.signature().ok_or_else(invalid)?;
+            let mut chars = kind.chars();
+            let value = match (chars.next(), chars.next()) {
+                (Some(c), None) => reader.value(c).ok_or_else(invalid)?,
+                // fields of other types are for others to read
+                _ => return Err(invalid()),
+            };
+            match (code, value) {
+                (PATH, Value::Path(s)) => message.path = Some(s),
+                (INTERFACE, Value::Str(s)) => message.interface = Some(s),
+                (MEMBER, Value::Str(s)) => message.member = Some(s),
+                (ERROR_NAME, Value::Str(s)) => message.error_name = Some(s),
+                (REPLY_SERIAL, Value::U32(n)) => message.reply_serial = Some(n),
+                (SENDER, Value::Str(s)) => message.sender = Some(s),
+                (SIGNATURE, Value::Signature(s)) => signature = s,
+                _ => {}
+            }
+        }
+        let mut reader = Reader {
+            data: &data,
+            at: body_start,
+            big,
+        };
+        message.body = signature.chars().map(|c| reader.value(c)).collect();
+        Ok(message)
+    }
+}
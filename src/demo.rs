@@ -0,0 +1,110 @@
+//! An attract mode: every built-in pattern in turn, sized to the terminal
+//! with a caption under it, round and round until Ctrl-C.
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use conway::{library, World};
+
+use crate::interrupt;
+use crate::pace::Pacer;
+use crate::term;
+
+/// What the caption says about each built-in pattern. One missing here is
+/// captioned with just its name.
+const CAPTIONS: [(&str, &str); 8] = [
+    (
+        "glider",
+        "the smallest spaceship, one cell diagonally every 4 generations",
+    ),
+    (
+        "lwss",
+        "a fleet of lightweight spaceships, one cell every 2 generations",
+    ),
+    (
+        "gosper-gun",
+        "the first pattern found to grow forever, a glider every 30",
+    ),
+    ("pulsar", "the most common oscillator of period 3"),
+    ("pentadecathlon", "an oscillator of period 15"),
+    (
+        "r-pentomino",
+        "five cells that take 1103 generations to settle",
+    ),
+    ("acorn", "seven cells that take 5206 generations to settle"),
+    ("diehard", "seven cells that vanish after 130 generations"),
+];
+/// Lightweight spaceships flown together, so they read as a fleet.
+const FLEET: usize = 4;
+
+/// Shows each pattern for `duration`, skipping those too big for the
+/// terminal.
+pub fn run(mut pacer: Pacer, duration: Duration) -> Result<(), String> {
+    loop {
+        let mut shown = false;
+        for name in library::names() {
+            // a line is left for the caption, and the size re-queried every
+            // pattern so a resized window is picked up
+            let (cols, rows) = term::size().unwrap_or((80, 24));
+            let mut world = World::new(cols, rows.saturating_sub(1).max(1))?;
+            if !place(&mut world, name) {
+                continue;
+            }
+            shown = true;
+            let caption = match CAPTIONS.iter().find(|&&(n, _)| n == name) {
+                Some((_, text)) => format!("{}: {}", name, text),
+                None => name.to_string(),
+            };
+            let caption: String = caption.chars().take(cols).collect();
+            let mut tmp = world.clone();
+            print!("{}", term::CLEAR);
+            let started = Instant::now();
+            while started.elapsed() < duration {
+                if interrupt::requested() {
+                    return Ok(());
+                }
+                if interrupt::resized() {
+                    // on to the next pattern, laid out for the new terminal
+                    break;
+                }
+                term::draw_in_place(&world, None).map_err(|e| e.to_string())?;
+                print!("\r\n{:<width$}", caption, width = cols);
+                io::stdout().flush().map_err(|e| e.to_string())?;
+                pacer.wait();
+                world.advance(&mut tmp);
+            }
+        }
+        if !shown {
+            return Err("the terminal is too small for any built-in pattern".to_string());
+        }
+    }
+}
+
+/// Puts the pattern `name` in the middle of `world`, a fleet of it if it is
+/// a spaceship, or says it does not fit.
+fn place(world: &mut World, name: &str) -> bool {
+    let pattern = match library::get(name) {
+        Some(pattern) => pattern,
+        None => return false,
+    };
+    // every other ship of a fleet a little behind, and as many ships as fit
+    let (stagger, spacing) = (pattern.width() / 2, pattern.height() + 3);
+    let height = |copies: usize| pattern.height() + (copies - 1) * spacing;
+    let mut copies = if name == "lwss" { FLEET } else { 1 };
+    while copies > 1 && height(copies) > world.height() {
+        copies -= 1;
+    }
+    let width = pattern.width() + if copies > 1 { stagger } else { 0 };
+    let height = height(copies);
+    if width > world.width() || height > world.height() {
+        return false;
+    }
+    let (x, y) = ((world.width() - width) / 2, (world.height() - height) / 2);
+    for copy in 0..copies {
+        world.stamp(&pattern, x + copy % 2 * stagger, y + copy * spacing);
+    }
+    if let Some(rule) = pattern.rule() {
+        world.set_rule(rule);
+    }
+    true
+}
@@ -0,0 +1,97 @@
+//! `conway diff`: the cells two patterns disagree on, drawn as a map, with
+//! the exit status telling scripts and tests whether they are the same.
+
+use std::collections::HashSet;
+use std::process;
+
+use conway::Pattern;
+
+use crate::cli::DiffOptions;
+use crate::memory;
+
+/// The live cells of `pattern`, moved by `offset`.
+fn cells(pattern: &Pattern, (dx, dy): (i64, i64)) -> HashSet<(i64, i64)> {
+    (0..pattern.height())
+        .flat_map(|y| (0..pattern.width()).map(move |x| (x, y)))
+        .filter(|&(x, y)| pattern.get(x, y))
+        .map(|(x, y)| (x as i64 + dx, y as i64 + dy))
+        .collect()
+}
+
+/// The smallest rectangle holding the cells of both, with each row as
+/// `.` where neither has a cell, `O` where both do, `-` where only the
+/// first does and `+` where only the second does.
+fn map(a: &HashSet<(i64, i64)>, b: &HashSet<(i64, i64)>) -> String {
+    let (mut x0, mut y0, mut x1, mut y1) = (i64::MAX, i64::MAX, i64::MIN, i64::MIN);
+    for &(x, y) in a.union(b) {
+        x0 = x0.min(x);
+        y0 = y0.min(y);
+        x1 = x1.max(x);
+        y1 = y1.max(y);
+    }
+    let mut s = String::new();
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            s.push(match (a.contains(&(x, y)), b.contains(&(x, y))) {
+                (true, true) => 'O',
+                (true, false) => '-',
+                (false, true) => '+',
+                (false, false) => '.',
+            });
+        }
+        s.push('\n');
+    }
+    s
+}
+
+/// Exit status when the patterns differ, as for diff(1), with errors
+/// leaving with 2 instead.
+const DIFFER_STATUS: i32 = 1;
+
+/// Compares the patterns, lined up by the top left corners of their live
+/// cells unless `--offset` says where the second goes, and exits with
+/// `DIFFER_STATUS` if they differ in any cell or in their rule.
+pub fn run(opts: &DiffOptions) -> Result<(), String> {
+    let load = |path: &str| Pattern::load_within(path, &memory::fits(None));
+    let (a, b) = (load(&opts.first)?, load(&opts.second)?);
+    let (a, b, offset) = match opts.offset {
+        Some(offset) => (a, b, offset),
+        None => (a.crop(), b.crop(), (0, 0)),
+    };
+    let (cells_a, cells_b) = (cells(&a, (0, 0)), cells(&b, offset));
+    let (rule_a, rule_b) = (a.rule().unwrap_or_default(), b.rule().unwrap_or_default());
+
+    let only_a = cells_a.difference(&cells_b).count();
+    let only_b = cells_b.difference(&cells_a).count();
+    if only_a == 0 && only_b == 0 && rule_a == rule_b {
+        println!(
+            "{} and {} are the same, {} cells, {}",
+            opts.first,
+            opts.second,
+            cells_a.len(),
+            rule_a
+        );
+        return Ok(());
+    }
+    if rule_a != rule_b {
+        println!(
+            "rule {} in {}, {} in {}",
+            rule_a, opts.first, rule_b, opts.second
+        );
+    }
+    if only_a > 0 || only_b > 0 {
+        if !opts.brief {
+            print!("{}", map(&cells_a, &cells_b));
+        }
+        println!(
+            "{} cells only in {} (-), {} only in {} (+), {} in both (O)",
+            only_a,
+            opts.first,
+            only_b,
+            opts.second,
+            cells_a.len() - only_a
+        );
+    }
+    println!("{} and {} differ", opts.first, opts.second);
+    process::exit(DIFFER_STATUS);
+}
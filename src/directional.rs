@@ -0,0 +1,148 @@
+//! Directional rules, which care which of the eight nearest neighbors are
+//! alive and not only how many. They are B/S rules whose terms, apart by
+//! commas, can add neighbors that must be alive after `@` or dead after `!`,
+//! named by compass point: `B3,2@N/S23` is Life with a birth too wherever
+//! exactly two neighbors are alive and one of them is the one to the north.
+//! A term's digits are the counts it takes, any count when there are none,
+//! so `B3/S23,@N!S` lets any cell with its north neighbor alive and its
+//! south one dead survive.
+
+use std::fmt;
+
+use crate::Cell;
+
+/// The neighbors in the order of their bits in a neighbor mask, row by row
+/// from the top left.
+const DIRECTIONS: [&str; 8] = ["NW", "N", "NE", "W", "E", "SW", "S", "SE"];
+
+/// Every count of neighbors.
+const ANY: u16 = 0x1ff;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct Term {
+    /// Bit n set for n neighbors alive.
+    counts: u16,
+    /// The neighbors that must be alive, and those that must be dead.
+    alive: u8,
+    dead: u8,
+}
+
+impl Term {
+    fn parse(s: &str) -> Option<Term> {
+        let digits = s.find(['@', '!']).unwrap_or(s.len());
+        let mut counts = 0;
+        for c in s[..digits].chars() {
+            counts |= 1 << c.to_digit(10).filter(|&n| n <= 8)?;
+        }
+        let mut term = Term {
+            counts: if digits == 0 { ANY } else { counts },
+            alive: 0,
+            dead: 0,
+        };
+        let mut rest = &s[digits..];
+        while let Some(mark) = rest.chars().next() {
+            let end = rest[1..].find(['@', '!']).map_or(rest.len(), |i| i + 1);
+            let name = rest[1..end].to_ascii_uppercase();
+            let bit = 1 << DIRECTIONS.iter().position(|&d| d == name)?;
+            match mark {
+                '@' => term.alive |= bit,
+                _ => term.dead |= bit,
+            }
+            rest = &rest[end..];
+        }
+        Some(term)
+    }
+
+    fn matches(&self, mask: u8) -> bool {
+        self.counts & 1 << mask.count_ones() != 0
+            && mask & self.alive == self.alive
+            && mask & self.dead == 0
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.counts != ANY || (self.alive, self.dead) == (0, 0) {
+            for n in (0..9).filter(|n| self.counts & 1 << n != 0) {
+                write!(f, "{}", n)?;
+            }
+        }
+        for (bits, mark) in [(self.alive, '@'), (self.dead, '!')] {
+            for (i, name) in DIRECTIONS.iter().enumerate() {
+                if bits & 1 << i != 0 {
+                    write!(f, "{}{}", mark, name)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Directional {
+    birth: Vec<Term>,
+    survival: Vec<Term>,
+    /// Whether a cell lives next, by its neighbor mask, dead cells first.
+    table: Vec<bool>,
+}
+
+impl Directional {
+    /// Whether `s` is written as a directional rule, with neighbors named.
+    pub fn is(s: &str) -> bool {
+        s.contains(['@', '!'])
+    }
+
+    pub fn parse(s: &str) -> Result<Directional, String> {
+        let err = || {
+            format!(
+                "invalid directional rule '{}', expected B/S terms apart by commas such as \
+                 B3,2@N/S23, with neighbors N, NE, E, SE, S, SW, W or NW after @ to be \
+                 alive and ! to be dead",
+                s
+            )
+        };
+        let terms = |side: &str| -> Result<Vec<Term>, String> {
+            match side {
+                "" => Ok(Vec::new()),
+                _ => side
+                    .split(',')
+                    .map(|t| Term::parse(t).ok_or_else(err))
+                    .collect(),
+            }
+        };
+        let tagged = |part: &str, tag: char| part.starts_with([tag, tag.to_ascii_lowercase()]);
+        let (birth, survival) = match s.split_once('/') {
+            Some((b, s)) if tagged(b, 'B') && tagged(s, 'S') => (b, s),
+            Some((s, b)) if tagged(b, 'B') && tagged(s, 'S') => (b, s),
+            _ => return Err(err()),
+        };
+        let (birth, survival) = (terms(&birth[1..])?, terms(&survival[1..])?);
+        let table = [&birth, &survival]
+            .iter()
+            .flat_map(|terms| (0..=255).map(move |mask| terms.iter().any(|t| t.matches(mask))))
+            .collect();
+        Ok(Directional {
+            birth,
+            survival,
+            table,
+        })
+    }
+
+    /// The cell's next state with the neighbors in `mask` alive.
+    pub fn next(&self, cell: Cell, mask: u8) -> Cell {
+        match self.table[usize::from(cell.n()) * 256 + usize::from(mask)] {
+            true => Cell::Live,
+            false => Cell::Dead,
+        }
+    }
+}
+
+impl fmt::Display for Directional {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = |terms: &[Term]| -> String {
+            let terms: Vec<String> = terms.iter().map(Term::to_string).collect();
+            terms.join(",")
+        };
+        write!(f, "B{}/S{}", list(&self.birth), list(&self.survival))
+    }
+}
@@ -0,0 +1,94 @@
+//! How far apart two worlds of the same size are: the cells they disagree
+//! on, how many, and where, for checking engines against each other and for
+//! tests that wait for a world to settle into another.
+
+use crate::{Cell, Rect, World};
+
+/// Cells along each side of the regions `DiffReport::regions` counts in.
+pub const REGION: usize = 16;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffReport {
+    /// The size of both worlds.
+    pub width: usize,
+    pub height: usize,
+    /// Every cell the worlds disagree on, row by row from the top, with what
+    /// the first world has there.
+    pub changed: Vec<(usize, usize, Cell)>,
+}
+
+impl World {
+    /// The cells this world and `other` disagree on, if they are the same
+    /// size. Rules, ages and generation counts are not compared.
+    pub fn diff(&self, other: &World) -> Result<DiffReport, String> {
+        let (w, h) = (self.width(), self.height());
+        if (other.width(), other.height()) != (w, h) {
+            return Err(format!(
+                "cannot compare a {}x{} world with a {}x{} one",
+                w,
+                h,
+                other.width(),
+                other.height()
+            ));
+        }
+        let changed = (0..h)
+            .flat_map(|y| (0..w).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.get(x, y) != other.get(x, y))
+            .map(|(x, y)| (x, y, self.get(x, y)))
+            .collect();
+        Ok(DiffReport {
+            width: w,
+            height: h,
+            changed,
+        })
+    }
+}
+
+impl DiffReport {
+    /// The Hamming distance: how many cells differ.
+    pub fn distance(&self) -> usize {
+        self.changed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty()
+    }
+
+    /// The share of all cells that differ, from 0 to 1.
+    pub fn fraction(&self) -> f64 {
+        self.distance() as f64 / (self.width * self.height).max(1) as f64
+    }
+
+    /// Whether the cell at `x`,`y` is one that differs.
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        self.changed
+            .binary_search_by_key(&(y, x), |&(cx, cy, _)| (cy, cx))
+            .is_ok()
+    }
+
+    /// The `REGION` by `REGION` squares of the world, the last ones along
+    /// each edge smaller, that have any cells differing, with how many, from
+    /// the top left.
+    pub fn regions(&self) -> Vec<(Rect, usize)> {
+        let columns = self.width.div_ceil(REGION);
+        let mut counts = vec![0; columns * self.height.div_ceil(REGION)];
+        for &(x, y, _) in &self.changed {
+            counts[y / REGION * columns + x / REGION] += 1;
+        }
+        counts
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .map(|(i, &count)| {
+                let (x, y) = (i % columns * REGION, i / columns * REGION);
+                let rect = Rect {
+                    x,
+                    y,
+                    width: REGION.min(self.width - x),
+                    height: REGION.min(self.height - y),
+                };
+                (rect, count)
+            })
+            .collect()
+    }
+}
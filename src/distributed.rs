@@ -0,0 +1,396 @@
+//! One world run across several `conway worker` processes, each holding a
+//! strip of its rows.
+//!
+//! The coordinator hands out the strips and then, every generation, sends
+//! each worker the rows bordering its strip and gets back the strip's own
+//! first and last rows, so two rows per worker go each way however big the
+//! world is. Whole frames are only gathered when asked for.
+//!
+//! Messages are a tag byte and fixed fields, numbers big-endian and rows
+//! packed eight cells to a byte:
+//!
+//! - `I`: width and height (u32), the rule (u8 length and text), then the
+//!   strip's rows;
+//! - `S`: the rows above and below the strip, answered with `E`, the strip's
+//!   first and last rows and its population, births and deaths (u64);
+//! - `G`, answered with all of the strip's rows;
+//! - `Q`, which ends the session.
+
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use conway::{Cell, Changes, Pattern, Rng, Rule, Strip, World};
+
+use crate::cli::{DistributeOptions, WorkerOptions};
+use crate::log;
+use crate::stop;
+
+fn pack(row: &[Cell]) -> Vec<u8> {
+    let mut bytes = vec![0; row.len().div_ceil(8)];
+    for (x, &cell) in row.iter().enumerate() {
+        if cell == Cell::Live {
+            bytes[x / 8] |= 1 << (x % 8);
+        }
+    }
+    bytes
+}
+
+fn unpack(bytes: &[u8], width: usize) -> Vec<Cell> {
+    (0..width)
+        .map(|x| match bytes[x / 8] >> (x % 8) & 1 {
+            1 => Cell::Live,
+            _ => Cell::Dead,
+        })
+        .collect()
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Either end of a connection between the coordinator and a worker.
+struct Link {
+    reader: BufReader<TcpStream>,
+    writer: BufWriter<TcpStream>,
+}
+
+impl Link {
+    fn new(stream: TcpStream) -> io::Result<Link> {
+        // every message waits on its answer, so nothing is gained by batching
+        stream.set_nodelay(true)?;
+        Ok(Link {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: BufWriter::new(stream),
+        })
+    }
+
+    fn read_bytes(&mut self, n: usize) -> io::Result<Vec<u8>> {
+        let mut bytes = vec![0; n];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> io::Result<u8> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> io::Result<u32> {
+        let mut bytes = [0; 4];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(u32::from_be_bytes(bytes))
+    }
+
+    fn read_u64(&mut self) -> io::Result<u64> {
+        let mut bytes = [0; 8];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(u64::from_be_bytes(bytes))
+    }
+
+    fn read_row(&mut self, width: usize) -> io::Result<Vec<Cell>> {
+        let bytes = self.read_bytes(width.div_ceil(8))?;
+        Ok(unpack(&bytes, width))
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.writer.write_all(bytes)
+    }
+
+    fn write_row(&mut self, row: &[Cell]) -> io::Result<()> {
+        self.writer.write_all(&pack(row))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Answers one coordinator until it is done.
+fn session(link: &mut Link) -> io::Result<()> {
+    let mut strip: Option<Strip> = None;
+    loop {
+        let tag = link.read_u8()?;
+        if tag == b'Q' {
+            return Ok(());
+        }
+        if tag == b'I' {
+            let width = link.read_u32()? as usize;
+            let height = link.read_u32()? as usize;
+            let len = link.read_u8()? as usize;
+            let rule = String::from_utf8_lossy(&link.read_bytes(len)?).into_owned();
+            let rule = Rule::parse(&rule).map_err(invalid)?;
+            let mut new = Strip::new(width, height, rule).map_err(invalid)?;
+            for y in 0..height {
+                for (x, cell) in link.read_row(width)?.into_iter().enumerate() {
+                    new.set(x, y, cell);
+                }
+            }
+            strip = Some(new);
+            continue;
+        }
+        let strip = strip
+            .as_mut()
+            .ok_or_else(|| invalid("no strip was handed out".to_string()))?;
+        let width = strip.width();
+        match tag {
+            b'S' => {
+                let above = link.read_row(width)?;
+                let below = link.read_row(width)?;
+                let changes = strip.advance(&above, &below);
+                link.write(b"E")?;
+                link.write_row(strip.row(0))?;
+                link.write_row(strip.row(strip.height() - 1))?;
+                for n in &[strip.population(), changes.births, changes.deaths] {
+                    link.write(&(*n as u64).to_be_bytes())?;
+                }
+            }
+            b'G' => {
+                for y in 0..strip.height() {
+                    link.write_row(strip.row(y))?;
+                }
+            }
+            _ => return Err(invalid(format!("unknown message {:?}", tag as char))),
+        }
+        link.flush()?;
+    }
+}
+
+/// Serves coordinators one after the other until interrupted.
+pub fn work(opts: &WorkerOptions) -> Result<(), String> {
+    let listener = TcpListener::bind(&opts.listen)
+        .map_err(|e| format!("cannot listen on {}: {}", opts.listen, e))?;
+    log::info("worker", &format!("worker listening on {}", opts.listen));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                log::warn("worker", &format!("cannot accept a coordinator: {}", e));
+                continue;
+            }
+        };
+        let peer = stream
+            .peer_addr()
+            .map_or_else(|_| "?".to_string(), |a| a.to_string());
+        log::debug("worker", &format!("session with {} started", peer));
+        // a coordinator going away only ends its own session
+        match Link::new(stream).and_then(|mut link| session(&mut link)) {
+            Ok(()) => log::info("worker", &format!("session with {} done", peer)),
+            Err(e) => log::warn("worker", &format!("session with {} failed: {}", peer, e)),
+        }
+    }
+    Ok(())
+}
+
+/// Where the world's cells come from.
+pub enum Start {
+    /// A random soup, made a strip at a time so the whole world never has to
+    /// fit in the coordinator's memory.
+    Soup {
+        width: usize,
+        height: usize,
+        rule: Rule,
+        density: f64,
+        rng: Rng,
+    },
+    /// Boxed as a world is much bigger than a soup's settings.
+    World(Box<World>),
+}
+
+impl Start {
+    fn size(&self) -> (usize, usize) {
+        match self {
+            Start::Soup { width, height, .. } => (*width, *height),
+            Start::World(world) => (world.width(), world.height()),
+        }
+    }
+
+    fn rule(&self) -> Rule {
+        match self {
+            Start::Soup { rule, .. } => *rule,
+            Start::World(world) => world.rule(),
+        }
+    }
+
+    /// The strip of `height` rows from row `y`, asked for from top to bottom.
+    fn strip(&mut self, y: usize, height: usize) -> Result<Strip, String> {
+        match self {
+            Start::Soup {
+                width,
+                rule,
+                density,
+                rng,
+                ..
+            } => {
+                let mut strip = Strip::new(*width, height, *rule)?;
+                strip.randomize(*density, rng);
+                Ok(strip)
+            }
+            Start::World(world) => Strip::from_world(world, y, height),
+        }
+    }
+}
+
+/// A worker as the coordinator sees it, with the edge rows of its strip as
+/// of the latest generation.
+struct Remote {
+    addr: String,
+    link: Link,
+    height: usize,
+    first: Vec<Cell>,
+    last: Vec<Cell>,
+}
+
+impl Remote {
+    fn fail(&self, e: io::Error) -> String {
+        format!("worker {}: {}", self.addr, e)
+    }
+
+    fn init(&mut self, strip: &Strip) -> io::Result<()> {
+        let rule = strip.rule().to_string();
+        self.link.write(b"I")?;
+        self.link.write(&(strip.width() as u32).to_be_bytes())?;
+        self.link.write(&(strip.height() as u32).to_be_bytes())?;
+        self.link.write(&[rule.len() as u8])?;
+        self.link.write(rule.as_bytes())?;
+        for y in 0..strip.height() {
+            self.link.write_row(strip.row(y))?;
+        }
+        self.link.flush()
+    }
+
+    fn step(&mut self, above: &[Cell], below: &[Cell]) -> io::Result<()> {
+        self.link.write(b"S")?;
+        self.link.write_row(above)?;
+        self.link.write_row(below)?;
+        self.link.flush()
+    }
+
+    /// Reads the answer to `step`, returning the strip's population and what
+    /// changed.
+    fn stepped(&mut self, width: usize) -> io::Result<(usize, Changes)> {
+        if self.link.read_u8()? != b'E' {
+            return Err(invalid("unexpected answer to a step".to_string()));
+        }
+        self.first = self.link.read_row(width)?;
+        self.last = self.link.read_row(width)?;
+        let population = self.link.read_u64()? as usize;
+        let births = self.link.read_u64()? as usize;
+        let deaths = self.link.read_u64()? as usize;
+        Ok((population, Changes { births, deaths }))
+    }
+
+    fn gather(&mut self, width: usize) -> io::Result<Vec<Vec<Cell>>> {
+        self.link.write(b"G")?;
+        self.link.flush()?;
+        (0..self.height)
+            .map(|_| self.link.read_row(width))
+            .collect()
+    }
+}
+
+/// Every row of the world, top to bottom.
+fn gather(remotes: &mut [Remote], width: usize) -> Result<Vec<Vec<Cell>>, String> {
+    let mut rows = Vec::new();
+    for remote in remotes.iter_mut() {
+        rows.extend(remote.gather(width).map_err(|e| remote.fail(e))?);
+    }
+    Ok(rows)
+}
+
+/// Runs the world on the workers, reporting every `--every` generations.
+pub fn run(mut start: Start, opts: &DistributeOptions) -> Result<(), String> {
+    let (width, height) = start.size();
+    let rule = start.rule();
+    let n = opts.workers.len();
+    if n > height {
+        return Err(format!(
+            "{} workers cannot share the {} rows of the world",
+            n, height
+        ));
+    }
+    let mut remotes = Vec::with_capacity(n);
+    let mut population = 0;
+    let mut y = 0;
+    for (i, addr) in opts.workers.iter().enumerate() {
+        let stream = TcpStream::connect(addr)
+            .map_err(|e| format!("cannot connect to worker {}: {}", addr, e))?;
+        let link = Link::new(stream).map_err(|e| format!("worker {}: {}", addr, e))?;
+        // the first workers take one row more when the rows don't divide evenly
+        let rows = height / n + usize::from(i < height % n);
+        let strip = start.strip(y, rows)?;
+        let mut remote = Remote {
+            addr: addr.clone(),
+            link,
+            height: rows,
+            first: strip.row(0).to_vec(),
+            last: strip.row(rows - 1).to_vec(),
+        };
+        remote.init(&strip).map_err(|e| remote.fail(e))?;
+        log::debug(
+            "distribute",
+            &format!("worker {} holds rows {} to {}", addr, y, y + rows - 1),
+        );
+        population += strip.population();
+        remotes.push(remote);
+        y += rows;
+    }
+    drop(start);
+
+    let report = |remotes: &mut [Remote], generation, population, changes: Changes| {
+        if opts.show {
+            for row in gather(remotes, width)? {
+                let line: String = row
+                    .iter()
+                    .map(|&cell| if cell == Cell::Live { 'O' } else { '.' })
+                    .collect();
+                println!("{}", line);
+            }
+        }
+        println!(
+            "generation {}: population {}, +{} -{}",
+            generation, population, changes.births, changes.deaths
+        );
+        Ok::<(), String>(())
+    };
+    report(&mut remotes, 0, population, Changes::default())?;
+    for generation in 1..=opts.generations {
+        // all the workers step at once, then their answers are collected
+        for i in 0..n {
+            let above = remotes[(i + n - 1) % n].last.clone();
+            let below = remotes[(i + 1) % n].first.clone();
+            let remote = &mut remotes[i];
+            remote.step(&above, &below).map_err(|e| remote.fail(e))?;
+        }
+        population = 0;
+        let mut changes = Changes::default();
+        for remote in remotes.iter_mut() {
+            let (p, c) = remote.stepped(width).map_err(|e| remote.fail(e))?;
+            population += p;
+            changes.births += c.births;
+            changes.deaths += c.deaths;
+        }
+        let settled = !changes.any();
+        if generation % opts.every == 0 || generation == opts.generations || settled {
+            report(&mut remotes, generation, population, changes)?;
+        }
+        if settled {
+            println!("{}", stop::settled(population, generation - 1));
+            break;
+        }
+    }
+
+    if let Some(path) = &opts.output {
+        let mut pattern = Pattern::new(width, height);
+        for (y, row) in gather(&mut remotes, width)?.iter().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                pattern.set(x, y, cell == Cell::Live);
+            }
+        }
+        pattern.set_rule(Some(rule));
+        pattern.save(path)?;
+    }
+    for remote in &mut remotes {
+        // the run is over either way, so a worker already gone doesn't matter
+        let _ = remote.link.write(b"Q").and_then(|_| remote.link.flush());
+    }
+    Ok(())
+}
@@ -0,0 +1,244 @@
+//! A simulation stepped on a timer by a thread of its own, for async code to
+//! await its frames without blocking the executor it runs on. It needs
+//! nothing beyond std: `AsyncSimulation::poll_next` has the signature of
+//! `futures::Stream::poll_next`, so under tokio or any other runtime a
+//! one-line `impl Stream` makes it a stream, and `next_event` gives a future of
+//! the next event for code that only wants to `.await` in a loop:
+//!
+//! ```text
+//! use std::time::Duration;
+//! use conway::driver::{AsyncSimulation, Event};
+//! use conway::World;
+//!
+//! let mut sim = AsyncSimulation::new(World::new(64, 64).unwrap(), Duration::from_millis(50));
+//! while let Some(event) = sim.next_event().await {
+//!     match event {
+//!         Event::Frame { generation, .. } if generation == 1000 => sim.cancel(),
+//!         Event::Frame { .. } => {}
+//!         Event::Stopped { reason, .. } => println!("{}", reason),
+//!     }
+//! }
+//! ```
+//!
+//! Everything that steers it, pausing, stepping, the interval and
+//! cancelling, takes `&self` and returns at once, so it can be shared among
+//! the tasks of a server that all steer one run. A consumer that falls
+//! behind misses the oldest frames rather than holding the run up.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::{Changes, World};
+
+/// Frames kept for a consumer that has fallen behind.
+const BACKLOG: usize = 16;
+
+/// What the simulation sends.
+#[derive(Clone)]
+pub enum Event {
+    /// The world once a generation is done, and what it changed.
+    Frame {
+        generation: u64,
+        world: Box<World>,
+        changes: Changes,
+    },
+    /// The run is over, and no more events follow.
+    Stopped { generation: u64, reason: String },
+}
+
+struct State {
+    events: VecDeque<Event>,
+    /// The task waiting for the next event.
+    waker: Option<Waker>,
+    interval: Duration,
+    paused: bool,
+    /// Generations to step while paused.
+    steps: u64,
+    cancelled: bool,
+    /// Whether the `Stopped` event has been sent.
+    finished: bool,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    /// Wakes the stepping thread when it is steered.
+    steered: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // a consumer that panicked holding it leaves nothing half done
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn send(&self, event: Event) {
+        let mut state = self.lock();
+        if state.events.len() >= BACKLOG {
+            state.events.pop_front();
+        }
+        state.finished |= matches!(event, Event::Stopped { .. });
+        state.events.push_back(event);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// A run of a world that steps itself every `interval` until cancelled or
+/// settled, as a stream of events.
+pub struct AsyncSimulation {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl AsyncSimulation {
+    /// Starts stepping `world`, a generation every `interval`.
+    pub fn new(world: World, interval: Duration) -> AsyncSimulation {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                events: VecDeque::new(),
+                waker: None,
+                interval,
+                paused: false,
+                steps: 0,
+                cancelled: false,
+                finished: false,
+            }),
+            steered: Condvar::new(),
+        });
+        let stepper = Arc::clone(&shared);
+        let thread = thread::spawn(move || step(world, &stepper));
+        AsyncSimulation {
+            shared,
+            thread: Some(thread),
+        }
+    }
+
+    fn steer(&self, change: impl FnOnce(&mut State)) {
+        change(&mut self.shared.lock());
+        self.shared.steered.notify_all();
+    }
+
+    pub fn pause(&self) {
+        self.steer(|state| state.paused = true);
+    }
+
+    pub fn resume(&self) {
+        self.steer(|state| state.paused = false);
+    }
+
+    /// Steps one generation of a paused run.
+    pub fn step(&self) {
+        self.steer(|state| state.steps += 1);
+    }
+
+    pub fn set_interval(&self, interval: Duration) {
+        self.steer(|state| state.interval = interval);
+    }
+
+    /// Ends the run: the events already sent can still be taken, then a
+    /// `Stopped` and no more.
+    pub fn cancel(&self) {
+        self.steer(|state| state.cancelled = true);
+    }
+
+    /// The next event, or `None` once the run is over and every event has
+    /// been taken.
+    pub fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+        let mut state = self.shared.lock();
+        match state.events.pop_front() {
+            Some(event) => Poll::Ready(Some(event)),
+            None if state.finished => Poll::Ready(None),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    /// A future of the next event, as `poll_next` gives it.
+    pub fn next_event(&mut self) -> Next<'_> {
+        Next { sim: self }
+    }
+}
+
+/// The future `AsyncSimulation::next_event` returns.
+pub struct Next<'a> {
+    sim: &'a mut AsyncSimulation,
+}
+
+impl Future for Next<'_> {
+    type Output = Option<Event>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+        Pin::new(&mut *self.sim).poll_next(cx)
+    }
+}
+
+impl Drop for AsyncSimulation {
+    fn drop(&mut self) {
+        self.cancel();
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+/// The stepping thread: waits out each interval, or a command, then steps.
+fn step(mut world: World, shared: &Shared) {
+    let mut tmp = world.clone();
+    let mut due = Instant::now();
+    loop {
+        {
+            let mut state = shared.lock();
+            loop {
+                if state.cancelled {
+                    drop(state);
+                    shared.send(Event::Stopped {
+                        generation: world.generation(),
+                        reason: "cancelled".to_string(),
+                    });
+                    return;
+                }
+                if state.paused && state.steps > 0 {
+                    state.steps -= 1;
+                    break;
+                }
+                let now = Instant::now();
+                if !state.paused && now >= due {
+                    due = (due + state.interval).max(now);
+                    break;
+                }
+                let wait = if state.paused {
+                    Duration::from_secs(60)
+                } else {
+                    due - now
+                };
+                state = shared
+                    .steered
+                    .wait_timeout(state, wait)
+                    .unwrap_or_else(|e| e.into_inner())
+                    .0;
+            }
+        }
+        let changes = world.advance(&mut tmp);
+        let generation = world.generation();
+        shared.send(Event::Frame {
+            generation,
+            world: Box::new(world.clone()),
+            changes,
+        });
+        if !changes.any() {
+            shared.send(Event::Stopped {
+                generation,
+                reason: "the world stopped changing".to_string(),
+            });
+            return;
+        }
+    }
+}
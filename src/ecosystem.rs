@@ -0,0 +1,77 @@
+//! `conway species`: runs a world of several species in the terminal, each
+//! in its own color, with how many cells each has under it.
+
+use std::io::{self, Write};
+
+use conway::species::Species;
+
+use crate::cli::SpeciesOptions;
+use crate::interrupt;
+use crate::pace::Pacer;
+use crate::term::{self, Color};
+
+/// The letter cells of species `s` are drawn with.
+fn letter(s: usize) -> char {
+    char::from(b'A' + s as u8)
+}
+
+/// How many cells each species has, like `A 12  B 30  C 0`.
+fn populations(world: &Species, apart: &str) -> String {
+    let counts: Vec<String> = world
+        .populations()
+        .iter()
+        .enumerate()
+        .map(|(s, count)| format!("{} {}", letter(s), count))
+        .collect();
+    counts.join(apart)
+}
+
+fn draw(world: &Species, generation: u64, color: bool) -> io::Result<()> {
+    let mut frame = term::frame_start();
+    for y in 0..world.height() {
+        for x in 0..world.width() {
+            match world.get(x, y) {
+                Some(s) if color => {
+                    frame.push_str(&Color::Basic(s as u8 + 1).foreground());
+                    frame.push(letter(s));
+                    frame.push_str(term::RESET);
+                }
+                Some(s) => frame.push(letter(s)),
+                None => frame.push('.'),
+            }
+        }
+        frame.push('\n');
+    }
+    frame.push_str(&format!(
+        "generation {}  {}\x1b[K\n",
+        generation,
+        populations(world, "  ")
+    ));
+    let mut out = io::stdout();
+    out.write_all(frame.as_bytes())?;
+    out.flush()
+}
+
+/// Whether no more than one species is left, which is only the end when
+/// there were more to begin with.
+fn settled(world: &Species) -> bool {
+    let alive = world.populations().iter().filter(|&&n| n > 0).count();
+    alive == 0 || (alive == 1 && world.species() > 1)
+}
+
+/// Runs `world` until `--generations` or it is settled, and prints how each
+/// species ended up.
+pub fn run(mut world: Species, opts: &SpeciesOptions) -> Result<(), String> {
+    let mut pacer = Pacer::delay(opts.delay);
+    print!("{}", term::CLEAR);
+    let mut generation = 0;
+    draw(&world, generation, opts.color).map_err(|e| e.to_string())?;
+    while generation < opts.generations && !settled(&world) && !interrupt::requested() {
+        pacer.wait();
+        world.advance();
+        generation += 1;
+        draw(&world, generation, opts.color).map_err(|e| e.to_string())?;
+    }
+    println!("generation {}: {}", generation, populations(&world, ", "));
+    Ok(())
+}
@@ -0,0 +1,803 @@
+//! Interactive pattern editor in the terminal.
+
+use std::fs;
+use std::io::{self, Write};
+use std::mem;
+use std::time::{Duration, Instant};
+
+use conway::{Cell, Pattern, Rect, Rng, Transform, World};
+
+use crate::clipboard;
+use crate::interrupt;
+use crate::pace::Timings;
+use crate::term::{self, Footprint, Key, RawMode};
+
+const HELP: &str = "arrows/hjkl move  space toggle  w wall  o source  x sink  n step  c clear  v select  p paste  \
+                    t footprint  M minimap  R fit to terminal  ^Z undo  ^Y redo  s save  q quit";
+const SELECT_HELP: &str = "arrows/hjkl resize  y copy as RLE  d clear  f fill  i invert  \
+                           r randomize  +/- density  m move  L line  b box  e/E ellipse  \
+                           v cancel";
+const PASTE_HELP: &str = "arrows/hjkl move  r rotate  f flip  enter/space stamp  q cancel";
+
+/// The most characters across and down the minimap takes.
+const MINIMAP: (usize, usize) = (32, 12);
+
+/// How full randomizing leaves a selection to begin with, the same as
+/// `--random`.
+const DENSITY: f64 = 0.3;
+
+/// Edits kept for undoing, oldest first to go, and the most memory they
+/// take between them.
+const HISTORY: usize = 1000;
+const HISTORY_BYTES: usize = 64 << 20;
+
+const CTRL_C: u8 = 0x03;
+const CTRL_Y: u8 = 0x19;
+const CTRL_Z: u8 = 0x1a;
+
+/// A change to the world that can be taken back.
+enum Edit {
+    Toggle(usize, usize),
+    /// The cell becomes a wall, or stops being one and becomes this cell
+    /// again.
+    Wall(usize, usize, Cell),
+    /// The cell becomes a source, a sink or with `None` neither, and is then
+    /// this cell unless forced.
+    Emitter(usize, usize, Option<Cell>, Cell),
+    /// Cells change, by a step, clearing the world or a paste, to these, and
+    /// the world is then at this generation.
+    Cells(Vec<(usize, usize, Spot)>, u64),
+    /// The world changes size, fitted to the terminal, to this one at this
+    /// generation, boxed as it is much bigger than a toggle.
+    Replace(Box<World>, u64),
+}
+
+impl Edit {
+    /// About how much memory the edit takes.
+    fn bytes(&self) -> usize {
+        match self {
+            Edit::Cells(cells, _) => cells.len() * mem::size_of::<(usize, usize, Spot)>(),
+            Edit::Replace(world, _) => {
+                World::memory(world.width(), world.height()).unwrap_or(usize::MAX)
+            }
+            _ => mem::size_of::<Edit>(),
+        }
+    }
+}
+
+/// Everything an edit can change about a cell.
+#[derive(Clone, Copy, PartialEq)]
+struct Spot {
+    cell: Cell,
+    wall: bool,
+    emitter: Option<Cell>,
+}
+
+/// The cells of a line from `a` to `b`, both ends included.
+fn line((x0, y0): (usize, usize), (x1, y1): (usize, usize)) -> Vec<(usize, usize)> {
+    let (x0, y0, x1, y1) = (x0 as i64, y0 as i64, x1 as i64, y1 as i64);
+    let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+    let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+    let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+    let mut cells = vec![(x as usize, y as usize)];
+    while (x, y) != (x1, y1) {
+        // Bresenham's: step along whichever axes keep closest to the line
+        let twice = 2 * error;
+        if twice >= dy {
+            error += dy;
+            x += sx;
+        }
+        if twice <= dx {
+            error += dx;
+            y += sy;
+        }
+        cells.push((x as usize, y as usize));
+    }
+    cells
+}
+
+/// The cells around the edge of `rect`.
+fn outline(rect: Rect) -> Vec<(usize, usize)> {
+    let (right, bottom) = (rect.x + rect.width - 1, rect.y + rect.height - 1);
+    let mut cells = Vec::new();
+    for y in rect.y..=bottom {
+        for x in rect.x..=right {
+            if x == rect.x || x == right || y == rect.y || y == bottom {
+                cells.push((x, y));
+            }
+        }
+    }
+    cells
+}
+
+/// The cells of the ellipse that fits `rect`, all of them when `filled` and
+/// otherwise those with a neighbor outside it.
+fn ellipse(rect: Rect, filled: bool) -> Vec<(usize, usize)> {
+    let (rx, ry) = (rect.width as f64 / 2.0, rect.height as f64 / 2.0);
+    // measured from the middle of the rectangle to the middle of each cell
+    let inside = |x: i64, y: i64| {
+        let dx = (x - rect.x as i64) as f64 + 0.5 - rx;
+        let dy = (y - rect.y as i64) as f64 + 0.5 - ry;
+        (dx / rx).powi(2) + (dy / ry).powi(2) <= 1.0
+    };
+    let mut cells = Vec::new();
+    for y in rect.y..rect.y + rect.height {
+        for x in rect.x..rect.x + rect.width {
+            let (x, y) = (x as i64, y as i64);
+            let edge = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                .iter()
+                .any(|&(dx, dy)| !inside(x + dx, y + dy));
+            if inside(x, y) && (filled || edge) {
+                cells.push((x as usize, y as usize));
+            }
+        }
+    }
+    cells
+}
+
+/// The whole world shrunk into a corner of a view too small for it, a
+/// character a square block of cells.
+struct Minimap {
+    width: usize,
+    height: usize,
+    block: usize,
+    /// Which blocks have a live cell, row by row.
+    live: Vec<bool>,
+}
+
+impl Minimap {
+    /// The minimap of `world` for `view`, if it doesn't show all of it.
+    fn new(world: &World, view: Rect) -> Option<Minimap> {
+        let (w, h) = (world.width(), world.height());
+        if view.width >= w && view.height >= h {
+            return None;
+        }
+        // no more than a quarter of the view each way
+        let (most_w, most_h) = (
+            (view.width / 4).clamp(1, MINIMAP.0),
+            (view.height / 4).clamp(1, MINIMAP.1),
+        );
+        let block = w.div_ceil(most_w).max(h.div_ceil(most_h));
+        let (width, height) = (w.div_ceil(block), h.div_ceil(block));
+        let mut live = vec![false; width * height];
+        for y in 0..h {
+            for x in 0..w {
+                if world.get(x, y) == Cell::Live {
+                    live[y / block * width + x / block] = true;
+                }
+            }
+        }
+        Some(Minimap {
+            width,
+            height,
+            block,
+            live,
+        })
+    }
+
+    /// The block at `mx`, `my` with its escapes, lighter where `view` is.
+    fn draw(&self, mx: usize, my: usize, view: Rect, frame: &mut String) {
+        let block = Rect {
+            x: mx * self.block,
+            y: my * self.block,
+            width: self.block,
+            height: self.block,
+        };
+        let background = if block.overlaps(&view) { 240 } else { 234 };
+        let glyph = if self.live[my * self.width + mx] {
+            '#'
+        } else {
+            ' '
+        };
+        frame.push_str(&format!(
+            "\x1b[48;5;{}m{}{}",
+            background,
+            glyph,
+            term::RESET
+        ));
+    }
+}
+
+struct Editor {
+    world: World,
+    tmp: World,
+    x: usize,
+    y: usize,
+    generation: u64,
+    path: String,
+    message: String,
+    /// What takes back each edit so far, and each undone one, newest last.
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    /// The pattern being pasted, shown with its top left corner at the
+    /// cursor until it is stamped.
+    paste: Option<Pattern>,
+    /// The corner of the selection opposite the cursor, while selecting.
+    anchor: Option<(usize, usize)>,
+    /// Where the pattern being pasted was lifted from, when it is being
+    /// moved, to be cleared as it is stamped.
+    lifted: Option<Rect>,
+    /// How full randomizing the selection leaves it.
+    density: f64,
+    rng: Rng,
+    /// Every cell alive since the editor opened, shown on a faint
+    /// background when `show_footprint` is on.
+    footprint: Footprint,
+    show_footprint: bool,
+    /// The part of the world that fits the terminal, kept around the cursor,
+    /// and whether a minimap shows the rest when it doesn't all fit.
+    view: Rect,
+    show_minimap: bool,
+    /// How long the steps took to work out and the last frame to draw.
+    timings: Timings,
+    drawn: Duration,
+}
+
+impl Editor {
+    /// Makes `edit` and gives back the edit that takes it back.
+    fn apply(&mut self, edit: Edit) -> Edit {
+        match edit {
+            Edit::Toggle(x, y) => {
+                self.world.toggle(x, y);
+                Edit::Toggle(x, y)
+            }
+            Edit::Wall(x, y, _) if !self.world.is_wall(x, y) => {
+                let covered = self.world.get(x, y);
+                self.world.set_wall(x, y, true);
+                Edit::Wall(x, y, covered)
+            }
+            Edit::Wall(x, y, cell) => {
+                self.world.set_wall(x, y, false);
+                self.world.set(x, y, cell);
+                Edit::Wall(x, y, Cell::Dead)
+            }
+            Edit::Emitter(x, y, emitter, cell) => {
+                let inverse = Edit::Emitter(x, y, self.world.emitter(x, y), self.world.get(x, y));
+                self.world.set(x, y, cell);
+                self.world.set_emitter(x, y, emitter);
+                inverse
+            }
+            Edit::Cells(cells, generation) => {
+                let before = cells
+                    .into_iter()
+                    .map(|(x, y, spot)| {
+                        let before = self.spot(x, y);
+                        self.set_spot(x, y, spot);
+                        (x, y, before)
+                    })
+                    .collect();
+                let generation = mem::replace(&mut self.generation, generation);
+                Edit::Cells(before, generation)
+            }
+            Edit::Replace(world, generation) => {
+                let world = mem::replace(&mut self.world, *world);
+                let generation = mem::replace(&mut self.generation, generation);
+                let (w, h) = (self.world.width(), self.world.height());
+                if (world.width(), world.height()) != (w, h) {
+                    // fitted to the terminal or that taken back
+                    self.tmp = self.world.clone();
+                    self.x = self.x.min(w - 1);
+                    self.y = self.y.min(h - 1);
+                    self.anchor = None;
+                    self.lifted = None;
+                }
+                Edit::Replace(Box::new(world), generation)
+            }
+        }
+    }
+
+    fn edit(&mut self, edit: Edit) {
+        let inverse = self.apply(edit);
+        self.undo.push(inverse);
+        self.redo.clear();
+        let mut bytes: usize = self.undo.iter().map(Edit::bytes).sum();
+        while self.undo.len() > 1 && (self.undo.len() > HISTORY || bytes > HISTORY_BYTES) {
+            bytes -= self.undo.remove(0).bytes();
+        }
+    }
+
+    fn spot(&self, x: usize, y: usize) -> Spot {
+        Spot {
+            cell: self.world.get(x, y),
+            wall: self.world.is_wall(x, y),
+            emitter: self.world.emitter(x, y),
+        }
+    }
+
+    fn set_spot(&mut self, x: usize, y: usize, spot: Spot) {
+        if spot.wall {
+            self.world.set_wall(x, y, true);
+            return;
+        }
+        if self.world.is_wall(x, y) {
+            self.world.set_wall(x, y, false);
+        }
+        if self.world.emitter(x, y) != spot.emitter {
+            self.world.set_emitter(x, y, spot.emitter);
+        }
+        self.world.set(x, y, spot.cell);
+    }
+
+    /// Makes the world `next`, the same size, at `generation`, as an edit of
+    /// just the cells that differ.
+    fn change_to(&mut self, next: &World, generation: u64) {
+        let mut cells = Vec::new();
+        for y in 0..next.height() {
+            for x in 0..next.width() {
+                let spot = Spot {
+                    cell: next.get(x, y),
+                    wall: next.is_wall(x, y),
+                    emitter: next.emitter(x, y),
+                };
+                if spot != self.spot(x, y) {
+                    cells.push((x, y, spot));
+                }
+            }
+        }
+        self.edit(Edit::Cells(cells, generation));
+    }
+
+    fn undo(&mut self) {
+        match self.undo.pop() {
+            Some(edit) => {
+                let inverse = self.apply(edit);
+                self.redo.push(inverse);
+            }
+            None => self.message = "nothing to undo".to_string(),
+        }
+    }
+
+    fn redo(&mut self) {
+        match self.redo.pop() {
+            Some(edit) => {
+                let inverse = self.apply(edit);
+                self.undo.push(inverse);
+            }
+            None => self.message = "nothing to redo".to_string(),
+        }
+    }
+
+    /// Whether the pattern being pasted has a live cell over `x`, `y`.
+    fn pasting(&self, x: usize, y: usize) -> bool {
+        let (w, h) = (self.world.width(), self.world.height());
+        // how far right of and below the cursor the cell is, around the edges
+        let (px, py) = ((x + w - self.x) % w, (y + h - self.y) % h);
+        self.paste.as_ref().is_some_and(|pattern| {
+            px < pattern.width() && py < pattern.height() && pattern.get(px, py)
+        })
+    }
+
+    /// The rectangle between the anchor and the cursor, both included.
+    fn selection(&self) -> Option<Rect> {
+        let (ax, ay) = self.anchor?;
+        Some(Rect {
+            x: ax.min(self.x),
+            y: ay.min(self.y),
+            width: ax.max(self.x) - ax.min(self.x) + 1,
+            height: ay.max(self.y) - ay.min(self.y) + 1,
+        })
+    }
+
+    /// The size of world that fits the terminal with the lines under it.
+    fn terminal_fit() -> Option<(usize, usize)> {
+        term::size().map(|(cols, rows)| (cols.max(3), rows.saturating_sub(3).max(3)))
+    }
+
+    /// Starts drawing afresh on a resized terminal, and says how to make
+    /// the world fit it if it no longer does.
+    fn resized(&mut self) {
+        print!("{}", term::CLEAR);
+        if let Some((w, h)) = Editor::terminal_fit() {
+            if (w, h) != (self.world.width(), self.world.height()) {
+                self.message = format!(
+                    "the terminal fits {}x{} now, R resizes the world to it",
+                    w, h
+                );
+            }
+        }
+    }
+
+    /// Resizes the world to the terminal, keeping the cells that still fit
+    /// from the top left.
+    fn fit_to_terminal(&mut self) -> Result<(), String> {
+        let (w, h) = match Editor::terminal_fit() {
+            Some(size) => size,
+            None => {
+                self.message = "the terminal's size is not known".to_string();
+                return Ok(());
+            }
+        };
+        if (w, h) == (self.world.width(), self.world.height()) {
+            self.message = format!("the world already is {}x{}", w, h);
+            return Ok(());
+        }
+        let mut fitted = World::new(w, h)?;
+        fitted.set_rule(self.world.rule());
+        fitted.set_meta(self.world.meta().clone());
+        for y in 0..h.min(self.world.height()) {
+            for x in 0..w.min(self.world.width()) {
+                if self.world.is_wall(x, y) {
+                    fitted.set_wall(x, y, true);
+                    continue;
+                }
+                fitted.set(x, y, self.world.get(x, y));
+                fitted.set_emitter(x, y, self.world.emitter(x, y));
+            }
+        }
+        self.edit(Edit::Replace(Box::new(fitted), self.generation));
+        self.message = format!("resized the world to {}x{}", w, h);
+        Ok(())
+    }
+
+    /// Fits the view to the terminal and moves it to keep the cursor in it.
+    fn follow_cursor(&mut self) {
+        let (w, h) = (self.world.width(), self.world.height());
+        // under the world go the status, the help and the message
+        let (cols, rows) = term::size().unwrap_or((w, h + 3));
+        let view = &mut self.view;
+        view.width = w.min(cols.max(1));
+        view.height = h.min(rows.saturating_sub(3).max(1));
+        if self.x < view.x {
+            view.x = self.x;
+        } else if self.x >= view.x + view.width {
+            view.x = self.x + 1 - view.width;
+        }
+        if self.y < view.y {
+            view.y = self.y;
+        } else if self.y >= view.y + view.height {
+            view.y = self.y + 1 - view.height;
+        }
+        view.x = view.x.min(w - view.width);
+        view.y = view.y.min(h - view.height);
+    }
+
+    fn draw(&self) -> io::Result<()> {
+        let selection = self.selection();
+        let view = self.view;
+        let minimap = if self.show_minimap {
+            Minimap::new(&self.world, view)
+        } else {
+            None
+        };
+        let mut frame = String::from(term::HOME);
+        for y in view.y..view.y + view.height {
+            for x in view.x..view.x + view.width {
+                // the minimap goes in the top right corner
+                let (sx, sy) = (x - view.x, y - view.y);
+                if let Some(map) = minimap.as_ref() {
+                    if sx + map.width >= view.width && sy < map.height {
+                        map.draw(sx + map.width - view.width, sy, view, &mut frame);
+                        continue;
+                    }
+                }
+                let pasting = self.pasting(x, y);
+                let lifted = self.lifted.is_some_and(|r| r.contains(x, y));
+                let live = pasting || (!lifted && self.world.get(x, y) == Cell::Live);
+                let emitter = self.world.emitter(x, y).filter(|_| !pasting);
+                let symbol = match (live, emitter) {
+                    _ if !pasting && self.world.is_wall(x, y) => '#',
+                    (_, Some(Cell::Live)) => '@',
+                    (_, Some(Cell::Dead)) => 'x',
+                    (true, None) => 'O',
+                    (false, None) => '.',
+                };
+                let mut style = String::new();
+                if (x, y) == (self.x, self.y) {
+                    style.push_str("\x1b[7m");
+                }
+                if selection.is_some_and(|s| s.contains(x, y)) {
+                    style.push_str("\x1b[44m");
+                }
+                if pasting {
+                    style.push_str("\x1b[36m");
+                }
+                if self.show_footprint && style.is_empty() && self.footprint.contains(x, y) {
+                    style.push_str("\x1b[48;5;236m");
+                }
+                if style.is_empty() {
+                    frame.push(symbol);
+                } else {
+                    frame.push_str(&style);
+                    frame.push(symbol);
+                    frame.push_str(term::RESET);
+                }
+            }
+            frame.push_str("\r\n");
+        }
+        let mut status = format!(
+            "{},{}  generation {}  population {}",
+            self.x,
+            self.y,
+            self.generation,
+            self.world.population()
+        );
+        if self.timings.frames > 0 {
+            let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+            let (simulate, render) = self.timings.rolling();
+            status.push_str(&format!(
+                "  step {:.2}ms ({:.2} avg)  draw {:.2}ms ({:.2} avg)",
+                ms(self.timings.last.0),
+                ms(simulate),
+                ms(self.timings.last.1),
+                ms(render)
+            ));
+        }
+        if minimap.is_some() {
+            status.push_str(&format!(
+                "  view {},{} of {}x{}",
+                view.x,
+                view.y,
+                self.world.width(),
+                self.world.height()
+            ));
+        }
+        if self.show_footprint {
+            status.push_str(&format!("  footprint {}", self.footprint.area()));
+        }
+        if let Some(selection) = selection {
+            status.push_str(&format!(
+                "  selection {}x{}  density {:.1}",
+                selection.width, selection.height, self.density
+            ));
+        }
+        let help = match (&self.paste, selection) {
+            (Some(_), _) => PASTE_HELP,
+            (None, Some(_)) => SELECT_HELP,
+            (None, None) => HELP,
+        };
+        frame.push_str(&format!(
+            "{}\x1b[K\r\n{}\x1b[K\r\n{}\x1b[K",
+            status, help, self.message
+        ));
+        let mut out = io::stdout();
+        out.write_all(frame.as_bytes())?;
+        out.flush()
+    }
+
+    /// Starts pasting the pattern on the clipboard, RLE or plaintext.
+    fn start_paste(&mut self) {
+        let pattern = clipboard::read().and_then(|text| Pattern::parse(&text));
+        match pattern.map(|pattern| pattern.crop()) {
+            Ok(pattern) if pattern.population() == 0 => {
+                self.message = "no live cells on the clipboard".to_string();
+            }
+            Ok(pattern) => {
+                self.message = format!(
+                    "pasting {}x{} pattern with {} cells",
+                    pattern.width(),
+                    pattern.height(),
+                    pattern.population()
+                );
+                self.paste = Some(pattern);
+            }
+            Err(e) => self.message = e,
+        }
+    }
+
+    /// Puts the selected cells on the clipboard as RLE and stops selecting.
+    fn copy(&mut self) {
+        let selection = match self.selection() {
+            Some(selection) => selection,
+            None => return,
+        };
+        let pattern = Pattern::from_region(&self.world, selection);
+        self.message = match clipboard::write(&pattern.to_rle()) {
+            Ok(()) => format!(
+                "copied {}x{} with {} cells",
+                pattern.width(),
+                pattern.height(),
+                pattern.population()
+            ),
+            Err(e) => e,
+        };
+        self.anchor = None;
+    }
+
+    /// Sets each selected cell to what `change` makes of it, as one edit.
+    fn change_selection(&mut self, mut change: impl FnMut(Cell, &mut Rng) -> Cell) {
+        let selection = match self.selection() {
+            Some(selection) => selection,
+            None => return,
+        };
+        let mut next = self.world.clone();
+        for y in selection.y..selection.y + selection.height {
+            for x in selection.x..selection.x + selection.width {
+                next.set(x, y, change(self.world.get(x, y), &mut self.rng));
+            }
+        }
+        self.change_to(&next, self.generation);
+    }
+
+    /// Brings `cells` to life, as one edit.
+    fn paint(&mut self, cells: Vec<(usize, usize)>) {
+        let mut next = self.world.clone();
+        for (x, y) in cells {
+            next.set(x, y, Cell::Live);
+        }
+        self.change_to(&next, self.generation);
+    }
+
+    /// Picks up the selected cells to paste somewhere else, leaving their
+    /// place empty once they are put down.
+    fn lift(&mut self) {
+        if let Some(selection) = self.selection() {
+            self.paste = Some(Pattern::from_region(&self.world, selection));
+            self.lifted = Some(selection);
+            (self.x, self.y) = (selection.x, selection.y);
+            self.anchor = None;
+        }
+    }
+
+    /// Stamps the pattern being pasted where it is shown, as one edit.
+    fn stamp(&mut self) {
+        if let Some(pattern) = self.paste.take() {
+            let mut next = self.world.clone();
+            if let Some(lifted) = self.lifted.take() {
+                for y in lifted.y..lifted.y + lifted.height {
+                    for x in lifted.x..lifted.x + lifted.width {
+                        next.set(x, y, Cell::Dead);
+                    }
+                }
+            }
+            next.stamp(&pattern, self.x, self.y);
+            self.change_to(&next, self.generation);
+        }
+    }
+
+    fn save(&mut self) {
+        let pattern = Pattern::from_live_cells(&self.world);
+        self.message = match pattern.save(&self.path) {
+            Ok(()) => format!(
+                "saved {}x{} pattern to {}",
+                pattern.width(),
+                pattern.height(),
+                self.path
+            ),
+            Err(e) => e,
+        };
+    }
+}
+
+/// Opens the editor on `world`, which should already contain the pattern
+/// loaded from `path` if there was one.
+pub fn run(world: World, path: &str) -> Result<(), String> {
+    let raw = RawMode::enable()?;
+    let mut editor = Editor {
+        tmp: world.clone(),
+        x: world.width() / 2,
+        y: world.height() / 2,
+        world,
+        generation: 0,
+        path: path.to_string(),
+        message: if fs::metadata(path).is_ok() {
+            format!("editing {}", path)
+        } else {
+            format!("new file {}", path)
+        },
+        undo: Vec::new(),
+        redo: Vec::new(),
+        paste: None,
+        anchor: None,
+        lifted: None,
+        density: DENSITY,
+        rng: Rng::from_time(),
+        footprint: Footprint::default(),
+        show_footprint: false,
+        view: Rect {
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+        },
+        show_minimap: true,
+        timings: Timings::default(),
+        drawn: Duration::ZERO,
+    };
+
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    loop {
+        if interrupt::resized() {
+            editor.resized();
+        }
+        let (w, h) = (editor.world.width(), editor.world.height());
+        editor.footprint.mark(&editor.world);
+        editor.follow_cursor();
+        let began = Instant::now();
+        editor.draw().map_err(|e| e.to_string())?;
+        editor.drawn = began.elapsed();
+        let key = term::read_key(&mut input).map_err(|e| e.to_string())?;
+        editor.message.clear();
+        if let Some(pattern) = editor.paste.as_mut() {
+            match key {
+                Key::Char(b'r') => *pattern = pattern.transformed(Transform::Rot90),
+                Key::Char(b'f') => *pattern = pattern.transformed(Transform::FlipX),
+                Key::Char(b'\r') | Key::Char(b'\n') | Key::Char(b' ') => {
+                    editor.stamp();
+                    continue;
+                }
+                Key::Char(b'q') => {
+                    editor.paste = None;
+                    editor.lifted = None;
+                    continue;
+                }
+                Key::Char(CTRL_C) => break,
+                _ => {}
+            }
+        } else if let (Some(anchor), Some(selection)) = (editor.anchor, editor.selection()) {
+            match key {
+                Key::Char(b'd') => editor.change_selection(|_, _| Cell::Dead),
+                Key::Char(b'f') => editor.change_selection(|_, _| Cell::Live),
+                Key::Char(b'i') => editor.change_selection(|cell, _| match cell {
+                    Cell::Live => Cell::Dead,
+                    Cell::Dead => Cell::Live,
+                }),
+                Key::Char(b'r') => {
+                    let density = editor.density;
+                    editor.change_selection(|_, rng| {
+                        if rng.chance(density) {
+                            Cell::Live
+                        } else {
+                            Cell::Dead
+                        }
+                    })
+                }
+                Key::Char(b'+') => editor.density = (editor.density + 0.1).min(0.9),
+                Key::Char(b'-') => editor.density = (editor.density - 0.1).max(0.1),
+                Key::Char(b'm') => editor.lift(),
+                Key::Char(b'L') => editor.paint(line(anchor, (editor.x, editor.y))),
+                Key::Char(b'b') => editor.paint(outline(selection)),
+                Key::Char(b'e') => editor.paint(ellipse(selection, false)),
+                Key::Char(b'E') => editor.paint(ellipse(selection, true)),
+                _ => {}
+            }
+        }
+        match key {
+            Key::Up | Key::Char(b'k') => editor.y = (editor.y + h - 1) % h,
+            Key::Down | Key::Char(b'j') => editor.y = (editor.y + 1) % h,
+            Key::Left | Key::Char(b'h') => editor.x = (editor.x + w - 1) % w,
+            Key::Right | Key::Char(b'l') => editor.x = (editor.x + 1) % w,
+            Key::Char(b' ') => editor.edit(Edit::Toggle(editor.x, editor.y)),
+            Key::Char(b'w') => editor.edit(Edit::Wall(editor.x, editor.y, Cell::Dead)),
+            Key::Char(key @ b'o') | Key::Char(key @ b'x') => {
+                let (x, y) = (editor.x, editor.y);
+                let kind = if key == b'o' { Cell::Live } else { Cell::Dead };
+                let emitter = match editor.world.emitter(x, y) {
+                    Some(cell) if cell == kind => None,
+                    _ => Some(kind),
+                };
+                editor.edit(Edit::Emitter(x, y, emitter, editor.world.get(x, y)));
+            }
+            Key::Char(b'n') => {
+                let mut next = editor.world.clone();
+                let began = Instant::now();
+                next.advance(&mut editor.tmp);
+                let step = began.elapsed();
+                editor
+                    .timings
+                    .frame(step, editor.drawn, Duration::ZERO, false);
+                editor.change_to(&next, editor.generation + 1);
+            }
+            Key::Char(b'c') => {
+                let mut empty = World::new(w, h)?;
+                empty.set_rule(editor.world.rule());
+                editor.change_to(&empty, 0);
+            }
+            Key::Char(CTRL_Z) => editor.undo(),
+            Key::Char(CTRL_Y) => editor.redo(),
+            Key::Char(b'v') if editor.anchor.is_some() => editor.anchor = None,
+            Key::Char(b'v') => editor.anchor = Some((editor.x, editor.y)),
+            Key::Char(b'y') => editor.copy(),
+            Key::Char(b'p') => editor.start_paste(),
+            Key::Char(b't') => editor.show_footprint = !editor.show_footprint,
+            Key::Char(b'M') => editor.show_minimap = !editor.show_minimap,
+            Key::Char(b'R') => editor.fit_to_terminal()?,
+            Key::Resize => editor.resized(),
+            Key::Char(b's') => editor.save(),
+            Key::Char(b'q') | Key::Char(CTRL_C) => break,
+            Key::Char(_) | Key::Click(..) => {}
+        }
+    }
+    drop(raw);
+    Ok(())
+}
@@ -0,0 +1,195 @@
+//! The engines a world can be stepped by, for checking them against each
+//! other: `World` itself, the world cut into `Strip`s as `distribute` runs
+//! it, and a plain grid counted the obvious way. Each is an `Automaton`, as
+//! is any other engine to check against them.
+
+use conway::{Automaton, Cell, Changes, Rule, Stepper, Strip, World};
+
+pub const NAMES: [&str; 3] = ["world", "strips", "grid"];
+
+/// Strips the `strips` engine cuts a world into, when it is tall enough.
+const STRIPS: usize = 4;
+
+/// The straightforward engine the others are checked against.
+pub struct Grid {
+    width: usize,
+    height: usize,
+    cells: Vec<bool>,
+}
+
+impl Grid {
+    pub fn from_world(world: &World) -> Grid {
+        let (width, height) = (world.width(), world.height());
+        let cells = (0..width * height)
+            .map(|i| world.get(i % width, i / width) == Cell::Live)
+            .collect();
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    pub fn step(&mut self, rule: Rule) -> Changes {
+        let (w, h) = (self.width, self.height);
+        let r = rule.radius();
+        let mut next = vec![false; w * h];
+        let mut changes = Changes::default();
+        for y in 0..h {
+            for x in 0..w {
+                // the cell itself too, as `next_within` counts
+                let (mut around, mut sum, mut mask) = (0, 0, 0u8);
+                for dy in 0..2 * r + 1 {
+                    for dx in 0..2 * r + 1 {
+                        let (ny, nx) = ((y + dy + h * r - r) % h, (x + dx + w * r - r) % w);
+                        let k = dy * (2 * r + 1) + dx;
+                        if self.cells[ny * w + nx] {
+                            around += 1;
+                            sum += rule.weights().map_or(0, |weights| weights[k]);
+                            // the eight around, skipping the cell itself
+                            if r == 1 && k != 4 {
+                                mask |= 1 << (k - usize::from(k > 4));
+                            }
+                        }
+                    }
+                }
+                let old = self.cells[y * w + x];
+                let cell = if old { Cell::Live } else { Cell::Dead };
+                let stepped = match rule.weights() {
+                    Some(_) => rule.next_weighted(cell, sum),
+                    None if rule.directional() => rule.next_mask(cell, mask),
+                    None => rule.next_within(cell, around),
+                };
+                let live = stepped == Cell::Live;
+                match (old, live) {
+                    (false, true) => changes.births += 1,
+                    (true, false) => changes.deaths += 1,
+                    _ => {}
+                }
+                next[y * w + x] = live;
+            }
+        }
+        self.cells = next;
+        changes
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Cell {
+        if self.cells[y * self.width + x] {
+            Cell::Live
+        } else {
+            Cell::Dead
+        }
+    }
+}
+
+/// Cuts `world` into strips starting at the rows in `cuts`, the first 0.
+pub fn cut(world: &World, cuts: &[usize]) -> Result<Vec<Strip>, String> {
+    let mut strips = Vec::with_capacity(cuts.len());
+    for (i, &y) in cuts.iter().enumerate() {
+        let end = cuts.get(i + 1).copied().unwrap_or(world.height());
+        strips.push(Strip::from_world(world, y, end - y)?);
+    }
+    Ok(strips)
+}
+
+/// Steps every strip once, each shown the rows bordering it.
+pub fn advance_strips(strips: &mut [Strip]) -> Changes {
+    let n = strips.len();
+    let edges: Vec<(Vec<Cell>, Vec<Cell>)> = (0..n)
+        .map(|i| {
+            let above = &strips[(i + n - 1) % n];
+            let below = &strips[(i + 1) % n];
+            (
+                above.row(above.height() - 1).to_vec(),
+                below.row(0).to_vec(),
+            )
+        })
+        .collect();
+    let mut changes = Changes::default();
+    for (strip, (above, below)) in strips.iter_mut().zip(&edges) {
+        let c = strip.advance(above, below);
+        changes.births += c.births;
+        changes.deaths += c.deaths;
+    }
+    changes
+}
+
+/// A world being stepped by one of the engines.
+pub enum Engine {
+    /// Boxed as it is much bigger than the rest.
+    World(Box<Stepper>),
+    /// The strips, and the row each starts at.
+    Strips(Vec<Strip>, Vec<usize>),
+    Grid(Grid, Rule),
+}
+
+impl Engine {
+    /// `world` to be stepped by the engine called `name`, one of `NAMES`.
+    pub fn new(name: &str, world: &World) -> Result<Engine, String> {
+        match name {
+            "world" => Ok(Engine::World(Box::new(Stepper::new(world.clone())))),
+            "strips" => {
+                let strips = STRIPS.min(world.height());
+                let cuts: Vec<usize> = (0..strips).map(|i| i * world.height() / strips).collect();
+                Ok(Engine::Strips(cut(world, &cuts)?, cuts))
+            }
+            "grid" => Ok(Engine::Grid(Grid::from_world(world), world.rule())),
+            _ => Err(format!(
+                "unknown engine '{}', expected one of {}",
+                name,
+                NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl Automaton for Engine {
+    fn step(&mut self) -> Changes {
+        match self {
+            Engine::World(stepper) => stepper.step(),
+            Engine::Strips(strips, _) => advance_strips(strips),
+            Engine::Grid(grid, rule) => grid.step(*rule),
+        }
+    }
+
+    fn cell(&self, x: usize, y: usize) -> Cell {
+        match self {
+            Engine::World(stepper) => stepper.cell(x, y),
+            Engine::Strips(strips, cuts) => {
+                let i = cuts.iter().rposition(|&start| start <= y).unwrap_or(0);
+                strips[i].get(x, y - cuts[i])
+            }
+            Engine::Grid(grid, _) => grid.get(x, y),
+        }
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        match self {
+            Engine::World(stepper) => stepper.dimensions(),
+            Engine::Strips(strips, _) => {
+                (strips[0].width(), strips.iter().map(Strip::height).sum())
+            }
+            Engine::Grid(grid, _) => (grid.width, grid.height),
+        }
+    }
+
+    fn population(&self) -> usize {
+        match self {
+            Engine::World(stepper) => stepper.population(),
+            Engine::Strips(strips, _) => strips.iter().map(Strip::population).sum(),
+            Engine::Grid(grid, _) => grid.cells.iter().filter(|&&live| live).count(),
+        }
+    }
+}
+
+/// The world as `automaton` has it now, the same size as `start` and with
+/// its rule.
+pub fn world_of(automaton: &dyn Automaton, start: &World) -> World {
+    let mut world = start.clone();
+    for y in 0..world.height() {
+        for x in 0..world.width() {
+            world.set(x, y, automaton.cell(x, y));
+        }
+    }
+    world
+}
@@ -0,0 +1,168 @@
+//! Ensembles: many soups alike but for their seeds, run at once, with
+//! statistics across all of them instead of a report on any one, to study
+//! how a density or a rule behaves rather than a single world.
+
+use std::fs;
+
+use conway::{Rule, World};
+
+use crate::cli::{AnalyzeOptions, WorldOptions};
+use crate::pool;
+use crate::stop::{Until, Watch};
+
+/// Population at every generation, summed over the worlds and the least and
+/// most of any of them.
+struct Curve {
+    sum: Vec<u64>,
+    min: Vec<usize>,
+    max: Vec<usize>,
+}
+
+impl Curve {
+    fn new(generations: usize) -> Curve {
+        Curve {
+            sum: vec![0; generations + 1],
+            min: vec![usize::MAX; generations + 1],
+            max: vec![0; generations + 1],
+        }
+    }
+
+    fn add(&mut self, generation: usize, population: usize) {
+        self.sum[generation] += population as u64;
+        self.min[generation] = self.min[generation].min(population);
+        self.max[generation] = self.max[generation].max(population);
+    }
+
+    fn merge(&mut self, other: &Curve) {
+        for g in 0..self.sum.len() {
+            self.sum[g] += other.sum[g];
+            self.min[g] = self.min[g].min(other.min[g]);
+            self.max[g] = self.max[g].max(other.max[g]);
+        }
+    }
+}
+
+/// How one world of the ensemble went.
+struct Member {
+    /// When it settled into something extinct, still or oscillating.
+    settled: Option<u64>,
+    extinct: bool,
+    peak: usize,
+    population: usize,
+    rule: Rule,
+}
+
+/// Runs `opts.ensemble` worlds, world `i` from seed `seed + i`, each for
+/// all of `opts.generations`.
+pub fn run(
+    opts: &AnalyzeOptions,
+    seed: u64,
+    build: fn(&WorldOptions) -> Result<World, String>,
+) -> Result<(), String> {
+    let generations = opts.generations as usize;
+    let (members, curves) = pool::run_with(
+        opts.ensemble as usize,
+        opts.threads,
+        || Curve::new(generations),
+        |own, i| run_member(opts, seed.wrapping_add(i as u64), build, own),
+    )?;
+    let mut curve = Curve::new(generations);
+    for own in &curves {
+        curve.merge(own);
+    }
+    let n = members.len() as f64;
+    let mean = |f: fn(&Member) -> f64| members.iter().map(f).sum::<f64>() / n;
+
+    println!(
+        "ensemble     {} worlds, rule {}, seeds {} to {}",
+        members.len(),
+        members[0].rule,
+        seed,
+        seed.wrapping_add(opts.ensemble - 1)
+    );
+    println!("generations  {}", opts.generations);
+    let settled: Vec<u64> = members.iter().filter_map(|m| m.settled).collect();
+    let extinct = members.iter().filter(|m| m.extinct).count();
+    println!(
+        "outcome      {} extinct, {} stable, {} still changing",
+        extinct,
+        settled.len() - extinct,
+        members.len() - settled.len()
+    );
+    // worlds still changing at the end lived at least that long
+    let lifespans: Vec<u64> = members
+        .iter()
+        .map(|m| m.settled.unwrap_or(opts.generations))
+        .collect();
+    println!(
+        "lifespan     {:.1} mean, {} min, {} max",
+        lifespans.iter().sum::<u64>() as f64 / n,
+        lifespans.iter().min().unwrap_or(&0),
+        lifespans.iter().max().unwrap_or(&0)
+    );
+    println!(
+        "population   {:.1} mean initial, {:.1} mean final, {:.1} mean peak",
+        curve.sum[0] as f64 / n,
+        mean(|m| m.population as f64),
+        mean(|m| m.peak as f64)
+    );
+    // ten points along the mean population curve
+    let points: Vec<String> = (0..=10)
+        .map(|i| i * generations / 10)
+        .map(|g| format!("{}: {:.1}", g, curve.sum[g] as f64 / n))
+        .collect();
+    println!("curve        {}", points.join(", "));
+    if let Some(path) = &opts.curve {
+        let mut csv = "generation,mean,min,max\n".to_string();
+        for g in 0..=generations {
+            csv.push_str(&format!(
+                "{},{:.3},{},{}\n",
+                g,
+                curve.sum[g] as f64 / n,
+                curve.min[g],
+                curve.max[g]
+            ));
+        }
+        fs::write(path, csv).map_err(|e| format!("cannot write {}: {}", path, e))?;
+    }
+    Ok(())
+}
+
+fn run_member(
+    opts: &AnalyzeOptions,
+    seed: u64,
+    build: fn(&WorldOptions) -> Result<World, String>,
+    curve: &mut Curve,
+) -> Result<Member, String> {
+    let world_opts = WorldOptions {
+        seed: Some(seed),
+        ..opts.world.clone()
+    };
+    let mut world = build(&world_opts)?;
+    let mut tmp = world.clone();
+    let mut watch = Watch::new(Until::Stable, &world, opts.max_period);
+    let mut peak = world.population();
+    curve.add(0, peak);
+    let generations = opts.generations as usize;
+    for generation in 1..=generations {
+        let changes = world.advance(&mut tmp);
+        watch.check(&world, generation as u64);
+        let population = world.population();
+        peak = peak.max(population);
+        curve.add(generation, population);
+        // a still world looks the same for the rest of the run
+        if !changes.any() {
+            for rest in generation + 1..=generations {
+                curve.add(rest, population);
+            }
+            break;
+        }
+    }
+    Ok(Member {
+        settled: watch.since(),
+        extinct: world.population() == 0,
+        peak,
+        population: world.population(),
+        rule: world.rule(),
+    })
+}
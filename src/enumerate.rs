@@ -0,0 +1,356 @@
+//! `conway enumerate`: every still life and oscillator that fits in a small
+//! box, found by trying every pattern that fills it edge to edge.
+//!
+//! Each one is stepped in a grid of 64-bit rows, the cells of a row stepped
+//! together with their neighbor counts added up a bit at a time, inside a
+//! border of dead cells wider than anything can travel in the generations
+//! it is run for, so the grid's edge never matters. Whatever comes back to
+//! where it started within `--max-period` generations is kept once: every
+//! phase of it, turned and flipped every way, is cropped, and the smallest
+//! of those stands for all of them.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use conway::{Cell, Pattern, Rule};
+
+use crate::cli::EnumerateOptions;
+
+/// The widest side of a box, and the longest period, that fit in a row
+/// with their border.
+pub const MAX_SIDE: usize = 8;
+pub const MAX_PERIOD: usize = 24;
+
+/// Patterns a thread takes at a time.
+const CHUNK: u64 = 1 << 16;
+
+/// A still life or oscillator in the smallest of its forms: the size of the
+/// box of its live cells and the rows of it, the first cell the lowest bit.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Shape {
+    width: usize,
+    height: usize,
+    rows: Vec<u64>,
+}
+
+impl Shape {
+    fn population(&self) -> u32 {
+        self.rows.iter().map(|row| row.count_ones()).sum()
+    }
+
+    fn pattern(&self, rule: Rule) -> Pattern {
+        let mut pattern = Pattern::new(self.width, self.height);
+        pattern.set_rule(Some(rule));
+        for (y, row) in self.rows.iter().enumerate() {
+            for x in 0..self.width {
+                pattern.set(x, y, row >> x & 1 == 1);
+            }
+        }
+        pattern
+    }
+
+    /// The shape turned a quarter turn and mirrored every way, the smallest
+    /// of the eight.
+    fn canonical(&self) -> Shape {
+        let get = |x: usize, y: usize| self.rows[y] >> x & 1 == 1;
+        let (w, h) = (self.width, self.height);
+        (0..8)
+            .map(|t| {
+                let (tw, th) = if t & 4 == 0 { (w, h) } else { (h, w) };
+                let mut rows = vec![0u64; th];
+                for (ty, row) in rows.iter_mut().enumerate() {
+                    for tx in 0..tw {
+                        // swap the axes, then mirror either of them
+                        let (x, y) = if t & 4 == 0 { (tx, ty) } else { (ty, tx) };
+                        let x = if t & 1 == 0 { x } else { w - 1 - x };
+                        let y = if t & 2 == 0 { y } else { h - 1 - y };
+                        if get(x, y) {
+                            *row |= 1 << tx;
+                        }
+                    }
+                }
+                Shape {
+                    width: tw,
+                    height: th,
+                    rows,
+                }
+            })
+            .min()
+            .unwrap_or_else(|| self.clone())
+    }
+}
+
+/// A grid of dead cells with a box of live ones to try in the middle.
+struct Grid {
+    /// Next states by neighbor count, the bits of the counts that give a
+    /// birth and those that let a cell survive.
+    birth: u16,
+    survival: u16,
+    rows: Vec<u64>,
+    scratch: Vec<u64>,
+    /// The rows live cells can have reached, which only grow, so the
+    /// generation before is dead outside them too.
+    top: usize,
+    bottom: usize,
+}
+
+impl Grid {
+    fn new(rule: Rule, height: usize) -> Grid {
+        let bits = |cell: Cell| {
+            (0..=8u8)
+                .filter(|&n| rule.next(cell, n) == Cell::Live)
+                .fold(0, |bits, n| bits | 1 << n)
+        };
+        Grid {
+            birth: bits(Cell::Dead),
+            survival: bits(Cell::Live),
+            rows: vec![0; height],
+            scratch: vec![0; height],
+            top: 0,
+            bottom: 0,
+        }
+    }
+
+    /// Clears the grid and puts `rows` in it, `pad` cells in from the top
+    /// and the left.
+    fn load(&mut self, rows: &[u64], pad: usize) {
+        self.rows.iter_mut().for_each(|row| *row = 0);
+        self.scratch.iter_mut().for_each(|row| *row = 0);
+        for (y, &row) in rows.iter().enumerate() {
+            self.rows[pad + y] = row << pad;
+        }
+        self.top = pad;
+        self.bottom = pad + rows.len() - 1;
+    }
+
+    fn step(&mut self) {
+        let h = self.rows.len();
+        self.top = self.top.saturating_sub(1);
+        self.bottom = (self.bottom + 1).min(h - 1);
+        for y in self.top..=self.bottom {
+            let up = if y > 0 { self.rows[y - 1] } else { 0 };
+            let down = if y + 1 < h { self.rows[y + 1] } else { 0 };
+            let row = self.rows[y];
+            // the count of each cell's live neighbors, a bit of it per plane
+            let mut planes = [0u64; 4];
+            for n in [
+                up << 1,
+                up,
+                up >> 1,
+                row << 1,
+                row >> 1,
+                down << 1,
+                down,
+                down >> 1,
+            ] {
+                let mut carry = n;
+                for plane in planes.iter_mut() {
+                    let next = *plane & carry;
+                    *plane ^= carry;
+                    carry = next;
+                }
+            }
+            let count = |n: u16| -> u64 {
+                planes.iter().enumerate().fold(!0, |all, (bit, &plane)| {
+                    all & if n >> bit & 1 == 1 { plane } else { !plane }
+                })
+            };
+            let (mut born, mut kept) = (0, 0);
+            for n in 0..=8 {
+                if self.birth >> n & 1 == 1 {
+                    born |= count(n);
+                }
+                if self.survival >> n & 1 == 1 {
+                    kept |= count(n);
+                }
+            }
+            self.scratch[y] = (row & kept) | (!row & born);
+        }
+        std::mem::swap(&mut self.rows, &mut self.scratch);
+    }
+
+    /// The live cells, cropped to their box, or `None` if there are none.
+    fn shape(&self) -> Option<Shape> {
+        let top = self.rows.iter().position(|&row| row != 0)?;
+        let bottom = self.rows.iter().rposition(|&row| row != 0)?;
+        let all = self.rows.iter().fold(0, |all, row| all | row);
+        let (left, right) = (all.trailing_zeros(), 63 - all.leading_zeros());
+        Some(Shape {
+            width: (right - left + 1) as usize,
+            height: bottom - top + 1,
+            rows: self.rows[top..=bottom]
+                .iter()
+                .map(|row| row >> left)
+                .collect(),
+        })
+    }
+}
+
+/// What was found, by the smallest form of each.
+type Found = HashMap<Shape, usize>;
+
+/// Tries every pattern of a `width` by `height` box from `start` to `end`,
+/// each number's bits its cells row by row.
+fn search(
+    opts: &EnumerateOptions,
+    width: usize,
+    height: usize,
+    start: u64,
+    end: u64,
+    found: &mut Found,
+) {
+    let pad = opts.max_period + 1;
+    let mut grid = Grid::new(opts.rule, height + 2 * pad);
+    let row_mask = (1u64 << width) - 1;
+    let (first_column, last_column) = (1u64, 1u64 << (width - 1));
+    let mut rows = vec![0; height];
+    for cells in start..end {
+        for (y, row) in rows.iter_mut().enumerate() {
+            *row = cells >> (y * width) & row_mask;
+        }
+        // a pattern that doesn't touch every side is found in a smaller box
+        let columns = rows.iter().fold(0, |all, row| all | row);
+        if rows[0] == 0
+            || rows[height - 1] == 0
+            || columns & first_column == 0
+            || columns & last_column == 0
+        {
+            continue;
+        }
+        // nor does one need trying if a mirror image of it comes first
+        let mirror = |row: u64| row.reverse_bits() >> (64 - width);
+        let number = |rows: &mut dyn Iterator<Item = u64>| {
+            rows.enumerate()
+                .fold(0, |cells, (y, row)| cells | row << (y * width))
+        };
+        if number(&mut rows.iter().rev().copied()) < cells
+            || number(&mut rows.iter().map(|&row| mirror(row))) < cells
+            || number(&mut rows.iter().rev().map(|&row| mirror(row))) < cells
+        {
+            continue;
+        }
+        grid.load(&rows, pad);
+        let start = grid.rows.clone();
+        let mut period = None;
+        for generation in 1..=opts.max_period {
+            grid.step();
+            if grid.rows == start {
+                period = Some(generation);
+                break;
+            }
+        }
+        let period = match period {
+            Some(period) => period,
+            None => continue,
+        };
+        // the grid is back where it started, so stepping it on goes through
+        // every phase again
+        let smallest = (0..period)
+            .filter_map(|_| {
+                let shape = grid.shape().map(|shape| shape.canonical());
+                grid.step();
+                shape
+            })
+            .min();
+        if let Some(shape) = smallest {
+            found.insert(shape, period);
+        }
+    }
+}
+
+pub fn run(opts: &EnumerateOptions) -> Result<(), String> {
+    let mut boxes = Vec::new();
+    for height in 1..=opts.height {
+        for width in 1..=opts.width {
+            boxes.push((width, height));
+        }
+    }
+    let found = Mutex::new(Found::new());
+    for &(width, height) in &boxes {
+        let total = 1u64 << (width * height);
+        let next = AtomicU64::new(0);
+        thread::scope(|scope| {
+            for _ in 0..opts.threads {
+                scope.spawn(|| {
+                    let mut mine = Found::new();
+                    loop {
+                        let start = next.fetch_add(CHUNK, Ordering::Relaxed);
+                        if start >= total {
+                            break;
+                        }
+                        search(
+                            opts,
+                            width,
+                            height,
+                            start,
+                            (start + CHUNK).min(total),
+                            &mut mine,
+                        );
+                    }
+                    found.lock().unwrap().extend(mine);
+                });
+            }
+        });
+    }
+    let mut found: Vec<(usize, Shape)> = found
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|(shape, period)| (period, shape))
+        .collect();
+    found.sort_by_key(|(period, shape)| (*period, shape.population(), shape.clone()));
+
+    let mut periods: Vec<(usize, usize)> = Vec::new();
+    for &(period, _) in &found {
+        match periods.last_mut() {
+            Some((p, n)) if *p == period => *n += 1,
+            _ => periods.push((period, 1)),
+        }
+    }
+    let counts: Vec<String> = periods
+        .iter()
+        .map(|&(period, n)| match period {
+            1 => format!("{} still lifes", n),
+            p => format!("{} of period {}", n, p),
+        })
+        .collect();
+    println!(
+        "enumerate    boxes up to {}x{}, periods up to {}, rule {}: {}",
+        opts.width,
+        opts.height,
+        opts.max_period,
+        opts.rule,
+        if counts.is_empty() {
+            "nothing".to_string()
+        } else {
+            counts.join(", ")
+        }
+    );
+    for (i, (period, shape)) in found.iter().enumerate() {
+        let kind = match period {
+            1 => "still life".to_string(),
+            p => format!("period {}", p),
+        };
+        println!(
+            "found        #{} {}, {} cells, {}x{}",
+            i + 1,
+            kind,
+            shape.population(),
+            shape.width,
+            shape.height
+        );
+        let pattern = shape.pattern(opts.rule);
+        print!("{}", pattern.to_rle());
+        if let Some(dir) = &opts.save {
+            let name = match period {
+                1 => format!("still-{}.rle", i + 1),
+                p => format!("p{}-{}.rle", p, i + 1),
+            };
+            pattern.save(&Path::new(dir).join(name).to_string_lossy())?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,187 @@
+//! `conway evolve`: a genetic search for small patterns that do something.
+//! A population of random squares is run, each scored by the fitness
+//! asked for, and the next population bred from the best of it: a few kept
+//! as they are, the rest children of two picked by tournament, each cell
+//! taken from either parent and then flipped now and then.
+//!
+//! Everything is drawn from the one seed and the scores don't depend on
+//! which thread worked them out, so a search can be run again exactly.
+
+use std::cmp::Reverse;
+use std::path::Path;
+
+use conway::census;
+use conway::{Pattern, Rng, World};
+
+use crate::cli::EvolveOptions;
+use crate::pool;
+use crate::stop::{self, Until, Watch};
+
+/// Individuals that meet in a tournament for the right to be a parent.
+const TOURNAMENT: usize = 3;
+
+/// What a pattern is scored on, higher being better.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Fitness {
+    /// Generations until it settles.
+    Longevity,
+    /// Live cells at the end.
+    Population,
+    /// How much bigger its bounding box is at the end than at the start.
+    Growth,
+    /// Gliders flying at the end.
+    Glider,
+}
+
+impl Fitness {
+    pub fn parse(s: &str) -> Result<Fitness, String> {
+        match s {
+            "longevity" => Ok(Fitness::Longevity),
+            "population" => Ok(Fitness::Population),
+            "growth" => Ok(Fitness::Growth),
+            "glider" => Ok(Fitness::Glider),
+            _ => Err(format!(
+                "unknown fitness '{}', expected longevity, population, growth or glider",
+                s
+            )),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Fitness::Longevity => "longevity",
+            Fitness::Population => "population",
+            Fitness::Growth => "growth",
+            Fitness::Glider => "glider",
+        }
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn pattern(genome: &[bool], opts: &EvolveOptions) -> Pattern {
+    let size = opts.size;
+    let mut pattern = Pattern::new(size, size);
+    pattern.set_rule(Some(opts.rule));
+    for (i, &live) in genome.iter().enumerate() {
+        pattern.set(i % size, i / size, live);
+    }
+    pattern
+}
+
+/// The score of the pattern `genome` is the cells of, run in the middle of
+/// an empty world.
+fn score(genome: &[bool], opts: &EvolveOptions) -> Result<i64, String> {
+    let mut world = World::new(opts.width, opts.height)?;
+    world.set_rule(opts.rule);
+    world.stamp(
+        &pattern(genome, opts),
+        (opts.width - opts.size) / 2,
+        (opts.height - opts.size) / 2,
+    );
+    let area = |world: &World| {
+        world
+            .bounding_box()
+            .map_or(0, |r| (r.width * r.height) as i64)
+    };
+    let start = area(&world);
+    // a glider thrown off comes back around after 4 times the common
+    // multiple of the sides, as in search
+    let window = 4 * opts.width / gcd(opts.width, opts.height) * opts.height;
+    let mut watch = Watch::new(Until::Stable, &world, window);
+    let mut tmp = world.clone();
+    let mut lived = opts.steps;
+    for generation in 1..=opts.steps {
+        let changes = world.advance(&mut tmp);
+        if !changes.any() || watch.check(&world, generation).is_some() {
+            lived = generation;
+            break;
+        }
+    }
+    Ok(match opts.fitness {
+        Fitness::Longevity => lived as i64,
+        Fitness::Population => world.population() as i64,
+        Fitness::Growth => area(&world) - start,
+        Fitness::Glider => census::spaceships(&world, stop::DEFAULT_WINDOW)
+            .iter()
+            .filter(|ship| ship.name == Some("glider"))
+            .count() as i64,
+    })
+}
+
+/// Scores every genome, on as many threads as the options allow.
+fn score_all(genomes: &[Vec<bool>], opts: &EvolveOptions) -> Result<Vec<i64>, String> {
+    pool::run(genomes.len(), opts.threads, |i| score(&genomes[i], opts))
+}
+
+/// The best of a few picked at random.
+fn tournament<'a>(ranked: &'a [(i64, Vec<bool>)], rng: &mut Rng) -> &'a [bool] {
+    let best = (0..TOURNAMENT)
+        .map(|_| (rng.next_u64() % ranked.len() as u64) as usize)
+        .min()
+        .unwrap_or(0);
+    &ranked[best].1
+}
+
+pub fn run(opts: &EvolveOptions, seed: u64) -> Result<(), String> {
+    let mut rng = Rng::new(seed);
+    let cells = opts.size * opts.size;
+    let mut genomes: Vec<Vec<bool>> = (0..opts.population)
+        .map(|_| (0..cells).map(|_| rng.chance(opts.density)).collect())
+        .collect();
+    println!(
+        "evolve       {} {}x{} patterns for {} generations, fitness {}, rule {}, seed {}",
+        opts.population,
+        opts.size,
+        opts.size,
+        opts.generations,
+        opts.fitness.name(),
+        opts.rule,
+        seed
+    );
+    for generation in 1..=opts.generations {
+        let scores = score_all(&genomes, opts)?;
+        let mut ranked: Vec<(i64, Vec<bool>)> = scores.into_iter().zip(genomes).collect();
+        // a stable sort, so equal scores keep their order and the run its seed
+        ranked.sort_by_key(|&(score, _)| Reverse(score));
+        let mean = ranked.iter().map(|&(score, _)| score as f64).sum::<f64>() / ranked.len() as f64;
+        let best = pattern(&ranked[0].1, opts).crop();
+        println!(
+            "generation   {} best {} mean {:.1}, {} cells",
+            generation,
+            ranked[0].0,
+            mean,
+            best.population()
+        );
+        print!("{}", best.to_rle());
+        if let Some(dir) = &opts.save {
+            let path = Path::new(dir).join(format!("best-{}.rle", generation));
+            best.save(&path.to_string_lossy())?;
+        }
+
+        genomes = ranked
+            .iter()
+            .take(opts.elite)
+            .map(|(_, genome)| genome.clone())
+            .collect();
+        while genomes.len() < opts.population {
+            let (a, b) = (tournament(&ranked, &mut rng), tournament(&ranked, &mut rng));
+            let child = a
+                .iter()
+                .zip(b)
+                .map(|(&a, &b)| {
+                    let live = if rng.chance(0.5) { a } else { b };
+                    live != rng.chance(opts.mutation)
+                })
+                .collect();
+            genomes.push(child);
+        }
+    }
+    Ok(())
+}
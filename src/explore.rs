@@ -0,0 +1,204 @@
+//! Rule-space exploration: one starting world under every rule of a family,
+//! each run until its outcome is clear, laid out as a matrix.
+
+use std::fmt;
+
+use conway::{Rule, World};
+
+use crate::cli::ExploreOptions;
+use crate::stop::{Growth, Until, Watch};
+
+/// Rules in a space; each digit doubles the count.
+const MAX_RULES: usize = 1 << 12;
+
+/// The rules made of any of some birth and survival counts, from notation
+/// like `B3678/S2345`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Space {
+    birth: Vec<u8>,
+    survival: Vec<u8>,
+}
+
+impl Space {
+    pub fn parse(s: &str) -> Result<Space, String> {
+        let err = || {
+            format!(
+                "invalid rule space '{}', expected the counts to vary like B3678/S2345",
+                s
+            )
+        };
+        let (birth, survival) = s.split_once('/').ok_or_else(err)?;
+        let counts = |part: &str, tag: char| -> Result<Vec<u8>, String> {
+            let digits = part
+                .strip_prefix(|c: char| c.eq_ignore_ascii_case(&tag))
+                .ok_or_else(err)?;
+            let mut counts = Vec::new();
+            for c in digits.chars() {
+                match c.to_digit(10) {
+                    Some(n) if n <= 8 && !counts.contains(&(n as u8)) => counts.push(n as u8),
+                    _ => return Err(err()),
+                }
+            }
+            counts.sort_unstable();
+            Ok(counts)
+        };
+        let space = Space {
+            birth: counts(birth, 'b')?,
+            survival: counts(survival, 's')?,
+        };
+        if space.len() > MAX_RULES {
+            return Err(format!(
+                "the rule space '{}' has {} rules, at most {} can be explored",
+                s,
+                space.len(),
+                MAX_RULES
+            ));
+        }
+        Ok(space)
+    }
+
+    fn len(&self) -> usize {
+        1 << (self.birth.len() + self.survival.len())
+    }
+}
+
+impl fmt::Display for Space {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits =
+            |counts: &[u8]| -> String { counts.iter().map(|&n| char::from(b'0' + n)).collect() };
+        write!(f, "B{}/S{}", digits(&self.birth), digits(&self.survival))
+    }
+}
+
+/// Every subset of `counts`, the empty one first, as digits.
+fn subsets(counts: &[u8]) -> Vec<String> {
+    (0..1usize << counts.len())
+        .map(|mask| {
+            counts
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| mask & 1 << i != 0)
+                .map(|(_, &n)| char::from(b'0' + n))
+                .collect()
+        })
+        .collect()
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Outcome {
+    Dies,
+    /// Settles into a still life.
+    Stabilizes,
+    Oscillates(usize),
+    Explodes,
+    /// Still changing at the end without having grown.
+    Churns,
+}
+
+impl Outcome {
+    fn symbol(self) -> char {
+        match self {
+            Outcome::Dies => '.',
+            Outcome::Stabilizes => 'S',
+            Outcome::Oscillates(_) => 'O',
+            Outcome::Explodes => 'X',
+            Outcome::Churns => '~',
+        }
+    }
+}
+
+fn classify(mut world: World, generations: u64, max_period: usize) -> Outcome {
+    let start = world.population();
+    let mut tmp = world.clone();
+    let mut watch = Watch::new(Until::Stable, &world, max_period);
+    let mut growth = Growth::new();
+    for generation in 1..=generations {
+        world.advance(&mut tmp);
+        if watch.check(&world, generation).is_some() {
+            return match (world.population(), world.detect_period(max_period)) {
+                (0, _) => Outcome::Dies,
+                (_, Some(1)) => Outcome::Stabilizes,
+                (_, Some(period)) => Outcome::Oscillates(period),
+                (_, None) => Outcome::Churns,
+            };
+        }
+        if growth.check(&world, generation).is_some() {
+            return Outcome::Explodes;
+        }
+    }
+    // too small a world for the growth to show its trend
+    if world.population() > start {
+        Outcome::Explodes
+    } else {
+        Outcome::Churns
+    }
+}
+
+pub fn run(world: &World, opts: &ExploreOptions) -> Result<(), String> {
+    let births = subsets(&opts.space.birth);
+    let survivals = subsets(&opts.space.survival);
+    let mut rows = Vec::with_capacity(births.len());
+    for birth in &births {
+        let mut row = Vec::with_capacity(survivals.len());
+        for survival in &survivals {
+            let rule = Rule::parse(&format!("B{}/S{}", birth, survival))?;
+            let mut start = world.clone();
+            start.set_rule(rule);
+            row.push((rule, classify(start, opts.generations, opts.max_period)));
+        }
+        rows.push(row);
+    }
+
+    // survival counts down the column headers, birth counts along the rows
+    let label = births.iter().map(|b| b.len()).max().unwrap_or(0) + 2;
+    let depth = survivals.iter().map(|s| s.len()).max().unwrap_or(0) + 1;
+    for line in 0..depth {
+        let mut header = " ".repeat(label);
+        for survival in &survivals {
+            let c = match line {
+                0 => 'S',
+                _ => survival.chars().nth(line - 1).unwrap_or(' '),
+            };
+            header.push(c);
+            header.push(' ');
+        }
+        println!("{}", header.trim_end());
+    }
+    for (birth, row) in births.iter().zip(&rows) {
+        let mut line = format!("{:<label$}", format!("B{}", birth), label = label);
+        for &(_, outcome) in row {
+            line.push(outcome.symbol());
+            line.push(' ');
+        }
+        println!("{}", line.trim_end());
+    }
+
+    let outcomes: Vec<(Rule, Outcome)> = rows.into_iter().flatten().collect();
+    let count = |f: fn(Outcome) -> bool| outcomes.iter().filter(|&&(_, o)| f(o)).count();
+    println!();
+    println!(
+        "rules        {} from {} over {} generations",
+        outcomes.len(),
+        opts.space,
+        opts.generations
+    );
+    println!(
+        "outcomes     {} die (.), {} stabilize (S), {} oscillate (O), {} explode (X), {} churn (~)",
+        count(|o| o == Outcome::Dies),
+        count(|o| o == Outcome::Stabilizes),
+        count(|o| matches!(o, Outcome::Oscillates(_))),
+        count(|o| o == Outcome::Explodes),
+        count(|o| o == Outcome::Churns)
+    );
+    let oscillators: Vec<String> = outcomes
+        .iter()
+        .filter_map(|&(rule, outcome)| match outcome {
+            Outcome::Oscillates(period) => Some(format!("{} (period {})", rule, period)),
+            _ => None,
+        })
+        .collect();
+    if !oscillators.is_empty() {
+        println!("oscillating  {}", oscillators.join(", "));
+    }
+    Ok(())
+}
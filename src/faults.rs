@@ -0,0 +1,143 @@
+//! `--faults`: the world as if it ran on hardware that fails, for seeing
+//! which constructions survive it. After every generation cells can get
+//! stuck at the state they are in for good, flip by themselves, or lose
+//! their whole row for the generation, each at a rate of its own.
+
+use conway::{Cell, Rng, World};
+
+use crate::log;
+
+/// Keeps the faults apart from the soup and the `--perturb` flips drawn
+/// from the same seed.
+const STREAM: u64 = 0xd1b5_4a32_d192_ed03;
+
+/// The chance of each fault: per cell and generation for sticking and
+/// flipping, per row and generation for a row going dead.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Faults {
+    pub stuck: f64,
+    pub flip: f64,
+    pub row: f64,
+}
+
+impl Faults {
+    /// `stuck=P,flip=P,row=P`, any of them, the rest 0.
+    pub fn parse(s: &str) -> Result<Faults, String> {
+        let err = || {
+            format!(
+                "invalid --faults '{}', expected chances from 0 to 1 like \
+                 stuck=0.0001,flip=0.001,row=0.01",
+                s
+            )
+        };
+        let mut faults = Faults::default();
+        for field in s.split(',') {
+            let (key, value) = field.split_once('=').ok_or_else(err)?;
+            let chance: f64 = value.trim().parse().map_err(|_| err())?;
+            if !(0.0..=1.0).contains(&chance) {
+                return Err(err());
+            }
+            match key.trim() {
+                "stuck" => faults.stuck = chance,
+                "flip" => faults.flip = chance,
+                "row" => faults.row = chance,
+                _ => return Err(err()),
+            }
+        }
+        Ok(faults)
+    }
+}
+
+pub struct Injector {
+    faults: Faults,
+    rng: Rng,
+    /// What each stuck cell is stuck at, row by row, sized on first use.
+    stuck: Vec<Option<Cell>>,
+    stuck_cells: u64,
+    flipped: u64,
+    dead_rows: u64,
+}
+
+impl Injector {
+    /// Faults drawn from `seed`, so a run given the same one fails the same
+    /// way.
+    pub fn new(faults: Faults, seed: u64) -> Injector {
+        Injector {
+            faults,
+            rng: Rng::new(seed ^ STREAM),
+            stuck: Vec::new(),
+            stuck_cells: 0,
+            flipped: 0,
+            dead_rows: 0,
+        }
+    }
+
+    /// Lets the faults of `generation` happen to `world`, telling `changed`
+    /// about every cell they change, and returns how many that was.
+    pub fn apply<F: FnMut(usize, usize, Cell)>(
+        &mut self,
+        world: &mut World,
+        generation: u64,
+        mut changed: F,
+    ) -> usize {
+        let (w, h) = (world.width(), world.height());
+        self.stuck.resize(w * h, None);
+        let mut changes = 0;
+        let mut set = |world: &mut World, x: usize, y: usize, cell: Cell| {
+            if world.get(x, y) != cell {
+                world.set(x, y, cell);
+                changed(x, y, cell);
+                changes += 1;
+            }
+        };
+        for y in 0..h {
+            for x in 0..w {
+                let i = y * w + x;
+                if self.stuck[i].is_none() && self.rng.chance(self.faults.stuck) {
+                    let cell = world.get(x, y);
+                    self.stuck[i] = Some(cell);
+                    self.stuck_cells += 1;
+                    let state = if cell == Cell::Live { "live" } else { "dead" };
+                    let message = format!(
+                        "generation {}: cell {},{} stuck {}",
+                        generation, x, y, state
+                    );
+                    log::debug("faults", &message);
+                }
+                match self.stuck[i] {
+                    Some(cell) => set(world, x, y, cell),
+                    None if self.rng.chance(self.faults.flip) => {
+                        let flipped = match world.get(x, y) {
+                            Cell::Live => Cell::Dead,
+                            Cell::Dead => Cell::Live,
+                        };
+                        set(world, x, y, flipped);
+                        self.flipped += 1;
+                    }
+                    None => {}
+                }
+            }
+        }
+        for y in 0..h {
+            if self.rng.chance(self.faults.row) {
+                self.dead_rows += 1;
+                log::debug(
+                    "faults",
+                    &format!("generation {}: row {} dead", generation, y),
+                );
+                for x in (0..w).filter(|&x| self.stuck[y * w + x].is_none()) {
+                    set(world, x, y, Cell::Dead);
+                }
+            }
+        }
+        changes
+    }
+
+    /// What went wrong over the run, for the end of a report.
+    pub fn describe(&self) -> String {
+        format!(
+            "{} cells stuck, {} flipped and {} rows dead",
+            self.stuck_cells, self.flipped, self.dead_rows
+        )
+    }
+}
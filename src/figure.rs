@@ -0,0 +1,141 @@
+//! How `render` and `thumb` draw worlds as images: pixels a cell, the colors
+//! of live cells, dead cells and the margin, grid lines between the cells and
+//! how cells are shrunk when several share a pixel. An `[image]` table in the
+//! config file sets them for both, and flags override it:
+//!
+//! ```toml
+//! [image]
+//! cell_size = 6            # render only, thumb fits its --size
+//! live = "#202020"         # colors as in [colors], by default theirs
+//! dead = "#ffffff"
+//! background = "#ffffff"   # the margin, by default the dead color
+//! grid = 250               # lines around every cell, none by default
+//! margin = 12
+//! filter = "nearest"       # or "average" [default]
+//! ```
+
+use crate::term::{Color, Style};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Filter {
+    /// A pixel covering several cells takes the color of the first.
+    Nearest,
+    /// A pixel covering several cells is shaded by how many are live.
+    Average,
+}
+
+impl Filter {
+    pub fn parse(s: &str) -> Result<Filter, String> {
+        match s {
+            "nearest" => Ok(Filter::Nearest),
+            "average" => Ok(Filter::Average),
+            _ => Err(format!(
+                "invalid filter '{}', expected nearest or average",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageStyle {
+    pub cell_size: usize,
+    /// Unset, the colors of the theme and `[colors]`.
+    pub live: Option<Color>,
+    pub dead: Option<Color>,
+    /// Unset, the dead color.
+    pub background: Option<Color>,
+    pub grid: Option<Color>,
+    pub margin: usize,
+    pub filter: Filter,
+}
+
+impl Default for ImageStyle {
+    fn default() -> ImageStyle {
+        ImageStyle {
+            cell_size: 8,
+            live: None,
+            dead: None,
+            background: None,
+            grid: None,
+            margin: 0,
+            filter: Filter::Average,
+        }
+    }
+}
+
+fn fill(rgb: &mut [u8], (r, g, b): (u8, u8, u8)) {
+    for pixel in rgb.chunks_mut(3) {
+        pixel.copy_from_slice(&[r, g, b]);
+    }
+}
+
+impl ImageStyle {
+    pub fn live_rgb(&self, style: &Style) -> (u8, u8, u8) {
+        self.live.map_or_else(|| style.live_rgb(), Color::rgb)
+    }
+
+    pub fn dead_rgb(&self, style: &Style) -> (u8, u8, u8) {
+        self.dead.map_or_else(|| style.dead_rgb(), Color::rgb)
+    }
+
+    pub fn background_rgb(&self, style: &Style) -> (u8, u8, u8) {
+        self.background
+            .map_or_else(|| self.dead_rgb(style), Color::rgb)
+    }
+
+    /// `columns` by `rows` cells of `cell_size` pixels colored by `cell`,
+    /// with the grid and margin: the image's width, height and RGB pixels.
+    pub fn draw(
+        &self,
+        style: &Style,
+        columns: usize,
+        rows: usize,
+        cell: impl Fn(usize, usize) -> (u8, u8, u8),
+    ) -> (usize, usize, Vec<u8>) {
+        let (width, height, cells) = self.cells(columns, rows, self.cell_size, cell);
+        let margin = self.margin;
+        let (w, h) = (width + 2 * margin, height + 2 * margin);
+        let mut rgb = vec![0; w * h * 3];
+        fill(&mut rgb, self.background_rgb(style));
+        for (y, row) in cells.chunks(width * 3).enumerate() {
+            let start = ((margin + y) * w + margin) * 3;
+            rgb[start..start + width * 3].copy_from_slice(row);
+        }
+        (w, h, rgb)
+    }
+
+    /// Just the cells, `pixels` square each and any grid lines around them,
+    /// as `draw` makes them before the margin goes around.
+    pub fn cells(
+        &self,
+        columns: usize,
+        rows: usize,
+        pixels: usize,
+        cell: impl Fn(usize, usize) -> (u8, u8, u8),
+    ) -> (usize, usize, Vec<u8>) {
+        let line = usize::from(self.grid.is_some());
+        let (width, height) = (
+            columns * (pixels + line) + line,
+            rows * (pixels + line) + line,
+        );
+        let mut rgb = vec![0; width * height * 3];
+        for (i, pixel) in rgb.chunks_mut(3).enumerate() {
+            let (px, py) = (i % width, i / width);
+            let on_line = line > 0 && (px % (pixels + 1) == 0 || py % (pixels + 1) == 0);
+            let (r, g, b) = match self.grid {
+                Some(grid) if on_line => grid.rgb(),
+                _ => cell(px / (pixels + line), py / (pixels + line)),
+            };
+            pixel.copy_from_slice(&[r, g, b]);
+        }
+        (width, height, rgb)
+    }
+
+    /// The pixels a side each of `cells` cells gets in `room` pixels along
+    /// with the grid, 0 when there's not a whole one each.
+    pub fn pixels_in(&self, room: usize, cells: usize) -> usize {
+        let line = usize::from(self.grid.is_some());
+        (room.saturating_sub(line) / cells.max(1)).saturating_sub(line)
+    }
+}
@@ -0,0 +1,143 @@
+//! `run --frames DIR`: every frame a run shows written to DIR as a PNG of
+//! its own, named after its generation by `--frame-template`, for video
+//! tools to put together or to flip through, drawn as `[image]` says.
+
+use std::fs;
+use std::path::PathBuf;
+
+use conway::{Cell, World};
+
+use crate::figure::ImageStyle;
+use crate::log;
+use crate::png;
+use crate::term::{Style, WALL_RGB};
+
+/// A file name with the generation in it: `{gen}`, or `{gen:06}` for six
+/// digits padded with zeros.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    head: String,
+    digits: usize,
+    tail: String,
+}
+
+impl Default for Template {
+    /// `gen{gen:06}.png`.
+    fn default() -> Template {
+        Template {
+            head: "gen".to_string(),
+            digits: 6,
+            tail: ".png".to_string(),
+        }
+    }
+}
+
+impl Template {
+    pub fn parse(s: &str) -> Result<Template, String> {
+        let invalid = || {
+            format!(
+                "invalid --frame-template '{}', expected a file name with {{gen}} or {{gen:06}} in it",
+                s
+            )
+        };
+        let start = s.find("{gen").ok_or_else(invalid)?;
+        let end = start + s[start..].find('}').ok_or_else(invalid)?;
+        let digits = match &s[start + "{gen".len()..end] {
+            "" => 0,
+            spec => spec
+                .strip_prefix(":0")
+                .and_then(|n| n.parse().ok())
+                .ok_or_else(invalid)?,
+        };
+        let tail = &s[end + 1..];
+        if tail.contains('/') || s[..start].contains('/') {
+            return Err(format!(
+                "--frame-template '{}' is a file name, the directory is --frames",
+                s
+            ));
+        }
+        Ok(Template {
+            head: s[..start].to_string(),
+            digits,
+            tail: tail.to_string(),
+        })
+    }
+
+    pub fn name(&self, generation: u64) -> String {
+        format!(
+            "{}{:0digits$}{}",
+            self.head,
+            generation,
+            self.tail,
+            digits = self.digits
+        )
+    }
+}
+
+pub struct Frames {
+    dir: PathBuf,
+    template: Template,
+    style: Style,
+    image: ImageStyle,
+    written: usize,
+    /// The first write that failed, kept for `finish` to report as frames
+    /// are written between generations.
+    error: Option<String>,
+}
+
+impl Frames {
+    /// Writes frames into `dir`, made first if it isn't there.
+    pub fn create(
+        dir: &str,
+        template: Template,
+        style: Style,
+        image: ImageStyle,
+    ) -> Result<Frames, String> {
+        fs::create_dir_all(dir).map_err(|e| format!("cannot make {}: {}", dir, e))?;
+        Ok(Frames {
+            dir: PathBuf::from(dir),
+            template,
+            style,
+            image,
+            written: 0,
+            error: None,
+        })
+    }
+
+    pub fn observe(&mut self, world: &World, generation: u64) {
+        if self.error.is_none() {
+            self.error = self.write(world, generation).err();
+        }
+    }
+
+    fn write(&mut self, world: &World, generation: u64) -> Result<(), String> {
+        let (live, dead) = (
+            self.image.live_rgb(&self.style),
+            self.image.dead_rgb(&self.style),
+        );
+        let (width, height, rgb) = self.image.draw(
+            &self.style,
+            world.width(),
+            world.height(),
+            |x, y| match world.get(x, y) {
+                _ if world.is_wall(x, y) => WALL_RGB,
+                Cell::Live => live,
+                Cell::Dead => dead,
+            },
+        );
+        let path = self.dir.join(self.template.name(generation));
+        let comment = format!("generation {}, rule {}", generation, world.rule());
+        png::write(&path.to_string_lossy(), width, height, &rgb, &comment)?;
+        self.written += 1;
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<(), String> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        let dir = self.dir.display();
+        log::info("run", &format!("wrote {} frames to {}", self.written, dir));
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+//! gzip files, for keeping the checkpoints and patterns of big worlds, which
+//! are mostly dead cells, at a fraction of their size. A path ending in
+//! `.gz`, like `checkpoint-100.bin.gz` or `soup.rle.gz`, is written
+//! compressed, and anything read is decompressed if it starts the way gzip
+//! files do, whatever its name.
+//!
+//! Compression finds repeats with hash chains and writes them with deflate's
+//! fixed codes, which runs of dead cells shrink well under, if not as small
+//! as gzip itself would make them. zstd isn't supported: its files are
+//! refused with an error rather than read as garbage.
+
+use std::fs;
+use std::path::Path;
+
+use crate::inflate;
+
+const MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// How far back and how long a repeat can be.
+const WINDOW: usize = 1 << 15;
+const MAX_MATCH: usize = 258;
+const MIN_MATCH: usize = 3;
+/// Earlier places with the same next three bytes tried for each repeat.
+const CHAIN: usize = 32;
+const HASH_BITS: u32 = 15;
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        *entry = (0..8).fold(n as u32, |c, _| {
+            if c & 1 != 0 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            }
+        });
+    }
+    !data.iter().fold(!0u32, |c, &b| {
+        table[((c ^ u32::from(b)) & 0xFF) as usize] ^ (c >> 8)
+    })
+}
+
+/// Whether a file at `path` is written compressed.
+pub fn is_compressed(path: &str) -> bool {
+    extension(path).as_deref() == Some("gz")
+}
+
+fn extension(path: &str) -> Option<String> {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+}
+
+/// `path` without a `.gz` or `.zst`, whose extension before says what is in
+/// the file.
+pub fn inner_path(path: &str) -> &str {
+    match extension(path).as_deref() {
+        Some(ext @ "gz") | Some(ext @ "zst") => &path[..path.len() - ext.len() - 1],
+        _ => path,
+    }
+}
+
+/// Writes bits after one another, lowest bit of each byte first.
+struct BitWriter {
+    out: Vec<u8>,
+    bit: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn put(&mut self, value: u32, n: u32) {
+        self.bit |= u64::from(value) << self.count;
+        self.count += n;
+        while self.count >= 8 {
+            self.out.push(self.bit as u8);
+            self.bit >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// A Huffman code, which goes highest bit first.
+    fn code(&mut self, code: u32, n: u32) {
+        self.put(code.reverse_bits() >> (32 - n), n);
+    }
+
+    /// A literal byte or length symbol in the fixed code.
+    fn symbol(&mut self, symbol: u16) {
+        let s = u32::from(symbol);
+        match symbol {
+            0..=143 => self.code(0x30 + s, 8),
+            144..=255 => self.code(0x190 + s - 144, 9),
+            256..=279 => self.code(s - 256, 7),
+            _ => self.code(0xC0 + s - 280, 8),
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.bit as u8);
+        }
+        self.out
+    }
+}
+
+/// The code and extra bits of a repeat's length or distance from its table.
+fn lookup(value: usize, base: &[u16], extra: &[u8]) -> (usize, u32, u32) {
+    let i = base
+        .iter()
+        .rposition(|&b| usize::from(b) <= value)
+        .unwrap_or(0);
+    (
+        i,
+        (value - usize::from(base[i])) as u32,
+        u32::from(extra[i]),
+    )
+}
+
+/// The hash of the three bytes from `i`.
+fn hash(data: &[u8], i: usize) -> usize {
+    let word = u32::from(data[i]) | u32::from(data[i + 1]) << 8 | u32::from(data[i + 2]) << 16;
+    (word.wrapping_mul(0x9E37_79B1) >> (32 - HASH_BITS)) as usize
+}
+
+/// Where each hash was last seen, and for each place in the window the one
+/// with its hash before it.
+struct Chains {
+    head: Vec<usize>,
+    prev: Vec<usize>,
+}
+
+impl Chains {
+    fn insert(&mut self, data: &[u8], i: usize) {
+        if i + MIN_MATCH <= data.len() {
+            let h = hash(data, i);
+            self.prev[i % WINDOW] = self.head[h];
+            self.head[h] = i;
+        }
+    }
+}
+
+/// `data` as a raw deflate stream of one block.
+fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut bits = BitWriter {
+        out: Vec::with_capacity(data.len() / 8),
+        bit: 0,
+        count: 0,
+    };
+    // last block, fixed codes
+    bits.put(0b011, 3);
+    let mut chains = Chains {
+        head: vec![usize::MAX; 1 << HASH_BITS],
+        prev: vec![usize::MAX; WINDOW],
+    };
+    let mut i = 0;
+    while i < data.len() {
+        let mut best = (0, 0);
+        if i + MIN_MATCH <= data.len() {
+            let mut candidate = chains.head[hash(data, i)];
+            let longest = MAX_MATCH.min(data.len() - i);
+            for _ in 0..CHAIN {
+                if candidate == usize::MAX || i - candidate >= WINDOW {
+                    break;
+                }
+                let length = data[candidate..]
+                    .iter()
+                    .zip(&data[i..i + longest])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if length > best.0 {
+                    best = (length, i - candidate);
+                    if length == longest {
+                        break;
+                    }
+                }
+                candidate = chains.prev[candidate % WINDOW];
+            }
+        }
+        let (length, distance) = best;
+        if length < MIN_MATCH {
+            bits.symbol(u16::from(data[i]));
+            chains.insert(data, i);
+            i += 1;
+            continue;
+        }
+        let (code, value, n) = lookup(length, &inflate::LENGTH_BASE, &inflate::LENGTH_EXTRA);
+        bits.symbol(257 + code as u16);
+        bits.put(value, n);
+        let (code, value, n) = lookup(distance, &inflate::DISTANCE_BASE, &inflate::DISTANCE_EXTRA);
+        bits.code(code as u32, 5);
+        bits.put(value, n);
+        for at in i..i + length {
+            chains.insert(data, at);
+        }
+        i += length;
+    }
+    bits.symbol(256);
+    bits.finish()
+}
+
+/// `data` as a gzip file.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    // no name or time, deflated, made on an unknown system
+    let mut out = vec![MAGIC[0], MAGIC[1], 8, 0, 0, 0, 0, 0, 0, 255];
+    out.extend_from_slice(&deflate(data));
+    out.extend_from_slice(&crc32(data).to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out
+}
+
+/// The data of a gzip file, all of its members one after another.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        if rest.len() < 10 || rest[..2] != MAGIC || rest[2] != 8 {
+            return Err("not a gzip file".to_string());
+        }
+        let flags = rest[3];
+        let mut pos = 10;
+        let short = || "the gzip file is cut short".to_string();
+        if flags & 4 != 0 {
+            let extra = rest.get(pos..pos + 2).ok_or_else(short)?;
+            pos += 2 + usize::from(u16::from_le_bytes([extra[0], extra[1]]));
+        }
+        // a file name and a comment, each ending in a NUL
+        for flag in [8, 16] {
+            if flags & flag != 0 {
+                let end = rest
+                    .get(pos..)
+                    .and_then(|r| r.iter().position(|&b| b == 0))
+                    .ok_or_else(short)?;
+                pos += end + 1;
+            }
+        }
+        if flags & 2 != 0 {
+            pos += 2;
+        }
+        let (member, used) = inflate::deflate(rest.get(pos..).ok_or_else(short)?)?;
+        pos += used;
+        let trailer = rest.get(pos..pos + 8).ok_or_else(short)?;
+        let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        if crc != crc32(&member) {
+            return Err("the gzip file is corrupt: its checksum doesn't match".to_string());
+        }
+        out.extend_from_slice(&member);
+        rest = &rest[pos + 8..];
+    }
+    Ok(out)
+}
+
+/// What goes in a file at `path` for `data`, compressed if the name says so.
+pub fn encode(path: &str, data: Vec<u8>) -> Result<Vec<u8>, String> {
+    match extension(path).as_deref() {
+        Some("gz") => Ok(compress(&data)),
+        Some("zst") => Err(format!(
+            "cannot write {}: zstd is not supported, use .gz",
+            path
+        )),
+        _ => Ok(data),
+    }
+}
+
+/// The bytes of the file at `path`, decompressed if it is gzip.
+pub fn read(path: &str) -> Result<Vec<u8>, String> {
+    let data = fs::read(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    if data.starts_with(&MAGIC) {
+        decompress(&data).map_err(|e| format!("{}: {}", path, e))
+    } else if data.starts_with(&ZSTD_MAGIC) {
+        Err(format!(
+            "{}: zstd files are not supported, recompress it with gzip",
+            path
+        ))
+    } else {
+        Ok(data)
+    }
+}
+
+/// The text of the file at `path`, decompressed if it is gzip.
+pub fn read_to_string(path: &str) -> Result<String, String> {
+    String::from_utf8(read(path)?).map_err(|_| format!("{}: the file is not text", path))
+}
+
+/// Writes `data` to `path`, compressed if the name says so.
+pub fn write(path: &str, data: Vec<u8>) -> Result<(), String> {
+    let data = encode(path, data)?;
+    fs::write(path, data).map_err(|e| format!("cannot write {}: {}", path, e))
+}
@@ -0,0 +1,87 @@
+//! `run --print-hash`: the state hash of the world after every generation or
+//! only the last, so two runs can be checked for coming out alike.
+
+use conway::World;
+
+use crate::cli::OutputFormat;
+use crate::jsonl;
+use crate::output;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum HashWhen {
+    Each,
+    End,
+}
+
+impl HashWhen {
+    pub fn parse(s: &str) -> Result<HashWhen, String> {
+        match s {
+            "each" => Ok(HashWhen::Each),
+            "end" => Ok(HashWhen::End),
+            _ => Err(format!(
+                "invalid value '{}' for --print-hash: expected each or end",
+                s
+            )),
+        }
+    }
+}
+
+/// One line of data, text or JSON, with the hash at `generation`.
+pub fn line(world: &World, generation: u64, format: OutputFormat) -> String {
+    let hash = world.state_hash();
+    match format {
+        OutputFormat::Text => format!("hash {} {:016x}", generation, hash),
+        OutputFormat::Jsonl => jsonl::hash(generation, hash),
+    }
+}
+
+/// Writes the hashes of a run as it goes, or of its last generation at the
+/// end.
+pub struct Hashes {
+    when: HashWhen,
+    generation: u64,
+    format: OutputFormat,
+    /// The first write that failed, kept for `finish` to report as writing
+    /// goes on between generations.
+    error: Option<String>,
+}
+
+impl Hashes {
+    /// Starts at generation `start`, with the hash of `world` there for
+    /// `HashWhen::Each`.
+    pub fn new(
+        world: &World,
+        start: u64,
+        when: HashWhen,
+        format: OutputFormat,
+    ) -> Result<Hashes, String> {
+        if when == HashWhen::Each {
+            output::data(&line(world, start, format))?;
+        }
+        Ok(Hashes {
+            when,
+            generation: start,
+            format,
+            error: None,
+        })
+    }
+
+    /// Called after every generation.
+    pub fn advanced(&mut self, world: &World) {
+        self.generation += 1;
+        if self.when == HashWhen::Each && self.error.is_none() {
+            self.error = output::data(&line(world, self.generation, self.format)).err();
+        }
+    }
+
+    /// Writes the hash of the world the run ended with, for `HashWhen::End`.
+    pub fn finish(self, world: &World) -> Result<(), String> {
+        match self.error {
+            Some(e) => Err(e),
+            None if self.when == HashWhen::End => {
+                output::data(&line(world, self.generation, self.format))
+            }
+            None => Ok(()),
+        }
+    }
+}
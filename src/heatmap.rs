@@ -0,0 +1,63 @@
+//! How often each cell was alive over a whole run, rendered as a PNG.
+//!
+//! Long chaotic runs leave glider lanes and busy regions as bright streaks,
+//! while cells that never came alive stay black.
+
+use conway::{Cell, World};
+
+use crate::png;
+
+/// Pixels per cell, the same as `render` uses by default.
+const CELL_SIZE: usize = 8;
+
+pub struct Heatmap {
+    width: usize,
+    height: usize,
+    /// Generations each cell was alive in, row-major.
+    alive: Vec<u64>,
+    rule: String,
+}
+
+impl Heatmap {
+    /// Starts counting with `world` as generation 0.
+    pub fn new(world: &World) -> Heatmap {
+        let mut heatmap = Heatmap {
+            width: world.width(),
+            height: world.height(),
+            alive: vec![0; world.width() * world.height()],
+            rule: world.rule().to_string(),
+        };
+        heatmap.observe(world);
+        heatmap
+    }
+
+    pub fn observe(&mut self, world: &World) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if world.get(x, y) == Cell::Live {
+                    self.alive[y * self.width + x] += 1;
+                }
+            }
+        }
+    }
+
+    pub fn write_png(&self, path: &str) -> Result<(), String> {
+        let max = self.alive.iter().copied().max().unwrap_or(0).max(1) as f64;
+        let (width, height) = (self.width * CELL_SIZE, self.height * CELL_SIZE);
+        let mut rgb = vec![0u8; width * height * 3];
+        for (i, pixel) in rgb.chunks_mut(3).enumerate() {
+            let (x, y) = (i % width / CELL_SIZE, i / width / CELL_SIZE);
+            let count = self.alive[y * self.width + x];
+            // the square root keeps cells visited by a single glider visible
+            // next to the ones alive all run long
+            let shade = if count == 0 {
+                0
+            } else {
+                48 + ((count as f64 / max).sqrt() * 207.0).round() as u8
+            };
+            pixel.copy_from_slice(&[shade; 3]);
+        }
+        let comment = format!("heat map, rule {}", self.rule);
+        png::write(path, width, height, &rgb, &comment)
+    }
+}
@@ -0,0 +1,71 @@
+//! The states of LifeHistory, the rule patterns on the wiki are annotated
+//! in: cells step as under the rule without the `History`, and the extra
+//! states remember where cells have been and what was marked.
+//!
+//! The odd states are alive, the even ones dead:
+//!
+//! - 0, off, and 1, on, as in any rule.
+//! - 2, history: off, but on at some point since.
+//! - 3, marked on, and 4, marked off: cells picked out by hand, which stay
+//!   marked as they die and are born again.
+//! - 5, start: on, and on in the pattern as it was given; it dies into
+//!   marked off.
+//! - 6, boundary: off for good, and no cell next to it is born.
+
+use crate::Cell;
+
+pub const OFF: u8 = 0;
+pub const ON: u8 = 1;
+pub const HISTORY: u8 = 2;
+pub const MARKED_ON: u8 = 3;
+pub const MARKED_OFF: u8 = 4;
+pub const START: u8 = 5;
+pub const BOUNDARY: u8 = 6;
+
+/// The color of each state as Golly draws it.
+pub const COLORS: [(u8, u8, u8); 7] = [
+    (48, 48, 48),
+    (0, 255, 0),
+    (0, 0, 160),
+    (255, 255, 255),
+    (216, 0, 0),
+    (255, 255, 0),
+    (96, 96, 96),
+];
+
+pub fn is_on(state: u8) -> bool {
+    state % 2 == 1
+}
+
+/// The state a cell in `state` goes to when it steps to `cell`.
+pub fn next(state: u8, cell: Cell) -> u8 {
+    match (state, cell) {
+        (BOUNDARY, _) => BOUNDARY,
+        (MARKED_OFF, Cell::Live) => MARKED_ON,
+        (state, Cell::Live) if is_on(state) => state,
+        (_, Cell::Live) => ON,
+        (ON, Cell::Dead) => HISTORY,
+        (MARKED_ON, Cell::Dead) | (START, Cell::Dead) => MARKED_OFF,
+        (state, Cell::Dead) => state,
+    }
+}
+
+/// The letter RLE writes `state` as: `.` for off and `A` onwards for the
+/// rest.
+pub fn letter(state: u8) -> char {
+    match state {
+        OFF => '.',
+        state => char::from(b'A' + state - 1),
+    }
+}
+
+/// The state a letter of multi-state RLE stands for, `b` and `o` as well as
+/// the letters `letter` writes.
+pub fn from_letter(c: char) -> Option<u8> {
+    match c {
+        '.' | 'b' => Some(OFF),
+        'o' => Some(ON),
+        'A'..='F' => Some(c as u8 - b'A' + 1),
+        _ => None,
+    }
+}
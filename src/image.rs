@@ -0,0 +1,58 @@
+//! Starting worlds from pictures: the image shrunk or stretched to the
+//! world, and every cell darker than a threshold alive.
+
+use conway::Pattern;
+
+use crate::png::Gray;
+
+/// `image` as a `width` by `height` pattern, each cell the average of the
+/// pixels it covers. With `dither` the error of each cell spreads to those
+/// right of and below it, Floyd-Steinberg style, so shades of gray become
+/// denser or sparser cells rather than all one or the other.
+pub fn pattern(image: &Gray, width: usize, height: usize, threshold: f64, dither: bool) -> Pattern {
+    let mut cells = vec![1.0; width * height];
+    if image.width > 0 && image.height > 0 {
+        for y in 0..height {
+            let (top, bottom) = span(y, height, image.height);
+            for x in 0..width {
+                let (left, right) = span(x, width, image.width);
+                let mut sum = 0.0;
+                for py in top..bottom {
+                    for px in left..right {
+                        sum += image.pixels[py * image.width + px];
+                    }
+                }
+                cells[y * width + x] = sum / ((bottom - top) * (right - left)) as f64;
+            }
+        }
+    }
+    let mut pattern = Pattern::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let value = cells[y * width + x];
+            let live = value < threshold;
+            pattern.set(x, y, live);
+            if !dither {
+                continue;
+            }
+            let error = value - if live { 0.0 } else { 1.0 };
+            let mut spread = |dx: isize, dy: usize, share: f64| {
+                let nx = x as isize + dx;
+                if nx >= 0 && (nx as usize) < width && y + dy < height {
+                    cells[(y + dy) * width + nx as usize] += error * share;
+                }
+            };
+            spread(1, 0, 7.0 / 16.0);
+            spread(-1, 1, 3.0 / 16.0);
+            spread(0, 1, 5.0 / 16.0);
+            spread(1, 1, 1.0 / 16.0);
+        }
+    }
+    pattern
+}
+
+/// The pixels cell `i` of `cells` covers out of `pixels`, at least one.
+fn span(i: usize, cells: usize, pixels: usize) -> (usize, usize) {
+    let start = i * pixels / cells;
+    (start, ((i + 1) * pixels / cells).max(start + 1))
+}
@@ -0,0 +1,135 @@
+//! Immigration, the two-player Life: every live cell belongs to a player, and
+//! a cell being born takes the side of the majority of its live neighbors.
+
+use crate::{Cell, Changes, Rng, Rule};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Player {
+    One,
+    Two,
+}
+
+/// A wrapping world whose live cells each belong to a player.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Immigration {
+    cells: Vec<Option<Player>>,
+    width: usize,
+    height: usize,
+    rule: Rule,
+}
+
+impl Immigration {
+    pub fn new(width: usize, height: usize) -> Result<Immigration, String> {
+        if width < 3 || height < 3 {
+            return Err("the world cannot be smaller than 3x3".to_string());
+        }
+        Ok(Immigration {
+            cells: vec![None; width * height],
+            width,
+            height,
+            rule: Rule::conway(),
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+    }
+
+    /// Whose the cell is, `None` if it is dead.
+    pub fn get(&self, x: usize, y: usize) -> Option<Player> {
+        self.cells[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, owner: Option<Player>) {
+        self.cells[y * self.width + x] = owner;
+    }
+
+    /// Live cells of each player.
+    pub fn score(&self) -> (usize, usize) {
+        self.cells
+            .iter()
+            .fold((0, 0), |(one, two), cell| match cell {
+                Some(Player::One) => (one + 1, two),
+                Some(Player::Two) => (one, two + 1),
+                None => (one, two),
+            })
+    }
+
+    /// Fills the left half at random with the first player's cells and
+    /// mirrors it onto the right half for the second, so neither side starts
+    /// ahead. The middle column of an odd width stays empty.
+    pub fn randomize_mirrored(&mut self, density: f64, rng: &mut Rng) {
+        let half = self.width / 2;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                self.set(x, y, None);
+            }
+            for x in 0..half {
+                if rng.chance(density) {
+                    self.set(x, y, Some(Player::One));
+                    self.set(self.width - 1 - x, y, Some(Player::Two));
+                }
+            }
+        }
+    }
+
+    /// Steps every cell by the rule. A birth goes to the player with more of
+    /// the neighbors; a tie, which only rules with even birth counts allow,
+    /// goes to the first player on cells where `x + y` is even and the second
+    /// on the others.
+    pub fn advance(&mut self) -> Changes {
+        let (w, h) = (self.width, self.height);
+        let mut next = vec![None; w * h];
+        let mut changes = Changes::default();
+        for y in 0..h {
+            for x in 0..w {
+                let (mut one, mut two) = (0u8, 0u8);
+                for dy in [h - 1, 0, 1] {
+                    for dx in [w - 1, 0, 1] {
+                        if (dx, dy) == (0, 0) {
+                            continue;
+                        }
+                        match self.get((x + dx) % w, (y + dy) % h) {
+                            Some(Player::One) => one += 1,
+                            Some(Player::Two) => two += 1,
+                            None => {}
+                        }
+                    }
+                }
+                let old = self.get(x, y);
+                let cell = if old.is_some() {
+                    Cell::Live
+                } else {
+                    Cell::Dead
+                };
+                next[y * w + x] = match (old, self.rule.next(cell, one + two)) {
+                    (_, Cell::Dead) => None,
+                    (Some(owner), Cell::Live) => Some(owner),
+                    (None, Cell::Live) if one > two => Some(Player::One),
+                    (None, Cell::Live) if two > one => Some(Player::Two),
+                    (None, Cell::Live) if (x + y) % 2 == 0 => Some(Player::One),
+                    (None, Cell::Live) => Some(Player::Two),
+                };
+                match (old.is_some(), next[y * w + x].is_some()) {
+                    (false, true) => changes.births += 1,
+                    (true, false) => changes.deaths += 1,
+                    _ => {}
+                }
+            }
+        }
+        self.cells = next;
+        changes
+    }
+}
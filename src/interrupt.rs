@@ -0,0 +1,216 @@
+//! Ctrl-C noticed between frames instead of killing the process in the
+//! middle of one, so a run can put the terminal back the way it was, finish
+//! its recordings and save the world before it exits. SIGUSR1 is noticed
+//! the same way, to save a snapshot of a long run without stopping it, and
+//! SIGWINCH, so what draws to the terminal can lay itself out again when
+//! the window is resized.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use conway::{Cell, Pattern, World};
+
+use crate::png;
+
+/// The exit status of a run ended with Ctrl-C, as a shell reports a process
+/// killed by SIGINT.
+pub const STATUS: i32 = 130;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static SNAPSHOT: AtomicBool = AtomicBool::new(false);
+static RESIZED: AtomicBool = AtomicBool::new(false);
+
+/// Pixels per cell of snapshots, the same as `render` uses by default.
+const CELL_SIZE: usize = 8;
+
+/// The most pixels a snapshot's PNG has, as many as 4096 by 4096: bigger
+/// worlds get fewer pixels a cell, down to one, and then a pixel for each
+/// square of cells, lit if any of them is alive.
+const MAX_PIXELS: usize = 1 << 24;
+
+/// From now on Ctrl-C only sets `requested`. A second one exits at once, for
+/// when whatever should be checking for the first is stuck.
+#[cfg(unix)]
+pub fn catch() {
+    use std::os::raw::{c_int, c_void};
+
+    const SIGINT: c_int = 2;
+
+    extern "C" {
+        fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+        fn write(fd: c_int, buf: *const c_void, count: usize) -> isize;
+        fn _exit(status: c_int) -> !;
+    }
+
+    extern "C" fn handle(_: c_int) {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            // only what is safe in a signal handler: colors off, cursor back
+            let restore = b"\x1b[0m\x1b[?25h\n";
+            unsafe {
+                write(1, restore.as_ptr() as *const c_void, restore.len());
+                _exit(STATUS);
+            }
+        }
+    }
+
+    unsafe {
+        signal(SIGINT, handle);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn catch() {}
+
+/// From now on SIGUSR1, as sent with `kill -USR1 PID`, only sets
+/// `snapshot_requested` rather than ending the process.
+#[cfg(unix)]
+pub fn catch_snapshots() {
+    use std::os::raw::c_int;
+
+    #[cfg(target_os = "macos")]
+    const SIGUSR1: c_int = 30;
+    #[cfg(not(target_os = "macos"))]
+    const SIGUSR1: c_int = 10;
+
+    extern "C" {
+        fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+    }
+
+    extern "C" fn handle(_: c_int) {
+        SNAPSHOT.store(true, Ordering::SeqCst);
+    }
+
+    unsafe {
+        signal(SIGUSR1, handle);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn catch_snapshots() {}
+
+/// From now on a resized terminal sets `resized`, and interrupts a wait for
+/// a key so `term::read_key` can say so.
+#[cfg(unix)]
+pub fn catch_resizes() {
+    use std::os::raw::c_int;
+
+    const SIGWINCH: c_int = 28;
+
+    extern "C" {
+        fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+        fn siginterrupt(signum: c_int, flag: c_int) -> c_int;
+    }
+
+    extern "C" fn handle(_: c_int) {
+        RESIZED.store(true, Ordering::SeqCst);
+    }
+
+    unsafe {
+        signal(SIGWINCH, handle);
+        siginterrupt(SIGWINCH, 1);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn catch_resizes() {}
+
+/// Whether Ctrl-C has been pressed since `catch`.
+pub fn requested() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Whether SIGUSR1 has come since this was last asked.
+pub fn snapshot_requested() -> bool {
+    SNAPSHOT.swap(false, Ordering::SeqCst)
+}
+
+/// Whether the terminal has been resized since this was last asked.
+pub fn resized() -> bool {
+    RESIZED.swap(false, Ordering::SeqCst)
+}
+
+/// Writes `world` to `conway-YYYYMMDD-HHMMSS.rle` in the current directory,
+/// the time in UTC, and returns the name. Another in the same second gets
+/// `-2` after the time, and so on.
+pub fn save(world: &World) -> Result<String, String> {
+    let path = format!("{}.rle", stamp());
+    Pattern::from_world(world).save(&path)?;
+    Ok(path)
+}
+
+/// Writes `world` at `generation` both as `conway-YYYYMMDD-HHMMSS.rle` and
+/// as a PNG of the same name, and returns the names.
+pub fn snapshot(world: &World, generation: u64) -> Result<(String, String), String> {
+    let stem = stamp();
+    let rle = format!("{}.rle", stem);
+    Pattern::from_world(world).save(&rle)?;
+
+    let (w, h) = (world.width(), world.height());
+    // pixels a side of a cell, or cells a side of a pixel
+    let side = (1..=CELL_SIZE)
+        .rev()
+        .find(|&side| w * h * side * side <= MAX_PIXELS)
+        .unwrap_or(1);
+    let cells = (1..)
+        .find(|&cells| w.div_ceil(cells) * h.div_ceil(cells) <= MAX_PIXELS)
+        .unwrap_or(1);
+    let (width, height) = (w.div_ceil(cells) * side, h.div_ceil(cells) * side);
+    let mut rgb = vec![0u8; width * height * 3];
+    for (i, pixel) in rgb.chunks_mut(3).enumerate() {
+        let (x, y) = (i % width / side * cells, i / width / side * cells);
+        let live = (y..(y + cells).min(h))
+            .any(|y| (x..(x + cells).min(w)).any(|x| world.get(x, y) == Cell::Live));
+        let shade = if live { 230 } else { 24 };
+        pixel.copy_from_slice(&[shade; 3]);
+    }
+    let image = format!("{}.png", stem);
+    let comment = format!("generation {}, rule {}", generation, world.rule());
+    png::write(&image, width, height, &rgb, &comment)?;
+    Ok((rle, image))
+}
+
+/// `conway-YYYYMMDD-HHMMSS` for the time now in UTC, with `-2`, `-3` and so
+/// on after it if there are snapshots of that name already.
+fn stamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (year, month, day) = civil_date(secs / 86_400);
+    let time = secs % 86_400;
+    let stem = format!(
+        "conway-{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    );
+    let taken = |stem: &str| {
+        Path::new(&format!("{}.rle", stem)).exists() || Path::new(&format!("{}.png", stem)).exists()
+    };
+    if !taken(&stem) {
+        return stem;
+    }
+    (2..)
+        .map(|n| format!("{}-{}", stem, n))
+        .find(|numbered| !taken(numbered))
+        .unwrap_or(stem)
+}
+
+/// The year, month and day `days` after 1970-01-01, by Howard Hinnant's
+/// `civil_from_days`.
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
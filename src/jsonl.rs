@@ -0,0 +1,99 @@
+//! One JSON object per generation, for piping into jq and friends.
+
+use conway::{metrics, Cell, Changes, Pattern, World};
+
+/// What each line says about the live cells besides their number.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Cells {
+    None,
+    /// `[[x, y], ...]` in row-major order.
+    List,
+    /// The whole world as an RLE string.
+    Rle,
+}
+
+impl Cells {
+    pub fn parse(s: &str) -> Result<Cells, String> {
+        match s {
+            "none" => Ok(Cells::None),
+            "list" => Ok(Cells::List),
+            "rle" => Ok(Cells::Rle),
+            _ => Err(format!(
+                "invalid value '{}' for --cells: expected none, list or rle",
+                s
+            )),
+        }
+    }
+}
+
+pub fn string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// `{"generation": .., "population": .., "births": .., "deaths": ..,
+/// "entropy": ..}`, plus the cells if asked for. Births and deaths are those
+/// of the step into `generation` alone, entropy is the world's block entropy.
+pub fn frame(world: &World, generation: u64, changes: Changes, cells: Cells) -> String {
+    let mut line = format!(
+        "{{\"generation\":{},\"population\":{},\"births\":{},\"deaths\":{},\"entropy\":{:.4}",
+        generation,
+        world.population(),
+        changes.births,
+        changes.deaths,
+        metrics::block_entropy(world)
+    );
+    match cells {
+        Cells::None => {}
+        Cells::List => {
+            let mut list = Vec::with_capacity(world.population());
+            for y in 0..world.height() {
+                for x in 0..world.width() {
+                    if world.get(x, y) == Cell::Live {
+                        list.push(format!("[{},{}]", x, y));
+                    }
+                }
+            }
+            line.push_str(&format!(",\"cells\":[{}]", list.join(",")));
+        }
+        Cells::Rle => {
+            let rle = Pattern::from_world(world).to_rle();
+            line.push_str(&format!(",\"rle\":{}", string(&rle)));
+        }
+    }
+    line.push('}');
+    line
+}
+
+/// The state hash of the world at `generation`, for `run --print-hash`.
+pub fn hash(generation: u64, hash: u64) -> String {
+    format!(
+        "{{\"hash\":{{\"generation\":{},\"value\":\"{:016x}\"}}}}",
+        generation, hash
+    )
+}
+
+/// The world a run ended with, for `run --final`.
+pub fn final_world(world: &World) -> String {
+    format!(
+        "{{\"final\":{{\"population\":{},\"rle\":{}}}}}",
+        world.population(),
+        string(&Pattern::from_world(world).to_rle())
+    )
+}
+
+/// The last line of a run that ended on a stop condition.
+pub fn stopped(reason: &str) -> String {
+    format!("{{\"stopped\":{}}}", string(reason))
+}
@@ -0,0 +1,119 @@
+//! A value of the caller's own on every cell of a world, like a nutrient
+//! level or an owner, worked out anew each generation alongside the cells so
+//! nothing has to keep a second grid in step by hand.
+
+use crate::{Cell, Changes, World};
+
+/// What a cell and its neighbors were and what the cell becomes, for working
+/// out its next value.
+pub struct Step<'a, D> {
+    pub x: usize,
+    pub y: usize,
+    pub before: Cell,
+    pub after: Cell,
+    /// The cell's value so far.
+    pub data: &'a D,
+    /// Each neighbor and its value, clockwise from the top left.
+    pub neighbors: [(Cell, &'a D); 8],
+}
+
+impl<'a, D> Step<'a, D> {
+    pub fn born(&self) -> bool {
+        self.before == Cell::Dead && self.after == Cell::Live
+    }
+
+    pub fn died(&self) -> bool {
+        self.before == Cell::Live && self.after == Cell::Dead
+    }
+
+    /// The values of the live neighbors.
+    pub fn live_neighbors(&self) -> impl Iterator<Item = &'a D> + '_ {
+        self.neighbors
+            .iter()
+            .filter(|(cell, _)| *cell == Cell::Live)
+            .map(|&(_, data)| data)
+    }
+}
+
+/// A world with a value of type `D` on each of its cells, row-major.
+#[derive(Clone)]
+pub struct Layered<D> {
+    world: World,
+    tmp: World,
+    data: Vec<D>,
+}
+
+impl<D: Clone> Layered<D> {
+    /// `world` with `fill` on every cell.
+    pub fn new(world: World, fill: D) -> Layered<D> {
+        let data = vec![fill; world.width() * world.height()];
+        Layered {
+            tmp: world.clone(),
+            world,
+            data,
+        }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// The world itself, to change its cells or rule. Values stay where they
+    /// are whatever is done to the cells under them.
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> &D {
+        &self.data[y * self.world.width() + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: D) {
+        let i = y * self.world.width() + x;
+        self.data[i] = value;
+    }
+
+    /// Every value, row-major.
+    pub fn data(&self) -> &[D] {
+        &self.data
+    }
+
+    /// Steps the world by its rule and gives every cell the value `update`
+    /// returns for it, all worked out from the values before the step.
+    pub fn advance<F: FnMut(&Step<D>) -> D>(&mut self, mut update: F) -> Changes {
+        let (w, h) = (self.world.width(), self.world.height());
+        let rule = self.world.rule();
+        let mut next = Vec::with_capacity(self.data.len());
+        for y in 0..h {
+            for x in 0..w {
+                let neighbor = |dx: usize, dy: usize| {
+                    let (nx, ny) = ((x + dx) % w, (y + dy) % h);
+                    (self.world.get(nx, ny), &self.data[ny * w + nx])
+                };
+                let (l, r, t, b) = (w - 1, 1, h - 1, 1);
+                let neighbors = [
+                    neighbor(l, t),
+                    neighbor(0, t),
+                    neighbor(r, t),
+                    neighbor(r, 0),
+                    neighbor(r, b),
+                    neighbor(0, b),
+                    neighbor(l, b),
+                    neighbor(l, 0),
+                ];
+                let live = neighbors.iter().filter(|(c, _)| *c == Cell::Live).count();
+                let before = self.world.get(x, y);
+                next.push(update(&Step {
+                    x,
+                    y,
+                    before,
+                    after: rule.next(before, live as u8),
+                    data: &self.data[y * w + x],
+                    neighbors,
+                }));
+            }
+        }
+        self.data = next;
+        self.world.advance(&mut self.tmp)
+    }
+}
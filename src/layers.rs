@@ -0,0 +1,164 @@
+//! `run --layer`: the world and more random soups stacked on it, each under
+//! its own rule, drawn over each other with their colors added up.
+
+use conway::stack::{Link, Stack};
+use conway::{Cell, Rng, Rule, World};
+
+use crate::cli::RunOptions;
+use crate::output;
+use crate::pace::Pacer;
+use crate::term::{Color, Style, RESET};
+
+/// Colors of the layers in turn, added together where they overlap.
+const COLORS: [(u8, u8, u8); 6] = [
+    (224, 64, 64),
+    (64, 224, 64),
+    (64, 96, 255),
+    (224, 224, 0),
+    (224, 0, 224),
+    (0, 224, 224),
+];
+
+/// The rule of a layer, which counts only the nearest neighbors.
+fn nearest(s: &str) -> Result<Rule, String> {
+    let rule = Rule::parse(s)?;
+    rule.nearest_only("a --layer")?;
+    Ok(rule)
+}
+
+/// Parses `RULE` or `RULE@DENSITY` for `--layer`, a soup of `density` unless
+/// it says otherwise.
+pub fn parse_layer(s: &str, density: f64) -> Result<(Rule, f64), String> {
+    match s.rsplit_once('@') {
+        Some((rule, d)) => {
+            let d: f64 = d
+                .parse()
+                .ok()
+                .filter(|d| (0.0..=1.0).contains(d))
+                .ok_or_else(|| {
+                    format!(
+                        "invalid --layer '{}', expected a density from 0 to 1 after @",
+                        s
+                    )
+                })?;
+            Ok((nearest(rule)?, d))
+        }
+        None => Ok((nearest(s)?, density)),
+    }
+}
+
+/// Parses `L:M` or `L:!M` for `--couple`, the layers counted from 1, the
+/// world itself.
+pub fn parse_link(s: &str) -> Result<Link, String> {
+    let err = || {
+        format!(
+            "invalid --couple '{}', expected LAYER:ON or LAYER:!ON like 2:1",
+            s
+        )
+    };
+    let (layer, on) = s.split_once(':').ok_or_else(err)?;
+    let (alive, on) = match on.strip_prefix('!') {
+        Some(on) => (false, on),
+        None => (true, on),
+    };
+    let number = |n: &str| n.trim().parse::<usize>().ok().filter(|&n| n >= 1);
+    match (number(layer), number(on)) {
+        (Some(layer), Some(on)) => Ok(Link {
+            layer: layer - 1,
+            on: on - 1,
+            alive,
+        }),
+        _ => Err(err()),
+    }
+}
+
+fn frame(stack: &Stack, style: &Style, color: bool) -> String {
+    let layers = stack.layers();
+    let labels: Vec<String> = layers
+        .iter()
+        .enumerate()
+        .map(|(i, layer)| {
+            let label = format!("{} {} ({})", i + 1, layer.rule(), layer.population());
+            if color {
+                let (r, g, b) = COLORS[i % COLORS.len()];
+                format!("{}{}{}", Color::Rgb(r, g, b).foreground(), label, RESET)
+            } else {
+                label
+            }
+        })
+        .collect();
+    let mut s = labels.join("  ");
+    s.push('\n');
+    for y in 0..layers[0].height() {
+        let mut current = None;
+        for x in 0..layers[0].width() {
+            let alive: Vec<usize> = (0..layers.len())
+                .filter(|&i| layers[i].get(x, y) == Cell::Live)
+                .collect();
+            match alive.as_slice() {
+                [] => {
+                    if current.take().is_some() {
+                        s.push_str(RESET);
+                    }
+                    s.push_str(&style.dead);
+                }
+                _ if color => {
+                    let add = |pick: fn((u8, u8, u8)) -> u8| {
+                        let sum: u32 = alive
+                            .iter()
+                            .map(|&i| u32::from(pick(COLORS[i % COLORS.len()])))
+                            .sum();
+                        sum.min(255) as u8
+                    };
+                    let blend = Color::Rgb(add(|c| c.0), add(|c| c.1), add(|c| c.2));
+                    // only emit escapes when the color actually changes
+                    if current != Some(blend) {
+                        s.push_str(&blend.foreground());
+                        current = Some(blend);
+                    }
+                    s.push_str(&style.live);
+                }
+                // without colors, which layer it is or that there are several
+                [only] if *only < 9 => s.push(char::from(b'1' + *only as u8)),
+                _ => s.push('#'),
+            }
+        }
+        if current.is_some() {
+            s.push_str(RESET);
+        }
+        s.push('\n');
+    }
+    s
+}
+
+/// Stacks `world` and a soup for each `--layer`, the soups seeded `seed`
+/// plus 1, plus 2 and so on, and steps them together, printing every
+/// `--every` generations and after the last one.
+pub fn run(world: &World, opts: &RunOptions, seed: u64, mut pacer: Pacer) -> Result<(), String> {
+    let (generations, every) = (opts.generations, opts.every);
+    let (style, color) = (&opts.style, opts.color);
+    let mut worlds = vec![world.clone()];
+    for (i, &(rule, density)) in opts.layers.iter().enumerate() {
+        let mut layer = World::new(world.width(), world.height())?;
+        layer.randomize(density, &mut Rng::new(seed.wrapping_add(i as u64 + 1)));
+        layer.set_rule(rule);
+        worlds.push(layer);
+    }
+    let mut stack = Stack::new(worlds)?;
+    for &link in &opts.couplings {
+        stack.link(link)?;
+    }
+    output::screen(&frame(&stack, style, color));
+    for generation in 1..=generations {
+        let changed = stack.advance().iter().any(|c| c.any());
+        if generation % every != 0 && generation != generations && changed {
+            continue;
+        }
+        pacer.wait();
+        output::screen(&frame(&stack, style, color));
+        if !changed {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,41 @@
+mod automaton;
+pub mod census;
+mod checkpoint;
+mod directional;
+pub mod distance;
+pub mod driver;
+pub mod gzip;
+pub mod history;
+pub mod immigration;
+pub mod inflate;
+pub mod layer;
+pub mod library;
+pub mod metrics;
+mod neighborhood;
+#[cfg(feature = "parallel")]
+pub mod par;
+mod pattern;
+pub mod predecessor;
+mod rng;
+mod rule;
+pub mod species;
+pub mod stack;
+mod strip;
+mod symmetry;
+mod table;
+pub mod timeline;
+mod weighted;
+mod world;
+
+#[cfg(feature = "web")]
+pub mod web;
+
+pub use automaton::{Automaton, Stepper};
+pub use checkpoint::Checkpoint;
+pub use distance::DiffReport;
+pub use pattern::{Fits, Format, Pattern, PatternMeta, Transform};
+pub use rng::Rng;
+pub use rule::Rule;
+pub use strip::Strip;
+pub use symmetry::Symmetry;
+pub use world::{Cell, Changes, Rect, World};
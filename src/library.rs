@@ -0,0 +1,41 @@
+//! Classic patterns built into the binary, in RLE.
+
+use crate::Pattern;
+
+const PATTERNS: [(&str, &str); 8] = [
+    // the phase the original hard-coded glider started in
+    ("glider", "x = 3, y = 3\n2bo$obo$b2o!"),
+    ("lwss", "x = 5, y = 4\nbo2bo$o4b$o3bo$4o!"),
+    (
+        "gosper-gun",
+        "x = 36, y = 9\n\
+         24bo11b$22bobo11b$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o14b$\n\
+         2o8bo3bob2o4bobo11b$10bo5bo7bo11b$11bo3bo20b$12b2o22b!",
+    ),
+    (
+        "pulsar",
+        "x = 13, y = 13\n\
+         2b3o3b3o2b2$o4bobo4bo$o4bobo4bo$o4bobo4bo$2b3o3b3o2b2$2b3o3b3o2b$\n\
+         o4bobo4bo$o4bobo4bo$o4bobo4bo2$2b3o3b3o!",
+    ),
+    (
+        "pentadecathlon",
+        "x = 10, y = 3\n2bo4bo2b$2ob4ob2o$2bo4bo2b!",
+    ),
+    ("r-pentomino", "x = 3, y = 3\nb2o$2ob$bo!"),
+    ("acorn", "x = 7, y = 3\nbo5b$3bo3b$2o2b3o!"),
+    ("diehard", "x = 8, y = 3\n6bob$2o6b$bo3b3o!"),
+];
+
+/// Names of the built-in patterns, in a fixed order.
+pub fn names() -> impl Iterator<Item = &'static str> {
+    PATTERNS.iter().map(|&(name, _)| name)
+}
+
+/// The built-in pattern called `name`, if there is one.
+pub fn get(name: &str) -> Option<Pattern> {
+    PATTERNS
+        .iter()
+        .find(|&&(n, _)| n == name)
+        .map(|&(_, rle)| Pattern::parse_rle(rle).expect("built-in patterns are valid RLE"))
+}
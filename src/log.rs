@@ -0,0 +1,116 @@
+//! Messages about what a command is doing, on stderr so they never mix with
+//! the frames and reports on stdout. `-v` and `-vv` let more of them
+//! through, and `--log-format json` writes each as a JSON object on a line of
+//! its own for log collectors to filter.
+
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::jsonl;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    /// What a command says without being asked, like the seed it drew.
+    Info,
+    /// `-v`: connections, commands and sessions.
+    Debug,
+    /// `-vv`: every generation.
+    Trace,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Result<Format, String> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("invalid log format '{}', expected text or json", s)),
+        }
+    }
+}
+
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Lets `verbose` levels past `Info` through, in `format`, from here on.
+pub fn init(verbose: u8, format: Format) {
+    let level = (Level::Info as u8 + verbose).min(Level::Trace as u8);
+    MAX_LEVEL.store(level, Ordering::Relaxed);
+    JSON.store(format == Format::Json, Ordering::Relaxed);
+}
+
+/// Whether messages at `level` are written, to skip working them out when
+/// they wouldn't be.
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= MAX_LEVEL.load(Ordering::Relaxed)
+}
+
+/// Writes `message` about `target`, the part of the program it comes from,
+/// if `level` is let through. As text, info is the message alone as it
+/// always was, errors and warnings say what they are first, and the rest
+/// their level and target.
+pub fn log(level: Level, target: &str, message: &str) {
+    if !enabled(level) {
+        return;
+    }
+    let line = if JSON.load(Ordering::Relaxed) {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64());
+        format!(
+            "{{\"time\":{:.3},\"level\":\"{}\",\"target\":\"{}\",\"message\":{}}}",
+            time,
+            level.name(),
+            target,
+            jsonl::string(message)
+        )
+    } else {
+        match level {
+            Level::Info => message.to_string(),
+            Level::Error | Level::Warn => format!("{}: {}", level.name(), message),
+            Level::Debug | Level::Trace => format!("{} {}: {}", level.name(), target, message),
+        }
+    };
+    // stderr going away is no reason to stop a run
+    let _ = writeln!(io::stderr().lock(), "{}", line);
+}
+
+pub fn error(target: &str, message: &str) {
+    log(Level::Error, target, message);
+}
+
+pub fn warn(target: &str, message: &str) {
+    log(Level::Warn, target, message);
+}
+
+pub fn info(target: &str, message: &str) {
+    log(Level::Info, target, message);
+}
+
+pub fn debug(target: &str, message: &str) {
+    log(Level::Debug, target, message);
+}
+
+pub fn trace(target: &str, message: &str) {
+    log(Level::Trace, target, message);
+}
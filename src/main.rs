@@ -1,147 +1,1420 @@
 use std::env;
-use std::time::Duration;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
 
-#[derive(Copy, Clone, Debug)]
-enum Cell {
-    Dead,
-    Live,
+use conway::immigration::Immigration;
+use conway::predecessor::{self, Predecessor};
+use conway::species::Species;
+use conway::{
+    census, gzip, history, library, metrics, Cell, Changes, Checkpoint, Format, Pattern, Rng, Rule,
+    Symmetry, World,
+};
+
+use crate::cli::{
+    AnalyzeOptions, BenchOptions, Command, ConvertOptions, DistributeOptions, EditOptions,
+    ExploreOptions, OutputFormat, PatternSource, RenderOptions, ReplOptions, RunOptions,
+    ScrubOptions, ServeOptions, SpeciesOptions, StepOptions, SweepOptions, VerifyOptions,
+    VersusOptions, WorldOptions,
+};
+use crate::config::Config;
+use crate::contact::ContactSheet;
+use crate::control::Control;
+use crate::faults::Injector;
+use crate::frames::Frames;
+use crate::hashes::Hashes;
+use crate::pace::Pacer;
+use crate::perturb::Perturber;
+use crate::progress::Progress;
+use crate::record::Recorders;
+use crate::reload::InputWatch;
+use crate::render::{Frame, Renderer};
+use crate::replay::Recorder;
+use crate::script::{Answer, Script};
+use crate::session::Session;
+use crate::sparkline::Sparkline;
+use crate::stop::{Breakpoints, Growth, Sighting, Until, Watch};
+use crate::summary::Summary;
+use crate::telnet::Telnet;
+use crate::term::Style;
+use crate::trace::Trace;
+
+mod audio;
+mod batch;
+mod caps;
+mod cavegen;
+mod check;
+mod cli;
+mod clipboard;
+mod collide;
+mod compare;
+mod config;
+mod contact;
+mod control;
+#[cfg(unix)]
+mod dbus;
+mod demo;
+mod diff;
+mod distributed;
+mod ecosystem;
+mod editor;
+mod engines;
+mod ensemble;
+mod enumerate;
+mod evolve;
+mod explore;
+mod faults;
+mod figure;
+mod frames;
+mod hashes;
+mod heatmap;
+mod image;
+mod interrupt;
+mod json;
+mod jsonl;
+mod layers;
+mod log;
+mod memory;
+mod midi;
+mod osc;
+mod output;
+mod pace;
+mod perturb;
+mod png;
+mod pool;
+mod progress;
+mod prometheus;
+mod record;
+mod reload;
+mod render;
+mod repl;
+mod replay;
+mod screensaver;
+mod script;
+mod scrub;
+#[cfg(feature = "sdl")]
+mod sdl;
+mod search;
+mod serve;
+mod session;
+mod soak;
+mod soup;
+mod sparkline;
+mod stop;
+mod summary;
+mod sweep;
+mod telnet;
+mod term;
+mod theme;
+mod thumb;
+mod trace;
+mod verify;
+mod versus;
+mod ws;
+
+/// Exit status of runs ended by `--stop-on-growth`, so scripts can tell them
+/// from the ones that ran their course (0) and from errors (1 and 2).
+const GROWTH_STATUS: i32 = 3;
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    match cli::take_logging(&mut args) {
+        Ok((verbose, format)) => log::init(verbose, format),
+        Err(e) => {
+            eprintln!("error: {}\n\nRun 'conway --help' for usage.", e);
+            process::exit(2);
+        }
+    }
+    let config = cli::take_config(&mut args).and_then(|path| {
+        let profile = cli::take_profile(&mut args)?;
+        let theme = cli::take_theme(&mut args)?;
+        let mut config = Config::load(path.as_deref())?;
+        config.apply_env()?;
+        if let Some(name) = profile {
+            config.apply_profile(&name)?;
+        }
+        if let Some(name) = theme {
+            config.apply_theme(&name)?;
+        }
+        let caps = config.fit_terminal();
+        term::set_depth(caps.depth);
+        log::debug("term", &format!("the terminal shows {}", caps));
+        Ok(config)
+    });
+    let command = match config.and_then(|config| cli::parse(args, &config)) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("error: {}\n\nRun 'conway --help' for usage.", e);
+            process::exit(2);
+        }
+    };
+    let result = match command {
+        Command::Run(opts) => run(*opts),
+        Command::Edit(opts) => edit(opts),
+        Command::Convert(opts) => convert(opts),
+        Command::Analyze(opts) => analyze(opts),
+        Command::Bench(opts) => bench(opts),
+        Command::Render(opts) => render(opts),
+        Command::Step(opts) => step(opts),
+        Command::Search(opts) => search::run(&opts, seed_or_time(opts.seed)),
+        Command::Evolve(opts) => evolve::run(&opts, seed_or_time(opts.seed)),
+        Command::Serve(opts) => serve(opts),
+        Command::Worker(opts) => distributed::work(&opts),
+        Command::Distribute(opts) => distribute(opts),
+        Command::Replay(opts) => replay::play(&opts),
+        Command::Soak(opts) => soak::run(&opts, seed_or_time(opts.seed)),
+        Command::Explore(opts) => explore(opts),
+        Command::Verify(opts) => verify(opts),
+        Command::Versus(opts) => versus(opts),
+        Command::Scrub(opts) => scrub(opts),
+        Command::Species(opts) => species(opts),
+        Command::Cavegen(opts) => cavegen::run(&opts, seed_or_time(opts.seed)),
+        Command::Enumerate(opts) => enumerate::run(&opts),
+        Command::Collide(opts) => collide::run(&opts),
+        Command::Repl(opts) => repl(opts),
+        Command::Batch(opts) => batch::run(opts, initial_world),
+        Command::Sweep(opts) => sweep(opts),
+        Command::Check(opts) => check::run(&opts),
+        Command::Thumb(opts) => thumb::run(&opts),
+        Command::Diff(opts) => diff::run(&opts).map_err(|e| {
+            // 1 is for patterns that differ
+            log::error("main", &e);
+            process::exit(2);
+        }),
+        Command::Help(usage) => {
+            print!("{}", usage);
+            Ok(())
+        }
+        Command::Version => {
+            println!("conway {}", env!("CARGO_PKG_VERSION"));
+            Ok(())
+        }
+    };
+    if let Err(e) = result {
+        log::error("main", &e);
+        process::exit(1);
+    }
+}
+
+/// Places the pattern in the middle of the world, adopting its rule if it has
+/// one and its name, author and comments.
+fn place_centered(world: &mut World, pattern: &Pattern) -> Result<(), String> {
+    if pattern.width() > world.width() || pattern.height() > world.height() {
+        return Err(format!(
+            "the {}x{} pattern does not fit in a {}x{} world, use --width and --height or --fit",
+            pattern.width(),
+            pattern.height(),
+            world.width(),
+            world.height()
+        ));
+    }
+    let x = (world.width() - pattern.width()) / 2;
+    let y = (world.height() - pattern.height()) / 2;
+    // a History rule first, for the world to keep the states it stamps
+    if let Some(rule) = pattern.rule() {
+        world.set_rule(rule);
+    }
+    world.stamp(pattern, x, y);
+    world.set_meta(pattern.meta().clone());
+    Ok(())
+}
+
+/// `seed`, or one from the clock that is printed so the run can be repeated.
+fn seed_or_time(seed: Option<u64>) -> u64 {
+    seed.unwrap_or_else(|| {
+        let seed = Rng::time_seed();
+        log::info("main", &format!("seed {}", seed));
+        seed
+    })
+}
+
+fn seeded_rng(seed: Option<u64>) -> Rng {
+    Rng::new(seed_or_time(seed))
+}
+
+/// An empty world, or a random soup with `--random`.
+fn blank_world(opts: &WorldOptions) -> Result<World, String> {
+    memory::check(opts.width, opts.height, opts.max_mem)?;
+    let mut world = World::new(opts.width, opts.height)?;
+    if let Some(rule) = opts.default_rule {
+        world.set_rule(rule);
+    }
+    if opts.walls_alive {
+        world.set_walls_alive(true);
+    }
+    if let Some(density) = opts.random {
+        opts.random_mode
+            .fill(&mut world, density, &mut seeded_rng(opts.seed));
+    }
+    Ok(world)
+}
+
+/// With `--fit`, `opts` sized to the live cells of `pattern` and the margin
+/// around them, and the pattern cropped to them.
+fn fit(
+    opts: &WorldOptions,
+    pattern: Option<Pattern>,
+) -> Result<(WorldOptions, Option<Pattern>), String> {
+    let mut opts = opts.clone();
+    let margin = match opts.fit {
+        Some(margin) => margin,
+        None => return Ok((opts, pattern)),
+    };
+    if opts.sized {
+        return Err("--fit sizes the world itself, so --width and --height cannot be given".into());
+    }
+    if let Some(PatternSource::Image(_)) = opts.pattern {
+        return Err(
+            "--fit cannot size the world to --seed-image, which is fitted to the world".into(),
+        );
+    }
+    let pattern = pattern
+        .ok_or("--fit needs a pattern to size the world to")?
+        .crop();
+    opts.width = (pattern.width() + 2 * margin).max(1);
+    opts.height = (pattern.height() + 2 * margin).max(1);
+    Ok((opts, Some(pattern)))
 }
 
-impl Cell {
-    fn n(self) -> u8 {
-        match self {
-            Cell::Dead => 0,
-            Cell::Live => 1,
+/// The pattern `source` names, `world` saying how to read text and images.
+fn load_pattern(source: &PatternSource, world: &WorldOptions) -> Result<Pattern, String> {
+    match source {
+        PatternSource::File(path) => Pattern::load_within(path, &memory::fits(world.max_mem)),
+        PatternSource::Builtin(pattern) => Ok(pattern.clone()),
+        PatternSource::Text(path) => {
+            let text =
+                fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+            Ok(Pattern::from_text(&text).scaled(world.text_scale))
         }
+        PatternSource::Image(path) => Ok(image::pattern(
+            &png::read(path)?,
+            world.width,
+            world.height,
+            world.threshold,
+            world.dither,
+        )),
     }
 }
 
-#[derive(Clone)]
-struct World {
-    cells: Vec<Vec<Cell>>,
-    width: usize,
-    height: usize,
+/// Stamps each `--place` pattern at its offset, refusing ones that would wrap
+/// around the edge or overlap an earlier one, and covers the world in each
+/// `--tile` pattern.
+fn place_all(world: &mut World, opts: &WorldOptions) -> Result<(), String> {
+    let mut taken: Vec<(usize, usize, usize, usize)> = Vec::new();
+    for placement in &opts.placements {
+        let pattern = placement
+            .transforms
+            .iter()
+            .fold(load_pattern(&placement.source, opts)?, |pattern, &t| {
+                pattern.transformed(t)
+            });
+        let (x, y) = (placement.x, placement.y);
+        if let Some((across, down)) = placement.every {
+            world.tile(&pattern, across, down, x as i64, y as i64);
+            if let Some(rule) = pattern.rule() {
+                world.set_rule(rule);
+            }
+            continue;
+        }
+        let (right, bottom) = (x + pattern.width(), y + pattern.height());
+        if right > world.width() || bottom > world.height() {
+            return Err(format!(
+                "the {}x{} pattern placed at {},{} does not fit in a {}x{} world",
+                pattern.width(),
+                pattern.height(),
+                x,
+                y,
+                world.width(),
+                world.height()
+            ));
+        }
+        if let Some(&(ox, oy, _, _)) = taken
+            .iter()
+            .find(|&&(ox, oy, or, ob)| x < or && ox < right && y < ob && oy < bottom)
+        {
+            return Err(format!(
+                "the pattern placed at {},{} overlaps the one placed at {},{}",
+                x, y, ox, oy
+            ));
+        }
+        taken.push((x, y, right, bottom));
+        world.stamp(&pattern, x, y);
+        if let Some(rule) = pattern.rule() {
+            world.set_rule(rule);
+        }
+    }
+    Ok(())
 }
 
-impl World {
-    fn new(width: usize, height: usize) -> Result<World, String> {
-        if width < 3 || height < 3 {
-            return Err("the world cannot be smaller than 3x3".to_string());
+/// The starting world: the given patterns, a random soup, or a glider.
+fn initial_world(opts: &WorldOptions) -> Result<World, String> {
+    let pattern = match &opts.pattern {
+        Some(source) => Some(load_pattern(source, opts)?),
+        None if opts.random.is_some() || !opts.placements.is_empty() => None,
+        None => library::get("glider"),
+    };
+    let (fitted, pattern) = fit(opts, pattern)?;
+    let opts = &fitted;
+    let mut world = blank_world(opts)?;
+    if let Some(pattern) = pattern {
+        place_centered(&mut world, &pattern)?;
+    }
+    place_all(&mut world, opts)?;
+    for &(rect, cell) in &opts.emitters {
+        if rect.x + rect.width > world.width() || rect.y + rect.height > world.height() {
+            return Err(format!(
+                "the {}x{} block of emitters at {},{} does not fit in a {}x{} world",
+                rect.width,
+                rect.height,
+                rect.x,
+                rect.y,
+                world.width(),
+                world.height()
+            ));
         }
-        let row = vec![Cell::Dead; width];
-        let cells = vec![row; height];
-        let world = World {
-            cells,
-            width,
-            height,
+        for y in rect.y..rect.y + rect.height {
+            for x in rect.x..rect.x + rect.width {
+                world.set_emitter(x, y, Some(cell));
+            }
+        }
+    }
+    if let Some(rule) = opts.rule {
+        world.set_rule(rule);
+    }
+    match opts.radius {
+        Some(radius) if world.rule().radius() != radius => Err(format!(
+            "--radius {} needs a --rule giving ranges of counts like B34..45/S33..57",
+            radius
+        )),
+        _ => Ok(world),
+    }
+}
+
+fn run(mut opts: RunOptions) -> Result<(), String> {
+    // the window has its own way of being closed
+    if !opts.window {
+        interrupt::catch();
+        interrupt::catch_resizes();
+    }
+    interrupt::catch_snapshots();
+    if opts.screensaver {
+        let pacer = match opts.fps {
+            Some(fps) => Pacer::fps(fps),
+            None => Pacer::delay(opts.delay.unwrap_or(100)),
+        };
+        screensaver::run(pacer, seeded_rng(opts.world.seed))?;
+        println!("{}", term::RESET);
+        process::exit(interrupt::STATUS);
+    }
+    if opts.demo {
+        let pacer = match opts.fps {
+            Some(fps) => Pacer::fps(fps),
+            None => Pacer::delay(opts.delay.unwrap_or(100)),
         };
-        Ok(world)
-    }
-
-    #[rustfmt::skip]
-    fn count_neighbors(&self, x: usize, y: usize) -> u8 {
-        let t = if y == 0 { self.height - 1 } else { y - 1 };
-        let b = if y == self.height - 1 { 0 } else { y + 1 };
-        let l = if x == 0 { self.width - 1 } else { x - 1 };
-        let r = if x == self.width - 1 { 0 } else { x + 1 };
-        self.cells[t][l].n() +
-        self.cells[t][x].n() +
-        self.cells[t][r].n() +
-        self.cells[y][l].n() +
-        self.cells[y][r].n() +
-        self.cells[b][l].n() +
-        self.cells[b][x].n() +
-        self.cells[b][r].n()
-    }
-
-    fn set(&mut self, x: usize, y: usize, value: Cell) {
-        self.cells[y][x] = value;
-    }
-
-    /// Any live cell with fewer than two live neighbors dies, as if by underpopulation.
-    /// Any live cell with two or three live neighbors lives on to the next generation.
-    /// Any live cell with more than three live neighbors dies, as if by overpopulation.
-    /// Any dead cell with exactly three live neighbors becomes a live cell, as if by reproduction.
-    fn advance(&mut self, tmp: &mut World) {
-        for (y, row) in self.cells.iter().enumerate() {
-            for (x, cell) in row.iter().enumerate() {
-                let num_neighbors = self.count_neighbors(x, y);
-
-                #[rustfmt::skip]
-                let new_cell = match (*cell, num_neighbors) {
-                    (Cell::Live, 0..=1) => Cell::Dead,
-                    (Cell::Live, 2..=3) => Cell::Live,
-                    (Cell::Live, _    ) => Cell::Dead,
-                    (Cell::Dead, 3    ) => Cell::Live,
-                    _                   => *cell,
-                };
-                tmp.set(x, y, new_cell);
-            }
-        }
-
-        for (y, row) in self.cells.iter_mut().enumerate() {
-            for (x, cell) in row.iter_mut().enumerate() {
-                *cell = tmp.cells[y][x];
-            }
-        }
-    }
-
-    fn as_string(&self) -> String {
-        let mut s = String::with_capacity(self.height * (self.width + 1));
-        for row in self.cells.iter() {
-            for cell in row {
-                let symbol = match cell {
-                    Cell::Dead => '.',
-                    Cell::Live => 'O',
-                };
-                s.push(symbol);
-            }
-            s.push('\n');
-        }
-        s
-    }
-}
-
-fn main() -> Result<(), String> {
-    let width = env::args()
-        .nth(1)
-        .map(|n| n.parse::<usize>().expect("invalid width"))
-        .unwrap_or(40);
-    let height = env::args()
-        .nth(2)
-        .map(|n| n.parse::<usize>().expect("invalid height"))
-        .unwrap_or(20);
-    let generations = env::args()
-        .nth(3)
-        .map(|n| n.parse::<u64>().expect("invalid generations"))
-        .unwrap_or(10);
-    let delay = env::args()
-        .nth(4)
-        .map(|n| n.parse::<u64>().expect("invalid delay"))
-        .unwrap_or(500);
+        demo::run(pacer, Duration::from_secs(opts.demo_seconds))?;
+        println!("{}", term::RESET);
+        process::exit(interrupt::STATUS);
+    }
+    let session = opts
+        .load_session
+        .as_deref()
+        .map(|path| Session::load(path, opts.world.max_mem))
+        .transpose()?;
+    if let Some(session) = &session {
+        session.apply(&mut opts);
+    }
+    // nothing is watching JSON lines scroll by, so they aren't slowed down
+    let delay = match (opts.fps, opts.output) {
+        (Some(fps), _) => 1000 / u64::from(fps),
+        (None, OutputFormat::Text) => opts.delay.unwrap_or(500),
+        (None, OutputFormat::Jsonl) => opts.delay.unwrap_or(0),
+    };
+    let pacer = match opts.fps {
+        Some(fps) => Pacer::fps(fps),
+        None => Pacer::delay(delay),
+    };
 
-    let mut world = World::new(width, height)?;
+    let (mut world, start, seed) = match (&session, &opts.resume) {
+        (Some(session), _) => {
+            log::info(
+                "run",
+                &format!(
+                    "carrying on the session at generation {}",
+                    session.generation
+                ),
+            );
+            (session.world.clone(), session.generation, session.seed)
+        }
+        (None, Some(path)) => {
+            let checkpoint = Checkpoint::load_within(path, &memory::fits(opts.world.max_mem))
+                .map_err(|e| format!("cannot resume: {}", e))?;
+            log::info(
+                "run",
+                &format!("resuming at generation {}", checkpoint.generation),
+            );
+            (checkpoint.world, checkpoint.generation, checkpoint.seed)
+        }
+        (None, None) => {
+            // checkpoints and recordings keep the soup's seed, so it has to be
+            // settled here
+            let kept = opts.checkpoint_every.is_some() || opts.record.is_some();
+            // the perturbation is drawn from the seed whatever the start
+            if opts.perturb.is_some() || opts.faults.is_some() {
+                opts.world.seed = Some(seed_or_time(opts.world.seed));
+            }
+            if kept && opts.world.random.is_some() {
+                opts.world.seed = Some(seed_or_time(opts.world.seed));
+            }
+            let seed = opts.world.random.and(opts.world.seed);
+            (initial_world(&opts.world)?, 0, seed)
+        }
+    };
+    if opts.style.trails > 0 {
+        world.track_ages();
+    }
+    opts.style.use_rule_colors(world.rule());
+
+    if let Some(rules) = opts.compare {
+        return compare::run(&world, rules, opts.generations, opts.every, pacer);
+    }
+    if !opts.layers.is_empty() {
+        return layers::run(&world, &opts, seed_or_time(opts.world.seed), pacer);
+    }
+
+    if let Some(path) = &opts.data_file {
+        output::to_file(path)?;
+    }
+    let mut recorders = Recorders {
+        audio: opts
+            .audio
+            .clone()
+            .map(|path| {
+                audio::Sonifier::create(&path, &world, opts.scale, opts.volume, delay)
+                    .map(|sonifier| (path.clone(), sonifier))
+                    .map_err(|e| format!("cannot write {}: {}", path, e))
+            })
+            .transpose()?,
+        midi: opts
+            .midi
+            .as_deref()
+            .map(|port| midi::Midi::open(port, &world, opts.scale))
+            .transpose()?,
+        osc: opts
+            .osc
+            .as_deref()
+            .map(|target| osc::Osc::new(target, opts.osc_rows))
+            .transpose()?,
+        heatmap: opts
+            .heatmap
+            .clone()
+            .map(|path| (path, heatmap::Heatmap::new(&world))),
+        contact_sheet: opts
+            .contact_sheet
+            .clone()
+            .map(|path| (path, ContactSheet::new(&world))),
+        frames: opts
+            .frames
+            .as_deref()
+            .map(|dir| {
+                let template = opts.frame_template.clone().unwrap_or_default();
+                Frames::create(dir, template, opts.style.clone(), opts.image.clone())
+            })
+            .transpose()?,
+        summary: if opts.summary {
+            Some(Summary::new(&world, opts.max_period, opts.output))
+        } else {
+            None
+        },
+        replay: opts
+            .record
+            .as_deref()
+            .map(|path| Recorder::create(path, &world, start, seed))
+            .transpose()?,
+        trace: if opts.watched.is_empty() {
+            None
+        } else {
+            let log = opts.watch_log.as_deref();
+            Some(Trace::create(log, &world, &opts.watched, start)?)
+        },
+        hashes: opts
+            .print_hash
+            .map(|when| Hashes::new(&world, start, when, opts.output))
+            .transpose()?,
+    };
+
+    let mut grew = false;
+    if opts.window {
+        #[cfg(feature = "sdl")]
+        {
+            let watch = opts
+                .until
+                .map(|until| Watch::new(until, &world, opts.max_period));
+            let breakpoints = Breakpoints::new(&world, &opts.breakpoints)?;
+            let mut tmp = world.clone();
+            sdl::run(
+                &mut world,
+                &mut tmp,
+                &opts,
+                delay,
+                watch,
+                breakpoints,
+                &mut recorders,
+            )?;
+        }
+    } else {
+        grew = run_terminal(
+            &mut world,
+            &opts,
+            pacer,
+            &mut recorders,
+            start,
+            seed,
+            session.as_ref(),
+        )?;
+    }
+    if opts.print_final {
+        match opts.output {
+            OutputFormat::Text => output::data(Pattern::from_world(&world).to_rle().trim_end())?,
+            OutputFormat::Jsonl => output::data(&jsonl::final_world(&world))?,
+        }
+    }
+    if let Some(hashes) = recorders.hashes.take() {
+        hashes.finish(&world)?;
+    }
+    recorders.finish()?;
+    output::finish()?;
+    if interrupt::requested() {
+        process::exit(interrupt::STATUS);
+    }
+    if grew {
+        process::exit(GROWTH_STATUS);
+    }
+    Ok(())
+}
+
+fn serve(mut opts: ServeOptions) -> Result<(), String> {
+    if opts.record.is_some() && opts.world.random.is_some() {
+        opts.world.seed = Some(seed_or_time(opts.world.seed));
+    }
+    let world = initial_world(&opts.world)?;
+    let seed = opts.world.random.and(opts.world.seed);
+    interrupt::catch_snapshots();
+    let replay = opts
+        .record
+        .as_deref()
+        .map(|path| Recorder::create(path, &world, 0, seed))
+        .transpose()?;
+    serve::run(world, replay, &opts)
+}
+
+fn distribute(opts: DistributeOptions) -> Result<(), String> {
+    let world = &opts.world;
+    let uniform = world.random_mode == soup::Mode::Uniform;
+    let start = match world.random {
+        Some(density) if uniform && world.pattern.is_none() && world.placements.is_empty() => {
+            distributed::Start::Soup {
+                width: world.width,
+                height: world.height,
+                rule: world
+                    .rule
+                    .or(world.default_rule)
+                    .unwrap_or_else(Rule::conway),
+                density,
+                rng: seeded_rng(world.seed),
+            }
+        }
+        _ => distributed::Start::World(Box::new(initial_world(world)?)),
+    };
+    distributed::run(start, &opts)
+}
+
+fn explore(opts: ExploreOptions) -> Result<(), String> {
+    let world = initial_world(&opts.world)?;
+    explore::run(&world, &opts)
+}
+
+fn verify(mut opts: VerifyOptions) -> Result<(), String> {
+    // the soup's seed goes in the file, so it has to be settled here
+    if opts.world.random.is_some() {
+        opts.world.seed = Some(seed_or_time(opts.world.seed));
+    }
+    verify::run(&opts, initial_world)
+}
+
+fn sweep(opts: SweepOptions) -> Result<(), String> {
+    let seed = seed_or_time(opts.world.seed);
+    sweep::run(&opts, seed, initial_world)
+}
+
+fn versus(opts: VersusOptions) -> Result<(), String> {
+    let world = &opts.world;
+    if world.random_mode != soup::Mode::Uniform {
+        return Err("--random-mode cannot be used with versus".to_string());
+    }
+    let mut game = Immigration::new(world.width, world.height)?;
+    if let Some(rule) = world.rule.or(world.default_rule) {
+        rule.nearest_only("versus")?;
+        game.set_rule(rule);
+    }
+    if let Some(density) = world.random.filter(|&d| d > 0.0) {
+        game.randomize_mirrored(density, &mut seeded_rng(world.seed));
+    }
+    interrupt::catch_resizes();
+    versus::play(game, &opts)
+}
+
+fn scrub(opts: ScrubOptions) -> Result<(), String> {
+    let world = initial_world(&opts.world)?;
+    interrupt::catch_resizes();
+    scrub::run(world, &opts)
+}
+
+fn species(opts: SpeciesOptions) -> Result<(), String> {
+    let world = &opts.world;
+    if world.random_mode != soup::Mode::Uniform {
+        return Err("--random-mode cannot be used with species".to_string());
+    }
+    let mut game = Species::new(world.width, world.height, opts.species)?;
+    if let Some(rule) = world.rule.or(world.default_rule) {
+        rule.nearest_only("species")?;
+        game.set_rule(rule);
+    }
+    game.set_ties(opts.ties);
+    for &(other, species) in &opts.dead_to {
+        game.set_blind(species, other, true);
+    }
+    if let Some(density) = world.random {
+        game.randomize(density, &mut seeded_rng(world.seed));
+    }
+    // each pattern goes to the next species, wrapping around the edges
+    for (i, placement) in world.placements.iter().enumerate() {
+        let pattern = placement
+            .transforms
+            .iter()
+            .fold(load_pattern(&placement.source, world)?, |pattern, &t| {
+                pattern.transformed(t)
+            });
+        for (x, y) in (0..pattern.height())
+            .flat_map(|y| (0..pattern.width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| pattern.get(x, y))
+        {
+            let (gx, gy) = (
+                (placement.x + x) % game.width(),
+                (placement.y + y) % game.height(),
+            );
+            game.set(gx, gy, Some(i % opts.species));
+        }
+    }
+    interrupt::catch();
+    interrupt::catch_resizes();
+    ecosystem::run(game, &opts)
+}
+
+fn repl(opts: ReplOptions) -> Result<(), String> {
+    let world = &opts.world;
+    // an empty world to build on rather than the usual glider
+    let start = if world.pattern.is_none() && world.placements.is_empty() {
+        let mut blank = blank_world(world)?;
+        if let Some(rule) = world.rule {
+            blank.set_rule(rule);
+        }
+        blank
+    } else {
+        initial_world(world)?
+    };
+    repl::run(start, world.seed)
+}
+
+fn edit(opts: EditOptions) -> Result<(), String> {
+    let pattern = if fs::metadata(&opts.path).is_ok() {
+        Some(Pattern::load_within(
+            &opts.path,
+            &memory::fits(opts.world.max_mem),
+        )?)
+    } else {
+        match &opts.world.pattern {
+            Some(source) => Some(load_pattern(source, &opts.world)?),
+            None => None,
+        }
+    };
+    let (fitted, pattern) = fit(&opts.world, pattern)?;
+    let mut world = blank_world(&fitted)?;
+    if let Some(pattern) = pattern {
+        place_centered(&mut world, &pattern)?;
+    }
+    if let Some(rule) = opts.world.rule {
+        world.set_rule(rule);
+    }
+    interrupt::catch_resizes();
+    editor::run(world, &opts.path)
+}
+
+fn convert(opts: ConvertOptions) -> Result<(), String> {
+    let mut pattern = match opts.from {
+        Some(format) => {
+            let text = gzip::read_to_string(&opts.input)?;
+            Pattern::parse_as_within(&text, format, &memory::fits(None))
+                .map_err(|e| format!("{}: {}", opts.input, e))?
+        }
+        None => Pattern::load_within(&opts.input, &memory::fits(None))?,
+    };
+    if opts.crop {
+        pattern = pattern.crop();
+    }
+    match opts.to {
+        Some(format) => gzip::write(&opts.output, pattern.to_format(format).into_bytes()),
+        None => pattern.save(&opts.output),
+    }
+}
+
+fn analyze(mut opts: AnalyzeOptions) -> Result<(), String> {
+    if opts.ensemble > 0 {
+        let seed = seed_or_time(opts.world.seed);
+        opts.world.seed = Some(seed);
+        return ensemble::run(&opts, seed, initial_world);
+    }
+    let mut world = initial_world(&opts.world)?;
+    if opts.predecessor {
+        return predecessor(&world, opts.margin);
+    }
+    let mut tmp = world.clone();
+
+    let initial = world.population();
+    let initial_entropy = metrics::block_entropy(&world);
+    let symmetry = Symmetry::of(&Pattern::from_live_cells(&world));
+    let (mut peak, mut peak_at) = (initial, 0);
+    let (mut low, mut low_at) = (initial, 0);
+    // cycles are reported whether or not they end the run
+    let mut watch = Watch::new(Until::Stable, &world, opts.max_period);
+    let (mut generations, mut settled) = (0, None);
+    let mut growth = Growth::new();
+    let mut grew = None;
+    let seed =
+        (opts.perturb.is_some() || opts.faults.is_some()).then(|| seed_or_time(opts.world.seed));
+    let mut perturber = opts
+        .perturb
+        .map(|perturb| Perturber::new(perturb, seed.unwrap_or(0)));
+    let mut injector = opts
+        .faults
+        .map(|faults| Injector::new(faults, seed.unwrap_or(0)));
+    while generations < opts.generations {
+        let changes = world.advance(&mut tmp);
+        generations += 1;
+        let generation = generations;
+        let mut flipped = perturber
+            .as_mut()
+            .map_or(0, |p| p.apply(&mut world, generation, |_, _, _| {}));
+        flipped += injector
+            .as_mut()
+            .map_or(0, |i| i.apply(&mut world, generation, |_, _, _| {}));
+        if settled.is_none() {
+            settled = watch.check(&world, generation);
+        }
+        if grew.is_none() {
+            grew = growth.check(&world, generation);
+        }
+        let n = world.population();
+        if n > peak {
+            peak = n;
+            peak_at = generation;
+        }
+        if n < low {
+            low = n;
+            low_at = generation;
+        }
+        let stop = match opts.until {
+            Some(Until::Stable) => settled.is_some(),
+            Some(Until::Extinct) => n == 0,
+            None => false,
+        };
+        if stop || (opts.stop_on_growth && grew.is_some()) {
+            break;
+        }
+        // the remaining generations would all look the same
+        if !changes.any() && flipped == 0 && perturber.is_none() && injector.is_none() {
+            if opts.until.is_none() {
+                generations = opts.generations;
+            }
+            break;
+        }
+    }
+
+    println!("rule         {}", world.rule());
+    println!("generations  {}", generations);
+    println!(
+        "population   {} initial, {} final",
+        initial,
+        world.population()
+    );
+    println!("peak         {} at generation {}", peak, peak_at);
+    println!("minimum      {} at generation {}", low, low_at);
+    println!(
+        "entropy      {:.3} initial, {:.3} final (bits per 2x2 block)",
+        initial_entropy,
+        metrics::block_entropy(&world)
+    );
+    println!("symmetry     {} initial", symmetry.name());
+    if let Some(reason) = &settled {
+        println!("outcome      {}", reason);
+    }
+    if let Some(perturber) = &perturber {
+        println!("perturbed    {}", perturber.describe());
+    }
+    if let Some(injector) = &injector {
+        println!("faults       {}", injector.describe());
+    }
+    // a methuselah grows for a while too, so only report what it didn't outlive
+    if let (Some(reason), None) = (&grew, &settled) {
+        println!("growth       {}", reason);
+    }
+    let census = census::census(&world);
+    if !census.is_empty() {
+        let counts: Vec<_> = census
+            .iter()
+            .map(|(name, n)| format!("{} {}", n, name))
+            .collect();
+        println!("census       {}", counts.join(", "));
+    }
+    if opts.apgcodes {
+        let counts: Vec<_> = census::apgcodes(&world)
+            .iter()
+            .map(|(code, n)| format!("{} {}", n, code))
+            .collect();
+        if !counts.is_empty() {
+            println!("apgcodes     {}", counts.join(", "));
+        }
+    }
+    let mut ships: Vec<(String, usize)> = Vec::new();
+    for ship in census::spaceships(&world, opts.max_period) {
+        let kind = format!("{} ({})", ship.name.unwrap_or("spaceship"), ship.speed());
+        match ships.iter_mut().find(|s| s.0 == kind) {
+            Some(count) => count.1 += 1,
+            None => ships.push((kind, 1)),
+        }
+    }
+    if !ships.is_empty() {
+        let counts: Vec<_> = ships
+            .iter()
+            .map(|(kind, n)| format!("{} {}", n, kind))
+            .collect();
+        println!("spaceships   {}", counts.join(", "));
+    }
+    if opts.stop_on_growth && grew.is_some() {
+        process::exit(GROWTH_STATUS);
+    }
+    Ok(())
+}
+
+/// Keeps a predecessor search to a few seconds, in which small patterns finish.
+const PREDECESSOR_LIMIT: u64 = 100_000_000;
+
+fn predecessor(world: &World, margin: usize) -> Result<(), String> {
+    world.rule().nearest_only("the predecessor search")?;
+    let target = Pattern::from_live_cells(world);
+    match predecessor::find(&target, world.rule(), margin, PREDECESSOR_LIMIT) {
+        Predecessor::Found(found) => {
+            println!(
+                "predecessor  {}x{}, {} cells",
+                found.width(),
+                found.height(),
+                found.population()
+            );
+            print!("{}", found.to_rle());
+        }
+        Predecessor::NoneWithin => println!(
+            "predecessor  none within {} cell{} of the pattern",
+            margin,
+            if margin == 1 { "" } else { "s" }
+        ),
+        Predecessor::GaveUp => println!(
+            "predecessor  unknown, gave up after {} steps",
+            PREDECESSOR_LIMIT
+        ),
+    }
+    Ok(())
+}
+
+fn bench(opts: BenchOptions) -> Result<(), String> {
+    let mut world = World::new(opts.width, opts.height)?;
+    // a fixed seed keeps runs comparable with each other
+    world.randomize(0.3, &mut Rng::new(opts.seed));
     let mut tmp = world.clone();
 
-    // create a glider
-    // ..O
-    // O.O
-    // .OO
-    let mx = width / 2;
-    let my = height / 2;
-    world.set(mx + 1, my - 1, Cell::Live);
-    world.set(mx - 1, my, Cell::Live);
-    world.set(mx + 1, my, Cell::Live);
-    world.set(mx, my + 1, Cell::Live);
-    world.set(mx + 1, my + 1, Cell::Live);
-
-    println!("{}", world.as_string());
-    for _ in 0..generations {
-        std::thread::sleep(Duration::from_millis(delay));
+    let started = Instant::now();
+    for _ in 0..opts.generations {
         world.advance(&mut tmp);
-        println!("{}", world.as_string());
     }
+    let secs = started.elapsed().as_secs_f64();
 
+    let cells = (opts.width * opts.height) as f64 * opts.generations as f64;
+    println!(
+        "{} generations of {}x{} in {:.3}s: {:.1} generations/s, {:.2} Mcells/s",
+        opts.generations,
+        opts.width,
+        opts.height,
+        secs,
+        opts.generations as f64 / secs,
+        cells / secs / 1e6
+    );
     Ok(())
 }
 
+fn render(opts: RenderOptions) -> Result<(), String> {
+    let mut world = initial_world(&opts.world)?;
+    if opts.by_age {
+        world.track_ages();
+    }
+    let mut tmp = world.clone();
+    for _ in 0..opts.generations {
+        world.advance(&mut tmp);
+    }
+
+    let mut style = opts.style.clone();
+    style.use_rule_colors(world.rule());
+    let (style, image) = (&style, &opts.image);
+    let (live, dead) = (image.live_rgb(style), image.dead_rgb(style));
+    let young = image
+        .live
+        .or(style.live_color)
+        .map_or((255, 255, 255), |c| c.rgb());
+    let old = style.old_color.map_or((110, 110, 110), |c| c.rgb());
+    let (width, height, rgb) = image.draw(style, world.width(), world.height(), |x, y| {
+        let state = world.history(x, y).filter(|&s| s > history::ON);
+        if let Some(&color) = state.and_then(|s| history::COLORS.get(usize::from(s))) {
+            return color;
+        }
+        if world.is_wall(x, y) {
+            return term::WALL_RGB;
+        }
+        match (world.get(x, y), world.age(x, y)) {
+            // long-lived cells dim towards a middling gray, or the theme's
+            // color for them
+            (Cell::Live, Some(age)) => term::mix(old, young, 1.0 / f64::from(age).sqrt()),
+            (Cell::Live, None) => live,
+            (Cell::Dead, _) => dead,
+        }
+    });
+    let comment = format!("rule {}", world.rule());
+    png::write(&opts.output, width, height, &rgb, &comment)
+}
+
+fn step(opts: StepOptions) -> Result<(), String> {
+    let mut text = String::new();
+    io::stdin()
+        .read_to_string(&mut text)
+        .map_err(|e| format!("cannot read stdin: {}", e))?;
+    let format = Format::detect(&text);
+    let pattern = Pattern::parse_as_within(&text, format, &memory::fits(None))
+        .map_err(|e| format!("stdin: {}", e))?;
+
+    let width = opts.width.unwrap_or(pattern.width()).max(3);
+    let height = opts.height.unwrap_or(pattern.height()).max(3);
+    memory::check(width, height, None)?;
+    let mut world = World::new(width, height)?;
+    place_centered(&mut world, &pattern)?;
+    if let Some(rule) = opts.rule {
+        world.set_rule(rule);
+    }
+    let mut tmp = world.clone();
+    let mut progress = opts
+        .progress
+        .then(|| Progress::new(opts.generations, "generations"));
+    let area = (width * height) as u64;
+    for generation in 1..=opts.generations {
+        world.advance(&mut tmp);
+        if let Some(progress) = progress.as_mut() {
+            progress.update(generation, generation * area);
+        }
+    }
+    if let Some(progress) = progress.as_mut() {
+        progress.finish(opts.generations, opts.generations * area);
+    }
+
+    let result = Pattern::from_world(&world);
+    let out = result.to_format(opts.format.unwrap_or(format));
+    match io::stdout().write_all(out.as_bytes()) {
+        // the reader has seen enough, as with `conway step | head`
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result.map_err(|e| format!("cannot write stdout: {}", e)),
+    }
+}
+
+/// Writes a checkpoint of `world` at `generation` into `dir`.
+fn save_checkpoint(
+    world: &World,
+    generation: u64,
+    seed: Option<u64>,
+    opts: &RunOptions,
+) -> Result<(), String> {
+    let dir = &opts.checkpoint_dir;
+    fs::create_dir_all(dir).map_err(|e| format!("cannot create {}: {}", dir, e))?;
+    let ext = if opts.checkpoint_gzip {
+        "bin.gz"
+    } else {
+        "bin"
+    };
+    let path = Path::new(dir).join(format!("checkpoint-{}.{}", generation, ext));
+    let checkpoint = Checkpoint {
+        world: world.clone(),
+        generation,
+        seed,
+    };
+    checkpoint.save(&path.to_string_lossy())
+}
+
+/// With `--watch-input`, the world to start over from once the pattern file
+/// has been saved again, if it loads.
+fn reloaded(input: &mut Option<InputWatch>, opts: &RunOptions) -> Option<World> {
+    let input = input.as_mut()?;
+    if !input.changed() {
+        return None;
+    }
+    match initial_world(&opts.world) {
+        Ok(mut world) => {
+            if opts.style.trails > 0 {
+                world.track_ages();
+            }
+            log::info("run", &format!("{} changed, starting over", input.path()));
+            Some(world)
+        }
+        Err(e) => {
+            log::warn("run", &format!("cannot reload {}: {}", input.path(), e));
+            None
+        }
+    }
+}
+
+/// Waits for the pattern file to be saved again once the run has ended,
+/// until Ctrl-C.
+fn wait_for_reload(input: &mut Option<InputWatch>, opts: &RunOptions) -> Option<World> {
+    while !interrupt::requested() {
+        if let Some(world) = reloaded(input, opts) {
+            return Some(world);
+        }
+        thread::sleep(reload::POLL);
+    }
+    None
+}
+
+/// Shows a frame with every renderer.
+fn draw_all(renderers: &mut [Box<dyn Renderer>], frame: &Frame) -> Result<(), String> {
+    renderers.iter_mut().try_for_each(|r| r.draw(frame))
+}
+
+/// Says why the run ended, below the last frame.
+fn stop_all(renderers: &mut [Box<dyn Renderer>], reason: &str) -> Result<(), String> {
+    renderers.iter_mut().try_for_each(|r| r.stopped(reason))
+}
+
+/// Writes a line under the frame in the color the style gives it.
+fn show_hud(text: &str, style: &Style) {
+    match style.hud_color {
+        Some(color) => output::screen(&format!("{}{}{}", color.foreground(), text, term::RESET)),
+        None => output::screen(text),
+    }
+}
+
+/// Runs from `start`, the generation `world` is at, with `seed` being what
+/// the soup came from for the checkpoints to keep.
+fn run_terminal(
+    world: &mut World,
+    opts: &RunOptions,
+    mut pacer: Pacer,
+    recorders: &mut Recorders,
+    start: u64,
+    seed: Option<u64>,
+    session: Option<&Session>,
+) -> Result<bool, String> {
+    let tmp = &mut world.clone();
+    let mut breakpoints = Breakpoints::new(world, &opts.breakpoints)?;
+    let sought = opts
+        .until_pattern
+        .as_ref()
+        .map(|source| load_pattern(source, &opts.world).map(|p| Sighting::new(&p)))
+        .transpose()?;
+    // with --diff, the generation before the one about to be shown
+    let mut previous = if opts.diff { Some(world.clone()) } else { None };
+    let mut renderers = vec![render::create(&opts.renderer, &opts.style, opts.cells)?];
+    if let Some(port) = opts.telnet_port {
+        renderers.push(Box::new(Telnet::new(port, opts.style.clone())));
+    }
+    for renderer in &mut renderers {
+        renderer.init(world)?;
+    }
+    let mut script = opts.script.as_deref().map(Script::start).transpose()?;
+    let mut control = opts
+        .control_fifo
+        .as_deref()
+        .map(Control::open)
+        .transpose()?;
+    if let Some(control) = control.as_mut() {
+        if session.is_some_and(|s| s.paused) {
+            control.pause();
+        }
+        control.record(world, start, false);
+    }
+    let first = match script.as_mut() {
+        Some(script) => script.hook(world, start, Changes::default(), recorders)?,
+        None => Answer::default(),
+    };
+    let frame = Frame {
+        world,
+        previous: previous.as_ref(),
+        generation: start,
+        changes: Changes::default(),
+    };
+    draw_all(&mut renderers, &frame)?;
+    recorders.frame(world, start);
+    let mut sparkline = opts.sparkline.map(Sparkline::new);
+    if let Some(sparkline) = sparkline.as_mut() {
+        if let Some((_, populations)) = session.and_then(|s| s.history.as_ref()) {
+            // the last of them is the world as it is now, pushed below
+            let before = &populations[..populations.len().saturating_sub(1)];
+            before.iter().for_each(|&p| sparkline.push(p));
+        }
+        sparkline.push(world.population());
+        show_hud(&sparkline.render(opts.style.unicode), &opts.style);
+    }
+    let mut watch = opts
+        .until
+        .map(|until| Watch::new(until, world, opts.max_period));
+    let mut growth = if opts.stop_on_growth {
+        Some(Growth::new())
+    } else {
+        None
+    };
+    let mut grew = false;
+    let mut perturber = opts
+        .perturb
+        .map(|perturb| Perturber::new(perturb, opts.world.seed.or(seed).unwrap_or(0)));
+    let mut injector = opts
+        .faults
+        .map(|faults| Injector::new(faults, opts.world.seed.or(seed).unwrap_or(0)));
+    // the script can stop the run before it even starts
+    let last = match &first.stop {
+        Some(reason) => {
+            stop_all(&mut renderers, reason)?;
+            start
+        }
+        None => opts.generations,
+    };
+    let mut input = opts.watch_input.as_deref().map(InputWatch::new);
+    // the world the watched file has been saved as, to start over from
+    let mut fresh = None;
+    let mut generation = start;
+    // the generation the world is at, for the saved session
+    let mut reached = start;
+    // as the control commands leave it
+    let mut zoom = opts.style.zoom;
+    loop {
+        if let Some(new) = fresh.take() {
+            *world = new;
+            tmp.clone_from(world);
+            if let Some(previous) = previous.as_mut() {
+                previous.clone_from(world);
+            }
+            watch = opts
+                .until
+                .map(|until| Watch::new(until, world, opts.max_period));
+            breakpoints = Breakpoints::new(world, &opts.breakpoints)?;
+            if opts.stop_on_growth {
+                growth = Some(Growth::new());
+            }
+            generation = start;
+            reached = start;
+            if let Some(control) = control.as_mut() {
+                control.record(world, start, true);
+            }
+            let frame = Frame {
+                world,
+                previous: previous.as_ref(),
+                generation,
+                changes: Changes::default(),
+            };
+            draw_all(&mut renderers, &frame)?;
+            recorders.frame(world, generation);
+            if let Some(sparkline) = sparkline.as_mut() {
+                sparkline.clear();
+                sparkline.push(world.population());
+                show_hud(&sparkline.render(opts.style.unicode), &opts.style);
+            }
+        }
+        if generation >= last {
+            break;
+        }
+        generation += 1;
+        if let Some(control) = control.as_mut() {
+            control.obey(
+                world,
+                &mut pacer,
+                recorders,
+                generation - 1,
+                |world, shown, to| {
+                    if let Some(to) = to {
+                        zoom = to;
+                        renderers.iter_mut().for_each(|r| r.zoom(to));
+                    }
+                    let frame = Frame {
+                        world,
+                        previous: None,
+                        generation: shown,
+                        changes: Changes::default(),
+                    };
+                    draw_all(&mut renderers, &frame)
+                },
+            )?;
+        }
+        if interrupt::requested() {
+            stop_all(
+                &mut renderers,
+                &format!("interrupted at generation {}", generation - 1),
+            )?;
+            if opts.save_on_interrupt {
+                let path = interrupt::save(world)?;
+                log::info("run", &format!("saved the world to {}", path));
+            }
+            break;
+        }
+        if interrupt::snapshot_requested() {
+            let (rle, png) = interrupt::snapshot(world, generation - 1)?;
+            log::info("run", &format!("saved a snapshot to {} and {}", rle, png));
+        }
+        fresh = reloaded(&mut input, opts);
+        if fresh.is_some() {
+            continue;
+        }
+        let began = Instant::now();
+        if let Some(previous) = previous.as_mut() {
+            previous.clone_from(world);
+        }
+        let changes = world.advance(tmp);
+        reached = generation;
+        recorders.generation(world, changes);
+        if let Some(sparkline) = sparkline.as_mut() {
+            sparkline.push(world.population());
+        }
+        // whether the world changed other than by stepping
+        let mut edited = false;
+        if let Some(perturber) = perturber.as_mut() {
+            perturber.apply(world, generation, |x, y, cell| {
+                edited = true;
+                recorders.perturbed(x, y, cell)
+            });
+        }
+        if let Some(injector) = injector.as_mut() {
+            injector.apply(world, generation, |x, y, cell| {
+                edited = true;
+                recorders.perturbed(x, y, cell)
+            });
+        }
+        let answer = match script.as_mut() {
+            Some(script) => script.hook(world, generation, changes, recorders)?,
+            None => Answer::default(),
+        };
+        if let Some(control) = control.as_mut() {
+            control.record(world, generation, edited || answer.edits > 0);
+        }
+        if opts
+            .checkpoint_every
+            .is_some_and(|every| generation.is_multiple_of(every))
+        {
+            save_checkpoint(world, generation, seed, opts)?;
+        }
+        let mut stopped = answer.stop;
+        if stopped.is_none() {
+            stopped = watch.as_mut().and_then(|w| w.check(world, generation));
+        }
+        if let (None, Some(sighting)) = (&stopped, &sought) {
+            stopped = sighting.check(world, generation);
+        }
+        if stopped.is_none() {
+            stopped = breakpoints.check(world, generation);
+        }
+        // nothing left to show but the same frame over and over
+        let still = !changes.any() && answer.edits == 0;
+        // a perturbed world may yet be knocked out of it, and a scrolling
+        // one still moves
+        let keep_going = opts.keep_going
+            || perturber.is_some()
+            || injector.is_some()
+            || opts.style.scroll.is_some();
+        if still && !keep_going && stopped.is_none() {
+            stopped = Some(stop::settled(world.population(), generation - 1));
+        }
+        if stopped.is_none() {
+            stopped = growth.as_mut().and_then(|g| g.check(world, generation));
+            grew = stopped.is_some();
+        }
+        pacer.simulated(began.elapsed());
+        let final_frame = generation == opts.generations || stopped.is_some();
+        if generation.is_multiple_of(opts.every) && !final_frame && pacer.skip() {
+            recorders.frame(world, generation);
+        } else if generation.is_multiple_of(opts.every) || final_frame {
+            pacer.wait();
+            let began = Instant::now();
+            let frame = Frame {
+                world,
+                previous: previous.as_ref(),
+                generation,
+                changes,
+            };
+            draw_all(&mut renderers, &frame)?;
+            recorders.frame(world, generation);
+            pacer.rendered(began.elapsed());
+            if opts.timing {
+                show_hud(&pacer.timings.hud(), &opts.style);
+            }
+            if let Some(sparkline) = &sparkline {
+                show_hud(&sparkline.render(opts.style.unicode), &opts.style);
+            }
+            if opts.metrics {
+                show_hud(&world.metrics().to_string(), &opts.style);
+            }
+        }
+        if let Some(reason) = &stopped {
+            stop_all(&mut renderers, reason)?;
+        }
+        if (final_frame || stopped.is_some()) && input.is_some() {
+            fresh = wait_for_reload(&mut input, opts);
+            if fresh.is_some() {
+                continue;
+            }
+        }
+        if stopped.is_some() {
+            break;
+        }
+    }
+    for renderer in renderers {
+        renderer.finish()?;
+    }
+    if let Some(script) = script {
+        script.finish()?;
+    }
+    if let Some(injector) = &injector {
+        log::info("faults", &injector.describe());
+    }
+    if let Some(path) = &opts.save_session {
+        let mut saved = Session::new(world, reached, seed, opts);
+        let (fps, delay) = pacer.speed();
+        saved.fps = fps;
+        saved.delay = delay;
+        saved.paused = control.as_ref().is_some_and(Control::paused);
+        saved.style.zoom = zoom;
+        if let Some((dx, dy)) = opts.style.scroll {
+            // where the scrolling has taken the view
+            let t = (reached - start) as f64;
+            saved.style.origin = (saved.style.origin.0 + dx * t, saved.style.origin.1 + dy * t);
+        }
+        saved.history = sparkline.map(|s| (s.span(), s.populations()));
+        saved.save(path)?;
+        log::info("run", &format!("saved the session to {}", path));
+    }
+    if let Some(summary) = recorders.summary.as_mut() {
+        summary.timings = Some(pacer.timings);
+    }
+
+    Ok(grew)
+}
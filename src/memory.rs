@@ -0,0 +1,83 @@
+//! Refusing worlds too big for the machine up front, with an error saying
+//! so, rather than letting the allocation abort or the system start
+//! swapping partway through building one.
+
+use std::fs;
+
+use conway::World;
+
+/// Worlds in memory at once for one being run: itself and the one each
+/// generation is computed into.
+const COPIES: usize = 2;
+
+const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+/// A size like `4096`, `512K`, `64M` or `4G`, the suffixes counting in
+/// powers of 1024.
+pub fn parse_size(s: &str) -> Option<u64> {
+    let upper = s.to_ascii_uppercase();
+    let digits = upper.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let shift = match upper[digits.len()..].trim_end_matches('B') {
+        "" => 0,
+        "K" => 10,
+        "M" => 20,
+        "G" => 30,
+        "T" => 40,
+        _ => return None,
+    };
+    digits.parse::<u64>().ok()?.checked_mul(1 << shift)
+}
+
+/// On Linux, the memory that can be had without swapping.
+pub fn available() -> Option<u64> {
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|l| l.starts_with("MemAvailable:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Fails if running a `width` by `height` world would take more than `limit`
+/// bytes, or with no limit, more than is `available`.
+pub fn check(width: usize, height: usize, limit: Option<u64>) -> Result<(), String> {
+    let (allowed, what) = match limit {
+        Some(limit) => (limit, "allowed by --max-mem"),
+        None => match available() {
+            Some(available) => (available, "of memory available, see --max-mem"),
+            None => return Ok(()),
+        },
+    };
+    let needed = World::memory(width, height).and_then(|bytes| bytes.checked_mul(COPIES));
+    match needed {
+        Some(needed) if needed as u64 <= allowed => Ok(()),
+        Some(needed) => Err(format!(
+            "a {}x{} world needs about {}, more than the {} {}",
+            width,
+            height,
+            format(needed as u64),
+            format(allowed),
+            what
+        )),
+        None => Err(format!("a {}x{} world is too big to exist", width, height)),
+    }
+}
+
+/// `check` for a pattern read from input, whose size is only its header's
+/// word until the cells are there, asked before any room is made for them.
+pub fn fits(limit: Option<u64>) -> impl Fn(usize, usize) -> Result<(), String> {
+    move |width, height| check(width, height, limit)
+}
+
+/// `bytes` in the largest unit that leaves at least one of them.
+fn format(bytes: u64) -> String {
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
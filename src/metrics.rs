@@ -0,0 +1,66 @@
+//! Measures of how ordered a world looks, and of where its live cells are.
+
+use std::fmt;
+
+use crate::{Cell, World};
+
+/// Where a world's live cells are and how spread out, from `World::metrics`.
+/// Positions are as the world is laid out, not allowing for the wrap.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Metrics {
+    /// The share of the cells alive, from 0 to 1.
+    pub density: f64,
+    /// The mean position of the live cells, `None` for an empty world.
+    pub centroid: Option<(f64, f64)>,
+    /// How far the live cells are from the centroid, the root of their mean
+    /// squared distance, 0 for an empty world.
+    pub radius_of_gyration: f64,
+    /// Live cells in the top left, top right, bottom left and bottom right
+    /// quarters, the middle row and column of an odd size going to the
+    /// bottom and right ones.
+    pub quadrants: [usize; 4],
+}
+
+impl fmt::Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "density {:.4}", self.density)?;
+        if let Some((x, y)) = self.centroid {
+            write!(f, "  centroid {:.1},{:.1}", x, y)?;
+        }
+        let [nw, ne, sw, se] = self.quadrants;
+        write!(
+            f,
+            "  gyration {:.1}  quadrants {} {} {} {}",
+            self.radius_of_gyration, nw, ne, sw, se
+        )
+    }
+}
+
+/// Shannon entropy, in bits, of the 2x2 blocks the world divides into: 0 for
+/// a world of one kind of block, such as an empty one, up to 4 when all 16
+/// kinds are equally common. Random soups start near the top and fall as
+/// they settle into ash. Worlds of odd size borrow their last blocks' cells
+/// from across the edge.
+pub fn block_entropy(world: &World) -> f64 {
+    let (w, h) = (world.width(), world.height());
+    let mut counts = [0usize; 16];
+    for y in (0..h).step_by(2) {
+        for x in (0..w).step_by(2) {
+            let live = |dx: usize, dy: usize| world.get((x + dx) % w, (y + dy) % h) == Cell::Live;
+            let kind = live(0, 0) as usize
+                | (live(1, 0) as usize) << 1
+                | (live(0, 1) as usize) << 2
+                | (live(1, 1) as usize) << 3;
+            counts[kind] += 1;
+        }
+    }
+    let blocks = counts.iter().sum::<usize>() as f64;
+    counts
+        .iter()
+        .filter(|&&n| n > 0)
+        .map(|&n| {
+            let p = n as f64 / blocks;
+            -p * p.log2()
+        })
+        .sum()
+}
@@ -0,0 +1,128 @@
+//! `run --midi PORT`: every generation shown plays the cells that were born
+//! and those that died as MIDI notes, for driving a synthesizer live.
+//!
+//! The port is a raw MIDI device, like the `/dev/snd/midiC1D0` ALSA makes
+//! for each hardware port and for the virtual ones of `snd-virmidi`, or a
+//! FIFO another program reads, and gets the bytes of the messages as they
+//! are played. A cell's column is its pitch, low on the left and high on the
+//! right along the scale, births sound on channel 1 and deaths on channel 2,
+//! and the busier the world the harder they are struck. The share of cells
+//! alive is also sent as channel pressure on both, for a synthesizer to
+//! swell with.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+
+use conway::{Cell, World};
+
+use crate::audio::Scale;
+use crate::log;
+
+/// The note of the leftmost column, C3.
+const LOWEST: usize = 48;
+/// Octaves the columns are spread over.
+const OCTAVES: usize = 4;
+
+const NOTE_ON: u8 = 0x90;
+const NOTE_OFF: u8 = 0x80;
+const PRESSURE: u8 = 0xd0;
+/// Channels, counting from 0 as the messages do.
+const BIRTHS: u8 = 0;
+const DEATHS: u8 = 1;
+
+pub struct Midi {
+    port: String,
+    out: File,
+    scale: Scale,
+    previous: World,
+    /// The notes still sounding from the last generation, and on which
+    /// channel.
+    sounding: Vec<(u8, u8)>,
+    /// The first write that failed, after which nothing more is sent.
+    failed: Option<String>,
+}
+
+impl Midi {
+    pub fn open(port: &str, world: &World, scale: Scale) -> Result<Midi, String> {
+        let out = OpenOptions::new()
+            .write(true)
+            .open(port)
+            .map_err(|e| format!("cannot open MIDI port {}: {}", port, e))?;
+        log::info("midi", &format!("playing to {}", port));
+        Ok(Midi {
+            port: port.to_string(),
+            out,
+            scale,
+            previous: world.clone(),
+            sounding: Vec::new(),
+            failed: None,
+        })
+    }
+
+    /// The note for column `x`.
+    fn pitch(&self, x: usize, width: usize) -> u8 {
+        let degrees = OCTAVES * self.scale.steps().len();
+        let degree = x * degrees / width.max(1);
+        (LOWEST + self.scale.semitones(degree)).min(127) as u8
+    }
+
+    fn send(&mut self, bytes: &[u8]) {
+        if self.failed.is_some() {
+            return;
+        }
+        if let Err(e) = self.out.write_all(bytes) {
+            self.failed = Some(format!("cannot write to MIDI port {}: {}", self.port, e));
+        }
+    }
+
+    fn silence(&mut self) {
+        let sounding = std::mem::take(&mut self.sounding);
+        let bytes: Vec<u8> = sounding
+            .iter()
+            .flat_map(|&(channel, note)| [NOTE_OFF | channel, note, 0])
+            .collect();
+        self.send(&bytes);
+    }
+
+    /// Ends the notes of the last generation and plays this one's.
+    pub fn observe(&mut self, world: &World) {
+        let width = world.width();
+        let (mut born, mut died) = (vec![false; 128], vec![false; 128]);
+        for y in 0..world.height() {
+            for x in 0..width {
+                let notes = match (self.previous.get(x, y), world.get(x, y)) {
+                    (Cell::Dead, Cell::Live) => &mut born,
+                    (Cell::Live, Cell::Dead) => &mut died,
+                    _ => continue,
+                };
+                notes[usize::from(self.pitch(x, width))] = true;
+            }
+        }
+        self.previous.clone_from(world);
+        self.silence();
+
+        // populations rarely go above half the world, so that is loudest
+        let area = (world.width() * world.height()) as f64;
+        let density = (world.population() as f64 / area * 2.0).min(1.0);
+        let velocity = 32 + (density * 95.0).round() as u8;
+        let pressure = (density * 127.0).round() as u8;
+        let mut bytes = vec![PRESSURE | BIRTHS, pressure, PRESSURE | DEATHS, pressure];
+        for (channel, notes) in [(BIRTHS, &born), (DEATHS, &died)] {
+            for note in (0..128u8).filter(|&n| notes[usize::from(n)]) {
+                bytes.extend_from_slice(&[NOTE_ON | channel, note, velocity]);
+                self.sounding.push((channel, note));
+            }
+        }
+        self.send(&bytes);
+    }
+
+    /// Stops every note still sounding, and fails if any message couldn't
+    /// be sent.
+    pub fn finish(mut self) -> Result<(), String> {
+        self.silence();
+        match self.failed {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
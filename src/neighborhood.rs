@@ -0,0 +1,35 @@
+//! Counting the cells within a radius of every cell at once, for Larger than
+//! Life rules: a summed-area table of the world with `radius` cells of the
+//! far side wrapped around each edge, from which each count is four lookups
+//! however large the radius.
+
+use crate::World;
+
+/// The live cells within `radius` of every cell of `world`, itself included,
+/// row by row. A radius reaching around a small world counts cells again.
+pub(crate) fn live_within(world: &World, radius: usize) -> Vec<u32> {
+    let (w, h) = (world.width(), world.height());
+    let side = 2 * radius + 1;
+    let (padded_w, padded_h) = (w + 2 * radius, h + 2 * radius);
+    // sums[y * stride + x]: the live cells above and to the left of x,y in
+    // the padded world
+    let stride = padded_w + 1;
+    let mut sums = vec![0u32; stride * (padded_h + 1)];
+    for py in 0..padded_h {
+        let y = (py % h + h - radius % h) % h;
+        let mut row = 0;
+        for px in 0..padded_w {
+            let x = (px % w + w - radius % w) % w;
+            row += u32::from(world.get(x, y).n());
+            sums[(py + 1) * stride + px + 1] = sums[py * stride + px + 1] + row;
+        }
+    }
+    (0..w * h)
+        .map(|i| {
+            let (x, y) = (i % w, i / w);
+            sums[(y + side) * stride + x + side] + sums[y * stride + x]
+                - sums[y * stride + x + side]
+                - sums[(y + side) * stride + x]
+        })
+        .collect()
+}
@@ -0,0 +1,108 @@
+//! `run --osc HOST:PORT`: every generation sent over UDP as Open Sound
+//! Control, for live-coding and visuals environments to follow the run. A
+//! generation is one bundle, to be acted on at once, of the messages
+//!
+//! ```text
+//! /conway/generation  i   the generation
+//! /conway/population  i   live cells
+//! /conway/births      i   cells born into it
+//! /conway/deaths      i   cells that died
+//! /conway/bbox        iiii  x, y, width and height of the live cells, all
+//!                         0 for an empty world
+//! /conway/rows        i…  with --osc-rows N, the live cells in each of N
+//!                         bands of rows from the top
+//! ```
+
+use std::convert::TryFrom;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+use conway::{Changes, World};
+
+use crate::log;
+
+/// The most bands `--osc-rows` can split the world into, keeping a bundle
+/// well inside a datagram.
+pub const MAX_ROWS: usize = 1024;
+
+/// An OSC string: its bytes, then one to four NULs up to a multiple of 4.
+fn string(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(s.as_bytes());
+    out.resize(out.len() + 4 - s.len() % 4, 0);
+}
+
+/// A message of 32-bit integers.
+fn message(address: &str, args: &[i32]) -> Vec<u8> {
+    let mut out = Vec::new();
+    string(&mut out, address);
+    let tags: String = std::iter::once(',')
+        .chain(args.iter().map(|_| 'i'))
+        .collect();
+    string(&mut out, &tags);
+    for arg in args {
+        out.extend_from_slice(&arg.to_be_bytes());
+    }
+    out
+}
+
+fn int(n: usize) -> i32 {
+    i32::try_from(n).unwrap_or(i32::MAX)
+}
+
+pub struct Osc {
+    socket: UdpSocket,
+    to: SocketAddr,
+    rows: usize,
+}
+
+impl Osc {
+    /// Sends to `target`, a `host:port`, with `rows` bands in `/conway/rows`
+    /// or none.
+    pub fn new(target: &str, rows: usize) -> Result<Osc, String> {
+        let to = target
+            .to_socket_addrs()
+            .map_err(|e| format!("cannot resolve --osc {}: {}", target, e))?
+            .next()
+            .ok_or_else(|| format!("--osc {} has no address", target))?;
+        let any = if to.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+        let socket = UdpSocket::bind(any).map_err(|e| format!("cannot open a socket: {}", e))?;
+        log::info("osc", &format!("sending to {}", to));
+        Ok(Osc { socket, to, rows })
+    }
+
+    /// Sends the bundle for the generation `world` is at, which `changes`
+    /// led to.
+    pub fn advanced(&mut self, world: &World, changes: Changes) {
+        let bbox = world
+            .bounding_box()
+            .map_or([0; 4], |r| [r.x, r.y, r.width, r.height]);
+        let mut messages = vec![
+            message("/conway/generation", &[world.generation() as i32]),
+            message("/conway/population", &[int(world.population())]),
+            message("/conway/births", &[int(changes.births)]),
+            message("/conway/deaths", &[int(changes.deaths)]),
+            message("/conway/bbox", &bbox.map(int)),
+        ];
+        if self.rows > 0 {
+            let height = world.height();
+            let mut bands = vec![0; self.rows.min(height)];
+            let n = bands.len();
+            for (y, &live) in world.row_populations().iter().enumerate() {
+                bands[y * n / height] += live;
+            }
+            let bands: Vec<i32> = bands.into_iter().map(int).collect();
+            messages.push(message("/conway/rows", &bands));
+        }
+        // a time tag of 1 means at once
+        let mut bundle = Vec::new();
+        string(&mut bundle, "#bundle");
+        bundle.extend_from_slice(&1u64.to_be_bytes());
+        for message in messages {
+            bundle.extend_from_slice(&int(message.len()).to_be_bytes());
+            bundle.extend_from_slice(&message);
+        }
+        // nothing may be listening yet, which UDP is for
+        if let Err(e) = self.socket.send_to(&bundle, self.to) {
+            log::debug("osc", &format!("cannot send to {}: {}", self.to, e));
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Where `run` writes. Frames, the reasons runs stop and timings are for
+//! whoever is watching, so they go to the terminal: stdout when it is one,
+//! stderr when stdout is a pipe or a file. JSON lines, the summary and the
+//! final world are data, and go to stdout or the file `--output` names, so
+//! a pipeline gets them without frames and escapes mixed in.
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::sync::Mutex;
+
+use crate::term;
+
+/// The `--output` file and its path, once given.
+static FILE: Mutex<Option<(String, BufWriter<File>)>> = Mutex::new(None);
+
+/// Sends the data to the file at `path` from now on instead of stdout.
+pub fn to_file(path: &str) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("cannot write {}: {}", path, e))?;
+    let mut current = FILE.lock().unwrap_or_else(|e| e.into_inner());
+    *current = Some((path.to_string(), BufWriter::new(file)));
+    Ok(())
+}
+
+/// `text` without its escapes if the terminal shows no colors, so frames
+/// come out as plain text.
+fn plain(text: &str) -> Cow<'_, str> {
+    if term::shows_colors() || !text.contains('\x1b') {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        // an escape runs from a [ to the letter that ends it
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Writes `text` and a newline where the frames go.
+pub fn screen(text: &str) {
+    let text = plain(text);
+    let stdout = io::stdout();
+    // a terminal that has gone away is no reason to stop a run
+    let _ = if stdout.is_terminal() {
+        writeln!(stdout.lock(), "{}", text)
+    } else {
+        writeln!(io::stderr().lock(), "{}", text)
+    };
+}
+
+/// Writes `text` where the frames go as it is, for escapes that draw over
+/// what is already there.
+pub fn redraw(text: &str) {
+    let text = plain(text);
+    let stdout = io::stdout();
+    let _ = if stdout.is_terminal() {
+        let mut out = stdout.lock();
+        out.write_all(text.as_bytes()).and_then(|_| out.flush())
+    } else {
+        let mut out = io::stderr().lock();
+        out.write_all(text.as_bytes()).and_then(|_| out.flush())
+    };
+}
+
+/// Writes `line` and a newline to the data.
+pub fn data(line: &str) -> Result<(), String> {
+    let mut file = FILE.lock().unwrap_or_else(|e| e.into_inner());
+    match file.as_mut() {
+        Some((path, out)) => {
+            writeln!(out, "{}", line).map_err(|e| format!("cannot write {}: {}", path, e))
+        }
+        None => writeln!(io::stdout().lock(), "{}", line)
+            .map_err(|e| format!("cannot write stdout: {}", e)),
+    }
+}
+
+/// Flushes the `--output` file, if there is one.
+pub fn finish() -> Result<(), String> {
+    let mut file = FILE.lock().unwrap_or_else(|e| e.into_inner());
+    match file.as_mut() {
+        Some((path, out)) => out
+            .flush()
+            .map_err(|e| format!("cannot write {}: {}", path, e)),
+        None => Ok(()),
+    }
+}
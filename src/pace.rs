@@ -0,0 +1,212 @@
+//! Frame timing.
+
+use std::collections::VecDeque;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Frames the rolling frame rate is worked out over.
+const ROLLING: usize = 30;
+/// Frames in a row `skip` lets go undrawn to catch up. Further behind than
+/// that, the schedule starts over from the frame that is late.
+const MAX_SKIPPED: u32 = 8;
+
+/// Where the time of the frames went: working out the generations since the
+/// one before, drawing, and waiting for the next.
+#[derive(Clone, Debug, Default)]
+pub struct Timings {
+    pub frames: u64,
+    /// Frames that came later than the target rate allows, or were not
+    /// drawn at all to catch up.
+    pub dropped: u64,
+    pub simulate: Duration,
+    pub render: Duration,
+    pub sleep: Duration,
+    /// The last frame's own times, in the same order.
+    pub last: (Duration, Duration, Duration),
+    /// When the last `ROLLING` frames were done, and their simulating and
+    /// rendering times.
+    recent: VecDeque<(Instant, Duration, Duration)>,
+}
+
+impl Timings {
+    /// Counts a frame done now.
+    pub fn frame(&mut self, simulate: Duration, render: Duration, sleep: Duration, dropped: bool) {
+        self.frames += 1;
+        self.dropped += u64::from(dropped);
+        self.simulate += simulate;
+        self.render += render;
+        self.sleep += sleep;
+        self.last = (simulate, render, sleep);
+        if self.recent.len() == ROLLING {
+            self.recent.pop_front();
+        }
+        self.recent.push_back((Instant::now(), simulate, render));
+    }
+
+    /// Frames a second over the last few, or 0 before there are two.
+    pub fn fps(&self) -> f64 {
+        match (self.recent.front(), self.recent.back()) {
+            (Some(first), Some(last)) if self.recent.len() > 1 => {
+                let secs = (last.0 - first.0).as_secs_f64();
+                (self.recent.len() - 1) as f64 / secs.max(1e-9)
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// The simulating and rendering times of the last few frames on average.
+    pub fn rolling(&self) -> (Duration, Duration) {
+        let n = self.recent.len().max(1) as u32;
+        let (simulate, render) = self.recent.iter().fold(
+            (Duration::ZERO, Duration::ZERO),
+            |(s, r), &(_, simulate, render)| (s + simulate, r + render),
+        );
+        (simulate / n, render / n)
+    }
+
+    /// The last frame's times beside their rolling averages, and the rolling
+    /// rate, to show under it.
+    pub fn hud(&self) -> String {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let (simulate, render, sleep) = self.last;
+        let (average_simulate, average_render) = self.rolling();
+        format!(
+            "simulate {:.2}ms ({:.2} avg), render {:.2}ms ({:.2} avg), sleep {:.2}ms, \
+             {:.1} fps, {} dropped",
+            ms(simulate),
+            ms(average_simulate),
+            ms(render),
+            ms(average_render),
+            ms(sleep),
+            self.fps(),
+            self.dropped
+        )
+    }
+
+    /// The average times of a frame, for the summary.
+    pub fn averages(&self) -> (Duration, Duration, Duration) {
+        let n = self.frames.max(1) as u32;
+        (self.simulate / n, self.render / n, self.sleep / n)
+    }
+}
+
+/// Waits between frames, either a fixed pause after each one or, with a
+/// target frame rate, whatever is left of the frame after the work done in it,
+/// skipping the drawing of frames that fall behind so the rate holds.
+pub struct Pacer {
+    interval: Duration,
+    steady: bool,
+    next: Option<Instant>,
+    /// Frames `skip` has let go since the last one drawn.
+    skipped: u32,
+    pub timings: Timings,
+    /// The frame under way so far: its simulating time, and its sleep and
+    /// whether it was late once `wait` has been.
+    simulate: Duration,
+    slept: Duration,
+    late: bool,
+}
+
+impl Pacer {
+    /// Sleeps `delay` milliseconds every frame, however long the frame took.
+    pub fn delay(delay: u64) -> Pacer {
+        Pacer {
+            interval: Duration::from_millis(delay),
+            steady: false,
+            next: None,
+            skipped: 0,
+            timings: Timings::default(),
+            simulate: Duration::ZERO,
+            slept: Duration::ZERO,
+            late: false,
+        }
+    }
+
+    /// Keeps frames `1/fps` seconds apart.
+    pub fn fps(fps: u32) -> Pacer {
+        Pacer {
+            interval: Duration::from_secs(1) / fps,
+            steady: true,
+            next: None,
+            skipped: 0,
+            timings: Timings::default(),
+            simulate: Duration::ZERO,
+            slept: Duration::ZERO,
+            late: false,
+        }
+    }
+
+    /// Keeps frames `1/fps` seconds apart from now on.
+    pub fn set_fps(&mut self, fps: u32) {
+        self.interval = Duration::from_secs(1) / fps;
+        self.steady = true;
+        self.next = None;
+    }
+
+    /// The frames a second it keeps, or else the milliseconds it sleeps each
+    /// frame, however it was last set.
+    pub fn speed(&self) -> (Option<u32>, u64) {
+        let ms = self.interval.as_millis() as u64;
+        match self.steady {
+            true if !self.interval.is_zero() => {
+                (Some((1.0 / self.interval.as_secs_f64()).round() as u32), ms)
+            }
+            _ => (None, ms),
+        }
+    }
+
+    /// Counts `spent` working out a generation towards the next frame.
+    pub fn simulated(&mut self, spent: Duration) {
+        self.simulate += spent;
+    }
+
+    pub fn wait(&mut self) {
+        if !self.steady {
+            sleep(self.interval);
+            self.slept = self.interval;
+            return;
+        }
+        let now = Instant::now();
+        let next = self.next.unwrap_or(now + self.interval);
+        self.late = next < now;
+        self.slept = next.saturating_duration_since(now);
+        if next > now {
+            sleep(next - now);
+        }
+        // the schedule holds, with `skip` making up for slow frames, unless
+        // it is too far behind to catch up
+        let behind = now.saturating_duration_since(next);
+        self.next = Some(if behind > self.interval * MAX_SKIPPED {
+            now + self.interval
+        } else {
+            next + self.interval
+        });
+        self.skipped = 0;
+    }
+
+    /// Whether to leave the frame that is due undrawn, as the one after it
+    /// is already due too. Its generations count towards the next frame.
+    pub fn skip(&mut self) -> bool {
+        match self.next {
+            Some(next)
+                if self.steady
+                    && self.skipped < MAX_SKIPPED
+                    && Instant::now() >= next + self.interval =>
+            {
+                self.next = Some(next + self.interval);
+                self.skipped += 1;
+                self.timings.dropped += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Ends the frame, drawn in `spent`.
+    pub fn rendered(&mut self, spent: Duration) {
+        let simulate = std::mem::take(&mut self.simulate);
+        let slept = std::mem::take(&mut self.slept);
+        let late = std::mem::take(&mut self.late);
+        self.timings.frame(simulate, spent, slept, late);
+    }
+}
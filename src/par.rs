@@ -0,0 +1,122 @@
+//! A world's cells gone over by several threads at once, for analyses of
+//! big worlds that would otherwise look at every cell on one core:
+//!
+//! ```text
+//! let edges = world
+//!     .par_iter()
+//!     .count(|x, y, cell| cell == Cell::Live && world.count_neighbors(x, y) < 2);
+//! let columns = world.par_iter().fold(
+//!     || vec![0usize; world.width()],
+//!     |mut sums, x, _, cell| {
+//!         sums[x] += usize::from(cell == Cell::Live);
+//!         sums
+//!     },
+//! );
+//! ```
+//!
+//! The world is split into bands of whole rows, one to a thread, and the
+//! cells are handed over in place rather than copied out. What comes back,
+//! the cells `filter` keeps or the partial results of `fold`, is in row
+//! order, top band first, however the threads finished. Only built with the
+//! `parallel` feature.
+
+use std::thread;
+
+use crate::{Cell, World};
+
+/// The cells of a world as `(x, y, cell)`, from `World::par_iter`.
+pub struct ParCells<'a> {
+    world: &'a World,
+    threads: usize,
+}
+
+impl<'a> ParCells<'a> {
+    pub(crate) fn new(world: &'a World) -> ParCells<'a> {
+        ParCells {
+            world,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+
+    /// Uses `n` threads instead of one per core.
+    pub fn threads(mut self, n: usize) -> ParCells<'a> {
+        self.threads = n.max(1);
+        self
+    }
+
+    /// Runs `f` on the rows `y0..y1` of each band, a thread to a band, giving
+    /// back what it returns for each in order.
+    fn bands<T, F>(&self, f: F) -> Vec<T>
+    where
+        T: Send,
+        F: Fn(usize, usize) -> T + Sync,
+    {
+        let height = self.world.height();
+        let bands = self.threads.min(height);
+        let rows = height.div_ceil(bands);
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..bands)
+                .map(|band| {
+                    let f = &f;
+                    scope.spawn(move || f(band * rows, ((band + 1) * rows).min(height)))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+                .collect()
+        })
+    }
+
+    /// Runs `f` on every cell.
+    pub fn for_each<F>(self, f: F)
+    where
+        F: Fn(usize, usize, Cell) + Sync,
+    {
+        self.fold(|| (), |(), x, y, cell| f(x, y, cell));
+    }
+
+    /// Folds each band's cells into a value of its own, starting from
+    /// `identity`, and gives back one value a band for the caller to combine.
+    pub fn fold<T, I, F>(self, identity: I, fold: F) -> Vec<T>
+    where
+        T: Send,
+        I: Fn() -> T + Sync,
+        F: Fn(T, usize, usize, Cell) -> T + Sync,
+    {
+        let world = self.world;
+        self.bands(|y0, y1| {
+            let mut acc = identity();
+            for y in y0..y1 {
+                for (x, &cell) in world.row(y).iter().enumerate() {
+                    acc = fold(acc, x, y, cell);
+                }
+            }
+            acc
+        })
+    }
+
+    /// The cells `keep` is true of, in row-major order.
+    pub fn filter<F>(self, keep: F) -> Vec<(usize, usize, Cell)>
+    where
+        F: Fn(usize, usize, Cell) -> bool + Sync,
+    {
+        self.fold(Vec::new, |mut kept, x, y, cell| {
+            if keep(x, y, cell) {
+                kept.push((x, y, cell));
+            }
+            kept
+        })
+        .concat()
+    }
+
+    /// How many cells `pred` is true of.
+    pub fn count<F>(self, pred: F) -> usize
+    where
+        F: Fn(usize, usize, Cell) -> bool + Sync,
+    {
+        self.fold(|| 0, |n, x, y, cell| n + usize::from(pred(x, y, cell)))
+            .into_iter()
+            .sum()
+    }
+}
@@ -0,0 +1,867 @@
+//! Patterns and their text formats: Golly/LifeWiki RLE, plaintext (`.cells`)
+//! and the older Life 1.05 and 1.06 (`.lif`).
+
+use std::convert::TryFrom;
+use std::path::Path;
+
+use crate::{census, gzip, history, Cell, Rect, Rule, World};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Format {
+    Plaintext,
+    Rle,
+    /// Blocks of `.` and `*` rows, each placed by a `#P X Y` line.
+    Life105,
+    /// One `X Y` line for each live cell.
+    Life106,
+}
+
+impl Format {
+    /// Picks the format by name: rle, plaintext (or cells), life105 or
+    /// life106 (or lif).
+    pub fn parse(name: &str) -> Result<Format, String> {
+        match name {
+            "rle" => Ok(Format::Rle),
+            "plaintext" | "cells" => Ok(Format::Plaintext),
+            "life105" => Ok(Format::Life105),
+            "life106" | "lif" => Ok(Format::Life106),
+            _ => Err(format!(
+                "invalid pattern format '{}', expected rle, plaintext, life105 or life106",
+                name
+            )),
+        }
+    }
+
+    /// The format a file's extension says, the one before a `.gz`. A `.lif`
+    /// file is taken for Life 1.06 here, though `Pattern::load` reads either
+    /// version.
+    pub fn from_path(path: &str) -> Result<Format, String> {
+        let ext = Path::new(gzip::inner_path(path))
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("rle") => Ok(Format::Rle),
+            Some("cells") | Some("txt") => Ok(Format::Plaintext),
+            Some("lif") | Some("life") => Ok(Format::Life106),
+            _ => Err(format!(
+                "cannot tell the format of '{}', expected a .rle, .cells, .txt or .lif file, \
+                 perhaps gzipped",
+                path
+            )),
+        }
+    }
+
+    /// Guesses the format of already loaded text.
+    pub fn detect(text: &str) -> Format {
+        let first = text.trim_start().lines().next().unwrap_or("").trim();
+        if first.starts_with("#Life 1.05") {
+            return Format::Life105;
+        }
+        if first.starts_with("#Life 1.06") {
+            return Format::Life106;
+        }
+        let rle = text
+            .lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty() && !l.starts_with('#'))
+            .is_some_and(|l| l.starts_with('x'));
+        if rle {
+            Format::Rle
+        } else {
+            Format::Plaintext
+        }
+    }
+}
+
+/// A way to turn or mirror a pattern, turns being clockwise.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Transform {
+    Rot90,
+    Rot180,
+    Rot270,
+    /// Mirrors left to right.
+    FlipX,
+    /// Mirrors top to bottom.
+    FlipY,
+}
+
+impl Transform {
+    pub fn parse(s: &str) -> Result<Transform, String> {
+        match s {
+            "rot90" => Ok(Transform::Rot90),
+            "rot180" => Ok(Transform::Rot180),
+            "rot270" => Ok(Transform::Rot270),
+            "flipx" => Ok(Transform::FlipX),
+            "flipy" => Ok(Transform::FlipY),
+            _ => Err(format!(
+                "invalid transform '{}', expected rot90, rot180, rot270, flipx or flipy",
+                s
+            )),
+        }
+    }
+}
+
+/// Where a pattern came from, as its file says: kept through loading,
+/// running and saving so converting a collection doesn't lose it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PatternMeta {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    /// Free text, a line each.
+    pub comments: Vec<String>,
+}
+
+impl PatternMeta {
+    /// Takes `Name:` and `Author:` lines as the name and author and the rest
+    /// as comments, for the formats that only have comments.
+    fn add_line(&mut self, line: &str) {
+        if let Some(name) = line.strip_prefix("Name:") {
+            self.name = Some(name.trim().to_string());
+        } else if let Some(author) = line.strip_prefix("Author:") {
+            self.author = Some(author.trim().to_string());
+        } else {
+            self.comments.push(line.to_string());
+        }
+    }
+
+    /// The lines `add_line` reads back, each after `prefix`.
+    fn lines(&self, prefix: &str) -> String {
+        let mut s = String::new();
+        if let Some(name) = &self.name {
+            s.push_str(&format!("{}Name: {}\n", prefix, name));
+        }
+        if let Some(author) = &self.author {
+            s.push_str(&format!("{}Author: {}\n", prefix, author));
+        }
+        for comment in &self.comments {
+            s.push_str(&format!("{}{}\n", prefix, comment));
+        }
+        s
+    }
+}
+
+/// The state of a wall cell, dead for good: `W` in RLE, `#` in plaintext.
+pub const WALL: u8 = 8;
+
+/// Says whether there is room for a pattern of a width and height, failing
+/// with why not if there isn't.
+pub type Fits<'a> = &'a dyn Fn(usize, usize) -> Result<(), String>;
+
+pub(crate) fn any_size(_: usize, _: usize) -> Result<(), String> {
+    Ok(())
+}
+
+/// A rectangle of cells with an optional rule, independent of any world.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    width: usize,
+    height: usize,
+    /// Row-major states: 0 dead and 1 alive, or any of LifeHistory's.
+    cells: Vec<u8>,
+    rule: Option<Rule>,
+    meta: PatternMeta,
+}
+
+impl Pattern {
+    pub fn new(width: usize, height: usize) -> Pattern {
+        Pattern {
+            width,
+            height,
+            cells: vec![history::OFF; width * height],
+            rule: None,
+            meta: PatternMeta::default(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn rule(&self) -> Option<Rule> {
+        self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: Option<Rule>) {
+        self.rule = rule;
+    }
+
+    pub fn meta(&self) -> &PatternMeta {
+        &self.meta
+    }
+
+    pub fn set_meta(&mut self, meta: PatternMeta) {
+        self.meta = meta;
+    }
+
+    /// Number of live cells.
+    pub fn population(&self) -> usize {
+        self.cells
+            .iter()
+            .filter(|&&state| history::is_on(state))
+            .count()
+    }
+
+    /// The apgcode Catagolue knows the pattern by, run under its rule or
+    /// Life's, if it is a still life, oscillator or spaceship of a period up
+    /// to `census::APGCODE_MAX_PERIOD`.
+    pub fn apgcode(&self) -> Option<String> {
+        let cells: Vec<(usize, usize)> = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.get(x, y))
+            .collect();
+        census::apgcode(&cells, self.rule.unwrap_or_default())
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        history::is_on(self.cells[y * self.width + x])
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, live: bool) {
+        self.cells[y * self.width + x] = u8::from(live);
+    }
+
+    /// The LifeHistory state of the cell at `x`, `y`, just 0 or 1 in
+    /// patterns that aren't annotated.
+    pub fn state(&self, x: usize, y: usize) -> u8 {
+        self.cells[y * self.width + x]
+    }
+
+    pub fn set_state(&mut self, x: usize, y: usize, state: u8) {
+        self.cells[y * self.width + x] = state;
+    }
+
+    /// Whether any cell is in a state other than plain off and on.
+    fn annotated(&self) -> bool {
+        self.cells
+            .iter()
+            .any(|&state| state > history::ON && state != WALL)
+    }
+
+    /// Any text as a pattern, each character other than whitespace a live
+    /// cell and each line a row, cut down to the characters there are. Tabs
+    /// move on to the next multiple of eight columns.
+    pub fn from_text(text: &str) -> Pattern {
+        let mut live = Vec::new();
+        for (y, line) in text.lines().enumerate() {
+            let mut x = 0;
+            for c in line.chars() {
+                match c {
+                    '\t' => x = (x / 8 + 1) * 8,
+                    c if c.is_whitespace() => x += 1,
+                    _ => {
+                        live.push((x, y));
+                        x += 1;
+                    }
+                }
+            }
+        }
+        let left = live.iter().map(|c| c.0).min().unwrap_or(0);
+        let top = live.iter().map(|c| c.1).min().unwrap_or(0);
+        let width = live.iter().map(|c| c.0 + 1 - left).max().unwrap_or(0);
+        let height = live.iter().map(|c| c.1 + 1 - top).max().unwrap_or(0);
+        let mut pattern = Pattern::new(width, height);
+        for (x, y) in live {
+            pattern.set(x - left, y - top, true);
+        }
+        pattern
+    }
+
+    /// Every cell grown into a `factor` by `factor` square.
+    pub fn scaled(&self, factor: usize) -> Pattern {
+        let mut scaled = Pattern::new(self.width * factor, self.height * factor);
+        for y in 0..scaled.height {
+            for x in 0..scaled.width {
+                scaled.set_state(x, y, self.state(x / factor, y / factor));
+            }
+        }
+        scaled.rule = self.rule;
+        scaled.meta = self.meta.clone();
+        scaled
+    }
+
+    /// Captures the whole world, including its rule.
+    pub fn from_world(world: &World) -> Pattern {
+        let all = Rect {
+            x: 0,
+            y: 0,
+            width: world.width(),
+            height: world.height(),
+        };
+        Pattern::from_region(world, all)
+    }
+
+    /// Captures `area` of the world, which must lie inside it, its rule and
+    /// where it came from.
+    pub fn from_region(world: &World, area: Rect) -> Pattern {
+        let mut pattern = Pattern::new(area.width, area.height);
+        for y in 0..area.height {
+            for x in 0..area.width {
+                let (wx, wy) = (area.x + x, area.y + y);
+                let state = match world.history(wx, wy) {
+                    _ if world.is_wall(wx, wy) => WALL,
+                    Some(state) => state,
+                    None => u8::from(world.get(wx, wy) == Cell::Live),
+                };
+                pattern.set_state(x, y, state);
+            }
+        }
+        pattern.rule = Some(world.rule());
+        pattern.meta = world.meta().clone();
+        pattern
+    }
+
+    /// Like `from_world(world).crop()`, without copying the empty space first.
+    pub fn from_live_cells(world: &World) -> Pattern {
+        let area = world.extent().unwrap_or(Rect {
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+        });
+        Pattern::from_region(world, area)
+    }
+
+    /// Trims empty rows and columns from the edges, keeping any cell in a
+    /// state other than off. An empty pattern becomes 0x0.
+    pub fn crop(&self) -> Pattern {
+        let live = || {
+            (0..self.height)
+                .flat_map(move |y| (0..self.width).map(move |x| (x, y)))
+                .filter(move |&(x, y)| self.state(x, y) != history::OFF)
+        };
+        let (mut x0, mut y0, mut x1, mut y1) = (usize::MAX, usize::MAX, 0, 0);
+        for (x, y) in live() {
+            x0 = x0.min(x);
+            y0 = y0.min(y);
+            x1 = x1.max(x + 1);
+            y1 = y1.max(y + 1);
+        }
+        if x0 == usize::MAX {
+            let mut empty = Pattern::new(0, 0);
+            empty.rule = self.rule;
+            empty.meta = self.meta.clone();
+            return empty;
+        }
+        let mut cropped = Pattern::new(x1 - x0, y1 - y0);
+        cropped.rule = self.rule;
+        cropped.meta = self.meta.clone();
+        for (x, y) in live() {
+            cropped.set_state(x - x0, y - y0, self.state(x, y));
+        }
+        cropped
+    }
+
+    /// The pattern turned or mirrored, keeping its rule.
+    pub fn transformed(&self, transform: Transform) -> Pattern {
+        let (w, h) = (self.width, self.height);
+        let mut out = match transform {
+            Transform::Rot90 | Transform::Rot270 => Pattern::new(h, w),
+            _ => Pattern::new(w, h),
+        };
+        out.rule = self.rule;
+        out.meta = self.meta.clone();
+        for y in 0..h {
+            for x in 0..w {
+                let (tx, ty) = match transform {
+                    Transform::Rot90 => (h - 1 - y, x),
+                    Transform::Rot180 => (w - 1 - x, h - 1 - y),
+                    Transform::Rot270 => (y, w - 1 - x),
+                    Transform::FlipX => (w - 1 - x, y),
+                    Transform::FlipY => (x, h - 1 - y),
+                };
+                out.set_state(tx, ty, self.state(x, y));
+            }
+        }
+        out
+    }
+
+    pub fn parse(text: &str) -> Result<Pattern, String> {
+        Pattern::parse_within(text, &any_size)
+    }
+
+    pub fn parse_as(text: &str, format: Format) -> Result<Pattern, String> {
+        Pattern::parse_as_within(text, format, &any_size)
+    }
+
+    /// Parses `text`, asking `fits` whether there is room for a pattern of
+    /// the width and height it gives before making any, so a header asking
+    /// for far more cells than it has fails instead of the allocation.
+    pub fn parse_within(text: &str, fits: Fits) -> Result<Pattern, String> {
+        Pattern::parse_as_within(text, Format::detect(text), fits)
+    }
+
+    pub fn parse_as_within(text: &str, format: Format, fits: Fits) -> Result<Pattern, String> {
+        match format {
+            Format::Plaintext => Pattern::plaintext(text, fits),
+            Format::Rle => Pattern::rle(text, fits),
+            Format::Life105 => Pattern::life105(text, fits),
+            Format::Life106 => Pattern::life106(text, fits),
+        }
+    }
+
+    /// An empty `width` by `height` pattern, if `fits` takes it.
+    fn sized(width: usize, height: usize, fits: Fits) -> Result<Pattern, String> {
+        if width.checked_mul(height).is_none() {
+            return Err(format!(
+                "a {}x{} pattern is too big to exist",
+                width, height
+            ));
+        }
+        fits(width, height)?;
+        Ok(Pattern::new(width, height))
+    }
+
+    /// The pattern just big enough for `live`, cells at any coordinates.
+    fn from_coordinates(live: &[(i64, i64)], fits: Fits) -> Result<Pattern, String> {
+        let (x0, y0) = (
+            live.iter().map(|c| c.0).min().unwrap_or(0),
+            live.iter().map(|c| c.1).min().unwrap_or(0),
+        );
+        let (x1, y1) = (
+            live.iter().map(|c| c.0).max().map_or(0, |x| x as i128 + 1),
+            live.iter().map(|c| c.1).max().map_or(0, |y| y as i128 + 1),
+        );
+        let span = |from: i64, to: i128| usize::try_from(to - from as i128).ok();
+        let (width, height) = match (span(x0, x1), span(y0, y1)) {
+            (Some(width), Some(height)) => (width, height),
+            _ => return Err("the cells are too far apart for a pattern".to_string()),
+        };
+        let mut pattern = Pattern::sized(width, height, fits)?;
+        for &(x, y) in live {
+            pattern.set((x - x0) as usize, (y - y0) as usize, true);
+        }
+        Ok(pattern)
+    }
+
+    /// `#Life 1.05`, then `#D` descriptions, `#N` for Conway's rule or `#R`
+    /// with one in S/B form like `23/3`, and blocks of `.` and `*` rows, each
+    /// starting at the cell a `#P X Y` line before it gives.
+    pub fn parse_life105(text: &str) -> Result<Pattern, String> {
+        Pattern::life105(text, &any_size)
+    }
+
+    fn life105(text: &str, fits: Fits) -> Result<Pattern, String> {
+        let mut live = Vec::new();
+        let (mut rule, mut meta) = (None, PatternMeta::default());
+        let (mut x0, mut y) = (0i64, 0i64);
+        for (i, line) in text.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+            if let Some(description) = line.strip_prefix("#D") {
+                meta.add_line(description.strip_prefix(' ').unwrap_or(description));
+            } else if let Some(value) = line.strip_prefix("#R") {
+                rule = Some(Rule::parse(value.trim())?);
+            } else if line.starts_with("#N") {
+                rule = Some(Rule::conway());
+            } else if let Some(at) = line.strip_prefix("#P") {
+                let mut at = at.split_whitespace().map(str::parse::<i64>);
+                match (at.next(), at.next()) {
+                    (Some(Ok(x)), Some(Ok(top))) => {
+                        x0 = x;
+                        y = top;
+                    }
+                    _ => return Err(format!("line {}: malformed #P line '{}'", i, line)),
+                }
+            } else if !line.starts_with('#') && !line.is_empty() {
+                for (x, c) in line.chars().enumerate() {
+                    match c {
+                        '.' => {}
+                        '*' => live.push((x0 + x as i64, y)),
+                        _ => {
+                            return Err(format!(
+                                "line {}: unexpected '{}' in Life 1.05 pattern",
+                                i, c
+                            ))
+                        }
+                    }
+                }
+                y += 1;
+            }
+        }
+        let mut pattern = Pattern::from_coordinates(&live, fits)?;
+        pattern.rule = rule;
+        pattern.meta = meta;
+        Ok(pattern)
+    }
+
+    /// `#Life 1.06`, then an `X Y` line for each live cell, anywhere.
+    pub fn parse_life106(text: &str) -> Result<Pattern, String> {
+        Pattern::life106(text, &any_size)
+    }
+
+    fn life106(text: &str, fits: Fits) -> Result<Pattern, String> {
+        let mut live = Vec::new();
+        for (i, line) in text.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+            if line.starts_with('#') || line.is_empty() {
+                continue;
+            }
+            let mut xy = line.split_whitespace().map(str::parse::<i64>);
+            match (xy.next(), xy.next(), xy.next()) {
+                (Some(Ok(x)), Some(Ok(y)), None) => live.push((x, y)),
+                _ => return Err(format!("line {}: expected a cell like '3 -1'", i)),
+            }
+        }
+        Pattern::from_coordinates(&live, fits)
+    }
+
+    /// `.` is dead, `O` or `*` is live, lines starting with `!` are comments.
+    /// A `!Rule: B36/S23` comment sets the rule, and `!Name:` and `!Author:`
+    /// ones say what the pattern is.
+    pub fn parse_plaintext(text: &str) -> Result<Pattern, String> {
+        Pattern::plaintext(text, &any_size)
+    }
+
+    fn plaintext(text: &str, fits: Fits) -> Result<Pattern, String> {
+        let (mut rule, mut meta) = (None, PatternMeta::default());
+        for line in text.lines() {
+            if let Some(value) = line.strip_prefix("!Rule:") {
+                rule = Some(Rule::parse(value.trim())?);
+            } else if let Some(comment) = line.strip_prefix('!') {
+                meta.add_line(comment.trim_end());
+            }
+        }
+        let rows: Vec<&str> = text
+            .lines()
+            .filter(|l| !l.starts_with('!'))
+            .map(str::trim_end)
+            .collect();
+        let height = rows
+            .iter()
+            .rposition(|r| !r.is_empty())
+            .map_or(0, |i| i + 1);
+        let width = rows.iter().map(|r| r.chars().count()).max().unwrap_or(0);
+        let mut pattern = Pattern::sized(width, height, fits)?;
+        for (y, row) in rows.iter().take(height).enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                match c {
+                    '.' | ' ' => {}
+                    'O' | 'o' | '*' => pattern.set(x, y, true),
+                    '#' => pattern.set_state(x, y, WALL),
+                    _ => {
+                        return Err(format!(
+                            "line {}: unexpected '{}' in plaintext pattern",
+                            y + 1,
+                            c
+                        ))
+                    }
+                }
+            }
+        }
+        pattern.rule = rule;
+        pattern.meta = meta;
+        Ok(pattern)
+    }
+
+    /// Parses the `#N` name, `#O` author and `#C` comment lines, the
+    /// `x = m, y = n, rule = ...` header and the run-length encoded body. An
+    /// old-style `#r` line gives the rule if the header doesn't.
+    pub fn parse_rle(text: &str) -> Result<Pattern, String> {
+        Pattern::rle(text, &any_size)
+    }
+
+    fn rle(text: &str, fits: Fits) -> Result<Pattern, String> {
+        let (mut meta, mut old_rule) = (PatternMeta::default(), None);
+        for line in text.lines().filter_map(|l| l.trim().strip_prefix('#')) {
+            let mut chars = line.chars();
+            let tag = chars.next();
+            let value = chars.as_str().trim();
+            match tag {
+                Some('N') => meta.name = Some(value.to_string()),
+                Some('O') => meta.author = Some(value.to_string()),
+                Some('C') | Some('c') => meta.comments.push(value.to_string()),
+                Some('r') => old_rule = Some(Rule::parse(value)?),
+                _ => {}
+            }
+        }
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l.trim()))
+            .filter(|(_, l)| !l.is_empty() && !l.starts_with('#'));
+
+        let (header_line, header) = lines.next().ok_or("empty RLE pattern")?;
+        let (mut width, mut height, mut rule) = (None, None, None);
+        // the rule comes last, and has commas of its own in Larger than Life
+        let (header, rule_field) = match header.find("rule") {
+            Some(i) => (
+                header[..i].trim_end().trim_end_matches(','),
+                Some(&header[i..]),
+            ),
+            None => (header, None),
+        };
+        for field in header.split(',').chain(rule_field) {
+            let mut kv = field.splitn(2, '=').map(str::trim);
+            let key = kv.next().unwrap_or("");
+            let value = kv.next().ok_or_else(|| {
+                format!(
+                    "line {}: malformed RLE header field '{}'",
+                    header_line, field
+                )
+            })?;
+            let number = || {
+                value
+                    .parse::<usize>()
+                    .map_err(|_| format!("line {}: invalid {} '{}'", header_line, key, value))
+            };
+            match key {
+                "x" => width = Some(number()?),
+                "y" => height = Some(number()?),
+                // ignore Golly's bounded grid suffix such as `:T40,30`, though
+                // not the colon after a weighted rule's radius
+                "rule" => {
+                    let value = match value.rfind(':') {
+                        Some(i)
+                            if value[i + 1..].starts_with(|c: char| c.is_ascii_alphabetic()) =>
+                        {
+                            &value[..i]
+                        }
+                        _ => value,
+                    };
+                    rule = Some(Rule::parse(value)?);
+                }
+                _ => {}
+            }
+        }
+        let (width, height) = match (width, height) {
+            (Some(w), Some(h)) => (w, h),
+            _ => return Err(format!("line {}: RLE header needs x and y", header_line)),
+        };
+
+        let mut pattern = Pattern::sized(width, height, fits)
+            .map_err(|e| format!("line {}: {}", header_line, e))?;
+        pattern.rule = rule.or(old_rule);
+        pattern.meta = meta;
+        // the letters of more states only mean something under a History rule
+        let annotated = pattern.rule.is_some_and(|rule| rule.history());
+        let (mut x, mut y) = (0, 0);
+        let mut count = String::new();
+        'body: for (line, body) in lines {
+            for c in body.chars() {
+                let run = || count.parse::<usize>().unwrap_or(1);
+                let state = match c {
+                    'b' | 'o' => history::from_letter(c),
+                    'W' => Some(WALL),
+                    c if annotated => history::from_letter(c),
+                    _ => None,
+                };
+                match (c, state) {
+                    ('0'..='9', _) => count.push(c),
+                    (_, Some(history::OFF)) => {
+                        x += run();
+                        count.clear();
+                    }
+                    (_, Some(state)) => {
+                        for _ in 0..run() {
+                            if x >= width || y >= height {
+                                return Err(format!(
+                                    "line {}: cell {},{} is outside the declared {}x{}",
+                                    line, x, y, width, height
+                                ));
+                            }
+                            pattern.set_state(x, y, state);
+                            x += 1;
+                        }
+                        count.clear();
+                    }
+                    ('$', _) => {
+                        y += run();
+                        x = 0;
+                        count.clear();
+                    }
+                    ('!', _) => break 'body,
+                    (c, _) if c.is_whitespace() => {}
+                    _ => return Err(format!("line {}: unsupported RLE cell state '{}'", line, c)),
+                }
+            }
+        }
+        Ok(pattern)
+    }
+
+    pub fn to_plaintext(&self) -> String {
+        let mut s = String::with_capacity(self.height * (self.width + 1));
+        s.push_str(&self.meta.lines("!"));
+        if let Some(rule) = self.rule {
+            s.push_str(&format!("!Rule: {}\n", rule));
+        }
+        for y in 0..self.height {
+            for x in 0..self.width {
+                s.push(match self.state(x, y) {
+                    WALL => '#',
+                    _ if self.get(x, y) => 'O',
+                    _ => '.',
+                });
+            }
+            s.push('\n');
+        }
+        s
+    }
+
+    /// RLE, with the letters of multi-state RLE for patterns with cells in
+    /// the extra states of LifeHistory.
+    pub fn to_rle(&self) -> String {
+        fn push_run(body: &mut String, n: usize, tag: char) {
+            if n > 1 {
+                body.push_str(&n.to_string());
+            }
+            if n > 0 {
+                body.push(tag);
+            }
+        }
+
+        let annotated = self.annotated();
+        let tag = |state| match state {
+            WALL => 'W',
+            _ if annotated => history::letter(state),
+            history::OFF => 'b',
+            _ => 'o',
+        };
+        let mut body = String::new();
+        let mut blank_rows = 0;
+        let mut emitted = false;
+        for y in 0..self.height {
+            let mut row = String::new();
+            let mut x = 0;
+            while x < self.width {
+                let state = self.state(x, y);
+                let start = x;
+                while x < self.width && self.state(x, y) == state {
+                    x += 1;
+                }
+                // trailing dead cells are implied by the end of the row
+                if state != history::OFF || x < self.width {
+                    push_run(&mut row, x - start, tag(state));
+                }
+            }
+            if row.is_empty() {
+                blank_rows += 1;
+                continue;
+            }
+            // the first row has no newline before it, only the blank rows above it
+            push_run(&mut body, blank_rows + emitted as usize, '$');
+            blank_rows = 0;
+            emitted = true;
+            body.push_str(&row);
+        }
+        body.push('!');
+
+        let mut s = String::new();
+        if let Some(name) = &self.meta.name {
+            s.push_str(&format!("#N {}\n", name));
+        }
+        if let Some(author) = &self.meta.author {
+            s.push_str(&format!("#O {}\n", author));
+        }
+        for comment in &self.meta.comments {
+            s.push_str(&format!("#C {}\n", comment));
+        }
+        let rule = self.rule.unwrap_or_default();
+        s.push_str(&format!(
+            "x = {}, y = {}, rule = {}\n",
+            self.width, self.height, rule
+        ));
+        // keep lines under 70 characters as the format recommends, splitting only
+        // after a complete run
+        let mut line_len = 0;
+        let mut token = String::new();
+        for c in body.chars() {
+            token.push(c);
+            if c.is_ascii_digit() {
+                continue;
+            }
+            if line_len + token.len() > 70 {
+                s.push('\n');
+                line_len = 0;
+            }
+            s.push_str(&token);
+            line_len += token.len();
+            token.clear();
+        }
+        s.push('\n');
+        s
+    }
+
+    /// The pattern centered on 0,0 as Life 1.05, in one block, its name,
+    /// author and comments as `#D` lines. Conway's rule is written as `#N`,
+    /// any other as `#R` in S/B form.
+    pub fn to_life105(&self) -> String {
+        let mut s = "#Life 1.05\n".to_string();
+        s.push_str(&self.meta.lines("#D "));
+        match self.rule.filter(|&rule| rule != Rule::conway()) {
+            None => s.push_str("#N\n"),
+            Some(rule) => {
+                let rule = rule.to_string();
+                let (birth, survival) = rule.split_once('/').unwrap_or(("B", "S"));
+                s.push_str(&format!("#R {}/{}\n", &survival[1..], &birth[1..]));
+            }
+        }
+        s.push_str(&format!(
+            "#P {} {}\n",
+            -((self.width / 2) as i64),
+            -((self.height / 2) as i64)
+        ));
+        for y in 0..self.height {
+            let row: String = (0..self.width)
+                .map(|x| if self.get(x, y) { '*' } else { '.' })
+                .collect();
+            // dead cells at the end of a row go without saying, but a row
+            // needs one character to count
+            match row.trim_end_matches('.') {
+                "" => s.push('.'),
+                row => s.push_str(row),
+            }
+            s.push('\n');
+        }
+        s
+    }
+
+    /// The pattern centered on 0,0 as Life 1.06, which has no place for a
+    /// rule, a name or comments.
+    pub fn to_life106(&self) -> String {
+        let mut s = "#Life 1.06\n".to_string();
+        let (dx, dy) = ((self.width / 2) as i64, (self.height / 2) as i64);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.get(x, y) {
+                    s.push_str(&format!("{} {}\n", x as i64 - dx, y as i64 - dy));
+                }
+            }
+        }
+        s
+    }
+
+    pub fn to_format(&self, format: Format) -> String {
+        match format {
+            Format::Rle => self.to_rle(),
+            Format::Plaintext => self.to_plaintext(),
+            Format::Life105 => self.to_life105(),
+            Format::Life106 => self.to_life106(),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Pattern, String> {
+        Pattern::load_within(path, &any_size)
+    }
+
+    /// Loads the pattern at `path`, refusing one `fits` has no room for as
+    /// `parse_within` does.
+    pub fn load_within(path: &str, fits: Fits) -> Result<Pattern, String> {
+        let text = gzip::read_to_string(path)?;
+        let parsed = match Format::from_path(path) {
+            Ok(Format::Rle) => Pattern::rle(&text, fits),
+            Ok(Format::Plaintext) => Pattern::plaintext(&text, fits),
+            // either version of Life, which only the header tells apart
+            Ok(Format::Life105) | Ok(Format::Life106) | Err(_) => {
+                Pattern::parse_within(&text, fits)
+            }
+        };
+        parsed.map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let text = self.to_format(Format::from_path(path)?);
+        gzip::write(path, text.into_bytes())
+    }
+}
@@ -0,0 +1,89 @@
+//! Noise for seeing how well a pattern stands up to being knocked about:
+//! every so often each cell flips with some small chance.
+
+use conway::{Cell, Rng, World};
+
+/// Keeps the flips apart from the soup drawn from the same seed.
+const STREAM: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// `--perturb P@N`: each cell flips with chance `chance` after every
+/// `every` generations.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Perturb {
+    pub chance: f64,
+    pub every: u64,
+}
+
+impl Perturb {
+    pub fn parse(s: &str) -> Result<Perturb, String> {
+        let err = || {
+            format!(
+                "invalid --perturb '{}', expected a chance from 0 to 1 and a number of \
+                 generations like 0.001@10",
+                s
+            )
+        };
+        let (chance, every) = s.split_once('@').ok_or_else(err)?;
+        let chance: f64 = chance.trim().parse().map_err(|_| err())?;
+        let every: u64 = every.trim().parse().map_err(|_| err())?;
+        if !(0.0..=1.0).contains(&chance) || every == 0 {
+            return Err(err());
+        }
+        Ok(Perturb { chance, every })
+    }
+}
+
+pub struct Perturber {
+    perturb: Perturb,
+    rng: Rng,
+    /// Times cells were flipped so far.
+    pub rounds: u64,
+    pub flipped: u64,
+}
+
+impl Perturber {
+    /// Flips drawn from `seed`, so a run given the same one is perturbed the
+    /// same way.
+    pub fn new(perturb: Perturb, seed: u64) -> Perturber {
+        Perturber {
+            perturb,
+            rng: Rng::new(seed ^ STREAM),
+            rounds: 0,
+            flipped: 0,
+        }
+    }
+
+    /// Flips cells if `generation` is one to, telling `flip` about each, and
+    /// returns how many it flipped.
+    pub fn apply<F: FnMut(usize, usize, Cell)>(
+        &mut self,
+        world: &mut World,
+        generation: u64,
+        mut flip: F,
+    ) -> usize {
+        if !generation.is_multiple_of(self.perturb.every) {
+            return 0;
+        }
+        self.rounds += 1;
+        let mut flipped = 0;
+        for y in 0..world.height() {
+            for x in 0..world.width() {
+                if self.rng.chance(self.perturb.chance) {
+                    world.toggle(x, y);
+                    flip(x, y, world.get(x, y));
+                    flipped += 1;
+                }
+            }
+        }
+        self.flipped += flipped as u64;
+        flipped
+    }
+
+    /// How the run was perturbed, for the end of a report.
+    pub fn describe(&self) -> String {
+        format!(
+            "{} cells flipped in {} rounds, each cell with chance {} every {} generations",
+            self.flipped, self.rounds, self.perturb.chance, self.perturb.every
+        )
+    }
+}
@@ -0,0 +1,284 @@
+//! Minimal PNG encoder: 8-bit RGB, unfiltered rows, stored (uncompressed)
+//! deflate blocks. Files are larger than they need to be, but it needs nothing
+//! beyond std. The decoder reads any PNG that isn't interlaced.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+
+use conway::inflate;
+
+fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        let mut c = n as u32;
+        for _ in 0..8 {
+            c = if c & 1 != 0 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+        }
+        *entry = c;
+    }
+    table
+}
+
+fn crc32(table: &[u32; 256], chunks: &[&[u8]]) -> u32 {
+    let mut c = 0xFFFF_FFFFu32;
+    for chunk in chunks {
+        for &b in chunk.iter() {
+            c = table[((c ^ u32::from(b)) & 0xFF) as usize] ^ (c >> 8);
+        }
+    }
+    c ^ 0xFFFF_FFFF
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += u32::from(byte);
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+fn write_chunk<W: Write>(
+    out: &mut W,
+    table: &[u32; 256],
+    kind: &[u8],
+    data: &[u8],
+) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    out.write_all(&crc32(table, &[kind, data]).to_be_bytes())
+}
+
+/// Encodes `rgb` (3 bytes per pixel, row-major) as a PNG image, with `comment`
+/// as a Comment text chunk.
+pub fn encode<W: Write>(
+    out: &mut W,
+    width: usize,
+    height: usize,
+    rgb: &[u8],
+    comment: &str,
+) -> io::Result<()> {
+    assert_eq!(rgb.len(), width * height * 3, "pixel buffer size mismatch");
+    let table = crc_table();
+    out.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // bit depth 8, color type 2 (RGB), default compression, filter and interlacing
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_chunk(out, &table, b"IHDR", &header)?;
+
+    let mut text = b"Comment\0".to_vec();
+    text.extend_from_slice(comment.as_bytes());
+    write_chunk(out, &table, b"tEXt", &text)?;
+
+    let mut raw = Vec::with_capacity((width * 3 + 1) * height);
+    for row in rgb.chunks(width * 3) {
+        raw.push(0); // no filter
+        raw.extend_from_slice(row);
+    }
+
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = raw.chunks(0xFFFF).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        zlib.push(last as u8);
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+    write_chunk(out, &table, b"IDAT", &zlib)?;
+
+    write_chunk(out, &table, b"IEND", &[])
+}
+
+pub fn write(
+    path: &str,
+    width: usize,
+    height: usize,
+    rgb: &[u8],
+    comment: &str,
+) -> Result<(), String> {
+    let result = File::create(path).and_then(|f| {
+        let mut out = BufWriter::new(f);
+        encode(&mut out, width, height, rgb, comment)?;
+        out.flush()
+    });
+    result.map_err(|e| format!("cannot write {}: {}", path, e))
+}
+
+/// A decoded image, as the lightness of each pixel from 0 (black) to 1
+/// (white). Transparent pixels count as white.
+pub struct Gray {
+    pub width: usize,
+    pub height: usize,
+    /// Row-major.
+    pub pixels: Vec<f64>,
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = i16::from(a) + i16::from(b) - i16::from(c);
+    let (pa, pb, pc) = (
+        (p - i16::from(a)).abs(),
+        (p - i16::from(b)).abs(),
+        (p - i16::from(c)).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Undoes the filter in front of each row, in place, giving back the rows.
+fn unfilter(data: &mut [u8], row_bytes: usize, pixel_bytes: usize) -> Result<Vec<&[u8]>, String> {
+    let mut rows: Vec<&[u8]> = Vec::new();
+    let mut previous: Vec<u8> = vec![0; row_bytes];
+    for chunk in data.chunks_mut(row_bytes + 1) {
+        if chunk.len() != row_bytes + 1 {
+            return Err("the image data is cut short".to_string());
+        }
+        let (filter, row) = chunk.split_at_mut(1);
+        for i in 0..row_bytes {
+            let left = if i >= pixel_bytes {
+                row[i - pixel_bytes]
+            } else {
+                0
+            };
+            let up = previous[i];
+            let corner = if i >= pixel_bytes {
+                previous[i - pixel_bytes]
+            } else {
+                0
+            };
+            row[i] = row[i].wrapping_add(match filter[0] {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((u16::from(left) + u16::from(up)) / 2) as u8,
+                4 => paeth(left, up, corner),
+                f => return Err(format!("invalid row filter {}", f)),
+            });
+        }
+        previous.copy_from_slice(row);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Gray, String> {
+    let data = bytes
+        .strip_prefix(b"\x89PNG\r\n\x1a\n")
+        .ok_or("not a PNG image")?;
+    let (mut header, mut palette, mut compressed) = (None, Vec::new(), Vec::new());
+    let mut rest = data;
+    while rest.len() >= 12 {
+        let len = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        let kind = &rest[4..8];
+        let chunk = rest.get(8..8 + len).ok_or("the image is cut short")?;
+        match kind {
+            b"IHDR" if len == 13 => header = Some(chunk.to_vec()),
+            b"PLTE" => palette = chunk.to_vec(),
+            b"IDAT" => compressed.extend_from_slice(chunk),
+            b"IEND" => break,
+            _ => {}
+        }
+        rest = rest.get(12 + len..).ok_or("the image is cut short")?;
+    }
+    let header = header.ok_or("the image has no header")?;
+    let number =
+        |i: usize| u32::from_be_bytes([header[i], header[i + 1], header[i + 2], header[i + 3]]);
+    let (width, height) = (number(0) as usize, number(4) as usize);
+    let (depth, color) = (header[8], header[9]);
+    if header[12] != 0 {
+        return Err("interlaced images are not supported".to_string());
+    }
+    let channels = match (color, depth) {
+        (0, 1) | (0, 2) | (0, 4) | (0, 8) | (0, 16) => 1,
+        (3, 1) | (3, 2) | (3, 4) | (3, 8) => 1,
+        (4, 8) | (4, 16) => 2,
+        (2, 8) | (2, 16) => 3,
+        (6, 8) | (6, 16) => 4,
+        _ => {
+            return Err(format!(
+                "unsupported color type {} at bit depth {}",
+                color, depth
+            ))
+        }
+    };
+    let bits_per_pixel = channels * usize::from(depth);
+    let row_bytes = (width * bits_per_pixel).div_ceil(8);
+    let mut raw = inflate::zlib(&compressed)?;
+    let rows = unfilter(&mut raw, row_bytes, bits_per_pixel.div_ceil(8))?;
+    if rows.len() < height {
+        return Err("the image data is cut short".to_string());
+    }
+
+    let max = f64::from((1u32 << depth) - 1);
+    let mut pixels = Vec::with_capacity(width * height);
+    for row in rows.iter().take(height) {
+        // each sample of the row as a fraction of its largest value
+        let sample = |i: usize| -> f64 {
+            match depth {
+                16 => f64::from(u16::from_be_bytes([row[i * 2], row[i * 2 + 1]])) / max,
+                8 => f64::from(row[i]) / max,
+                _ => {
+                    let bit = i * usize::from(depth);
+                    let shift = 8 - usize::from(depth) - bit % 8;
+                    f64::from((row[bit / 8] >> shift) & ((1 << depth) - 1) as u8) / max
+                }
+            }
+        };
+        let luma = |r: f64, g: f64, b: f64| 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        for x in 0..width {
+            let (value, alpha) = match color {
+                0 => (sample(x), 1.0),
+                4 => (sample(x * 2), sample(x * 2 + 1)),
+                2 => (
+                    luma(sample(x * 3), sample(x * 3 + 1), sample(x * 3 + 2)),
+                    1.0,
+                ),
+                6 => (
+                    luma(sample(x * 4), sample(x * 4 + 1), sample(x * 4 + 2)),
+                    sample(x * 4 + 3),
+                ),
+                _ => {
+                    let index = (sample(x) * max).round() as usize;
+                    let rgb = palette
+                        .get(index * 3..index * 3 + 3)
+                        .ok_or("a pixel outside the palette")?;
+                    let channel = |c: u8| f64::from(c) / 255.0;
+                    (luma(channel(rgb[0]), channel(rgb[1]), channel(rgb[2])), 1.0)
+                }
+            };
+            pixels.push(value * alpha + (1.0 - alpha));
+        }
+    }
+    Ok(Gray {
+        width,
+        height,
+        pixels,
+    })
+}
+
+pub fn read(path: &str) -> Result<Gray, String> {
+    let bytes = fs::read(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    decode(&bytes).map_err(|e| format!("{}: {}", path, e))
+}
@@ -0,0 +1,78 @@
+//! The pool of threads the commands made of many independent runs share:
+//! jobs are handed out one at a time, so a thread stuck on a long one
+//! doesn't hold up the others, and the first to fail stops the rest.
+
+use std::panic;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Runs `job` on each of `0..jobs` across `threads` threads, giving back
+/// what each returned in order, or the first error, after which no more
+/// jobs start.
+pub fn run<T, F>(jobs: usize, threads: usize, job: F) -> Result<Vec<T>, String>
+where
+    T: Send,
+    F: Fn(usize) -> Result<T, String> + Sync,
+{
+    run_with(jobs, threads, || (), |(), i| job(i)).map(|(results, _)| results)
+}
+
+/// `run` with a state for each thread, made by `state` and handed to every
+/// job the thread runs, given back at the end for the caller to combine.
+pub fn run_with<S, T, I, F>(
+    jobs: usize,
+    threads: usize,
+    state: I,
+    job: F,
+) -> Result<(Vec<T>, Vec<S>), String>
+where
+    S: Send,
+    T: Send,
+    I: Fn() -> S + Sync,
+    F: Fn(&mut S, usize) -> Result<T, String> + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let results = Mutex::new((0..jobs).map(|_| None).collect::<Vec<_>>());
+    let failed = Mutex::new(None);
+    let states = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads.clamp(1, jobs.max(1)))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut own = state();
+                    while !stop.load(Ordering::Relaxed) {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        if i >= jobs {
+                            break;
+                        }
+                        match job(&mut own, i) {
+                            Ok(result) => results.lock().unwrap()[i] = Some(result),
+                            Err(e) => {
+                                stop.store(true, Ordering::Relaxed);
+                                // one started before the stop may fail too
+                                failed.lock().unwrap().get_or_insert(e);
+                                break;
+                            }
+                        }
+                    }
+                    own
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect()
+    });
+    if let Some(e) = failed.into_inner().unwrap() {
+        return Err(e);
+    }
+    let results = results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect();
+    Ok((results, states))
+}
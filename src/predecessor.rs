@@ -0,0 +1,141 @@
+//! Searching for a state that becomes a given pattern one generation later.
+//!
+//! The search is a backtracking one over the cells of a box around the
+//! pattern, on an otherwise empty plane rather than a torus. Each cell is set
+//! in row-major order, and as soon as all the neighbors of some cell of the
+//! next generation are known it is checked against the pattern, which prunes
+//! most of the tree. It is still exponential in the width of the box, so it
+//! is meant for small patterns.
+
+use crate::{Cell, Pattern, Rule};
+
+/// How a predecessor search turned out.
+#[derive(Clone, Debug)]
+pub enum Predecessor {
+    Found(Pattern),
+    /// There is no predecessor that fits in the box searched.
+    NoneWithin,
+    /// The search tried `limit` cell assignments without finishing.
+    GaveUp,
+}
+
+struct Search<'a> {
+    target: &'a Pattern,
+    rule: Rule,
+    margin: usize,
+    /// Size of the box the predecessor is looked for in.
+    width: usize,
+    height: usize,
+    cells: Vec<bool>,
+    steps: u64,
+    limit: u64,
+}
+
+impl<'a> Search<'a> {
+    /// The candidate's cell, with everything outside the box dead.
+    fn get(&self, x: isize, y: isize) -> bool {
+        x >= 0
+            && y >= 0
+            && (x as usize) < self.width
+            && (y as usize) < self.height
+            && self.cells[y as usize * self.width + x as usize]
+    }
+
+    fn wanted(&self, x: isize, y: isize) -> bool {
+        let (x, y) = (x - self.margin as isize, y - self.margin as isize);
+        x >= 0
+            && y >= 0
+            && (x as usize) < self.target.width()
+            && (y as usize) < self.target.height()
+            && self.target.get(x as usize, y as usize)
+    }
+
+    /// Whether the cell at `x`, `y` of the next generation comes out as the
+    /// pattern wants, once all of its neighbors are set.
+    fn fits(&self, x: isize, y: isize) -> bool {
+        let mut neighbors = 0;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if (dx, dy) != (0, 0) && self.get(x + dx, y + dy) {
+                    neighbors += 1;
+                }
+            }
+        }
+        let cell = if self.get(x, y) {
+            Cell::Live
+        } else {
+            Cell::Dead
+        };
+        (self.rule.next(cell, neighbors) == Cell::Live) == self.wanted(x, y)
+    }
+
+    /// Checks everything the cell at index `i` was the last one needed for.
+    fn consistent(&self, i: usize) -> bool {
+        let (x, y) = ((i % self.width) as isize, (i / self.width) as isize);
+        let (w, h) = (self.width as isize, self.height as isize);
+        if !self.fits(x - 1, y - 1) {
+            return false;
+        }
+        // the last column completes the cells past the right edge of the box
+        if x == w - 1 && !(self.fits(x, y - 1) && self.fits(x + 1, y - 1)) {
+            return false;
+        }
+        // and the last cell everything below the box
+        if i == self.cells.len() - 1 {
+            return (-1..=w).all(|x| self.fits(x, h - 1) && self.fits(x, h));
+        }
+        true
+    }
+
+    /// Tries both values for cell `i` and everything after it. `None` means
+    /// the limit was reached.
+    fn solve(&mut self, i: usize) -> Option<bool> {
+        if i == self.cells.len() {
+            return Some(true);
+        }
+        for &live in &[false, true] {
+            self.steps += 1;
+            if self.steps > self.limit {
+                return None;
+            }
+            self.cells[i] = live;
+            if self.consistent(i) && self.solve(i + 1)? {
+                return Some(true);
+            }
+        }
+        self.cells[i] = false;
+        Some(false)
+    }
+}
+
+/// Looks for a state of at most the pattern's size plus `margin` cells on
+/// every side that becomes the pattern, and nothing else, after one
+/// generation under `rule`. Gives up after `limit` cell assignments.
+pub fn find(target: &Pattern, rule: Rule, margin: usize, limit: u64) -> Predecessor {
+    let width = target.width() + 2 * margin;
+    let height = target.height() + 2 * margin;
+    let mut search = Search {
+        target,
+        rule,
+        margin,
+        width,
+        height,
+        cells: vec![false; width * height],
+        steps: 0,
+        limit,
+    };
+    match search.solve(0) {
+        Some(true) => {
+            let mut found = Pattern::new(width, height);
+            for y in 0..height {
+                for x in 0..width {
+                    found.set(x, y, search.cells[y * width + x]);
+                }
+            }
+            found.set_rule(Some(rule));
+            Predecessor::Found(found.crop())
+        }
+        Some(false) => Predecessor::NoneWithin,
+        None => Predecessor::GaveUp,
+    }
+}
@@ -0,0 +1,109 @@
+//! A progress bar on stderr for long runs that show nothing else until
+//! they are done, so they can be told apart from ones that are stuck.
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+const WIDTH: usize = 30;
+/// Redrawing more often than this only slows the run down.
+const INTERVAL: Duration = Duration::from_millis(100);
+
+pub struct Progress {
+    total: u64,
+    /// What is being counted, like `generations` or `soups`.
+    unit: &'static str,
+    started: Instant,
+    drawn: Option<Instant>,
+}
+
+impl Progress {
+    pub fn new(total: u64, unit: &'static str) -> Progress {
+        Progress {
+            total,
+            unit,
+            started: Instant::now(),
+            drawn: None,
+        }
+    }
+
+    /// Redraws the bar for `done` of the total, `cells` being how many cell
+    /// updates that took, unless it was drawn only a moment ago.
+    pub fn update(&mut self, done: u64, cells: u64) {
+        let now = Instant::now();
+        if self.drawn.is_some_and(|drawn| now - drawn < INTERVAL) {
+            return;
+        }
+        self.drawn = Some(now);
+        self.draw(done, cells);
+    }
+
+    /// Draws the bar a last time and moves past it.
+    pub fn finish(&mut self, done: u64, cells: u64) {
+        self.draw(done, cells);
+        eprintln!();
+    }
+
+    fn draw(&self, done: u64, cells: u64) {
+        let fraction = if self.total == 0 {
+            1.0
+        } else {
+            done as f64 / self.total as f64
+        };
+        let filled = (fraction * WIDTH as f64) as usize;
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let speed = if elapsed > 0.0 {
+            cells as f64 / elapsed
+        } else {
+            0.0
+        };
+        let eta = if done > 0 && done < self.total {
+            let left = elapsed / done as f64 * (self.total - done) as f64;
+            format!(", {} left", clock(left as u64))
+        } else {
+            String::new()
+        };
+        let mut err = io::stderr();
+        // the spaces wipe what is left of a longer line drawn before
+        let _ = write!(
+            err,
+            "\r{:3}% [{}{}] {}/{} {}, {} cells/s{}   ",
+            (fraction * 100.0) as u64,
+            "#".repeat(filled),
+            ".".repeat(WIDTH - filled),
+            done,
+            self.total,
+            self.unit,
+            si(speed),
+            eta
+        );
+        let _ = err.flush();
+    }
+}
+
+/// `n` with a k, M or G suffix, to about three significant figures.
+fn si(n: f64) -> String {
+    let mut value = n;
+    let mut suffix = 0;
+    while value >= 1000.0 && suffix < 3 {
+        value /= 1000.0;
+        suffix += 1;
+    }
+    let digits = if suffix == 0 || value >= 100.0 {
+        0
+    } else if value >= 10.0 {
+        1
+    } else {
+        2
+    };
+    format!("{:.*}{}", digits, value, ["", "k", "M", "G"][suffix])
+}
+
+/// `secs` as H:MM:SS, or M:SS under an hour.
+fn clock(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{}:{:02}", m, s)
+    }
+}
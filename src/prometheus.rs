@@ -0,0 +1,152 @@
+//! Counters of a long run in the Prometheus text exposition format, for
+//! `serve`'s `/metrics`.
+
+use std::time::Duration;
+
+use conway::{Changes, Rule};
+
+/// Upper bounds, in seconds, of the step duration histogram's buckets.
+const BUCKETS: [f64; 8] = [1e-5, 1e-4, 5e-4, 1e-3, 5e-3, 0.01, 0.1, 1.0];
+
+pub struct Metrics {
+    rule: Rule,
+    width: usize,
+    height: usize,
+    generation: u64,
+    population: usize,
+    births: u64,
+    deaths: u64,
+    paused: bool,
+    /// Steps that took at most each of `BUCKETS`, not counting the faster
+    /// buckets', and how long all of them took together.
+    steps: [u64; BUCKETS.len()],
+    slower: u64,
+    step_seconds: f64,
+}
+
+impl Metrics {
+    pub fn new(rule: Rule, width: usize, height: usize, population: usize) -> Metrics {
+        Metrics {
+            rule,
+            width,
+            height,
+            generation: 0,
+            population,
+            births: 0,
+            deaths: 0,
+            paused: false,
+            steps: [0; BUCKETS.len()],
+            slower: 0,
+            step_seconds: 0.0,
+        }
+    }
+
+    /// Records a step into `generation` that took `took`.
+    pub fn step(&mut self, generation: u64, population: usize, changes: Changes, took: Duration) {
+        self.generation = generation;
+        self.population = population;
+        self.births += changes.births as u64;
+        self.deaths += changes.deaths as u64;
+        let seconds = took.as_secs_f64();
+        self.step_seconds += seconds;
+        match BUCKETS.iter().position(|&bound| seconds <= bound) {
+            Some(i) => self.steps[i] += 1,
+            None => self.slower += 1,
+        }
+    }
+
+    /// For cells changed by hand between steps.
+    pub fn set_population(&mut self, population: usize) {
+        self.population = population;
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// The metrics, with `viewers` clients following the run.
+    pub fn render(&self, viewers: usize) -> String {
+        let mut out = String::new();
+        // each sample is what follows the name, such as `_sum` or labels, and
+        // its value
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, String)]| {
+            out.push_str(&format!(
+                "# HELP {} {}\n# TYPE {} {}\n",
+                name, help, name, kind
+            ));
+            for (suffix, value) in samples {
+                out.push_str(&format!("{}{} {}\n", name, suffix, value));
+            }
+        };
+        let plain = |value: String| vec![(String::new(), value)];
+        metric(
+            "conway_info",
+            "gauge",
+            "The engine's version and the world it runs.",
+            &[(
+                format!(
+                    "{{version=\"{}\",rule=\"{}\",width=\"{}\",height=\"{}\"}}",
+                    env!("CARGO_PKG_VERSION"),
+                    self.rule,
+                    self.width,
+                    self.height
+                ),
+                "1".to_string(),
+            )],
+        );
+        metric(
+            "conway_generation",
+            "gauge",
+            "The generation the world is at.",
+            &plain(self.generation.to_string()),
+        );
+        metric(
+            "conway_population",
+            "gauge",
+            "Live cells.",
+            &plain(self.population.to_string()),
+        );
+        metric(
+            "conway_births_total",
+            "counter",
+            "Cells born, so rate() gives births per second.",
+            &plain(self.births.to_string()),
+        );
+        metric(
+            "conway_deaths_total",
+            "counter",
+            "Cells that died, so rate() gives deaths per second.",
+            &plain(self.deaths.to_string()),
+        );
+        metric(
+            "conway_paused",
+            "gauge",
+            "1 while the run is paused.",
+            &plain(u8::from(self.paused).to_string()),
+        );
+        metric(
+            "conway_viewers",
+            "gauge",
+            "Browsers and other clients following the run.",
+            &plain(viewers.to_string()),
+        );
+        // histogram buckets count every step up to their bound
+        let mut samples = Vec::new();
+        let mut total = 0;
+        for (bound, n) in BUCKETS.iter().zip(&self.steps) {
+            total += n;
+            samples.push((format!("_bucket{{le=\"{}\"}}", bound), total.to_string()));
+        }
+        total += self.slower;
+        samples.push(("_bucket{le=\"+Inf\"}".to_string(), total.to_string()));
+        samples.push(("_sum".to_string(), self.step_seconds.to_string()));
+        samples.push(("_count".to_string(), total.to_string()));
+        metric(
+            "conway_step_duration_seconds",
+            "histogram",
+            "Time taken to compute each generation.",
+            &samples,
+        );
+        out
+    }
+}
@@ -0,0 +1,121 @@
+//! What a run is recorded into besides what it shows: files, and the summary
+//! printed at the end.
+
+use conway::{Cell, Changes, World};
+
+use crate::audio::Sonifier;
+use crate::contact::ContactSheet;
+use crate::frames::Frames;
+use crate::hashes::Hashes;
+use crate::heatmap::Heatmap;
+use crate::midi::Midi;
+use crate::osc::Osc;
+use crate::replay::Recorder;
+use crate::summary::Summary;
+use crate::trace::Trace;
+
+pub struct Recorders {
+    pub audio: Option<(String, Sonifier)>,
+    pub midi: Option<Midi>,
+    pub osc: Option<Osc>,
+    pub heatmap: Option<(String, Heatmap)>,
+    pub contact_sheet: Option<(String, ContactSheet)>,
+    pub frames: Option<Frames>,
+    pub summary: Option<Summary>,
+    pub replay: Option<Recorder>,
+    pub trace: Option<Trace>,
+    /// Taken out to finish with the last world once the run is over.
+    pub hashes: Option<Hashes>,
+}
+
+impl Recorders {
+    /// Called after every generation.
+    pub fn generation(&mut self, world: &World, changes: Changes) {
+        if let Some((_, heatmap)) = self.heatmap.as_mut() {
+            heatmap.observe(world);
+        }
+        if let Some(summary) = self.summary.as_mut() {
+            summary.observe(world, changes);
+        }
+        if let Some(replay) = self.replay.as_mut() {
+            replay.advanced();
+        }
+        if let Some(trace) = self.trace.as_mut() {
+            trace.advanced(world);
+        }
+        if let Some(hashes) = self.hashes.as_mut() {
+            hashes.advanced(world);
+        }
+        if let Some((_, audio)) = self.audio.as_mut() {
+            audio.advanced(changes);
+        }
+        if let Some(osc) = self.osc.as_mut() {
+            osc.advanced(world, changes);
+        }
+    }
+
+    /// Called for every cell changed other than by the rule: by hand in the
+    /// window, or by a script.
+    pub fn edit(&mut self, x: usize, y: usize, cell: Cell) {
+        if let Some(replay) = self.replay.as_mut() {
+            replay.set(x, y, cell);
+        }
+    }
+
+    /// Called for every cell flipped by `--perturb` or changed by `--faults`,
+    /// which a recording keeps like any other edit so it plays back the same.
+    pub fn perturbed(&mut self, x: usize, y: usize, cell: Cell) {
+        self.edit(x, y, cell);
+        if let Some(summary) = self.summary.as_mut() {
+            summary.perturbed += 1;
+        }
+    }
+
+    /// Called for every frame shown, which with `--every` isn't every
+    /// generation.
+    pub fn frame(&mut self, world: &World, generation: u64) {
+        if let Some((_, audio)) = self.audio.as_mut() {
+            audio.observe(world);
+        }
+        if let Some(midi) = self.midi.as_mut() {
+            midi.observe(world);
+        }
+        if let Some((_, sheet)) = self.contact_sheet.as_mut() {
+            sheet.observe(world, generation);
+        }
+        if let Some(frames) = self.frames.as_mut() {
+            frames.observe(world, generation);
+        }
+    }
+
+    /// Writes out everything recorded once the run is over.
+    pub fn finish(self) -> Result<(), String> {
+        if let Some(summary) = &self.summary {
+            summary.print()?;
+        }
+        if let Some((path, audio)) = self.audio {
+            audio
+                .finish()
+                .map_err(|e| format!("cannot write {}: {}", path, e))?;
+        }
+        if let Some(midi) = self.midi {
+            midi.finish()?;
+        }
+        if let Some((path, heatmap)) = self.heatmap {
+            heatmap.write_png(&path)?;
+        }
+        if let Some((path, sheet)) = self.contact_sheet {
+            sheet.write_png(&path)?;
+        }
+        if let Some(frames) = self.frames {
+            frames.finish()?;
+        }
+        if let Some(replay) = self.replay {
+            replay.finish()?;
+        }
+        if let Some(trace) = self.trace {
+            trace.finish()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+//! `run --watch-input`: notices the pattern file a run started from being
+//! saved again, by its modification time and size, so the run can start
+//! over from it.
+
+use std::fs;
+use std::time::{Duration, SystemTime};
+
+/// How often a run that has ended looks for the file to change.
+pub const POLL: Duration = Duration::from_millis(250);
+
+pub struct InputWatch {
+    path: String,
+    seen: Option<(SystemTime, u64)>,
+}
+
+/// When the file at `path` was last changed and its size, if it's there.
+fn stamp(path: &str) -> Option<(SystemTime, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+impl InputWatch {
+    pub fn new(path: &str) -> InputWatch {
+        InputWatch {
+            path: path.to_string(),
+            seen: stamp(path),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Whether the file has changed since the last time this was asked.
+    /// A file that is gone for now, as while some editors save, hasn't.
+    pub fn changed(&mut self) -> bool {
+        match stamp(&self.path) {
+            Some(now) if Some(now) != self.seen => {
+                self.seen = Some(now);
+                true
+            }
+            _ => false,
+        }
+    }
+}
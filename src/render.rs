@@ -0,0 +1,295 @@
+//! The ways `run` shows its frames in the terminal, each behind `Renderer`
+//! and picked by name with `--renderer`, so a new one is added here and
+//! nowhere else.
+
+use conway::{Cell, Changes, World};
+
+use crate::interrupt;
+use crate::jsonl::{self, Cells};
+use crate::output;
+use crate::term::{self, Color, Style, Zoom, RESET};
+
+/// Names `--renderer` accepts. `window` stands apart as the SDL window runs
+/// a loop of its own.
+pub const NAMES: [&str; 5] = ["text", "incremental", "jsonl", "braille", "window"];
+
+/// A generation about to be shown.
+pub struct Frame<'a> {
+    pub world: &'a World,
+    /// With `--diff`, the generation before.
+    pub previous: Option<&'a World>,
+    pub generation: u64,
+    pub changes: Changes,
+}
+
+pub trait Renderer {
+    /// Called once before the first frame.
+    fn init(&mut self, _world: &World) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &Frame) -> Result<(), String>;
+
+    /// Shows the frames after this with `zoom`, where the renderer has any.
+    fn zoom(&mut self, _zoom: Zoom) {}
+
+    /// Says why the run ended, after its last frame.
+    fn stopped(&mut self, reason: &str) -> Result<(), String>;
+
+    /// Called once the run is over.
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// The renderer called `name` in `NAMES`, other than `window`.
+pub fn create(name: &str, style: &Style, cells: Cells) -> Result<Box<dyn Renderer>, String> {
+    match name {
+        "text" => Ok(Box::new(Text {
+            style: style.clone(),
+            start: 0,
+            origin: style.origin,
+        })),
+        "incremental" => Ok(Box::new(Incremental {
+            style: style.clone(),
+            shown: Vec::new(),
+            size: (0, 0),
+            blocks: (0, 0),
+            rows: Vec::new(),
+        })),
+        "jsonl" => Ok(Box::new(Jsonl { cells })),
+        // braille is Unicode, so without it the cells are drawn as text
+        "braille" if !style.unicode => create("text", style, cells),
+        "braille" => Ok(Box::new(Braille {
+            style: style.clone(),
+        })),
+        _ => Err(invalid(name)),
+    }
+}
+
+pub fn invalid(name: &str) -> String {
+    format!(
+        "invalid renderer '{}', expected one of {}",
+        name,
+        NAMES.join(", ")
+    )
+}
+
+/// A character a cell, in the cells' glyphs and colors, or a character a
+/// block of them when zoomed out or the world is too big for the terminal.
+struct Text {
+    style: Style,
+    /// The generation of the world's own count the first frame was at, as
+    /// trails only show what died after it and scrolling starts from it.
+    start: u64,
+    /// Where the style put the top left at that first frame.
+    origin: (f64, f64),
+}
+
+impl Renderer for Text {
+    fn init(&mut self, world: &World) -> Result<(), String> {
+        self.start = world.generation();
+        self.origin = self.style.origin;
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &Frame) -> Result<(), String> {
+        if let Some((dx, dy)) = self.style.scroll {
+            let t = (frame.world.generation() - self.start) as f64;
+            self.style.origin = (self.origin.0 + dx * t, self.origin.1 + dy * t);
+        }
+        if let Some(footprint) = self.style.footprint.as_mut() {
+            footprint.mark(frame.world);
+        }
+        // leaving a row for the prompt under the last frame
+        let room = term::size().map(|(columns, rows)| (columns, rows.saturating_sub(1)));
+        let blocks = self.style.blocks(frame.world, room);
+        let rendered = match frame.previous {
+            _ if blocks != (1, 1) => self.style.render_blocks(frame.world, blocks),
+            Some(previous) => self.style.render_diff(frame.world, previous),
+            None if self.style.trails > 0 && frame.world.tracks_ages() => {
+                self.style.render_trails(frame.world, self.start)
+            }
+            None => self.style.render(frame.world),
+        };
+        output::screen(&rendered);
+        Ok(())
+    }
+
+    fn zoom(&mut self, zoom: Zoom) {
+        self.style.zoom = zoom;
+    }
+
+    fn stopped(&mut self, reason: &str) -> Result<(), String> {
+        output::screen(reason);
+        Ok(())
+    }
+}
+
+/// The frames of `Text` drawn over one another in place, the first in full
+/// and each after it by moving the cursor to just the cells that changed,
+/// as redrawing every cell is what costs over a slow link or on a large
+/// terminal. Zoomed out, it rewrites the rows of blocks that changed.
+struct Incremental {
+    style: Style,
+    /// The glyph and color each cell was last drawn with, row-major.
+    shown: Vec<(String, Option<Color>)>,
+    /// The world's size and the blocks it was last drawn in, which if either
+    /// changes means drawing in full again.
+    size: (usize, usize),
+    blocks: (usize, usize),
+    /// The rows last drawn while zoomed out.
+    rows: Vec<String>,
+}
+
+impl Incremental {
+    /// The rows the last frame took up on the screen.
+    fn height(&self) -> usize {
+        match self.blocks {
+            (1, 1) => self.size.1,
+            _ => self.rows.len(),
+        }
+    }
+}
+
+/// The escape moving the cursor to `column` and `row`, counted from 0.
+fn cursor(column: usize, row: usize) -> String {
+    format!("\x1b[{};{}H", row + 1, column + 1)
+}
+
+impl Renderer for Incremental {
+    fn draw(&mut self, frame: &Frame) -> Result<(), String> {
+        let world = frame.world;
+        let size = (world.width(), world.height());
+        let room = term::size().map(|(columns, rows)| (columns, rows.saturating_sub(1)));
+        let blocks = self.style.blocks(world, room);
+        let full = size != self.size || blocks != self.blocks || interrupt::resized();
+        let mut out = String::new();
+        if full {
+            out.push_str(term::CLEAR);
+            self.shown.clear();
+            self.rows.clear();
+        }
+        if blocks != (1, 1) {
+            let rendered = self.style.render_blocks(world, blocks);
+            let rows: Vec<String> = rendered.lines().map(str::to_string).collect();
+            for (y, row) in rows.iter().enumerate() {
+                if self.rows.get(y) != Some(row) {
+                    out.push_str(&cursor(0, y));
+                    out.push_str(row);
+                    out.push_str("\x1b[K");
+                }
+            }
+            self.rows = rows;
+        } else {
+            let (w, h) = size;
+            // glyphs of more than one character take up that many columns
+            let width = self.style.live.chars().count().max(1);
+            let mut at = None;
+            for y in 0..h {
+                for x in 0..w {
+                    let (glyph, color) = self.style.cell(world, x, y);
+                    let i = y * w + x;
+                    if let Some((shown, shown_color)) = self.shown.get(i) {
+                        if shown == glyph && *shown_color == color {
+                            continue;
+                        }
+                    }
+                    if at != Some((x, y)) {
+                        out.push_str(&cursor(x * width, y));
+                    }
+                    match color {
+                        Some(color) => {
+                            out.push_str(&color.foreground());
+                            out.push_str(glyph);
+                            out.push_str(RESET);
+                        }
+                        None => out.push_str(glyph),
+                    }
+                    at = Some((x + 1, y));
+                    let drawn = (glyph.to_string(), color);
+                    match self.shown.get_mut(i) {
+                        Some(shown) => *shown = drawn,
+                        None => self.shown.push(drawn),
+                    }
+                }
+            }
+        }
+        self.size = size;
+        self.blocks = blocks;
+        // leaving the cursor under the frame for whatever is written next
+        out.push_str(&cursor(0, self.height()));
+        output::redraw(&out);
+        Ok(())
+    }
+
+    fn zoom(&mut self, zoom: Zoom) {
+        self.style.zoom = zoom;
+    }
+
+    fn stopped(&mut self, reason: &str) -> Result<(), String> {
+        output::screen(reason);
+        Ok(())
+    }
+}
+
+/// A JSON object a generation, for other programs to read.
+struct Jsonl {
+    cells: Cells,
+}
+
+impl Renderer for Jsonl {
+    fn draw(&mut self, frame: &Frame) -> Result<(), String> {
+        let line = jsonl::frame(frame.world, frame.generation, frame.changes, self.cells);
+        output::data(&line)
+    }
+
+    fn stopped(&mut self, reason: &str) -> Result<(), String> {
+        output::data(&jsonl::stopped(reason))
+    }
+}
+
+/// Braille characters of two by four dots, fitting eight times the cells of
+/// `Text` into the same space.
+struct Braille {
+    style: Style,
+}
+
+/// The dot of each cell of a braille character, by row and then column.
+pub const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+impl Renderer for Braille {
+    fn draw(&mut self, frame: &Frame) -> Result<(), String> {
+        let world = frame.world;
+        let (w, h) = (world.width(), world.height());
+        let mut rendered = String::with_capacity((w.div_ceil(2) * 3 + 1) * h.div_ceil(4));
+        for row in (0..h).step_by(4) {
+            if let Some(color) = self.style.live_color {
+                rendered.push_str(&color.foreground());
+            }
+            for column in (0..w).step_by(2) {
+                let mut bits = 0;
+                for (dy, dots) in DOTS.iter().enumerate() {
+                    for (dx, dot) in dots.iter().enumerate() {
+                        let (x, y) = (column + dx, row + dy);
+                        if x < w && y < h && world.get(x, y) == Cell::Live {
+                            bits |= dot;
+                        }
+                    }
+                }
+                rendered.push(char::from_u32(0x2800 + bits).unwrap_or(' '));
+            }
+            if self.style.live_color.is_some() {
+                rendered.push_str(RESET);
+            }
+            rendered.push('\n');
+        }
+        output::screen(&rendered);
+        Ok(())
+    }
+
+    fn stopped(&mut self, reason: &str) -> Result<(), String> {
+        output::screen(reason);
+        Ok(())
+    }
+}
@@ -0,0 +1,226 @@
+//! A prompt for driving the world one command at a time, for trying things
+//! out without starting over after every change.
+
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use conway::{library, Cell, Pattern, Rng, Rule, World};
+
+use crate::memory;
+
+const HELP: &str = "\
+Commands:
+  show                 print the world
+  info                 generation, population, size and rule
+  step [N]             advance N generations [default: 1]
+  set X Y [live|dead]  set a cell [default: live]
+  toggle X Y           flip a cell
+  clear                kill every cell
+  random [DENSITY [SEED]]
+                       replace the world with a random soup [default
+                       density: 0.3, seed: --seed or a fresh one]
+  new W H              start over with an empty world of that size
+  load FILE|NAME       start over with a pattern file or built-in pattern
+                       at the center
+  save FILE            write the live cells as RLE or plaintext
+  rule [RULE]          print or change the rule
+  help                 print this
+  quit                 leave, as does end of input
+";
+
+struct Repl {
+    world: World,
+    tmp: World,
+    generation: u64,
+    /// From `--seed`, for soups not given their own.
+    seed: Option<u64>,
+}
+
+/// What a command did, printed after it when there is something to say.
+type Outcome = Result<Option<String>, String>;
+
+fn number<T: std::str::FromStr>(word: &str, what: &str) -> Result<T, String> {
+    word.parse()
+        .map_err(|_| format!("invalid {} '{}'", what, word))
+}
+
+impl Repl {
+    fn cell(&self, x: &str, y: &str) -> Result<(usize, usize), String> {
+        let (x, y) = (number(x, "x")?, number(y, "y")?);
+        self.world.try_get(x, y)?;
+        Ok((x, y))
+    }
+
+    fn info(&self) -> String {
+        format!(
+            "generation {}, population {}, {}x{}, rule {}",
+            self.generation,
+            self.world.population(),
+            self.world.width(),
+            self.world.height(),
+            self.world.rule()
+        )
+    }
+
+    /// Makes `world` the one being driven, from generation 0.
+    fn replace(&mut self, world: World) {
+        self.tmp = world.clone();
+        self.world = world;
+        self.generation = 0;
+    }
+
+    fn load(&mut self, source: &str) -> Result<(), String> {
+        let pattern = match library::get(source) {
+            Some(pattern) if std::fs::metadata(source).is_err() => pattern,
+            _ => Pattern::load_within(source, &memory::fits(None))?,
+        };
+        let (width, height) = (self.world.width(), self.world.height());
+        if pattern.width() > width || pattern.height() > height {
+            return Err(format!(
+                "the {}x{} pattern does not fit in the {}x{} world, make a bigger one with new",
+                pattern.width(),
+                pattern.height(),
+                width,
+                height
+            ));
+        }
+        let mut world = World::new(width, height)?;
+        world.set_rule(pattern.rule().unwrap_or_else(|| self.world.rule()));
+        world.set_meta(pattern.meta().clone());
+        world.stamp(
+            &pattern,
+            (width - pattern.width()) / 2,
+            (height - pattern.height()) / 2,
+        );
+        self.replace(world);
+        Ok(())
+    }
+
+    fn execute(&mut self, words: &[&str]) -> Outcome {
+        match words {
+            ["show"] => Ok(Some(self.world.as_string().trim_end().to_string())),
+            ["info"] => Ok(Some(self.info())),
+            ["step"] | ["step", _] => {
+                let n: u64 = match words.get(1) {
+                    Some(n) => number(n, "number of generations")?,
+                    None => 1,
+                };
+                for _ in 0..n {
+                    self.world.advance(&mut self.tmp);
+                    self.generation += 1;
+                }
+                Ok(Some(self.info()))
+            }
+            ["set", x, y] | ["set", x, y, _] => {
+                let (x, y) = self.cell(x, y)?;
+                let cell = match words.get(3) {
+                    None | Some(&"live") => Cell::Live,
+                    Some(&"dead") => Cell::Dead,
+                    Some(state) => {
+                        return Err(format!("invalid state '{}', expected live or dead", state))
+                    }
+                };
+                self.world.try_set(x, y, cell)?;
+                Ok(None)
+            }
+            ["toggle", x, y] => {
+                let (x, y) = self.cell(x, y)?;
+                self.world.toggle(x, y);
+                Ok(None)
+            }
+            ["clear"] => {
+                let mut world = World::new(self.world.width(), self.world.height())?;
+                world.set_rule(self.world.rule());
+                self.world = world;
+                Ok(None)
+            }
+            ["random"] | ["random", _] | ["random", _, _] => {
+                let density: f64 = match words.get(1) {
+                    Some(d) => number(d, "density")?,
+                    None => 0.3,
+                };
+                if !(0.0..=1.0).contains(&density) {
+                    return Err(format!("density must be between 0 and 1, got {}", density));
+                }
+                let seed = match words.get(2) {
+                    Some(seed) => number(seed, "seed")?,
+                    None => self.seed.unwrap_or_else(Rng::time_seed),
+                };
+                let mut world = self.world.clone();
+                world.randomize(density, &mut Rng::new(seed));
+                self.replace(world);
+                Ok(Some(format!("seed {}, {}", seed, self.info())))
+            }
+            ["new", w, h] => {
+                let (width, height) = (number(w, "width")?, number(h, "height")?);
+                memory::check(width, height, None)?;
+                let mut world = World::new(width, height)?;
+                world.set_rule(self.world.rule());
+                self.replace(world);
+                Ok(None)
+            }
+            ["load", source] => {
+                self.load(source)?;
+                Ok(Some(self.info()))
+            }
+            ["save", path] => {
+                let pattern = Pattern::from_live_cells(&self.world);
+                pattern.save(path)?;
+                Ok(Some(format!(
+                    "saved {}x{} pattern to {}",
+                    pattern.width(),
+                    pattern.height(),
+                    path
+                )))
+            }
+            ["rule"] => Ok(Some(self.world.rule().to_string())),
+            ["rule", rule] => {
+                self.world.set_rule(Rule::parse(rule)?);
+                Ok(None)
+            }
+            ["help"] => Ok(Some(HELP.trim_end().to_string())),
+            [command, ..] => Err(format!(
+                "unknown command or arguments '{}', try help",
+                command
+            )),
+            [] => Ok(None),
+        }
+    }
+}
+
+/// Reads commands from stdin until `quit` or the end of input. A failed
+/// command is reported and the prompt carries on.
+pub fn run(world: World, seed: Option<u64>) -> Result<(), String> {
+    let mut repl = Repl {
+        tmp: world.clone(),
+        world,
+        generation: 0,
+        seed,
+    };
+    let stdin = io::stdin();
+    // no prompt for commands piped in
+    let prompt = stdin.is_terminal();
+    let mut lines = stdin.lock().lines();
+    loop {
+        if prompt {
+            print!("> ");
+            io::stdout().flush().map_err(|e| e.to_string())?;
+        }
+        let line = match lines.next() {
+            Some(line) => line.map_err(|e| e.to_string())?,
+            None => break,
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.first().is_some_and(|w| w.starts_with('#')) {
+            continue;
+        }
+        if matches!(words.as_slice(), ["quit"] | ["exit"]) {
+            break;
+        }
+        match repl.execute(&words) {
+            Ok(Some(output)) => println!("{}", output),
+            Ok(None) => {}
+            Err(e) => eprintln!("error: {}", e),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,237 @@
+//! Recordings of a run that `conway replay` plays back exactly: the world it
+//! started from and every cell changed by hand along the way.
+//!
+//! A recording is text, so it can be passed around and read:
+//!
+//! ```text
+//! # conway replay
+//! seed 42
+//! generation 0
+//! world
+//! x = 40, y = 20, rule = B3/S23
+//! ...!
+//! at 12 set 3 4 live
+//! end 100
+//! ```
+//!
+//! `seed` is only there for runs that started from a random soup, since the
+//! world follows as RLE anyway, with the rule in its header. Each `at` line
+//! is a cell set once the world had reached that generation, and `end` is
+//! where the run stopped. A recording cut short ends at its last edit.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use conway::{Cell, Changes, Format, Pattern, World};
+
+use crate::cli::{OutputFormat, ReplayOptions};
+use crate::log;
+use crate::memory;
+use crate::pace::Pacer;
+use crate::render::{self, Frame};
+
+const HEADER: &str = "# conway replay";
+
+fn state(cell: Cell) -> &'static str {
+    match cell {
+        Cell::Live => "live",
+        Cell::Dead => "dead",
+    }
+}
+
+/// Writes a recording as the run goes, so it survives the run being killed.
+pub struct Recorder {
+    path: String,
+    out: BufWriter<File>,
+    generation: u64,
+    /// The first write that failed, reported by `finish`.
+    error: Option<io::Error>,
+}
+
+impl Recorder {
+    /// Starts a recording of `world`, which is at `generation`.
+    pub fn create(
+        path: &str,
+        world: &World,
+        generation: u64,
+        seed: Option<u64>,
+    ) -> Result<Recorder, String> {
+        let cannot = |e: io::Error| format!("cannot write {}: {}", path, e);
+        let mut out = BufWriter::new(File::create(path).map_err(cannot)?);
+        let mut head = format!("{}\n", HEADER);
+        if let Some(seed) = seed {
+            head.push_str(&format!("seed {}\n", seed));
+        }
+        head.push_str(&format!(
+            "generation {}\nworld\n{}",
+            generation,
+            Pattern::from_world(world).to_rle()
+        ));
+        out.write_all(head.as_bytes())
+            .and_then(|()| out.flush())
+            .map_err(cannot)?;
+        Ok(Recorder {
+            path: path.to_string(),
+            out,
+            generation,
+            error: None,
+        })
+    }
+
+    fn write(&mut self, line: String) {
+        if self.error.is_none() {
+            let written = writeln!(self.out, "{}", line).and_then(|()| self.out.flush());
+            self.error = written.err();
+        }
+    }
+
+    /// Called after every generation.
+    pub fn advanced(&mut self) {
+        self.generation += 1;
+    }
+
+    /// Records a cell set by hand.
+    pub fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        let line = format!("at {} set {} {} {}", self.generation, x, y, state(cell));
+        self.write(line);
+    }
+
+    pub fn finish(mut self) -> Result<(), String> {
+        let line = format!("end {}", self.generation);
+        self.write(line);
+        match self.error {
+            Some(e) => Err(format!("cannot write {}: {}", self.path, e)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A recording read back.
+pub struct Replay {
+    pub world: World,
+    pub generation: u64,
+    pub seed: Option<u64>,
+    /// The generation of each edit, in order, and the cell it set.
+    pub edits: Vec<(u64, usize, usize, Cell)>,
+    pub end: Option<u64>,
+}
+
+impl Replay {
+    pub fn parse(text: &str) -> Result<Replay, String> {
+        let mut lines = text.lines().enumerate();
+        if lines.next().map(|(_, line)| line.trim()) != Some(HEADER) {
+            return Err("not a recording, it should start with '# conway replay'".to_string());
+        }
+        let (mut generation, mut seed, mut end) = (0, None, None);
+        let mut world: Option<World> = None;
+        let mut edits: Vec<(u64, usize, usize, Cell)> = Vec::new();
+        while let Some((i, line)) = lines.next() {
+            let invalid = |what: &str| format!("line {}: invalid {} '{}'", i + 1, what, line);
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                [] => {}
+                ["seed", s] => seed = Some(s.parse().map_err(|_| invalid("seed"))?),
+                ["generation", g] => generation = g.parse().map_err(|_| invalid("generation"))?,
+                ["world"] => {
+                    // the RLE runs up to its closing '!'
+                    let mut rle = String::new();
+                    for (_, line) in lines.by_ref() {
+                        rle.push_str(line);
+                        rle.push('\n');
+                        if line.contains('!') {
+                            break;
+                        }
+                    }
+                    let pattern = Pattern::parse_as_within(&rle, Format::Rle, &memory::fits(None))?;
+                    let mut start = World::new(pattern.width(), pattern.height())?;
+                    start.stamp(&pattern, 0, 0);
+                    if let Some(rule) = pattern.rule() {
+                        start.set_rule(rule);
+                    }
+                    world = Some(start);
+                }
+                ["at", at, "set", x, y, cell] => {
+                    let at: u64 = at.parse().map_err(|_| invalid("edit"))?;
+                    let last = edits.last().map_or(generation, |e| e.0);
+                    let world = match &world {
+                        Some(world) if at >= last => world,
+                        _ => return Err(invalid("edit, edits follow the world in order")),
+                    };
+                    let (x, y) = match (x.parse(), y.parse()) {
+                        (Ok(x), Ok(y)) if x < world.width() && y < world.height() => (x, y),
+                        _ => return Err(invalid("cell")),
+                    };
+                    let cell = match *cell {
+                        "live" => Cell::Live,
+                        "dead" => Cell::Dead,
+                        _ => return Err(invalid("cell state")),
+                    };
+                    edits.push((at, x, y, cell));
+                }
+                ["end", e] => end = Some(e.parse().map_err(|_| invalid("end"))?),
+                _ if line.starts_with('#') => {}
+                _ => return Err(invalid("line")),
+            }
+        }
+        Ok(Replay {
+            world: world.ok_or("the recording has no world")?,
+            generation,
+            seed,
+            edits,
+            end,
+        })
+    }
+
+    pub fn load(path: &str) -> Result<Replay, String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        Replay::parse(&text).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+/// Plays a recording back, showing frames the way `run` does.
+pub fn play(opts: &ReplayOptions) -> Result<(), String> {
+    let replay = Replay::load(&opts.path)?;
+    // the soup came from it, as with `run`
+    if let Some(seed) = replay.seed {
+        log::info("replay", &format!("seed {}", seed));
+    }
+    let start = replay.generation;
+    let end = replay
+        .end
+        .or_else(|| replay.edits.last().map(|e| e.0))
+        .unwrap_or(start);
+    let mut world = replay.world;
+    let mut tmp = world.clone();
+    let mut edits = replay.edits.iter().peekable();
+    let mut pacer = Pacer::delay(opts.delay);
+    let name = match opts.output {
+        OutputFormat::Text => "text",
+        OutputFormat::Jsonl => "jsonl",
+    };
+    let mut renderer = render::create(name, &opts.style, opts.cells)?;
+    renderer.init(&world)?;
+    for generation in start..=end {
+        let changes = if generation > start {
+            world.advance(&mut tmp)
+        } else {
+            Changes::default()
+        };
+        while let Some(&(_, x, y, cell)) = edits.next_if(|e| e.0 == generation) {
+            world.set(x, y, cell);
+        }
+        if (generation - start) % opts.every == 0 || generation == end {
+            if generation > start {
+                pacer.wait();
+            }
+            let frame = Frame {
+                world: &world,
+                previous: None,
+                generation,
+                changes,
+            };
+            renderer.draw(&frame)?;
+        }
+    }
+    renderer.finish()
+}
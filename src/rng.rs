@@ -0,0 +1,60 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Small, fast, non-cryptographic generator (xorshift64*).
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // xorshift gets stuck on zero, and small seeds need a few rounds to spread out
+        let mut rng = Rng {
+            state: seed ^ 0x9E37_79B9_7F4A_7C15,
+        };
+        if rng.state == 0 {
+            rng.state = 1;
+        }
+        for _ in 0..4 {
+            rng.next_u64();
+        }
+        rng
+    }
+
+    pub fn from_time() -> Rng {
+        Rng::new(Rng::time_seed())
+    }
+
+    /// A seed that differs between runs, for callers that want to report it.
+    pub fn time_seed() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0)
+    }
+
+    /// A seed made from `text` (64-bit FNV-1a), the same for the same text
+    /// on every machine and every run.
+    pub fn text_seed(text: &str) -> u64 {
+        text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform in `0.0..1.0`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// True with probability `p`.
+    pub fn chance(&mut self, p: f64) -> bool {
+        self.next_f64() < p
+    }
+}
@@ -0,0 +1,538 @@
+use std::fmt;
+use std::fs;
+use std::sync::Mutex;
+
+use crate::directional::Directional;
+use crate::table::Table;
+use crate::weighted::Weighted;
+use crate::Cell;
+
+/// Well-known rules that can be given by name.
+const NAMED: [(&str, &str); 10] = [
+    ("life", "B3/S23"),
+    ("conway", "B3/S23"),
+    ("highlife", "B36/S23"),
+    ("seeds", "B2/S"),
+    ("daynight", "B3678/S34678"),
+    ("life-without-death", "B3/S012345678"),
+    ("2x2", "B36/S125"),
+    ("replicator", "B1357/S1357"),
+    ("maze", "B3/S12345"),
+    ("morley", "B368/S245"),
+];
+
+/// The widest neighborhood a rule can count, as in Golly.
+pub const MAX_RADIUS: usize = 500;
+
+/// The table rules loaded so far, so their names parse.
+static TABLES: Mutex<Vec<&'static Table>> = Mutex::new(Vec::new());
+
+/// Life-like totalistic rule: which neighbor counts give birth to a dead cell
+/// and which let a live cell survive.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Rule {
+    birth: [bool; 9],
+    survival: [bool; 9],
+    /// Whether cells have the extra states of LifeHistory, as in `history`.
+    history: bool,
+    /// Set for Larger than Life rules, which count further than the eight
+    /// nearest neighbors and stand in for `birth` and `survival`.
+    wide: Option<Wide>,
+    /// Set for weighted rules, which sum weights rather than count. Leaked,
+    /// for rules to stay `Copy`; there are only ever a few.
+    weighted: Option<&'static Weighted>,
+    /// Set for directional rules, which see which neighbors are alive and
+    /// not only how many, leaked as weighted ones are.
+    directional: Option<&'static Directional>,
+    /// Set for rules from a Golly rule table, which see which neighbors are
+    /// alive too.
+    table: Option<&'static Table>,
+}
+
+/// A Larger than Life rule: the cells within `radius` of a cell in either
+/// direction are counted, and the counts that make a birth or survival are
+/// ranges, empty when the first is above the second.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct Wide {
+    radius: usize,
+    /// Whether a cell counts itself.
+    middle: bool,
+    birth: (u32, u32),
+    survival: (u32, u32),
+}
+
+impl Wide {
+    /// The most cells a neighborhood can count.
+    fn most(&self) -> u32 {
+        let side = 2 * self.radius as u32 + 1;
+        side * side - u32::from(!self.middle)
+    }
+
+    /// Golly's notation such as `R2,C0,M0,S2..3,B3..3,NM`.
+    fn parse(s: &str) -> Result<Wide, String> {
+        let err = |why: &str| format!("invalid rule '{}': {}", s, why);
+        let (mut radius, mut middle, mut birth, mut survival) = (None, false, None, None);
+        for field in s.split(',').map(str::trim) {
+            let (key, value) = field.split_at(field.chars().next().map_or(0, char::len_utf8));
+            match key.to_ascii_uppercase().as_str() {
+                "R" => radius = value.parse().ok(),
+                "C" => match value {
+                    "0" | "1" | "2" => {}
+                    _ => return Err(err("cells have two states, so C is 0 or 2")),
+                },
+                "M" => match value {
+                    "0" => middle = false,
+                    "1" => middle = true,
+                    _ => return Err(err("M is 0 or 1")),
+                },
+                "S" => survival = Some(range(value).ok_or_else(|| err("expected S like S2..3"))?),
+                "B" => birth = Some(range(value).ok_or_else(|| err("expected B like B3..3"))?),
+                "N" if value.eq_ignore_ascii_case("m") => {}
+                "N" => return Err(err("only the square neighborhood NM is counted")),
+                _ => return Err(err(&format!("unknown field '{}'", field))),
+            }
+        }
+        let wide = match (radius, birth, survival) {
+            (Some(radius), Some(birth), Some(survival)) => Wide {
+                radius,
+                middle,
+                birth,
+                survival,
+            },
+            _ => return Err(err("expected R, S and B, as in R2,C0,M0,S2..3,B3..3,NM")),
+        };
+        wide.check().map_err(|why| err(&why))?;
+        Ok(wide)
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if !(1..=MAX_RADIUS).contains(&self.radius) {
+            return Err(format!("the radius must be from 1 to {}", MAX_RADIUS));
+        }
+        let most = self.most();
+        match [self.birth, self.survival]
+            .iter()
+            .find(|&&(low, high)| low <= high && high > most)
+        {
+            Some(&(_, high)) => Err(format!(
+                "{} cells cannot be counted when there are {} to count",
+                high, most
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// The rule itself, with `birth` and `survival` when it counts the same
+    /// cells as a Life-like one.
+    fn into_rule(self) -> Rule {
+        let mut rule = Rule {
+            birth: [false; 9],
+            survival: [false; 9],
+            history: false,
+            wide: None,
+            weighted: None,
+            directional: None,
+            table: None,
+        };
+        if self.radius > 1 || self.middle {
+            rule.wide = Some(self);
+        } else {
+            for n in 0..9 {
+                rule.birth[n as usize] = within(self.birth, n);
+                rule.survival[n as usize] = within(self.survival, n);
+            }
+        }
+        rule
+    }
+}
+
+/// `low..high`, or a single count.
+fn range(s: &str) -> Option<(u32, u32)> {
+    match s.split_once("..") {
+        Some((low, high)) => Some((low.parse().ok()?, high.parse().ok()?)),
+        None => s.parse().ok().map(|n| (n, n)),
+    }
+}
+
+fn within((low, high): (u32, u32), n: u32) -> bool {
+    low <= n && n <= high
+}
+
+/// Whether `s` starts like a weighted rule, `W` and its radius.
+fn is_weighted(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some('W') | Some('w'))
+        && chars.next().is_some_and(|c| c.is_ascii_digit())
+}
+
+/// Whether `s` starts like Golly's Larger than Life notation, `R` and a number.
+fn is_wide(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some('R') | Some('r'))
+        && chars.next().is_some_and(|c| c.is_ascii_digit())
+}
+
+impl Rule {
+    /// Conway's original B3/S23:
+    /// any live cell with fewer than two live neighbors dies, as if by underpopulation;
+    /// any live cell with two or three live neighbors lives on to the next generation;
+    /// any live cell with more than three live neighbors dies, as if by overpopulation;
+    /// any dead cell with exactly three live neighbors becomes a live cell, as if by reproduction.
+    pub fn conway() -> Rule {
+        Rule::parse("B3/S23").unwrap()
+    }
+
+    /// Parses `B36/S23` notation (case-insensitive, either order), the older
+    /// `S/B` form such as `23/36`, or the name of a well-known rule. Any of
+    /// these followed by `History`, like `LifeHistory` or `B36/S23History`,
+    /// is the rule with the states of LifeHistory. Either side of B/S can
+    /// also be a range such as `2..3`, and Golly's Larger than Life notation
+    /// like `R2,C0,M0,S2..3,B3..3,NM` counts further. Weighted and
+    /// directional rules are written as `crate::weighted` and
+    /// `crate::directional` say, and a rule table loaded by `load` by its
+    /// name.
+    pub fn parse(s: &str) -> Result<Rule, String> {
+        if let Some(&table) = TABLES.lock().unwrap().iter().find(|t| t.name() == s) {
+            return Ok(Rule::tabled(table));
+        }
+        let suffix = s.len().checked_sub("history".len());
+        if let Some(base) = suffix
+            .filter(|&i| i > 0 && s.is_char_boundary(i))
+            .filter(|&i| s[i..].eq_ignore_ascii_case("history"))
+            .map(|i| &s[..i])
+        {
+            let rule = Rule::parse(base)?;
+            if !rule.counts_nearest() {
+                return Err(format!(
+                    "invalid rule '{}': History rules count the eight nearest neighbors",
+                    s
+                ));
+            }
+            return Ok(Rule {
+                history: true,
+                ..rule
+            });
+        }
+        if let Some(&(_, rule)) = NAMED.iter().find(|(name, _)| name.eq_ignore_ascii_case(s)) {
+            return Rule::parse(rule);
+        }
+        if is_wide(s) {
+            return Ok(Wide::parse(s)?.into_rule());
+        }
+        if is_weighted(s) {
+            return Ok(Rule::weighted(Weighted::parse(s)?));
+        }
+        if Directional::is(s) {
+            return Ok(Rule {
+                directional: Some(Box::leak(Box::new(Directional::parse(s)?))),
+                ..Rule::parse("B/S")?
+            });
+        }
+        let err = || {
+            let names: Vec<&str> = NAMED.iter().map(|&(name, _)| name).collect();
+            format!(
+                "invalid rule '{}', expected B/S notation like B36/S23, S/B like 23/36 \
+                 or one of {}",
+                s,
+                names.join(", ")
+            )
+        };
+        let parts: Vec<&str> = s.split('/').collect();
+        if parts.len() != 2 {
+            return Err(err());
+        }
+
+        let tagged =
+            |part: &str, tag: char| part.starts_with(|c: char| c.eq_ignore_ascii_case(&tag));
+        let (birth, survival) = if tagged(parts[0], 'b') && tagged(parts[1], 's') {
+            (&parts[0][1..], &parts[1][1..])
+        } else if tagged(parts[0], 's') && tagged(parts[1], 'b') {
+            (&parts[1][1..], &parts[0][1..])
+        } else {
+            (parts[1], parts[0])
+        };
+
+        let counts = |digits: &str| -> Result<[bool; 9], String> {
+            let mut set = [false; 9];
+            if digits.contains("..") {
+                let span = range(digits).ok_or_else(err)?;
+                if span.1 > 8 {
+                    return Err(format!(
+                        "invalid rule '{}': counting more than 8 cells needs a radius, from \
+                         --radius before --rule or as in R2,C0,M0,S2..3,B3..3,NM",
+                        s
+                    ));
+                }
+                for (n, counted) in set.iter_mut().enumerate() {
+                    *counted = within(span, n as u32);
+                }
+                return Ok(set);
+            }
+            for c in digits.chars() {
+                match c.to_digit(10) {
+                    Some(n) if n <= 8 => set[n as usize] = true,
+                    _ => return Err(err()),
+                }
+            }
+            Ok(set)
+        };
+        Ok(Rule {
+            birth: counts(birth)?,
+            survival: counts(survival)?,
+            history: false,
+            wide: None,
+            weighted: None,
+            directional: None,
+            table: None,
+        })
+    }
+
+    /// `s` as a rule counting the cells within `radius` of each cell: B/S
+    /// notation with a range of counts on either side such as
+    /// `B34..45/S33..57`, or Golly's notation for the same radius. At radius
+    /// 1 this is `parse`.
+    pub fn parse_radius(s: &str, radius: usize) -> Result<Rule, String> {
+        if radius == 1 && !is_wide(s) {
+            return Rule::parse(s);
+        }
+        if is_wide(s) {
+            let wide = Wide::parse(s)?;
+            if wide.radius != radius {
+                return Err(format!(
+                    "the rule '{}' has radius {}, not the {} given",
+                    s, wide.radius, radius
+                ));
+            }
+            return Ok(wide.into_rule());
+        }
+        let err = || {
+            format!(
+                "invalid rule '{}' for radius {}, expected ranges of counts like B34..45/S33..57",
+                s, radius
+            )
+        };
+        let (birth, survival) = match s.split_once('/') {
+            Some((b, s)) if b.starts_with(['B', 'b']) && s.starts_with(['S', 's']) => (b, s),
+            Some((s, b)) if b.starts_with(['B', 'b']) && s.starts_with(['S', 's']) => (b, s),
+            _ => return Err(err()),
+        };
+        // an empty side counts nothing
+        let side = |text: &str| match &text[1..] {
+            "" => Ok((1, 0)),
+            span if span.contains("..") => range(span).ok_or_else(err),
+            _ => Err(err()),
+        };
+        let wide = Wide {
+            radius,
+            middle: false,
+            birth: side(birth)?,
+            survival: side(survival)?,
+        };
+        wide.check()
+            .map_err(|why| format!("invalid rule '{}': {}", s, why))?;
+        Ok(wide.into_rule())
+    }
+
+    /// The rule a file at `path` gives: a Golly `.rule` file, as in
+    /// `crate::table`, or a weighted rule description, as in
+    /// `crate::weighted`.
+    pub fn load(path: &str) -> Result<Rule, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        if text.trim_start().starts_with("@RULE") {
+            let table = Table::parse(&text).map_err(|e| format!("{}: {}", path, e))?;
+            let mut tables = TABLES.lock().unwrap();
+            // loading a rule again, perhaps changed, replaces it
+            tables.retain(|t| t.name() != table.name());
+            let table: &'static Table = Box::leak(Box::new(table));
+            tables.push(table);
+            return Ok(Rule::tabled(table));
+        }
+        let weighted = Weighted::describe(&text).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(Rule::weighted(weighted))
+    }
+
+    fn weighted(weighted: Weighted) -> Rule {
+        Rule {
+            birth: [false; 9],
+            survival: [false; 9],
+            history: false,
+            wide: None,
+            weighted: Some(Box::leak(Box::new(weighted))),
+            directional: None,
+            table: None,
+        }
+    }
+
+    fn tabled(table: &'static Table) -> Rule {
+        Rule {
+            birth: [false; 9],
+            survival: [false; 9],
+            history: false,
+            wide: None,
+            weighted: None,
+            directional: None,
+            table: Some(table),
+        }
+    }
+
+    /// The color a rule file gives `cell`, if it gives it one.
+    pub fn color(&self, cell: Cell) -> Option<(u8, u8, u8)> {
+        self.table.and_then(|table| table.color(cell))
+    }
+
+    /// How far from a cell the cells it counts are, 1 for the eight nearest
+    /// neighbors.
+    pub fn radius(&self) -> usize {
+        match (self.wide, self.weighted) {
+            (Some(wide), _) => wide.radius,
+            (_, Some(weighted)) => weighted.radius(),
+            _ => 1,
+        }
+    }
+
+    /// The weights of a weighted rule, row by row across the square of
+    /// `radius` around a cell, the cell itself in the middle.
+    pub fn weights(&self) -> Option<&[i32]> {
+        self.weighted.map(Weighted::weights)
+    }
+
+    /// Whether `next` steps this rule, because only the number of the eight
+    /// nearest neighbors alive matters.
+    fn counts_nearest(&self) -> bool {
+        self.weighted.is_none()
+            && self.directional.is_none()
+            && self.table.is_none()
+            && self.radius() == 1
+    }
+
+    /// Whether this is a directional rule or a rule table, which
+    /// `next_mask` steps.
+    pub fn directional(&self) -> bool {
+        self.directional.is_some() || self.table.is_some()
+    }
+
+    /// The cell's next state with the neighbors in `mask` alive, bit 0 the
+    /// one to the northwest and on row by row to bit 7 the one to the
+    /// southeast.
+    pub fn next_mask(&self, cell: Cell, mask: u8) -> Cell {
+        match (self.directional, self.table) {
+            (Some(directional), _) => directional.next(cell, mask),
+            (_, Some(table)) => table.next(cell, mask),
+            _ => self.next(cell, mask.count_ones() as u8),
+        }
+    }
+
+    /// An error saying `what` can't step this rule, if it counts more than
+    /// the eight nearest neighbors.
+    pub fn nearest_only(&self, what: &str) -> Result<(), String> {
+        if self.counts_nearest() {
+            return Ok(());
+        }
+        Err(format!(
+            "{} counts the eight nearest neighbors alone, so it cannot step rule {}",
+            what, self
+        ))
+    }
+
+    /// Whether this is a History rule, whose cells have the states of
+    /// `crate::history`.
+    pub fn history(&self) -> bool {
+        self.history
+    }
+
+    /// The cell's next state with `neighbors` of the eight nearest live.
+    pub fn next(&self, cell: Cell, neighbors: u8) -> Cell {
+        if self.wide.is_some() {
+            return self.next_within(cell, u32::from(neighbors + cell.n()));
+        }
+        let n = neighbors as usize;
+        match cell {
+            Cell::Dead if self.birth[n] => Cell::Live,
+            Cell::Live if self.survival[n] => Cell::Live,
+            _ => Cell::Dead,
+        }
+    }
+
+    /// The cell's next state with `sum` the weights of the live cells
+    /// around it, under a weighted rule.
+    pub fn next_weighted(&self, cell: Cell, sum: i32) -> Cell {
+        self.weighted
+            .map_or(Cell::Dead, |weighted| weighted.next(cell, sum))
+    }
+
+    /// The sum of `weights` for every cell of `world`, row by row, if this
+    /// is a weighted rule.
+    pub(crate) fn weighted_sums(&self, world: &crate::World) -> Option<Vec<i32>> {
+        self.weighted.map(|weighted| weighted.sums(world))
+    }
+
+    /// The cell's next state with `live` cells within `radius` of it, itself
+    /// included.
+    pub fn next_within(&self, cell: Cell, live: u32) -> Cell {
+        let wide = match self.wide {
+            Some(wide) => wide,
+            None => return self.next(cell, (live - u32::from(cell.n())) as u8),
+        };
+        let counted = if wide.middle {
+            live
+        } else {
+            live - u32::from(cell.n())
+        };
+        match cell {
+            Cell::Dead if within(wide.birth, counted) => Cell::Live,
+            Cell::Live if within(wide.survival, counted) => Cell::Live,
+            _ => Cell::Dead,
+        }
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Rule {
+        Rule::conway()
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = |set: &[bool; 9]| -> String {
+            (0..9)
+                .filter(|&n| set[n])
+                .map(|n| std::char::from_digit(n as u32, 10).unwrap())
+                .collect()
+        };
+        if let Some(weighted) = self.weighted {
+            return write!(f, "{}", weighted);
+        }
+        if let Some(directional) = self.directional {
+            return write!(f, "{}", directional);
+        }
+        if let Some(table) = self.table {
+            return write!(f, "{}", table);
+        }
+        if let Some(wide) = self.wide {
+            return write!(
+                f,
+                "R{},C0,M{},S{}..{},B{}..{},NM",
+                wide.radius,
+                u8::from(wide.middle),
+                wide.survival.0,
+                wide.survival.1,
+                wide.birth.0,
+                wide.birth.1
+            );
+        }
+        let conway = Rule::conway();
+        match self.history {
+            true if (self.birth, self.survival) == (conway.birth, conway.survival) => {
+                write!(f, "LifeHistory")
+            }
+            history => write!(
+                f,
+                "B{}/S{}{}",
+                digits(&self.birth),
+                digits(&self.survival),
+                if history { "History" } else { "" }
+            ),
+        }
+    }
+}
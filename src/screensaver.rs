@@ -0,0 +1,54 @@
+//! Endless random soups sized to the terminal.
+
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use conway::{Rng, World};
+
+use crate::interrupt;
+use crate::pace::Pacer;
+use crate::stop::{self, Until, Watch};
+use crate::term;
+
+const DENSITY: f64 = 0.3;
+/// Soups that keep a glider or two alive would otherwise never be replaced.
+const TIME_LIMIT: Duration = Duration::from_secs(120);
+/// Grayscale ramp of the 256-color palette, from white down to almost black.
+const FADE: [u8; 8] = [255, 252, 249, 246, 243, 240, 237, 234];
+const FADE_DELAY: Duration = Duration::from_millis(80);
+
+/// Runs until interrupted with Ctrl-C. The same seed and terminal size give
+/// the same sequence of soups.
+pub fn run(mut pacer: Pacer, mut rng: Rng) -> Result<(), String> {
+    'soups: loop {
+        // re-query for every soup so a resized window is picked up
+        let (cols, rows) = term::size().unwrap_or((80, 24));
+        let mut world = World::new(cols, rows)?;
+        world.randomize(DENSITY, &mut rng);
+        let mut tmp = world.clone();
+
+        print!("{}", term::CLEAR);
+        let started = Instant::now();
+        let mut watch = Watch::new(Until::Stable, &world, stop::DEFAULT_WINDOW);
+        for generation in 1.. {
+            if interrupt::requested() {
+                return Ok(());
+            }
+            if interrupt::resized() {
+                // straight on to a soup the size of the new terminal
+                continue 'soups;
+            }
+            term::draw_in_place(&world, Some(FADE[0])).map_err(|e| e.to_string())?;
+            pacer.wait();
+            world.advance(&mut tmp);
+            if watch.check(&world, generation).is_some() || started.elapsed() >= TIME_LIMIT {
+                break;
+            }
+        }
+
+        for &shade in &FADE[1..] {
+            term::draw_in_place(&world, Some(shade)).map_err(|e| e.to_string())?;
+            sleep(FADE_DELAY);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Per-generation hooks in any language: `run --script PROGRAM` starts the
+//! program once, and after every generation writes it a JSON line like
+//! `--output-format jsonl --cells list` gives, then reads commands back
+//! until `next`:
+//!
+//! ```text
+//! set X Y live|dead    set a cell
+//! place NAME X Y       stamp a built-in pattern or file there
+//! log MESSAGE          print MESSAGE on stderr
+//! stop [REASON]        end the run
+//! next                 carry on to the next generation
+//! ```
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use conway::{library, Cell, Changes, Pattern, World};
+
+use crate::jsonl::{self, Cells};
+use crate::log;
+use crate::memory;
+use crate::record::Recorders;
+
+/// What the script did with a generation.
+#[derive(Default)]
+pub struct Answer {
+    /// Cells it changed.
+    pub edits: usize,
+    /// Why it stopped the run, if it did.
+    pub stop: Option<String>,
+}
+
+pub struct Script {
+    command: String,
+    child: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+}
+
+impl Script {
+    /// Starts `command` through the shell.
+    pub fn start(command: &str) -> Result<Script, String> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("cannot start the script '{}': {}", command, e))?;
+        let input = child.stdin.take().unwrap();
+        let output = BufReader::new(child.stdout.take().unwrap());
+        Ok(Script {
+            command: command.to_string(),
+            child,
+            input,
+            output,
+        })
+    }
+
+    /// Shows the script the world at `generation` and carries out its answer,
+    /// each cell it changes recorded.
+    pub fn hook(
+        &mut self,
+        world: &mut World,
+        generation: u64,
+        changes: Changes,
+        recorders: &mut Recorders,
+    ) -> Result<Answer, String> {
+        let frame = jsonl::frame(world, generation, changes, Cells::List);
+        // a script that has stopped listening may still have answered, so
+        // whether it did is what counts
+        let _ = writeln!(self.input, "{}", frame).and_then(|()| self.input.flush());
+        let mut answer = Answer::default();
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let read = self
+                .output
+                .read_line(&mut line)
+                .map_err(|e| format!("cannot read from the script '{}': {}", self.command, e))?;
+            if read == 0 {
+                return Err(format!(
+                    "the script '{}' ended without answering generation {}",
+                    self.command, generation
+                ));
+            }
+            let line = line.trim();
+            let invalid = || {
+                format!(
+                    "invalid command '{}' from the script at generation {}",
+                    line, generation
+                )
+            };
+            let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let words: Vec<&str> = rest.split_whitespace().collect();
+            match (command, words.as_slice()) {
+                ("next", []) => return Ok(answer),
+                ("stop", _) => {
+                    let mut reason = format!("stopped by the script at generation {}", generation);
+                    if !rest.trim().is_empty() {
+                        reason.push_str(": ");
+                        reason.push_str(rest.trim());
+                    }
+                    answer.stop = Some(reason);
+                    return Ok(answer);
+                }
+                ("log", _) => log::info("script", rest.trim()),
+                ("set", [x, y, state]) => {
+                    let (x, y) = cell(x, y).ok_or_else(invalid)?;
+                    let cell = match *state {
+                        "live" => Cell::Live,
+                        "dead" => Cell::Dead,
+                        _ => return Err(invalid()),
+                    };
+                    if world.try_get(x, y)? != cell {
+                        world.try_set(x, y, cell)?;
+                        recorders.edit(x, y, cell);
+                        answer.edits += 1;
+                    }
+                }
+                ("place", [name, x, y]) => {
+                    let (x, y) = cell(x, y).ok_or_else(invalid)?;
+                    world.try_get(x, y)?;
+                    let pattern = match library::get(name) {
+                        Some(pattern) => pattern,
+                        None => Pattern::load_within(name, &memory::fits(None))?,
+                    };
+                    let (w, h) = (world.width(), world.height());
+                    for py in 0..pattern.height() {
+                        for px in 0..pattern.width() {
+                            let (cx, cy) = ((x + px) % w, (y + py) % h);
+                            if pattern.get(px, py) && world.get(cx, cy) == Cell::Dead {
+                                world.set(cx, cy, Cell::Live);
+                                recorders.edit(cx, cy, Cell::Live);
+                                answer.edits += 1;
+                            }
+                        }
+                    }
+                }
+                ("", []) => {}
+                _ => return Err(invalid()),
+            }
+        }
+    }
+
+    /// Lets the script see its input end and waits for it to exit.
+    pub fn finish(self) -> Result<(), String> {
+        let Script {
+            command,
+            mut child,
+            input,
+            ..
+        } = self;
+        drop(input);
+        match child.wait() {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => Err(format!("the script '{}' failed with {}", command, status)),
+            Err(e) => Err(format!("cannot wait for the script '{}': {}", command, e)),
+        }
+    }
+}
+
+fn cell(x: &str, y: &str) -> Option<(usize, usize)> {
+    Some((x.parse().ok()?, y.parse().ok()?))
+}
@@ -0,0 +1,276 @@
+//! `conway scrub`: a run in the terminal with a timeline of its population
+//! under it, to go back to any generation since it started, by keys or by
+//! clicking and dragging along the timeline, and carry on from there. The
+//! generations after one already seen are played again as they went unless
+//! a cell is toggled, which branches the run there, forgetting what came
+//! after. The past is kept by a `Timeline`, so going back costs stepping
+//! from the nearest snapshot rather than memory for every generation.
+
+use std::io::{self, Write};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use conway::timeline::Timeline;
+use conway::{Cell, World};
+
+use crate::cli::ScrubOptions;
+use crate::interrupt;
+use crate::term::{self, Key, MouseReports, RawMode};
+
+const HELP: &str = "p play/pause  left/right step  [ ] a tenth  0/$ first/latest  \
+                    click or drag the timeline  hjkl move  space toggle  q quit";
+
+const CTRL_C: u8 = 0x03;
+
+/// How often a paused run looks for the terminal being resized.
+const POLL: Duration = Duration::from_millis(250);
+
+/// The heights of the timeline's columns, lowest first.
+const BARS: [&str; 9] = [" ", "▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+const ASCII_BARS: [&str; 9] = [" ", ".", "_", "-", "~", "=", "+", "*", "#"];
+
+/// Rows under the world: the timeline, its marker, the status and the keys.
+const BELOW: usize = 4;
+
+struct Scrubber {
+    world: World,
+    /// The generation shown, and the latest one reached.
+    at: u64,
+    latest: u64,
+    timeline: Timeline,
+    /// The population of every generation up to the latest.
+    populations: Vec<usize>,
+    playing: bool,
+    x: usize,
+    y: usize,
+    message: String,
+    live: String,
+    dead: String,
+    unicode: bool,
+}
+
+impl Scrubber {
+    /// The part of the world in view, as the column and row of its top left
+    /// and its width and height, and how wide the timeline is.
+    fn layout(&self) -> ((usize, usize), (usize, usize), usize) {
+        let (columns, rows) = term::size().unwrap_or((80, 24));
+        let width = self.world.width().min(columns.max(1));
+        let height = self.world.height().min(rows.saturating_sub(BELOW).max(1));
+        // the view follows the cursor
+        let left = (self.x + 1).saturating_sub(width);
+        let top = (self.y + 1).saturating_sub(height);
+        ((left, top), (width, height), columns.max(1))
+    }
+
+    /// The column of the timeline `generation` falls in.
+    fn column(&self, generation: u64, columns: usize) -> usize {
+        let n = self.latest + 1;
+        if n <= columns as u64 {
+            generation as usize
+        } else {
+            (generation * columns as u64 / n) as usize
+        }
+    }
+
+    /// The generation a column of the timeline stands for.
+    fn generation(&self, column: usize, columns: usize) -> u64 {
+        let n = self.latest + 1;
+        let generation = if n <= columns as u64 {
+            column as u64
+        } else {
+            (column as u64 * n + n / 2) / columns as u64
+        };
+        generation.min(self.latest)
+    }
+
+    fn draw(&self) -> io::Result<()> {
+        let ((left, top), (width, height), columns) = self.layout();
+        let mut frame = term::frame_start();
+        for y in top..top + height {
+            for x in left..left + width {
+                let cursor = (x, y) == (self.x, self.y);
+                if cursor {
+                    frame.push_str("\x1b[7m");
+                }
+                frame.push_str(match self.world.get(x, y) {
+                    Cell::Live => &self.live,
+                    Cell::Dead => &self.dead,
+                });
+                if cursor {
+                    frame.push_str(term::RESET);
+                }
+            }
+            frame.push_str("\x1b[K\r\n");
+        }
+        // the highest population in each column, as a height against the
+        // highest of the run
+        let used = self.column(self.latest, columns) + 1;
+        let mut highest = vec![0; used];
+        for (generation, &population) in self.populations.iter().enumerate() {
+            let column = self.column(generation as u64, columns);
+            highest[column] = highest[column].max(population);
+        }
+        let peak = highest.iter().copied().max().unwrap_or(0).max(1);
+        let bars = if self.unicode { &BARS } else { &ASCII_BARS };
+        let shown = self.column(self.at, columns);
+        for (column, &population) in highest.iter().enumerate() {
+            let level = (population * 8).div_ceil(peak);
+            if column == shown {
+                frame.push_str("\x1b[7m");
+            }
+            frame.push_str(bars[level]);
+            if column == shown {
+                frame.push_str(term::RESET);
+            }
+        }
+        frame.push_str("\x1b[K\r\n");
+        frame.push_str(&" ".repeat(shown));
+        frame.push_str("^\x1b[K\r\n");
+        frame.push_str(&format!(
+            "generation {} of {}  population {}  {}  {}\x1b[K\r\n",
+            self.at,
+            self.latest,
+            self.world.population(),
+            if self.playing { "playing" } else { "paused" },
+            self.message
+        ));
+        frame.push_str(HELP);
+        frame.push_str("\x1b[K");
+        print!("{}", frame);
+        io::stdout().flush()
+    }
+
+    /// Shows the generation after the one shown, working it out for the
+    /// first time if it is past the latest.
+    fn forward(&mut self) {
+        let mut tmp = self.world.clone();
+        self.world.advance(&mut tmp);
+        self.at += 1;
+        if self.at > self.latest {
+            self.latest = self.at;
+            self.timeline.record(&self.world, self.at);
+            self.populations.push(self.world.population());
+        }
+    }
+
+    /// Shows `generation`, going back through the timeline or on from the
+    /// generation shown, whichever is nearer.
+    fn jump(&mut self, generation: u64) {
+        let generation = generation.min(self.latest);
+        if generation > self.at && generation - self.at <= 64 {
+            while self.at < generation {
+                self.forward();
+            }
+        } else if generation != self.at {
+            if let Some(world) = self.timeline.at(generation) {
+                self.world = world;
+                self.at = generation;
+            }
+        }
+        self.message.clear();
+    }
+
+    /// Toggles the cell under the cursor, branching the run off from the
+    /// generation shown.
+    fn toggle(&mut self) {
+        self.world.toggle(self.x, self.y);
+        let dropped = self.latest - self.at;
+        self.message = match dropped {
+            0 => String::new(),
+            n => format!("branched at generation {}, forgetting {} after", self.at, n),
+        };
+        self.latest = self.at;
+        self.timeline.keep(&self.world, self.at);
+        self.populations.truncate(self.at as usize + 1);
+        self.populations[self.at as usize] = self.world.population();
+    }
+
+    /// Moves the cursor to a click on the world, or the run to a click on
+    /// the timeline.
+    fn click(&mut self, column: usize, row: usize) {
+        let ((left, top), (width, height), columns) = self.layout();
+        if row < height && column < width {
+            self.x = left + column;
+            self.y = top + row;
+        } else if row == height || row == height + 1 {
+            let generation = self.generation(column.min(columns - 1), columns);
+            self.jump(generation);
+        }
+    }
+}
+
+pub fn run(world: World, opts: &ScrubOptions) -> Result<(), String> {
+    let mut scrubber = Scrubber {
+        timeline: Timeline::new(&world, 0),
+        populations: vec![world.population()],
+        x: world.width() / 2,
+        y: world.height() / 2,
+        world,
+        at: 0,
+        latest: 0,
+        playing: true,
+        message: String::new(),
+        live: opts.style.live.clone(),
+        dead: opts.style.dead.clone(),
+        unicode: opts.style.unicode,
+    };
+    let raw = RawMode::enable()?;
+    let mouse = MouseReports::enable();
+    // keys come in on a thread of their own, so the run plays on between them
+    let (send, keys) = mpsc::channel();
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        let mut input = stdin.lock();
+        while let Ok(key) = term::read_key(&mut input) {
+            if send.send(key).is_err() {
+                return;
+            }
+        }
+    });
+    let delay = Duration::from_millis(opts.delay);
+    loop {
+        scrubber.draw().map_err(|e| e.to_string())?;
+        let key = match keys.recv_timeout(if scrubber.playing { delay } else { POLL }) {
+            Ok(key) => key,
+            Err(RecvTimeoutError::Timeout) => {
+                if interrupt::resized() {
+                    print!("{}", term::CLEAR);
+                } else if scrubber.playing {
+                    scrubber.forward();
+                }
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        let (w, h) = (scrubber.world.width(), scrubber.world.height());
+        let tenth = (scrubber.latest / 10).max(1);
+        match key {
+            Key::Char(b'p') => scrubber.playing = !scrubber.playing,
+            Key::Right => scrubber.forward(),
+            Key::Left => scrubber.jump(scrubber.at.saturating_sub(1)),
+            Key::Char(b']') => scrubber.jump(scrubber.at + tenth),
+            Key::Char(b'[') => scrubber.jump(scrubber.at.saturating_sub(tenth)),
+            Key::Char(b'0') => scrubber.jump(0),
+            Key::Char(b'$') => scrubber.jump(scrubber.latest),
+            Key::Click(column, row) => scrubber.click(column, row),
+            Key::Char(b'k') => scrubber.y = (scrubber.y + h - 1) % h,
+            Key::Char(b'j') => scrubber.y = (scrubber.y + 1) % h,
+            Key::Char(b'h') => scrubber.x = (scrubber.x + w - 1) % w,
+            Key::Char(b'l') => scrubber.x = (scrubber.x + 1) % w,
+            Key::Char(b' ') => scrubber.toggle(),
+            Key::Char(b'q') | Key::Char(CTRL_C) => break,
+            Key::Resize => print!("{}", term::CLEAR),
+            Key::Up | Key::Down | Key::Char(_) => {}
+        }
+    }
+    drop(mouse);
+    drop(raw);
+    println!(
+        "generation {} of {}, {} snapshots kept",
+        scrubber.at,
+        scrubber.latest,
+        scrubber.timeline.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,898 @@
+//! Window frontend on top of SDL2.
+//!
+//! Only the handful of SDL functions we need are declared here, so the feature
+//! requires nothing but the system libSDL2 at link time. That leaves out
+//! SDL_ttf too, so the control panel beside the grid writes its labels in a
+//! small built-in font.
+
+use std::ffi::{c_void, CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::time::{Duration, Instant};
+
+use conway::{library, Cell, Changes, Pattern, Rng, Rule, World};
+
+use crate::cli::RunOptions;
+use crate::interrupt;
+use crate::log;
+use crate::memory;
+use crate::pace::Timings;
+use crate::record::Recorders;
+use crate::stop::{Breakpoints, Watch};
+use crate::term::Style;
+
+const SDL_INIT_VIDEO: u32 = 0x0000_0020;
+const SDL_WINDOWPOS_CENTERED: c_int = 0x2FFF_0000;
+const SDL_WINDOW_RESIZABLE: u32 = 0x0000_0020;
+const SDL_RENDERER_ACCELERATED: u32 = 0x0000_0002;
+const SDL_RENDERER_PRESENTVSYNC: u32 = 0x0000_0004;
+
+const SDL_QUIT: u32 = 0x100;
+const SDL_KEYDOWN: u32 = 0x300;
+const SDL_MOUSEMOTION: u32 = 0x400;
+const SDL_MOUSEBUTTONDOWN: u32 = 0x401;
+const SDL_MOUSEBUTTONUP: u32 = 0x402;
+const SDL_DROPFILE: u32 = 0x1000;
+const SDL_BUTTON_LEFT: u8 = 1;
+const SDL_BUTTON_LMASK: u32 = 1;
+
+const SDLK_ESCAPE: i32 = 27;
+const SDLK_SPACE: i32 = 32;
+const SDLK_Q: i32 = 113;
+
+const CELL_SIZE: c_int = 12;
+
+enum SdlWindow {}
+enum SdlRenderer {}
+
+#[repr(C)]
+struct SdlRect {
+    x: c_int,
+    y: c_int,
+    w: c_int,
+    h: c_int,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct SdlKeysym {
+    scancode: i32,
+    sym: i32,
+    modifiers: u16,
+    unused: u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct SdlKeyboardEvent {
+    kind: u32,
+    timestamp: u32,
+    window_id: u32,
+    state: u8,
+    repeat: u8,
+    padding: [u8; 2],
+    keysym: SdlKeysym,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct SdlMouseButtonEvent {
+    kind: u32,
+    timestamp: u32,
+    window_id: u32,
+    which: u32,
+    button: u8,
+    state: u8,
+    clicks: u8,
+    padding: u8,
+    x: i32,
+    y: i32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct SdlMouseMotionEvent {
+    kind: u32,
+    timestamp: u32,
+    window_id: u32,
+    which: u32,
+    state: u32,
+    x: i32,
+    y: i32,
+    xrel: i32,
+    yrel: i32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct SdlDropEvent {
+    kind: u32,
+    timestamp: u32,
+    file: *mut c_char,
+    window_id: u32,
+}
+
+#[repr(C)]
+union SdlEvent {
+    kind: u32,
+    key: SdlKeyboardEvent,
+    button: SdlMouseButtonEvent,
+    motion: SdlMouseMotionEvent,
+    drop: SdlDropEvent,
+    padding: [u8; 56],
+}
+
+#[link(name = "SDL2")]
+extern "C" {
+    fn SDL_Init(flags: u32) -> c_int;
+    fn SDL_Quit();
+    fn SDL_GetError() -> *const c_char;
+    fn SDL_free(mem: *mut c_void);
+    fn SDL_GetTicks() -> u32;
+    fn SDL_Delay(ms: u32);
+    fn SDL_CreateWindow(
+        title: *const c_char,
+        x: c_int,
+        y: c_int,
+        w: c_int,
+        h: c_int,
+        flags: u32,
+    ) -> *mut SdlWindow;
+    fn SDL_DestroyWindow(window: *mut SdlWindow);
+    fn SDL_SetWindowTitle(window: *mut SdlWindow, title: *const c_char);
+    fn SDL_GetWindowSize(window: *mut SdlWindow, w: *mut c_int, h: *mut c_int);
+    fn SDL_CreateRenderer(window: *mut SdlWindow, index: c_int, flags: u32) -> *mut SdlRenderer;
+    fn SDL_DestroyRenderer(renderer: *mut SdlRenderer);
+    fn SDL_GetRendererOutputSize(renderer: *mut SdlRenderer, w: *mut c_int, h: *mut c_int)
+        -> c_int;
+    fn SDL_SetRenderDrawColor(renderer: *mut SdlRenderer, r: u8, g: u8, b: u8, a: u8) -> c_int;
+    fn SDL_RenderClear(renderer: *mut SdlRenderer) -> c_int;
+    fn SDL_RenderFillRects(
+        renderer: *mut SdlRenderer,
+        rects: *const SdlRect,
+        count: c_int,
+    ) -> c_int;
+    fn SDL_RenderPresent(renderer: *mut SdlRenderer);
+    fn SDL_PollEvent(event: *mut SdlEvent) -> c_int;
+}
+
+fn last_error() -> String {
+    unsafe { CStr::from_ptr(SDL_GetError()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Owns the SDL context, window and renderer, tearing them down on drop.
+struct Context {
+    window: *mut SdlWindow,
+    renderer: *mut SdlRenderer,
+}
+
+impl Context {
+    fn new(title: &str, width: c_int, height: c_int) -> Result<Context, String> {
+        let title = CString::new(title).map_err(|e| e.to_string())?;
+        unsafe {
+            if SDL_Init(SDL_INIT_VIDEO) != 0 {
+                return Err(last_error());
+            }
+            let window = SDL_CreateWindow(
+                title.as_ptr(),
+                SDL_WINDOWPOS_CENTERED,
+                SDL_WINDOWPOS_CENTERED,
+                width,
+                height,
+                SDL_WINDOW_RESIZABLE,
+            );
+            if window.is_null() {
+                let err = last_error();
+                SDL_Quit();
+                return Err(err);
+            }
+            let flags = SDL_RENDERER_ACCELERATED | SDL_RENDERER_PRESENTVSYNC;
+            let renderer = SDL_CreateRenderer(window, -1, flags);
+            if renderer.is_null() {
+                let err = last_error();
+                SDL_DestroyWindow(window);
+                SDL_Quit();
+                return Err(err);
+            }
+            Ok(Context { window, renderer })
+        }
+    }
+
+    fn set_title(&self, title: &str) {
+        if let Ok(title) = CString::new(title) {
+            unsafe { SDL_SetWindowTitle(self.window, title.as_ptr()) };
+        }
+    }
+
+    fn output_size(&self) -> (c_int, c_int) {
+        let (mut w, mut h) = (0, 0);
+        unsafe { SDL_GetRendererOutputSize(self.renderer, &mut w, &mut h) };
+        (w, h)
+    }
+
+    /// Converts window coordinates (as reported by mouse events) to output pixels,
+    /// which differ on high-DPI displays.
+    fn to_pixels(&self, x: c_int, y: c_int) -> (c_int, c_int) {
+        let (mut w, mut h) = (0, 0);
+        unsafe { SDL_GetWindowSize(self.window, &mut w, &mut h) };
+        let (pw, ph) = self.output_size();
+        (x * pw / w.max(1), y * ph / h.max(1))
+    }
+
+    /// Output pixels to a window point, 2 on most high-DPI displays.
+    fn scale(&self) -> c_int {
+        let (mut w, mut h) = (0, 0);
+        unsafe { SDL_GetWindowSize(self.window, &mut w, &mut h) };
+        (self.output_size().0 / w.max(1)).max(1)
+    }
+}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        unsafe {
+            SDL_DestroyRenderer(self.renderer);
+            SDL_DestroyWindow(self.window);
+            SDL_Quit();
+        }
+    }
+}
+
+/// Placement of the grid inside the window: square cells, centered, scaled to fit.
+struct Layout {
+    cell: c_int,
+    left: c_int,
+    top: c_int,
+}
+
+impl Layout {
+    fn fit(world: &World, (w, h): (c_int, c_int)) -> Layout {
+        let cell = (w / world.width() as c_int)
+            .min(h / world.height() as c_int)
+            .max(1);
+        Layout {
+            cell,
+            left: (w - cell * world.width() as c_int) / 2,
+            top: (h - cell * world.height() as c_int) / 2,
+        }
+    }
+
+    fn cell_at(&self, world: &World, x: c_int, y: c_int) -> Option<(usize, usize)> {
+        if x < self.left || y < self.top {
+            return None;
+        }
+        let cx = ((x - self.left) / self.cell) as usize;
+        let cy = ((y - self.top) / self.cell) as usize;
+        if cx < world.width() && cy < world.height() {
+            Some((cx, cy))
+        } else {
+            None
+        }
+    }
+}
+
+/// The width of the control panel down the right of the window, in points.
+const PANEL: c_int = 200;
+/// The height the window is opened with at least, for all of the panel.
+const PANEL_HEIGHT: c_int = PAD + 14 * ROW;
+/// The space around the panel's controls, the height of a row and of a
+/// button in it.
+const PAD: c_int = 12;
+const ROW: c_int = 24;
+const BUTTON: c_int = 20;
+/// Points to a pixel of the font, and from one letter to the next.
+const DOT: c_int = 2;
+const ADVANCE: c_int = 4 * DOT;
+/// Letters across the panel, and between the buttons either side of a row.
+const LINE: usize = ((PANEL - 2 * PAD) / ADVANCE) as usize;
+const BETWEEN: usize = ((PANEL - 2 * PAD - 2 * BUTTON - 2 * DOT) / ADVANCE) as usize;
+
+/// Rules the panel goes through, by the names `--rule` takes.
+const RULES: [&str; 9] = [
+    "life",
+    "highlife",
+    "seeds",
+    "daynight",
+    "life-without-death",
+    "2x2",
+    "replicator",
+    "maze",
+    "morley",
+];
+
+/// Delays between generations the speed buttons go through, in ms.
+const SPEEDS: [u64; 9] = [0, 10, 25, 50, 100, 200, 500, 1000, 2000];
+
+#[derive(Copy, Clone, PartialEq)]
+enum Control {
+    RulePrev,
+    RuleNext,
+    Slower,
+    Faster,
+    Density,
+    Randomize,
+    PatternPrev,
+    PatternNext,
+    Place,
+    Pause,
+    Step,
+    Clear,
+}
+
+/// Where each control is: its left, top, width and height in points from
+/// the panel's top left.
+const CONTROLS: [(Control, (c_int, c_int, c_int, c_int)); 12] = [
+    (Control::RulePrev, (PAD, PAD + ROW, BUTTON, BUTTON)),
+    (
+        Control::RuleNext,
+        (PANEL - PAD - BUTTON, PAD + ROW, BUTTON, BUTTON),
+    ),
+    (Control::Slower, (PAD, PAD + 3 * ROW, 84, BUTTON)),
+    (
+        Control::Faster,
+        (PANEL - PAD - 84, PAD + 3 * ROW, 84, BUTTON),
+    ),
+    (
+        Control::Density,
+        (PAD, PAD + 5 * ROW, PANEL - 2 * PAD, BUTTON),
+    ),
+    (
+        Control::Randomize,
+        (PAD, PAD + 6 * ROW, PANEL - 2 * PAD, BUTTON),
+    ),
+    (Control::PatternPrev, (PAD, PAD + 8 * ROW, BUTTON, BUTTON)),
+    (
+        Control::PatternNext,
+        (PANEL - PAD - BUTTON, PAD + 8 * ROW, BUTTON, BUTTON),
+    ),
+    (
+        Control::Place,
+        (PAD, PAD + 9 * ROW, PANEL - 2 * PAD, BUTTON),
+    ),
+    (Control::Pause, (PAD, PAD + 11 * ROW, 56, BUTTON)),
+    (Control::Step, (PAD + 60, PAD + 11 * ROW, 56, BUTTON)),
+    (Control::Clear, (PAD + 120, PAD + 11 * ROW, 56, BUTTON)),
+];
+
+/// What the panel shows besides the world's own rule and the speed.
+struct Panel {
+    density: f64,
+    /// Which of `library::names` Place puts down.
+    pattern: usize,
+    patterns: Vec<&'static str>,
+    /// What the last thing done came to, like a file that wouldn't load.
+    message: String,
+}
+
+impl Panel {
+    /// The panel's left edge in output pixels, and how many of them make a
+    /// point.
+    fn origin(ctx: &Context) -> (c_int, c_int) {
+        let scale = ctx.scale();
+        (ctx.output_size().0 - PANEL * scale, scale)
+    }
+
+    /// The control at output pixel `x`, `y`, and how far across it that is.
+    fn control_at(ctx: &Context, x: c_int, y: c_int) -> Option<(Control, f64)> {
+        let (left, scale) = Panel::origin(ctx);
+        let (x, y) = ((x - left) / scale, y / scale);
+        CONTROLS
+            .iter()
+            .find(|&&(_, (cx, cy, w, h))| x >= cx && x < cx + w && y >= cy && y < cy + h)
+            .map(|&(control, (cx, _, w, _))| (control, f64::from(x - cx) / f64::from(w)))
+    }
+
+    /// How far across the density slider output pixel column `x` is, held
+    /// to its ends so dragging past them keeps following the mouse.
+    fn slid(ctx: &Context, x: c_int) -> f64 {
+        let (left, scale) = Panel::origin(ctx);
+        let (cx, w) = CONTROLS
+            .iter()
+            .find(|&&(control, _)| control == Control::Density)
+            .map_or((0, 1), |&(_, (cx, _, w, _))| (cx, w));
+        (f64::from((x - left) / scale - cx) / f64::from(w)).clamp(0.0, 1.0)
+    }
+}
+
+/// The name of `rule` among `RULES`, if it has one.
+fn rule_name(rule: Rule) -> Option<usize> {
+    RULES
+        .iter()
+        .position(|name| Rule::parse(name).ok() == Some(rule))
+}
+
+/// The rows of the font's letters, three pixels wide with the leftmost in
+/// the highest bit. Anything else is left blank.
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '<' => [0b001, 0b010, 0b100, 0b010, 0b001],
+        '>' => [0b100, 0b010, 0b001, 0b010, 0b100],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        '(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        _ => [0; 5],
+    }
+}
+
+/// The pixels of `text` written from point `x`, `y` of the panel, cut off
+/// at the panel's edge.
+fn text(rects: &mut Vec<SdlRect>, text: &str, (left, scale): (c_int, c_int), x: c_int, y: c_int) {
+    let fits = ((PANEL - PAD - x + DOT) / ADVANCE).max(0) as usize;
+    for (i, c) in text.chars().take(fits).enumerate() {
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for column in 0..3 {
+                if bits & (0b100 >> column) != 0 {
+                    rects.push(SdlRect {
+                        x: left + (x + i as c_int * ADVANCE + column * DOT) * scale,
+                        y: (y + row as c_int * DOT) * scale,
+                        w: DOT * scale,
+                        h: DOT * scale,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// `label` in the middle of `control`.
+fn caption(rects: &mut Vec<SdlRect>, label: &str, origin: (c_int, c_int), control: Control) {
+    if let Some(&(_, (x, y, w, h))) = CONTROLS.iter().find(|&&(c, _)| c == control) {
+        let width = label.chars().count() as c_int * ADVANCE - DOT;
+        text(
+            rects,
+            label,
+            origin,
+            x + (w - width) / 2,
+            y + (h - 5 * DOT) / 2,
+        );
+    }
+}
+
+fn draw_panel(ctx: &Context, world: &World, panel: &Panel, delay: u64, paused: bool) {
+    let origin @ (left, scale) = Panel::origin(ctx);
+    let at = |x: c_int, y: c_int, w: c_int, h: c_int| SdlRect {
+        x: left + x * scale,
+        y: y * scale,
+        w: w * scale,
+        h: h * scale,
+    };
+    let background = at(0, 0, PANEL, ctx.output_size().1 / scale);
+    let mut buttons = Vec::new();
+    let mut filled = None;
+    for &(control, (x, y, w, h)) in CONTROLS.iter() {
+        if control == Control::Density {
+            // a track, filled up to the density
+            buttons.push(at(x, y + h / 2 - 2, w, 4));
+            let across = (f64::from(w) * panel.density) as c_int;
+            filled = Some(at(x, y + h / 2 - 2, across, 4));
+            buttons.push(at(x + across - 3, y + 2, 6, h - 4));
+        } else {
+            buttons.push(at(x, y, w, h));
+        }
+    }
+    let mut letters = Vec::new();
+    let label = |letters: &mut Vec<SdlRect>, row: c_int, line: &str| {
+        text(
+            letters,
+            line,
+            origin,
+            PAD,
+            PAD + row * ROW + (BUTTON - 5 * DOT) / 2,
+        )
+    };
+    // a value between the < and > of a row, in the middle
+    let between = |letters: &mut Vec<SdlRect>, row: c_int, value: &str| {
+        let value: String = value.chars().take(BETWEEN).collect();
+        let width = value.chars().count() as c_int * ADVANCE - DOT;
+        let y = PAD + row * ROW + (BUTTON - 5 * DOT) / 2;
+        text(letters, &value, origin, (PANEL - width) / 2, y)
+    };
+    let rule = world.rule();
+    match rule_name(rule) {
+        Some(i) => label(&mut letters, 0, &format!("rule {}", RULES[i])),
+        None => label(&mut letters, 0, "rule"),
+    }
+    between(&mut letters, 1, &rule.to_string());
+    caption(&mut letters, "<", origin, Control::RulePrev);
+    caption(&mut letters, ">", origin, Control::RuleNext);
+    match delay {
+        0 => label(&mut letters, 2, "speed: no delay"),
+        ms => label(&mut letters, 2, &format!("speed: {} ms", ms)),
+    }
+    caption(&mut letters, "slower", origin, Control::Slower);
+    caption(&mut letters, "faster", origin, Control::Faster);
+    label(
+        &mut letters,
+        4,
+        &format!("density: {:.0}%", panel.density * 100.0),
+    );
+    caption(&mut letters, "random", origin, Control::Randomize);
+    label(&mut letters, 7, "pattern");
+    between(&mut letters, 8, panel.patterns[panel.pattern]);
+    caption(&mut letters, "<", origin, Control::PatternPrev);
+    caption(&mut letters, ">", origin, Control::PatternNext);
+    caption(&mut letters, "place", origin, Control::Place);
+    caption(
+        &mut letters,
+        if paused { "play" } else { "pause" },
+        origin,
+        Control::Pause,
+    );
+    caption(&mut letters, "step", origin, Control::Step);
+    caption(&mut letters, "clear", origin, Control::Clear);
+    // the message wrapped at the words
+    let mut lines = vec![String::new()];
+    for word in panel.message.split_whitespace() {
+        let line = lines.last_mut().unwrap();
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > LINE {
+            lines.push(word.to_string());
+        } else {
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+    }
+    for (i, line) in lines.iter().take(4).enumerate() {
+        text(
+            &mut letters,
+            line,
+            origin,
+            PAD,
+            PAD + 12 * ROW + i as c_int * 3 * ADVANCE / 2,
+        );
+    }
+    unsafe {
+        SDL_SetRenderDrawColor(ctx.renderer, 40, 40, 40, 255);
+        SDL_RenderFillRects(ctx.renderer, &background, 1);
+        SDL_SetRenderDrawColor(ctx.renderer, 90, 90, 90, 255);
+        SDL_RenderFillRects(ctx.renderer, buttons.as_ptr(), buttons.len() as c_int);
+        if let Some(filled) = &filled {
+            SDL_SetRenderDrawColor(ctx.renderer, 160, 160, 160, 255);
+            SDL_RenderFillRects(ctx.renderer, filled, 1);
+        }
+        SDL_SetRenderDrawColor(ctx.renderer, 230, 230, 230, 255);
+        SDL_RenderFillRects(ctx.renderer, letters.as_ptr(), letters.len() as c_int);
+    }
+}
+
+fn draw(ctx: &Context, world: &World, layout: &Layout, style: &Style) {
+    // leave a one pixel gap between cells once they are big enough to see it
+    let gap = if layout.cell > 3 { 1 } else { 0 };
+    let mut rects = Vec::new();
+    for y in 0..world.height() {
+        for x in 0..world.width() {
+            if world.get(x, y) == Cell::Live {
+                rects.push(SdlRect {
+                    x: layout.left + x as c_int * layout.cell,
+                    y: layout.top + y as c_int * layout.cell,
+                    w: layout.cell - gap,
+                    h: layout.cell - gap,
+                });
+            }
+        }
+    }
+    let board = SdlRect {
+        x: layout.left,
+        y: layout.top,
+        w: layout.cell * world.width() as c_int,
+        h: layout.cell * world.height() as c_int,
+    };
+    unsafe {
+        SDL_SetRenderDrawColor(ctx.renderer, 0, 0, 0, 255);
+        SDL_RenderClear(ctx.renderer);
+        let (r, g, b) = style.dead_rgb();
+        SDL_SetRenderDrawColor(ctx.renderer, r, g, b, 255);
+        SDL_RenderFillRects(ctx.renderer, &board, 1);
+        let (r, g, b) = style.live_rgb();
+        SDL_SetRenderDrawColor(ctx.renderer, r, g, b, 255);
+        SDL_RenderFillRects(ctx.renderer, rects.as_ptr(), rects.len() as c_int);
+    }
+}
+
+/// Sets every cell of `world` to what `cell` says, recording those changed.
+fn repaint(world: &mut World, recorders: &mut Recorders, cell: impl Fn(usize, usize) -> Cell) {
+    for y in 0..world.height() {
+        for x in 0..world.width() {
+            let cell = cell(x, y);
+            if world.get(x, y) != cell {
+                world.set(x, y, cell);
+                recorders.edit(x, y, cell);
+            }
+        }
+    }
+}
+
+/// Puts `pattern` in the middle of `world`, refusing one too big for it.
+fn place(world: &mut World, pattern: &Pattern, recorders: &mut Recorders) -> Result<(), String> {
+    let mut placed = world.clone();
+    crate::place_centered(&mut placed, pattern)?;
+    world.set_rule(placed.rule());
+    world.set_meta(pattern.meta().clone());
+    repaint(world, recorders, |x, y| placed.get(x, y));
+    Ok(())
+}
+
+/// Runs the simulation in a window until it is closed.
+///
+/// Left click toggles a cell, space pauses, Esc or Q quits. The panel beside
+/// the grid changes the rule, the speed and the density of the soup its
+/// random button makes, places the built-in patterns and steps or clears the
+/// world, and a pattern file dropped on the window is put in the middle of
+/// it. After `--generations` steps, or once `watch` says so, the world stops
+/// advancing but stays editable. A cell of `breakpoints` changing pauses it.
+pub fn run(
+    world: &mut World,
+    tmp: &mut World,
+    opts: &RunOptions,
+    delay: u64,
+    mut watch: Option<Watch>,
+    mut breakpoints: Breakpoints,
+    recorders: &mut Recorders,
+) -> Result<(), String> {
+    let (generations, every) = (opts.generations, opts.every);
+    let ctx = Context::new(
+        "Conway's Game of Life",
+        world.width() as c_int * CELL_SIZE + PANEL,
+        (world.height() as c_int * CELL_SIZE).max(PANEL_HEIGHT),
+    )?;
+
+    recorders.frame(world, 0);
+
+    let mut panel = Panel {
+        density: opts.world.random.unwrap_or(0.3),
+        pattern: 0,
+        patterns: library::names().collect(),
+        message: "drop a pattern file on the window to load it".to_string(),
+    };
+    let mut delay = delay;
+    // drawn from only once the random button is pressed
+    let mut rng: Option<Rng> = None;
+    let mut generation = 0;
+    let mut stopped = false;
+    let mut paused = false;
+    let mut step = false;
+    let mut sliding = false;
+    let mut changes = Changes::default();
+    let mut shown = None;
+    let mut timings = Timings::default();
+    let mut stepped = None;
+    // an unchanging world needs no stepping and hardly any redrawing
+    let mut idle = false;
+    let mut last_step = unsafe { SDL_GetTicks() };
+    loop {
+        if interrupt::snapshot_requested() {
+            let (rle, png) = interrupt::snapshot(world, generation)?;
+            log::info("run", &format!("saved a snapshot to {} and {}", rle, png));
+        }
+        let (left, _) = Panel::origin(&ctx);
+        let layout = Layout::fit(world, (left, ctx.output_size().1));
+
+        let mut event = SdlEvent { padding: [0; 56] };
+        while unsafe { SDL_PollEvent(&mut event) } != 0 {
+            let mut pressed = None;
+            match unsafe { event.kind } {
+                SDL_QUIT => return Ok(()),
+                SDL_KEYDOWN => match unsafe { event.key.keysym.sym } {
+                    SDLK_ESCAPE | SDLK_Q => return Ok(()),
+                    SDLK_SPACE => paused = !paused,
+                    _ => {}
+                },
+                SDL_MOUSEBUTTONDOWN => {
+                    let button = unsafe { event.button };
+                    if button.button == SDL_BUTTON_LEFT {
+                        let (px, py) = ctx.to_pixels(button.x, button.y);
+                        if px >= left {
+                            pressed = Panel::control_at(&ctx, px, py);
+                            sliding = pressed.is_some_and(|(c, _)| c == Control::Density);
+                        } else if let Some((x, y)) = layout.cell_at(world, px, py) {
+                            world.toggle(x, y);
+                            recorders.edit(x, y, world.get(x, y));
+                            idle = false;
+                        }
+                    }
+                }
+                SDL_MOUSEMOTION => {
+                    let motion = unsafe { event.motion };
+                    if sliding && motion.state & SDL_BUTTON_LMASK != 0 {
+                        let (px, _) = ctx.to_pixels(motion.x, motion.y);
+                        pressed = Some((Control::Density, Panel::slid(&ctx, px)));
+                    }
+                }
+                SDL_MOUSEBUTTONUP => sliding = false,
+                SDL_DROPFILE => {
+                    let file = unsafe { event.drop.file };
+                    let path = unsafe { CStr::from_ptr(file) }
+                        .to_string_lossy()
+                        .into_owned();
+                    unsafe { SDL_free(file as *mut c_void) };
+                    let loaded = Pattern::load_within(&path, &memory::fits(opts.world.max_mem))
+                        .and_then(|pattern| place(world, &pattern, recorders));
+                    panel.message = match loaded {
+                        Ok(()) => format!("placed {}", path.rsplit('/').next().unwrap_or(&path)),
+                        Err(e) => {
+                            log::warn("window", &e);
+                            e
+                        }
+                    };
+                    idle = false;
+                }
+                _ => {}
+            }
+            let (control, across) = match pressed {
+                Some(pressed) => pressed,
+                None => continue,
+            };
+            let rule = rule_name(world.rule());
+            let names = RULES.len();
+            match control {
+                Control::RulePrev | Control::RuleNext => {
+                    let i = match (control, rule) {
+                        (Control::RulePrev, Some(i)) => (i + names - 1) % names,
+                        (Control::RulePrev, None) => names - 1,
+                        (_, Some(i)) => (i + 1) % names,
+                        (_, None) => 0,
+                    };
+                    world.set_rule(Rule::parse(RULES[i])?);
+                    idle = false;
+                }
+                Control::Slower => {
+                    delay = SPEEDS.iter().copied().find(|&d| d > delay).unwrap_or(delay)
+                }
+                Control::Faster => {
+                    delay = SPEEDS
+                        .iter()
+                        .copied()
+                        .rev()
+                        .find(|&d| d < delay)
+                        .unwrap_or(delay)
+                }
+                Control::Density => panel.density = (across * 100.0).round() / 100.0,
+                Control::Randomize => {
+                    let rng = rng.get_or_insert_with(|| crate::seeded_rng(opts.world.seed));
+                    let mut soup = world.clone();
+                    opts.world.random_mode.fill(&mut soup, panel.density, rng);
+                    repaint(world, recorders, |x, y| soup.get(x, y));
+                    idle = false;
+                }
+                Control::PatternPrev => {
+                    panel.pattern =
+                        (panel.pattern + panel.patterns.len() - 1) % panel.patterns.len()
+                }
+                Control::PatternNext => panel.pattern = (panel.pattern + 1) % panel.patterns.len(),
+                Control::Place => {
+                    let name = panel.patterns[panel.pattern];
+                    let placed = match library::get(name) {
+                        Some(pattern) => place(world, &pattern, recorders),
+                        None => Err(format!("no built-in pattern {}", name)),
+                    };
+                    panel.message = match placed {
+                        Ok(()) => format!("placed the {}", name),
+                        Err(e) => e,
+                    };
+                    idle = false;
+                }
+                Control::Pause => paused = !paused,
+                Control::Step => {
+                    paused = true;
+                    step = true;
+                    idle = false;
+                }
+                Control::Clear => {
+                    repaint(world, recorders, |_, _| Cell::Dead);
+                    idle = false;
+                }
+            }
+        }
+
+        let now = unsafe { SDL_GetTicks() };
+        let running = (!paused || step) && !stopped && !idle && generation < generations;
+        if running && (step || u64::from(now - last_step) >= delay) {
+            let began = Instant::now();
+            // a frame shows every `every` generations, or what is left of
+            // them, and a step from the panel one
+            let steps = if step {
+                1
+            } else {
+                every.min(generations - generation)
+            };
+            for _ in 0..steps {
+                generation += 1;
+                changes = world.advance(tmp);
+                recorders.generation(world, changes);
+                if !changes.any() {
+                    idle = true;
+                    break;
+                }
+                if let Some(reason) = watch.as_mut().and_then(|w| w.check(world, generation)) {
+                    println!("{}", reason);
+                    stopped = true;
+                    break;
+                }
+                if let Some(reason) = breakpoints.check(world, generation) {
+                    println!("{}, paused", reason);
+                    paused = true;
+                    break;
+                }
+            }
+            last_step = now;
+            recorders.frame(world, generation);
+            stepped = Some(began.elapsed());
+        }
+        step = false;
+
+        let hud = (generation, world.population(), changes);
+        if shown != Some(hud) {
+            let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+            let (simulate, render) = timings.rolling();
+            ctx.set_title(&format!(
+                "Conway's Game of Life - generation {}, population {}, +{} -{}, {:.1} fps, \
+                 simulate {:.2}ms ({:.2} avg), render {:.2}ms ({:.2} avg)",
+                hud.0,
+                hud.1,
+                changes.births,
+                changes.deaths,
+                timings.fps(),
+                ms(timings.last.0),
+                ms(simulate),
+                ms(timings.last.1),
+                ms(render)
+            ));
+            shown = Some(hud);
+        }
+        let began = Instant::now();
+        draw(&ctx, world, &layout, &opts.style);
+        draw_panel(&ctx, world, &panel, delay, paused);
+        unsafe { SDL_RenderPresent(ctx.renderer) };
+        // only frames with a step in them count, the rest just redraw
+        if let Some(simulate) = stepped.take() {
+            timings.frame(simulate, began.elapsed(), Duration::ZERO, false);
+        }
+        // vsync normally paces us, but not every driver honors it
+        unsafe { SDL_Delay(if idle { 30 } else { 1 }) };
+    }
+}
@@ -0,0 +1,261 @@
+//! Soup searching: many small random soups, each run until it settles, with
+//! a census of what they leave behind or a leaderboard of the longest lived.
+//!
+//! Soup `i` of a search with seed `S` is the first soup of the search with
+//! seed `S + i`, so anything interesting can be looked at again on its own.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use conway::census::{self, Spaceship};
+use conway::{Pattern, Rng, World};
+
+use crate::cli::SearchOptions;
+use crate::pool;
+use crate::progress::Progress;
+use crate::stop::{self, Growth, Until, Watch};
+
+/// What one soup left behind.
+struct Soup {
+    index: u64,
+    /// When it was found to have settled, if it did.
+    settled: Option<u64>,
+    /// Whether it was given up on for seeming to grow without bound.
+    explosive: bool,
+    /// How many generations it was run for.
+    ran: u64,
+    /// The soup itself, as it started.
+    start: Pattern,
+    census: Vec<(&'static str, usize)>,
+    /// The census by apgcode, with `--apgcodes`.
+    apgcodes: Vec<(String, usize)>,
+    ships: Vec<Spaceship>,
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn run_soup(opts: &SearchOptions, seed: u64, index: u64) -> Result<Soup, String> {
+    let size = opts.soup_size;
+    let mut rng = Rng::new(seed.wrapping_add(index));
+    let random: Vec<bool> = (0..size * size).map(|_| rng.chance(opts.density)).collect();
+
+    let mut start = Pattern::new(size, size);
+    start.set_rule(Some(opts.rule));
+    for y in 0..size {
+        for x in 0..size {
+            // every cell copies the first of the cells the symmetry ties it to
+            let (sx, sy) = opts
+                .symmetry
+                .images(x, y, size)
+                .into_iter()
+                .min_by_key(|&(x, y)| (y, x))
+                .unwrap_or((x, y));
+            start.set(x, y, random[sy * size + sx]);
+        }
+    }
+    let mut world = World::new(opts.width, opts.height)?;
+    world.set_rule(opts.rule);
+    world.stamp(&start, (opts.width - size) / 2, (opts.height - size) / 2);
+
+    // a glider comes back to where it started after 4 times the common
+    // multiple of the sides, so looking that far back lets soups that
+    // threw one off settle too
+    let window = 4 * opts.width / gcd(opts.width, opts.height) * opts.height;
+    let mut watch = Watch::new(Until::Stable, &world, window);
+    let mut tmp = world.clone();
+    let mut growth = Growth::new();
+    let (mut settled, mut explosive, mut ran) = (None, false, 0);
+    for generation in 1..=opts.max_generations {
+        ran = generation;
+        let changes = world.advance(&mut tmp);
+        if !changes.any() || watch.check(&world, generation).is_some() {
+            settled = Some(generation);
+            break;
+        }
+        // the long-lived soups a methuselah search is after grow for a long
+        // time too, so it lets them run to the end
+        if !opts.methuselah && growth.check(&world, generation).is_some() {
+            explosive = true;
+            break;
+        }
+    }
+    // a methuselah search only cares how long the soups took
+    let (census, ships) = if opts.methuselah {
+        (Vec::new(), Vec::new())
+    } else {
+        (
+            census::census(&world),
+            census::spaceships(&world, stop::DEFAULT_WINDOW),
+        )
+    };
+    let apgcodes = if opts.apgcodes {
+        census::apgcodes(&world)
+    } else {
+        Vec::new()
+    };
+    Ok(Soup {
+        index,
+        settled,
+        explosive,
+        ran,
+        start: start.crop(),
+        census,
+        apgcodes,
+        ships,
+    })
+}
+
+fn count<K: PartialEq>(counts: &mut Vec<(K, usize)>, key: K, n: usize) {
+    match counts.iter_mut().find(|c| c.0 == key) {
+        Some(count) => count.1 += n,
+        None => counts.push((key, n)),
+    }
+}
+
+fn ship_kind(ship: &Spaceship) -> String {
+    format!("{} ({})", ship.name.unwrap_or("spaceship"), ship.speed())
+}
+
+fn join<K: std::fmt::Display>(counts: &[(K, usize)]) -> String {
+    let parts: Vec<_> = counts
+        .iter()
+        .map(|(kind, n)| format!("{} {}", n, kind))
+        .collect();
+    parts.join(", ")
+}
+
+pub fn run(opts: &SearchOptions, seed: u64) -> Result<(), String> {
+    // soups are handed out one at a time, so a thread stuck on a long-lived
+    // one doesn't hold up the others
+    let progress = Mutex::new(opts.progress.then(|| Progress::new(opts.soups, "soups")));
+    let area = (opts.width * opts.height) as u64;
+    let (done, cells) = (AtomicU64::new(0), AtomicU64::new(0));
+    let soups = pool::run(opts.soups as usize, opts.threads, |index| {
+        let soup = run_soup(opts, seed, index as u64)?;
+        let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+        let cells = cells.fetch_add(soup.ran * area, Ordering::Relaxed) + soup.ran * area;
+        // another thread drawing it will do
+        if let Ok(mut progress) = progress.try_lock() {
+            if let Some(progress) = progress.as_mut() {
+                progress.update(done, cells);
+            }
+        }
+        Ok(soup)
+    });
+    if let Some(progress) = progress.into_inner().unwrap().as_mut() {
+        progress.finish(done.into_inner(), cells.into_inner());
+    }
+    let soups = soups?;
+
+    let settled: Vec<u64> = soups.iter().filter_map(|s| s.settled).collect();
+    let mut objects = Vec::new();
+    let mut codes = Vec::new();
+    let mut ships = Vec::new();
+    for soup in &soups {
+        for &(name, n) in &soup.census {
+            count(&mut objects, name, n);
+        }
+        for (code, n) in &soup.apgcodes {
+            count(&mut codes, code.as_str(), *n);
+        }
+        for ship in &soup.ships {
+            count(&mut ships, ship_kind(ship), 1);
+        }
+    }
+    objects.sort_by_key(|&(name, n)| (name == "other", std::cmp::Reverse(n), name));
+    codes.sort_by_key(|&(code, n)| (code == "other", std::cmp::Reverse(n), code));
+    ships.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+
+    println!(
+        "soups        {} of {}x{} at density {}, symmetry {}, rule {}, seed {}",
+        soups.len(),
+        opts.soup_size,
+        opts.soup_size,
+        opts.density,
+        opts.symmetry.name(),
+        opts.rule,
+        seed
+    );
+    if !settled.is_empty() {
+        println!(
+            "settled      {} after {} generations on average",
+            settled.len(),
+            settled.iter().sum::<u64>() / settled.len() as u64
+        );
+    }
+    let explosive = soups.iter().filter(|s| s.explosive).count();
+    if explosive > 0 {
+        println!("explosive    {} seemed to grow without bound", explosive);
+    }
+    if settled.len() + explosive < soups.len() {
+        println!(
+            "unsettled    {} still changing after {} generations",
+            soups.len() - settled.len() - explosive,
+            opts.max_generations
+        );
+    }
+    if let Some(longest) = soups
+        .iter()
+        .filter(|s| s.settled.is_some())
+        .max_by_key(|s| s.settled)
+    {
+        println!(
+            "longest      soup {} (seed {}), {} generations",
+            longest.index,
+            seed.wrapping_add(longest.index),
+            longest.settled.unwrap_or(0)
+        );
+    }
+    if !objects.is_empty() {
+        println!("census       {}", join(&objects));
+    }
+    if !codes.is_empty() {
+        println!("apgcodes     {}", join(&codes));
+    }
+    if !ships.is_empty() {
+        println!("spaceships   {}", join(&ships));
+    }
+    // gliders come out of most soups, anything else that moves is worth a look
+    for soup in &soups {
+        let mut rare = Vec::new();
+        for ship in soup.ships.iter().filter(|s| s.name != Some("glider")) {
+            count(&mut rare, ship_kind(ship), 1);
+        }
+        if !rare.is_empty() {
+            println!(
+                "rare         soup {} (seed {}): {}",
+                soup.index,
+                seed.wrapping_add(soup.index),
+                join(&rare)
+            );
+        }
+    }
+
+    let mut longest: Vec<&Soup> = soups.iter().filter(|s| s.settled.is_some()).collect();
+    longest.sort_by_key(|s| std::cmp::Reverse(s.settled));
+    for (rank, soup) in longest.iter().take(opts.top).enumerate() {
+        let soup_seed = seed.wrapping_add(soup.index);
+        println!(
+            "methuselah   #{} soup {} (seed {}), {} generations, {} cells",
+            rank + 1,
+            soup.index,
+            soup_seed,
+            soup.settled.unwrap_or(0),
+            soup.start.population()
+        );
+        print!("{}", soup.start.to_rle());
+        if let Some(dir) = &opts.save {
+            let path =
+                Path::new(dir).join(format!("methuselah-{}-seed-{}.rle", rank + 1, soup_seed));
+            soup.start.save(&path.to_string_lossy())?;
+        }
+    }
+    Ok(())
+}
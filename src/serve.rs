@@ -0,0 +1,994 @@
+//! A small HTTP server that streams the simulation to browsers and lets
+//! them steer it.
+//!
+//! `/` is a page that draws the frames and takes clicks and keys. There are
+//! two ways to follow the run itself:
+//!
+//! - `/stream` gives server-sent events: `world` with the size and rule,
+//!   `state` with whether the run is paused and its delay, one unnamed event
+//!   per frame with the generation as its id, and `stopped` if the run ends.
+//! - `/ws` is a WebSocket that sends the same events as JSON objects with
+//!   the event's name as their one key, or `frame` and `generation` for
+//!   frames, and takes the commands `Control::parse` knows. Everyone
+//!   connected steers the same run.
+//!
+//! `/metrics` has counters for Prometheus to scrape, and a REST API lets
+//! other programs look at and change the world:
+//!
+//! - `GET /state` gives the generation, population, whether the run is
+//!   paused, its delay, size, rule and live cells as JSON, or the world as
+//!   RLE with `?format=rle`;
+//! - `POST /cells` sets the cells given as `X Y live|dead` lines;
+//! - `POST /place?x=X&y=Y` stamps the RLE or plaintext pattern in the body
+//!   with its top left corner there;
+//! - `POST /pause`, `/resume`, `/step` and `/delay?ms=MS` steer the run.
+//!
+//! The `POST` requests answer with the generation, population, whether the
+//! run is paused and its delay once the change is made.
+//!
+//! With `--dbus` the run is also on the session bus as `org.conway.Life`,
+//! whose object `/org/conway/Life` has the methods `Pause`, `Resume`,
+//! `Step`, `GetPopulation` and `SetCell(x, y, live)`.
+//!
+//! Every connection gets a thread of its own, and viewers joining late start
+//! from the latest frame.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use conway::{Cell, Changes, Pattern, World};
+
+use crate::cli::{OutputFormat, ServeOptions};
+#[cfg(unix)]
+use crate::dbus;
+use crate::interrupt;
+use crate::jsonl;
+use crate::log;
+use crate::memory;
+use crate::prometheus::Metrics;
+use crate::replay::Recorder;
+use crate::stop::{self, Watch};
+use crate::ws;
+
+const PAGE: &str = include_str!("../web/serve.html");
+
+/// Frames a viewer may fall behind by before it misses some.
+const BACKLOG: usize = 16;
+/// How often a paused server looks for a SIGUSR1 asking for a snapshot.
+const SNAPSHOT_POLL: Duration = Duration::from_millis(250);
+
+/// Something the run tells its viewers.
+#[derive(Clone)]
+struct Event {
+    /// `world`, `state`, `frame` or `stopped`, or `error` for a command that
+    /// went wrong.
+    name: &'static str,
+    /// The generation, for frames.
+    id: Option<u64>,
+    data: String,
+    /// Whether `data` is JSON already, rather than text.
+    json: bool,
+}
+
+impl Event {
+    /// In the `text/event-stream` format.
+    fn sse(&self) -> Vec<u8> {
+        let mut out = String::new();
+        if self.name != "frame" {
+            out.push_str(&format!("event: {}\n", self.name));
+        }
+        if let Some(id) = self.id {
+            out.push_str(&format!("id: {}\n", id));
+        }
+        for line in self.data.lines() {
+            out.push_str("data: ");
+            out.push_str(line);
+            out.push('\n');
+        }
+        out.push('\n');
+        out.into_bytes()
+    }
+
+    /// As a WebSocket text message.
+    fn websocket(&self) -> Vec<u8> {
+        let data = if self.json {
+            self.data.clone()
+        } else {
+            jsonl::string(&self.data)
+        };
+        let message = match self.id {
+            Some(id) => format!("{{\"{}\":{},\"generation\":{}}}", self.name, data, id),
+            None => format!("{{\"{}\":{}}}", self.name, data),
+        };
+        ws::text(&message)
+    }
+}
+
+/// Someone following the run, with how events are written for them.
+struct Viewer {
+    sender: SyncSender<Vec<u8>>,
+    encode: fn(&Event) -> Vec<u8>,
+}
+
+/// What the simulation hands to the connections.
+struct Broadcast {
+    /// The latest event of each kind that a new viewer needs to catch up.
+    world: Event,
+    state: Event,
+    frame: Option<Event>,
+    stopped: Option<Event>,
+    viewers: Vec<Viewer>,
+}
+
+impl Broadcast {
+    fn send(&mut self, event: Event) {
+        // a viewer too far behind skips the event rather than holding up the
+        // others, and one that left is forgotten
+        self.viewers.retain(
+            |viewer| match viewer.sender.try_send((viewer.encode)(&event)) {
+                Ok(()) | Err(TrySendError::Full(_)) => true,
+                Err(TrySendError::Disconnected(_)) => false,
+            },
+        );
+        match event.name {
+            "state" => self.state = event,
+            // a frame after the run stopped means it was poked back to life
+            "frame" => {
+                self.frame = Some(event);
+                self.stopped = None;
+            }
+            "stopped" => self.stopped = Some(event),
+            _ => {}
+        }
+    }
+
+    /// Starts sending events to `sender`, beginning with the ones needed to
+    /// catch up.
+    fn join(&mut self, sender: SyncSender<Vec<u8>>, encode: fn(&Event) -> Vec<u8>) {
+        let current = [Some(&self.world), Some(&self.state)];
+        let latest = [self.frame.as_ref(), self.stopped.as_ref()];
+        for event in current.iter().chain(&latest).flatten() {
+            // fresh channels have room for these few
+            let _ = sender.try_send(encode(event));
+        }
+        self.viewers.push(Viewer { sender, encode });
+    }
+}
+
+/// A change to the run, from a WebSocket client or an HTTP request.
+#[derive(Clone, Debug)]
+enum Control {
+    Pause,
+    Resume,
+    /// Advances one generation and pauses.
+    Step,
+    Cells(Vec<(usize, usize, Cell)>),
+    Toggle(usize, usize),
+    /// Stamps a pattern's live cells with its top left corner at `x`, `y`.
+    Place(usize, usize, Pattern),
+    Delay(u64),
+}
+
+/// What an HTTP request waits for once its control, if any, is applied.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Reply {
+    /// Generation, population and whether the run is paused, as JSON.
+    Status,
+    /// That, the size, rule and live cells.
+    Json,
+    Rle,
+    /// Just the population.
+    Population,
+}
+
+struct Request {
+    control: Option<Control>,
+    reply: Option<(Reply, Sender<String>)>,
+}
+
+fn parse_cell(x: &str, y: &str, width: usize, height: usize) -> Result<(usize, usize), String> {
+    match (x.parse::<usize>(), y.parse::<usize>()) {
+        (Ok(x), Ok(y)) if x < width && y < height => Ok((x, y)),
+        _ => Err(format!(
+            "invalid cell '{} {}', expected X below {} and Y below {}",
+            x, y, width, height
+        )),
+    }
+}
+
+fn parse_state(state: &str) -> Result<Cell, String> {
+    match state {
+        "live" | "1" => Ok(Cell::Live),
+        "dead" | "0" => Ok(Cell::Dead),
+        _ => Err(format!(
+            "invalid cell state '{}', expected live or dead",
+            state
+        )),
+    }
+}
+
+/// `X Y live|dead` on each line, as `POST /cells` takes them.
+fn parse_cells(body: &str, width: usize, height: usize) -> Result<Control, String> {
+    let mut cells = Vec::new();
+    for line in body.lines().filter(|line| !line.trim().is_empty()) {
+        match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [x, y, state] => {
+                let (x, y) = parse_cell(x, y, width, height)?;
+                cells.push((x, y, parse_state(state)?));
+            }
+            _ => {
+                return Err(format!(
+                    "invalid line '{}', expected X Y live|dead",
+                    line.trim()
+                ))
+            }
+        }
+    }
+    Ok(Control::Cells(cells))
+}
+
+fn parse_delay(ms: &str) -> Result<Control, String> {
+    ms.parse()
+        .map(Control::Delay)
+        .map_err(|_| format!("invalid delay '{}', expected milliseconds", ms))
+}
+
+impl Control {
+    /// `pause`, `resume`, `step`, `set X Y live|dead`, `toggle X Y` or
+    /// `delay MS`, for a world of the given size.
+    fn parse(s: &str, width: usize, height: usize) -> Result<Control, String> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        match words.as_slice() {
+            ["pause"] => Ok(Control::Pause),
+            ["resume"] => Ok(Control::Resume),
+            ["step"] => Ok(Control::Step),
+            ["set", x, y, state] => {
+                let (x, y) = parse_cell(x, y, width, height)?;
+                Ok(Control::Cells(vec![(x, y, parse_state(state)?)]))
+            }
+            ["toggle", x, y] => parse_cell(x, y, width, height).map(|(x, y)| Control::Toggle(x, y)),
+            ["delay", ms] => parse_delay(ms),
+            _ => Err(format!(
+                "unknown command '{}', expected pause, resume, step, set X Y live|dead, \
+                 toggle X Y or delay MS",
+                s.trim()
+            )),
+        }
+    }
+}
+
+fn respond(mut stream: TcpStream, status: &str, kind: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        kind,
+        body.len(),
+        body
+    )
+}
+
+fn stream_events(mut stream: TcpStream, broadcast: &Mutex<Broadcast>) -> io::Result<()> {
+    stream.write_all(
+        b"HTTP/1.1 200 OK\r\n\
+          Content-Type: text/event-stream\r\n\
+          Cache-Control: no-cache\r\n\
+          Access-Control-Allow-Origin: *\r\n\r\n",
+    )?;
+    let (sender, receiver) = mpsc::sync_channel(BACKLOG);
+    broadcast.lock().unwrap().join(sender, Event::sse);
+    for event in receiver {
+        stream.write_all(&event)?;
+    }
+    Ok(())
+}
+
+/// Everything a connection needs from the run.
+struct Shared {
+    broadcast: Mutex<Broadcast>,
+    metrics: Mutex<Metrics>,
+    width: usize,
+    height: usize,
+    max_mem: Option<u64>,
+}
+
+fn error(message: &str) -> Vec<u8> {
+    let event = Event {
+        name: "error",
+        id: None,
+        data: message.to_string(),
+        json: false,
+    };
+    event.websocket()
+}
+
+fn websocket(
+    reader: BufReader<TcpStream>,
+    key: &str,
+    shared: &Shared,
+    controls: Sender<Request>,
+) -> io::Result<()> {
+    let mut stream = reader.get_ref().try_clone()?;
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\n\
+         Upgrade: websocket\r\n\
+         Connection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n",
+        ws::accept(key)
+    )?;
+    // answers to this client go through the same channel as the broadcast,
+    // so one writer keeps the frames whole, and an empty one ends it
+    let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(BACKLOG);
+    let replies = sender.clone();
+    shared
+        .broadcast
+        .lock()
+        .unwrap()
+        .join(sender, Event::websocket);
+    let writer = thread::spawn(move || {
+        for bytes in receiver {
+            if bytes.is_empty() || stream.write_all(&bytes).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut reader = ws::Reader::new(reader);
+    loop {
+        let reply = match reader.read() {
+            Ok(ws::Message::Text(text)) => match Control::parse(&text, shared.width, shared.height)
+            {
+                Ok(control) => {
+                    let _ = controls.send(Request {
+                        control: Some(control),
+                        reply: None,
+                    });
+                    continue;
+                }
+                Err(e) => error(&e),
+            },
+            Ok(ws::Message::Binary) => error("commands are text messages"),
+            Ok(ws::Message::Ping(data)) => ws::frame(ws::PONG, &data),
+            Ok(ws::Message::Close) | Err(_) => break,
+        };
+        if replies.send(reply).is_err() {
+            break;
+        }
+    }
+    let _ = replies.send(ws::frame(ws::CLOSE, &[]));
+    let _ = replies.send(Vec::new());
+    let _ = writer.join();
+    Ok(())
+}
+
+/// The value of `name` in a query string like `x=1&y=2`.
+fn param<'q>(query: &'q str, name: &str) -> Option<&'q str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|&(key, _)| key == name)
+        .map(|(_, value)| value)
+}
+
+/// The control a REST request asks for and what it wants back.
+fn rest(
+    method: &str,
+    path: &str,
+    query: &str,
+    body: &str,
+    shared: &Shared,
+) -> Result<(Option<Control>, Reply), String> {
+    let (width, height) = (shared.width, shared.height);
+    let control = match (method, path) {
+        ("GET", "/state") => {
+            return match param(query, "format").unwrap_or("json") {
+                "json" => Ok((None, Reply::Json)),
+                "rle" => Ok((None, Reply::Rle)),
+                other => Err(format!("invalid format '{}', expected json or rle", other)),
+            }
+        }
+        ("POST", "/pause") => Control::Pause,
+        ("POST", "/resume") => Control::Resume,
+        ("POST", "/step") => Control::Step,
+        ("POST", "/delay") => parse_delay(param(query, "ms").unwrap_or(body.trim()))?,
+        ("POST", "/cells") => parse_cells(body, width, height)?,
+        ("POST", "/place") => {
+            let (x, y) = parse_cell(
+                param(query, "x").unwrap_or("0"),
+                param(query, "y").unwrap_or("0"),
+                width,
+                height,
+            )?;
+            // the size is the body's word, so it is checked before the
+            // pattern is made
+            let fits = |w: usize, h: usize| {
+                if w > width || h > height {
+                    return Err(format!(
+                        "a {}x{} pattern is bigger than the {}x{} world",
+                        w, h, width, height
+                    ));
+                }
+                memory::check(w, h, shared.max_mem)
+            };
+            let pattern = Pattern::parse_within(body, &fits)?;
+            Control::Place(x, y, pattern)
+        }
+        _ => unreachable!("rest() is only called for the API's paths"),
+    };
+    Ok((Some(control), Reply::Status))
+}
+
+/// Paths of the REST API, with the method each takes.
+const API: [(&str, &str); 7] = [
+    ("GET", "/state"),
+    ("POST", "/pause"),
+    ("POST", "/resume"),
+    ("POST", "/step"),
+    ("POST", "/delay"),
+    ("POST", "/cells"),
+    ("POST", "/place"),
+];
+
+/// Longest request body taken, which is plenty for a pattern.
+const MAX_BODY: usize = 1 << 20;
+
+/// Longest request line and headers taken together.
+const MAX_HEAD: u64 = 16 << 10;
+
+fn handle(stream: TcpStream, shared: &Shared, controls: Sender<Request>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    // through a limit so a client can't send an endless line
+    let mut head = (&mut reader).take(MAX_HEAD);
+    let mut request = String::new();
+    head.read_line(&mut request)?;
+    let (mut key, mut length) = (None, 0);
+    let mut header = String::new();
+    while head.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        // the WebSocket key and the body's length are all that matter
+        if let Some((name, value)) = header.split_once(':') {
+            let name = name.trim();
+            if name.eq_ignore_ascii_case("sec-websocket-key") {
+                key = Some(value.trim().to_string());
+            } else if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().unwrap_or(0);
+            }
+        }
+        header.clear();
+    }
+    if head.limit() == 0 {
+        return respond(
+            stream,
+            "431 Request Header Fields Too Large",
+            "text/plain",
+            "headers too long\n",
+        );
+    }
+    let mut parts = request.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let target = parts.next().unwrap_or("");
+    if log::enabled(log::Level::Debug) {
+        let peer = stream
+            .peer_addr()
+            .map_or_else(|_| "?".to_string(), |a| a.to_string());
+        log::debug("serve", &format!("{} {} from {}", method, target, peer));
+    }
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    if API.iter().any(|&(_, p)| p == path) {
+        if !API.contains(&(method, path)) {
+            let message = format!(
+                "{} takes {}\n",
+                path,
+                if method == "GET" { "POST" } else { "GET" }
+            );
+            return respond(stream, "405 Method Not Allowed", "text/plain", &message);
+        }
+        if length > MAX_BODY {
+            return respond(
+                stream,
+                "413 Payload Too Large",
+                "text/plain",
+                "body too long\n",
+            );
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+        let body = String::from_utf8_lossy(&body);
+        let (control, reply) = match rest(method, path, query, &body, shared) {
+            Ok(request) => request,
+            Err(e) => return respond(stream, "400 Bad Request", "text/plain", &format!("{}\n", e)),
+        };
+        let (sender, receiver) = mpsc::channel();
+        let _ = controls.send(Request {
+            control,
+            reply: Some((reply, sender)),
+        });
+        return match (receiver.recv(), reply) {
+            (Ok(answer), Reply::Rle) => respond(stream, "200 OK", "text/plain", &answer),
+            (Ok(answer), _) => respond(stream, "200 OK", "application/json", &answer),
+            (Err(_), _) => respond(
+                stream,
+                "503 Service Unavailable",
+                "text/plain",
+                "the run is gone\n",
+            ),
+        };
+    }
+    match (method, path, key) {
+        ("GET", "/", _) => respond(stream, "200 OK", "text/html; charset=utf-8", PAGE),
+        ("GET", "/stream", _) => stream_events(stream, &shared.broadcast),
+        ("GET", "/metrics", _) => {
+            let viewers = shared.broadcast.lock().unwrap().viewers.len();
+            let metrics = shared.metrics.lock().unwrap().render(viewers);
+            respond(stream, "200 OK", "text/plain; version=0.0.4", &metrics)
+        }
+        ("GET", "/ws", Some(key)) => websocket(reader, &key, shared, controls),
+        ("GET", "/ws", None) => respond(
+            stream,
+            "400 Bad Request",
+            "text/plain",
+            "expected a WebSocket handshake\n",
+        ),
+        ("GET", _, _) => respond(stream, "404 Not Found", "text/plain", "not found\n"),
+        _ => respond(
+            stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            "only GET is supported here\n",
+        ),
+    }
+}
+
+const DBUS_NAME: &str = "org.conway.Life";
+const DBUS_PATH: &str = "/org/conway/Life";
+
+/// What `--dbus` serves, for tools like `busctl` and `d-feet` to show.
+const INTROSPECTION: &str = r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+<node>
+  <interface name="org.conway.Life">
+    <method name="Pause"/>
+    <method name="Resume"/>
+    <method name="Step"/>
+    <method name="GetPopulation">
+      <arg name="population" type="t" direction="out"/>
+    </method>
+    <method name="SetCell">
+      <arg name="x" type="u" direction="in"/>
+      <arg name="y" type="u" direction="in"/>
+      <arg name="live" type="b" direction="in"/>
+    </method>
+  </interface>
+  <interface name="org.freedesktop.DBus.Introspectable">
+    <method name="Introspect">
+      <arg name="xml" type="s" direction="out"/>
+    </method>
+  </interface>
+</node>
+"#;
+
+/// The answer to a method call on the bus, or the error name and message
+/// to answer it with.
+#[cfg(unix)]
+fn dbus_answer(
+    call: &dbus::Message,
+    shared: &Shared,
+    controls: &Sender<Request>,
+) -> Result<Vec<dbus::Value>, (&'static str, String)> {
+    use dbus::Value;
+
+    if call.path.as_deref() != Some(DBUS_PATH) {
+        let path = call.path.clone().unwrap_or_default();
+        return Err((dbus::UNKNOWN_OBJECT, format!("no object at {}", path)));
+    }
+    let member = call.member.as_deref().unwrap_or("");
+    let args = match &call.body {
+        Some(args) => args.as_slice(),
+        None => {
+            return Err((
+                dbus::INVALID_ARGS,
+                format!("unexpected arguments to {}", member),
+            ))
+        }
+    };
+    let control = match (call.interface.as_deref(), member, args) {
+        (Some("org.freedesktop.DBus.Introspectable"), "Introspect", []) => {
+            return Ok(vec![Value::Str(INTROSPECTION.to_string())]);
+        }
+        (Some("org.freedesktop.DBus.Peer"), "Ping", []) => return Ok(Vec::new()),
+        (Some(DBUS_NAME) | None, "Pause", []) => Some(Control::Pause),
+        (Some(DBUS_NAME) | None, "Resume", []) => Some(Control::Resume),
+        (Some(DBUS_NAME) | None, "Step", []) => Some(Control::Step),
+        (Some(DBUS_NAME) | None, "GetPopulation", []) => None,
+        (Some(DBUS_NAME) | None, "SetCell", [Value::U32(x), Value::U32(y), Value::Bool(live)]) => {
+            let (x, y) = parse_cell(&x.to_string(), &y.to_string(), shared.width, shared.height)
+                .map_err(|e| (dbus::INVALID_ARGS, e))?;
+            let cell = if *live { Cell::Live } else { Cell::Dead };
+            Some(Control::Cells(vec![(x, y, cell)]))
+        }
+        (Some(DBUS_NAME) | None, "SetCell", _) => {
+            let message = "SetCell takes x and y as u and live as b".to_string();
+            return Err((dbus::INVALID_ARGS, message));
+        }
+        _ => {
+            let message = format!("no method {} here", member);
+            return Err((dbus::UNKNOWN_METHOD, message));
+        }
+    };
+    // waiting for the answer means the change has been made when it returns
+    let (sender, receiver) = mpsc::channel();
+    let _ = controls.send(Request {
+        control,
+        reply: Some((Reply::Population, sender)),
+    });
+    match receiver.recv() {
+        Ok(population) if member == "GetPopulation" => {
+            Ok(vec![Value::U64(population.parse().unwrap_or(0))])
+        }
+        Ok(_) => Ok(Vec::new()),
+        Err(_) => Err((dbus::FAILED, "the run is gone".to_string())),
+    }
+}
+
+/// Takes the run's name on the session bus and answers calls to it from a
+/// thread of its own.
+#[cfg(unix)]
+fn dbus_service(shared: Arc<Shared>, controls: Sender<Request>) -> Result<(), String> {
+    let mut connection = dbus::Connection::session()?;
+    connection.request_name(DBUS_NAME)?;
+    log::info("serve", &format!("on the session bus as {}", DBUS_NAME));
+    thread::spawn(move || loop {
+        let call = match connection.read() {
+            Ok(call) if call.is_call() => call,
+            Ok(_) => continue,
+            Err(e) => {
+                log::warn("serve", &format!("lost the session bus: {}", e));
+                return;
+            }
+        };
+        log::debug(
+            "serve",
+            &format!(
+                "D-Bus {} from {}",
+                call.member.as_deref().unwrap_or("?"),
+                call.sender.as_deref().unwrap_or("?")
+            ),
+        );
+        let answer = dbus_answer(&call, &shared, &controls);
+        if !call.wants_reply() {
+            continue;
+        }
+        let sent = match answer {
+            Ok(body) => connection.reply(&call, &body),
+            Err((name, message)) => connection.error(&call, name, &message),
+        };
+        if let Err(e) = sent {
+            log::warn("serve", &format!("lost the session bus: {}", e));
+            return;
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn dbus_service(_: Arc<Shared>, _: Sender<Request>) -> Result<(), String> {
+    Err("--dbus needs a desktop with D-Bus, on Unix".to_string())
+}
+
+/// The simulation and what the viewers have done to it.
+struct Run<'a> {
+    opts: &'a ServeOptions,
+    shared: &'a Shared,
+    world: World,
+    tmp: World,
+    generation: u64,
+    watch: Option<Watch>,
+    /// Whether the run has ended, until someone changes a cell.
+    stopped: bool,
+    paused: bool,
+    delay: u64,
+    replay: Option<Recorder>,
+}
+
+impl<'a> Run<'a> {
+    fn send(&self, event: Event) {
+        self.shared.broadcast.lock().unwrap().send(event);
+    }
+
+    fn state(&self) -> Event {
+        Event {
+            name: "state",
+            id: None,
+            data: format!("{{\"paused\":{},\"delay\":{}}}", self.paused, self.delay),
+            json: true,
+        }
+    }
+
+    fn frame(&self, changes: Changes) -> Event {
+        let (data, json) = match self.opts.output {
+            OutputFormat::Text => (self.world.as_string(), false),
+            OutputFormat::Jsonl => (
+                jsonl::frame(&self.world, self.generation, changes, self.opts.cells),
+                true,
+            ),
+        };
+        Event {
+            name: "frame",
+            id: Some(self.generation),
+            data,
+            json,
+        }
+    }
+
+    fn advance(&mut self) {
+        self.generation += 1;
+        if let Some(replay) = self.replay.as_mut() {
+            replay.advanced();
+        }
+        let started = Instant::now();
+        let changes = self.world.advance(&mut self.tmp);
+        self.shared.metrics.lock().unwrap().step(
+            self.generation,
+            self.world.population(),
+            changes,
+            started.elapsed(),
+        );
+        let generation = self.generation;
+        let world = &self.world;
+        let mut stopped = self.watch.as_mut().and_then(|w| w.check(world, generation));
+        if !changes.any() && stopped.is_none() {
+            stopped = Some(stop::settled(self.world.population(), generation - 1));
+        }
+        self.send(self.frame(changes));
+        if log::enabled(log::Level::Trace) {
+            log::trace(
+                "serve",
+                &format!(
+                    "generation {}: population {}, +{} -{}",
+                    generation,
+                    self.world.population(),
+                    changes.births,
+                    changes.deaths
+                ),
+            );
+        }
+        if let Some(reason) = stopped {
+            log::info("serve", &reason);
+            self.stopped = true;
+            self.send(Event {
+                name: "stopped",
+                id: None,
+                data: reason,
+                json: false,
+            });
+        }
+    }
+
+    /// Catches everything up on cells changed by hand.
+    fn edited(&mut self) {
+        self.shared
+            .metrics
+            .lock()
+            .unwrap()
+            .set_population(self.world.population());
+        // the cycles seen so far are no longer what the world does
+        self.stopped = false;
+        self.watch = self
+            .opts
+            .until
+            .map(|until| Watch::new(until, &self.world, self.opts.max_period));
+        self.send(self.frame(Changes::default()));
+    }
+
+    fn apply(&mut self, control: Control) {
+        log::debug("serve", &format!("{:?}", control));
+        match control {
+            Control::Pause => self.paused = true,
+            Control::Resume => self.paused = false,
+            Control::Step => {
+                self.paused = true;
+                self.advance();
+            }
+            Control::Delay(delay) => self.delay = delay,
+            Control::Cells(cells) => {
+                let mut changed = false;
+                for (x, y, cell) in cells {
+                    // checked against the size as they came in, but the world
+                    // has the last word
+                    match self.world.try_get(x, y) {
+                        Ok(old) if old == cell => continue,
+                        Ok(_) => {}
+                        Err(e) => {
+                            log::warn("serve", &e);
+                            continue;
+                        }
+                    }
+                    self.world.set(x, y, cell);
+                    if let Some(replay) = self.replay.as_mut() {
+                        replay.set(x, y, cell);
+                    }
+                    changed = true;
+                }
+                if changed {
+                    self.edited();
+                }
+                return;
+            }
+            Control::Toggle(x, y) => {
+                let cell = match self.world.try_get(x, y) {
+                    Ok(Cell::Dead) => Cell::Live,
+                    Ok(Cell::Live) => Cell::Dead,
+                    Err(e) => return log::warn("serve", &e),
+                };
+                return self.apply(Control::Cells(vec![(x, y, cell)]));
+            }
+            Control::Place(x, y, pattern) => {
+                // the pattern's live cells, wrapping around the edges as a
+                // stamp does
+                let (width, height) = (self.world.width(), self.world.height());
+                let mut cells = Vec::new();
+                for py in 0..pattern.height() {
+                    for px in 0..pattern.width() {
+                        if pattern.get(px, py) {
+                            cells.push(((x + px) % width, (y + py) % height, Cell::Live));
+                        }
+                    }
+                }
+                return self.apply(Control::Cells(cells));
+            }
+        }
+        self.shared.metrics.lock().unwrap().set_paused(self.paused);
+        self.send(self.state());
+    }
+
+    fn answer(&self, reply: Reply) -> String {
+        let status = format!(
+            "\"generation\":{},\"population\":{},\"paused\":{},\"delay\":{}",
+            self.generation,
+            self.world.population(),
+            self.paused,
+            self.delay
+        );
+        match reply {
+            Reply::Status => format!("{{{}}}\n", status),
+            Reply::Json => {
+                let mut cells = Vec::with_capacity(self.world.population());
+                for y in 0..self.world.height() {
+                    for x in 0..self.world.width() {
+                        if self.world.get(x, y) == Cell::Live {
+                            cells.push(format!("[{},{}]", x, y));
+                        }
+                    }
+                }
+                format!(
+                    "{{{},\"width\":{},\"height\":{},\"rule\":{},\"cells\":[{}]}}\n",
+                    status,
+                    self.world.width(),
+                    self.world.height(),
+                    jsonl::string(&self.world.rule().to_string()),
+                    cells.join(",")
+                )
+            }
+            Reply::Rle => Pattern::from_world(&self.world).to_rle(),
+            Reply::Population => self.world.population().to_string(),
+        }
+    }
+
+    fn running(&self) -> bool {
+        !self.paused && !self.stopped && self.generation < self.opts.generations
+    }
+}
+
+/// Serves the run until interrupted, carrying on after it ends so its last
+/// frame can still be looked at and changed. The cells the viewers change are
+/// recorded into `replay` if given.
+pub fn run(world: World, replay: Option<Recorder>, opts: &ServeOptions) -> Result<(), String> {
+    let listener = TcpListener::bind(&opts.bind)
+        .map_err(|e| format!("cannot listen on {}: {}", opts.bind, e))?;
+    let info = format!(
+        "{{\"width\":{},\"height\":{},\"rule\":{}}}",
+        world.width(),
+        world.height(),
+        jsonl::string(&world.rule().to_string())
+    );
+    let event = |name, data| Event {
+        name,
+        id: None,
+        data,
+        json: true,
+    };
+    let shared = Arc::new(Shared {
+        broadcast: Mutex::new(Broadcast {
+            world: event("world", info),
+            // replaced before anyone can connect
+            state: event("state", String::new()),
+            frame: None,
+            stopped: None,
+            viewers: Vec::new(),
+        }),
+        metrics: Mutex::new(Metrics::new(
+            world.rule(),
+            world.width(),
+            world.height(),
+            world.population(),
+        )),
+        width: world.width(),
+        height: world.height(),
+        max_mem: opts.world.max_mem,
+    });
+
+    let mut run = Run {
+        opts,
+        shared: &shared,
+        tmp: world.clone(),
+        watch: opts
+            .until
+            .map(|until| Watch::new(until, &world, opts.max_period)),
+        world,
+        generation: 0,
+        stopped: false,
+        paused: false,
+        delay: opts.delay,
+        replay,
+    };
+    run.send(run.state());
+    run.send(run.frame(Changes::default()));
+
+    let (controls, received) = mpsc::channel();
+    if opts.dbus {
+        dbus_service(Arc::clone(&shared), controls.clone())?;
+    }
+    let accepting = Arc::clone(&shared);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let shared = Arc::clone(&accepting);
+            let controls = controls.clone();
+            // a viewer that goes away mid-answer is nothing to report
+            thread::spawn(move || handle(stream, &shared, controls).ok());
+        }
+    });
+    log::info("serve", &format!("serving on http://{}/", opts.bind));
+
+    let mut next = Instant::now() + Duration::from_millis(run.delay);
+    loop {
+        if interrupt::snapshot_requested() {
+            let (rle, png) = interrupt::snapshot(&run.world, run.generation)?;
+            log::info("serve", &format!("saved a snapshot to {} and {}", rle, png));
+        }
+        // commands are taken as they come, steps when they are due, and
+        // while paused a look every so often for a snapshot to save
+        let due = if run.running() {
+            next.saturating_duration_since(Instant::now())
+        } else {
+            SNAPSHOT_POLL
+        };
+        let control = received.recv_timeout(due);
+        match control {
+            Ok(request) => {
+                let was_running = run.running();
+                if let Some(control) = request.control {
+                    run.apply(control);
+                }
+                if let Some((reply, sender)) = request.reply {
+                    let _ = sender.send(run.answer(reply));
+                }
+                if run.running() && !was_running {
+                    next = Instant::now() + Duration::from_millis(run.delay);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) if !run.running() => {}
+            Err(RecvTimeoutError::Timeout) => {
+                run.advance();
+                // without catching up on steps missed while busy
+                next = (next + Duration::from_millis(run.delay)).max(Instant::now());
+            }
+            // the listener thread keeps a sender for as long as it runs
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+    }
+}
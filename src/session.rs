@@ -0,0 +1,264 @@
+//! `run --save-session` and `--load-session`: everything about a run in the
+//! terminal, to pick an exploration up where it was left. A session is a JSON
+//! file, gzipped if its name ends in `.gz`, written when the run ends, Ctrl-C
+//! included:
+//!
+//! ```json
+//! {
+//!   "format": "conway session 1",
+//!   "generation": 1200,
+//!   "seed": "42",
+//!   "world": "x = 80, y = 40, rule = B3/S23\n...!\n",
+//!   "fps": null,
+//!   "delay": 500,
+//!   "every": 1,
+//!   "paused": false,
+//!   "renderer": "text",
+//!   "view": { "zoom": "2", "origin": [12.5, 0], "scroll": [0.5, 0] },
+//!   "style": { "live": "O", "dead": ".", "live_color": "green", "dead_color": null,
+//!              "trails": 0, "ghosts": false, "density": false, "footprint": false,
+//!              "diff": false },
+//!   "history": { "span": 40, "populations": [36, 38, 41] }
+//! }
+//! ```
+//!
+//! The world is RLE with its rule, and its walls as the wall state. `seed`
+//! is a string, as a `u64` doesn't fit a JSON number. `history` is what the
+//! `--sparkline` charts, or null, and `paused` is whether `--control-fifo`
+//! had paused the run. Loading a session sets all of these over the flags.
+
+use conway::{Format, Pattern, World};
+
+use crate::cli::RunOptions;
+use crate::gzip;
+use crate::json::Json;
+use crate::jsonl;
+use crate::memory;
+use crate::term::{Color, Footprint, Style, Zoom};
+
+const FORMAT: &str = "conway session 1";
+
+pub struct Session {
+    pub world: World,
+    pub generation: u64,
+    /// The seed of the soup the run started from, if it was random.
+    pub seed: Option<u64>,
+    pub fps: Option<u32>,
+    pub delay: u64,
+    pub every: u64,
+    pub paused: bool,
+    pub renderer: String,
+    pub diff: bool,
+    /// Of which the glyphs, the cell colors, the view, trails, ghosts,
+    /// density and whether there is a footprint are kept.
+    pub style: Style,
+    /// The span of the sparkline and the populations it charts.
+    pub history: Option<(usize, Vec<usize>)>,
+}
+
+impl Session {
+    /// The session of a run of `opts` at `generation`, with the speed and view
+    /// it was started with.
+    pub fn new(world: &World, generation: u64, seed: Option<u64>, opts: &RunOptions) -> Session {
+        Session {
+            world: world.clone(),
+            generation,
+            seed,
+            fps: opts.fps,
+            delay: opts.delay.unwrap_or(500),
+            every: opts.every,
+            paused: false,
+            renderer: opts.renderer.clone(),
+            diff: opts.diff,
+            style: opts.style.clone(),
+            history: None,
+        }
+    }
+
+    /// Sets the settings of the session over those of `opts`.
+    pub fn apply(&self, opts: &mut RunOptions) {
+        opts.fps = self.fps;
+        opts.delay = Some(self.delay);
+        opts.every = self.every;
+        opts.renderer = self.renderer.clone();
+        opts.diff = self.diff;
+        opts.sparkline = self.history.as_ref().map(|&(span, _)| span);
+        let (style, saved) = (&mut opts.style, &self.style);
+        style.live = saved.live.clone();
+        style.dead = saved.dead.clone();
+        style.live_color = saved.live_color;
+        style.dead_color = saved.dead_color;
+        style.zoom = saved.zoom;
+        style.origin = saved.origin;
+        style.scroll = saved.scroll;
+        style.trails = saved.trails;
+        style.ghosts = saved.ghosts;
+        style.density = saved.density;
+        style.footprint = saved.footprint.as_ref().map(|_| Footprint::default());
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let color =
+            |c: Option<Color>| c.map_or("null".to_string(), |c| jsonl::string(&c.to_string()));
+        let pair = |(x, y): (f64, f64)| format!("[{}, {}]", x, y);
+        let style = &self.style;
+        let history = match &self.history {
+            Some((span, populations)) => {
+                let populations: Vec<String> = populations.iter().map(|p| p.to_string()).collect();
+                format!(
+                    "{{ \"span\": {}, \"populations\": [{}] }}",
+                    span,
+                    populations.join(", ")
+                )
+            }
+            None => "null".to_string(),
+        };
+        let text = format!(
+            "{{\n  \"format\": {},\n  \"generation\": {},\n  \"seed\": {},\n  \"world\": {},\n  \
+             \"fps\": {},\n  \"delay\": {},\n  \"every\": {},\n  \"paused\": {},\n  \
+             \"renderer\": {},\n  \
+             \"view\": {{ \"zoom\": {}, \"origin\": {}, \"scroll\": {} }},\n  \
+             \"style\": {{ \"live\": {}, \"dead\": {}, \"live_color\": {}, \"dead_color\": {}, \
+             \"trails\": {}, \"ghosts\": {}, \"density\": {}, \"footprint\": {}, \"diff\": {} }},\n  \
+             \"history\": {}\n}}\n",
+            jsonl::string(FORMAT),
+            self.generation,
+            self.seed
+                .map_or("null".to_string(), |seed| jsonl::string(&seed.to_string())),
+            jsonl::string(&Pattern::from_world(&self.world).to_rle()),
+            self.fps.map_or("null".to_string(), |fps| fps.to_string()),
+            self.delay,
+            self.every,
+            self.paused,
+            jsonl::string(&self.renderer),
+            jsonl::string(&style.zoom.to_string()),
+            pair(style.origin),
+            style.scroll.map_or("null".to_string(), pair),
+            jsonl::string(&style.live),
+            jsonl::string(&style.dead),
+            color(style.live_color),
+            color(style.dead_color),
+            style.trails,
+            style.ghosts,
+            style.density,
+            style.footprint.is_some(),
+            self.diff,
+            history
+        );
+        gzip::write(path, text.into_bytes())
+    }
+
+    /// Reads the session at `path`, refusing a world bigger than `max_mem`
+    /// allows before making it.
+    pub fn load(path: &str, max_mem: Option<u64>) -> Result<Session, String> {
+        let text = gzip::read_to_string(path)?;
+        let json = Json::parse(&text).map_err(|e| format!("{}: {}", path, e))?;
+        let field = |object: &Json, key: &str| -> Result<Json, String> {
+            object
+                .get(key)
+                .cloned()
+                .ok_or_else(|| format!("{}: not a session, '{}' is missing", path, key))
+        };
+        let invalid = |key: &str| format!("{}: invalid session, '{}' is wrong", path, key);
+        if field(&json, "format")?.as_str() != Some(FORMAT) {
+            return Err(format!("{}: not a session in {}", path, FORMAT));
+        }
+        let string = |object: &Json, key: &str| -> Result<String, String> {
+            field(object, key)?
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| invalid(key))
+        };
+        let number = |object: &Json, key: &str| -> Result<u64, String> {
+            field(object, key)?
+                .as_f64()
+                .filter(|n| *n >= 0.0 && n.fract() == 0.0)
+                .map(|n| n as u64)
+                .ok_or_else(|| invalid(key))
+        };
+        let flag = |object: &Json, key: &str| -> Result<bool, String> {
+            field(object, key)?.as_bool().ok_or_else(|| invalid(key))
+        };
+        let color = |object: &Json, key: &str| -> Result<Option<Color>, String> {
+            match field(object, key)? {
+                Json::Null => Ok(None),
+                Json::Str(s) => Color::parse(&s)
+                    .map(Some)
+                    .map_err(|e| format!("{}: {}", path, e)),
+                _ => Err(invalid(key)),
+            }
+        };
+        let pair = |json: &Json, key: &str| -> Result<(f64, f64), String> {
+            match json.as_array() {
+                Some([x, y]) => x.as_f64().zip(y.as_f64()).ok_or_else(|| invalid(key)),
+                _ => Err(invalid(key)),
+            }
+        };
+
+        let rle = string(&json, "world")?;
+        let pattern = Pattern::parse_as_within(&rle, Format::Rle, &memory::fits(max_mem))
+            .map_err(|e| format!("{}: {}", path, e))?;
+        let mut world = World::new(pattern.width(), pattern.height())?;
+        world.stamp(&pattern, 0, 0);
+        if let Some(rule) = pattern.rule() {
+            world.set_rule(rule);
+        }
+        let seed = match field(&json, "seed")? {
+            Json::Null => None,
+            Json::Str(s) => Some(s.parse().map_err(|_| invalid("seed"))?),
+            _ => return Err(invalid("seed")),
+        };
+        let fps = match field(&json, "fps")? {
+            Json::Null => None,
+            _ => Some(number(&json, "fps")? as u32).filter(|&fps| fps > 0),
+        };
+        let view = field(&json, "view")?;
+        let saved = field(&json, "style")?;
+        let mut style = Style {
+            live: string(&saved, "live")?,
+            dead: string(&saved, "dead")?,
+            live_color: color(&saved, "live_color")?,
+            dead_color: color(&saved, "dead_color")?,
+            trails: number(&saved, "trails")?,
+            ghosts: flag(&saved, "ghosts")?,
+            density: flag(&saved, "density")?,
+            zoom: Zoom::parse(&string(&view, "zoom")?).map_err(|e| format!("{}: {}", path, e))?,
+            origin: pair(&field(&view, "origin")?, "origin")?,
+            scroll: match field(&view, "scroll")? {
+                Json::Null => None,
+                scroll => Some(pair(&scroll, "scroll")?),
+            },
+            ..Style::default()
+        };
+        if flag(&saved, "footprint")? {
+            style.footprint = Some(Footprint::default());
+        }
+        let history = match field(&json, "history")? {
+            Json::Null => None,
+            history => {
+                let populations = field(&history, "populations")?
+                    .as_array()
+                    .and_then(|p| {
+                        p.iter()
+                            .map(|p| p.as_f64().map(|p| p as usize))
+                            .collect::<Option<Vec<usize>>>()
+                    })
+                    .ok_or_else(|| invalid("populations"))?;
+                Some((number(&history, "span")?.max(2) as usize, populations))
+            }
+        };
+        Ok(Session {
+            world,
+            generation: number(&json, "generation")?,
+            seed,
+            fps,
+            delay: number(&json, "delay")?,
+            every: number(&json, "every")?.max(1),
+            paused: flag(&json, "paused")?,
+            renderer: string(&json, "renderer")?,
+            diff: flag(&saved, "diff")?,
+            style,
+            history,
+        })
+    }
+}
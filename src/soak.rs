@@ -0,0 +1,209 @@
+//! Soak testing: random worlds under random rules, stepped by every engine
+//! at once, stopping at the first generation where any two disagree or an
+//! invariant breaks.
+//!
+//! The engines are those of `engines`, the plain grid there checking the
+//! other two. Configuration N of a soak with seed S is the first one of
+//! the soak with seed S + N, so a failure can be run again on its own.
+
+use std::panic::{self, AssertUnwindSafe};
+
+use conway::{Cell, Checkpoint, Pattern, Rect, Rng, Rule, World};
+
+use crate::cli::SoakOptions;
+use crate::engines::{advance_strips, cut, Grid};
+
+/// What went wrong, if anything.
+type Check = Result<(), String>;
+
+/// A random Life-like rule.
+fn random_rule(rng: &mut Rng) -> Rule {
+    let digits = |rng: &mut Rng| -> String {
+        (0..=8u8)
+            .filter(|_| rng.chance(0.3))
+            .map(|n| char::from(b'0' + n))
+            .collect()
+    };
+    let birth = digits(rng);
+    let survival = digits(rng);
+    Rule::parse(&format!("B{}/S{}", birth, survival)).unwrap_or_else(|_| Rule::conway())
+}
+
+/// Cuts `height` rows into between one and four strips of random heights.
+fn random_cuts(height: usize, rng: &mut Rng) -> Vec<usize> {
+    let strips = 1 + (rng.next_u64() % 4) as usize;
+    let mut cuts: Vec<usize> = (1..height).filter(|_| rng.chance(0.5)).collect();
+    while cuts.len() >= strips {
+        let i = (rng.next_u64() % cuts.len() as u64) as usize;
+        cuts.remove(i);
+    }
+    cuts.insert(0, 0);
+    cuts
+}
+
+/// The world's bookkeeping against a count of its cells.
+fn check_world(world: &World) -> Check {
+    let live: Vec<(usize, usize)> = (0..world.height())
+        .flat_map(|y| (0..world.width()).map(move |x| (x, y)))
+        .filter(|&(x, y)| world.get(x, y) == Cell::Live)
+        .collect();
+    if world.population() != live.len() {
+        return Err(format!(
+            "population is {} but {} cells are live",
+            world.population(),
+            live.len()
+        ));
+    }
+    let span = |coords: Vec<usize>| Some((*coords.iter().min()?, *coords.iter().max()? + 1));
+    let bounds = span(live.iter().map(|c| c.0).collect())
+        .zip(span(live.iter().map(|c| c.1).collect()))
+        .map(|((x0, x1), (y0, y1))| Rect {
+            x: x0,
+            y: y0,
+            width: x1 - x0,
+            height: y1 - y0,
+        });
+    if world.bounding_box() != bounds {
+        return Err(format!(
+            "bounding box is {:?} but the live cells span {:?}",
+            world.bounding_box(),
+            bounds
+        ));
+    }
+    Ok(())
+}
+
+/// The ways a world is written out, each read back.
+fn check_round_trips(world: &World, generation: u64) -> Check {
+    let pattern = Pattern::from_world(world);
+    if Pattern::parse_rle(&pattern.to_rle()).as_ref() != Ok(&pattern) {
+        return Err("the world does not survive RLE".to_string());
+    }
+    let checkpoint = Checkpoint {
+        world: world.clone(),
+        generation,
+        seed: None,
+    };
+    match Checkpoint::from_bytes(&checkpoint.to_bytes()) {
+        Ok(read) if read.world == *world && read.generation == generation => Ok(()),
+        Ok(_) => Err("the world does not survive a checkpoint".to_string()),
+        Err(e) => Err(format!("a checkpoint cannot be read back: {}", e)),
+    }
+}
+
+/// Runs one random configuration, giving back what it was and how it went.
+fn soak_one(seed: u64, generations: u64, max_size: usize) -> (String, Check) {
+    let mut rng = Rng::new(seed);
+    let width = 3 + (rng.next_u64() % (max_size as u64 - 2)) as usize;
+    let height = 3 + (rng.next_u64() % (max_size as u64 - 2)) as usize;
+    let rule = random_rule(&mut rng);
+    let density = rng.next_f64();
+    let cuts = random_cuts(height, &mut rng);
+    let what = format!(
+        "{}x{} world under {} at density {:.2} in {} strips",
+        width,
+        height,
+        rule,
+        density,
+        cuts.len()
+    );
+    let check = panic::catch_unwind(AssertUnwindSafe(|| {
+        soak_world(&mut rng, (width, height), rule, density, &cuts, generations)
+    }))
+    .unwrap_or_else(|_| Err("panicked".to_string()));
+    (what, check)
+}
+
+fn soak_world(
+    rng: &mut Rng,
+    (width, height): (usize, usize),
+    rule: Rule,
+    density: f64,
+    cuts: &[usize],
+    generations: u64,
+) -> Check {
+    let mut world = World::new(width, height)?;
+    world.set_rule(rule);
+    world.randomize(density, rng);
+    let mut tmp = world.clone();
+    let mut grid = Grid::from_world(&world);
+    let mut strips = cut(&world, cuts)?;
+    check_round_trips(&world, 0)?;
+    for generation in 1..=generations {
+        let before = world.population();
+        let changes = world.advance(&mut tmp);
+        let strip_changes = advance_strips(&mut strips);
+        let grid_changes = grid.step(rule);
+        let at = |what: String| format!("generation {}: {}", generation, what);
+        check_world(&world).map_err(at)?;
+        if before + changes.births != world.population() + changes.deaths {
+            return Err(at(format!(
+                "population went from {} to {} with +{} -{}",
+                before,
+                world.population(),
+                changes.births,
+                changes.deaths
+            )));
+        }
+        if strip_changes != changes || grid_changes != changes {
+            return Err(at(format!(
+                "the world changed by {:?}, the strips by {:?} and the grid by {:?}",
+                changes, strip_changes, grid_changes
+            )));
+        }
+        for (i, strip) in strips.iter().enumerate() {
+            for sy in 0..strip.height() {
+                for x in 0..width {
+                    let y = cuts[i] + sy;
+                    let cell = world.get(x, y);
+                    let grid_cell = grid.get(x, y);
+                    if strip.get(x, sy) != cell || grid_cell != cell {
+                        return Err(at(format!(
+                            "cell {},{} is {:?} in the world, {:?} in its strip and {:?} in the grid",
+                            x,
+                            y,
+                            cell,
+                            strip.get(x, sy),
+                            grid_cell
+                        )));
+                    }
+                }
+            }
+        }
+        if !changes.any() {
+            break;
+        }
+    }
+    check_round_trips(&world, generations)
+}
+
+pub fn run(opts: &SoakOptions, seed: u64) -> Result<(), String> {
+    let (mut passed, mut failures) = (0u64, 0u64);
+    let mut index = 0;
+    while opts.configs == 0 || index < opts.configs {
+        let config_seed = seed.wrapping_add(index);
+        let (what, check) = soak_one(config_seed, opts.generations, opts.max_size);
+        match check {
+            Ok(()) => passed += 1,
+            Err(e) => {
+                failures += 1;
+                println!(
+                    "failed       configuration {} (seed {}), {}: {}",
+                    index, config_seed, what, e
+                );
+            }
+        }
+        index += 1;
+        if index % 100 == 0 {
+            println!("soaked       {} configurations, {} failed", index, failures);
+        }
+    }
+    println!(
+        "soak         {} configurations of up to {} generations, {} passed, {} failed",
+        index, opts.generations, passed, failures
+    );
+    if failures > 0 {
+        return Err(format!("{} configurations failed", failures));
+    }
+    Ok(())
+}
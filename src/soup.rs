@@ -0,0 +1,131 @@
+//! The shapes `--random-mode` gives a random soup, since where the cells
+//! start out shapes what becomes of them about as much as how many there
+//! are. Every mode draws from the same seed, and `uniform`, the usual soup,
+//! is the one `World::randomize` makes.
+
+use conway::{Cell, Rng, Symmetry, World};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Mode {
+    /// Each cell alive with the soup's density.
+    Uniform,
+    /// Round blobs of soup at that density scattered over an empty world,
+    /// covering about a quarter of it.
+    Cluster,
+    /// The density at the left edge thinning out to nothing at the right.
+    Gradient,
+    /// A ring of soup around the middle, its hole half as wide as it.
+    Ring,
+    /// A square soup in the middle, as wide as the world's shorter side, the
+    /// same under the turns and mirrors of the symmetry.
+    Symmetric(Symmetry),
+}
+
+impl Mode {
+    pub fn parse(s: &str) -> Result<Mode, String> {
+        let (name, symmetry) = s.split_once(':').unwrap_or((s, ""));
+        match (name, symmetry) {
+            ("uniform", "") => Ok(Mode::Uniform),
+            ("cluster", "") => Ok(Mode::Cluster),
+            ("gradient", "") => Ok(Mode::Gradient),
+            ("ring", "") => Ok(Mode::Ring),
+            ("symmetric", "") => Ok(Mode::Symmetric(Symmetry::D8)),
+            ("symmetric", symmetry) => Ok(Mode::Symmetric(Symmetry::parse(symmetry)?)),
+            _ => Err(format!(
+                "invalid --random-mode '{}', expected uniform, cluster, gradient, ring or \
+                 symmetric, with :c2, :d4 or another symmetry after it",
+                s
+            )),
+        }
+    }
+
+    /// Fills `world` with a soup of `density`, every cell set.
+    pub fn fill(self, world: &mut World, density: f64, rng: &mut Rng) {
+        let (w, h) = (world.width(), world.height());
+        let (cx, cy) = (w as f64 / 2.0, h as f64 / 2.0);
+        let soup = |rng: &mut Rng, p: f64| {
+            if rng.chance(p) {
+                Cell::Live
+            } else {
+                Cell::Dead
+            }
+        };
+        match self {
+            Mode::Uniform => world.randomize(density, rng),
+            Mode::Cluster => {
+                // blobs up to an eighth of the shorter side across, and enough
+                // of them to cover a quarter of the world on average
+                let largest = (w.min(h) as f64 / 16.0).max(2.0);
+                let mean_area = std::f64::consts::PI * (largest * 0.75).powi(2);
+                let blobs = ((w * h) as f64 / 4.0 / mean_area).ceil().max(1.0) as usize;
+                let mut inside = vec![false; w * h];
+                for _ in 0..blobs {
+                    let (bx, by) = (rng.next_f64() * w as f64, rng.next_f64() * h as f64);
+                    let r = largest * (0.5 + rng.next_f64() / 2.0);
+                    let (x0, x1) = ((bx - r).max(0.0) as usize, ((bx + r) as usize).min(w - 1));
+                    let (y0, y1) = ((by - r).max(0.0) as usize, ((by + r) as usize).min(h - 1));
+                    for y in y0..=y1 {
+                        for x in x0..=x1 {
+                            let (dx, dy) = (x as f64 + 0.5 - bx, y as f64 + 0.5 - by);
+                            if dx * dx + dy * dy <= r * r {
+                                inside[y * w + x] = true;
+                            }
+                        }
+                    }
+                }
+                for y in 0..h {
+                    for x in 0..w {
+                        let p = if inside[y * w + x] { density } else { 0.0 };
+                        world.set(x, y, soup(rng, p));
+                    }
+                }
+            }
+            Mode::Gradient => {
+                for y in 0..h {
+                    for x in 0..w {
+                        let p = density * (1.0 - x as f64 / (w.max(2) - 1) as f64);
+                        world.set(x, y, soup(rng, p));
+                    }
+                }
+            }
+            Mode::Ring => {
+                let outer = w.min(h) as f64 * 0.45;
+                let inner = outer / 2.0;
+                for y in 0..h {
+                    for x in 0..w {
+                        let (dx, dy) = (x as f64 + 0.5 - cx, y as f64 + 0.5 - cy);
+                        let r = (dx * dx + dy * dy).sqrt();
+                        let p = if (inner..=outer).contains(&r) {
+                            density
+                        } else {
+                            0.0
+                        };
+                        world.set(x, y, soup(rng, p));
+                    }
+                }
+            }
+            Mode::Symmetric(symmetry) => {
+                let size = w.min(h);
+                let (left, top) = ((w - size) / 2, (h - size) / 2);
+                let random: Vec<Cell> = (0..size * size).map(|_| soup(rng, density)).collect();
+                for y in 0..h {
+                    for x in 0..w {
+                        world.set(x, y, Cell::Dead);
+                    }
+                }
+                for y in 0..size {
+                    for x in 0..size {
+                        // every cell copies the first of the cells the symmetry
+                        // ties it to
+                        let (sx, sy) = symmetry
+                            .images(x, y, size)
+                            .into_iter()
+                            .min_by_key(|&(x, y)| (y, x))
+                            .unwrap_or((x, y));
+                        world.set(left + x, top + y, random[sy * size + sx]);
+                    }
+                }
+            }
+        }
+    }
+}
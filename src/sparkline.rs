@@ -0,0 +1,91 @@
+//! `run --sparkline N`: the population over the last N generations as a
+//! small braille chart under each frame, two generations a character and
+//! eight dots high, scaled from the lowest to the highest population in it.
+
+use std::collections::VecDeque;
+
+use crate::render::DOTS;
+
+/// Rows of braille characters the chart is tall.
+const ROWS: usize = 2;
+/// A row of characters from low to high, for terminals without Unicode.
+const ASCII: [char; 8] = ['_', '.', ',', '-', '~', '*', '^', '\''];
+
+pub struct Sparkline {
+    span: usize,
+    populations: VecDeque<usize>,
+}
+
+impl Sparkline {
+    /// A chart of the last `span` generations.
+    pub fn new(span: usize) -> Sparkline {
+        Sparkline {
+            span,
+            populations: VecDeque::with_capacity(span),
+        }
+    }
+
+    pub fn span(&self) -> usize {
+        self.span
+    }
+
+    /// The populations charted, oldest first.
+    pub fn populations(&self) -> Vec<usize> {
+        self.populations.iter().copied().collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.populations.clear();
+    }
+
+    pub fn push(&mut self, population: usize) {
+        if self.populations.len() == self.span {
+            self.populations.pop_front();
+        }
+        self.populations.push_back(population);
+    }
+
+    /// The chart, with the newest population and the range it spans beside
+    /// the bottom row, in braille or with `unicode` off in one row of ASCII.
+    pub fn render(&self, unicode: bool) -> String {
+        let dots = ROWS * 4;
+        let low = self.populations.iter().copied().min().unwrap_or(0);
+        let high = self.populations.iter().copied().max().unwrap_or(0);
+        // how many dots of its column each population fills, from the bottom
+        let heights: Vec<usize> = self
+            .populations
+            .iter()
+            .map(|&p| match high - low {
+                0 => dots / 2,
+                range => 1 + (p - low) * (dots - 1) / range,
+            })
+            .collect();
+        let last = self.populations.back().copied().unwrap_or(0);
+        if !unicode {
+            let mut chart: String = heights.iter().map(|&h| ASCII[h - 1]).collect();
+            chart.push_str(&format!(" {} ({}-{})", last, low, high));
+            return chart;
+        }
+        let mut chart = String::new();
+        for row in 0..ROWS {
+            for pair in heights.chunks(2) {
+                let mut bits = 0;
+                for (dx, &height) in pair.iter().enumerate() {
+                    for (dy, dot) in DOTS.iter().enumerate() {
+                        // dots counted up from the bottom of the chart
+                        if dots - (row * 4 + dy) <= height {
+                            bits |= dot[dx];
+                        }
+                    }
+                }
+                chart.push(char::from_u32(0x2800 + bits).unwrap_or(' '));
+            }
+            if row + 1 == ROWS {
+                chart.push_str(&format!(" {} ({}-{})", last, low, high));
+            } else {
+                chart.push('\n');
+            }
+        }
+        chart
+    }
+}
@@ -0,0 +1,195 @@
+//! Colored Life for any number of species, after Immigration: every live
+//! cell belongs to a species, and a cell being born takes the species of
+//! most of its live neighbors. A species can be made blind to another, so
+//! that the other's cells count as dead around its own, neither keeping
+//! them alive nor helping them be born.
+
+use crate::{Cell, Changes, Rng, Rule};
+
+/// The most species a world can have, one to each basic terminal color
+/// other than black and white.
+pub const MAX_SPECIES: usize = 6;
+
+/// Who gets a birth that two or more species have the most neighbors for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Ties {
+    /// The tied species take turns along the diagonals, as in Immigration.
+    Alternate,
+    /// The first of them in order.
+    First,
+    /// No one: the cell stays dead.
+    None,
+}
+
+impl Ties {
+    pub fn parse(s: &str) -> Result<Ties, String> {
+        match s {
+            "alternate" => Ok(Ties::Alternate),
+            "first" => Ok(Ties::First),
+            "none" => Ok(Ties::None),
+            _ => Err(format!(
+                "unknown tie rule '{}', expected alternate, first or none",
+                s
+            )),
+        }
+    }
+}
+
+/// A wrapping world whose live cells each belong to one of its species.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Species {
+    /// The species of each cell plus one, 0 for a dead cell.
+    cells: Vec<u8>,
+    width: usize,
+    height: usize,
+    species: usize,
+    rule: Rule,
+    ties: Ties,
+    /// Whether cells of a species, the row, see those of another, the
+    /// column, as alive.
+    sees: Vec<bool>,
+}
+
+impl Species {
+    pub fn new(width: usize, height: usize, species: usize) -> Result<Species, String> {
+        if width < 3 || height < 3 {
+            return Err("the world cannot be smaller than 3x3".to_string());
+        }
+        if !(1..=MAX_SPECIES).contains(&species) {
+            return Err(format!(
+                "a world has from 1 to {} species, not {}",
+                MAX_SPECIES, species
+            ));
+        }
+        Ok(Species {
+            cells: vec![0; width * height],
+            width,
+            height,
+            species,
+            rule: Rule::conway(),
+            ties: Ties::Alternate,
+            sees: vec![true; species * species],
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// How many species there are, numbered from 0.
+    pub fn species(&self) -> usize {
+        self.species
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+    }
+
+    pub fn set_ties(&mut self, ties: Ties) {
+        self.ties = ties;
+    }
+
+    /// Makes the cells of species `other` count as dead around those of
+    /// `species`, or alive again.
+    pub fn set_blind(&mut self, species: usize, other: usize, blind: bool) {
+        self.sees[species * self.species + other] = !blind;
+    }
+
+    /// The cell's species, `None` if it is dead.
+    pub fn get(&self, x: usize, y: usize) -> Option<usize> {
+        match self.cells[y * self.width + x] {
+            0 => None,
+            n => Some(usize::from(n - 1)),
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, species: Option<usize>) {
+        self.cells[y * self.width + x] = species.map_or(0, |s| s as u8 + 1);
+    }
+
+    /// Live cells of each species.
+    pub fn populations(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.species];
+        for &cell in self.cells.iter().filter(|&&cell| cell > 0) {
+            counts[usize::from(cell - 1)] += 1;
+        }
+        counts
+    }
+
+    /// Fills the world at random, each live cell of a species picked evenly.
+    pub fn randomize(&mut self, density: f64, rng: &mut Rng) {
+        for i in 0..self.cells.len() {
+            self.cells[i] = match rng.chance(density) {
+                true => (rng.next_u64() % self.species as u64) as u8 + 1,
+                false => 0,
+            };
+        }
+    }
+
+    /// Steps every cell by the rule, each species counting the neighbors it
+    /// sees. A birth goes to the species with the most neighbors among those
+    /// the rule gives one to, ties settled as `set_ties` says.
+    pub fn advance(&mut self) -> Changes {
+        let (w, h, n) = (self.width, self.height, self.species);
+        let mut next = vec![0; w * h];
+        let mut changes = Changes::default();
+        for y in 0..h {
+            for x in 0..w {
+                let mut counts = [0u8; MAX_SPECIES];
+                for dy in [h - 1, 0, 1] {
+                    for dx in [w - 1, 0, 1] {
+                        if (dx, dy) == (0, 0) {
+                            continue;
+                        }
+                        if let Some(s) = self.get((x + dx) % w, (y + dy) % h) {
+                            counts[s] += 1;
+                        }
+                    }
+                }
+                let seen = |s: usize| -> u8 {
+                    (0..n)
+                        .filter(|&other| self.sees[s * n + other])
+                        .map(|other| counts[other])
+                        .sum()
+                };
+                let old = self.get(x, y);
+                let new = match old {
+                    Some(s) => {
+                        Some(s).filter(|&s| self.rule.next(Cell::Live, seen(s)) == Cell::Live)
+                    }
+                    None => {
+                        let born: Vec<usize> = (0..n)
+                            .filter(|&s| counts[s] > 0)
+                            .filter(|&s| self.rule.next(Cell::Dead, seen(s)) == Cell::Live)
+                            .collect();
+                        let most = born.iter().map(|&s| counts[s]).max().unwrap_or(0);
+                        let tied: Vec<usize> =
+                            born.into_iter().filter(|&s| counts[s] == most).collect();
+                        match (tied.len(), self.ties) {
+                            (0, _) => None,
+                            (1, _) | (_, Ties::First) => Some(tied[0]),
+                            (_, Ties::Alternate) => Some(tied[(x + y) % tied.len()]),
+                            (_, Ties::None) => None,
+                        }
+                    }
+                };
+                match (old.is_some(), new.is_some()) {
+                    (false, true) => changes.births += 1,
+                    (true, false) => changes.deaths += 1,
+                    _ => {}
+                }
+                next[y * w + x] = new.map_or(0, |s| s as u8 + 1);
+            }
+        }
+        self.cells = next;
+        changes
+    }
+}
@@ -0,0 +1,95 @@
+//! Several worlds of the same size stepped together, each under its own
+//! rule, with links letting what is alive in one layer decide where another
+//! can come alive.
+
+use crate::{Cell, Changes, World};
+
+/// Births in `layer` allowed only where `on` was alive the generation
+/// before, or with `alive` false, only where it was dead.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Link {
+    pub layer: usize,
+    pub on: usize,
+    pub alive: bool,
+}
+
+#[derive(Clone)]
+pub struct Stack {
+    layers: Vec<World>,
+    links: Vec<Link>,
+    tmp: World,
+}
+
+impl Stack {
+    pub fn new(layers: Vec<World>) -> Result<Stack, String> {
+        let first = layers.first().ok_or("a stack needs at least one layer")?;
+        let (w, h) = (first.width(), first.height());
+        if let Some(other) = layers.iter().find(|l| (l.width(), l.height()) != (w, h)) {
+            return Err(format!(
+                "the layers of a stack must be the same size, not {}x{} and {}x{}",
+                w,
+                h,
+                other.width(),
+                other.height()
+            ));
+        }
+        Ok(Stack {
+            tmp: first.clone(),
+            layers,
+            links: Vec::new(),
+        })
+    }
+
+    pub fn layers(&self) -> &[World] {
+        &self.layers
+    }
+
+    pub fn layer_mut(&mut self, i: usize) -> &mut World {
+        &mut self.layers[i]
+    }
+
+    pub fn link(&mut self, link: Link) -> Result<(), String> {
+        let n = self.layers.len();
+        if link.layer >= n || link.on >= n {
+            return Err(format!(
+                "the stack has no layer {}",
+                link.layer.max(link.on)
+            ));
+        }
+        if link.layer == link.on {
+            return Err(format!("layer {} cannot be linked to itself", link.layer));
+        }
+        self.links.push(link);
+        Ok(())
+    }
+
+    /// Steps every layer by its own rule, then undoes the births the links
+    /// rule out, going by the layers as they were before the step.
+    pub fn advance(&mut self) -> Vec<Changes> {
+        let before: Vec<World> = if self.links.is_empty() {
+            Vec::new()
+        } else {
+            self.layers.clone()
+        };
+        let tmp = &mut self.tmp;
+        let mut changes: Vec<Changes> = self
+            .layers
+            .iter_mut()
+            .map(|layer| layer.advance(tmp))
+            .collect();
+        for link in &self.links {
+            let (was, on) = (&before[link.layer], &before[link.on]);
+            let layer = &mut self.layers[link.layer];
+            for y in 0..layer.height() {
+                for x in 0..layer.width() {
+                    let born = was.get(x, y) == Cell::Dead && layer.get(x, y) == Cell::Live;
+                    if born && (on.get(x, y) == Cell::Live) != link.alive {
+                        layer.set(x, y, Cell::Dead);
+                        changes[link.layer].births -= 1;
+                    }
+                }
+            }
+        }
+        changes
+    }
+}
@@ -0,0 +1,250 @@
+//! Stop conditions for runs whose length isn't known up front.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+
+use conway::{Cell, Pattern, World};
+
+/// By default a state repeating within this many generations counts as
+/// stable, which catches still lifes and all the common oscillators.
+pub const DEFAULT_WINDOW: usize = 32;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Until {
+    /// No live cells are left.
+    Extinct,
+    /// The world is extinct, a still life or a short-period oscillator.
+    Stable,
+}
+
+impl Until {
+    pub fn parse(s: &str) -> Result<Until, String> {
+        match s {
+            "extinct" => Ok(Until::Extinct),
+            "stable" => Ok(Until::Stable),
+            _ => Err(format!(
+                "invalid value '{}' for --until: expected stable or extinct",
+                s
+            )),
+        }
+    }
+}
+
+fn state_hash(world: &World) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    world.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Why a run ends whose world, of `population` live cells, has stopped
+/// changing since `generation`.
+pub fn settled(population: usize, generation: u64) -> String {
+    if population == 0 {
+        format!("extinct at generation {}", generation)
+    } else {
+        format!("stabilized at generation {} with period 1", generation)
+    }
+}
+
+/// Watches for a pattern to turn up, in any orientation, on its own: the
+/// cells around the copy have to be dead too, so a soup doesn't set it off
+/// with a lookalike in the middle of a mess.
+pub struct Sighting {
+    /// The pattern cropped, with a border of dead cells.
+    framed: Pattern,
+}
+
+impl Sighting {
+    pub fn new(pattern: &Pattern) -> Sighting {
+        let pattern = pattern.crop();
+        let mut framed = Pattern::new(pattern.width() + 2, pattern.height() + 2);
+        for y in 0..pattern.height() {
+            for x in 0..pattern.width() {
+                framed.set(x + 1, y + 1, pattern.get(x, y));
+            }
+        }
+        Sighting { framed }
+    }
+
+    /// Looks at the world after `generation` and says where the first copy
+    /// is, by the top left of its bounding box, if there is one.
+    pub fn check(&self, world: &World, generation: u64) -> Option<String> {
+        let &(x, y) = world.find(&self.framed, true).first()?;
+        let (x, y) = world.wrap(x as i64 + 1, y as i64 + 1);
+        Some(format!(
+            "pattern found at {},{} at generation {}",
+            x, y, generation
+        ))
+    }
+}
+
+/// `--break-on`: cells that end the run, or pause the window, the first
+/// time they change.
+pub struct Breakpoints {
+    /// Each cell still watched and its state when last looked at.
+    cells: Vec<(usize, usize, Cell)>,
+}
+
+impl Breakpoints {
+    pub fn new(world: &World, cells: &[(usize, usize)]) -> Result<Breakpoints, String> {
+        if let Some(&(x, y)) = cells
+            .iter()
+            .find(|&&(x, y)| x >= world.width() || y >= world.height())
+        {
+            return Err(format!(
+                "--break-on {},{} is outside the {}x{} world",
+                x,
+                y,
+                world.width(),
+                world.height()
+            ));
+        }
+        Ok(Breakpoints {
+            cells: cells
+                .iter()
+                .map(|&(x, y)| (x, y, world.get(x, y)))
+                .collect(),
+        })
+    }
+
+    /// Looks at the world after `generation` and says which cell changed, if
+    /// one did. That one is not watched any more.
+    pub fn check(&mut self, world: &World, generation: u64) -> Option<String> {
+        let i = self
+            .cells
+            .iter()
+            .position(|&(x, y, cell)| world.get(x, y) != cell)?;
+        let (x, y, _) = self.cells.remove(i);
+        let became = match world.get(x, y) {
+            Cell::Live => "came alive",
+            Cell::Dead => "died",
+        };
+        Some(format!(
+            "cell {},{} {} at generation {}",
+            x, y, became, generation
+        ))
+    }
+}
+
+/// Generations looked back over to decide whether a world keeps growing.
+pub const GROWTH_WINDOW: usize = 1000;
+
+/// Spots worlds that look like they grow without bound: over the last
+/// `GROWTH_WINDOW` generations the population went up from each quarter to the
+/// next, by at least a quarter overall and with the lowest count of the last
+/// quarter above the highest of the first, while the bounding box got bigger.
+/// The first `GROWTH_WINDOW` generations don't count. Guns and puffers are caught this way, at least until a torus brings
+/// their output back around or the box fills the world.
+pub struct Growth {
+    /// Population and bounding box area of the last generations.
+    samples: VecDeque<(usize, usize)>,
+}
+
+impl Growth {
+    pub fn new() -> Growth {
+        Growth {
+            samples: VecDeque::with_capacity(GROWTH_WINDOW + 1),
+        }
+    }
+
+    /// Looks at the world after `generation` and describes the growth, if it
+    /// seems unbounded.
+    pub fn check(&mut self, world: &World, generation: u64) -> Option<String> {
+        let area = world.bounding_box().map_or(0, |b| b.width * b.height);
+        // soups and methuselahs often grow for a while before they settle
+        if generation < GROWTH_WINDOW as u64 {
+            return None;
+        }
+        if self.samples.len() > GROWTH_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((world.population(), area));
+        if self.samples.len() <= GROWTH_WINDOW {
+            return None;
+        }
+
+        let quarter = GROWTH_WINDOW / 4;
+        let populations = |q: usize| {
+            self.samples
+                .iter()
+                .skip(q * quarter)
+                .take(quarter)
+                .map(|&(population, _)| population)
+        };
+        let means: Vec<usize> = (0..4)
+            .map(|q| populations(q).sum::<usize>() / quarter)
+            .collect();
+        let rising =
+            means.windows(2).all(|w| w[1] > w[0]) && populations(3).min() > populations(0).max();
+        let (first, last) = (self.samples[0], self.samples[GROWTH_WINDOW]);
+        let whole = world.width() * world.height();
+        if rising && last.0 * 4 >= first.0 * 5 && last.1 > first.1 && last.1 < whole {
+            let since = generation.saturating_sub(GROWTH_WINDOW as u64);
+            Some(format!("growing without bound since generation {}", since))
+        } else {
+            None
+        }
+    }
+}
+
+/// Follows a run generation by generation until its condition is met.
+pub struct Watch {
+    until: Until,
+    /// Hashes of the last `window` generations.
+    history: VecDeque<u64>,
+    window: usize,
+    /// The generation the world settled at, once it has.
+    since: Option<u64>,
+}
+
+impl Watch {
+    /// Starts watching from `world`, generation 0, for cycles with a period up
+    /// to `window`.
+    pub fn new(until: Until, world: &World, window: usize) -> Watch {
+        let mut watch = Watch {
+            until,
+            history: VecDeque::with_capacity(window),
+            window,
+            since: None,
+        };
+        watch.check(world, 0);
+        watch
+    }
+
+    /// Looks at the world after `generation` and describes why the run should
+    /// stop, if it should.
+    pub fn check(&mut self, world: &World, generation: u64) -> Option<String> {
+        if world.population() == 0 {
+            self.since.get_or_insert(generation);
+            return Some(format!("extinct at generation {}", generation));
+        }
+        if self.until == Until::Extinct {
+            return None;
+        }
+
+        let hash = state_hash(world);
+        if let Some(i) = self.history.iter().rposition(|&h| h == hash) {
+            // a hash collision would be a false alarm, so confirm by stepping a copy
+            let period = self.history.len() - i;
+            if world.detect_period(period) == Some(period) {
+                let since = generation - period as u64;
+                self.since.get_or_insert(since);
+                return Some(match period {
+                    1 => settled(world.population(), since),
+                    _ => format!("stabilized at generation {} with period {}", since, period),
+                });
+            }
+        }
+        if self.history.len() == self.window {
+            self.history.pop_front();
+        }
+        self.history.push_back(hash);
+        None
+    }
+
+    /// The generation the world settled at, if `check` has said it did.
+    pub fn since(&self) -> Option<u64> {
+        self.since
+    }
+}
@@ -0,0 +1,129 @@
+//! A band of whole rows out of a larger toroidal world, so a world too big
+//! for one machine can be stepped in pieces: each strip only needs to be
+//! shown the row just above and just below it every generation.
+
+use crate::{Cell, Changes, Rng, Rule, World};
+
+#[derive(Clone, Debug)]
+pub struct Strip {
+    width: usize,
+    rows: Vec<Vec<Cell>>,
+    /// Scratch space for `advance`.
+    next: Vec<Vec<Cell>>,
+    rule: Rule,
+    population: usize,
+}
+
+impl Strip {
+    pub fn new(width: usize, height: usize, rule: Rule) -> Result<Strip, String> {
+        if width < 3 || height == 0 {
+            return Err("a strip must be at least 3 cells wide and 1 tall".to_string());
+        }
+        rule.nearest_only("a strip")?;
+        let rows = vec![vec![Cell::Dead; width]; height];
+        Ok(Strip {
+            width,
+            next: rows.clone(),
+            rows,
+            rule,
+            population: 0,
+        })
+    }
+
+    /// The `height` rows of `world` starting at row `y`, under its rule.
+    pub fn from_world(world: &World, y: usize, height: usize) -> Result<Strip, String> {
+        let mut strip = Strip::new(world.width(), height, world.rule())?;
+        for sy in 0..height {
+            for x in 0..world.width() {
+                strip.set(x, sy, world.get(x, y + sy));
+            }
+        }
+        Ok(strip)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn population(&self) -> usize {
+        self.population
+    }
+
+    pub fn row(&self, y: usize) -> &[Cell] {
+        &self.rows[y]
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Cell {
+        self.rows[y][x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: Cell) {
+        let old = std::mem::replace(&mut self.rows[y][x], value);
+        self.population = self.population + value.n() as usize - old.n() as usize;
+    }
+
+    /// Like `World::randomize`, drawing from `rng` in the same order, so the
+    /// strips of a world filled one after the other from a single generator
+    /// make up the same soup the whole world would get.
+    pub fn randomize(&mut self, density: f64, rng: &mut Rng) {
+        for y in 0..self.height() {
+            for x in 0..self.width {
+                let cell = if rng.chance(density) {
+                    Cell::Live
+                } else {
+                    Cell::Dead
+                };
+                self.set(x, y, cell);
+            }
+        }
+    }
+
+    /// Steps every cell, with `above` and `below` as the rows of the rest of
+    /// the world that border the strip. Rows wrap around left to right as in
+    /// a world.
+    pub fn advance(&mut self, above: &[Cell], below: &[Cell]) -> Changes {
+        let (width, height) = (self.width, self.height());
+        let mut changes = Changes::default();
+        let mut population = 0;
+        for y in 0..height {
+            let top = if y == 0 { above } else { &self.rows[y - 1] };
+            let bottom = if y == height - 1 {
+                below
+            } else {
+                &self.rows[y + 1]
+            };
+            let row = &self.rows[y];
+            for x in 0..width {
+                let l = if x == 0 { width - 1 } else { x - 1 };
+                let r = if x == width - 1 { 0 } else { x + 1 };
+                let neighbors = top[l].n()
+                    + top[x].n()
+                    + top[r].n()
+                    + row[l].n()
+                    + row[r].n()
+                    + bottom[l].n()
+                    + bottom[x].n()
+                    + bottom[r].n();
+                let cell = self.rule.next(row[x], neighbors);
+                match (row[x], cell) {
+                    (Cell::Dead, Cell::Live) => changes.births += 1,
+                    (Cell::Live, Cell::Dead) => changes.deaths += 1,
+                    _ => {}
+                }
+                population += cell.n() as usize;
+                self.next[y][x] = cell;
+            }
+        }
+        std::mem::swap(&mut self.rows, &mut self.next);
+        self.population = population;
+        changes
+    }
+}
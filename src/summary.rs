@@ -0,0 +1,157 @@
+//! The report printed at the end of a run.
+
+use std::time::{Duration, Instant};
+
+use conway::{Changes, World};
+
+use crate::cli::OutputFormat;
+use crate::jsonl;
+use crate::output;
+use crate::pace::Timings;
+use crate::stop::{Until, Watch};
+
+pub struct Summary {
+    output: OutputFormat,
+    started: Instant,
+    generations: u64,
+    population: usize,
+    peak: usize,
+    peak_at: u64,
+    births: u64,
+    deaths: u64,
+    /// Looks for cycles whether or not they end the run.
+    watch: Watch,
+    settled: Option<String>,
+    /// Cells flipped by `--perturb` or changed by `--faults`.
+    pub perturbed: u64,
+    /// How long the frames took, for runs in the terminal.
+    pub timings: Option<Timings>,
+}
+
+impl Summary {
+    /// Starts with `world` as generation 0, counting cycles up to
+    /// `max_period` generations long as stable.
+    pub fn new(world: &World, max_period: usize, output: OutputFormat) -> Summary {
+        Summary {
+            output,
+            started: Instant::now(),
+            generations: 0,
+            population: world.population(),
+            peak: world.population(),
+            peak_at: 0,
+            births: 0,
+            deaths: 0,
+            watch: Watch::new(Until::Stable, world, max_period),
+            settled: None,
+            perturbed: 0,
+            timings: None,
+        }
+    }
+
+    pub fn observe(&mut self, world: &World, changes: Changes) {
+        self.generations += 1;
+        self.population = world.population();
+        if self.population > self.peak {
+            self.peak = self.population;
+            self.peak_at = self.generations;
+        }
+        self.births += changes.births as u64;
+        self.deaths += changes.deaths as u64;
+        if self.settled.is_none() {
+            self.settled = self.watch.check(world, self.generations);
+        }
+    }
+
+    /// Writes the report with the rest of the run's data.
+    pub fn print(&self) -> Result<(), String> {
+        let secs = self.started.elapsed().as_secs_f64();
+        let rate = self.generations as f64 / secs.max(1e-9);
+        match self.output {
+            OutputFormat::Text => {
+                output::data(&format!(
+                    "summary      {} in {:.3}s, {:.1} generations/s",
+                    count(self.generations, "generation"),
+                    secs,
+                    rate
+                ))?;
+                output::data(&format!(
+                    "population   {} final, peak {} at generation {}",
+                    self.population, self.peak, self.peak_at
+                ))?;
+                output::data(&format!(
+                    "changes      {}, {}",
+                    count(self.births, "birth"),
+                    count(self.deaths, "death")
+                ))?;
+                if self.perturbed > 0 {
+                    output::data(&format!(
+                        "perturbed    {} flipped",
+                        count(self.perturbed, "cell")
+                    ))?;
+                }
+                if let Some(reason) = &self.settled {
+                    output::data(&format!("outcome      {}", reason))?;
+                }
+                if let Some(timings) = &self.timings {
+                    let (simulate, render, sleep) = timings.averages();
+                    output::data(&format!(
+                        "frames       {} shown, {:.1} fps at the end, {} dropped",
+                        timings.frames,
+                        timings.fps(),
+                        timings.dropped
+                    ))?;
+                    output::data(&format!(
+                        "frame time   {:.2}ms simulate, {:.2}ms render, {:.2}ms sleep on average",
+                        ms(simulate),
+                        ms(render),
+                        ms(sleep)
+                    ))?;
+                }
+            }
+            OutputFormat::Jsonl => {
+                let outcome = self
+                    .settled
+                    .as_ref()
+                    .map_or("null".to_string(), |r| jsonl::string(r));
+                let frames = self.timings.as_ref().map_or(String::new(), |t| {
+                    let (simulate, render, sleep) = t.averages();
+                    format!(
+                        ",\"frames\":{{\"shown\":{},\"fps\":{:.1},\"dropped\":{},\
+                         \"simulate_ms\":{:.3},\"render_ms\":{:.3},\"sleep_ms\":{:.3}}}",
+                        t.frames,
+                        t.fps(),
+                        t.dropped,
+                        ms(simulate),
+                        ms(render),
+                        ms(sleep)
+                    )
+                });
+                output::data(&format!(
+                    "{{\"summary\":{{\"generations\":{},\"seconds\":{:.3},\"population\":{},\
+                     \"peak\":{},\"peak_generation\":{},\"births\":{},\"deaths\":{},\
+                     \"perturbed\":{},\"outcome\":{}{}}}}}",
+                    self.generations,
+                    secs,
+                    self.population,
+                    self.peak,
+                    self.peak_at,
+                    self.births,
+                    self.deaths,
+                    self.perturbed,
+                    outcome,
+                    frames
+                ))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `n` of `what`, with the plural for any number but one.
+fn count(n: u64, what: &str) -> String {
+    format!("{} {}{}", n, what, if n == 1 { "" } else { "s" })
+}
+
+fn ms(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
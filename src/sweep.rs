@@ -0,0 +1,456 @@
+//! Parameter sweeps: soups run at every point of a range of one or two of
+//! their settings, to see how lifespan and what is left behind depend on
+//! them, as a table and optionally a heat map.
+
+use std::fmt::Write as _;
+
+use conway::{Rule, World};
+
+use crate::cli::{ReportFormat, SweepOptions, WorldOptions};
+use crate::png;
+use crate::pool;
+use crate::stop::{Until, Watch};
+
+/// Points a sweep may have, so a step too small is an error rather than a
+/// run that never ends.
+pub const MAX_POINTS: usize = 10_000;
+/// Pixels on a side of each point of the heat map.
+const POINT_SIZE: usize = 16;
+
+/// The values one setting takes over a sweep.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Axis {
+    Density(Vec<f64>),
+    /// Sets of birth counts as digits, each used with the survival counts
+    /// of the world's rule.
+    Birth(Vec<String>),
+    /// Square worlds of each side.
+    Size(Vec<usize>),
+    Width(Vec<usize>),
+    Height(Vec<usize>),
+}
+
+impl Axis {
+    /// Parses `PARAM=START:END:STEP` or `PARAM=A,B,C`.
+    pub fn parse(s: &str) -> Result<Axis, String> {
+        let (param, values) = s.split_once('=').ok_or_else(|| {
+            format!(
+                "invalid sweep '{}', expected PARAM=START:END:STEP or PARAM=A,B,C",
+                s
+            )
+        })?;
+        let axis = match param {
+            "density" => {
+                let values = numbers(values, "a density")?;
+                if let Some(d) = values.iter().find(|&&d| !(0.0..=1.0).contains(&d)) {
+                    return Err(format!("invalid density {} to sweep: expected 0 to 1", d));
+                }
+                Axis::Density(values)
+            }
+            "birth" => {
+                let sets: Vec<String> = values.split(',').map(str::to_string).collect();
+                for set in &sets {
+                    let valid = set.chars().all(|c| matches!(c, '0'..='8'))
+                        && set.chars().enumerate().all(|(i, c)| !set[..i].contains(c));
+                    if !valid {
+                        return Err(format!(
+                            "invalid birth counts '{}' to sweep: expected a list like 3,36,378",
+                            set
+                        ));
+                    }
+                }
+                Axis::Birth(sets)
+            }
+            "size" | "width" | "height" => {
+                let values = numbers(values, "a number of cells")?;
+                if let Some(n) = values.iter().find(|&&n| n < 1.0 || n.fract() != 0.0) {
+                    return Err(format!(
+                        "invalid {} {} to sweep: expected whole cells",
+                        param, n
+                    ));
+                }
+                let cells = values.into_iter().map(|n| n as usize).collect();
+                match param {
+                    "size" => Axis::Size(cells),
+                    "width" => Axis::Width(cells),
+                    _ => Axis::Height(cells),
+                }
+            }
+            _ => {
+                return Err(format!(
+                    "invalid sweep parameter '{}': expected density, birth, size, width or height",
+                    param
+                ))
+            }
+        };
+        if axis.len() > MAX_POINTS {
+            return Err(format!(
+                "the sweep '{}' has {} points, at most {} can be run",
+                s,
+                axis.len(),
+                MAX_POINTS
+            ));
+        }
+        Ok(axis)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Axis::Density(_) => "density",
+            Axis::Birth(_) => "birth",
+            Axis::Size(_) => "size",
+            Axis::Width(_) => "width",
+            Axis::Height(_) => "height",
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Axis::Density(values) => values.len(),
+            Axis::Birth(sets) => sets.len(),
+            Axis::Size(cells) | Axis::Width(cells) | Axis::Height(cells) => cells.len(),
+        }
+    }
+
+    fn label(&self, i: usize) -> String {
+        match self {
+            Axis::Density(values) => values[i].to_string(),
+            Axis::Birth(sets) => format!("B{}", sets[i]),
+            Axis::Size(cells) | Axis::Width(cells) | Axis::Height(cells) => cells[i].to_string(),
+        }
+    }
+
+    /// Changes `world` to the setting's `i`th value.
+    fn apply(&self, i: usize, world: &mut WorldOptions) -> Result<(), String> {
+        match self {
+            Axis::Density(values) => world.random = Some(values[i]),
+            Axis::Birth(sets) => {
+                let rule = world.rule.or(world.default_rule).unwrap_or_default();
+                let survival = rule.to_string();
+                let survival = survival.split_once('/').map_or("S", |(_, s)| s);
+                world.rule = Some(Rule::parse(&format!("B{}/{}", sets[i], survival))?);
+            }
+            Axis::Size(cells) => {
+                world.width = cells[i];
+                world.height = cells[i];
+            }
+            Axis::Width(cells) => world.width = cells[i],
+            Axis::Height(cells) => world.height = cells[i],
+        }
+        Ok(())
+    }
+}
+
+/// `START:END:STEP`, counting up or down from START, or `A,B,C`.
+fn numbers(s: &str, what: &str) -> Result<Vec<f64>, String> {
+    let number = |part: &str| -> Result<f64, String> {
+        part.trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|n| n.is_finite())
+            .ok_or_else(|| format!("invalid value '{}' to sweep: expected {}", part, what))
+    };
+    let range: Vec<&str> = s.split(':').collect();
+    if range.len() == 1 {
+        return s.split(',').map(number).collect();
+    }
+    if range.len() != 3 {
+        return Err(format!(
+            "invalid range '{}' to sweep: expected START:END:STEP",
+            s
+        ));
+    }
+    let (start, end, step) = (number(range[0])?, number(range[1])?, number(range[2])?);
+    if step <= 0.0 {
+        return Err(format!(
+            "invalid range '{}' to sweep: STEP must be above 0",
+            s
+        ));
+    }
+    // a little slack so 0.1:0.5:0.1 ends at 0.5 despite rounding
+    let steps = ((end - start).abs() / step + 1e-9).floor();
+    if steps >= MAX_POINTS as f64 {
+        return Err(format!(
+            "the range '{}' has more than {} points, at most {} can be run",
+            s, MAX_POINTS, MAX_POINTS
+        ));
+    }
+    let sign = if end < start { -1.0 } else { 1.0 };
+    // each value from the start rather than added up, so errors don't grow
+    Ok((0..=steps as usize)
+        .map(|i| {
+            let n = start + sign * step * i as f64;
+            (n * 1e9).round() / 1e9
+        })
+        .collect())
+}
+
+/// What is measured at each point, over all its runs.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Measure {
+    /// Mean generations until a run settled, or the limit if it never did.
+    Lifespan,
+    /// Fraction of the runs that settled into something extinct, still or
+    /// oscillating.
+    Settled,
+    /// Fraction of the runs that died out.
+    Extinct,
+    /// Mean fraction of the cells alive at the end.
+    Alive,
+}
+
+impl Measure {
+    pub fn parse(s: &str) -> Result<Measure, String> {
+        match s {
+            "lifespan" => Ok(Measure::Lifespan),
+            "settled" => Ok(Measure::Settled),
+            "extinct" => Ok(Measure::Extinct),
+            "alive" => Ok(Measure::Alive),
+            _ => Err(format!(
+                "invalid value '{}' for --measure: expected lifespan, settled, extinct or alive",
+                s
+            )),
+        }
+    }
+
+    fn of(self, point: &Point, generations: u64) -> f64 {
+        let runs = point.runs.max(1) as f64;
+        match self {
+            Measure::Lifespan => {
+                let unsettled = (point.runs - point.settled) as u64 * generations;
+                (point.lifespans + unsettled) as f64 / runs
+            }
+            Measure::Settled => point.settled as f64 / runs,
+            Measure::Extinct => point.extinct as f64 / runs,
+            Measure::Alive => point.density / runs,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Measure::Lifespan => "lifespan",
+            Measure::Settled => "settled",
+            Measure::Extinct => "extinct",
+            Measure::Alive => "alive",
+        }
+    }
+}
+
+const MEASURES: [Measure; 4] = [
+    Measure::Lifespan,
+    Measure::Settled,
+    Measure::Extinct,
+    Measure::Alive,
+];
+
+/// The runs of one point so far.
+#[derive(Clone, Default)]
+struct Point {
+    runs: usize,
+    settled: usize,
+    extinct: usize,
+    /// Generations the settled runs took, added up.
+    lifespans: u64,
+    /// Final densities added up.
+    density: f64,
+}
+
+/// Runs `opts.runs` soups at every point, run `k` of each from seed
+/// `seed + k` so the points differ only in the settings swept.
+pub fn run(
+    opts: &SweepOptions,
+    seed: u64,
+    build: fn(&WorldOptions) -> Result<World, String>,
+) -> Result<(), String> {
+    let columns = opts.axes[0].len();
+    let rows = opts.axes.get(1).map_or(1, Axis::len);
+    let points = columns * rows;
+    // without a soup every run of a point would be the same
+    let runs =
+        if opts.world.random.is_some() || opts.axes.iter().any(|a| matches!(a, Axis::Density(_))) {
+            opts.runs
+        } else {
+            1
+        };
+    let outcomes = pool::run(points * runs, opts.threads, |i| {
+        let (point, k) = (i / runs, i % runs);
+        let mut world = world_at(opts, point % columns, point / columns)?;
+        world.seed = Some(seed.wrapping_add(k as u64));
+        Ok(run_one(build(&world)?, opts))
+    })?;
+    let mut results = vec![Point::default(); points];
+    for (i, (settled, extinct, density)) in outcomes.into_iter().enumerate() {
+        let p = &mut results[i / runs];
+        p.runs += 1;
+        if let Some(generation) = settled {
+            p.settled += 1;
+            p.lifespans += generation;
+        }
+        p.extinct += usize::from(extinct);
+        p.density += density;
+    }
+    let names: Vec<&str> = opts.axes.iter().map(Axis::name).collect();
+    let table = match opts.format {
+        Some(ReportFormat::Csv) => csv(opts, &results),
+        Some(ReportFormat::Json) => json(opts, &results),
+        None => text(opts, &results, runs),
+    };
+    print!("{}", table);
+    if let Some(path) = &opts.heatmap {
+        let values: Vec<f64> = results
+            .iter()
+            .map(|p| opts.measure.of(p, opts.generations))
+            .collect();
+        let comment = format!(
+            "sweep of {}, {} from {} (black) to {} (white)",
+            names.join(" and "),
+            opts.measure.name(),
+            values.iter().copied().fold(f64::INFINITY, f64::min),
+            values.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+        );
+        write_heatmap(path, columns, rows, &values, &comment)?;
+    }
+    Ok(())
+}
+
+/// The world options of the point in `column` of the first axis and `row`
+/// of the second.
+fn world_at(opts: &SweepOptions, column: usize, row: usize) -> Result<WorldOptions, String> {
+    let mut world = opts.world.clone();
+    opts.axes[0].apply(column, &mut world)?;
+    if let Some(axis) = opts.axes.get(1) {
+        axis.apply(row, &mut world)?;
+    }
+    Ok(world)
+}
+
+/// When the run settled, whether it died out and its final density.
+fn run_one(mut world: World, opts: &SweepOptions) -> (Option<u64>, bool, f64) {
+    let mut tmp = world.clone();
+    let mut watch = Watch::new(Until::Stable, &world, opts.max_period);
+    for generation in 1..=opts.generations {
+        world.advance(&mut tmp);
+        if watch.check(&world, generation).is_some() {
+            break;
+        }
+    }
+    let cells = (world.width() * world.height()) as f64;
+    (
+        watch.since(),
+        world.population() == 0,
+        world.population() as f64 / cells,
+    )
+}
+
+/// The settings of each point, the first axis varying fastest.
+fn labels(opts: &SweepOptions, point: usize) -> Vec<String> {
+    let columns = opts.axes[0].len();
+    let mut labels = vec![opts.axes[0].label(point % columns)];
+    if let Some(axis) = opts.axes.get(1) {
+        labels.push(axis.label(point / columns));
+    }
+    labels
+}
+
+fn text(opts: &SweepOptions, results: &[Point], runs: usize) -> String {
+    let mut header: Vec<String> = opts.axes.iter().map(|a| a.name().to_string()).collect();
+    header.extend(MEASURES.iter().map(|m| m.name().to_string()));
+    let mut lines = vec![header];
+    for (i, point) in results.iter().enumerate() {
+        let mut line = labels(opts, i);
+        line.push(format!(
+            "{:.1}",
+            Measure::Lifespan.of(point, opts.generations)
+        ));
+        for &measure in &MEASURES[1..] {
+            line.push(format!("{:.3}", measure.of(point, opts.generations)));
+        }
+        lines.push(line);
+    }
+    let widths: Vec<usize> = (0..lines[0].len())
+        .map(|c| lines.iter().map(|l| l[c].len()).max().unwrap_or(0))
+        .collect();
+    let mut out = format!(
+        "{} runs a point of up to {} generations\n",
+        runs, opts.generations
+    );
+    for line in &lines {
+        let cells: Vec<String> = line
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{:>1$}", cell, width))
+            .collect();
+        out.push_str(cells.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn csv(opts: &SweepOptions, results: &[Point]) -> String {
+    let mut header: Vec<&str> = opts.axes.iter().map(Axis::name).collect();
+    header.extend(MEASURES.iter().map(|m| m.name()));
+    let mut out = header.join(",");
+    out.push('\n');
+    for (i, point) in results.iter().enumerate() {
+        let mut row = labels(opts, i);
+        row.extend(
+            MEASURES
+                .iter()
+                .map(|m| format!("{:.4}", m.of(point, opts.generations))),
+        );
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+fn json(opts: &SweepOptions, results: &[Point]) -> String {
+    let rows: Vec<String> = results
+        .iter()
+        .enumerate()
+        .map(|(i, point)| {
+            let mut row = String::from("  {");
+            for (axis, label) in opts.axes.iter().zip(labels(opts, i)) {
+                let value = match axis {
+                    Axis::Birth(_) => format!("\"{}\"", label),
+                    _ => label,
+                };
+                let _ = write!(row, "\"{}\":{},", axis.name(), value);
+            }
+            let measures: Vec<String> = MEASURES
+                .iter()
+                .map(|m| format!("\"{}\":{:.4}", m.name(), m.of(point, opts.generations)))
+                .collect();
+            row.push_str(&measures.join(","));
+            row.push('}');
+            row
+        })
+        .collect();
+    format!("[\n{}\n]\n", rows.join(",\n"))
+}
+
+/// A block of gray for each point, the least value black and the most white,
+/// the first axis across and the second down.
+fn write_heatmap(
+    path: &str,
+    columns: usize,
+    rows: usize,
+    values: &[f64],
+    comment: &str,
+) -> Result<(), String> {
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let (width, height) = (columns * POINT_SIZE, rows * POINT_SIZE);
+    let mut rgb = vec![0u8; width * height * 3];
+    for (i, pixel) in rgb.chunks_mut(3).enumerate() {
+        let (x, y) = (i % width / POINT_SIZE, i / width / POINT_SIZE);
+        let value = values[y * columns + x];
+        let shade = if max > min {
+            ((value - min) / (max - min) * 255.0).round() as u8
+        } else {
+            128
+        };
+        pixel.copy_from_slice(&[shade; 3]);
+    }
+    png::write(path, width, height, &rgb, comment)
+}
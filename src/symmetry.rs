@@ -0,0 +1,137 @@
+//! The symmetry classes patterns are sorted into, as in soup searches.
+
+use crate::Pattern;
+
+type Transform = fn(i64, i64) -> (i64, i64);
+
+/// The eight ways to turn or mirror a square, on coordinates centered on it.
+const TRANSFORMS: [Transform; 8] = [
+    |x, y| (x, y),
+    |x, y| (-y, x),
+    |x, y| (-x, -y),
+    |x, y| (y, -x),
+    |x, y| (-x, y),
+    |x, y| (x, -y),
+    |x, y| (y, x),
+    |x, y| (-y, -x),
+];
+const HALF_TURN: usize = 2;
+const QUARTER_TURN: usize = 1;
+const MIRRORS: [usize; 2] = [4, 5];
+const DIAGONALS: [usize; 2] = [6, 7];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Symmetry {
+    /// None at all.
+    C1,
+    /// The same after a half turn.
+    C2,
+    /// The same after a quarter turn.
+    C4,
+    /// Mirrored across a line parallel to an edge.
+    D2,
+    /// Mirrored across a diagonal.
+    D2Diagonal,
+    /// Mirrored across both lines parallel to the edges.
+    D4,
+    /// Mirrored across both diagonals.
+    D4Diagonal,
+    /// Every turn and mirror image the same.
+    D8,
+}
+
+impl Symmetry {
+    pub fn parse(s: &str) -> Result<Symmetry, String> {
+        match s {
+            "none" | "c1" => Ok(Symmetry::C1),
+            "c2" => Ok(Symmetry::C2),
+            "c4" => Ok(Symmetry::C4),
+            "d2" => Ok(Symmetry::D2),
+            "d2x" => Ok(Symmetry::D2Diagonal),
+            "d4" => Ok(Symmetry::D4),
+            "d4x" => Ok(Symmetry::D4Diagonal),
+            "d8" => Ok(Symmetry::D8),
+            _ => Err(format!(
+                "invalid symmetry '{}', expected c1, c2, c4, d2, d2x, d4, d4x or d8",
+                s
+            )),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Symmetry::C1 => "c1",
+            Symmetry::C2 => "c2",
+            Symmetry::C4 => "c4",
+            Symmetry::D2 => "d2",
+            Symmetry::D2Diagonal => "d2x",
+            Symmetry::D4 => "d4",
+            Symmetry::D4Diagonal => "d4x",
+            Symmetry::D8 => "d8",
+        }
+    }
+
+    /// Indices into `TRANSFORMS` of the turns and mirrors that leave a
+    /// pattern of this class unchanged, for the left to right mirror and the
+    /// main diagonal where there is a choice.
+    fn transforms(self) -> &'static [usize] {
+        match self {
+            Symmetry::C1 => &[0],
+            Symmetry::C2 => &[0, 2],
+            Symmetry::C4 => &[0, 1, 2, 3],
+            Symmetry::D2 => &[0, 4],
+            Symmetry::D2Diagonal => &[0, 6],
+            Symmetry::D4 => &[0, 2, 4, 5],
+            Symmetry::D4Diagonal => &[0, 2, 6, 7],
+            Symmetry::D8 => &[0, 1, 2, 3, 4, 5, 6, 7],
+        }
+    }
+
+    /// Where the cell at `x`, `y` of a `size` square goes under each of the
+    /// class's turns and mirrors, itself included.
+    pub fn images(self, x: usize, y: usize, size: usize) -> Vec<(usize, usize)> {
+        // doubled so the center of an even square is a whole number too
+        let far = size as i64 - 1;
+        let (cx, cy) = (2 * x as i64 - far, 2 * y as i64 - far);
+        self.transforms()
+            .iter()
+            .map(|&t| {
+                let (tx, ty) = TRANSFORMS[t](cx, cy);
+                (((tx + far) / 2) as usize, ((ty + far) / 2) as usize)
+            })
+            .collect()
+    }
+
+    /// The largest class the pattern's live cells belong to, ignoring where
+    /// they sit in the pattern.
+    pub fn of(pattern: &Pattern) -> Symmetry {
+        let pattern = pattern.crop();
+        let (w, h) = (pattern.width() as i64, pattern.height() as i64);
+        let mut cells = Vec::new();
+        for y in 0..pattern.height() {
+            for x in 0..pattern.width() {
+                if pattern.get(x, y) {
+                    cells.push((2 * x as i64 - (w - 1), 2 * y as i64 - (h - 1)));
+                }
+            }
+        }
+        cells.sort_unstable();
+        let keeps = |t: usize| {
+            let mut moved: Vec<_> = cells.iter().map(|&(x, y)| TRANSFORMS[t](x, y)).collect();
+            moved.sort_unstable();
+            moved == cells
+        };
+        let mirrors = MIRRORS.iter().filter(|&&t| keeps(t)).count();
+        let diagonals = DIAGONALS.iter().filter(|&&t| keeps(t)).count();
+        match (keeps(QUARTER_TURN), keeps(HALF_TURN)) {
+            (true, _) if mirrors > 0 => Symmetry::D8,
+            (true, _) => Symmetry::C4,
+            (_, true) if mirrors == 2 => Symmetry::D4,
+            (_, true) if diagonals == 2 => Symmetry::D4Diagonal,
+            (_, true) => Symmetry::C2,
+            _ if mirrors > 0 => Symmetry::D2,
+            _ if diagonals > 0 => Symmetry::D2Diagonal,
+            _ => Symmetry::C1,
+        }
+    }
+}
@@ -0,0 +1,339 @@
+//! Golly's `.rule` files, for the rules the community has written as
+//! tables. A file names its rule after `@RULE`, lists transitions under
+//! `@TABLE` and may give each state a color under `@COLORS`:
+//!
+//! ```text
+//! @RULE Cells
+//! @TABLE
+//! n_states:2
+//! neighborhood:Moore
+//! symmetries:rotate4reflect
+//! var a={0,1}
+//! # the cell, then N, NE, E, SE, S, SW, W and NW, then what it becomes
+//! 0,1,1,1,0,0,0,0,0,1
+//! 1,a,a,0,0,0,0,0,0,0
+//! @COLORS
+//! 0 48 48 48
+//! 1 255 200 0
+//! ```
+//!
+//! The first transition that matches a cell and its neighbors, turned or
+//! mirrored as the symmetries allow, says what it becomes, and a cell
+//! nothing matches stays as it is. A variable named twice in a transition
+//! stands for the same state both times. Cells here are alive or dead, so
+//! only tables of two states can be loaded, over the Moore or von Neumann
+//! neighborhood. Once loaded, a table rule is written by its name, which
+//! `Rule::parse` then takes too.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::Cell;
+
+/// The bits of neighbor masks, as `Rule::next_mask` takes them, of the
+/// neighbors clockwise from the north: N, NE, E, SE, S, SW, W and NW.
+const MOORE: [u8; 8] = [1, 2, 4, 7, 6, 5, 3, 0];
+/// Those of N, E, S and W.
+const VON_NEUMANN: [u8; 4] = [1, 4, 6, 3];
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Table {
+    name: String,
+    /// Whether a cell lives next, by its neighbor mask, dead cells first.
+    table: Vec<bool>,
+    /// The colors `@COLORS` gives the dead and the live state.
+    colors: [Option<(u8, u8, u8)>; 2],
+}
+
+/// A state or a variable in a transition.
+#[derive(Clone, Copy)]
+enum Entry {
+    State(u8),
+    Var(usize),
+}
+
+/// The ways a transition's neighbors may be rearranged and still match.
+fn symmetries(name: &str, n: usize) -> Option<Vec<Vec<usize>>> {
+    let turn = |by: usize| -> Vec<usize> { (0..n).map(|i| (i + by) % n).collect() };
+    let mirror = |p: &Vec<usize>| -> Vec<usize> { (0..n).map(|i| p[(n - i) % n]).collect() };
+    // a quarter turn is two neighbors round the Moore ring, one round von
+    // Neumann's
+    let quarter = n / 4;
+    let turns = |step: usize| -> Vec<Vec<usize>> { (0..n).step_by(step).map(turn).collect() };
+    let reflected = |ps: Vec<Vec<usize>>| -> Vec<Vec<usize>> {
+        let mirrored: Vec<Vec<usize>> = ps.iter().map(mirror).collect();
+        ps.into_iter().chain(mirrored).collect()
+    };
+    Some(match name {
+        "none" => vec![turn(0)],
+        "rotate2" => turns(n / 2),
+        "rotate4" => turns(quarter),
+        "rotate8" if n == 8 => turns(1),
+        "reflect_horizontal" => reflected(vec![turn(0)]),
+        "rotate4reflect" => reflected(turns(quarter)),
+        "rotate8reflect" if n == 8 => reflected(turns(1)),
+        // the neighbors in any order at all, which `fill` sees to itself
+        "permute" => Vec::new(),
+        _ => return None,
+    })
+}
+
+/// A state, 0 or 1, written as a number.
+fn state(s: &str) -> Option<u8> {
+    s.parse().ok().filter(|&n| n < 2)
+}
+
+impl Table {
+    /// Parses the text of a `.rule` file.
+    pub fn parse(text: &str) -> Result<Table, String> {
+        let mut name = None;
+        let mut section = "";
+        let mut colors = [None; 2];
+        let mut neighbors = 8;
+        let mut symmetry = "none".to_string();
+        let mut vars: HashMap<String, (usize, Vec<u8>)> = HashMap::new();
+        let mut next: Vec<Option<bool>> = vec![None; 512];
+        let mut tabled = false;
+        for (i, line) in text.lines().enumerate() {
+            let at = |why: String| format!("line {}: {}", i + 1, why);
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('@') {
+                let (head, rest) = header
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((header, ""));
+                section = match head {
+                    "RULE" => {
+                        name = Some(rest.trim().to_string()).filter(|n| !n.is_empty());
+                        "RULE"
+                    }
+                    "TABLE" => {
+                        tabled = true;
+                        "TABLE"
+                    }
+                    "COLORS" => "COLORS",
+                    "TREE" => {
+                        return Err(at("@TREE rules cannot be loaded, only @TABLE ones".into()))
+                    }
+                    // icons, names and anything newer are only for show
+                    _ => "",
+                };
+                continue;
+            }
+            match section {
+                "COLORS" => {
+                    let numbers: Option<Vec<u8>> =
+                        line.split_whitespace().map(|n| n.parse().ok()).collect();
+                    match numbers.as_deref() {
+                        Some(&[s, r, g, b]) if s < 2 => colors[usize::from(s)] = Some((r, g, b)),
+                        // a gradient over the live states, which here is one
+                        Some(&[r, g, b, _, _, _]) => colors[1] = Some((r, g, b)),
+                        Some(&[_, _, _, _]) => {}
+                        _ => return Err(at(format!("invalid color '{}'", line))),
+                    }
+                }
+                "TABLE" => {
+                    if let Some((key, value)) = line.split_once(':') {
+                        let value = value.trim();
+                        match key.trim() {
+                            "n_states" => match value.parse::<usize>() {
+                                Ok(2) => {}
+                                Ok(n) => {
+                                    return Err(at(format!(
+                                        "the rule has {} states, but cells here are only alive \
+                                         or dead, so only two-state tables can be loaded",
+                                        n
+                                    )))
+                                }
+                                Err(_) => return Err(at(format!("invalid n_states '{}'", value))),
+                            },
+                            "neighborhood" => {
+                                neighbors = match value {
+                                    "Moore" => 8,
+                                    "vonNeumann" => 4,
+                                    _ => {
+                                        return Err(at(format!(
+                                            "neighborhood {} cannot be loaded, only Moore or \
+                                             vonNeumann",
+                                            value
+                                        )))
+                                    }
+                                }
+                            }
+                            "symmetries" => symmetry = value.to_string(),
+                            key => return Err(at(format!("unknown setting '{}'", key))),
+                        }
+                        continue;
+                    }
+                    if let Some(var) = line.strip_prefix("var ") {
+                        let (var, values) = var
+                            .split_once('=')
+                            .ok_or_else(|| at(format!("invalid variable '{}'", line)))?;
+                        // one variable may also be set to another without braces
+                        let values = values.trim();
+                        let values = values
+                            .strip_prefix('{')
+                            .and_then(|v| v.strip_suffix('}'))
+                            .unwrap_or(values);
+                        let mut states = Vec::new();
+                        for value in values.split(',').map(str::trim) {
+                            match (state(value), vars.get(value)) {
+                                (Some(s), _) => states.push(s),
+                                (None, Some((_, more))) => states.extend(more),
+                                _ => return Err(at(format!("invalid state '{}'", value))),
+                            }
+                        }
+                        let id = vars.len();
+                        vars.insert(var.trim().to_string(), (id, states));
+                        continue;
+                    }
+                    let perms = symmetries(&symmetry, neighbors)
+                        .ok_or_else(|| at(format!("unknown symmetries '{}'", symmetry)))?;
+                    let words: Vec<&str> = if line.contains(',') {
+                        line.split(',').map(str::trim).collect()
+                    } else {
+                        // states of one digit may be run together
+                        (0..line.len()).filter_map(|i| line.get(i..i + 1)).collect()
+                    };
+                    if words.len() != neighbors + 2 {
+                        return Err(at(format!(
+                            "a transition needs {} states, got {}",
+                            neighbors + 2,
+                            words.len()
+                        )));
+                    }
+                    let entries = words
+                        .iter()
+                        .map(|&w| match (state(w), vars.get(w)) {
+                            (Some(s), _) => Ok(Entry::State(s)),
+                            (None, Some(&(id, _))) => Ok(Entry::Var(id)),
+                            _ => Err(at(format!("invalid state '{}'", w))),
+                        })
+                        .collect::<Result<Vec<Entry>, String>>()?;
+                    let values: HashMap<usize, &Vec<u8>> =
+                        vars.values().map(|(id, states)| (*id, states)).collect();
+                    fill(&mut next, &entries, &values, &perms, neighbors);
+                }
+                _ => return Err(at(format!("'{}' is outside any section", line))),
+            }
+        }
+        let name = name.ok_or("a rule file needs a name after @RULE")?;
+        if !tabled {
+            return Err(format!("{} has no @TABLE", name));
+        }
+        // a cell nothing matched stays as it is
+        let table = next
+            .iter()
+            .enumerate()
+            .map(|(i, next)| next.unwrap_or(i >= 256))
+            .collect();
+        Ok(Table {
+            name,
+            table,
+            colors,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The cell's next state with the neighbors in `mask` alive.
+    pub fn next(&self, cell: Cell, mask: u8) -> Cell {
+        match self.table[usize::from(cell.n()) * 256 + usize::from(mask)] {
+            true => Cell::Live,
+            false => Cell::Dead,
+        }
+    }
+
+    /// The color `@COLORS` gives `cell`, if it does.
+    pub fn color(&self, cell: Cell) -> Option<(u8, u8, u8)> {
+        self.colors[usize::from(cell.n())]
+    }
+}
+
+/// Sets every entry of `next` the transition `entries` gives that an
+/// earlier one didn't.
+fn fill(
+    next: &mut [Option<bool>],
+    entries: &[Entry],
+    values: &HashMap<usize, &Vec<u8>>,
+    perms: &[Vec<usize>],
+    neighbors: usize,
+) {
+    // every way of giving the transition's variables a state each
+    let mut ids: Vec<usize> = entries[..entries.len() - 1]
+        .iter()
+        .filter_map(|e| match e {
+            Entry::Var(id) => Some(*id),
+            Entry::State(_) => None,
+        })
+        .collect();
+    ids.sort_unstable();
+    ids.dedup();
+    let mut bindings = vec![HashMap::new()];
+    for id in ids {
+        bindings = bindings
+            .into_iter()
+            .flat_map(|binding: HashMap<usize, u8>| {
+                values[&id].iter().map(move |&s| {
+                    let mut binding = binding.clone();
+                    binding.insert(id, s);
+                    binding
+                })
+            })
+            .collect();
+    }
+    let bits = if neighbors == 8 {
+        &MOORE[..]
+    } else {
+        &VON_NEUMANN[..]
+    };
+    let unseen: u8 = !bits.iter().fold(0, |all, &bit| all | 1 << bit);
+    for binding in bindings {
+        let get = |e: &Entry| match *e {
+            Entry::State(s) => Some(s),
+            Entry::Var(id) => binding.get(&id).copied(),
+        };
+        let (cell, out) = match (get(&entries[0]), get(&entries[neighbors + 1])) {
+            (Some(cell), Some(out)) => (cell, out),
+            // an output variable named nowhere before can't be known
+            _ => continue,
+        };
+        let ring: Vec<u8> = entries[1..=neighbors].iter().filter_map(get).collect();
+        let arranged: Vec<Vec<u8>> = if perms.is_empty() {
+            let live = ring.iter().filter(|&&s| s == 1).count();
+            (0..1u32 << neighbors)
+                .filter(|m| m.count_ones() as usize == live)
+                .map(|m| (0..neighbors).map(|i| (m >> i & 1) as u8).collect())
+                .collect()
+        } else {
+            perms
+                .iter()
+                .map(|p| p.iter().map(|&i| ring[i]).collect())
+                .collect()
+        };
+        for ring in arranged {
+            let mask = ring
+                .iter()
+                .zip(bits)
+                .fold(0u8, |mask, (&s, &bit)| mask | s << bit);
+            // von Neumann's leaves the diagonal neighbors to be anything
+            for extra in 0..=255u8 {
+                if extra & !unseen != 0 {
+                    continue;
+                }
+                let entry = &mut next[usize::from(cell) * 256 + usize::from(mask | extra)];
+                entry.get_or_insert(out == 1);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
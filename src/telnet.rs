@@ -0,0 +1,144 @@
+//! Read-only viewing of `run` over plain TCP: everyone connected with `nc`
+//! or `telnet` sees the frames the terminal shows, redrawn in place.
+
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use conway::World;
+
+use crate::log;
+use crate::render::{self, Renderer};
+use crate::term::{Style, CLEAR, HOME};
+
+/// How long a viewer may take to accept a frame before it is let go.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type Frame = Arc<Vec<u8>>;
+
+#[derive(Default)]
+struct Viewers {
+    /// The newest frame, for whoever connects next.
+    last: Option<Frame>,
+    senders: Vec<SyncSender<Frame>>,
+    writers: Vec<JoinHandle<()>>,
+}
+
+/// Shows the frames to every viewer on a port, alongside what the terminal
+/// shows.
+pub struct Telnet {
+    port: u16,
+    style: Style,
+    viewers: Arc<Mutex<Viewers>>,
+    /// The last frame, which the run ends under.
+    rendered: String,
+}
+
+impl Telnet {
+    pub fn new(port: u16, style: Style) -> Telnet {
+        Telnet {
+            port,
+            style,
+            viewers: Arc::new(Mutex::new(Viewers::default())),
+            rendered: String::new(),
+        }
+    }
+
+    /// Shows every viewer `rendered`, a frame as the terminal gets it, with
+    /// `status` below it.
+    fn frame(&self, rendered: &str, status: &str) {
+        let mut frame = String::with_capacity(rendered.len() * 2);
+        frame.push_str(HOME);
+        // telnet wants carriage returns, and each line clears what is left
+        // of a longer one before it
+        for line in rendered.lines().chain(std::iter::once(status)) {
+            frame.push_str(line);
+            frame.push_str("\x1b[K\r\n");
+        }
+        let frame = Arc::new(frame.into_bytes());
+        let mut viewers = self.viewers.lock().unwrap();
+        viewers.senders.retain(|sender| {
+            !matches!(
+                sender.try_send(Arc::clone(&frame)),
+                Err(TrySendError::Disconnected(_))
+            )
+        });
+        viewers.last = Some(frame);
+    }
+}
+
+impl Renderer for Telnet {
+    /// Accepts viewers on the port of every interface from now on.
+    fn init(&mut self, _world: &World) -> Result<(), String> {
+        let port = self.port;
+        let listener = TcpListener::bind(("0.0.0.0", port))
+            .map_err(|e| format!("cannot listen on port {}: {}", port, e))?;
+        let accepting = Arc::clone(&self.viewers);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let peer = stream
+                    .peer_addr()
+                    .map_or_else(|_| "?".to_string(), |a| a.to_string());
+                log::debug("telnet", &format!("viewer {} connected", peer));
+                // a few frames of slack; one that falls further behind skips
+                // frames rather than holding up the run
+                let (sender, receiver) = mpsc::sync_channel(4);
+                let mut viewers = accepting.lock().unwrap();
+                if let Some(last) = &viewers.last {
+                    let _ = sender.try_send(Arc::clone(last));
+                }
+                viewers.senders.push(sender);
+                viewers.writers.push(thread::spawn(move || {
+                    let gone = watch(stream, receiver).err();
+                    let why = gone.map_or_else(String::new, |e| format!(": {}", e));
+                    log::debug("telnet", &format!("viewer {} left{}", peer, why));
+                }));
+            }
+        });
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &render::Frame) -> Result<(), String> {
+        self.rendered = match frame.previous {
+            Some(previous) => self.style.render_diff(frame.world, previous),
+            None => self.style.render(frame.world),
+        };
+        let status = format!(
+            "generation {}, population {}",
+            frame.generation,
+            frame.world.population()
+        );
+        self.frame(&self.rendered, &status);
+        Ok(())
+    }
+
+    fn stopped(&mut self, reason: &str) -> Result<(), String> {
+        self.frame(&self.rendered, reason);
+        Ok(())
+    }
+
+    /// Lets the viewers see the last frames before the run ends.
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        let writers = {
+            let mut viewers = self.viewers.lock().unwrap();
+            viewers.senders.clear();
+            std::mem::take(&mut viewers.writers)
+        };
+        for writer in writers {
+            let _ = writer.join();
+        }
+        Ok(())
+    }
+}
+
+fn watch(mut stream: TcpStream, frames: Receiver<Frame>) -> io::Result<()> {
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    stream.write_all(CLEAR.as_bytes())?;
+    for frame in frames {
+        stream.write_all(&frame)?;
+    }
+    Ok(())
+}
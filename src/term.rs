@@ -0,0 +1,816 @@
+//! Bare ANSI terminal helpers.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use conway::{history, Cell, Rule, World};
+
+use crate::caps::Depth;
+use crate::interrupt;
+
+pub const CLEAR: &str = "\x1b[2J";
+pub const HOME: &str = "\x1b[H";
+pub const RESET: &str = "\x1b[0m";
+
+/// Glyphs of a trail from the newest to the oldest.
+const TRAIL: [&str; 4] = ["o", "*", "+", ":"];
+/// The glyphs of the ghost border without colors.
+const GHOST_LIVE: &str = "o";
+const GHOST_DEAD: &str = " ";
+/// The glyph of a dead cell in the footprint without colors.
+const FOOTPRINT_DEAD: &str = ",";
+/// Glyphs of downsampled blocks up to a quarter live, up to half, up to
+/// three quarters and beyond.
+const SHADES: [&str; 4] = ["\u{2591}", "\u{2592}", "\u{2593}", "\u{2588}"];
+/// The same on a terminal without Unicode.
+const ASCII_SHADES: [&str; 4] = [":", "+", "*", "#"];
+/// Glyphs of the states of LifeHistory after off and on, from history to
+/// boundary.
+const HISTORY: [&str; 5] = [":", "#", "x", "@", "%"];
+/// The glyph of wall cells, as plaintext patterns write them, and their
+/// color in terminals and images.
+pub const WALL: &str = "#";
+pub const WALL_RGB: (u8, u8, u8) = (140, 140, 140);
+/// What `--color density` fades live cells through: alone, with the three
+/// neighbors Life needs, and with all eight.
+const LONELY: (u8, u8, u8) = (70, 110, 255);
+const BALANCED: (u8, u8, u8) = (80, 230, 110);
+const CROWDED: (u8, u8, u8) = (255, 60, 50);
+
+/// The colors the terminal shows, as `caps::Depth` by its place, all of
+/// them until `set_depth` says otherwise.
+static DEPTH: AtomicU8 = AtomicU8::new(Depth::Rgb as u8);
+
+/// From now on colors are written as the nearest the terminal can show, or
+/// not at all with `Depth::None`.
+pub fn set_depth(depth: Depth) {
+    DEPTH.store(depth as u8, Ordering::Relaxed);
+}
+
+/// Whether escapes for colors are written at all.
+pub fn shows_colors() -> bool {
+    depth() != Depth::None
+}
+
+fn depth() -> Depth {
+    match DEPTH.load(Ordering::Relaxed) {
+        0 => Depth::None,
+        1 => Depth::Basic,
+        2 => Depth::Indexed,
+        _ => Depth::Rgb,
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Color {
+    /// One of the eight basic colors, 0 (black) to 7 (white).
+    Basic(u8),
+    /// An entry of the 256-color palette.
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+/// The basic colors by number, as `Color::parse` takes them.
+const NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+impl Color {
+    /// Accepts a basic color name, a palette index or `#rrggbb`.
+    pub fn parse(s: &str) -> Result<Color, String> {
+        if let Some(i) = NAMES.iter().position(|&n| n == s) {
+            return Ok(Color::Basic(i as u8));
+        }
+        if let Ok(n) = s.parse::<u8>() {
+            return Ok(Color::Indexed(n));
+        }
+        let hex = s.strip_prefix('#').filter(|h| h.len() == 6);
+        if let Some(Ok(rgb)) = hex.map(|h| u32::from_str_radix(h, 16)) {
+            return Ok(Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8));
+        }
+        Err(format!(
+            "invalid color '{}', expected a name like green, 0-255 or #rrggbb",
+            s
+        ))
+    }
+
+    pub fn foreground(self) -> String {
+        match self.nearest(depth()) {
+            Some(Color::Basic(n)) => format!("\x1b[{}m", 30 + n),
+            Some(Color::Indexed(n)) => format!("\x1b[38;5;{}m", n),
+            Some(Color::Rgb(r, g, b)) => format!("\x1b[38;2;{};{};{}m", r, g, b),
+            None => String::new(),
+        }
+    }
+
+    pub fn background(self) -> String {
+        match self.nearest(depth()) {
+            Some(Color::Basic(n)) => format!("\x1b[{}m", 40 + n),
+            Some(Color::Indexed(n)) => format!("\x1b[48;5;{}m", n),
+            Some(Color::Rgb(r, g, b)) => format!("\x1b[48;2;{};{};{}m", r, g, b),
+            None => String::new(),
+        }
+    }
+
+    /// The color itself if a terminal of `depth` shows it, else the closest
+    /// one that it does.
+    pub fn nearest(self, depth: Depth) -> Option<Color> {
+        let distance = |c: Color| {
+            let ((r, g, b), (r2, g2, b2)) = (self.rgb(), c.rgb());
+            let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+            d(r, r2) + d(g, g2) + d(b, b2)
+        };
+        match (depth, self) {
+            (Depth::None, _) => None,
+            (Depth::Rgb, color) | (_, color @ Color::Basic(_)) => Some(color),
+            (Depth::Indexed, color @ Color::Indexed(_)) => Some(color),
+            (Depth::Indexed, Color::Rgb(r, g, b)) => {
+                // the nearest level of the cube for each channel, or the
+                // nearest gray of the ramp
+                let step = |v: u8| match v {
+                    0..=47 => 0,
+                    48..=114 => 1,
+                    v => (v - 35) / 40,
+                };
+                let cube = Color::Indexed(16 + 36 * step(r) + 6 * step(g) + step(b));
+                let mean = (u16::from(r) + u16::from(g) + u16::from(b)) / 3;
+                let gray = Color::Indexed(232 + (mean.saturating_sub(3) / 10).min(23) as u8);
+                Some(if distance(gray) < distance(cube) {
+                    gray
+                } else {
+                    cube
+                })
+            }
+            (Depth::Basic, _) => (0..8).map(Color::Basic).min_by_key(|&c| distance(c)),
+        }
+    }
+
+    /// The color as xterm shows it, for images.
+    pub fn rgb(self) -> (u8, u8, u8) {
+        const BASIC: [(u8, u8, u8); 16] = [
+            (0, 0, 0),
+            (205, 0, 0),
+            (0, 205, 0),
+            (205, 205, 0),
+            (0, 0, 238),
+            (205, 0, 205),
+            (0, 205, 205),
+            (229, 229, 229),
+            (127, 127, 127),
+            (255, 0, 0),
+            (0, 255, 0),
+            (255, 255, 0),
+            (92, 92, 255),
+            (255, 0, 255),
+            (0, 255, 255),
+            (255, 255, 255),
+        ];
+        // the 6x6x6 cube's levels, then a ramp of grays
+        let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+        match self {
+            Color::Basic(n) | Color::Indexed(n @ 0..=15) => BASIC[usize::from(n % 16)],
+            Color::Indexed(n @ 16..=231) => {
+                let n = n - 16;
+                (level(n / 36), level(n / 6 % 6), level(n % 6))
+            }
+            Color::Indexed(n) => {
+                let gray = 8 + (n - 232) * 10;
+                (gray, gray, gray)
+            }
+            Color::Rgb(r, g, b) => (r, g, b),
+        }
+    }
+}
+
+/// `t` of the way from `a` to `b`.
+pub fn mix(a: (u8, u8, u8), b: (u8, u8, u8), t: f64) -> (u8, u8, u8) {
+    let channel = |a: u8, b: u8| (f64::from(a) + (f64::from(b) - f64::from(a)) * t) as u8;
+    (channel(a.0, b.0), channel(a.1, b.1), channel(a.2, b.2))
+}
+
+/// The color as `parse` takes it.
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Color::Basic(n) => write!(f, "{}", NAMES[usize::from(n % 8)]),
+            Color::Indexed(n) => write!(f, "{}", n),
+            Color::Rgb(r, g, b) => write!(f, "#{:02x}{:02x}{:02x}", r, g, b),
+        }
+    }
+}
+
+/// How many cells of the world each character of the text view shows.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Zoom {
+    /// A cell a character while the world fits the terminal, and blocks just
+    /// big enough to fit it when it doesn't.
+    Fit,
+    /// Blocks of this many columns and rows, 1x1 showing every cell.
+    Blocks(usize, usize),
+}
+
+impl Zoom {
+    /// Accepts `fit`, `N` for N by N blocks or `NxM`.
+    pub fn parse(s: &str) -> Result<Zoom, String> {
+        if s == "fit" {
+            return Ok(Zoom::Fit);
+        }
+        let number = |n: &str| n.parse::<usize>().ok().filter(|&n| n > 0);
+        let blocks = match s.split_once('x') {
+            Some((w, h)) => number(w).zip(number(h)),
+            None => number(s).map(|n| (n, n)),
+        };
+        blocks.map(|(w, h)| Zoom::Blocks(w, h)).ok_or_else(|| {
+            format!(
+                "invalid zoom '{}', expected fit, N or NxM cells a character",
+                s
+            )
+        })
+    }
+}
+
+/// The zoom as `parse` takes it.
+impl fmt::Display for Zoom {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Zoom::Fit => write!(f, "fit"),
+            Zoom::Blocks(w, h) if w == h => write!(f, "{}", w),
+            Zoom::Blocks(w, h) => write!(f, "{}x{}", w, h),
+        }
+    }
+}
+
+/// The cells that have been alive in any world marked so far.
+#[derive(Clone, Debug, Default)]
+pub struct Footprint {
+    width: usize,
+    cells: Vec<bool>,
+}
+
+impl Footprint {
+    /// Adds the live cells of `world`, starting over if its size changed.
+    pub fn mark(&mut self, world: &World) {
+        let (w, h) = (world.width(), world.height());
+        if (self.width, self.cells.len()) != (w, w * h) {
+            self.width = w;
+            self.cells = vec![false; w * h];
+        }
+        for y in 0..h {
+            for x in 0..w {
+                if world.get(x, y) == Cell::Live {
+                    self.cells[y * w + x] = true;
+                }
+            }
+        }
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x < self.width && self.cells.get(y * self.width + x) == Some(&true)
+    }
+
+    /// How many cells have been alive.
+    pub fn area(&self) -> usize {
+        self.cells.iter().filter(|&&c| c).count()
+    }
+}
+
+/// How cells look in the terminal.
+#[derive(Clone, Debug)]
+pub struct Style {
+    pub live: String,
+    pub dead: String,
+    pub live_color: Option<Color>,
+    pub dead_color: Option<Color>,
+    /// Colors of the cells `render_diff` marks as born and as died.
+    pub birth_color: Option<Color>,
+    pub death_color: Option<Color>,
+    /// The colors a trail fades through from the newest cell to the oldest,
+    /// and the color of the ghost border.
+    pub trail_colors: (Color, Color),
+    pub ghost_color: Color,
+    /// The background of the cells in the footprint.
+    pub footprint_color: Color,
+    /// The color of the `--timing` line.
+    pub hud_color: Option<Color>,
+    /// The color long-lived cells fade to in `render --by-age`.
+    pub old_color: Option<Color>,
+    /// Generations a cell that died stays in sight, fading, for
+    /// `render_trails`.
+    pub trails: u64,
+    /// Draw a border of the cells across each edge, dimmed, that the ones
+    /// at the edges of the world have as neighbors.
+    pub ghosts: bool,
+    /// Whether trails and ghosts are dimmed with grays, and the states of
+    /// LifeHistory colored, not only told apart by glyphs.
+    pub dim_colors: bool,
+    /// Color live cells by how many live neighbors they have, from blue for
+    /// lonely ones through green to red for crowded ones, unless colors are off.
+    pub density: bool,
+    /// The cells alive in any frame drawn so far, to show on a faint
+    /// background, once `run --footprint` asks for it.
+    pub footprint: Option<Footprint>,
+    /// The point of the world drawn at the top left, wrapping around, and
+    /// how far it moves each generation with `run --scroll`. Fractions move
+    /// it a cell once they add up to one.
+    pub origin: (f64, f64),
+    pub scroll: Option<(f64, f64)>,
+    pub zoom: Zoom,
+    /// Whether the terminal shows Unicode, for the shades of blocks, braille
+    /// and the sparkline, which fall back to ASCII without it.
+    pub unicode: bool,
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        Style {
+            live: "O".to_string(),
+            dead: ".".to_string(),
+            live_color: None,
+            dead_color: None,
+            birth_color: Some(Color::Basic(2)),
+            death_color: Some(Color::Basic(1)),
+            trail_colors: (Color::Indexed(250), Color::Indexed(236)),
+            ghost_color: Color::Indexed(240),
+            footprint_color: Color::Indexed(235),
+            hud_color: None,
+            old_color: None,
+            trails: 0,
+            ghosts: false,
+            dim_colors: true,
+            density: false,
+            footprint: None,
+            origin: (0.0, 0.0),
+            scroll: None,
+            zoom: Zoom::Fit,
+            unicode: true,
+        }
+    }
+}
+
+impl Style {
+    /// The colors of live and dead cells in images, gray unless set.
+    pub fn live_rgb(&self) -> (u8, u8, u8) {
+        self.live_color.map_or((230, 230, 230), Color::rgb)
+    }
+
+    pub fn dead_rgb(&self) -> (u8, u8, u8) {
+        self.dead_color.map_or((24, 24, 24), Color::rgb)
+    }
+
+    /// Colors the cells as the rule's file asks, unless colors are off.
+    pub fn use_rule_colors(&mut self, rule: Rule) {
+        if !self.dim_colors {
+            return;
+        }
+        let rgb = |cell| rule.color(cell).map(|(r, g, b)| Color::Rgb(r, g, b));
+        self.live_color = rgb(Cell::Live).or(self.live_color);
+        self.dead_color = rgb(Cell::Dead).or(self.dead_color);
+    }
+
+    /// The color of a trail `since` generations old out of `span`. Between
+    /// two grays of the palette it stays in the palette.
+    fn trail_color(&self, since: u64, span: u64) -> Color {
+        match self.trail_colors {
+            (Color::Indexed(first @ 232..=255), Color::Indexed(last @ 232..=255)) => {
+                let step = (i64::from(first) - i64::from(last)) * since as i64 / span as i64;
+                Color::Indexed((i64::from(first) - step) as u8)
+            }
+            (first, last) => {
+                let (r, g, b) = mix(first.rgb(), last.rgb(), since as f64 / span as f64);
+                Color::Rgb(r, g, b)
+            }
+        }
+    }
+
+    /// The glyph and color of the live cell at (x, y).
+    fn live_cell(&self, world: &World, x: usize, y: usize) -> (&str, Option<Color>) {
+        if !(self.density && self.dim_colors) {
+            return (&self.live, self.live_color);
+        }
+        let n = f64::from(world.count_neighbors(x, y));
+        let (r, g, b) = if n <= 3.0 {
+            mix(LONELY, BALANCED, n / 3.0)
+        } else {
+            mix(BALANCED, CROWDED, (n - 3.0) / 5.0)
+        };
+        (&self.live, Some(Color::Rgb(r, g, b)))
+    }
+
+    /// Like `World::as_string`, with this style's glyphs and colors, and
+    /// glyphs and colors of their own for walls and the states of
+    /// LifeHistory.
+    pub fn render(&self, world: &World) -> String {
+        self.render_cells(world, |x, y| self.cell(world, x, y))
+    }
+
+    /// The glyph and color `render` shows the cell at (x, y) with.
+    pub fn cell(&self, world: &World, x: usize, y: usize) -> (&str, Option<Color>) {
+        let state = world
+            .history(x, y)
+            .filter(|&s| s > history::ON)
+            .and_then(|state| {
+                let glyph = HISTORY.get(usize::from(state - history::HISTORY))?;
+                let (r, g, b) = history::COLORS[usize::from(state)];
+                Some((*glyph, Color::Rgb(r, g, b)))
+            });
+        match (world.get(x, y), state) {
+            _ if world.is_wall(x, y) => {
+                let (r, g, b) = WALL_RGB;
+                (WALL, Some(Color::Rgb(r, g, b)).filter(|_| self.dim_colors))
+            }
+            (_, Some((glyph, color))) => (glyph, Some(color).filter(|_| self.dim_colors)),
+            (Cell::Dead, None) => (&self.dead, self.dead_color),
+            (Cell::Live, None) => self.live_cell(world, x, y),
+        }
+    }
+
+    /// Like `render`, but cells born since `previous` show as a `+` and cells
+    /// that died as a `-`, each in its own color.
+    pub fn render_diff(&self, world: &World, previous: &World) -> String {
+        let report = match world.diff(previous) {
+            Ok(report) => report,
+            Err(_) => return self.render(world),
+        };
+        self.render_cells(world, |x, y| {
+            match (report.contains(x, y), world.get(x, y)) {
+                (true, Cell::Live) => ("+", self.birth_color),
+                (true, Cell::Dead) => ("-", self.death_color),
+                (false, Cell::Dead) => (&self.dead, self.dead_color),
+                (false, Cell::Live) => self.live_cell(world, x, y),
+            }
+        })
+    }
+
+    /// Like `render`, but cells that died in the last `trails` generations
+    /// fade out instead of vanishing, going by the ages of a world that has
+    /// tracked them since generation `start`.
+    pub fn render_trails(&self, world: &World, start: u64) -> String {
+        let span = self.trails.max(1);
+        self.render_cells(world, |x, y| {
+            let since = world
+                .last_changed(x, y)
+                .filter(|&changed| changed > start)
+                .map(|changed| world.generation() - changed);
+            match (world.get(x, y), since) {
+                (Cell::Live, _) => self.live_cell(world, x, y),
+                (Cell::Dead, Some(since)) if since < span => {
+                    let glyph = TRAIL[(since * TRAIL.len() as u64 / span) as usize];
+                    let color = self.trail_color(since, span);
+                    (glyph, Some(color).filter(|_| self.dim_colors))
+                }
+                (Cell::Dead, _) => (&self.dead, self.dead_color),
+            }
+        })
+    }
+
+    /// The columns and rows of cells each character stands for, which
+    /// `Zoom::Fit` works out from the room the terminal has, if known.
+    pub fn blocks(&self, world: &World, room: Option<(usize, usize)>) -> (usize, usize) {
+        match (self.zoom, room) {
+            (Zoom::Blocks(w, h), _) => (w, h),
+            (Zoom::Fit, Some((columns, rows))) => {
+                let border = 2 * usize::from(self.ghosts);
+                let (w, h) = (world.width() + border, world.height() + border);
+                if w <= columns && h <= rows {
+                    (1, 1)
+                } else {
+                    (
+                        world.width().div_ceil(columns.max(1)),
+                        world.height().div_ceil(rows.max(1)),
+                    )
+                }
+            }
+            (Zoom::Fit, None) => (1, 1),
+        }
+    }
+
+    /// The world downsampled, each character a block of `bw` by `bh` cells
+    /// shaded by how many of them are live, in a theme's colors faded from
+    /// dead to live as well. Blocks only know live and dead, so there are no
+    /// trails, ghosts or footprint.
+    pub fn render_blocks(&self, world: &World, (bw, bh): (usize, usize)) -> String {
+        let (w, h) = (world.width(), world.height());
+        let (ox, oy) = self.offset(world);
+        let mut s = String::with_capacity(h.div_ceil(bh) * (w.div_ceil(bw) + 1));
+        for by in (0..h).step_by(bh) {
+            let mut current = None;
+            for bx in (0..w).step_by(bw) {
+                let (rows, columns) = (by..h.min(by + bh), bx..w.min(bx + bw));
+                let cells = rows.len() * columns.len();
+                let live = rows
+                    .flat_map(|y| columns.clone().map(move |x| (x, y)))
+                    .filter(|&(x, y)| {
+                        let (wx, wy) = world.wrap(x as i64 + ox, y as i64 + oy);
+                        world.get(wx, wy) == Cell::Live
+                    })
+                    .count();
+                let (glyph, color) = match live {
+                    0 => (self.dead.as_str(), self.dead_color),
+                    _ => {
+                        let shades = if self.unicode { SHADES } else { ASCII_SHADES };
+                        let shade = shades[(live * SHADES.len()).div_ceil(cells) - 1];
+                        let color = match self.live_color {
+                            Some(_) if self.dim_colors => {
+                                let t = live as f64 / cells as f64;
+                                let (r, g, b) = mix(self.dead_rgb(), self.live_rgb(), t);
+                                Some(Color::Rgb(r, g, b))
+                            }
+                            color => color,
+                        };
+                        (shade, color)
+                    }
+                };
+                if color != current {
+                    if current.is_some() {
+                        s.push_str(RESET);
+                    }
+                    if let Some(color) = color {
+                        s.push_str(&color.foreground());
+                    }
+                    current = color;
+                }
+                s.push_str(glyph);
+            }
+            if current.is_some() {
+                s.push_str(RESET);
+            }
+            s.push('\n');
+        }
+        s
+    }
+
+    /// The cell `origin` puts at the top left.
+    fn offset(&self, world: &World) -> (i64, i64) {
+        let (w, h) = (world.width() as f64, world.height() as f64);
+        (
+            self.origin.0.rem_euclid(w).floor() as i64,
+            self.origin.1.rem_euclid(h).floor() as i64,
+        )
+    }
+
+    fn render_cells<'a>(
+        &self,
+        world: &World,
+        cell: impl Fn(usize, usize) -> (&'a str, Option<Color>),
+    ) -> String {
+        let (w, h) = (world.width() as i64, world.height() as i64);
+        let border = i64::from(self.ghosts);
+        let (ox, oy) = self.offset(world);
+        let mut s = String::with_capacity(world.height() * (world.width() + 1));
+        for y in -border..h + border {
+            let mut current = (None, None);
+            for x in -border..w + border {
+                let (wx, wy) = world.wrap(x + ox, y + oy);
+                let (mut glyph, mut color) = cell(wx, wy);
+                let mut background = None;
+                if !(0..w).contains(&x) || !(0..h).contains(&y) {
+                    let live = world.get(wx, wy) == Cell::Live;
+                    if self.dim_colors {
+                        color = Some(self.ghost_color);
+                    } else {
+                        glyph = if live { GHOST_LIVE } else { GHOST_DEAD };
+                        color = None;
+                    }
+                } else if self.footprint.as_ref().is_some_and(|f| f.contains(wx, wy)) {
+                    if self.dim_colors {
+                        background = Some(self.footprint_color);
+                    } else if glyph == self.dead {
+                        glyph = FOOTPRINT_DEAD;
+                    }
+                }
+                // only emit escapes when the colors actually change
+                if (color, background) != current {
+                    if current.1.is_some() || (current.0.is_some() && color.is_none()) {
+                        s.push_str(RESET);
+                    }
+                    if let Some(color) = color {
+                        s.push_str(&color.foreground());
+                    }
+                    if let Some(background) = background {
+                        s.push_str(&background.background());
+                    }
+                    current = (color, background);
+                }
+                s.push_str(glyph);
+            }
+            if current != (None, None) {
+                s.push_str(RESET);
+            }
+            s.push('\n');
+        }
+        s
+    }
+}
+
+/// Columns and rows of the terminal the frames go to: stdout's, or with it
+/// piped elsewhere, stderr's, as `output::screen` writes there then.
+pub fn size() -> Option<(usize, usize)> {
+    ioctl_size().or_else(|| {
+        let cols = std::env::var("COLUMNS").ok()?.parse().ok()?;
+        let rows = std::env::var("LINES").ok()?.parse().ok()?;
+        Some((cols, rows))
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn ioctl_size() -> Option<(usize, usize)> {
+    use std::io::IsTerminal;
+    use std::os::raw::{c_int, c_ulong};
+
+    #[repr(C)]
+    struct Winsize {
+        rows: u16,
+        cols: u16,
+        xpixel: u16,
+        ypixel: u16,
+    }
+
+    #[cfg(target_os = "linux")]
+    const TIOCGWINSZ: c_ulong = 0x5413;
+    #[cfg(target_os = "macos")]
+    const TIOCGWINSZ: c_ulong = 0x4008_7468;
+
+    extern "C" {
+        fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+    }
+
+    let mut ws = Winsize {
+        rows: 0,
+        cols: 0,
+        xpixel: 0,
+        ypixel: 0,
+    };
+    let fd = if io::stdout().is_terminal() { 1 } else { 2 };
+    if unsafe { ioctl(fd, TIOCGWINSZ, &mut ws) } != 0 || ws.cols == 0 || ws.rows == 0 {
+        return None;
+    }
+    Some((ws.cols as usize, ws.rows as usize))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn ioctl_size() -> Option<(usize, usize)> {
+    None
+}
+
+/// What a frame drawn over the last one starts with: going to the top left,
+/// after clearing the screen if the terminal was resized since, as the last
+/// frame will have been wrapped into garbage.
+pub fn frame_start() -> String {
+    if interrupt::resized() {
+        format!("{}{}", CLEAR, HOME)
+    } else {
+        HOME.to_string()
+    }
+}
+
+/// Redraws the world over the previous frame instead of scrolling, optionally
+/// tinted with a 256-color palette index. No newline follows the last row, so
+/// a world as tall as the terminal doesn't scroll it.
+pub fn draw_in_place(world: &World, color: Option<u8>) -> io::Result<()> {
+    let mut frame = String::with_capacity((world.width() + 2) * world.height() + 16);
+    frame.push_str(HOME);
+    if let Some(color) = color {
+        frame.push_str(&format!("\x1b[38;5;{}m", color));
+    }
+    for y in 0..world.height() {
+        if y > 0 {
+            frame.push_str("\r\n");
+        }
+        for x in 0..world.width() {
+            frame.push(match world.get(x, y) {
+                Cell::Dead => ' ',
+                Cell::Live => 'O',
+            });
+        }
+    }
+    if color.is_some() {
+        frame.push_str(RESET);
+    }
+    let mut out = io::stdout();
+    out.write_all(frame.as_bytes())?;
+    out.flush()
+}
+
+fn stty(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()
+        .map_err(|e| format!("cannot run stty: {}", e))?;
+    if !output.status.success() {
+        return Err("this needs an interactive terminal".to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Unbuffered, unechoed keyboard input for as long as it is alive.
+pub struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    pub fn enable() -> Result<RawMode, String> {
+        let saved = stty(&["-g"])?;
+        // without signals, so Ctrl-Z and Ctrl-Y come through as keys
+        stty(&["-icanon", "-echo", "-isig", "min", "1"])?;
+        print!("{}\x1b[?25l", CLEAR);
+        Ok(RawMode { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        print!("{}{}\x1b[?25h", RESET, CLEAR);
+        let _ = io::stdout().flush();
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Char(u8),
+    /// No key, but the terminal was resized while waiting for one.
+    Resize,
+    /// The left button pressed or dragged over a column and row, from 0, once
+    /// `MouseReports` has asked for them.
+    Click(usize, usize),
+}
+
+pub fn read_key(input: &mut impl Read) -> io::Result<Key> {
+    let mut byte = [0u8];
+    loop {
+        match input.read(&mut byte) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(_) => break,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {
+                if interrupt::resized() {
+                    return Ok(Key::Resize);
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    if byte[0] != 0x1b {
+        return Ok(Key::Char(byte[0]));
+    }
+    let mut seq = [0u8; 2];
+    input.read_exact(&mut seq)?;
+    Ok(match seq {
+        [b'[', b'A'] => Key::Up,
+        [b'[', b'B'] => Key::Down,
+        [b'[', b'C'] => Key::Right,
+        [b'[', b'D'] => Key::Left,
+        [b'[', b'<'] => return read_mouse(input),
+        _ => Key::Char(0x1b),
+    })
+}
+
+/// The rest of a mouse report like `0;12;3M`, looking past those of other
+/// buttons and releases for the next key.
+fn read_mouse(input: &mut impl Read) -> io::Result<Key> {
+    let mut report = Vec::new();
+    let mut byte = [0u8];
+    let end = loop {
+        input.read_exact(&mut byte)?;
+        match byte[0] {
+            end @ (b'M' | b'm') => break end,
+            _ if report.len() > 16 => return read_key(input),
+            b => report.push(b),
+        }
+    };
+    let fields: Vec<usize> = String::from_utf8_lossy(&report)
+        .split(';')
+        .filter_map(|n| n.parse().ok())
+        .collect();
+    match fields.as_slice() {
+        // 32 is added while dragging
+        &[button, x, y] if end == b'M' && button & !32 == 0 && x > 0 && y > 0 => {
+            Ok(Key::Click(x - 1, y - 1))
+        }
+        _ => read_key(input),
+    }
+}
+
+/// Reports of the mouse pressed and dragged, as `Key::Click`, for as long as
+/// it is alive.
+pub struct MouseReports;
+
+impl MouseReports {
+    pub fn enable() -> MouseReports {
+        // button events with motion while held, in the SGR form
+        print!("\x1b[?1002h\x1b[?1006h");
+        MouseReports
+    }
+}
+
+impl Drop for MouseReports {
+    fn drop(&mut self) {
+        print!("\x1b[?1006l\x1b[?1002l");
+        let _ = io::stdout().flush();
+    }
+}
@@ -0,0 +1,128 @@
+//! Color themes: the colors of cells, trails, the ghost border and the
+//! `--timing` line in the terminal, and of cells in the images `render` and
+//! `thumb` write and in the window. `theme = "amber"` in the config file or
+//! `--theme amber` picks one, and `[themes.NAME]` tables change a built-in
+//! one or add another:
+//!
+//! ```toml
+//! [themes.night]
+//! live = "#ffb000"            # colors as in [colors]
+//! dead = 236
+//! trail = ["#c08400", "#402c00"]
+//! footprint = 234
+//! hud = "none"                # leaves the terminal's own color
+//! ```
+
+use crate::term::{Color, Style};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub live: Option<Color>,
+    pub dead: Option<Color>,
+    pub old: Option<Color>,
+    pub birth: Option<Color>,
+    pub death: Option<Color>,
+    pub trail: (Color, Color),
+    pub ghost: Color,
+    /// The background of `run --footprint`.
+    pub footprint: Color,
+    pub hud: Option<Color>,
+    /// No colors at all beyond the terminal's own, with trails, ghosts and
+    /// the states of LifeHistory told apart by their glyphs alone.
+    pub monochrome: bool,
+}
+
+/// The look without a theme.
+pub const DEFAULT: Theme = Theme {
+    live: None,
+    dead: None,
+    old: None,
+    birth: Some(Color::Basic(2)),
+    death: Some(Color::Basic(1)),
+    trail: (Color::Indexed(250), Color::Indexed(236)),
+    ghost: Color::Indexed(240),
+    footprint: Color::Indexed(235),
+    hud: None,
+    monochrome: false,
+};
+
+pub const BUILTIN: [(&str, Theme); 5] = [
+    ("default", DEFAULT),
+    (
+        "mono",
+        Theme {
+            birth: None,
+            death: None,
+            monochrome: true,
+            ..DEFAULT
+        },
+    ),
+    (
+        "phosphor",
+        Theme {
+            live: Some(Color::Rgb(0x33, 0xff, 0x66)),
+            dead: Some(Color::Rgb(0x0d, 0x26, 0x11)),
+            old: Some(Color::Rgb(0x0f, 0x66, 0x29)),
+            birth: Some(Color::Rgb(0xb3, 0xff, 0xc6)),
+            death: Some(Color::Rgb(0x1a, 0x80, 0x33)),
+            trail: (Color::Rgb(0x1f, 0x9e, 0x3f), Color::Rgb(0x0a, 0x33, 0x14)),
+            ghost: Color::Rgb(0x14, 0x52, 0x22),
+            footprint: Color::Rgb(0x06, 0x1a, 0x0b),
+            hud: Some(Color::Rgb(0x33, 0xff, 0x66)),
+            monochrome: false,
+        },
+    ),
+    (
+        "amber",
+        Theme {
+            live: Some(Color::Rgb(0xff, 0xb0, 0x00)),
+            dead: Some(Color::Rgb(0x3a, 0x28, 0x00)),
+            old: Some(Color::Rgb(0x80, 0x58, 0x00)),
+            birth: Some(Color::Rgb(0xff, 0xd8, 0x80)),
+            death: Some(Color::Rgb(0x80, 0x40, 0x00)),
+            trail: (Color::Rgb(0xc0, 0x84, 0x00), Color::Rgb(0x40, 0x2c, 0x00)),
+            ghost: Color::Rgb(0x5a, 0x3e, 0x00),
+            footprint: Color::Rgb(0x1f, 0x15, 0x00),
+            hud: Some(Color::Rgb(0xff, 0xb0, 0x00)),
+            monochrome: false,
+        },
+    ),
+    (
+        "ice",
+        Theme {
+            live: Some(Color::Rgb(0x9b, 0xe7, 0xff)),
+            dead: Some(Color::Rgb(0x0b, 0x22, 0x33)),
+            old: Some(Color::Rgb(0x2f, 0x6f, 0x99)),
+            birth: Some(Color::Rgb(0xff, 0xff, 0xff)),
+            death: Some(Color::Rgb(0x3a, 0x6e, 0xa5)),
+            trail: (Color::Rgb(0x4f, 0xa3, 0xc7), Color::Rgb(0x12, 0x34, 0x47)),
+            ghost: Color::Rgb(0x1d, 0x4a, 0x63),
+            footprint: Color::Rgb(0x07, 0x18, 0x24),
+            hud: Some(Color::Rgb(0x9b, 0xe7, 0xff)),
+            monochrome: false,
+        },
+    ),
+];
+
+pub fn builtin(name: &str) -> Option<Theme> {
+    BUILTIN
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|&(_, theme)| theme)
+}
+
+impl Theme {
+    pub fn apply(&self, style: &mut Style) {
+        let colored = |color: Option<Color>| color.filter(|_| !self.monochrome);
+        style.live_color = colored(self.live);
+        style.dead_color = colored(self.dead);
+        style.old_color = colored(self.old);
+        style.birth_color = colored(self.birth);
+        style.death_color = colored(self.death);
+        style.trail_colors = self.trail;
+        style.ghost_color = self.ghost;
+        style.footprint_color = self.footprint;
+        style.hud_color = colored(self.hud);
+        style.dim_colors = !self.monochrome;
+    }
+}
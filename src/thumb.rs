@@ -0,0 +1,108 @@
+//! `conway thumb`: a pattern drawn to a square image of a fixed size, scaled
+//! up or down to fit whatever its size, for previews of a whole collection.
+
+use std::convert::TryFrom;
+
+use conway::{Pattern, World};
+
+use crate::cli::ThumbOptions;
+use crate::figure::Filter;
+use crate::memory;
+use crate::png;
+use crate::term;
+
+/// The pattern after `opts.generations`, cropped to its live cells. The
+/// world is made large enough that nothing can reach around the edges and
+/// back in that many generations, as nothing travels faster than a cell a
+/// generation.
+fn evolve(pattern: &Pattern, opts: &ThumbOptions) -> Result<Pattern, String> {
+    if opts.generations == 0 || pattern.population() == 0 {
+        return Ok(pattern.crop());
+    }
+    let margin = usize::try_from(opts.generations)
+        .ok()
+        .and_then(|g| g.checked_mul(2))
+        .and_then(|m| m.checked_add(2))
+        .ok_or("too many generations to leave room for")?;
+    let mut world = World::new(pattern.width() + margin, pattern.height() + margin)?;
+    world.stamp(pattern, margin / 2, margin / 2);
+    if let Some(rule) = opts.rule.or_else(|| pattern.rule()) {
+        world.set_rule(rule);
+    }
+    let mut tmp = world.clone();
+    for _ in 0..opts.generations {
+        if !world.advance(&mut tmp).any() {
+            break;
+        }
+    }
+    Ok(Pattern::from_live_cells(&world))
+}
+
+/// Renders the pattern into `opts.size` pixels square, inside the margin.
+/// Patterns smaller than the image get a whole number of pixels a cell;
+/// larger ones a whole number of cells a pixel, which the average filter
+/// shades by how many of them are alive, brightened so that a lone cell in a
+/// block still shows.
+pub fn run(opts: &ThumbOptions) -> Result<(), String> {
+    let pattern = Pattern::load_within(&opts.input, &memory::fits(None))?;
+    let pattern = evolve(&pattern, opts)?;
+    let size = opts.size;
+    let mut image = opts.image.clone();
+    let room = size.saturating_sub(2 * image.margin);
+    if room == 0 {
+        return Err(format!(
+            "a margin of {} leaves no room in a {} pixel image",
+            image.margin, size
+        ));
+    }
+    let side = pattern.width().max(pattern.height()).max(1);
+    let (pixels, cells) = match image.pixels_in(room, side) {
+        0 => {
+            // lines between cells that share pixels would hide them
+            image.grid = None;
+            (1, side.div_ceil(room))
+        }
+        pixels => (pixels, 1),
+    };
+
+    let (live_color, dead_color) = (image.live_rgb(&opts.style), image.dead_rgb(&opts.style));
+    let columns = pattern.width().div_ceil(cells);
+    let rows = pattern.height().div_ceil(cells);
+    let (w, h, art) = image.cells(columns, rows, pixels, |column, row| {
+        let (x0, y0) = (column * cells, row * cells);
+        if image.filter == Filter::Nearest {
+            return if pattern.get(x0, y0) {
+                live_color
+            } else {
+                dead_color
+            };
+        }
+        let (x1, y1) = (
+            (x0 + cells).min(pattern.width()),
+            (y0 + cells).min(pattern.height()),
+        );
+        let live = (y0..y1)
+            .flat_map(|y| (x0..x1).map(move |x| (x, y)))
+            .filter(|&(x, y)| pattern.get(x, y))
+            .count();
+        let fraction = live as f64 / (cells * cells) as f64;
+        term::mix(dead_color, live_color, fraction.sqrt())
+    });
+    // the pattern in pixels, centered in the image
+    let (left, top) = ((size - w) / 2, (size - h) / 2);
+    let (r, g, b) = image.background_rgb(&opts.style);
+    let mut rgb = [r, g, b].repeat(size * size);
+    for (y, line) in art.chunks(w.max(1) * 3).enumerate() {
+        let i = ((top + y) * size + left) * 3;
+        rgb[i..i + w * 3].copy_from_slice(line);
+    }
+    let comment = format!(
+        "{} after {} generations, {}x{} with {} cells",
+        opts.input,
+        opts.generations,
+        pattern.width(),
+        pattern.height(),
+        pattern.population()
+    );
+    png::write(&opts.output, size, size, &rgb, &comment)
+}
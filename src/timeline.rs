@@ -0,0 +1,157 @@
+//! A run's past kept as snapshots spaced further apart the older they are,
+//! so any generation since the start can be had again without keeping every
+//! one: the last few generations are all there, and going back, one in two,
+//! one in four and so on, a snapshot every `2^k` generations for the last
+//! `recent * 2^k`. A generation between snapshots is worked out again from
+//! the one before it, a step for each generation in between, so the older
+//! the generation the longer it takes, while the snapshots kept only grow
+//! with the logarithm of the run's length.
+//!
+//! The first snapshot is always kept, and so is every one `keep` takes of a
+//! world changed by hand, since stepping an earlier snapshot wouldn't make
+//! the change again.
+
+use crate::World;
+
+/// Snapshots kept at each spacing.
+const RECENT: usize = 8;
+
+struct Snapshot {
+    generation: u64,
+    world: World,
+    /// Never thinned out.
+    kept: bool,
+}
+
+pub struct Timeline {
+    snapshots: Vec<Snapshot>,
+    recent: usize,
+    /// Worlds thinned out, to copy the next snapshots into.
+    spare: Vec<World>,
+}
+
+impl Timeline {
+    /// The timeline of a run at `generation` so far.
+    pub fn new(world: &World, generation: u64) -> Timeline {
+        Timeline {
+            snapshots: vec![Snapshot {
+                generation,
+                world: world.clone(),
+                kept: true,
+            }],
+            recent: RECENT,
+            spare: Vec::new(),
+        }
+    }
+
+    /// Keeps `n` snapshots at each spacing instead of 8: more makes going
+    /// back faster and takes more memory.
+    pub fn recent(mut self, n: usize) -> Timeline {
+        self.recent = n.max(1);
+        self
+    }
+
+    /// Records `world` at `generation`, thinning out the snapshots that are
+    /// no longer needed. A generation at or before the last one recorded
+    /// starts the timeline over from there.
+    pub fn record(&mut self, world: &World, generation: u64) {
+        self.push(world, generation, false);
+        self.thin();
+    }
+
+    /// Records `world` at `generation` once it has been changed other than
+    /// by stepping, to be kept for good.
+    pub fn keep(&mut self, world: &World, generation: u64) {
+        self.push(world, generation, true);
+    }
+
+    fn push(&mut self, world: &World, generation: u64, kept: bool) {
+        while self
+            .snapshots
+            .last()
+            .is_some_and(|last| last.generation >= generation)
+        {
+            if let Some(last) = self.snapshots.pop() {
+                self.spare.push(last.world);
+            }
+        }
+        let world = match self.spare.pop() {
+            Some(mut spare) => {
+                spare.clone_from(world);
+                spare
+            }
+            None => world.clone(),
+        };
+        self.snapshots.push(Snapshot {
+            generation,
+            world,
+            kept,
+        });
+    }
+
+    /// Drops the snapshots no spacing wants any more.
+    fn thin(&mut self) {
+        let (first, last) = self.range();
+        let recent = self.recent as u64;
+        let wanted = |generation: u64| {
+            let (offset, age) = (generation - first, last - generation);
+            // the largest spacing the generation falls on
+            let spacing = match offset {
+                0 => return true,
+                offset => 1u64
+                    .checked_shl(offset.trailing_zeros())
+                    .unwrap_or(u64::MAX),
+            };
+            age < recent.saturating_mul(spacing)
+        };
+        let mut i = 0;
+        while i < self.snapshots.len() {
+            let snapshot = &self.snapshots[i];
+            if snapshot.kept || wanted(snapshot.generation) {
+                i += 1;
+            } else {
+                let dropped = self.snapshots.remove(i);
+                self.spare.push(dropped.world);
+            }
+        }
+        // no more spare worlds than a record can need
+        self.spare.truncate(2);
+    }
+
+    /// The first and last generations recorded.
+    pub fn range(&self) -> (u64, u64) {
+        match (self.snapshots.first(), self.snapshots.last()) {
+            (Some(first), Some(last)) => (first.generation, last.generation),
+            _ => (0, 0),
+        }
+    }
+
+    /// How many snapshots are kept.
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// The world as it was at `generation`, stepped on from the snapshot
+    /// before it if there isn't one of it, or `None` outside the range
+    /// recorded.
+    pub fn at(&self, generation: u64) -> Option<World> {
+        let (first, last) = self.range();
+        if generation < first || generation > last {
+            return None;
+        }
+        let i = self
+            .snapshots
+            .partition_point(|snapshot| snapshot.generation <= generation);
+        let nearest = &self.snapshots[i.checked_sub(1)?];
+        let mut world = nearest.world.clone();
+        let mut tmp = world.clone();
+        for _ in nearest.generation..generation {
+            world.advance(&mut tmp);
+        }
+        Some(world)
+    }
+}
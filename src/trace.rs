@@ -0,0 +1,95 @@
+//! `run --watch`: the states of a few cells logged every generation, one line
+//! each, to check the timing of guns, reflectors and logic gates by numbers
+//! rather than by eye.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use conway::{Cell, World};
+
+pub struct Trace {
+    /// Where the log goes, for messages, or None for stderr.
+    path: Option<String>,
+    out: Box<dyn Write>,
+    cells: Vec<(usize, usize)>,
+    generation: u64,
+    /// The first write that failed, reported by `finish`.
+    error: Option<io::Error>,
+}
+
+impl Trace {
+    /// Starts logging `cells` of `world`, which is at `generation`, to the
+    /// file at `path` or to stderr. The first line names the columns:
+    /// `generation` and each cell as `X,Y`, then every line has the
+    /// generation and a 1 or 0 for each cell.
+    pub fn create(
+        path: Option<&str>,
+        world: &World,
+        cells: &[(usize, usize)],
+        generation: u64,
+    ) -> Result<Trace, String> {
+        if let Some(&(x, y)) = cells
+            .iter()
+            .find(|&&(x, y)| x >= world.width() || y >= world.height())
+        {
+            return Err(format!(
+                "--watch {},{} is outside the {}x{} world",
+                x,
+                y,
+                world.width(),
+                world.height()
+            ));
+        }
+        let out: Box<dyn Write> = match path {
+            Some(path) => Box::new(BufWriter::new(
+                File::create(path).map_err(|e| format!("cannot write {}: {}", path, e))?,
+            )),
+            None => Box::new(io::stderr()),
+        };
+        let mut trace = Trace {
+            path: path.map(str::to_string),
+            out,
+            cells: cells.to_vec(),
+            generation,
+            error: None,
+        };
+        let head: Vec<String> = cells.iter().map(|(x, y)| format!("{},{}", x, y)).collect();
+        trace.write(format!("generation {}", head.join(" ")));
+        trace.observe(world);
+        Ok(trace)
+    }
+
+    fn write(&mut self, line: String) {
+        if self.error.is_none() {
+            self.error = writeln!(self.out, "{}", line).err();
+        }
+    }
+
+    fn observe(&mut self, world: &World) {
+        let mut line = self.generation.to_string();
+        for &(x, y) in &self.cells {
+            line.push_str(match world.get(x, y) {
+                Cell::Live => " 1",
+                Cell::Dead => " 0",
+            });
+        }
+        self.write(line);
+    }
+
+    /// Called after every generation.
+    pub fn advanced(&mut self, world: &World) {
+        self.generation += 1;
+        self.observe(world);
+    }
+
+    pub fn finish(mut self) -> Result<(), String> {
+        if self.error.is_none() {
+            self.error = self.out.flush().err();
+        }
+        match (self.error, self.path) {
+            (Some(e), Some(path)) => Err(format!("cannot write {}: {}", path, e)),
+            (Some(e), None) => Err(format!("cannot write the --watch log: {}", e)),
+            (None, _) => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! `conway verify`: golden runs. `--record` steps a world and writes the state
+//! hash of every generation to a JSON file along with the world it started
+//! from; `--against` plays that world forward again and fails at the first
+//! generation whose hash differs, to catch a change in how the engine
+//! evolves worlds:
+//!
+//! ```json
+//! {
+//!   "format": "conway verify 1",
+//!   "seed": 42,
+//!   "world": "x = 40, y = 20, rule = B3/S23\n...!\n",
+//!   "hashes": ["5d2f...", "..."]
+//! }
+//! ```
+//!
+//! `seed` is that of the soup the world started from, or null, and is only
+//! there to tell what was recorded. The hashes are `World::state_hash` as 16
+//! hex digits, the first for the world as it starts.
+
+use std::fs;
+
+use conway::{Automaton, Format, Pattern, World};
+
+use crate::cli::{VerifyMode, VerifyOptions, WorldOptions};
+use crate::engines::{self, Engine};
+use crate::json::Json;
+use crate::jsonl;
+use crate::memory;
+
+const FORMAT: &str = "conway verify 1";
+
+fn hex(world: &World) -> String {
+    format!("{:016x}", world.state_hash())
+}
+
+pub fn run(
+    opts: &VerifyOptions,
+    initial_world: impl Fn(&WorldOptions) -> Result<World, String>,
+) -> Result<(), String> {
+    match &opts.mode {
+        VerifyMode::Record(path) => record(path, initial_world(&opts.world)?, opts),
+        VerifyMode::Against(path) => against(path),
+        VerifyMode::Engines(a, b) => engines(a, b, &initial_world(&opts.world)?, opts),
+    }
+}
+
+fn engines(a: &str, b: &str, world: &World, opts: &VerifyOptions) -> Result<(), String> {
+    let mut first = Engine::new(a, world)?;
+    let mut second = Engine::new(b, world)?;
+    for generation in 1..=opts.generations {
+        first.step();
+        second.step();
+        let report = engines::world_of(&first, world).diff(&engines::world_of(&second, world))?;
+        if let Some(&(x, y, cell)) = report.changed.first() {
+            return Err(format!(
+                "generation {}: {} cells differ in {} regions, the first {},{} being {:?} under {} but {:?} under {}",
+                generation,
+                report.distance(),
+                report.regions().len(),
+                x,
+                y,
+                cell,
+                a,
+                second.cell(x, y),
+                b
+            ));
+        }
+    }
+    println!(
+        "verified     {} and {} agree over {} generations of a {}x{} world",
+        a,
+        b,
+        opts.generations,
+        world.width(),
+        world.height()
+    );
+    Ok(())
+}
+
+fn record(path: &str, mut world: World, opts: &VerifyOptions) -> Result<(), String> {
+    let start = Pattern::from_world(&world).to_rle();
+    let mut tmp = world.clone();
+    let mut hashes = vec![hex(&world)];
+    for _ in 0..opts.generations {
+        world.advance(&mut tmp);
+        hashes.push(hex(&world));
+    }
+    let seed = opts
+        .world
+        .random
+        .and(opts.world.seed)
+        .map_or("null".to_string(), |seed| seed.to_string());
+    let hashes: Vec<String> = hashes.iter().map(|h| format!("\"{}\"", h)).collect();
+    let golden = format!(
+        "{{\n  \"format\": {},\n  \"seed\": {},\n  \"world\": {},\n  \"hashes\": [\n    {}\n  ]\n}}\n",
+        jsonl::string(FORMAT),
+        seed,
+        jsonl::string(&start),
+        hashes.join(",\n    ")
+    );
+    fs::write(path, golden).map_err(|e| format!("cannot write {}: {}", path, e))?;
+    println!(
+        "recorded     {} generations of a {}x{} world to {}",
+        opts.generations,
+        world.width(),
+        world.height(),
+        path
+    );
+    Ok(())
+}
+
+fn against(path: &str) -> Result<(), String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    let golden = Json::parse(&text).map_err(|e| format!("{}: {}", path, e))?;
+    let field = |key: &str| {
+        golden
+            .get(key)
+            .ok_or_else(|| format!("{}: not a golden run, '{}' is missing", path, key))
+    };
+    if field("format")?.as_str() != Some(FORMAT) {
+        return Err(format!("{}: not a golden run in {}", path, FORMAT));
+    }
+    let rle = field("world")?
+        .as_str()
+        .ok_or_else(|| format!("{}: 'world' must be RLE", path))?;
+    let hashes: Vec<&str> = field("hashes")?
+        .as_array()
+        .and_then(|hashes| hashes.iter().map(Json::as_str).collect())
+        .filter(|hashes: &Vec<&str>| !hashes.is_empty())
+        .ok_or_else(|| format!("{}: 'hashes' must be a list of hex strings", path))?;
+
+    let pattern = Pattern::parse_as_within(rle, Format::Rle, &memory::fits(None))
+        .map_err(|e| format!("{}: {}", path, e))?;
+    let mut world = World::new(pattern.width(), pattern.height())?;
+    world.stamp(&pattern, 0, 0);
+    if let Some(rule) = pattern.rule() {
+        world.set_rule(rule);
+    }
+    let mut tmp = world.clone();
+    for (generation, &expected) in hashes.iter().enumerate() {
+        if generation > 0 {
+            world.advance(&mut tmp);
+        }
+        let actual = hex(&world);
+        if actual != expected {
+            return Err(format!(
+                "generation {} diverges from {}: hash {}, expected {}",
+                generation, path, actual, expected
+            ));
+        }
+    }
+    println!(
+        "verified     {} generations of a {}x{} world against {}",
+        hashes.len() - 1,
+        world.width(),
+        world.height(),
+        path
+    );
+    Ok(())
+}
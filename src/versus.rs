@@ -0,0 +1,179 @@
+//! Two-player Life in the terminal: the world runs a round at a time, and
+//! between rounds each player places a few cells of their own.
+
+use std::io::{self, Write};
+
+use conway::immigration::{Immigration, Player};
+
+use crate::cli::VersusOptions;
+use crate::pace::Pacer;
+use crate::term::{self, Color, Key, RawMode};
+
+const HELP: &str = "arrows/hjkl move  space place  enter end turn  q quit";
+
+const CTRL_C: u8 = 0x03;
+
+fn name(player: Player) -> &'static str {
+    match player {
+        Player::One => "player one (X)",
+        Player::Two => "player two (O)",
+    }
+}
+
+struct Game {
+    world: Immigration,
+    generation: u64,
+    /// Whose turn it is, `None` while a round runs.
+    turn: Option<Player>,
+    /// Cells placed this turn, which can still be taken back.
+    placed: Vec<(usize, usize)>,
+    x: usize,
+    y: usize,
+    cells: usize,
+    color: bool,
+}
+
+impl Game {
+    fn draw(&self) -> io::Result<()> {
+        let mut frame = term::frame_start();
+        for y in 0..self.world.height() {
+            for x in 0..self.world.width() {
+                let cursor = self.turn.is_some() && (x, y) == (self.x, self.y);
+                if cursor {
+                    frame.push_str("\x1b[7m");
+                }
+                match self.world.get(x, y) {
+                    Some(player) => {
+                        if self.color {
+                            let color = match player {
+                                Player::One => Color::Basic(1),
+                                Player::Two => Color::Basic(4),
+                            };
+                            frame.push_str(&color.foreground());
+                        }
+                        frame.push(match player {
+                            Player::One => 'X',
+                            Player::Two => 'O',
+                        });
+                        if self.color && !cursor {
+                            frame.push_str(term::RESET);
+                        }
+                    }
+                    None => frame.push('.'),
+                }
+                if cursor {
+                    frame.push_str(term::RESET);
+                }
+            }
+            frame.push_str("\r\n");
+        }
+        let (one, two) = self.world.score();
+        frame.push_str(&format!(
+            "generation {}  X {}  O {}\x1b[K\r\n",
+            self.generation, one, two
+        ));
+        match self.turn {
+            Some(player) => frame.push_str(&format!(
+                "{} to place {} more  {}\x1b[K",
+                name(player),
+                self.cells - self.placed.len(),
+                HELP
+            )),
+            None => frame.push_str("\x1b[K"),
+        }
+        let mut out = io::stdout();
+        out.write_all(frame.as_bytes())?;
+        out.flush()
+    }
+
+    /// Places a cell for the player whose turn it is, or takes one of this
+    /// turn's back.
+    fn place(&mut self, player: Player) {
+        let (x, y) = (self.x, self.y);
+        if let Some(i) = self.placed.iter().position(|&c| c == (x, y)) {
+            self.placed.remove(i);
+            self.world.set(x, y, None);
+        } else if self.world.get(x, y).is_none() && self.placed.len() < self.cells {
+            self.placed.push((x, y));
+            self.world.set(x, y, Some(player));
+        }
+    }
+
+    fn over(&self, generations: u64) -> bool {
+        let (one, two) = self.world.score();
+        self.generation >= generations || one == 0 || two == 0
+    }
+}
+
+/// Plays a game on `world` and prints how it ended.
+pub fn play(world: Immigration, opts: &VersusOptions) -> Result<(), String> {
+    let raw = RawMode::enable()?;
+    let mut game = Game {
+        x: world.width() / 2,
+        y: world.height() / 2,
+        world,
+        generation: 0,
+        turn: Some(Player::One),
+        placed: Vec::new(),
+        cells: opts.cells,
+        color: opts.color,
+    };
+    let mut pacer = Pacer::delay(opts.delay);
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let (w, h) = (game.world.width(), game.world.height());
+    let mut quit = false;
+    while !quit {
+        game.draw().map_err(|e| e.to_string())?;
+        let player = match game.turn {
+            Some(player) => player,
+            None => {
+                for _ in 0..opts.every {
+                    pacer.wait();
+                    game.world.advance();
+                    game.generation += 1;
+                    game.draw().map_err(|e| e.to_string())?;
+                    if game.over(opts.generations) {
+                        break;
+                    }
+                }
+                if game.over(opts.generations) {
+                    break;
+                }
+                game.turn = Some(Player::One);
+                continue;
+            }
+        };
+        match term::read_key(&mut input).map_err(|e| e.to_string())? {
+            Key::Up | Key::Char(b'k') => game.y = (game.y + h - 1) % h,
+            Key::Down | Key::Char(b'j') => game.y = (game.y + 1) % h,
+            Key::Left | Key::Char(b'h') => game.x = (game.x + w - 1) % w,
+            Key::Right | Key::Char(b'l') => game.x = (game.x + 1) % w,
+            Key::Char(b' ') => game.place(player),
+            Key::Char(b'\n') | Key::Char(b'\r') => {
+                game.placed.clear();
+                game.turn = match player {
+                    Player::One => Some(Player::Two),
+                    Player::Two => None,
+                };
+            }
+            Key::Char(b'q') | Key::Char(CTRL_C) => quit = true,
+            Key::Resize => print!("{}", term::CLEAR),
+            Key::Char(_) | Key::Click(..) => {}
+        }
+    }
+    drop(raw);
+
+    let (one, two) = game.world.score();
+    let result = match one.cmp(&two) {
+        _ if quit => "game abandoned".to_string(),
+        std::cmp::Ordering::Greater => format!("{} wins", name(Player::One)),
+        std::cmp::Ordering::Less => format!("{} wins", name(Player::Two)),
+        std::cmp::Ordering::Equal => "a draw".to_string(),
+    };
+    println!(
+        "generation {}: X {}, O {}, {}",
+        game.generation, one, two, result
+    );
+    Ok(())
+}
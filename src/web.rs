@@ -0,0 +1,109 @@
+//! Plain C ABI over the engine for the browser demo in `web/`.
+//!
+//! Built for `wasm32-unknown-unknown` there is no JS binding generator involved:
+//! the page instantiates the module itself and reads the cell buffer straight
+//! out of linear memory.
+
+use crate::{Cell, World};
+
+pub struct Simulation {
+    world: World,
+    tmp: World,
+    cells: Vec<u8>,
+}
+
+impl Simulation {
+    fn put(&mut self, x: usize, y: usize, cell: Cell) {
+        self.world.set(x, y, cell);
+        self.cells[y * self.world.width() + x] = match cell {
+            Cell::Dead => 0,
+            Cell::Live => 1,
+        };
+    }
+
+    fn sync(&mut self) {
+        for y in 0..self.world.height() {
+            for x in 0..self.world.width() {
+                let cell = self.world.get(x, y);
+                self.put(x, y, cell);
+            }
+        }
+    }
+}
+
+/// Creates an empty world, or returns null if the dimensions are invalid.
+#[no_mangle]
+pub extern "C" fn conway_new(width: u32, height: u32) -> *mut Simulation {
+    match World::new(width as usize, height as usize) {
+        Ok(world) => {
+            let sim = Simulation {
+                tmp: world.clone(),
+                cells: vec![0; world.width() * world.height()],
+                world,
+            };
+            Box::into_raw(Box::new(sim))
+        }
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// # Safety
+///
+/// `sim` must come from `conway_new` and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn conway_free(sim: *mut Simulation) {
+    if !sim.is_null() {
+        drop(Box::from_raw(sim));
+    }
+}
+
+/// # Safety
+///
+/// `sim` must be a live pointer returned by `conway_new`.
+#[no_mangle]
+pub unsafe extern "C" fn conway_step(sim: *mut Simulation) {
+    let sim = &mut *sim;
+    sim.world.advance(&mut sim.tmp);
+    sim.sync();
+}
+
+/// Sets a cell, ignoring coordinates outside the world.
+///
+/// # Safety
+///
+/// `sim` must be a live pointer returned by `conway_new`.
+#[no_mangle]
+pub unsafe extern "C" fn conway_set(sim: *mut Simulation, x: u32, y: u32, live: bool) {
+    let sim = &mut *sim;
+    let cell = if live { Cell::Live } else { Cell::Dead };
+    if sim.world.try_get(x as usize, y as usize).is_ok() {
+        sim.put(x as usize, y as usize, cell);
+    }
+}
+
+/// Toggles a cell, ignoring coordinates outside the world.
+///
+/// # Safety
+///
+/// `sim` must be a live pointer returned by `conway_new`.
+#[no_mangle]
+pub unsafe extern "C" fn conway_toggle(sim: *mut Simulation, x: u32, y: u32) {
+    let sim = &mut *sim;
+    let (x, y) = (x as usize, y as usize);
+    match sim.world.try_get(x, y) {
+        Ok(Cell::Dead) => sim.put(x, y, Cell::Live),
+        Ok(Cell::Live) => sim.put(x, y, Cell::Dead),
+        Err(_) => {}
+    }
+}
+
+/// Row-major buffer of `width * height` bytes, 1 for live cells and 0 for dead.
+/// It stays valid until the simulation is freed.
+///
+/// # Safety
+///
+/// `sim` must be a live pointer returned by `conway_new`.
+#[no_mangle]
+pub unsafe extern "C" fn conway_cells(sim: *const Simulation) -> *const u8 {
+    (*sim).cells.as_ptr()
+}
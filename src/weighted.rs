@@ -0,0 +1,212 @@
+//! Weighted rules, as in MCell's Weighted Life and Golly's weighted rule
+//! tables: every cell of the square around a cell, the cell itself too,
+//! counts with a weight of its own, and the sum of the weights of the live
+//! ones decides what the cell becomes. A rule description file gives one:
+//!
+//! ```text
+//! # orthogonal neighbors count twice
+//! weights
+//! 1 2 1
+//! 2 0 2
+//! 1 2 1
+//! birth 3 5
+//! survival 2 3 6
+//! ```
+//!
+//! and it is written on one line, in files and headers, as
+//! `W1:1,2,1,2,0,2,1,2,1/B3,5/S2,3,6`, the number after `W` being the
+//! radius.
+
+use std::fmt;
+
+use crate::{Cell, World};
+
+/// The widest square of weights a rule can have, 15 by 15.
+const MAX_RADIUS: usize = 7;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Weighted {
+    radius: usize,
+    /// Row by row from the top left, the cell itself in the middle.
+    weights: Vec<i32>,
+    /// The sums that make a birth and let a cell survive, sorted.
+    birth: Vec<i32>,
+    survival: Vec<i32>,
+}
+
+/// Numbers apart by commas or spaces, sorted without repeats.
+fn sums(s: &str) -> Option<Vec<i32>> {
+    let mut sums = s
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|n| !n.is_empty())
+        .map(|n| n.parse().ok())
+        .collect::<Option<Vec<i32>>>()?;
+    sums.sort_unstable();
+    sums.dedup();
+    Some(sums)
+}
+
+impl Weighted {
+    /// The one-line form, `W1:1,2,1,2,0,2,1,2,1/B3,5/S2,3,6`.
+    pub fn parse(s: &str) -> Result<Weighted, String> {
+        let err = || {
+            format!(
+                "invalid weighted rule '{}', expected the radius, the weights row by row and \
+                 the sums, like W1:1,2,1,2,0,2,1,2,1/B3,5/S2,3,6",
+                s
+            )
+        };
+        let (radius, rest) = s[1..].split_once(':').ok_or_else(err)?;
+        let parts: Vec<&str> = rest.split('/').collect();
+        let (weights, birth, survival) = match parts[..] {
+            [weights, b, s] if b.starts_with(['B', 'b']) && s.starts_with(['S', 's']) => {
+                (weights, &b[1..], &s[1..])
+            }
+            _ => return Err(err()),
+        };
+        let weighted = Weighted {
+            radius: radius.parse().map_err(|_| err())?,
+            weights: weights
+                .split(',')
+                .map(|w| w.trim().parse().ok())
+                .collect::<Option<_>>()
+                .ok_or_else(err)?,
+            birth: sums(birth).ok_or_else(err)?,
+            survival: sums(survival).ok_or_else(err)?,
+        };
+        weighted.check()?;
+        Ok(weighted)
+    }
+
+    /// A rule description file: `weights` on a line of its own and the
+    /// square of them after it, `birth` and `survival` lines with their
+    /// sums, and `#` comments.
+    pub fn describe(text: &str) -> Result<Weighted, String> {
+        let (mut rows, mut birth, mut survival) = (Vec::new(), None, None);
+        let mut in_weights = false;
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let err = |what: &str| format!("line {}: {}", i + 1, what);
+            let (key, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            match key {
+                "" => {}
+                "weights" => in_weights = true,
+                "birth" => birth = Some(sums(rest).ok_or_else(|| err("invalid birth sums"))?),
+                "survival" => {
+                    survival = Some(sums(rest).ok_or_else(|| err("invalid survival sums"))?)
+                }
+                _ if in_weights => {
+                    let row: Vec<i32> = line
+                        .split_whitespace()
+                        .map(|w| w.parse().ok())
+                        .collect::<Option<_>>()
+                        .ok_or_else(|| err("invalid weights, expected whole numbers"))?;
+                    rows.push(row);
+                }
+                _ => return Err(err(&format!("unknown line '{}'", line))),
+            }
+        }
+        let side = rows.len();
+        if side % 2 == 0 || rows.iter().any(|row| row.len() != side) {
+            return Err(format!(
+                "the weights must be a square with an odd number of rows, the cell in the \
+                 middle, not {} rows",
+                side
+            ));
+        }
+        let weighted = Weighted {
+            radius: side / 2,
+            weights: rows.concat(),
+            birth: birth.ok_or("the rule has no birth line")?,
+            survival: survival.ok_or("the rule has no survival line")?,
+        };
+        weighted.check()?;
+        Ok(weighted)
+    }
+
+    fn check(&self) -> Result<(), String> {
+        let side = 2 * self.radius + 1;
+        if !(1..=MAX_RADIUS).contains(&self.radius) {
+            return Err(format!(
+                "weights reach from 1 to {} cells around a cell, not {}",
+                MAX_RADIUS, self.radius
+            ));
+        }
+        if self.weights.len() != side * side {
+            return Err(format!(
+                "radius {} takes {} weights, {} by {}, not {}",
+                self.radius,
+                side * side,
+                side,
+                side,
+                self.weights.len()
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn radius(&self) -> usize {
+        self.radius
+    }
+
+    pub fn weights(&self) -> &[i32] {
+        &self.weights
+    }
+
+    /// The cell's next state with `sum` the weights of the live cells
+    /// around it.
+    pub fn next(&self, cell: Cell, sum: i32) -> Cell {
+        let sums = match cell {
+            Cell::Dead => &self.birth,
+            Cell::Live => &self.survival,
+        };
+        match sums.binary_search(&sum) {
+            Ok(_) => Cell::Live,
+            Err(_) => Cell::Dead,
+        }
+    }
+
+    /// The sum of the weights of the live cells around every cell of
+    /// `world`, row by row, wrapping around the edges.
+    pub fn sums(&self, world: &World) -> Vec<i32> {
+        let (w, h, r) = (world.width(), world.height(), self.radius);
+        let side = 2 * r + 1;
+        let offsets: Vec<(usize, usize, i32)> = self
+            .weights
+            .iter()
+            .enumerate()
+            .filter(|&(_, &weight)| weight != 0)
+            .map(|(i, &weight)| (i % side, i / side, weight))
+            .collect();
+        (0..w * h)
+            .map(|i| {
+                let (x, y) = (i % w, i / w);
+                offsets
+                    .iter()
+                    .filter(|&&(dx, dy, _)| {
+                        let (nx, ny) = ((x + dx + w * r - r) % w, (y + dy + h * r - r) % h);
+                        world.get(nx, ny) == Cell::Live
+                    })
+                    .map(|&(_, _, weight)| weight)
+                    .sum()
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Weighted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = |numbers: &[i32]| -> String {
+            let numbers: Vec<String> = numbers.iter().map(i32::to_string).collect();
+            numbers.join(",")
+        };
+        write!(
+            f,
+            "W{}:{}/B{}/S{}",
+            self.radius,
+            list(&self.weights),
+            list(&self.birth),
+            list(&self.survival)
+        )
+    }
+}
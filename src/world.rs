@@ -0,0 +1,849 @@
+use std::hash::{Hash, Hasher};
+use std::mem;
+
+use crate::metrics::Metrics;
+use crate::pattern::WALL;
+use crate::{history, neighborhood, Pattern, PatternMeta, Rng, Rule, Transform};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Cell {
+    Dead,
+    Live,
+}
+
+impl Cell {
+    pub(crate) fn n(self) -> u8 {
+        match self {
+            Cell::Dead => 0,
+            Cell::Live => 1,
+        }
+    }
+}
+
+/// What one generation did to the world.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Changes {
+    /// Dead cells that came alive.
+    pub births: usize,
+    /// Live cells that died.
+    pub deaths: usize,
+}
+
+impl Changes {
+    /// Whether any cell changed at all. If none did, the world is a still life
+    /// or empty and will stay that way.
+    pub fn any(&self) -> bool {
+        self.births > 0 || self.deaths > 0
+    }
+}
+
+/// A rectangle of cells, `x` and `y` being its top left corner.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
+
+    /// Whether the two have a cell in common.
+    pub fn overlaps(&self, other: &Rect) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
+/// How long each cell has been the way it is, row-major.
+#[derive(Clone)]
+struct Ages {
+    /// Generations each live cell has been alive in a row, 0 for dead ones.
+    alive: Vec<u32>,
+    /// The generation each cell last changed in.
+    changed: Vec<u64>,
+}
+
+/// Worlds are equal when their cells and rules are, however they got there.
+#[derive(Clone)]
+pub struct World {
+    cells: Vec<Vec<Cell>>,
+    width: usize,
+    height: usize,
+    rule: Rule,
+    /// Live cells, kept up to date by every change instead of being recounted.
+    population: usize,
+    /// Live cells in each row and each column, so the bounding box can be
+    /// found without looking at every cell.
+    row_population: Vec<usize>,
+    column_population: Vec<usize>,
+    /// Live cells in each quarter of the world, for `metrics`.
+    quadrants: [usize; 4],
+    /// Generations advanced since the world was made.
+    generation: u64,
+    /// Kept only once `track_ages` is called, as it takes more memory than
+    /// the cells themselves.
+    ages: Option<Ages>,
+    /// The LifeHistory state of each cell, row-major, kept while the rule is
+    /// a History one.
+    history: Option<Vec<u8>>,
+    /// Cells that never change, kept once there is one.
+    walls: Option<Walls>,
+    /// Sources, forced alive after every step whatever the rule, and sinks,
+    /// forced dead, kept once there is one.
+    emitters: Option<Vec<Option<Cell>>>,
+    /// What the pattern it started from said about itself, to write back out
+    /// when it is saved. Boxed as most worlds have none and all get copied.
+    meta: Box<PatternMeta>,
+}
+
+/// Wall cells: always dead themselves, and counted by their neighbors as
+/// dead or, with `alive`, as alive.
+#[derive(Clone, Debug)]
+struct Walls {
+    at: Vec<bool>,
+    alive: bool,
+}
+
+impl PartialEq for World {
+    fn eq(&self, other: &World) -> bool {
+        self.cells == other.cells && self.rule == other.rule
+    }
+}
+
+impl Eq for World {}
+
+impl Hash for World {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.cells.hash(state);
+        self.rule.hash(state);
+    }
+}
+
+impl World {
+    pub fn new(width: usize, height: usize) -> Result<World, String> {
+        if width < 3 || height < 3 {
+            return Err("the world cannot be smaller than 3x3".to_string());
+        }
+        if World::memory(width, height).is_none() {
+            return Err(format!("a {}x{} world is too big to exist", width, height));
+        }
+        let row = vec![Cell::Dead; width];
+        let cells = vec![row; height];
+        let world = World {
+            cells,
+            width,
+            height,
+            rule: Rule::conway(),
+            population: 0,
+            row_population: vec![0; height],
+            column_population: vec![0; width],
+            quadrants: [0; 4],
+            generation: 0,
+            ages: None,
+            history: None,
+            walls: None,
+            emitters: None,
+            meta: Box::default(),
+        };
+        Ok(world)
+    }
+
+    /// Roughly the bytes a `width` by `height` world takes, or `None` if
+    /// that is more than can be counted. Tracking ages takes 12 more a cell.
+    pub fn memory(width: usize, height: usize) -> Option<usize> {
+        let cells = width
+            .checked_mul(height)?
+            .checked_mul(mem::size_of::<Cell>())?;
+        let rows = height.checked_mul(mem::size_of::<Vec<Cell>>())?;
+        let counts = width
+            .checked_add(height)?
+            .checked_mul(mem::size_of::<usize>())?;
+        cells.checked_add(rows)?.checked_add(counts)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The density, centroid, spread and quarters of the live cells, from
+    /// the counts kept up to date with every change, so only as costly as
+    /// the world is wide and tall.
+    pub fn metrics(&self) -> Metrics {
+        let n = self.population as f64;
+        // the sums of the positions and of their squares, along each axis
+        let moments = |counts: &[usize]| {
+            counts
+                .iter()
+                .enumerate()
+                .fold((0.0, 0.0), |(s, s2), (i, &c)| {
+                    let (i, c) = (i as f64, c as f64);
+                    (s + i * c, s2 + i * i * c)
+                })
+        };
+        let ((sx, sx2), (sy, sy2)) = (
+            moments(&self.column_population),
+            moments(&self.row_population),
+        );
+        let centroid = (self.population > 0).then(|| (sx / n, sy / n));
+        let radius_of_gyration = centroid.map_or(0.0, |(cx, cy)| {
+            (sx2 / n - cx * cx + sy2 / n - cy * cy).max(0.0).sqrt()
+        });
+        Metrics {
+            density: n / (self.width * self.height) as f64,
+            centroid,
+            radius_of_gyration,
+            quadrants: self.quadrants,
+        }
+    }
+
+    /// Live cells in each row, from the top.
+    pub fn row_populations(&self) -> &[usize] {
+        &self.row_population
+    }
+
+    /// The cells of row `y`, from the left.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn row(&self, y: usize) -> &[Cell] {
+        &self.cells[y]
+    }
+
+    /// Every cell, gone over by a thread per core; see `conway::par`.
+    #[cfg(feature = "parallel")]
+    pub fn par_iter(&self) -> crate::par::ParCells<'_> {
+        crate::par::ParCells::new(self)
+    }
+
+    /// Generations advanced since the world was made.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// From now on keeps each cell's age and the generation it last changed
+    /// in, as `age` and `last_changed` return. The cells alive already start
+    /// at age 1, as if born just now.
+    pub fn track_ages(&mut self) {
+        if self.ages.is_some() {
+            return;
+        }
+        let mut alive = vec![0; self.width * self.height];
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                alive[y * self.width + x] = u32::from(cell.n());
+            }
+        }
+        self.ages = Some(Ages {
+            alive,
+            changed: vec![self.generation; self.width * self.height],
+        });
+    }
+
+    pub fn tracks_ages(&self) -> bool {
+        self.ages.is_some()
+    }
+
+    /// Generations the cell at `x`, `y` has been alive in a row, 1 for one
+    /// born in the latest and 0 for a dead one, or `None` unless tracking
+    /// ages.
+    pub fn age(&self, x: usize, y: usize) -> Option<u32> {
+        let ages = self.ages.as_ref()?;
+        Some(ages.alive[y * self.width + x])
+    }
+
+    /// The generation the cell at `x`, `y` last came alive or died in, or
+    /// when tracking ages began if it hasn't since. `None` unless tracking
+    /// ages.
+    pub fn last_changed(&self, x: usize, y: usize) -> Option<u64> {
+        let ages = self.ages.as_ref()?;
+        Some(ages.changed[y * self.width + x])
+    }
+
+    /// Number of live cells.
+    pub fn population(&self) -> usize {
+        self.population
+    }
+
+    /// A hash of the size and cells alone, the same on every machine and in
+    /// every version, to check that two runs came out alike. It is 64-bit
+    /// FNV-1a over the width and then the height as little-endian `u64`s,
+    /// then each row from the top as bytes of eight cells, the leftmost in
+    /// the lowest bit and the last byte padded with dead ones. The rule,
+    /// ages and generation count are left out.
+    pub fn state_hash(&self) -> u64 {
+        const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0100_0000_01b3;
+        let byte = |hash: u64, byte: u8| (hash ^ u64::from(byte)).wrapping_mul(PRIME);
+        let mut hash = OFFSET;
+        for n in [self.width as u64, self.height as u64] {
+            hash = n.to_le_bytes().iter().fold(hash, |hash, &b| byte(hash, b));
+        }
+        for row in &self.cells {
+            for chunk in row.chunks(8) {
+                let bits = chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0u8, |bits, (i, &cell)| bits | cell.n() << i);
+                hash = byte(hash, bits);
+            }
+        }
+        hash
+    }
+
+    /// The smallest rectangle holding every live cell, `None` for an empty
+    /// world. Patterns wrapping around an edge get a box spanning the world.
+    pub fn bounding_box(&self) -> Option<Rect> {
+        let span = |counts: &[usize]| {
+            let first = counts.iter().position(|&n| n > 0)?;
+            let last = counts.iter().rposition(|&n| n > 0)?;
+            Some((first, last + 1 - first))
+        };
+        let (y, height) = span(&self.row_population)?;
+        let (x, width) = span(&self.column_population)?;
+        Some(Rect {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+
+    /// The smallest rectangle holding every live cell and wall, `None` if
+    /// there are neither.
+    pub fn extent(&self) -> Option<Rect> {
+        let walls = self.walls.as_ref().map_or(&[][..], |walls| &walls.at[..]);
+        let cells = walls
+            .iter()
+            .enumerate()
+            .filter(|&(_, &wall)| wall)
+            .map(|(i, _)| (i % self.width, i / self.width));
+        let (x0, y0, x1, y1) = self.bounding_box().into_iter().fold(
+            cells.fold(
+                (usize::MAX, usize::MAX, 0, 0),
+                |(x0, y0, x1, y1), (x, y)| (x0.min(x), y0.min(y), x1.max(x + 1), y1.max(y + 1)),
+            ),
+            |(x0, y0, x1, y1), r| {
+                (
+                    x0.min(r.x),
+                    y0.min(r.y),
+                    x1.max(r.x + r.width),
+                    y1.max(r.y + r.height),
+                )
+            },
+        );
+        (x0 < x1).then(|| Rect {
+            x: x0,
+            y: y0,
+            width: x1 - x0,
+            height: y1 - y0,
+        })
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    /// Sets the rule, a History one starting the cells off in the states of
+    /// plain on and off if they weren't in any yet.
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+        if !rule.history() {
+            self.history = None;
+        } else if self.history.is_none() {
+            let states = self.cells.iter().flatten().map(|cell| cell.n()).collect();
+            self.history = Some(states);
+        }
+    }
+
+    /// The LifeHistory state of the cell at `x`, `y`, or `None` unless the
+    /// rule is a History one.
+    pub fn history(&self, x: usize, y: usize) -> Option<u8> {
+        let states = self.history.as_ref()?;
+        Some(states[y * self.width + x])
+    }
+
+    /// Sets the cell at `x`, `y` alive if `state` is an on state of
+    /// LifeHistory and dead if not, keeping the state itself under a History
+    /// rule.
+    pub fn set_state(&mut self, x: usize, y: usize, state: u8) {
+        if state == WALL {
+            self.set_wall(x, y, true);
+            return;
+        }
+        let cell = if history::is_on(state) {
+            Cell::Live
+        } else {
+            Cell::Dead
+        };
+        self.set(x, y, cell);
+        if let Some(states) = &mut self.history {
+            states[y * self.width + x] = state;
+        }
+    }
+
+    pub fn is_wall(&self, x: usize, y: usize) -> bool {
+        self.walls
+            .as_ref()
+            .is_some_and(|walls| walls.at[y * self.width + x])
+    }
+
+    /// Makes the cell at `x`, `y` a wall, which is dead and stays so, or
+    /// an ordinary dead cell again.
+    pub fn set_wall(&mut self, x: usize, y: usize, wall: bool) {
+        let size = self.width * self.height;
+        let walls = self.walls.get_or_insert_with(|| Walls {
+            at: vec![false; size],
+            alive: false,
+        });
+        walls.at[y * self.width + x] = wall;
+        self.set(x, y, Cell::Dead);
+    }
+
+    /// What the cell at `x`, `y` is forced to be every generation, alive for
+    /// a source and dead for a sink, if anything.
+    pub fn emitter(&self, x: usize, y: usize) -> Option<Cell> {
+        self.emitters.as_ref()?[y * self.width + x]
+    }
+
+    /// Makes the cell at `x`, `y` a source or a sink, setting it so now, or
+    /// with `None` an ordinary cell again.
+    pub fn set_emitter(&mut self, x: usize, y: usize, emitter: Option<Cell>) {
+        let size = self.width * self.height;
+        let emitters = self.emitters.get_or_insert_with(|| vec![None; size]);
+        emitters[y * self.width + x] = emitter;
+        if let Some(cell) = emitter {
+            self.set(x, y, cell);
+        }
+    }
+
+    /// Whether walls count as alive for their neighbors, rather than dead.
+    pub fn set_walls_alive(&mut self, alive: bool) {
+        let size = self.width * self.height;
+        self.walls
+            .get_or_insert_with(|| Walls {
+                at: vec![false; size],
+                alive,
+            })
+            .alive = alive;
+    }
+
+    /// The world as the cells around a wall see it when walls count as
+    /// alive, if they do.
+    fn with_walls_lit(&self) -> Option<World> {
+        let walls = self.walls.as_ref().filter(|walls| walls.alive)?;
+        let mut lit = self.clone();
+        lit.walls = None;
+        for (i, _) in walls.at.iter().enumerate().filter(|&(_, &wall)| wall) {
+            lit.cells[i / self.width][i % self.width] = Cell::Live;
+        }
+        Some(lit)
+    }
+
+    pub fn meta(&self) -> &PatternMeta {
+        &self.meta
+    }
+
+    pub fn set_meta(&mut self, meta: PatternMeta) {
+        *self.meta = meta;
+    }
+
+    /// How many of the eight cells around (x, y) are alive, wrapping around.
+    #[rustfmt::skip]
+    pub fn count_neighbors(&self, x: usize, y: usize) -> u8 {
+        let t = if y == 0 { self.height - 1 } else { y - 1 };
+        let b = if y == self.height - 1 { 0 } else { y + 1 };
+        let l = if x == 0 { self.width - 1 } else { x - 1 };
+        let r = if x == self.width - 1 { 0 } else { x + 1 };
+        self.cells[t][l].n() +
+        self.cells[t][x].n() +
+        self.cells[t][r].n() +
+        self.cells[y][l].n() +
+        self.cells[y][r].n() +
+        self.cells[b][l].n() +
+        self.cells[b][x].n() +
+        self.cells[b][r].n()
+    }
+
+    /// Which of the eight nearest neighbors of `x`,`y` are alive, as
+    /// `Rule::next_mask` takes them.
+    pub fn neighbor_mask(&self, x: usize, y: usize) -> u8 {
+        let (w, h) = (self.width, self.height);
+        let mut mask = 0;
+        let mut bit = 0;
+        for dy in [h - 1, 0, 1] {
+            for dx in [w - 1, 0, 1] {
+                if (dx, dy) == (0, 0) {
+                    continue;
+                }
+                mask |= self.cells[(y + dy) % h][(x + dx) % w].n() << bit;
+                bit += 1;
+            }
+        }
+        mask
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Cell {
+        self.cells[y][x]
+    }
+
+    /// The cell at `x`,`y`, or an error saying it is outside the world, for
+    /// coordinates from outside the program.
+    pub fn try_get(&self, x: usize, y: usize) -> Result<Cell, String> {
+        if x < self.width && y < self.height {
+            Ok(self.cells[y][x])
+        } else {
+            Err(format!(
+                "cell {},{} is outside the {}x{} world",
+                x, y, self.width, self.height
+            ))
+        }
+    }
+
+    /// Sets the cell at `x`,`y` as `set` does, or says it is outside the world.
+    pub fn try_set(&mut self, x: usize, y: usize, value: Cell) -> Result<(), String> {
+        self.try_get(x, y)?;
+        self.set(x, y, value);
+        Ok(())
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: Cell) {
+        let old = std::mem::replace(&mut self.cells[y][x], value);
+        let (new, old) = (value.n() as usize, old.n() as usize);
+        self.population = self.population + new - old;
+        self.row_population[y] = self.row_population[y] + new - old;
+        self.column_population[x] = self.column_population[x] + new - old;
+        let quadrant = usize::from(x >= self.width / 2) + 2 * usize::from(y >= self.height / 2);
+        self.quadrants[quadrant] = self.quadrants[quadrant] + new - old;
+        if let Some(ages) = &mut self.ages {
+            if new != old {
+                let i = y * self.width + x;
+                ages.alive[i] = new as u32;
+                ages.changed[i] = self.generation;
+            }
+        }
+        if let Some(states) = &mut self.history {
+            if new != old {
+                states[y * self.width + x] = new as u8;
+            }
+        }
+    }
+
+    /// The cell a signed coordinate lands on, the world repeating in every
+    /// direction so that -1 is the last column or row.
+    pub fn wrap(&self, x: i64, y: i64) -> (usize, usize) {
+        (
+            x.rem_euclid(self.width as i64) as usize,
+            y.rem_euclid(self.height as i64) as usize,
+        )
+    }
+
+    /// `get` at a signed coordinate, wrapped onto the world.
+    pub fn get_at(&self, x: i64, y: i64) -> Cell {
+        let (x, y) = self.wrap(x, y);
+        self.get(x, y)
+    }
+
+    /// `set` at a signed coordinate, wrapped onto the world.
+    pub fn set_at(&mut self, x: i64, y: i64, value: Cell) {
+        let (x, y) = self.wrap(x, y);
+        self.set(x, y, value);
+    }
+
+    pub fn toggle(&mut self, x: usize, y: usize) {
+        let new_cell = match self.get(x, y) {
+            Cell::Dead => Cell::Live,
+            Cell::Live => Cell::Dead,
+        };
+        self.set(x, y, new_cell);
+    }
+
+    /// Sets the pattern's live cells with its top left corner at `x`, `y`,
+    /// wrapping around the edges. Dead cells of the pattern are left alone,
+    /// unless they are in one of the dead states of LifeHistory.
+    pub fn stamp(&mut self, pattern: &Pattern, x: usize, y: usize) {
+        self.stamp_at(pattern, x as i64, y as i64);
+    }
+
+    /// `stamp` at a signed coordinate, so a pattern can hang off the top or
+    /// left edge and wrap around to the other side.
+    pub fn stamp_at(&mut self, pattern: &Pattern, x: i64, y: i64) {
+        for py in 0..pattern.height() {
+            for px in 0..pattern.width() {
+                let state = pattern.state(px, py);
+                if state != history::OFF {
+                    let (x, y) = self.wrap(x + px as i64, y + py as i64);
+                    self.set_state(x, y, state);
+                }
+            }
+        }
+    }
+
+    /// Stamps `pattern` all over the world, a copy every `across` cells along
+    /// and `down` cells down with one at `x`, `y`. The copies at the right
+    /// and bottom edges are cut short where a size isn't a multiple of the
+    /// spacing, rather than wrapping over the first ones.
+    pub fn tile(&mut self, pattern: &Pattern, across: usize, down: usize, x: i64, y: i64) {
+        let (across, down) = (across.max(1), down.max(1));
+        // the first copy at or left of and above the edge
+        let x0 = x.rem_euclid(across as i64) - across as i64;
+        let y0 = y.rem_euclid(down as i64) - down as i64;
+        let (w, h) = (self.width as i64, self.height as i64);
+        for ty in (y0..h).step_by(down) {
+            for tx in (x0..w).step_by(across) {
+                for py in 0..pattern.height() as i64 {
+                    for px in 0..pattern.width() as i64 {
+                        let (cx, cy) = (tx + px, ty + py);
+                        if (0..w).contains(&cx)
+                            && (0..h).contains(&cy)
+                            && pattern.get(px as usize, py as usize)
+                        {
+                            self.set(cx as usize, cy as usize, Cell::Live);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The top left corners of every copy of `pattern` in the world, row by
+    /// row, each cell of its rectangle alive or dead just as in the pattern
+    /// and wrapping around the edges. With `turned`, copies rotated or
+    /// mirrored count too.
+    pub fn find(&self, pattern: &Pattern, turned: bool) -> Vec<(usize, usize)> {
+        let mut images = vec![pattern.clone()];
+        if turned {
+            let flipped = pattern.transformed(Transform::FlipX);
+            for image in [pattern, &flipped] {
+                images.push(image.transformed(Transform::Rot90));
+                images.push(image.transformed(Transform::Rot180));
+                images.push(image.transformed(Transform::Rot270));
+            }
+            images.push(flipped);
+        }
+        // symmetric patterns look the same some ways round
+        let mut unique: Vec<Pattern> = Vec::new();
+        for image in images {
+            if !unique.contains(&image) {
+                unique.push(image);
+            }
+        }
+        let mut found = Vec::new();
+        for image in &unique {
+            if image.width() > self.width || image.height() > self.height {
+                continue;
+            }
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    if self.matches(image, x, y) {
+                        found.push((x, y));
+                    }
+                }
+            }
+        }
+        found.sort_by_key(|&(x, y)| (y, x));
+        found.dedup();
+        found
+    }
+
+    fn matches(&self, pattern: &Pattern, x: usize, y: usize) -> bool {
+        (0..pattern.height()).all(|py| {
+            let row = &self.cells[(y + py) % self.height];
+            (0..pattern.width())
+                .all(|px| (row[(x + px) % self.width] == Cell::Live) == pattern.get(px, py))
+        })
+    }
+
+    /// Brings every live cell of `other` to life here, its top left corner at
+    /// `x`, `y`.
+    pub fn union(&mut self, other: &World, x: i64, y: i64) -> Result<(), String> {
+        self.combine(other, x, y, |a, b| a || b)
+    }
+
+    /// Kills every cell that isn't alive in `other` too, its top left corner
+    /// at `x`, `y`, including all those outside it.
+    pub fn intersection(&mut self, other: &World, x: i64, y: i64) -> Result<(), String> {
+        self.combine(other, x, y, |a, b| a && b)?;
+        for oy in other.height as i64..self.height as i64 {
+            for ox in 0..self.width as i64 {
+                self.set_at(x + ox, y + oy, Cell::Dead);
+            }
+        }
+        for oy in 0..other.height as i64 {
+            for ox in other.width as i64..self.width as i64 {
+                self.set_at(x + ox, y + oy, Cell::Dead);
+            }
+        }
+        Ok(())
+    }
+
+    /// Kills every cell alive in `other`, its top left corner at `x`, `y`.
+    pub fn difference(&mut self, other: &World, x: i64, y: i64) -> Result<(), String> {
+        self.combine(other, x, y, |a, b| a && !b)
+    }
+
+    /// Flips every cell alive in `other`, its top left corner at `x`, `y`, so
+    /// of two generations of a world it leaves the cells that changed.
+    pub fn xor(&mut self, other: &World, x: i64, y: i64) -> Result<(), String> {
+        self.combine(other, x, y, |a, b| a != b)
+    }
+
+    /// Sets each cell under `other` to `op` of whether it and the cell of
+    /// `other` over it are alive, wrapping around the edges.
+    fn combine(
+        &mut self,
+        other: &World,
+        x: i64,
+        y: i64,
+        op: fn(bool, bool) -> bool,
+    ) -> Result<(), String> {
+        if other.width > self.width || other.height > self.height {
+            return Err(format!(
+                "a {}x{} world does not fit over a {}x{} one",
+                other.width, other.height, self.width, self.height
+            ));
+        }
+        for (oy, row) in other.cells.iter().enumerate() {
+            for (ox, &cell) in row.iter().enumerate() {
+                let (sx, sy) = self.wrap(x + ox as i64, y + oy as i64);
+                let live = op(self.get(sx, sy) == Cell::Live, cell == Cell::Live);
+                self.set(sx, sy, if live { Cell::Live } else { Cell::Dead });
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces every cell with a live one with probability `density`.
+    pub fn randomize(&mut self, density: f64, rng: &mut Rng) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let cell = if rng.chance(density) {
+                    Cell::Live
+                } else {
+                    Cell::Dead
+                };
+                self.set(x, y, cell);
+            }
+        }
+    }
+
+    /// Next to a boundary cell of LifeHistory.
+    fn near_boundary(&self, states: &[u8], x: usize, y: usize) -> bool {
+        (-1..=1).any(|dy| {
+            (-1..=1).any(|dx| {
+                let (x, y) = self.wrap(x as i64 + dx, y as i64 + dy);
+                states[y * self.width + x] == history::BOUNDARY
+            })
+        })
+    }
+
+    /// Steps every cell according to the world's rule, Conway's B3/S23 unless
+    /// changed with `set_rule`, and counts the cells that were born and died.
+    pub fn advance(&mut self, tmp: &mut World) -> Changes {
+        let mut changes = Changes::default();
+        let lit = self.with_walls_lit();
+        let counted = lit.as_ref().unwrap_or(self);
+        let sums = self.rule.weighted_sums(counted);
+        let masks = self.rule.directional();
+        let live_within = match self.rule.radius() {
+            _ if sums.is_some() => None,
+            1 => None,
+            radius => Some(neighborhood::live_within(counted, radius)),
+        };
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let i = y * self.width + x;
+                let mut new_cell = match (&sums, &live_within) {
+                    (Some(sums), _) => self.rule.next_weighted(*cell, sums[i]),
+                    (_, Some(live)) => self.rule.next_within(*cell, live[i]),
+                    _ if masks => self.rule.next_mask(*cell, counted.neighbor_mask(x, y)),
+                    _ => self.rule.next(*cell, counted.count_neighbors(x, y)),
+                };
+                if self.walls.as_ref().is_some_and(|walls| walls.at[i]) {
+                    new_cell = Cell::Dead;
+                }
+                if let Some(cell) = self.emitters.as_ref().and_then(|emitters| emitters[i]) {
+                    new_cell = cell;
+                }
+                if let Some(states) = &self.history {
+                    if *cell == Cell::Dead && self.near_boundary(states, x, y) {
+                        new_cell = Cell::Dead;
+                    }
+                }
+                match (*cell, new_cell) {
+                    (Cell::Dead, Cell::Live) => changes.births += 1,
+                    (Cell::Live, Cell::Dead) => changes.deaths += 1,
+                    _ => {}
+                }
+                tmp.set(x, y, new_cell);
+            }
+        }
+
+        self.generation += 1;
+        for (y, row) in self.cells.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                let new_cell = tmp.cells[y][x];
+                if let Some(ages) = &mut self.ages {
+                    let i = y * self.width + x;
+                    match (*cell, new_cell) {
+                        (Cell::Live, Cell::Live) => ages.alive[i] = ages.alive[i].saturating_add(1),
+                        (Cell::Dead, Cell::Dead) => {}
+                        (_, new_cell) => {
+                            ages.alive[i] = u32::from(new_cell.n());
+                            ages.changed[i] = self.generation;
+                        }
+                    }
+                }
+                if let Some(states) = &mut self.history {
+                    let i = y * self.width + x;
+                    states[i] = history::next(states[i], new_cell);
+                }
+                *cell = new_cell;
+            }
+        }
+        // every cell of tmp was just set, so its counts are this generation's
+        self.population = tmp.population;
+        self.row_population.clone_from(&tmp.row_population);
+        self.column_population.clone_from(&tmp.column_population);
+        self.quadrants = tmp.quadrants;
+        changes
+    }
+
+    /// The smallest period up to `max` after which the world comes back to its
+    /// current state, 1 for a still life. `None` if it doesn't within `max`
+    /// generations, which includes anything that moves on a large enough world.
+    pub fn detect_period(&self, max: usize) -> Option<usize> {
+        let mut world = self.clone();
+        world.ages = None;
+        let mut tmp = world.clone();
+        for period in 1..=max {
+            world.advance(&mut tmp);
+            if world.cells == self.cells {
+                return Some(period);
+            }
+        }
+        None
+    }
+
+    pub fn as_string(&self) -> String {
+        let mut s = String::with_capacity(self.height * (self.width + 1));
+        for row in self.cells.iter() {
+            for cell in row {
+                let symbol = match cell {
+                    Cell::Dead => '.',
+                    Cell::Live => 'O',
+                };
+                s.push(symbol);
+            }
+            s.push('\n');
+        }
+        s
+    }
+}
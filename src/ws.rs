@@ -0,0 +1,210 @@
+//! Just enough of the WebSocket protocol (RFC 6455) for `serve`: the
+//! handshake, unfragmented frames from the server and whole messages from
+//! clients.
+
+use std::io::{self, Read};
+
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const CONTINUATION: u8 = 0x0;
+const TEXT: u8 = 0x1;
+const BINARY: u8 = 0x2;
+pub const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+pub const PONG: u8 = 0xA;
+
+/// Longest message a client may send; commands are a few bytes.
+const MAX_MESSAGE: usize = 64 * 1024;
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [
+        0x6745_2301,
+        0xEFCD_AB89,
+        0x98BA_DCFE,
+        0x1032_5476,
+        0xC3D2_E1F0,
+    ];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let t = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = t;
+        }
+        for (h, v) in h.iter_mut().zip(&[a, b, c, d, e]) {
+            *h = h.wrapping_add(*v);
+        }
+    }
+    let mut digest = [0; 20];
+    for (out, v) in digest.chunks_mut(4).zip(&h) {
+        out.copy_from_slice(&v.to_be_bytes());
+    }
+    digest
+}
+
+fn base64(data: &[u8]) -> String {
+    const DIGITS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(DIGITS[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// The `Sec-WebSocket-Accept` answer to a client's `Sec-WebSocket-Key`.
+pub fn accept(key: &str) -> String {
+    base64(&sha1(format!("{}{}", key.trim(), GUID).as_bytes()))
+}
+
+/// A frame as the server sends them: final and unmasked.
+pub fn frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x80 | opcode];
+    match payload.len() {
+        n if n < 126 => out.push(n as u8),
+        n if n <= 0xFFFF => {
+            out.push(126);
+            out.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        n => {
+            out.push(127);
+            out.extend_from_slice(&(n as u64).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(payload);
+    out
+}
+
+pub fn text(s: &str) -> Vec<u8> {
+    frame(TEXT, s.as_bytes())
+}
+
+/// What a client sent, with fragments put back together and pongs skipped.
+pub enum Message {
+    Text(String),
+    Binary,
+    Ping(Vec<u8>),
+    Close,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// One frame: whether it is the last of its message, its opcode and its
+/// unmasked payload.
+fn read_frame(reader: &mut impl Read) -> io::Result<(bool, u8, Vec<u8>)> {
+    let mut header = [0; 2];
+    reader.read_exact(&mut header)?;
+    let (fin, opcode) = (header[0] & 0x80 != 0, header[0] & 0x0F);
+    if header[1] & 0x80 == 0 {
+        return Err(invalid("client frames must be masked"));
+    }
+    let len = match header[1] & 0x7F {
+        126 => {
+            let mut len = [0; 2];
+            reader.read_exact(&mut len)?;
+            u64::from(u16::from_be_bytes(len))
+        }
+        127 => {
+            let mut len = [0; 8];
+            reader.read_exact(&mut len)?;
+            u64::from_be_bytes(len)
+        }
+        len => u64::from(len),
+    };
+    if len > MAX_MESSAGE as u64 {
+        return Err(invalid("message too long"));
+    }
+    let mut mask = [0; 4];
+    reader.read_exact(&mut mask)?;
+    let mut payload = vec![0; len as usize];
+    reader.read_exact(&mut payload)?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+    Ok((fin, opcode, payload))
+}
+
+/// Reads whole messages from a client.
+pub struct Reader<R> {
+    inner: R,
+    /// The fragments so far of a message still coming in, and its opcode.
+    data: Vec<u8>,
+    kind: Option<u8>,
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(inner: R) -> Reader<R> {
+        Reader {
+            inner,
+            data: Vec::new(),
+            kind: None,
+        }
+    }
+
+    /// Reads frames until a message is in, answering nothing itself.
+    pub fn read(&mut self) -> io::Result<Message> {
+        loop {
+            let (fin, opcode, payload) = read_frame(&mut self.inner)?;
+            match opcode {
+                // control frames may come between the fragments of a message
+                PING => return Ok(Message::Ping(payload)),
+                PONG => continue,
+                CLOSE => return Ok(Message::Close),
+                TEXT | BINARY if self.kind.is_none() => self.kind = Some(opcode),
+                CONTINUATION if self.kind.is_some() => {}
+                _ => return Err(invalid("unexpected frame")),
+            }
+            self.data.extend_from_slice(&payload);
+            if self.data.len() > MAX_MESSAGE {
+                return Err(invalid("message too long"));
+            }
+            if fin {
+                let data = std::mem::take(&mut self.data);
+                return match self.kind.take() {
+                    Some(TEXT) => String::from_utf8(data)
+                        .map(Message::Text)
+                        .map_err(|_| invalid("text message is not UTF-8")),
+                    _ => Ok(Message::Binary),
+                };
+            }
+        }
+    }
+}
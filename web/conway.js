@@ -0,0 +1,75 @@
+"use strict";
+
+const WIDTH = 120;
+const HEIGHT = 80;
+const CELL = 8;
+const DENSITY = 0.3;
+const STEP_MS = 100;
+
+async function main() {
+  const { instance } = await WebAssembly.instantiateStreaming(fetch("conway.wasm"));
+  const lib = instance.exports;
+
+  const sim = lib.conway_new(WIDTH, HEIGHT);
+  if (sim === 0) {
+    throw new Error("invalid world dimensions");
+  }
+
+  const canvas = document.getElementById("world");
+  canvas.width = WIDTH * CELL;
+  canvas.height = HEIGHT * CELL;
+  const ctx = canvas.getContext("2d");
+
+  function reseed() {
+    for (let y = 0; y < HEIGHT; y++) {
+      for (let x = 0; x < WIDTH; x++) {
+        lib.conway_set(sim, x, y, Math.random() < DENSITY);
+      }
+    }
+  }
+
+  function draw() {
+    // linear memory may have been grown (and the old view detached) since the last frame
+    const cells = new Uint8Array(lib.memory.buffer, lib.conway_cells(sim), WIDTH * HEIGHT);
+    ctx.clearRect(0, 0, canvas.width, canvas.height);
+    ctx.fillStyle = "#e6e6e6";
+    for (let y = 0; y < HEIGHT; y++) {
+      for (let x = 0; x < WIDTH; x++) {
+        if (cells[y * WIDTH + x]) {
+          ctx.fillRect(x * CELL, y * CELL, CELL - 1, CELL - 1);
+        }
+      }
+    }
+  }
+
+  let paused = false;
+  let last = 0;
+  function frame(now) {
+    if (!paused && now - last >= STEP_MS) {
+      lib.conway_step(sim);
+      last = now;
+    }
+    draw();
+    requestAnimationFrame(frame);
+  }
+
+  canvas.addEventListener("click", (e) => {
+    const rect = canvas.getBoundingClientRect();
+    const x = Math.floor((e.clientX - rect.left) * canvas.width / rect.width / CELL);
+    const y = Math.floor((e.clientY - rect.top) * canvas.height / rect.height / CELL);
+    lib.conway_toggle(sim, x, y);
+  });
+  document.addEventListener("keydown", (e) => {
+    if (e.key === " ") {
+      paused = !paused;
+      e.preventDefault();
+    } else if (e.key === "r") {
+      reseed();
+    }
+  });
+
+  reseed();
+  requestAnimationFrame(frame);
+}
+
+main();